@@ -504,9 +504,14 @@ fn scenario_to_llm_request(
                         name: tc.name.clone(),
                         arguments: tc.arguments.clone(),
                         provider_signature: None,
+                        original_arguments: None,
                     }));
                 }
-                prompt = prompt.with_item(InputItem::Assistant { content });
+                prompt = prompt.with_item(InputItem::Assistant {
+                    content,
+                    name: None,
+                    metadata: Default::default(),
+                });
             }
             "assistant" => {
                 prompt = prompt.with_assistant(m.content.clone().unwrap_or_default());
@@ -561,7 +566,11 @@ fn scenario_to_llm_request(
                     // Empty user message: skip rather than emit a no-content turn.
                     continue;
                 }
-                prompt = prompt.with_item(InputItem::User { content });
+                prompt = prompt.with_item(InputItem::User {
+                    content,
+                    name: None,
+                    metadata: Default::default(),
+                });
             }
         }
     }
@@ -669,7 +678,11 @@ fn parse_reasoning(v: &Value) -> Result<ReasoningConfig, String> {
         Some(other) => return Err(format!("unknown reasoning.summary: {other}")),
         None => None,
     };
-    Ok(ReasoningConfig { effort, summary })
+    Ok(ReasoningConfig {
+        effort,
+        summary,
+        ..Default::default()
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -74,6 +74,7 @@ async fn main() -> Result<(), Error> {
             name: "get_weather".into(),
             arguments: r#"{"city":"Paris"}"#.into(),
             provider_signature: None,
+            original_arguments: None,
         }))
         .reply("It is sunny in Paris.")
         .build();
@@ -2,10 +2,25 @@
 //!
 //! Every event names its target part by index. Reconstruction is a
 //! straight-line dispatch — no implicit "currently-active part" state.
+//!
+//! Deltas append in place (amortized O(1) per byte), so the cost that
+//! grows with output length is *reading* the text back while it
+//! streams. A UI polling [`crate::accumulator::ResponseAccumulator::current_content`] after
+//! every event re-copies the whole text each time — quadratic over a
+//! 100k-token answer. Polling loops should borrow with
+//! [`crate::accumulator::ResponseAccumulator::current_text`] or fetch only what's new with
+//! [`crate::accumulator::ResponseAccumulator::text_since`].
+
+use std::borrow::Cow;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
-use crate::response::CompleteResponse;
+use futures_util::stream::{Stream, StreamExt};
+
+use crate::response::{CompleteResponse, EventStream};
 use crate::types::{
-    AssistantPart, FinishReason, FunctionCall, PartKind, PartUpdate, StreamEvent, Usage,
+    AssistantPart, FinishReason, FunctionCall, PartKind, PartUpdate, SafetyFeedback, StreamEvent,
+    Usage,
 };
 use crate::Error;
 
@@ -18,6 +33,9 @@ pub struct ResponseAccumulator {
     parts: Vec<AssistantPart>,
     finish_reason: Option<FinishReason>,
     usage: Option<Usage>,
+    safety: Option<SafetyFeedback>,
+    /// Bytes of text-part content so far.
+    text_len: usize,
 }
 
 impl ResponseAccumulator {
@@ -48,6 +66,9 @@ impl ResponseAccumulator {
             StreamEvent::Delta { index, delta } => {
                 let part = self.part_mut(index)?;
                 append_delta(part, &delta);
+                if matches!(part, AssistantPart::Text { .. }) {
+                    self.text_len += delta.len();
+                }
             }
             StreamEvent::PartUpdate { index, update } => {
                 let part = self.part_mut(index)?;
@@ -57,6 +78,9 @@ impl ResponseAccumulator {
                 let part = self.part_mut(index)?;
                 finalize_part(part);
             }
+            StreamEvent::Safety { feedback } => {
+                self.safety = Some(feedback);
+            }
             StreamEvent::Done {
                 finish_reason,
                 usage,
@@ -85,24 +109,84 @@ impl ResponseAccumulator {
     /// the finish reason is [`FinishReason::Incomplete`] — *not*
     /// `Stop` — so callers can distinguish a clean finish from a cut
     /// off one; usage falls back to zeros.
-    pub fn finalize(self) -> Result<CompleteResponse, Error> {
+    pub fn finalize(mut self) -> Result<CompleteResponse, Error> {
+        // Growth by doubling can leave up to half of each long part's
+        // allocation unused; the response may be kept around a while.
+        for part in &mut self.parts {
+            if let Some(content) = streamed_content_mut(part) {
+                content.shrink_to_fit();
+            }
+        }
         Ok(CompleteResponse {
             content: self.parts,
             finish_reason: self.finish_reason.unwrap_or(FinishReason::Incomplete),
             usage: self.usage.unwrap_or_default(),
+            safety: self.safety,
+            timing: None,
         })
     }
 
-    /// Concatenation of all accumulated text-part content so far. Intended
-    /// for live previews while streaming is still in flight.
+    /// Concatenation of all accumulated text-part content so far, as a
+    /// fresh `String`. Copies the whole text on every call — see the
+    /// [module docs](self) for the cheaper ways to poll a long stream.
     pub fn current_content(&self) -> String {
-        self.parts
-            .iter()
-            .filter_map(|p| match p {
-                AssistantPart::Text { content, .. } => Some(content.as_str()),
-                _ => None,
-            })
-            .collect()
+        self.current_text().into_owned()
+    }
+
+    /// [`Self::current_content`], borrowed when the text lives in a
+    /// single part (the common case) and only joined when it spans
+    /// several.
+    pub fn current_text(&self) -> Cow<'_, str> {
+        let mut texts = self.texts();
+        match (texts.next(), texts.next()) {
+            (None, _) => Cow::Borrowed(""),
+            (Some(only), None) => Cow::Borrowed(only),
+            (Some(first), Some(second)) => {
+                let mut joined = String::with_capacity(self.text_len);
+                joined.extend([first, second]);
+                joined.extend(texts);
+                Cow::Owned(joined)
+            }
+        }
+    }
+
+    /// Length in bytes of [`Self::current_text`], without building it.
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    /// The text appended since [`Self::text_len`] was `offset` — what a
+    /// UI has to add to what it already shows. Borrowed unless the new
+    /// text spans parts. An `offset` past the end, or not on a char
+    /// boundary, yields everything from the next valid position.
+    pub fn text_since(&self, offset: usize) -> Cow<'_, str> {
+        let mut skip = offset;
+        let mut tail: Option<Cow<'_, str>> = None;
+        for text in self.texts() {
+            let piece = if skip >= text.len() {
+                skip -= text.len();
+                continue;
+            } else {
+                let mut start = skip;
+                while !text.is_char_boundary(start) {
+                    start += 1;
+                }
+                skip = 0;
+                &text[start..]
+            };
+            tail = Some(match tail {
+                None => Cow::Borrowed(piece),
+                Some(joined) => Cow::Owned(joined.into_owned() + piece),
+            });
+        }
+        tail.unwrap_or(Cow::Borrowed(""))
+    }
+
+    fn texts(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|p| match p {
+            AssistantPart::Text { content, .. } => Some(content.as_str()),
+            _ => None,
+        })
     }
 
     /// All function-call parts seen so far, cloned out. Note that the
@@ -117,6 +201,111 @@ impl ResponseAccumulator {
             })
             .collect()
     }
+
+    /// The tool call at part `index`, if that part is one.
+    pub(crate) fn function_call(&self, index: u32) -> Option<&FunctionCall> {
+        match self.parts.get(index as usize)? {
+            AssistantPart::ToolCall(call) => Some(call),
+            _ => None,
+        }
+    }
+
+    /// The tool call at part `index`'s arguments parsed as far as they
+    /// have streamed — `{"location": "Os` reads as `{"location": "Os"}`
+    /// — so a UI can show fields before the call completes. `None`
+    /// when the part isn't a tool call or nothing usable has arrived.
+    /// See [`crate::middleware::json_repair::parse_partial_json`].
+    pub fn partial_arguments(&self, index: u32) -> Option<serde_json::Value> {
+        match self.parts.get(index as usize)? {
+            AssistantPart::ToolCall(call) => {
+                crate::middleware::json_repair::parse_partial_json(&call.arguments)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A [`crate::Response`]'s event stream with a [`ResponseAccumulator`]
+/// fed along the way, from [`crate::Response::accumulate`]. Between
+/// events, [`Self::accumulator`] is the state after everything yielded
+/// so far — a live UI reads the text or partial tool arguments from it
+/// instead of running its own accumulator beside the stream:
+///
+/// ```no_run
+/// # async fn demo(response: platformed_llm::Response) -> Result<(), platformed_llm::Error> {
+/// use futures_util::StreamExt;
+///
+/// let mut stream = response.accumulate();
+/// let mut shown = 0;
+/// while let Some(event) = stream.next().await {
+///     event?;
+///     let accumulator = stream.accumulator();
+///     print!("{}", accumulator.text_since(shown));
+///     shown = accumulator.text_len();
+/// }
+/// let complete = stream.finish()?;
+/// # Ok(()) }
+/// ```
+///
+/// Errors — from the provider or an out-of-order event — are yielded
+/// once and end the stream.
+pub struct AccumulatingStream {
+    inner: EventStream,
+    accumulator: ResponseAccumulator,
+    failed: bool,
+}
+
+impl std::fmt::Debug for AccumulatingStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccumulatingStream")
+            .field("accumulator", &self.accumulator)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AccumulatingStream {
+    pub(crate) fn new(inner: EventStream) -> Self {
+        Self {
+            inner,
+            accumulator: ResponseAccumulator::new(),
+            failed: false,
+        }
+    }
+
+    /// Everything accumulated from the events yielded so far.
+    pub fn accumulator(&self) -> &ResponseAccumulator {
+        &self.accumulator
+    }
+
+    /// The response as accumulated so far — call once the stream has
+    /// ended. Events not yet polled are dropped, and a stream that
+    /// never reached `Done` finalizes as
+    /// [`FinishReason::Incomplete`]. `timing` is `None`; use
+    /// [`crate::Response::buffer`] when latency matters.
+    pub fn finish(self) -> Result<CompleteResponse, Error> {
+        self.accumulator.finalize()
+    }
+}
+
+impl Stream for AccumulatingStream {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.failed {
+            return Poll::Ready(None);
+        }
+        let item = match self.inner.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(event))) => match self.accumulator.process_event(event.clone()) {
+                Ok(()) => Ok(event),
+                Err(e) => Err(e),
+            },
+            Poll::Ready(Some(Err(e))) => Err(e),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+        self.failed = item.is_err();
+        Poll::Ready(Some(item))
+    }
 }
 
 fn open_part(kind: PartKind) -> AssistantPart {
@@ -136,6 +325,7 @@ fn open_part(kind: PartKind) -> AssistantPart {
             name,
             arguments: String::new(),
             provider_signature: None,
+            original_arguments: None,
         }),
         PartKind::BuiltinToolCall { kind } => AssistantPart::BuiltinToolCall {
             kind,
@@ -147,15 +337,22 @@ fn open_part(kind: PartKind) -> AssistantPart {
 }
 
 fn append_delta(part: &mut AssistantPart, delta: &str) {
+    if let Some(content) = streamed_content_mut(part) {
+        content.push_str(delta);
+    }
+}
+
+/// The string a part's deltas append to, if it has one.
+fn streamed_content_mut(part: &mut AssistantPart) -> Option<&mut String> {
     match part {
-        AssistantPart::Text { content, .. } => content.push_str(delta),
-        AssistantPart::Reasoning { content, .. } => content.push_str(delta),
-        AssistantPart::Refusal(content) => content.push_str(delta),
-        AssistantPart::ToolCall(call) => call.arguments.push_str(delta),
-        AssistantPart::BuiltinToolCall { arguments, .. } => arguments.push_str(delta),
+        AssistantPart::Text { content, .. }
+        | AssistantPart::Reasoning { content, .. }
+        | AssistantPart::Refusal(content) => Some(content),
+        AssistantPart::ToolCall(call) => Some(&mut call.arguments),
+        AssistantPart::BuiltinToolCall { arguments, .. } => Some(arguments),
         AssistantPart::RedactedReasoning { .. }
         | AssistantPart::Continuation(_)
-        | AssistantPart::CacheBreakpoint => {}
+        | AssistantPart::CacheBreakpoint => None,
     }
 }
 
@@ -167,6 +364,9 @@ fn apply_update(part: &mut AssistantPart, update: PartUpdate) {
         (AssistantPart::ToolCall(call), PartUpdate::Signature(sig)) => {
             call.provider_signature = Some(sig);
         }
+        (AssistantPart::ToolCall(call), PartUpdate::OriginalArguments(raw)) => {
+            call.original_arguments = Some(raw);
+        }
         (AssistantPart::Text { annotations, .. }, PartUpdate::Annotation(ann)) => {
             annotations.push(ann);
         }
@@ -218,6 +418,58 @@ mod tests {
         assert_eq!(acc.current_content(), "Hello, world!");
     }
 
+    #[test]
+    fn text_reads_borrow_and_resume_from_an_offset() {
+        let mut acc = ResponseAccumulator::new();
+        for event in [
+            StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::Text,
+            },
+            StreamEvent::Delta {
+                index: 0,
+                delta: "Grüße, ".into(),
+            },
+            StreamEvent::PartStart {
+                index: 1,
+                kind: PartKind::Reasoning,
+            },
+            StreamEvent::Delta {
+                index: 1,
+                delta: "hidden".into(),
+            },
+        ] {
+            acc.process_event(event).unwrap();
+        }
+        assert!(matches!(acc.current_text(), Cow::Borrowed("Grüße, ")));
+        let shown = acc.text_len();
+        assert_eq!(shown, "Grüße, ".len());
+
+        for event in [
+            StreamEvent::PartStart {
+                index: 2,
+                kind: PartKind::Text,
+            },
+            StreamEvent::Delta {
+                index: 2,
+                delta: "world".into(),
+            },
+        ] {
+            acc.process_event(event).unwrap();
+        }
+        assert_eq!(acc.text_since(shown), "world");
+        assert!(matches!(acc.text_since(shown), Cow::Borrowed(_)));
+        assert_eq!(acc.text_since(2), "üße, world");
+        assert_eq!(
+            acc.text_since(3),
+            "ße, world",
+            "mid-char offset skips ahead"
+        );
+        assert_eq!(acc.text_since(100), "");
+        assert_eq!(acc.current_text(), "Grüße, world");
+        assert_eq!(acc.text_len(), acc.current_content().len());
+    }
+
     #[test]
     fn accumulates_tool_call_arguments_via_deltas() {
         let mut acc = ResponseAccumulator::new();
@@ -309,6 +561,92 @@ mod tests {
         assert_eq!(calls[0].provider_signature.as_deref(), Some("sig_abc"));
     }
 
+    #[tokio::test]
+    async fn accumulating_stream_exposes_state_between_events() {
+        let events = vec![
+            Ok(StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::Text,
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "Hel".into(),
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "lo".into(),
+            }),
+            Ok(StreamEvent::PartEnd { index: 0 }),
+            Ok(StreamEvent::Done {
+                finish_reason: FinishReason::Stop,
+                usage: Usage::default(),
+            }),
+        ];
+        let mut stream =
+            crate::Response::from_stream(futures_util::stream::iter(events)).accumulate();
+        let mut previews = Vec::new();
+        while let Some(event) = stream.next().await {
+            event.unwrap();
+            previews.push(stream.accumulator().current_content());
+        }
+        assert_eq!(previews, ["", "Hel", "Hello", "Hello", "Hello"]);
+        let complete = stream.finish().unwrap();
+        assert_eq!(complete.text(), "Hello");
+        assert_eq!(complete.finish_reason, FinishReason::Stop);
+    }
+
+    #[tokio::test]
+    async fn accumulating_stream_ends_after_an_error() {
+        let events = vec![
+            Ok(StreamEvent::Delta {
+                index: 3,
+                delta: "orphan".into(),
+            }),
+            Ok(StreamEvent::Done {
+                finish_reason: FinishReason::Stop,
+                usage: Usage::default(),
+            }),
+        ];
+        let mut stream =
+            crate::Response::from_stream(futures_util::stream::iter(events)).accumulate();
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn partial_arguments_track_the_streamed_prefix() {
+        let mut acc = ResponseAccumulator::new();
+        acc.process_event(StreamEvent::PartStart {
+            index: 0,
+            kind: PartKind::ToolCall {
+                call_id: "c1".into(),
+                name: "get_weather".into(),
+            },
+        })
+        .unwrap();
+        assert_eq!(acc.partial_arguments(0), None);
+
+        let mut seen = Vec::new();
+        for delta in [r#"{"loca"#, r#"tion": "Os"#, r#"lo", "days"#, r#"": 3}"#] {
+            acc.process_event(StreamEvent::Delta {
+                index: 0,
+                delta: delta.into(),
+            })
+            .unwrap();
+            seen.push(acc.partial_arguments(0).unwrap());
+        }
+        assert_eq!(
+            seen,
+            [
+                serde_json::json!({}),
+                serde_json::json!({"location": "Os"}),
+                serde_json::json!({"location": "Oslo"}),
+                serde_json::json!({"location": "Oslo", "days": 3}),
+            ]
+        );
+        assert_eq!(acc.partial_arguments(1), None);
+    }
+
     #[test]
     fn part_start_must_be_in_order() {
         let mut acc = ResponseAccumulator::new();
@@ -0,0 +1,535 @@
+//! JSON Lines audit trail of every call, for compliance retention.
+//!
+//! [`AuditProvider`] wraps any [`Provider`] and, once per call, writes
+//! an [`AuditRecord`] — when, which model, for which tenant and user,
+//! how it ended, usage, finish reason, latency — to an [`AuditSink`].
+//! [`JsonlAuditSink`] appends each record as one line of JSON to a
+//! file or any other writer; implement [`AuditSink`] to ship records
+//! elsewhere.
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use platformed_llm::audit::{AuditLayer, ContentPolicy, JsonlAuditSink};
+//!
+//! let sink = Arc::new(JsonlAuditSink::open("/var/log/llm/audit.jsonl")?);
+//! let provider = ProviderStack::new()
+//!     .layer(AuditLayer::new(sink).with_policy(ContentPolicy::Hash))
+//!     .service(openai);
+//! ```
+//!
+//! # Content
+//!
+//! What a record says about the prompt and response is the
+//! [`ContentPolicy`]'s call. The default, [`ContentPolicy::Hash`],
+//! keeps SHA-256 digests only — enough to prove later which exchange
+//! took place without the log itself holding user data.
+//! [`ContentPolicy::Full`] records the prompt and the response parts
+//! verbatim; [`ContentPolicy::Metadata`] records neither. Hashes cover
+//! the prompt's and the response parts' JSON serialization.
+//!
+//! # When a record is written
+//!
+//! Exactly once per call, like [`crate::metrics`]: immediately if
+//! `generate` fails, otherwise when the stream reaches `Done`, fails,
+//! ends early, or is dropped. A record for a stream that failed or was
+//! cut short carries what had arrived by then.
+
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+
+use crate::accumulator::ResponseAccumulator;
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{
+    AssistantPart, Capabilities, Error, FinishReason, Prompt, Provider, RawConfig, Response,
+    StreamEvent, Usage,
+};
+
+/// How much of the prompt and response an [`AuditRecord`] keeps. See
+/// the module docs.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentPolicy {
+    /// Neither content nor digests.
+    Metadata,
+    /// SHA-256 digests of the prompt and response.
+    #[default]
+    Hash,
+    /// Digests plus the prompt and response verbatim.
+    Full,
+}
+
+/// How a call ended, from [`AuditRecord::outcome`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The stream reached `Done`.
+    Success,
+    /// `generate` or the stream failed.
+    Error,
+    /// The stream ended without `Done`, or the caller dropped it.
+    Cancelled,
+}
+
+/// One call, as written to an [`AuditSink`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// When the call started, in milliseconds since the UNIX epoch.
+    pub started_at_unix_ms: u64,
+    /// Model the call was made with.
+    pub model: String,
+    /// Tenant the call was made for ([`crate::ConfigBuilder::tenant`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// End user the call was made for
+    /// ([`crate::ConfigBuilder::user_id`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// The call's [`crate::RawConfig::request_id`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Backend that served the call, when a wrapper underneath
+    /// recorded one ([`crate::ResponseMetadata::served_by`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub served_by: Option<String>,
+    /// How the call ended.
+    pub outcome: AuditOutcome,
+    /// [`Error::kind`] of the failure, for [`AuditOutcome::Error`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    /// Why generation stopped, when the stream reached `Done`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
+    /// Token accounting, when the stream reached `Done`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    /// From the `generate` call to the outcome, in milliseconds.
+    pub latency_ms: u64,
+    /// From the `generate` call to the first stream event, in
+    /// milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_to_first_token_ms: Option<u64>,
+    /// Hex SHA-256 of the prompt, unless the policy is
+    /// [`ContentPolicy::Metadata`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_sha256: Option<String>,
+    /// Hex SHA-256 of the response parts, unless the policy is
+    /// [`ContentPolicy::Metadata`] or `generate` failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_sha256: Option<String>,
+    /// The prompt, under [`ContentPolicy::Full`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<Prompt>,
+    /// The response parts, under [`ContentPolicy::Full`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<Vec<AssistantPart>>,
+}
+
+/// Receives an [`AuditRecord`] per call. Implemented for any
+/// `Fn(&AuditRecord)` closure.
+pub trait AuditSink: Send + Sync {
+    /// Persist one record. Runs inline on the task polling the
+    /// response stream; a sink that can block for long should hand
+    /// records to a background writer.
+    fn write(&self, record: &AuditRecord);
+}
+
+impl<F> AuditSink for F
+where
+    F: Fn(&AuditRecord) + Send + Sync,
+{
+    fn write(&self, record: &AuditRecord) {
+        self(record)
+    }
+}
+
+/// Shared handle to an [`AuditSink`].
+pub type SharedAuditSink = Arc<dyn AuditSink>;
+
+/// An [`AuditSink`] writing one JSON line per record, flushed as it
+/// goes. Write failures are logged with `tracing::warn!` and otherwise
+/// ignored, so an unwritable log never fails a call.
+pub struct JsonlAuditSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonlAuditSink {
+    /// Append to the file at `path`, creating it and its directory if
+    /// missing. Existing records are never truncated.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                Error::config(format!(
+                    "cannot create audit log dir {}: {e}",
+                    dir.display()
+                ))
+            })?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::config(format!("cannot open audit log {}: {e}", path.display())))?;
+        Ok(Self::from_writer(file))
+    }
+
+    /// Write to `writer`.
+    pub fn from_writer(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+}
+
+impl std::fmt::Debug for JsonlAuditSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonlAuditSink").finish_non_exhaustive()
+    }
+}
+
+impl AuditSink for JsonlAuditSink {
+    fn write(&self, record: &AuditRecord) {
+        let line = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(err) => {
+                tracing::warn!(error = %err, "failed to serialize audit record");
+                return;
+            }
+        };
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(err) = writeln!(writer, "{line}").and_then(|()| writer.flush()) {
+            tracing::warn!(error = %err, "failed to write audit record");
+        }
+    }
+}
+
+fn sha256_json(value: &impl Serialize) -> Option<String> {
+    let json = serde_json::to_vec(value).ok()?;
+    let digest = Sha256::digest(&json);
+    Some(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// A [`Provider`] wrapper writing an [`AuditRecord`] for every call.
+/// See the module docs.
+#[derive(Clone)]
+pub struct AuditProvider {
+    inner: SharedProvider,
+    sink: SharedAuditSink,
+    policy: ContentPolicy,
+}
+
+impl AuditProvider {
+    /// Audit `inner`'s calls to `sink` under [`ContentPolicy::Hash`].
+    pub fn new(inner: impl Provider, sink: SharedAuditSink) -> Self {
+        Self::from_shared(Arc::new(inner), sink)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, sink: SharedAuditSink) -> Self {
+        Self {
+            inner,
+            sink,
+            policy: ContentPolicy::default(),
+        }
+    }
+
+    /// Keep prompt and response content as `policy` says.
+    pub fn with_policy(mut self, policy: ContentPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl std::fmt::Debug for AuditProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditProvider")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// In-flight record for one call. Writes on drop if nothing else has,
+/// so a dropped stream still yields a `Cancelled` record.
+struct Entry {
+    sink: SharedAuditSink,
+    policy: ContentPolicy,
+    started: Instant,
+    first_event: Option<Instant>,
+    accumulator: Option<ResponseAccumulator>,
+    record: Option<AuditRecord>,
+}
+
+impl Entry {
+    fn on_item(&mut self, item: &Result<StreamEvent, Error>) {
+        self.first_event.get_or_insert_with(Instant::now);
+        match item {
+            Ok(event) => {
+                if let Some(accumulator) = self.accumulator.as_mut() {
+                    // An accumulator error only means the audit copy
+                    // is incomplete; the caller's stream is untouched.
+                    let _ = accumulator.process_event(event.clone());
+                }
+                if let StreamEvent::Done {
+                    finish_reason,
+                    usage,
+                } = event
+                {
+                    self.finish(AuditOutcome::Success, None, |record| {
+                        record.finish_reason = Some(finish_reason.clone());
+                        record.usage = Some(usage.clone());
+                    });
+                }
+            }
+            Err(err) => self.finish(AuditOutcome::Error, Some(err.kind()), |_| {}),
+        }
+    }
+
+    fn finish(
+        &mut self,
+        outcome: AuditOutcome,
+        error_kind: Option<&'static str>,
+        fill: impl FnOnce(&mut AuditRecord),
+    ) {
+        let Some(mut record) = self.record.take() else {
+            return;
+        };
+        record.outcome = outcome;
+        record.error_kind = error_kind.map(str::to_string);
+        record.latency_ms = millis(self.started.elapsed());
+        record.time_to_first_token_ms = self
+            .first_event
+            .map(|first| millis(first.saturating_duration_since(self.started)));
+        if let Some(content) = self
+            .accumulator
+            .take()
+            .and_then(|accumulator| accumulator.finalize().ok())
+            .map(|complete| complete.content)
+        {
+            record.response_sha256 = sha256_json(&content);
+            if self.policy == ContentPolicy::Full {
+                record.response = Some(content);
+            }
+        }
+        fill(&mut record);
+        self.sink.write(&record);
+    }
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        self.finish(AuditOutcome::Cancelled, None, |_| {});
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for AuditProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let keep_content = self.policy != ContentPolicy::Metadata;
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut entry = Entry {
+            sink: self.sink.clone(),
+            policy: self.policy,
+            started: Instant::now(),
+            first_event: None,
+            accumulator: None,
+            record: Some(AuditRecord {
+                started_at_unix_ms: millis(started_at),
+                model: config.model.clone(),
+                tenant: config.tenant.map(|tenant| tenant.to_string()),
+                user_id: config.metadata.user_id.clone(),
+                request_id: config.request_id.clone(),
+                served_by: None,
+                outcome: AuditOutcome::Cancelled,
+                error_kind: None,
+                finish_reason: None,
+                usage: None,
+                latency_ms: 0,
+                time_to_first_token_ms: None,
+                prompt_sha256: keep_content.then(|| sha256_json(prompt)).flatten(),
+                response_sha256: None,
+                prompt: (self.policy == ContentPolicy::Full).then(|| prompt.clone()),
+                response: None,
+            }),
+        };
+        match self.inner.generate(prompt, config).await {
+            Ok(response) => {
+                if let Some(record) = entry.record.as_mut() {
+                    record.served_by = response.metadata().served_by.clone();
+                }
+                if keep_content {
+                    entry.accumulator = Some(ResponseAccumulator::new());
+                }
+                Ok(response
+                    .map_stream(move |stream| stream.inspect(move |item| entry.on_item(item))))
+            }
+            Err(err) => {
+                entry.finish(AuditOutcome::Error, Some(err.kind()), |_| {});
+                Err(err)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in an [`AuditProvider`]
+/// writing to one shared sink.
+#[derive(Clone)]
+pub struct AuditLayer {
+    sink: SharedAuditSink,
+    policy: ContentPolicy,
+}
+
+impl AuditLayer {
+    /// A layer auditing every wrapped provider's calls to `sink` under
+    /// [`ContentPolicy::Hash`].
+    pub fn new(sink: SharedAuditSink) -> Self {
+        Self {
+            sink,
+            policy: ContentPolicy::default(),
+        }
+    }
+
+    /// Keep prompt and response content as `policy` says.
+    pub fn with_policy(mut self, policy: ContentPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl std::fmt::Debug for AuditLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLayer")
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProviderLayer for AuditLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(AuditProvider::from_shared(inner, self.sink.clone()).with_policy(self.policy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::{generate, Config};
+
+    /// A `Write` whose bytes the test can read back.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn lines(&self) -> Vec<serde_json::Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    async fn audited(policy: ContentPolicy, provider: MockProvider) -> Vec<serde_json::Value> {
+        let buffer = Buffer::default();
+        let provider = AuditProvider::new(
+            provider,
+            Arc::new(JsonlAuditSink::from_writer(buffer.clone())),
+        )
+        .with_policy(policy);
+        let config = Config::builder("m")
+            .tenant(uuid::Uuid::from_u128(7))
+            .user_id("u-1")
+            .build();
+        if let Ok(response) = generate(&provider, &Prompt::user("secret question"), &config).await {
+            let _ = response.text().await;
+        }
+        buffer.lines()
+    }
+
+    #[tokio::test]
+    async fn hashes_content_by_default() {
+        let lines = audited(
+            ContentPolicy::Hash,
+            MockProvider::with_text("secret answer"),
+        )
+        .await;
+        assert_eq!(lines.len(), 1);
+        let record = &lines[0];
+        assert_eq!(record["model"], "m");
+        assert_eq!(record["outcome"], "success");
+        assert_eq!(record["finish_reason"], "stop");
+        assert_eq!(record["tenant"], uuid::Uuid::from_u128(7).to_string());
+        assert_eq!(record["user_id"], "u-1");
+        assert_eq!(record["prompt_sha256"].as_str().unwrap().len(), 64);
+        assert!(record["response_sha256"].is_string());
+        assert!(record.get("usage").is_some());
+        let text = record.to_string();
+        assert!(!text.contains("secret"), "{text}");
+    }
+
+    #[tokio::test]
+    async fn full_policy_keeps_content_and_metadata_policy_drops_it() {
+        let full = audited(ContentPolicy::Full, MockProvider::with_text("an answer")).await;
+        assert_eq!(
+            full[0]["prompt"],
+            serde_json::to_value(Prompt::user("secret question")).unwrap()
+        );
+        assert!(full[0]["response"].to_string().contains("an answer"));
+
+        let bare = audited(ContentPolicy::Metadata, MockProvider::with_text("x")).await;
+        for field in ["prompt", "response", "prompt_sha256", "response_sha256"] {
+            assert!(bare[0].get(field).is_none(), "{field}");
+        }
+    }
+
+    #[tokio::test]
+    async fn records_failures_once() {
+        let lines = audited(
+            ContentPolicy::Hash,
+            MockProvider::builder()
+                .reply(
+                    MockResponse::text("partial")
+                        .with_stream_error(Error::provider_with_status("Mock", 500, "boom")),
+                )
+                .build(),
+        )
+        .await;
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["outcome"], "error");
+        assert_eq!(lines[0]["error_kind"], "provider");
+    }
+}
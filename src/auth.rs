@@ -0,0 +1,35 @@
+//! Caller-supplied credentials.
+//!
+//! Each hosted provider has a built-in way to authenticate — an OpenAI
+//! API key, Vertex access tokens / ADC / express-mode keys. Deployments
+//! that mint short-lived credentials elsewhere (Vault, a workload
+//! identity sidecar, an STS exchange) implement [`TokenSource`] instead
+//! and hand it to the provider:
+//!
+//! - [`crate::providers::OpenAIProvider::with_token_source`]
+//! - [`crate::providers::VertexEndpoint::with_token_source`] (and the
+//!   `with_token_source` constructors on the Vertex providers)
+//! - [`crate::ProviderConfig::with_token_source`] for factory-built
+//!   providers
+//!
+//! The provider asks for a token before every request (and file
+//! upload) and sends it as `Authorization: Bearer …`. It never caches:
+//! a source backed by a slow broker should cache internally and refresh
+//! ahead of expiry.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::Error;
+
+/// An async source of bearer tokens. See the [module docs](self).
+#[async_trait]
+pub trait TokenSource: Send + Sync + 'static {
+    /// A token valid for the request about to be sent. Errors abort the
+    /// request; [`Error::auth`] is the natural kind.
+    async fn token(&self) -> Result<String, Error>;
+}
+
+/// The shared handle providers hold.
+pub type SharedTokenSource = Arc<dyn TokenSource>;
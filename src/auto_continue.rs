@@ -0,0 +1,445 @@
+//! Continuing responses cut off by the output-token cap.
+//!
+//! A long answer that hits `max_tokens` ends with
+//! [`FinishReason::Length`] mid-sentence. [`AutoContinueProvider`]
+//! picks it up again: it re-sends the prompt with everything generated
+//! so far as an assistant prefill (see
+//! [`Prompt::with_assistant_prefill`]) and splices the follow-up onto
+//! the same stream, up to a configurable number of follow-ups.
+//!
+//! ```ignore
+//! use platformed_llm::AutoContinueProvider;
+//!
+//! let provider = AutoContinueProvider::new(backend, 3);
+//! let text = generate(&provider, &prompt, &config).await?.text().await?;
+//! ```
+//!
+//! The caller sees one seamless turn:
+//!
+//! - the text part a round ended on stays open, and the next round's
+//!   opening text is streamed into it; any other parts are renumbered
+//!   after the ones already emitted;
+//! - there is a single `Done`, carrying the last round's finish reason
+//!   and the usage of every round summed, so billing and metrics layers
+//!   outside this one see the real cost;
+//! - a [`StreamEvent::Safety`] event, if any, is the last round's.
+//!
+//! Only turns that produced text and no tool call are continued. The
+//! limit caps the *extra* requests: with `max_continuations = 3` a
+//! turn costs at most four, and if the fourth is cut off too the
+//! stream ends with `FinishReason::Length` as usual.
+//!
+//! How faithfully a provider resumes depends on its prefill support —
+//! Anthropic, Gemini and local models continue mid-token, OpenAI is
+//! asked to continue by instruction.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::types::{FinishReason, InputItem, PartKind, SafetyFeedback, StreamEvent, Usage};
+use crate::{Capabilities, Error, EventStream, Prompt, Provider, RawConfig, Response};
+
+/// A [`Provider`] wrapper that continues length-truncated responses.
+/// See the module docs.
+#[derive(Clone)]
+pub struct AutoContinueProvider {
+    inner: SharedProvider,
+    max_continuations: usize,
+}
+
+impl AutoContinueProvider {
+    /// Continue `inner`'s truncated responses with up to
+    /// `max_continuations` follow-up requests each.
+    pub fn new(inner: impl Provider, max_continuations: usize) -> Self {
+        Self::from_shared(Arc::new(inner), max_continuations)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, max_continuations: usize) -> Self {
+        Self {
+            inner,
+            max_continuations,
+        }
+    }
+
+    /// The most follow-up requests one call may make.
+    pub fn max_continuations(&self) -> usize {
+        self.max_continuations
+    }
+}
+
+impl std::fmt::Debug for AutoContinueProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AutoContinueProvider")
+            .field("max_continuations", &self.max_continuations)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for AutoContinueProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let response = self.inner.generate(prompt, config).await?;
+        if self.max_continuations == 0 {
+            return Ok(response);
+        }
+        // A caller's own prefill is part of the answer being continued.
+        let mut items = prompt.items().to_vec();
+        let generated = match prompt.assistant_prefill() {
+            Some(prefill) => {
+                let prefill = prefill.to_string();
+                items.pop();
+                prefill
+            }
+            None => String::new(),
+        };
+        let mut stitcher = Stitcher {
+            inner: self.inner.clone(),
+            items,
+            config: config.clone(),
+            remaining: self.max_continuations,
+            stream: None,
+            generated,
+            round_text: false,
+            round_tool_call: false,
+            round_started: false,
+            index_map: HashMap::new(),
+            text_parts: HashSet::new(),
+            next_index: 0,
+            held_end: None,
+            usage: Usage::default(),
+            safety: None,
+            queue: VecDeque::new(),
+            finished: false,
+        };
+        Ok(response.map_stream(move |stream| {
+            stitcher.stream = Some(stream);
+            Box::pin(futures_util::stream::unfold(stitcher, |mut s| async move {
+                let item = s.next().await?;
+                Some((item, s))
+            }))
+        }))
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// Splices successive rounds into one event stream.
+struct Stitcher {
+    inner: SharedProvider,
+    /// The prompt without any caller prefill.
+    items: Vec<InputItem>,
+    config: RawConfig,
+    remaining: usize,
+    stream: Option<EventStream>,
+    /// Caller prefill plus all text generated so far — the next
+    /// round's prefill.
+    generated: String,
+    round_text: bool,
+    round_tool_call: bool,
+    /// Whether the current round has opened any part yet.
+    round_started: bool,
+    /// Current round's part index → index on the stitched stream.
+    index_map: HashMap<u32, u32>,
+    /// Stitched indices of text parts.
+    text_parts: HashSet<u32>,
+    next_index: u32,
+    /// Stitched index of a text part whose `PartEnd` is withheld in
+    /// case the next round continues it.
+    held_end: Option<u32>,
+    usage: Usage,
+    safety: Option<SafetyFeedback>,
+    queue: VecDeque<Result<StreamEvent, Error>>,
+    finished: bool,
+}
+
+impl Stitcher {
+    async fn next(&mut self) -> Option<Result<StreamEvent, Error>> {
+        loop {
+            if let Some(item) = self.queue.pop_front() {
+                return Some(item);
+            }
+            if self.finished {
+                return None;
+            }
+            let stream = self.stream.as_mut()?;
+            match stream.next().await {
+                Some(Ok(event)) => self.on_event(event).await,
+                Some(Err(err)) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+                None => {
+                    // Upstream ended without `Done`; pass the truncation on.
+                    self.flush_held_end();
+                    self.finished = true;
+                }
+            }
+        }
+    }
+
+    async fn on_event(&mut self, event: StreamEvent) {
+        match event {
+            StreamEvent::PartStart { index, kind } => {
+                if let (false, Some(open), PartKind::Text) =
+                    (self.round_started, self.held_end, &kind)
+                {
+                    // The round resumes the text the previous one was
+                    // cut off in.
+                    self.held_end = None;
+                    self.round_started = true;
+                    self.round_text = true;
+                    self.index_map.insert(index, open);
+                    return;
+                }
+                self.flush_held_end();
+                self.round_started = true;
+                let mapped = self.next_index;
+                self.next_index += 1;
+                self.index_map.insert(index, mapped);
+                match kind {
+                    PartKind::Text => {
+                        self.round_text = true;
+                        self.text_parts.insert(mapped);
+                    }
+                    PartKind::ToolCall { .. } => self.round_tool_call = true,
+                    _ => {}
+                }
+                self.emit(StreamEvent::PartStart {
+                    index: mapped,
+                    kind,
+                });
+            }
+            StreamEvent::Delta { index, delta } => {
+                self.flush_held_end();
+                let mapped = self.map(index);
+                if self.text_parts.contains(&mapped) {
+                    self.generated.push_str(&delta);
+                }
+                self.emit(StreamEvent::Delta {
+                    index: mapped,
+                    delta,
+                });
+            }
+            StreamEvent::PartUpdate { index, update } => {
+                self.flush_held_end();
+                let index = self.map(index);
+                self.emit(StreamEvent::PartUpdate { index, update });
+            }
+            StreamEvent::PartEnd { index } => {
+                self.flush_held_end();
+                let mapped = self.map(index);
+                if self.text_parts.contains(&mapped) {
+                    self.held_end = Some(mapped);
+                } else {
+                    self.emit(StreamEvent::PartEnd { index: mapped });
+                }
+            }
+            StreamEvent::Safety { feedback } => self.safety = Some(feedback),
+            StreamEvent::Done {
+                finish_reason,
+                usage,
+            } => {
+                add_usage(&mut self.usage, &usage);
+                let truncated = matches!(finish_reason, FinishReason::Length)
+                    && self.round_text
+                    && !self.round_tool_call;
+                if truncated && self.remaining > 0 {
+                    self.remaining -= 1;
+                    self.next_round().await;
+                    return;
+                }
+                self.flush_held_end();
+                if let Some(feedback) = self.safety.take() {
+                    self.emit(StreamEvent::Safety { feedback });
+                }
+                let usage = std::mem::take(&mut self.usage);
+                self.emit(StreamEvent::Done {
+                    finish_reason,
+                    usage,
+                });
+                self.finished = true;
+            }
+        }
+    }
+
+    /// Request the continuation of everything generated so far.
+    async fn next_round(&mut self) {
+        tracing::debug!(
+            generated_bytes = self.generated.len(),
+            remaining = self.remaining,
+            "response hit the length cap; requesting a continuation"
+        );
+        let prompt = Prompt::new()
+            .with_items(self.items.clone())
+            .with_assistant_prefill(self.generated.clone());
+        match self.inner.generate(&prompt, &self.config).await {
+            Ok(response) => {
+                self.stream = Some(response.stream());
+                self.index_map.clear();
+                self.round_started = false;
+                self.round_text = false;
+                self.round_tool_call = false;
+                self.safety = None;
+            }
+            Err(err) => {
+                self.queue.push_back(Err(err));
+                self.finished = true;
+            }
+        }
+    }
+
+    fn map(&self, index: u32) -> u32 {
+        // Unknown indices are the provider's invariant violation;
+        // pass them through for the accumulator to reject.
+        self.index_map.get(&index).copied().unwrap_or(index)
+    }
+
+    fn flush_held_end(&mut self) {
+        if let Some(index) = self.held_end.take() {
+            self.emit(StreamEvent::PartEnd { index });
+        }
+    }
+
+    fn emit(&mut self, event: StreamEvent) {
+        self.queue.push_back(Ok(event));
+    }
+}
+
+fn add_usage(total: &mut Usage, usage: &Usage) {
+    fn add(total: &mut Option<u32>, more: Option<u32>) {
+        if let Some(more) = more {
+            *total = Some(total.unwrap_or(0).saturating_add(more));
+        }
+    }
+    total.input_tokens = total.input_tokens.saturating_add(usage.input_tokens);
+    total.output_tokens = total.output_tokens.saturating_add(usage.output_tokens);
+    add(
+        &mut total.cache_read_input_tokens,
+        usage.cache_read_input_tokens,
+    );
+    add(
+        &mut total.cache_creation_input_tokens,
+        usage.cache_creation_input_tokens,
+    );
+    add(&mut total.reasoning_tokens, usage.reasoning_tokens);
+    add(&mut total.total_tokens, usage.total_tokens);
+}
+
+/// [`ProviderLayer`] that wraps providers in an [`AutoContinueProvider`].
+#[derive(Debug, Clone)]
+pub struct AutoContinueLayer {
+    max_continuations: usize,
+}
+
+impl AutoContinueLayer {
+    /// A layer allowing up to `max_continuations` follow-ups per call.
+    pub fn new(max_continuations: usize) -> Self {
+        Self { max_continuations }
+    }
+}
+
+impl ProviderLayer for AutoContinueLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(AutoContinueProvider::from_shared(
+            inner,
+            self.max_continuations,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::types::AssistantPart;
+    use crate::{generate, Config};
+
+    fn text(content: &str, finish_reason: FinishReason) -> MockResponse {
+        MockResponse::from_parts(
+            vec![AssistantPart::Text {
+                content: content.into(),
+                annotations: Vec::new(),
+            }],
+            finish_reason,
+        )
+        .usage(Usage {
+            input_tokens: 10,
+            output_tokens: 5,
+            ..Usage::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn stitches_continuations_into_one_text_part() {
+        let mock = MockProvider::builder()
+            .reply(text("The quick brown", FinishReason::Length))
+            .reply(text(" fox jumps", FinishReason::Length))
+            .reply(text(" over.", FinishReason::Stop))
+            .build();
+        let log = mock.call_log();
+        let provider = AutoContinueProvider::new(mock, 5);
+        let complete = generate(
+            &provider,
+            &Prompt::user("Tell me"),
+            &Config::builder("m").build(),
+        )
+        .await
+        .unwrap()
+        .buffer()
+        .await
+        .unwrap();
+
+        assert_eq!(complete.content.len(), 1);
+        assert_eq!(complete.text(), "The quick brown fox jumps over.");
+        assert_eq!(complete.finish_reason, FinishReason::Stop);
+        assert_eq!(complete.usage.input_tokens, 30);
+        assert_eq!(complete.usage.output_tokens, 15);
+
+        let calls = log.calls();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[1].prompt.assistant_prefill(), Some("The quick brown"));
+        assert_eq!(
+            calls[2].prompt.assistant_prefill(),
+            Some("The quick brown fox jumps")
+        );
+        assert_eq!(calls[2].prompt.items().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_limit_and_extends_a_caller_prefill() {
+        let mock = MockProvider::builder()
+            .reply(text("a", FinishReason::Length))
+            .reply(text("b", FinishReason::Length))
+            .reply(text("c", FinishReason::Stop))
+            .build();
+        let log = mock.call_log();
+        let provider = AutoContinueProvider::new(mock, 1);
+        let complete = generate(
+            &provider,
+            &Prompt::user("x").with_assistant_prefill("["),
+            &Config::builder("m").build(),
+        )
+        .await
+        .unwrap()
+        .buffer()
+        .await
+        .unwrap();
+
+        assert_eq!(complete.text(), "ab");
+        assert!(complete.was_truncated());
+        let calls = log.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1].prompt.assistant_prefill(), Some("[a"));
+        assert_eq!(calls[1].prompt.items().len(), 2);
+    }
+}
@@ -0,0 +1,267 @@
+//! Cross-request micro-batching for embeddings.
+//!
+//! Pipelines that embed documents one at a time from many tasks pay a
+//! round trip per text, even though embeddings endpoints take a list of
+//! inputs per request. [`BatchingEmbedder`] sits in front of an
+//! [`Embedder`] and coalesces the [`Embedder::embed`] calls that arrive
+//! within a short window into one [`Embedder::embed_batch`] call:
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use platformed_llm::batching::BatchingEmbedder;
+//!
+//! let embedder = Arc::new(
+//!     BatchingEmbedder::new(Arc::new(my_embedder))
+//!         .with_window(Duration::from_millis(10))
+//!         .with_max_batch_size(128),
+//! );
+//! // Concurrent `embed` calls now share requests.
+//! ```
+//!
+//! A batch opens with the first text queued and is sent when it
+//! reaches `max_batch_size` texts or its window elapses, whichever
+//! comes first — so a lone call waits at most one window. There is no
+//! background task: the caller that fills the batch, or whichever
+//! waiter's timer fires first, sends it on behalf of all of them. A
+//! failed batch fails every call in it with the same error.
+//!
+//! Only the wrapped embedder's [`Embedder::embed_batch`] decides
+//! whether batching saves requests; its default sends one request per
+//! text. Generation isn't batched: chat endpoints take one
+//! conversation per request, and provider batch APIs are offline jobs
+//! with hours of latency, not something to hide behind a call.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::future::{select, Either};
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+use crate::semantic_cache::{Embedder, SharedEmbedder};
+use crate::Error;
+
+type Reply = oneshot::Sender<Result<Vec<f32>, Error>>;
+
+/// Texts queued for the next [`Embedder::embed_batch`] call.
+#[derive(Default)]
+struct Batch {
+    texts: Vec<String>,
+    replies: Vec<Reply>,
+}
+
+struct Pending {
+    /// Bumped each time a batch opens, so a waiter can tell whether
+    /// the batch it joined is still the one queued.
+    generation: u64,
+    opened: Instant,
+    batch: Batch,
+}
+
+/// An [`Embedder`] that coalesces concurrent [`Embedder::embed`] calls
+/// into batched calls to the embedder it wraps. See the module docs.
+#[derive(Clone)]
+pub struct BatchingEmbedder {
+    inner: SharedEmbedder,
+    window: Duration,
+    max_batch_size: usize,
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl BatchingEmbedder {
+    /// Batch calls to `inner`. Defaults: a 5 ms window and at most 64
+    /// texts per batch.
+    pub fn new(inner: SharedEmbedder) -> Self {
+        Self {
+            inner,
+            window: Duration::from_millis(5),
+            max_batch_size: 64,
+            pending: Arc::new(Mutex::new(Pending {
+                generation: 0,
+                opened: Instant::now(),
+                batch: Batch::default(),
+            })),
+        }
+    }
+
+    /// How long a batch collects texts after its first one. Longer
+    /// windows make larger batches at the cost of added latency.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Send a batch as soon as it holds `max_batch_size` texts — the
+    /// upstream's input-list limit, typically. Clamped to at least 1.
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take the queued batch if it is still `generation`.
+    fn take(&self, generation: u64) -> Option<Batch> {
+        let mut pending = self.lock();
+        (pending.generation == generation && !pending.batch.texts.is_empty())
+            .then(|| std::mem::take(&mut pending.batch))
+    }
+
+    async fn send(&self, batch: Batch) {
+        let Batch { texts, replies } = batch;
+        match self.inner.embed_batch(&texts).await {
+            Ok(vectors) if vectors.len() == replies.len() => {
+                for (reply, vector) in replies.into_iter().zip(vectors) {
+                    let _ = reply.send(Ok(vector));
+                }
+            }
+            Ok(vectors) => {
+                let err = Error::provider(
+                    "BatchingEmbedder",
+                    format!(
+                        "embed_batch returned {} vectors for {} texts",
+                        vectors.len(),
+                        replies.len()
+                    ),
+                );
+                for reply in replies {
+                    let _ = reply.send(Err(err.duplicate()));
+                }
+            }
+            Err(err) => {
+                for reply in replies {
+                    let _ = reply.send(Err(err.duplicate()));
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for BatchingEmbedder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchingEmbedder")
+            .field("window", &self.window)
+            .field("max_batch_size", &self.max_batch_size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedder for BatchingEmbedder {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, Error> {
+        let (reply, rx) = oneshot::channel();
+        let (generation, deadline, full) = {
+            let mut pending = self.lock();
+            if pending.batch.texts.is_empty() {
+                pending.generation += 1;
+                pending.opened = Instant::now();
+            }
+            pending.batch.texts.push(text.to_owned());
+            pending.batch.replies.push(reply);
+            let full = (pending.batch.texts.len() >= self.max_batch_size)
+                .then(|| std::mem::take(&mut pending.batch));
+            (pending.generation, pending.opened + self.window, full)
+        };
+        let replied = match full {
+            Some(batch) => {
+                self.send(batch).await;
+                rx.await
+            }
+            None => {
+                let timer = std::pin::pin!(tokio::time::sleep_until(deadline));
+                match select(rx, timer).await {
+                    Either::Left((replied, _)) => replied,
+                    Either::Right((_, rx)) => {
+                        if let Some(batch) = self.take(generation) {
+                            self.send(batch).await;
+                        }
+                        rx.await
+                    }
+                }
+            }
+        };
+        replied.unwrap_or_else(|_| {
+            Err(Error::provider(
+                "BatchingEmbedder",
+                "the call sending this batch was cancelled",
+            ))
+        })
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Error> {
+        // Already a batch: pass it straight through.
+        self.inner.embed_batch(texts).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds each text as `[len]`, recording every batch it receives.
+    #[derive(Default)]
+    struct Recording {
+        batches: Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Embedder for Recording {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>, Error> {
+            Ok(vec![text.len() as f32])
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Error> {
+            self.batches.lock().unwrap().push(texts.to_vec());
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        }
+    }
+
+    fn texts(n: usize) -> Vec<String> {
+        (1..=n).map(|i| "x".repeat(i)).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_calls_share_one_batch() {
+        let inner = Arc::new(Recording::default());
+        let embedder = BatchingEmbedder::new(inner.clone());
+        let texts = texts(10);
+        let vectors = futures_util::future::join_all(texts.iter().map(|t| embedder.embed(t))).await;
+        for (i, vector) in vectors.into_iter().enumerate() {
+            assert_eq!(vector.unwrap(), [(i + 1) as f32]);
+        }
+        assert_eq!(*inner.batches.lock().unwrap(), [texts]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_batches_go_out_without_waiting_for_the_window() {
+        let inner = Arc::new(Recording::default());
+        let embedder = BatchingEmbedder::new(inner.clone())
+            .with_window(Duration::from_secs(60))
+            .with_max_batch_size(4);
+        let texts = texts(8);
+        let start = Instant::now();
+        futures_util::future::join_all(texts.iter().map(|t| embedder.embed(t))).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let sizes: Vec<_> = inner.batches.lock().unwrap().iter().map(Vec::len).collect();
+        assert_eq!(sizes, [4, 4]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_failed_batch_fails_every_caller() {
+        struct Failing;
+
+        #[async_trait::async_trait]
+        impl Embedder for Failing {
+            async fn embed(&self, _: &str) -> Result<Vec<f32>, Error> {
+                Err(Error::rate_limit(Some(1), "slow down"))
+            }
+        }
+
+        let embedder = BatchingEmbedder::new(Arc::new(Failing));
+        let (a, b) = futures_util::future::join(embedder.embed("a"), embedder.embed("b")).await;
+        assert!(matches!(a, Err(Error::RateLimit { .. })));
+        assert!(matches!(b, Err(Error::RateLimit { .. })));
+    }
+}
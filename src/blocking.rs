@@ -0,0 +1,154 @@
+//! Synchronous wrapper for callers without an async runtime.
+//!
+//! [`BlockingProvider`] owns a private current-thread Tokio runtime and
+//! drives any [`Provider`] on it, so a CLI tool or an otherwise
+//! synchronous codebase can make calls without setting up Tokio itself —
+//! the same trade-off as `reqwest::blocking`.
+//!
+//! ```ignore
+//! use platformed_llm::blocking::BlockingProvider;
+//! use platformed_llm::{Config, Prompt};
+//!
+//! let provider = BlockingProvider::new(OpenAIProvider::new(api_key)?)?;
+//! let config = Config::builder("gpt-4o-mini").build();
+//! let answer = provider.text_blocking(&Prompt::user("Hello"), &config)?;
+//!
+//! for event in provider.stream_blocking(&Prompt::user("Count to five"), &config)? {
+//!     println!("{:?}", event?);
+//! }
+//! ```
+//!
+//! Calls go through [`crate::generate`], so the config's middleware
+//! chain applies exactly as in async code.
+//!
+//! # Panics
+//!
+//! Every `*_blocking` method blocks the current thread on the owned
+//! runtime and panics when invoked from inside an async context (Tokio
+//! refuses to nest runtimes). Async code should call the provider
+//! directly instead.
+
+use futures_util::StreamExt;
+use tokio::runtime::Runtime;
+
+use crate::layer::SharedProvider;
+use crate::{CompleteResponse, Config, Error, EventStream, Prompt, Provider, StreamEvent};
+
+/// A [`Provider`] paired with its own runtime, exposing synchronous
+/// calls. See the module docs.
+pub struct BlockingProvider {
+    inner: SharedProvider,
+    runtime: Runtime,
+}
+
+impl BlockingProvider {
+    /// Wrap `inner` with a fresh current-thread runtime. Fails only if
+    /// the runtime can't be created.
+    pub fn new(inner: impl Provider) -> Result<Self, Error> {
+        Self::from_shared(std::sync::Arc::new(inner))
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider) -> Result<Self, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| Error::config(format!("failed to start blocking runtime: {err}")))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Run a call to completion and return the buffered response.
+    pub fn generate_blocking(
+        &self,
+        prompt: &Prompt,
+        config: &Config,
+    ) -> Result<CompleteResponse, Error> {
+        self.runtime.block_on(async {
+            crate::generate(&*self.inner, prompt, config)
+                .await?
+                .buffer()
+                .await
+        })
+    }
+
+    /// Run a call to completion and return the concatenated text.
+    pub fn text_blocking(&self, prompt: &Prompt, config: &Config) -> Result<String, Error> {
+        self.runtime.block_on(async {
+            crate::generate(&*self.inner, prompt, config)
+                .await?
+                .text()
+                .await
+        })
+    }
+
+    /// Start a call and return its events as a blocking iterator. Each
+    /// `next` drives the runtime until the following event arrives, so
+    /// output can be printed as it streams. Dropping the iterator
+    /// cancels the call.
+    pub fn stream_blocking(
+        &self,
+        prompt: &Prompt,
+        config: &Config,
+    ) -> Result<BlockingStream<'_>, Error> {
+        let response = self
+            .runtime
+            .block_on(crate::generate(&*self.inner, prompt, config))?;
+        Ok(BlockingStream {
+            runtime: &self.runtime,
+            stream: response.stream(),
+        })
+    }
+}
+
+impl std::fmt::Debug for BlockingProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingProvider").finish_non_exhaustive()
+    }
+}
+
+/// Blocking iterator over a response's events, from
+/// [`BlockingProvider::stream_blocking`].
+pub struct BlockingStream<'a> {
+    runtime: &'a Runtime,
+    stream: EventStream,
+}
+
+impl Iterator for BlockingStream<'_> {
+    type Item = Result<StreamEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+impl std::fmt::Debug for BlockingStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingStream").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+
+    #[test]
+    fn text_and_stream_without_a_caller_runtime() {
+        let provider = BlockingProvider::new(MockProvider::with_text("one two")).unwrap();
+        let config = Config::builder("m").build();
+
+        assert_eq!(
+            provider.text_blocking(&Prompt::user("x"), &config).unwrap(),
+            "one two"
+        );
+        let deltas: String = provider
+            .stream_blocking(&Prompt::user("x"), &config)
+            .unwrap()
+            .filter_map(|event| match event.unwrap() {
+                StreamEvent::Delta { delta, .. } => Some(delta),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deltas, "one two");
+    }
+}
@@ -0,0 +1,186 @@
+//! Bounded, backpressure-aware streaming.
+//!
+//! A [`Response`] is pulled lazily: nothing is read off the wire until
+//! the consumer polls. That breaks down once the consumer hands events
+//! to something decoupled from it — a UI task behind an unbounded
+//! channel, a websocket writer — and the backlog grows without limit
+//! when that side falls behind. [`Response::bounded`] moves the
+//! upstream reads onto a separate [`StreamPump`] future that feeds a
+//! channel of fixed `capacity`, and applies an [`OverflowPolicy`]
+//! when the channel is full:
+//!
+//! - [`OverflowPolicy::Wait`] — the pump stops reading until the
+//!   consumer catches up, so the slowdown propagates to the upstream
+//!   connection (TCP flow control) instead of piling up in memory.
+//! - [`OverflowPolicy::Fail`] — the pump gives up and drops the
+//!   upstream request. The consumer still gets the events already
+//!   buffered, then [`Error::StreamOverflow`], then the end of the
+//!   stream. For consumers that would rather fail fast than hold a
+//!   connection open while they stall.
+//!
+//! The crate doesn't assume a runtime, so spawning the pump is the
+//! caller's job; until it runs, the response yields nothing:
+//!
+//! ```ignore
+//! use platformed_llm::OverflowPolicy;
+//!
+//! let (response, pump) = generate(&provider, &prompt, &config)
+//!     .await?
+//!     .bounded(64, OverflowPolicy::Wait);
+//! tokio::spawn(pump);
+//! ```
+//!
+//! Dropping the bounded response stops the pump at its next event,
+//! which drops (and so cancels) the upstream call.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+
+use crate::{Error, Response};
+
+/// What [`Response::bounded`]'s pump does when the consumer is
+/// `capacity` events behind. See the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum OverflowPolicy {
+    /// Stop reading from the upstream until there is room.
+    #[default]
+    Wait,
+    /// End the stream with [`Error::StreamOverflow`] and cancel the
+    /// upstream call.
+    Fail,
+}
+
+/// The future driving a [`Response::bounded`] stream. Spawn it on the
+/// caller's runtime; it completes when the upstream stream ends, the
+/// consumer goes away, or [`OverflowPolicy::Fail`] trips.
+#[must_use = "the bounded response yields nothing until its pump runs"]
+pub struct StreamPump {
+    future: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl std::fmt::Debug for StreamPump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamPump").finish_non_exhaustive()
+    }
+}
+
+impl Future for StreamPump {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.future.as_mut().poll(cx)
+    }
+}
+
+impl Response {
+    /// Decouple reading the upstream from consuming the events through a
+    /// channel holding at most `capacity` events. Returns the bounded
+    /// response, metadata kept, and the [`StreamPump`] that must be
+    /// spawned to feed it. See [`crate::bounded`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn bounded(self, capacity: usize, policy: OverflowPolicy) -> (Response, StreamPump) {
+        assert!(capacity > 0, "bounded stream capacity must be non-zero");
+        let (tx, rx) = mpsc::channel(capacity);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        let mut pump = None;
+        let response = self.map_stream(|mut upstream| {
+            let flag = Arc::clone(&overflowed);
+            pump = Some(StreamPump {
+                future: Box::pin(async move {
+                    while let Some(item) = upstream.next().await {
+                        let delivered = match policy {
+                            OverflowPolicy::Wait => tx.send(item).await.is_ok(),
+                            OverflowPolicy::Fail => match tx.try_send(item) {
+                                Ok(()) => true,
+                                Err(mpsc::error::TrySendError::Full(_)) => {
+                                    flag.store(true, Ordering::Release);
+                                    false
+                                }
+                                Err(mpsc::error::TrySendError::Closed(_)) => false,
+                            },
+                        };
+                        if !delivered {
+                            break;
+                        }
+                    }
+                }),
+            });
+            futures_util::stream::unfold(Some(rx), move |state| {
+                let overflowed = Arc::clone(&overflowed);
+                async move {
+                    let mut rx = state?;
+                    match rx.recv().await {
+                        Some(item) => Some((item, Some(rx))),
+                        None if overflowed.load(Ordering::Acquire) => {
+                            Some((Err(Error::stream_overflow(capacity)), None))
+                        }
+                        None => None,
+                    }
+                }
+            })
+        });
+        (response, pump.expect("map_stream calls its closure"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PartKind, StreamEvent};
+    use std::sync::atomic::AtomicUsize;
+
+    /// `count` events, bumping `pulled` as each is read off the source.
+    fn counted(count: u32, pulled: Arc<AtomicUsize>) -> Response {
+        Response::from_stream(futures_util::stream::iter(0..count).map(move |index| {
+            pulled.fetch_add(1, Ordering::SeqCst);
+            Ok(StreamEvent::PartStart {
+                index,
+                kind: PartKind::Text,
+            })
+        }))
+    }
+
+    #[tokio::test]
+    async fn wait_policy_stops_reading_ahead_of_a_slow_consumer() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let (response, pump) = counted(100, pulled.clone()).bounded(4, OverflowPolicy::Wait);
+        let pump = tokio::spawn(pump);
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        // Four buffered, plus the one waiting for room.
+        assert_eq!(pulled.load(Ordering::SeqCst), 5);
+
+        let events: Vec<_> = StreamExt::collect(response).await;
+        assert_eq!(events.len(), 100);
+        assert!(events.iter().all(Result::is_ok));
+        pump.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn fail_policy_delivers_the_buffer_then_overflows() {
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let (response, pump) = counted(100, pulled.clone()).bounded(4, OverflowPolicy::Fail);
+        pump.await;
+        assert_eq!(pulled.load(Ordering::SeqCst), 5);
+
+        let events: Vec<_> = StreamExt::collect(response).await;
+        assert_eq!(events.len(), 5);
+        assert!(events[..4].iter().all(Result::is_ok));
+        let err = events[4].as_ref().unwrap_err();
+        assert!(
+            matches!(err, Error::StreamOverflow { capacity: 4 }),
+            "got {err}"
+        );
+    }
+}
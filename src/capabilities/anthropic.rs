@@ -24,6 +24,8 @@ const fn caps(context: u32, output: u32) -> Capabilities {
         response_schema_with_tools: false,
         context_window_tokens: context,
         max_output_tokens: output,
+        sampling_parameters: true,
+        penalties: false,
     }
 }
 
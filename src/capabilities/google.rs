@@ -4,7 +4,8 @@
 //! Google's docs, the 3.x series supports combining `responseSchema`
 //! with function-calling tools (preview), while 2.5 / 2.0 / 1.5
 //! support schema-constrained output but **not** in combination with
-//! tools.
+//! tools. Presence / frequency penalties are accepted by 2.0 and 1.5
+//! only; 2.5 and later reject them.
 
 use super::{Capabilities, ModelEntry, ModelMatch};
 use ModelMatch::Prefix;
@@ -18,6 +19,16 @@ const fn caps(schema_with_tools: bool, context: u32, output: u32) -> Capabilitie
         response_schema_with_tools: schema_with_tools,
         context_window_tokens: context,
         max_output_tokens: output,
+        sampling_parameters: true,
+        penalties: false,
+    }
+}
+
+/// [`caps`] for the pre-2.5 models that still accept penalties.
+const fn with_penalties(caps: Capabilities) -> Capabilities {
+    Capabilities {
+        penalties: true,
+        ..caps
     }
 }
 
@@ -32,12 +43,27 @@ pub(super) static MODELS: &[ModelEntry] = &[
     (Prefix("gemini-2.5-pro"), caps(false, 1_048_576, 65_536)),
     (Prefix("gemini-2.5"), caps(false, 1_048_576, 65_535)),
     // ----- Gemini 2.0 -----
-    (Prefix("gemini-2.0"), caps(false, 1_000_000, 8_192)),
+    (
+        Prefix("gemini-2.0"),
+        with_penalties(caps(false, 1_000_000, 8_192)),
+    ),
     // ----- Gemini 1.5 -----
-    (Prefix("gemini-1.5-pro"), caps(false, 2_000_000, 8192)),
-    (Prefix("gemini-1.5-flash-8b"), caps(false, 1_000_000, 8192)),
-    (Prefix("gemini-1.5-flash"), caps(false, 1_000_000, 8192)),
-    (Prefix("gemini-1.5"), caps(false, 1_000_000, 8192)),
+    (
+        Prefix("gemini-1.5-pro"),
+        with_penalties(caps(false, 2_000_000, 8192)),
+    ),
+    (
+        Prefix("gemini-1.5-flash-8b"),
+        with_penalties(caps(false, 1_000_000, 8192)),
+    ),
+    (
+        Prefix("gemini-1.5-flash"),
+        with_penalties(caps(false, 1_000_000, 8192)),
+    ),
+    (
+        Prefix("gemini-1.5"),
+        with_penalties(caps(false, 1_000_000, 8192)),
+    ),
     // ----- Family catch-all -----
     (Prefix("gemini-"), caps(false, 1_000_000, 8192)),
 ];
@@ -127,6 +127,14 @@ pub struct Capabilities {
     /// `max_tokens` higher than this is a caller error that will
     /// surface server-side.
     pub max_output_tokens: u32,
+    /// Model accepts the sampling parameters `temperature`, `top_p`
+    /// and the presence / frequency penalties. `false` for OpenAI's
+    /// reasoning models (o-series, GPT-5), which reject them with a
+    /// 400; the OpenAI provider omits them from those requests.
+    pub sampling_parameters: bool,
+    /// Model accepts `presence_penalty` / `frequency_penalty`. Anthropic
+    /// has no penalty parameters, and Gemini 2.5+ rejects them.
+    pub penalties: bool,
 }
 
 impl Default for Capabilities {
@@ -136,7 +144,9 @@ impl Default for Capabilities {
     /// using. Always overriding-friendly — the headroom helpers
     /// against these values err on the side of triggering compaction
     /// earlier than necessary, which is the safe direction for a
-    /// fallback.
+    /// fallback. Sampling parameters and penalties are assumed
+    /// accepted: dropping a caller's `temperature` on an unknown model
+    /// is the surprising direction.
     fn default() -> Self {
         Self {
             native_json_mode: false,
@@ -144,6 +154,8 @@ impl Default for Capabilities {
             response_schema_with_tools: false,
             context_window_tokens: 4096,
             max_output_tokens: 1024,
+            sampling_parameters: true,
+            penalties: true,
         }
     }
 }
@@ -166,6 +178,24 @@ impl Capabilities {
     pub fn would_exceed_context(&self, tokens: u32) -> bool {
         tokens > self.context_window_tokens
     }
+
+    /// Whether `prompt` sent with `config` is likely to fit this model's
+    /// context window: the prompt's [`crate::Prompt::estimated_tokens`],
+    /// plus the tool definitions, plus the `max_tokens` the request
+    /// reserves for output (providers reject input + `max_tokens` over
+    /// the window). An estimate — leave a margin rather than packing
+    /// the window to the last token.
+    pub fn fits_in_context(&self, prompt: &crate::Prompt, config: &crate::RawConfig) -> bool {
+        let tools = config.tools.as_ref().map_or(0, |tools| {
+            serde_json::to_string(tools)
+                .map_or(0, |json| crate::types::estimated_text_tokens(&json))
+        });
+        let needed = prompt
+            .estimated_tokens()
+            .saturating_add(tools)
+            .saturating_add(config.max_tokens.unwrap_or(0));
+        !self.would_exceed_context(needed)
+    }
 }
 
 impl Capabilities {
@@ -281,6 +311,16 @@ mod openai;
 mod tests {
     use super::*;
 
+    #[test]
+    fn fits_in_context_counts_the_output_reservation() {
+        let caps = Capabilities::default();
+        let prompt = crate::Prompt::user("x".repeat(8_000));
+        let config = crate::Config::builder("m").build();
+        assert!(caps.fits_in_context(&prompt, config.raw()));
+        let config = crate::Config::builder("m").max_tokens(3_000).build();
+        assert!(!caps.fits_in_context(&prompt, config.raw()));
+    }
+
     #[test]
     fn default_is_most_restrictive() {
         let c = Capabilities::default();
@@ -323,6 +363,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn openai_reasoning_models_reject_sampling_parameters() {
+        assert!(!Capabilities::openai("o3-2025-04-16").sampling_parameters);
+        assert!(!Capabilities::openai("gpt-5-mini").sampling_parameters);
+        assert!(Capabilities::openai("gpt-5-chat-latest").sampling_parameters);
+        assert!(Capabilities::openai("gpt-4o").sampling_parameters);
+        assert!(Capabilities::for_model("gemini-2.5-pro").sampling_parameters);
+    }
+
     #[test]
     fn openai_prefix_fallback_for_version_suffix() {
         // Dated / pinned variants must match the family prefix entry.
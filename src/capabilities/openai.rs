@@ -17,19 +17,34 @@ const fn caps(context: u32, output: u32) -> Capabilities {
         response_schema_with_tools: true,
         context_window_tokens: context,
         max_output_tokens: output,
+        sampling_parameters: true,
+        penalties: true,
+    }
+}
+
+/// [`caps`] for a reasoning model: same JSON support, but
+/// `temperature` / `top_p` / penalties are rejected.
+const fn reasoning(context: u32, output: u32) -> Capabilities {
+    Capabilities {
+        sampling_parameters: false,
+        penalties: false,
+        ..caps(context, output)
     }
 }
 
 /// OpenAI model table, ordered most-specific first.
 pub(super) static MODELS: &[ModelEntry] = &[
     // ----- GPT-5 family (released 2025; gpt-5.5 added 2026-04) -----
-    (Prefix("gpt-5.5"), caps(1_050_000, 128_000)),
-    (Prefix("gpt-5.4-mini"), caps(400_000, 128_000)),
-    (Prefix("gpt-5.4-nano"), caps(400_000, 128_000)),
-    (Prefix("gpt-5.4"), caps(1_050_000, 128_000)),
-    (Prefix("gpt-5-mini"), caps(400_000, 128_000)),
-    (Prefix("gpt-5-nano"), caps(400_000, 128_000)),
-    (Prefix("gpt-5"), caps(400_000, 128_000)),
+    (Prefix("gpt-5.5"), reasoning(1_050_000, 128_000)),
+    (Prefix("gpt-5.4-mini"), reasoning(400_000, 128_000)),
+    (Prefix("gpt-5.4-nano"), reasoning(400_000, 128_000)),
+    (Prefix("gpt-5.4"), reasoning(1_050_000, 128_000)),
+    // gpt-5-chat is the non-reasoning ChatGPT snapshot — sampling
+    // parameters allowed, smaller window.
+    (Prefix("gpt-5-chat"), caps(128_000, 16_384)),
+    (Prefix("gpt-5-mini"), reasoning(400_000, 128_000)),
+    (Prefix("gpt-5-nano"), reasoning(400_000, 128_000)),
+    (Prefix("gpt-5"), reasoning(400_000, 128_000)),
     // ----- GPT-4.1 family (1M context) -----
     (Prefix("gpt-4.1"), caps(1_047_576, 32_768)),
     // ----- GPT-4o family -----
@@ -50,13 +65,13 @@ pub(super) static MODELS: &[ModelEntry] = &[
     (Exact("gpt-4"), caps(8192, 8192)),
     (Prefix("gpt-4-"), caps(8192, 8192)),
     // ----- o-series reasoning models -----
-    (Prefix("o1-mini"), caps(128_000, 65_536)),
-    (Prefix("o1-preview"), caps(128_000, 32_768)),
-    (Prefix("o1"), caps(200_000, 100_000)),
-    (Prefix("o3-mini"), caps(200_000, 100_000)),
-    (Prefix("o3"), caps(200_000, 100_000)),
-    (Prefix("o4-mini"), caps(200_000, 100_000)),
-    (Prefix("o4"), caps(200_000, 100_000)),
+    (Prefix("o1-mini"), reasoning(128_000, 65_536)),
+    (Prefix("o1-preview"), reasoning(128_000, 32_768)),
+    (Prefix("o1"), reasoning(200_000, 100_000)),
+    (Prefix("o3-mini"), reasoning(200_000, 100_000)),
+    (Prefix("o3"), reasoning(200_000, 100_000)),
+    (Prefix("o4-mini"), reasoning(200_000, 100_000)),
+    (Prefix("o4"), reasoning(200_000, 100_000)),
     // ----- Family catch-all -----
     // No `Prefix("o")` row on purpose — it would over-match names like
     // `openai-experimental` or `oracle-x` and disagree with
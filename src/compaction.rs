@@ -432,7 +432,7 @@ fn group_items(items: Vec<InputItem>) -> Vec<Group> {
     let mut iter = items.into_iter().peekable();
     while let Some(item) = iter.next() {
         match item {
-            InputItem::Assistant { ref content } if has_tool_call(content) => {
+            InputItem::Assistant { ref content, .. } if has_tool_call(content) => {
                 // Try to fuse with the next user turn IF that user
                 // turn's content has any ToolResult parts.
                 if iter.peek().is_some_and(is_user_with_tool_result) {
@@ -453,8 +453,9 @@ fn group_items(items: Vec<InputItem>) -> Vec<Group> {
             }
             // System slipping through here is unusual but we preserve
             // it as a User-shaped pass-through so the rebuild doesn't
-            // drop it silently.
-            InputItem::System(_) => {
+            // drop it silently. Developer instructions (never split
+            // off) take the same path and keep their position.
+            InputItem::System(_) | InputItem::Developer(_) => {
                 groups.push(Group::User(item));
             }
         }
@@ -472,7 +473,7 @@ fn has_tool_call(content: &[crate::AssistantPart]) -> bool {
 fn is_user_with_tool_result(item: &InputItem) -> bool {
     use crate::UserPart;
     match item {
-        InputItem::User { content } => content
+        InputItem::User { content, .. } => content
             .iter()
             .any(|p| matches!(p, UserPart::ToolResult { .. })),
         _ => false,
@@ -654,7 +655,7 @@ mod tests {
         assert_eq!(items.len(), 3, "{items:?}");
         assert!(matches!(&items[0], InputItem::System(s) if s == "be helpful"));
         match &items[1] {
-            InputItem::User { content } => {
+            InputItem::User { content, .. } => {
                 assert_eq!(content.len(), 1);
                 match &content[0] {
                     UserPart::Text(t) => {
@@ -667,7 +668,7 @@ mod tests {
             other => panic!("memo must land as user turn, got {other:?}"),
         }
         match &items[2] {
-            InputItem::User { content } => match &content[0] {
+            InputItem::User { content, .. } => match &content[0] {
                 UserPart::Text(t) => assert_eq!(t, "the live question"),
                 other => panic!("tail's first part must be the verbatim question, got {other:?}"),
             },
@@ -695,6 +696,7 @@ mod tests {
                 name: "search".into(),
                 arguments: r#"{"q":"old"}"#.into(),
                 provider_signature: None,
+                original_arguments: None,
             })
             .with_tool_result("call_old", "old result")
             .with_assistant("here you go")
@@ -704,6 +706,7 @@ mod tests {
                 name: "search".into(),
                 arguments: r#"{"q":"new"}"#.into(),
                 provider_signature: None,
+                original_arguments: None,
             })
             .with_tool_result("call_pending", "fresh result");
 
@@ -724,7 +727,7 @@ mod tests {
         // The pending tool_call rides through; the OLDER call_old is
         // summarized into the memo, NOT preserved as a turn.
         match &items[2] {
-            InputItem::Assistant { content } => {
+            InputItem::Assistant { content, .. } => {
                 let calls: Vec<&FunctionCall> = content
                     .iter()
                     .filter_map(|p| match p {
@@ -741,7 +744,7 @@ mod tests {
             other => panic!("expected preserved assistant tool_call, got {other:?}"),
         }
         match &items[3] {
-            InputItem::User { content } => {
+            InputItem::User { content, .. } => {
                 let results: Vec<&str> = content
                     .iter()
                     .filter_map(|p| match p {
@@ -774,26 +777,34 @@ mod tests {
                     name: "get_weather".into(),
                     arguments: r#"{"city":"Paris"}"#.into(),
                     provider_signature: None,
+                    original_arguments: None,
                 }),
                 AssistantPart::ToolCall(FunctionCall {
                     call_id: "call_b".into(),
                     name: "get_weather".into(),
                     arguments: r#"{"city":"London"}"#.into(),
                     provider_signature: None,
+                    original_arguments: None,
                 }),
             ],
+            name: None,
+            metadata: Default::default(),
         };
         let parallel_results = InputItem::User {
             content: vec![
                 UserPart::ToolResult {
                     call_id: "call_a".into(),
                     content: vec![UserPart::Text("sunny".into())],
+                    is_error: false,
                 },
                 UserPart::ToolResult {
                     call_id: "call_b".into(),
                     content: vec![UserPart::Text("rainy".into())],
+                    is_error: false,
                 },
             ],
+            name: None,
+            metadata: Default::default(),
         };
         let prompt = Prompt::system("sys")
             .with_user("warm up")
@@ -812,7 +823,7 @@ mod tests {
         // Shape: [system, user(memo), assistant(call_a + call_b), user(result_a + result_b)]
         assert_eq!(items.len(), 4, "{items:?}");
         match &items[2] {
-            InputItem::Assistant { content } => {
+            InputItem::Assistant { content, .. } => {
                 let ids: Vec<&str> = content
                     .iter()
                     .filter_map(|p| match p {
@@ -825,7 +836,7 @@ mod tests {
             other => panic!("expected parallel assistant block, got {other:?}"),
         }
         match &items[3] {
-            InputItem::User { content } => {
+            InputItem::User { content, .. } => {
                 let ids: Vec<&str> = content
                     .iter()
                     .filter_map(|p| match p {
@@ -870,7 +881,7 @@ mod tests {
         assert!(matches!(&items[0], InputItem::System(_)));
         assert!(matches!(&items[1], InputItem::User { .. }));
         match &items[2] {
-            InputItem::Assistant { content } => {
+            InputItem::Assistant { content, .. } => {
                 use crate::AssistantPart;
                 assert!(content.iter().any(|p| matches!(
                     p,
@@ -899,6 +910,8 @@ mod tests {
                 UserPart::CacheBreakpoint,
                 UserPart::Text("what do you see?".into()),
             ],
+            name: None,
+            metadata: Default::default(),
         };
         let prompt = Prompt::system("sys")
             .with_user("warm up")
@@ -917,7 +930,14 @@ mod tests {
         // Tail at index 2 must equal the multipart input we supplied —
         // same parts in the same order.
         match (&items[2], &multipart_tail) {
-            (InputItem::User { content: actual }, InputItem::User { content: expected }) => {
+            (
+                InputItem::User {
+                    content: actual, ..
+                },
+                InputItem::User {
+                    content: expected, ..
+                },
+            ) => {
                 assert_eq!(actual.len(), expected.len(), "tail part count drifted");
                 for (i, (a, e)) in actual.iter().zip(expected.iter()).enumerate() {
                     match (a, e) {
@@ -965,7 +985,7 @@ mod tests {
         let request_items = calls[0].prompt.items();
         // The held-out tail must not be in the summarization request.
         for item in request_items {
-            if let InputItem::User { content } = item {
+            if let InputItem::User { content, .. } = item {
                 for part in content {
                     if let UserPart::Text(t) = part {
                         assert!(
@@ -979,7 +999,7 @@ mod tests {
         // Sanity: the items being discarded ARE in the summarization
         // request (so the model has something to summarize).
         let saw_discarded = request_items.iter().any(|i| match i {
-            InputItem::User { content } => content
+            InputItem::User { content, .. } => content
                 .iter()
                 .any(|p| matches!(p, UserPart::Text(t) if t.contains("first thing"))),
             _ => false,
@@ -1072,21 +1092,21 @@ mod tests {
         use crate::AssistantPart;
         assert!(matches!(
             &items[2],
-            InputItem::Assistant { content } if content.iter().any(|p| matches!(
+            InputItem::Assistant { content, .. } if content.iter().any(|p| matches!(
                 p,
                 AssistantPart::Text { content: t, .. } if t == "a2"
             ))
         ));
         assert!(matches!(
             &items[3],
-            InputItem::User { content } if content.iter().any(|p| matches!(
+            InputItem::User { content, .. } if content.iter().any(|p| matches!(
                 p,
                 UserPart::Text(t) if t == "q3"
             ))
         ));
         assert!(matches!(
             &items[4],
-            InputItem::Assistant { content } if content.iter().any(|p| matches!(
+            InputItem::Assistant { content, .. } if content.iter().any(|p| matches!(
                 p,
                 AssistantPart::Text { content: t, .. } if t == "a3"
             ))
@@ -0,0 +1,195 @@
+//! Per-provider concurrency limiting.
+//!
+//! A burst of background jobs fanned out over one provider can open
+//! hundreds of simultaneous streams against a single API key — more
+//! than the upstream allows, and more sockets than the process wants.
+//! [`ConcurrencyLimitProvider`] caps the calls in flight at once;
+//! callers past the cap queue (first come, first served) for a slot.
+//!
+//! A slot is held for the whole call: from before the request is sent
+//! until its response stream ends or is dropped, so the cap bounds open
+//! streams, not just open handshakes. A failed call frees its slot
+//! immediately.
+//!
+//! The factory wraps its provider in one when
+//! [`crate::ProviderConfig::with_max_in_flight`] is set. To share one
+//! cap across several providers (e.g. different models behind the same
+//! key), wrap them all with one [`ConcurrencyLimitLayer`]. For a
+//! request-rate budget on top, see
+//! [`crate::rate_limit::TokenBucketRateLimiter`].
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{Capabilities, Error, EventStream, Prompt, Provider, RawConfig, Response};
+
+/// A [`Provider`] wrapper allowing at most `max_in_flight` calls
+/// through at once. See the module docs.
+#[derive(Clone)]
+pub struct ConcurrencyLimitProvider {
+    inner: SharedProvider,
+    max_in_flight: usize,
+    slots: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitProvider {
+    /// Allow at most `max_in_flight` concurrent calls to `inner`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_in_flight` is zero.
+    pub fn new(inner: impl Provider, max_in_flight: usize) -> Self {
+        Self::from_shared(Arc::new(inner), max_in_flight)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_in_flight` is zero.
+    pub fn from_shared(inner: SharedProvider, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "max_in_flight must be > 0");
+        Self::with_slots(
+            inner,
+            max_in_flight,
+            Arc::new(Semaphore::new(max_in_flight)),
+        )
+    }
+
+    fn with_slots(inner: SharedProvider, max_in_flight: usize, slots: Arc<Semaphore>) -> Self {
+        Self {
+            inner,
+            max_in_flight,
+            slots,
+        }
+    }
+
+    /// The configured cap.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Slots free right now.
+    pub fn available(&self) -> usize {
+        self.slots.available_permits()
+    }
+}
+
+impl std::fmt::Debug for ConcurrencyLimitProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConcurrencyLimitProvider")
+            .field("max_in_flight", &self.max_in_flight)
+            .field("available", &self.available())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for ConcurrencyLimitProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let slot = Arc::clone(&self.slots)
+            .acquire_owned()
+            .await
+            .map_err(|_| Error::config("concurrency limit semaphore closed"))?;
+        let response = self.inner.generate(prompt, config).await?;
+        Ok(response.map_stream(move |stream| holding(stream, slot)))
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// `stream`, releasing `slot` once it ends (or is dropped).
+fn holding(stream: EventStream, slot: OwnedSemaphorePermit) -> EventStream {
+    Box::pin(futures_util::stream::unfold(
+        (stream, slot),
+        |(mut stream, slot)| async move {
+            let item = stream.next().await?;
+            Some((item, (stream, slot)))
+        },
+    ))
+}
+
+/// [`ProviderLayer`] that wraps providers in a
+/// [`ConcurrencyLimitProvider`]. Every provider one layer wraps draws
+/// from the same slots, so the cap is per layer, not per provider.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitLayer {
+    max_in_flight: usize,
+    slots: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimitLayer {
+    /// A layer allowing `max_in_flight` concurrent calls across all
+    /// the providers it wraps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_in_flight` is zero.
+    pub fn new(max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "max_in_flight must be > 0");
+        Self {
+            max_in_flight,
+            slots: Arc::new(Semaphore::new(max_in_flight)),
+        }
+    }
+}
+
+impl ProviderLayer for ConcurrencyLimitLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(ConcurrencyLimitProvider::with_slots(
+            inner,
+            self.max_in_flight,
+            Arc::clone(&self.slots),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, Config};
+    use std::time::Duration;
+
+    async fn call(provider: &impl Provider) -> Response {
+        generate(provider, &Prompt::user("x"), &Config::builder("m").build())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slot_is_held_until_the_stream_ends() {
+        let provider = ConcurrencyLimitProvider::new(MockProvider::with_text("hi"), 1);
+        let first = call(&provider).await;
+        assert_eq!(provider.available(), 0);
+        let blocked = tokio::time::timeout(Duration::from_secs(5), call(&provider)).await;
+        assert!(blocked.is_err(), "second call must wait for the slot");
+
+        assert_eq!(first.text().await.unwrap(), "hi");
+        assert_eq!(provider.available(), 1);
+        let second = call(&provider).await;
+        drop(second);
+        assert_eq!(provider.available(), 1);
+    }
+
+    #[tokio::test]
+    async fn layer_shares_one_cap_across_providers() {
+        let layer = ConcurrencyLimitLayer::new(1);
+        let a = layer.layer(Arc::new(MockProvider::with_text("a")));
+        let b = layer.layer(Arc::new(MockProvider::with_text("b")));
+        let held = call(&a).await;
+        let blocked = tokio::time::timeout(Duration::from_millis(50), call(&b)).await;
+        assert!(blocked.is_err(), "providers from one layer share slots");
+        drop(held);
+        assert_eq!(call(&b).await.text().await.unwrap(), "b");
+    }
+}
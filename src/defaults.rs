@@ -0,0 +1,201 @@
+//! Per-provider request defaults.
+//!
+//! Applications usually pick one model and a couple of generation
+//! settings per deployment, then repeat them on every
+//! [`crate::Config::builder`] call. [`DefaultsProvider`] holds them on
+//! the provider instead and fills them into every call that leaves them
+//! unset:
+//!
+//! - **model** — when the request's model is empty, as with
+//!   [`crate::Config::with_provider_defaults`].
+//! - **temperature** / **max_tokens** — when the request's is `None`.
+//!
+//! The factory wraps its provider in one when
+//! [`crate::ProviderConfig::with_default_model`] (or one of its
+//! siblings) is set. [`DefaultsProvider::capabilities`] resolves an
+//! empty model to the default too, so [`crate::generate`] picks
+//! middleware for the model that will actually serve the call.
+
+use std::sync::Arc;
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response};
+
+/// Values a [`DefaultsProvider`] fills into requests that leave them
+/// unset. See the module docs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestDefaults {
+    /// Model used when the request's model is empty.
+    pub model: Option<String>,
+    /// `temperature` used when the request sets none.
+    pub temperature: Option<f32>,
+    /// `max_tokens` used when the request sets none.
+    pub max_tokens: Option<u32>,
+}
+
+impl RequestDefaults {
+    /// Whether no default is set, i.e. applying these is a no-op.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// `config` with every unset field this holds a default for filled
+    /// in, or `None` when nothing changes.
+    fn apply(&self, config: &RawConfig) -> Option<RawConfig> {
+        let model = self.model.as_ref().filter(|_| config.model.is_empty());
+        let temperature = self.temperature.filter(|_| config.temperature.is_none());
+        let max_tokens = self.max_tokens.filter(|_| config.max_tokens.is_none());
+        if model.is_none() && temperature.is_none() && max_tokens.is_none() {
+            return None;
+        }
+        let mut config = config.clone();
+        if let Some(model) = model {
+            config.model = model.clone();
+        }
+        config.temperature = config.temperature.or(temperature);
+        config.max_tokens = config.max_tokens.or(max_tokens);
+        Some(config)
+    }
+}
+
+/// A [`Provider`] wrapper filling [`RequestDefaults`] into every call.
+/// A call with an empty model and no default model fails with
+/// [`Error::Config`].
+#[derive(Clone)]
+pub struct DefaultsProvider {
+    inner: SharedProvider,
+    defaults: RequestDefaults,
+}
+
+impl DefaultsProvider {
+    /// Apply `defaults` to `inner`'s calls.
+    pub fn new(inner: impl Provider, defaults: RequestDefaults) -> Self {
+        Self::from_shared(Arc::new(inner), defaults)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, defaults: RequestDefaults) -> Self {
+        Self { inner, defaults }
+    }
+
+    /// The defaults applied.
+    pub fn defaults(&self) -> &RequestDefaults {
+        &self.defaults
+    }
+}
+
+impl std::fmt::Debug for DefaultsProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DefaultsProvider")
+            .field("defaults", &self.defaults)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for DefaultsProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let applied = self.defaults.apply(config);
+        let config = applied.as_ref().unwrap_or(config);
+        if config.model.is_empty() {
+            return Err(Error::config(
+                "request sets no model and the provider has no default model",
+            ));
+        }
+        self.inner.generate(prompt, config).await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        match (&self.defaults.model, model.is_empty()) {
+            (Some(default), true) => self.inner.capabilities(default),
+            _ => self.inner.capabilities(model),
+        }
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in a [`DefaultsProvider`].
+#[derive(Debug, Clone)]
+pub struct DefaultsLayer {
+    defaults: RequestDefaults,
+}
+
+impl DefaultsLayer {
+    /// A layer giving every wrapped provider `defaults`.
+    pub fn new(defaults: RequestDefaults) -> Self {
+        Self { defaults }
+    }
+}
+
+impl ProviderLayer for DefaultsLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(DefaultsProvider::from_shared(inner, self.defaults.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, Config};
+
+    fn defaults() -> RequestDefaults {
+        RequestDefaults {
+            model: Some("gpt-4o-mini".into()),
+            temperature: Some(0.2),
+            max_tokens: Some(512),
+        }
+    }
+
+    #[tokio::test]
+    async fn unset_fields_take_the_defaults() {
+        let mock = MockProvider::with_text("hi");
+        let log = mock.call_log();
+        let provider = DefaultsProvider::new(mock, defaults());
+        generate(
+            &provider,
+            &Prompt::user("x"),
+            &Config::with_provider_defaults().max_tokens(64).build(),
+        )
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+        let config = &log.calls()[0].config;
+        assert_eq!(config.model, "gpt-4o-mini");
+        assert_eq!(config.temperature, Some(0.2));
+        assert_eq!(config.max_tokens, Some(64));
+    }
+
+    #[tokio::test]
+    async fn empty_model_without_a_default_is_a_config_error() {
+        let provider = DefaultsProvider::new(
+            MockProvider::with_text("hi"),
+            RequestDefaults {
+                temperature: Some(0.2),
+                ..RequestDefaults::default()
+            },
+        );
+        let err = generate(
+            &provider,
+            &Prompt::user("x"),
+            &Config::with_provider_defaults().build(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "got {err}");
+    }
+
+    #[test]
+    fn capabilities_resolve_an_empty_model_to_the_default() {
+        let provider = DefaultsProvider::new(MockProvider::with_text("hi"), defaults());
+        assert_eq!(
+            provider.capabilities(""),
+            MockProvider::with_text("hi").capabilities("gpt-4o-mini")
+        );
+    }
+}
@@ -21,6 +21,9 @@ pub enum Error {
         status: Option<u16>,
         /// Provider-supplied error description.
         message: String,
+        /// Structured details from the provider's error response,
+        /// as on [`Self::Provider`]. Read via [`Error::provider_details`].
+        details: Option<Box<ProviderErrorDetails>>,
     },
 
     /// JSON (de)serialization failure.
@@ -45,6 +48,12 @@ pub enum Error {
         retry_after: Option<Duration>,
         /// Provider-supplied error description.
         message: String,
+        /// Machine-readable fields parsed from the provider's error
+        /// envelope and response headers — error type / code and the
+        /// upstream request ID — when the provider sent any. Boxed so
+        /// the common no-details case doesn't grow every `Result<_,
+        /// Error>`. Read via [`Error::provider_details`].
+        details: Option<Box<ProviderErrorDetails>>,
     },
 
     /// Caller misconfiguration (wrong env, invalid value).
@@ -72,11 +81,20 @@ pub enum Error {
         retry_after: Option<Duration>,
         /// Provider-supplied error description.
         message: String,
+        /// Structured details from the provider's error response,
+        /// as on [`Self::Provider`]. Read via [`Error::provider_details`].
+        details: Option<Box<ProviderErrorDetails>>,
     },
 
     /// Model not available (typically a 404 on the model name).
-    #[error("model not available: {0}")]
-    ModelNotAvailable(String),
+    #[error("model not available: {message}")]
+    ModelNotAvailable {
+        /// Provider-supplied error description.
+        message: String,
+        /// Structured details from the provider's error response,
+        /// as on [`Self::Provider`]. Read via [`Error::provider_details`].
+        details: Option<Box<ProviderErrorDetails>>,
+    },
 
     /// Request rejected because the prompt exceeded the model's
     /// context window. Distinct from a generic
@@ -96,6 +114,25 @@ pub enum Error {
         provider: &'static str,
         /// Provider-supplied error description.
         message: String,
+        /// Structured details from the provider's error response,
+        /// as on [`Self::Provider`]. Read via [`Error::provider_details`].
+        details: Option<Box<ProviderErrorDetails>>,
+    },
+
+    /// The provider rejected the request or cut the response off on
+    /// content-policy grounds and reported it as an error — typically
+    /// an in-stream error event after a 200 — rather than ending with
+    /// [`crate::FinishReason::ContentFilter`]. Not retryable: the same
+    /// request will be refused again.
+    #[error("content policy violation ({provider}): {message}")]
+    ContentPolicy {
+        /// Short identifier of the provider that raised the error.
+        provider: &'static str,
+        /// Provider-supplied error description.
+        message: String,
+        /// Structured details from the provider's error response,
+        /// as on [`Self::Provider`]. Read via [`Error::provider_details`].
+        details: Option<Box<ProviderErrorDetails>>,
     },
 
     /// Compaction couldn't produce a usable memo — the
@@ -128,6 +165,68 @@ pub enum Error {
         /// The unsupported modality (`"audio"`, `"video"`).
         modality: &'static str,
     },
+
+    /// The call ran past its deadline — the per-request
+    /// [`crate::ConfigBuilder::timeout`] or a
+    /// [`crate::timeout::TimeoutProvider`] default. Covers the whole
+    /// call, streaming included, so it can fire mid-stream. Retryable:
+    /// a slow upstream is usually transient.
+    #[error("request timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// The response produced no event for this long — a stalled
+    /// connection rather than a slow one. Raised by the
+    /// [`crate::ConfigBuilder::idle_timeout`] /
+    /// [`crate::timeout::IdleTimeoutProvider`] watchdog. Keep-alive
+    /// pings don't count as events. Retryable.
+    #[error("response stalled: no event for {0:?}")]
+    IdleTimeout(Duration),
+
+    /// The consumer of a [`crate::Response::bounded`] stream fell
+    /// `capacity` events behind under [`crate::OverflowPolicy::Fail`];
+    /// the upstream call was dropped. Not retryable: a retry would
+    /// overflow the same way.
+    #[error("stream consumer fell {capacity} events behind")]
+    StreamOverflow {
+        /// The bounded stream's capacity.
+        capacity: usize,
+    },
+
+    /// A provider's SSE stream sent a line or event bigger than the
+    /// parser allows (see [`crate::sse_stream::SseLimits`]); the
+    /// stream stops there instead of buffering without bound. `what`
+    /// is `"line"` or `"event"`. Not retryable: a retry would most
+    /// likely hit the same oversized frame.
+    #[error("SSE {what} from {provider} exceeded the {limit}-byte limit")]
+    SseLimitExceeded {
+        /// Short identifier of the provider whose stream overflowed.
+        provider: &'static str,
+        /// Which limit was hit: `"line"` or `"event"`.
+        what: &'static str,
+        /// The limit in bytes.
+        limit: usize,
+    },
+
+    /// A completed tool call's arguments don't satisfy the declared
+    /// [`crate::Function::parameters`] schema. Raised by
+    /// [`crate::middleware::ToolArgumentValidationMiddleware`] in place
+    /// of the call's `PartEnd`. The caller can re-ask the model with
+    /// `problems` fed back, or repair `arguments` itself.
+    #[error(
+        "invalid arguments for tool '{name}' (call {call_id}): {}",
+        .problems.join("; ")
+    )]
+    InvalidToolArguments {
+        /// The tool call's id.
+        call_id: String,
+        /// The tool's name.
+        name: String,
+        /// The raw arguments the model produced.
+        arguments: String,
+        /// One entry per violation, each prefixed with the JSON path
+        /// it applies to (`$.location: missing required property`).
+        problems: Vec<String>,
+    },
 }
 
 impl Error {
@@ -141,6 +240,7 @@ impl Error {
             retryable: false,
             retry_after: None,
             message: message.into(),
+            details: None,
         }
     }
 
@@ -158,6 +258,7 @@ impl Error {
             retryable,
             retry_after: None,
             message: message.into(),
+            details: None,
         }
     }
 
@@ -181,6 +282,221 @@ impl Error {
             retryable,
             retry_after: retry_after_seconds.map(Duration::from_secs),
             message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attach structured [`ProviderErrorDetails`] to an error from a
+    /// provider response: [`Self::Provider`], [`Self::Auth`],
+    /// [`Self::RateLimit`], [`Self::ModelNotAvailable`],
+    /// [`Self::ContextWindowExceeded`] or [`Self::ContentPolicy`].
+    /// Fields left `None` in `details` keep whatever the error already
+    /// carried, so a provider can attach the body-derived code first
+    /// and the header-derived request ID later. A no-op on every other
+    /// variant.
+    pub fn with_provider_details(mut self, details: ProviderErrorDetails) -> Self {
+        if let Some(slot) = self.details_slot() {
+            let merged = match slot.take() {
+                Some(existing) => existing.merge(details),
+                None => details,
+            };
+            *slot = (!merged.is_empty()).then(|| Box::new(merged));
+        }
+        self
+    }
+
+    fn details_slot(&mut self) -> Option<&mut Option<Box<ProviderErrorDetails>>> {
+        match self {
+            Error::Provider { details, .. }
+            | Error::Auth { details, .. }
+            | Error::RateLimit { details, .. }
+            | Error::ModelNotAvailable { details, .. }
+            | Error::ContextWindowExceeded { details, .. }
+            | Error::ContentPolicy { details, .. } => Some(details),
+            _ => None,
+        }
+    }
+
+    /// Attach an upstream request ID read from a response header —
+    /// shorthand for [`Self::with_provider_details`] at the HTTP-error
+    /// sites, which have the header as an `Option<&str>`.
+    #[cfg(any(feature = "openai", feature = "anthropic-vertex"))]
+    pub(crate) fn with_request_id(self, request_id: Option<&str>) -> Self {
+        match request_id {
+            Some(id) => self.with_provider_details(ProviderErrorDetails::new().with_request_id(id)),
+            None => self,
+        }
+    }
+
+    /// Attach the caller-side [`crate::RawConfig::request_id`] of the
+    /// failed call. Like [`Self::with_provider_details`], only errors
+    /// from a provider response carry it; every other variant passes
+    /// through unchanged.
+    pub(crate) fn with_client_request_id(self, request_id: &str) -> Self {
+        self.with_provider_details(ProviderErrorDetails::new().with_client_request_id(request_id))
+    }
+
+    /// Structured details of an error from a provider response (see
+    /// [`Self::with_provider_details`] for the variants), if the
+    /// provider reported any. `None` for every other variant.
+    ///
+    /// Branch on [`ProviderErrorDetails::code`] /
+    /// [`ProviderErrorDetails::error_type`] rather than matching on
+    /// the message text — the message is human-oriented and changes
+    /// without notice.
+    pub fn provider_details(&self) -> Option<&ProviderErrorDetails> {
+        match self {
+            Error::Provider { details, .. }
+            | Error::Auth { details, .. }
+            | Error::RateLimit { details, .. }
+            | Error::ModelNotAvailable { details, .. }
+            | Error::ContextWindowExceeded { details, .. }
+            | Error::ContentPolicy { details, .. } => details.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// HTTP status the error was derived from, for the variants that
+    /// record one ([`Self::Auth`], [`Self::Provider`]). `None` for
+    /// non-HTTP failures and for variants that don't keep the status.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Auth { status, .. } | Error::Provider { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    /// Stable `snake_case` name of the variant (`"rate_limit"`,
+    /// `"provider"`, …) — low-cardinality, so it's safe as a metric
+    /// label or span attribute where the message text is not.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Error::Transport(_) => "transport",
+            Error::Auth { .. } => "auth",
+            Error::Serialization(_) => "serialization",
+            Error::Provider { .. } => "provider",
+            Error::Config(_) => "config",
+            Error::InvalidPrompt(_) => "invalid_prompt",
+            Error::RateLimit { .. } => "rate_limit",
+            Error::ModelNotAvailable { .. } => "model_not_available",
+            Error::ContextWindowExceeded { .. } => "context_window_exceeded",
+            Error::ContentPolicy { .. } => "content_policy",
+            Error::Compaction { .. } => "compaction",
+            Error::UnsupportedInput { .. } => "unsupported_input",
+            Error::Timeout(_) => "timeout",
+            Error::IdleTimeout(_) => "idle_timeout",
+            Error::StreamOverflow { .. } => "stream_overflow",
+            Error::SseLimitExceeded { .. } => "sse_limit_exceeded",
+            Error::InvalidToolArguments { .. } => "invalid_tool_arguments",
+        }
+    }
+
+    /// A copy of this error for a second consumer (e.g. the other half
+    /// of [`crate::Response::tee`]). Variant and fields carry over;
+    /// the two wrapping foreign errors (`reqwest` / `serde_json`) can't
+    /// be cloned and become [`Self::Provider`] errors with the same
+    /// message and retryability.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Error::Transport(_) => Error::Provider {
+                provider: "Library",
+                status: None,
+                retryable: self.is_retryable(),
+                retry_after: None,
+                message: self.to_string(),
+                details: None,
+            },
+            Error::Serialization(_) => Error::provider("Library", self.to_string()),
+            Error::Auth {
+                status,
+                message,
+                details,
+            } => Error::Auth {
+                status: *status,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Error::Provider {
+                provider,
+                status,
+                retryable,
+                retry_after,
+                message,
+                details,
+            } => Error::Provider {
+                provider,
+                status: *status,
+                retryable: *retryable,
+                retry_after: *retry_after,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Error::Config(message) => Error::Config(message.clone()),
+            Error::InvalidPrompt(message) => Error::InvalidPrompt(message.clone()),
+            Error::RateLimit {
+                retry_after,
+                message,
+                details,
+            } => Error::RateLimit {
+                retry_after: *retry_after,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Error::ModelNotAvailable { message, details } => Error::ModelNotAvailable {
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Error::ContextWindowExceeded {
+                provider,
+                message,
+                details,
+            } => Error::ContextWindowExceeded {
+                provider,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Error::ContentPolicy {
+                provider,
+                message,
+                details,
+            } => Error::ContentPolicy {
+                provider,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Error::Compaction { reason } => Error::Compaction {
+                reason: reason.clone(),
+            },
+            Error::UnsupportedInput { provider, modality } => {
+                Error::UnsupportedInput { provider, modality }
+            }
+            Error::Timeout(after) => Error::Timeout(*after),
+            Error::IdleTimeout(after) => Error::IdleTimeout(*after),
+            Error::StreamOverflow { capacity } => Error::StreamOverflow {
+                capacity: *capacity,
+            },
+            Error::SseLimitExceeded {
+                provider,
+                what,
+                limit,
+            } => Error::SseLimitExceeded {
+                provider,
+                what,
+                limit: *limit,
+            },
+            Error::InvalidToolArguments {
+                call_id,
+                name,
+                arguments,
+                problems,
+            } => Error::InvalidToolArguments {
+                call_id: call_id.clone(),
+                name: name.clone(),
+                arguments: arguments.clone(),
+                problems: problems.clone(),
+            },
         }
     }
 
@@ -200,6 +516,7 @@ impl Error {
         Error::Auth {
             status: None,
             message: message.into(),
+            details: None,
         }
     }
 
@@ -208,6 +525,7 @@ impl Error {
         Error::Auth {
             status: Some(status),
             message: message.into(),
+            details: None,
         }
     }
 
@@ -217,6 +535,16 @@ impl Error {
         Error::RateLimit {
             retry_after: retry_after_seconds.map(Duration::from_secs),
             message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Build a model-not-available error, typically for a 404 on the
+    /// model name.
+    pub fn model_not_available(message: impl Into<String>) -> Self {
+        Error::ModelNotAvailable {
+            message: message.into(),
+            details: None,
         }
     }
 
@@ -226,6 +554,28 @@ impl Error {
         Error::ContextWindowExceeded {
             provider,
             message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Build an idle-timeout error for a response silent for `idle`.
+    pub fn idle_timeout(idle: Duration) -> Self {
+        Error::IdleTimeout(idle)
+    }
+
+    /// Build a stream-overflow error for a bounded stream of
+    /// `capacity` events.
+    pub fn stream_overflow(capacity: usize) -> Self {
+        Error::StreamOverflow { capacity }
+    }
+
+    /// Build a content-policy error. Use when a provider reports a
+    /// policy refusal as an error rather than a finish reason.
+    pub fn content_policy(provider: &'static str, message: impl Into<String>) -> Self {
+        Error::ContentPolicy {
+            provider,
+            message: message.into(),
+            details: None,
         }
     }
 
@@ -244,6 +594,11 @@ impl Error {
         Error::UnsupportedInput { provider, modality }
     }
 
+    /// Build a timeout error for a call that exceeded `after`.
+    pub fn timeout(after: Duration) -> Self {
+        Error::Timeout(after)
+    }
+
     /// Whether this error represents a transient failure where
     /// re-issuing the same request is likely to behave differently
     /// next time.
@@ -302,18 +657,30 @@ impl Error {
                 // error), `is_decode()` for JSON/wire-format decode
                 // failures (we surface those as `Serialization`
                 // anyway), and anything else not in the above set.
-                e.is_connect() || e.is_timeout() || e.is_request() || e.is_body()
+                //
+                // Under wasm32 `fetch` exposes no connect-phase
+                // distinction; a failed request surfaces as
+                // `is_request()`.
+                #[cfg(not(target_arch = "wasm32"))]
+                let connect = e.is_connect();
+                #[cfg(target_arch = "wasm32")]
+                let connect = false;
+                connect || e.is_timeout() || e.is_request() || e.is_body()
             }
-            Error::RateLimit { .. } => true,
+            Error::RateLimit { .. } | Error::Timeout(_) | Error::IdleTimeout(_) => true,
             Error::Provider { retryable, .. } => *retryable,
             Error::Auth { .. }
             | Error::Serialization(_)
             | Error::Config(_)
             | Error::InvalidPrompt(_)
-            | Error::ModelNotAvailable(_)
+            | Error::ModelNotAvailable { .. }
             | Error::ContextWindowExceeded { .. }
+            | Error::ContentPolicy { .. }
             | Error::UnsupportedInput { .. }
-            | Error::Compaction { .. } => false,
+            | Error::Compaction { .. }
+            | Error::StreamOverflow { .. }
+            | Error::SseLimitExceeded { .. }
+            | Error::InvalidToolArguments { .. } => false,
         }
     }
 
@@ -336,6 +703,107 @@ impl Error {
     }
 }
 
+/// Machine-readable fields from a provider's error response, read via
+/// [`Error::provider_details`] on any error mapped from one. Each
+/// provider fills what its wire format exposes:
+///
+/// | provider | `error_type` | `code` | `request_id` header |
+/// |---|---|---|---|
+/// | OpenAI | `error.type` | `error.code` | `x-request-id` |
+/// | Anthropic | `error.type` | — | `request-id` |
+/// | Google (Vertex) | `error.status` (`"INVALID_ARGUMENT"`, …) | `error.code` (numeric, as a string) | — |
+///
+/// The error's message is the envelope's `error.message`; the whole
+/// HTTP body, scrubbed of credentials, is kept in `raw_body`.
+///
+/// All fields but `client_request_id` are best-effort: a proxy can
+/// replace the body with HTML, and mid-stream errors have no headers
+/// or body. Quote `request_id` in provider support tickets, and match
+/// `client_request_id` against your own logs.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProviderErrorDetails {
+    /// Provider's error category (`"invalid_request_error"`,
+    /// `"overloaded_error"`, `"RESOURCE_EXHAUSTED"`, …).
+    pub error_type: Option<String>,
+    /// Provider's finer-grained error code, where the wire format has
+    /// one separate from the type (`"invalid_api_key"`,
+    /// `"context_length_exceeded"`, …).
+    pub code: Option<String>,
+    /// Upstream request identifier from the response headers.
+    pub request_id: Option<String>,
+    /// The call's own [`crate::RawConfig::request_id`], attached by
+    /// [`crate::generate`]. Client-side errors have no details to
+    /// carry it; set the ID yourself to correlate those.
+    pub client_request_id: Option<String>,
+    /// The HTTP error body as received (after credential scrubbing),
+    /// for debugging shapes the typed fields don't cover.
+    pub raw_body: Option<String>,
+}
+
+impl ProviderErrorDetails {
+    /// Empty details — every field `None`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`Self::error_type`]. Empty strings are treated as absent.
+    pub fn with_error_type(mut self, error_type: impl Into<String>) -> Self {
+        self.error_type = non_empty(error_type.into());
+        self
+    }
+
+    /// Set [`Self::code`]. Empty strings are treated as absent.
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = non_empty(code.into());
+        self
+    }
+
+    /// Set [`Self::request_id`]. Empty strings are treated as absent.
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = non_empty(request_id.into());
+        self
+    }
+
+    /// Set [`Self::client_request_id`]. Empty strings are treated as
+    /// absent.
+    pub fn with_client_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.client_request_id = non_empty(request_id.into());
+        self
+    }
+
+    /// Set [`Self::raw_body`]. Empty strings are treated as absent.
+    pub fn with_raw_body(mut self, body: impl Into<String>) -> Self {
+        self.raw_body = non_empty(body.into());
+        self
+    }
+
+    /// `true` when no field is set.
+    pub fn is_empty(&self) -> bool {
+        self.error_type.is_none()
+            && self.code.is_none()
+            && self.request_id.is_none()
+            && self.client_request_id.is_none()
+            && self.raw_body.is_none()
+    }
+
+    /// Field-wise merge: `other`'s set fields win, unset ones keep
+    /// `self`'s.
+    fn merge(self, other: Self) -> Self {
+        Self {
+            error_type: other.error_type.or(self.error_type),
+            code: other.code.or(self.code),
+            request_id: other.request_id.or(self.request_id),
+            client_request_id: other.client_request_id.or(self.client_request_id),
+            raw_body: other.raw_body.or(self.raw_body),
+        }
+    }
+}
+
+fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
 /// Status fragment for the `Provider` Display. Returns only the
 /// `, status NNN` part (or empty) — the surrounding `(…)`: and
 /// message live in the format string itself, so editing this helper
@@ -484,7 +952,7 @@ mod tests {
         assert!(!Error::auth_with_status(401, "bad key").is_retryable());
         assert!(!Error::config("nope").is_retryable());
         assert!(!Error::invalid_prompt("nope").is_retryable());
-        assert!(!Error::ModelNotAvailable("gpt-x".into()).is_retryable());
+        assert!(!Error::model_not_available("gpt-x").is_retryable());
         assert!(!Error::context_window_exceeded("OpenAI", "too long").is_retryable());
         assert!(!Error::compaction("empty memo").is_retryable());
     }
@@ -514,4 +982,43 @@ mod tests {
         assert!(no_hint.is_retryable());
         assert_eq!(no_hint.retry_after(), None);
     }
+
+    #[test]
+    fn provider_details_merge_field_wise() {
+        let err = Error::provider_with_status("OpenAI", 400, "bad")
+            .with_provider_details(
+                ProviderErrorDetails::new()
+                    .with_error_type("invalid_request_error")
+                    .with_code("invalid_value"),
+            )
+            .with_provider_details(ProviderErrorDetails::new().with_request_id("req_123"));
+        let details = err.provider_details().expect("details attached");
+        assert_eq!(details.error_type.as_deref(), Some("invalid_request_error"));
+        assert_eq!(details.code.as_deref(), Some("invalid_value"));
+        assert_eq!(details.request_id.as_deref(), Some("req_123"));
+        assert_eq!(err.status(), Some(400));
+    }
+
+    #[test]
+    fn empty_provider_details_are_not_attached() {
+        let err = Error::provider("OpenAI", "bad")
+            .with_provider_details(ProviderErrorDetails::new().with_code(""));
+        assert!(err.provider_details().is_none());
+    }
+
+    #[test]
+    fn mapped_http_variants_carry_details_and_client_side_ones_do_not() {
+        let err = Error::auth_with_status(401, "nope")
+            .with_provider_details(ProviderErrorDetails::new().with_raw_body("{}"));
+        assert_eq!(
+            err.provider_details().and_then(|d| d.raw_body.as_deref()),
+            Some("{}")
+        );
+        assert_eq!(err.status(), Some(401));
+        assert!(err.duplicate().provider_details().is_some());
+
+        let err = Error::invalid_prompt("empty")
+            .with_provider_details(ProviderErrorDetails::new().with_code("x"));
+        assert!(err.provider_details().is_none());
+    }
 }
@@ -0,0 +1,403 @@
+//! Ordered failover across several providers / models.
+//!
+//! [`FallbackProvider`] holds a list of backends and tries them in
+//! order: the first is the primary, the rest are only called when the
+//! one before them failed in a way another backend might not
+//! (a 5xx, a 429, a connection drop, a timeout, a model the backend
+//! doesn't serve). Deterministic failures — bad auth, a malformed
+//! prompt, a context-window overflow — surface immediately, since
+//! every backend would reject the same request the same way.
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use platformed_llm::FallbackProvider;
+//!
+//! let provider = FallbackProvider::new()
+//!     .with_backend("openai", openai)
+//!     .with_backend_model("gemini", google, "gemini-2.5-flash")
+//!     .with_attempt_timeout(Duration::from_secs(20));
+//!
+//! let response = generate(&provider, &prompt, &config).await?;
+//! tracing::info!(served_by = ?response.metadata().served_by);
+//! ```
+//!
+//! The serving backend's label (and the model it was asked for) is
+//! recorded on [`crate::ResponseMetadata`].
+//!
+//! # What counts as "failed"
+//!
+//! Same rule as [`crate::RetryingProvider`]: the backend's `generate`
+//! returned `Err`, or its stream's *first* item was an `Err`. Once an
+//! event has been handed to the caller the stream is committed to
+//! that backend — a mid-stream failure surfaces as-is, because a
+//! different backend's completion wouldn't stitch onto the partial
+//! output.
+//!
+//! # Middleware runs once
+//!
+//! [`crate::generate`] runs the middleware pipeline against
+//! [`FallbackProvider::capabilities`], which reports the *primary*
+//! backend's capabilities. A prompt rewritten for the primary (e.g.
+//! structured output polyfilled into a tool call) is passed verbatim
+//! to the fallbacks, so keep the chain to backends with comparable
+//! capabilities.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::layer::SharedProvider;
+use crate::retry::{key_for_target, peek_first, RetryClassifier};
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response};
+
+/// One entry in a [`FallbackProvider`] chain.
+#[derive(Clone)]
+struct Backend {
+    label: String,
+    provider: SharedProvider,
+    /// Model to request from this backend instead of the caller's.
+    model: Option<String>,
+}
+
+/// A [`Provider`] that fails over through an ordered list of
+/// backends. See the module docs.
+#[derive(Clone, Default)]
+pub struct FallbackProvider {
+    backends: Vec<Backend>,
+    attempt_timeout: Option<Duration>,
+    classifier: Option<RetryClassifier>,
+}
+
+impl FallbackProvider {
+    /// An empty chain. Add at least one backend before calling
+    /// `generate` — an empty chain fails with [`Error::Config`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a backend that receives the caller's model unchanged.
+    /// `label` is what [`crate::ResponseMetadata::served_by`] reports.
+    pub fn with_backend(self, label: impl Into<String>, provider: impl Provider) -> Self {
+        self.push(label.into(), Arc::new(provider), None)
+    }
+
+    /// Append a backend that is asked for `model` instead of the
+    /// caller's — the usual shape for cross-vendor chains, where the
+    /// model names don't carry over.
+    pub fn with_backend_model(
+        self,
+        label: impl Into<String>,
+        provider: impl Provider,
+        model: impl Into<String>,
+    ) -> Self {
+        self.push(label.into(), Arc::new(provider), Some(model.into()))
+    }
+
+    /// Append an already-shared backend (e.g. a converted
+    /// `Box<dyn Provider>` from [`crate::ProviderFactory`], or a
+    /// layered [`crate::ProviderStack`] output). `model` works as in
+    /// [`Self::with_backend_model`]; `None` keeps the caller's.
+    pub fn with_shared_backend(
+        self,
+        label: impl Into<String>,
+        provider: SharedProvider,
+        model: Option<String>,
+    ) -> Self {
+        self.push(label.into(), provider, model)
+    }
+
+    /// Bound each backend's time to first event (the `generate` call
+    /// plus the first stream item). A backend that doesn't produce an
+    /// event in time counts as failed and the next one is tried. The
+    /// rest of the stream is not timed — once a backend is streaming,
+    /// it owns the response.
+    pub fn with_attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// Replace the default failover rule (see [`Self::should_fail_over`]).
+    pub fn with_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Number of backends in the chain.
+    pub fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    /// `true` when no backends have been added.
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    /// Whether `err` from one backend should move on to the next.
+    ///
+    /// Default: anything [`Error::is_retryable`] (429, 5xx, transport
+    /// drops, attempt timeouts), plus [`Error::ModelNotAvailable`] and
+    /// [`Error::UnsupportedInput`] — both are properties of the
+    /// backend, not the request, so the next backend may well accept
+    /// it. Overridden by [`Self::with_classifier`].
+    pub fn should_fail_over(&self, err: &Error) -> bool {
+        match &self.classifier {
+            Some(classify) => classify(err),
+            None => {
+                err.is_retryable()
+                    || matches!(
+                        err,
+                        Error::ModelNotAvailable { .. } | Error::UnsupportedInput { .. }
+                    )
+            }
+        }
+    }
+
+    fn push(mut self, label: String, provider: SharedProvider, model: Option<String>) -> Self {
+        self.backends.push(Backend {
+            label,
+            provider,
+            model,
+        });
+        self
+    }
+
+    async fn attempt(
+        &self,
+        backend: &Backend,
+        prompt: &Prompt,
+        config: &RawConfig,
+    ) -> Result<Response, Error> {
+        let call = async {
+            let response = backend.provider.generate(prompt, config).await?;
+            peek_first(response).await
+        };
+        match self.attempt_timeout {
+            None => call.await,
+            Some(limit) => match tokio::time::timeout(limit, call).await {
+                Ok(result) => result,
+                // Retryable, so the default classifier fails over and
+                // an outer `RetryingProvider` treats it like any other
+                // transient blip.
+                Err(_) => Err(Error::timeout(limit)),
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for FallbackProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let backends: Vec<_> = self
+            .backends
+            .iter()
+            .map(|b| (b.label.as_str(), b.model.as_deref()))
+            .collect();
+        f.debug_struct("FallbackProvider")
+            .field("backends", &backends)
+            .field("attempt_timeout", &self.attempt_timeout)
+            .field("custom_classifier", &self.classifier.is_some())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for FallbackProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let mut last_err = None;
+        for (index, backend) in self.backends.iter().enumerate() {
+            let mut config = key_for_target(config, index);
+            if let Some(model) = &backend.model {
+                config.to_mut().model = model.clone();
+            }
+            match self.attempt(backend, prompt, &config).await {
+                Ok(mut response) => {
+                    let metadata = response.metadata_mut();
+                    metadata.served_by = Some(backend.label.clone());
+                    metadata.model = Some(config.model.clone());
+                    return Ok(response);
+                }
+                Err(err) => {
+                    let is_last = index + 1 == self.backends.len();
+                    if is_last || !self.should_fail_over(&err) {
+                        return Err(err);
+                    }
+                    tracing::warn!(
+                        backend = %backend.label,
+                        model = %config.model,
+                        error = %err,
+                        "fallback backend failed; trying next",
+                    );
+                    last_err = Some(err);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| Error::config("FallbackProvider has no backends")))
+    }
+
+    /// The primary backend's name, matching [`Self::capabilities`];
+    /// the backend that served a call is in
+    /// [`crate::ResponseMetadata::served_by`].
+    fn name(&self) -> &str {
+        match self.backends.first() {
+            Some(primary) => primary.provider.name(),
+            None => "Fallback",
+        }
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        match self.backends.first() {
+            Some(primary) => primary
+                .provider
+                .capabilities(primary.model.as_deref().unwrap_or(model)),
+            None => Capabilities::for_model(model),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, Config};
+
+    fn cfg() -> Config {
+        Config::builder("primary-model").build()
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_retryable_error_and_records_backend() {
+        let primary = MockProvider::builder()
+            .fail(Error::provider_with_status("Mock", 503, "down"))
+            .build();
+        let secondary = MockProvider::with_text("from secondary");
+        let secondary_log = secondary.call_log();
+        let provider = FallbackProvider::new()
+            .with_backend("primary", primary)
+            .with_backend_model("secondary", secondary, "other-model");
+
+        let response = generate(&provider, &Prompt::user("x"), &cfg())
+            .await
+            .unwrap();
+        assert_eq!(response.metadata().served_by.as_deref(), Some("secondary"));
+        assert_eq!(response.metadata().model.as_deref(), Some("other-model"));
+        assert_eq!(response.text().await.unwrap(), "from secondary");
+        assert_eq!(secondary_log.calls()[0].config.model, "other-model");
+    }
+
+    #[tokio::test]
+    async fn each_backend_gets_its_own_idempotency_key() {
+        let primary = MockProvider::builder()
+            .fail(Error::provider_with_status("Mock", 503, "down"))
+            .build();
+        let primary_log = primary.call_log();
+        let secondary = MockProvider::with_text("ok");
+        let secondary_log = secondary.call_log();
+        let provider = FallbackProvider::new()
+            .with_backend("primary", primary)
+            .with_backend("secondary", secondary);
+        let config = Config::builder("m").idempotency_key("key").build();
+
+        generate(&provider, &Prompt::user("x"), &config)
+            .await
+            .unwrap();
+        let primary_key = primary_log.calls()[0].config.idempotency_key.clone();
+        let secondary_key = secondary_log.calls()[0].config.idempotency_key.clone();
+        assert_eq!(primary_key.as_deref(), Some("key"));
+        assert_eq!(secondary_key.as_deref(), Some("key-1"));
+    }
+
+    #[tokio::test]
+    async fn primary_success_never_touches_fallback() {
+        let secondary = MockProvider::with_text("unused");
+        let secondary_log = secondary.call_log();
+        let provider = FallbackProvider::new()
+            .with_backend("primary", MockProvider::with_text("ok"))
+            .with_backend("secondary", secondary);
+        let response = generate(&provider, &Prompt::user("x"), &cfg())
+            .await
+            .unwrap();
+        assert_eq!(response.metadata().served_by.as_deref(), Some("primary"));
+        assert_eq!(response.metadata().model.as_deref(), Some("primary-model"));
+        assert!(secondary_log.is_empty());
+    }
+
+    #[tokio::test]
+    async fn terminal_error_does_not_fail_over() {
+        let secondary = MockProvider::with_text("unused");
+        let secondary_log = secondary.call_log();
+        let provider = FallbackProvider::new()
+            .with_backend(
+                "primary",
+                MockProvider::builder()
+                    .fail(Error::auth_with_status(401, "bad key"))
+                    .build(),
+            )
+            .with_backend("secondary", secondary);
+        let Err(err) = generate(&provider, &Prompt::user("x"), &cfg()).await else {
+            panic!("auth failure must surface");
+        };
+        assert!(matches!(err, Error::Auth { .. }));
+        assert!(secondary_log.is_empty());
+    }
+
+    #[tokio::test]
+    async fn exhausted_chain_returns_last_error() {
+        let provider = FallbackProvider::new()
+            .with_backend(
+                "a",
+                MockProvider::builder()
+                    .fail(Error::rate_limit(None, "a slow"))
+                    .build(),
+            )
+            .with_backend(
+                "b",
+                MockProvider::builder()
+                    .fail(Error::provider_with_status("Mock", 502, "b down"))
+                    .build(),
+            );
+        let Err(err) = generate(&provider, &Prompt::user("x"), &cfg()).await else {
+            panic!("every backend failed");
+        };
+        assert!(err.to_string().contains("b down"), "got {err}");
+    }
+
+    /// Never produces an event — stands in for a hung upstream.
+    struct Hanging;
+
+    #[async_trait::async_trait]
+    impl Provider for Hanging {
+        async fn generate(&self, _: &Prompt, _: &RawConfig) -> Result<Response, Error> {
+            Ok(Response::from_stream(futures_util::stream::pending()))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn attempt_timeout_fails_over_to_next_backend() {
+        let provider = FallbackProvider::new()
+            .with_backend("hung", Hanging)
+            .with_backend("ok", MockProvider::with_text("rescued"))
+            .with_attempt_timeout(Duration::from_secs(5));
+        let response = generate(&provider, &Prompt::user("x"), &cfg())
+            .await
+            .unwrap();
+        assert_eq!(response.metadata().served_by.as_deref(), Some("ok"));
+        assert_eq!(response.text().await.unwrap(), "rescued");
+
+        let provider = FallbackProvider::new()
+            .with_backend("hung", Hanging)
+            .with_attempt_timeout(Duration::from_secs(5));
+        let Err(err) = generate(&provider, &Prompt::user("x"), &cfg()).await else {
+            panic!("a hung chain must time out");
+        };
+        assert!(matches!(err, Error::Timeout(limit) if limit == Duration::from_secs(5)));
+    }
+
+    #[tokio::test]
+    async fn empty_chain_is_a_config_error() {
+        let Err(err) = generate(&FallbackProvider::new(), &Prompt::user("x"), &cfg()).await else {
+            panic!("empty chain must error");
+        };
+        assert!(matches!(err, Error::Config(_)));
+    }
+}
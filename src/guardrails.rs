@@ -0,0 +1,376 @@
+//! Pre- and post-generation guardrail hooks.
+//!
+//! Moderation and policy checks tend to get scattered: a prompt filter
+//! in one handler, a PII scrub in another, an output classifier bolted
+//! onto whichever endpoint needed it first. [`GuardrailsProvider`]
+//! gives them one place, around any [`Provider`]:
+//!
+//! - [`InputGuardrail`]s see the prompt and config before the request
+//!   is sent, and may rewrite either or reject the call.
+//! - [`StreamGuardrail`]s see every response event as it streams, and
+//!   may rewrite it or end the stream with an error.
+//! - [`OutputGuardrail`]s see the finished [`CompleteResponse`], and
+//!   may rewrite it or replace it with an error.
+//!
+//! ```ignore
+//! use platformed_llm::guardrails::GuardrailsProvider;
+//!
+//! let provider = GuardrailsProvider::new(openai)
+//!     .with_input(Arc::new(BlockedTopics::load()?))
+//!     .with_output(Arc::new(ModerationCheck::new(moderation_client)));
+//! ```
+//!
+//! Hooks of each kind run in the order they were added. Rejecting is
+//! just returning an error — [`Error::content_policy`] is the natural
+//! one, and it isn't retryable, so a [`crate::RetryingProvider`] outside
+//! the guardrails won't resend a rejected request.
+//!
+//! Output guardrails need the whole turn, so when any are installed the
+//! response is buffered before the caller sees the first event and then
+//! replayed (one delta per part, see [`Response::from_complete`]).
+//! Stream guardrails alone keep the response live. Input hooks run on
+//! every call, including each retry or fallback attempt the guardrails
+//! wrap.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use futures_util::stream::{self, StreamExt};
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{
+    Capabilities, CompleteResponse, Error, EventStream, Prompt, Provider, RawConfig, Response,
+    StreamEvent,
+};
+
+/// Checks or rewrites a request before it is sent.
+#[async_trait::async_trait]
+pub trait InputGuardrail: Send + Sync {
+    /// Inspect the request. Rewrite it through [`Cow::to_mut`] (which
+    /// clones once, on first write), or return an error to reject the
+    /// call without sending it.
+    async fn check_input(
+        &self,
+        prompt: &mut Cow<'_, Prompt>,
+        config: &mut Cow<'_, RawConfig>,
+    ) -> Result<(), Error>;
+}
+
+/// Checks or rewrites each response event as it streams.
+pub trait StreamGuardrail: Send + Sync {
+    /// Inspect `event`, rewriting it in place if needed. An error is
+    /// delivered in place of the event and ends the stream, dropping
+    /// (and so cancelling) the upstream call.
+    fn check_event(&self, event: &mut StreamEvent) -> Result<(), Error>;
+}
+
+/// Checks or rewrites the finished response.
+#[async_trait::async_trait]
+pub trait OutputGuardrail: Send + Sync {
+    /// Inspect the buffered turn, rewriting it in place if needed. An
+    /// error is delivered instead of the response.
+    async fn check_output(&self, response: &mut CompleteResponse) -> Result<(), Error>;
+}
+
+/// The hooks shared by [`GuardrailsProvider`] and [`GuardrailsLayer`].
+#[derive(Clone, Default)]
+struct Hooks {
+    input: Vec<Arc<dyn InputGuardrail>>,
+    stream: Vec<Arc<dyn StreamGuardrail>>,
+    output: Vec<Arc<dyn OutputGuardrail>>,
+}
+
+impl std::fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hooks")
+            .field("input", &self.input.len())
+            .field("stream", &self.stream.len())
+            .field("output", &self.output.len())
+            .finish()
+    }
+}
+
+/// A [`Provider`] wrapper running guardrail hooks around every call.
+/// See the module docs.
+#[derive(Clone)]
+pub struct GuardrailsProvider {
+    inner: SharedProvider,
+    hooks: Hooks,
+}
+
+impl GuardrailsProvider {
+    /// Wrap `inner`, with no hooks yet.
+    pub fn new(inner: impl Provider) -> Self {
+        Self::from_shared(Arc::new(inner))
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider) -> Self {
+        Self {
+            inner,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Run `guardrail` on each request, after those already added.
+    pub fn with_input(mut self, guardrail: Arc<dyn InputGuardrail>) -> Self {
+        self.hooks.input.push(guardrail);
+        self
+    }
+
+    /// Run `guardrail` on each streamed event, after those already
+    /// added.
+    pub fn with_stream(mut self, guardrail: Arc<dyn StreamGuardrail>) -> Self {
+        self.hooks.stream.push(guardrail);
+        self
+    }
+
+    /// Run `guardrail` on each finished response, after those already
+    /// added. Makes responses buffer; see the module docs.
+    pub fn with_output(mut self, guardrail: Arc<dyn OutputGuardrail>) -> Self {
+        self.hooks.output.push(guardrail);
+        self
+    }
+}
+
+impl std::fmt::Debug for GuardrailsProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GuardrailsProvider")
+            .field("hooks", &self.hooks)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for GuardrailsProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let mut prompt = Cow::Borrowed(prompt);
+        let mut config = Cow::Borrowed(config);
+        for guardrail in &self.hooks.input {
+            guardrail.check_input(&mut prompt, &mut config).await?;
+        }
+        let mut response = self.inner.generate(&prompt, &config).await?;
+        if !self.hooks.stream.is_empty() {
+            let guardrails = self.hooks.stream.clone();
+            response = response.map_stream(|stream| checking_events(stream, guardrails));
+        }
+        if !self.hooks.output.is_empty() {
+            let guardrails = self.hooks.output.clone();
+            response = response.map_stream(|stream| checking_output(stream, guardrails));
+        }
+        Ok(response)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// `stream` with every event passed through `guardrails`, ending at
+/// the first rejection.
+fn checking_events(stream: EventStream, guardrails: Vec<Arc<dyn StreamGuardrail>>) -> EventStream {
+    Box::pin(stream::unfold(Some(stream), move |state| {
+        let guardrails = guardrails.clone();
+        async move {
+            let mut stream = state?;
+            let item = stream.next().await?.and_then(|mut event| {
+                for guardrail in &guardrails {
+                    guardrail.check_event(&mut event)?;
+                }
+                Ok(event)
+            });
+            let next = item.is_ok().then_some(stream);
+            Some((item, next))
+        }
+    }))
+}
+
+/// `stream` buffered, passed through `guardrails`, then replayed.
+fn checking_output(stream: EventStream, guardrails: Vec<Arc<dyn OutputGuardrail>>) -> EventStream {
+    Box::pin(
+        stream::once(async move {
+            let mut complete = Response::from_stream(stream).buffer().await?;
+            for guardrail in &guardrails {
+                guardrail.check_output(&mut complete).await?;
+            }
+            Ok(complete)
+        })
+        .flat_map(|checked: Result<CompleteResponse, Error>| match checked {
+            Ok(complete) => Response::from_complete(complete).stream(),
+            Err(err) => Box::pin(stream::iter([Err(err)])),
+        }),
+    )
+}
+
+/// [`ProviderLayer`] that wraps providers in a [`GuardrailsProvider`]
+/// running the same hooks.
+#[derive(Debug, Clone, Default)]
+pub struct GuardrailsLayer {
+    hooks: Hooks,
+}
+
+impl GuardrailsLayer {
+    /// A layer with no hooks yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [`GuardrailsProvider::with_input`].
+    pub fn with_input(mut self, guardrail: Arc<dyn InputGuardrail>) -> Self {
+        self.hooks.input.push(guardrail);
+        self
+    }
+
+    /// See [`GuardrailsProvider::with_stream`].
+    pub fn with_stream(mut self, guardrail: Arc<dyn StreamGuardrail>) -> Self {
+        self.hooks.stream.push(guardrail);
+        self
+    }
+
+    /// See [`GuardrailsProvider::with_output`].
+    pub fn with_output(mut self, guardrail: Arc<dyn OutputGuardrail>) -> Self {
+        self.hooks.output.push(guardrail);
+        self
+    }
+}
+
+impl ProviderLayer for GuardrailsLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(GuardrailsProvider {
+            inner,
+            hooks: self.hooks.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, AssistantPart, Config, FinishReason};
+
+    struct BlockWord(&'static str);
+
+    #[async_trait::async_trait]
+    impl InputGuardrail for BlockWord {
+        async fn check_input(
+            &self,
+            prompt: &mut Cow<'_, Prompt>,
+            _config: &mut Cow<'_, RawConfig>,
+        ) -> Result<(), Error> {
+            let text = serde_json::to_string(prompt.items()).unwrap();
+            if text.contains(self.0) {
+                return Err(Error::content_policy("guardrails", "blocked topic"));
+            }
+            Ok(())
+        }
+    }
+
+    struct PinModel;
+
+    #[async_trait::async_trait]
+    impl InputGuardrail for PinModel {
+        async fn check_input(
+            &self,
+            _prompt: &mut Cow<'_, Prompt>,
+            config: &mut Cow<'_, RawConfig>,
+        ) -> Result<(), Error> {
+            config.to_mut().model = "approved-model".into();
+            Ok(())
+        }
+    }
+
+    struct Shout;
+
+    impl StreamGuardrail for Shout {
+        fn check_event(&self, event: &mut StreamEvent) -> Result<(), Error> {
+            if let StreamEvent::Delta { delta, .. } = event {
+                *delta = delta.to_uppercase();
+            }
+            Ok(())
+        }
+    }
+
+    struct Redact(&'static str);
+
+    #[async_trait::async_trait]
+    impl OutputGuardrail for Redact {
+        async fn check_output(&self, response: &mut CompleteResponse) -> Result<(), Error> {
+            for part in &mut response.content {
+                if let AssistantPart::Text { content, .. } = part {
+                    *content = content.replace(self.0, "[redacted]");
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn config() -> Config {
+        Config::builder("m").build()
+    }
+
+    #[tokio::test]
+    async fn input_guardrails_rewrite_or_reject_before_sending() {
+        let mock = MockProvider::with_text("ok");
+        let log = mock.call_log();
+        let provider = GuardrailsProvider::new(mock)
+            .with_input(Arc::new(BlockWord("forbidden")))
+            .with_input(Arc::new(PinModel));
+
+        let err = generate(&provider, &Prompt::user("a forbidden topic"), &config())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ContentPolicy { .. }), "got {err}");
+        assert!(log.is_empty(), "a rejected request is never sent");
+
+        generate(&provider, &Prompt::user("hello"), &config())
+            .await
+            .unwrap()
+            .buffer()
+            .await
+            .unwrap();
+        assert_eq!(log.calls()[0].config.model, "approved-model");
+    }
+
+    #[tokio::test]
+    async fn stream_and_output_guardrails_rewrite_the_response() {
+        let provider = GuardrailsProvider::new(MockProvider::with_text("call me at home"))
+            .with_stream(Arc::new(Shout))
+            .with_output(Arc::new(Redact("HOME")));
+        let complete = generate(&provider, &Prompt::user("hi"), &config())
+            .await
+            .unwrap()
+            .buffer()
+            .await
+            .unwrap();
+        assert_eq!(complete.text(), "CALL ME AT [redacted]");
+        assert_eq!(complete.finish_reason, FinishReason::Stop);
+    }
+
+    #[tokio::test]
+    async fn rejected_output_becomes_a_stream_error() {
+        struct RejectAll;
+
+        #[async_trait::async_trait]
+        impl OutputGuardrail for RejectAll {
+            async fn check_output(&self, _: &mut CompleteResponse) -> Result<(), Error> {
+                Err(Error::content_policy("guardrails", "flagged"))
+            }
+        }
+
+        let provider = GuardrailsLayer::new()
+            .with_output(Arc::new(RejectAll))
+            .layer(Arc::new(MockProvider::with_text("anything")));
+        let err = generate(&*provider, &Prompt::user("hi"), &config())
+            .await
+            .unwrap()
+            .buffer()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ContentPolicy { .. }), "got {err}");
+    }
+}
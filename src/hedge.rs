@@ -0,0 +1,416 @@
+//! Hedged requests: race a slow primary against a delayed duplicate.
+//!
+//! Tail latency on hosted models is dominated by the occasional request
+//! that sits in a queue upstream for several seconds before its first
+//! token. Most of those would have been fast on a second try. A
+//! [`HedgedProvider`] sends every request to its primary backend and,
+//! if no token has arrived after `delay`, fires the *same* request at
+//! a hedge backend. Whichever stream yields its first token first is
+//! returned; the other call is dropped, which closes its HTTP
+//! connection and stops its generation.
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use platformed_llm::HedgedProvider;
+//!
+//! // p95 time-to-first-token on the primary is ~1.2s.
+//! let provider = HedgedProvider::new("vertex", vertex, "openai", openai, Duration::from_millis(1500))
+//!     .with_hedge_model("gpt-4.1-mini");
+//! ```
+//!
+//! Pick `delay` around the primary's p90–p95 time to first token: the
+//! hedge then fires on roughly one request in ten or twenty, which
+//! bounds the extra spend, while cutting the tail that pushes p99 up.
+//! Both calls bill for their input tokens, so a hedge is never free.
+//!
+//! # What counts as "first token"
+//!
+//! The first stream event that carries output — anything other than
+//! [`StreamEvent::PartStart`], which some backends emit as soon as the
+//! response headers land. Opening events seen before that are buffered
+//! and replayed, so the winner's stream is delivered intact.
+//!
+//! # Errors
+//!
+//! An attempt that fails before its first token (its `generate`
+//! returned `Err`, or the stream's first item was an `Err`) drops out
+//! of the race. If the primary fails that way *before* `delay` with an
+//! [`Error::is_retryable`] error, the hedge is fired immediately rather
+//! than waiting out the timer; a non-retryable failure surfaces as-is.
+//! When both attempts fail, the primary's error is returned. After the
+//! first token the stream is committed to its backend, exactly as with
+//! [`crate::FallbackProvider`].
+//!
+//! [`crate::ResponseMetadata::served_by`] records which backend won.
+//! Middleware runs once against the primary's capabilities, so hedge
+//! only across backends with comparable capabilities.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::{select, Either};
+use futures_util::StreamExt;
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::retry::key_for_target;
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response, StreamEvent};
+
+#[derive(Clone)]
+struct Backend {
+    label: String,
+    provider: SharedProvider,
+    /// Model to request from this backend instead of the caller's.
+    model: Option<String>,
+}
+
+impl Backend {
+    /// Run one attempt up to (and including) its first token.
+    async fn first_token(
+        &self,
+        prompt: &Prompt,
+        config: &RawConfig,
+        target: usize,
+    ) -> Result<Response, Error> {
+        let mut config = key_for_target(config, target).into_owned();
+        if let Some(model) = &self.model {
+            config.model = model.clone();
+        }
+        let mut response = self.provider.generate(prompt, &config).await?;
+        let metadata = response.metadata_mut();
+        metadata.served_by = Some(self.label.clone());
+        metadata.model = Some(config.model);
+
+        let mut head = Vec::new();
+        loop {
+            match response.next().await {
+                Some(Err(err)) => return Err(err),
+                Some(Ok(event)) => {
+                    let is_token = !matches!(event, StreamEvent::PartStart { .. });
+                    head.push(Ok(event));
+                    if is_token {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        Ok(response.map_stream(|rest| futures_util::stream::iter(head).chain(rest)))
+    }
+}
+
+/// A [`Provider`] that hedges slow requests onto a second backend. See
+/// the module docs.
+#[derive(Clone)]
+pub struct HedgedProvider {
+    primary: Backend,
+    hedge: Backend,
+    delay: Duration,
+}
+
+impl HedgedProvider {
+    /// Send every request to `primary`, and to `hedge` as well once
+    /// `delay` has passed without a token from `primary`. Labels are
+    /// what [`crate::ResponseMetadata::served_by`] reports.
+    pub fn new(
+        primary_label: impl Into<String>,
+        primary: impl Provider,
+        hedge_label: impl Into<String>,
+        hedge: impl Provider,
+        delay: Duration,
+    ) -> Self {
+        Self::from_shared(
+            primary_label,
+            Arc::new(primary),
+            hedge_label,
+            Arc::new(hedge),
+            delay,
+        )
+    }
+
+    /// [`Self::new`] over already-shared providers.
+    pub fn from_shared(
+        primary_label: impl Into<String>,
+        primary: SharedProvider,
+        hedge_label: impl Into<String>,
+        hedge: SharedProvider,
+        delay: Duration,
+    ) -> Self {
+        Self {
+            primary: Backend {
+                label: primary_label.into(),
+                provider: primary,
+                model: None,
+            },
+            hedge: Backend {
+                label: hedge_label.into(),
+                provider: hedge,
+                model: None,
+            },
+            delay,
+        }
+    }
+
+    /// Ask the hedge backend for `model` instead of the caller's — for
+    /// cross-vendor hedges, where model names don't carry over.
+    pub fn with_hedge_model(mut self, model: impl Into<String>) -> Self {
+        self.hedge.model = Some(model.into());
+        self
+    }
+
+    /// Configured hedge delay.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+}
+
+impl std::fmt::Debug for HedgedProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HedgedProvider")
+            .field("primary", &self.primary.label)
+            .field("hedge", &(&self.hedge.label, &self.hedge.model))
+            .field("delay", &self.delay)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for HedgedProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let primary = Box::pin(self.primary.first_token(prompt, config, 0));
+        let timer = Box::pin(tokio::time::sleep(self.delay));
+
+        let primary = match select(primary, timer).await {
+            Either::Left((Ok(response), _)) => return Ok(response),
+            Either::Left((Err(err), _)) => {
+                if !err.is_retryable() {
+                    return Err(err);
+                }
+                tracing::warn!(
+                    backend = %self.primary.label,
+                    error = %err,
+                    "hedge primary failed before first token; firing hedge early",
+                );
+                return match self.hedge.first_token(prompt, config, 1).await {
+                    Ok(response) => Ok(response),
+                    Err(_) => Err(err),
+                };
+            }
+            Either::Right(((), primary)) => primary,
+        };
+
+        tracing::debug!(
+            primary = %self.primary.label,
+            hedge = %self.hedge.label,
+            delay_ms = self.delay.as_millis() as u64,
+            "no first token before hedge delay; firing hedge",
+        );
+        let hedge = Box::pin(self.hedge.first_token(prompt, config, 1));
+        // Dropping the losing future cancels its in-flight call.
+        match select(primary, hedge).await {
+            Either::Left((Ok(response), _)) | Either::Right((Ok(response), _)) => Ok(response),
+            Either::Left((Err(primary_err), hedge)) => hedge.await.map_err(|_| primary_err),
+            Either::Right((Err(_), primary)) => primary.await,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.primary.provider.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.primary.provider.capabilities(model)
+    }
+}
+
+/// [`ProviderLayer`] that hedges every wrapped provider (the primary)
+/// onto one shared hedge backend. See [`HedgedProvider`].
+#[derive(Clone)]
+pub struct HedgeLayer {
+    hedge_label: String,
+    hedge: SharedProvider,
+    hedge_model: Option<String>,
+    delay: Duration,
+}
+
+impl HedgeLayer {
+    /// A layer that hedges onto `hedge` after `delay`. The wrapped
+    /// provider is reported as `"primary"` in
+    /// [`crate::ResponseMetadata::served_by`].
+    pub fn new(hedge_label: impl Into<String>, hedge: SharedProvider, delay: Duration) -> Self {
+        Self {
+            hedge_label: hedge_label.into(),
+            hedge,
+            hedge_model: None,
+            delay,
+        }
+    }
+
+    /// See [`HedgedProvider::with_hedge_model`].
+    pub fn with_hedge_model(mut self, model: impl Into<String>) -> Self {
+        self.hedge_model = Some(model.into());
+        self
+    }
+}
+
+impl std::fmt::Debug for HedgeLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HedgeLayer")
+            .field("hedge", &(&self.hedge_label, &self.hedge_model))
+            .field("delay", &self.delay)
+            .finish()
+    }
+}
+
+impl ProviderLayer for HedgeLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        let mut provider = HedgedProvider::from_shared(
+            "primary",
+            inner,
+            self.hedge_label.clone(),
+            self.hedge.clone(),
+            self.delay,
+        );
+        provider.hedge.model = self.hedge_model.clone();
+        Arc::new(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::{generate, Config, FinishReason};
+
+    /// A provider that waits `delay` before delegating to `inner`.
+    struct Slow {
+        delay: Duration,
+        inner: MockProvider,
+    }
+
+    impl Slow {
+        fn new(delay: Duration, inner: MockProvider) -> Self {
+            Self { delay, inner }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for Slow {
+        async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.generate(prompt, config).await
+        }
+    }
+
+    async fn run(provider: &HedgedProvider) -> Result<(String, String), Error> {
+        let response =
+            generate(provider, &Prompt::user("x"), &Config::builder("m").build()).await?;
+        let label = response.metadata().served_by.clone().unwrap();
+        Ok((label, response.text().await?))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fast_primary_never_fires_hedge() {
+        let hedge = MockProvider::with_text("hedge");
+        let hedge_log = hedge.call_log();
+        let provider = HedgedProvider::new(
+            "primary",
+            Slow::new(
+                Duration::from_millis(100),
+                MockProvider::with_text("primary"),
+            ),
+            "hedge",
+            hedge,
+            Duration::from_millis(500),
+        );
+        assert_eq!(
+            run(&provider).await.unwrap(),
+            ("primary".into(), "primary".into())
+        );
+        assert!(hedge_log.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_primary_loses_to_hedge() {
+        let provider = HedgedProvider::new(
+            "primary",
+            Slow::new(Duration::from_secs(10), MockProvider::with_text("primary")),
+            "hedge",
+            Slow::new(Duration::from_millis(100), MockProvider::with_text("hedge")),
+            Duration::from_millis(500),
+        )
+        .with_hedge_model("hedge-model");
+        let start = tokio::time::Instant::now();
+        let response = generate(&provider, &Prompt::user("x"), &Config::builder("m").build())
+            .await
+            .unwrap();
+        assert_eq!(response.metadata().model.as_deref(), Some("hedge-model"));
+        assert_eq!(response.text().await.unwrap(), "hedge");
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn primary_still_wins_if_it_beats_late_hedge() {
+        let provider = HedgedProvider::new(
+            "primary",
+            Slow::new(
+                Duration::from_millis(600),
+                MockProvider::with_text("primary"),
+            ),
+            "hedge",
+            Slow::new(Duration::from_secs(10), MockProvider::with_text("hedge")),
+            Duration::from_millis(500),
+        );
+        assert_eq!(run(&provider).await.unwrap().0, "primary");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retryable_primary_failure_fires_hedge_early() {
+        let provider = HedgedProvider::new(
+            "primary",
+            MockProvider::builder()
+                .fail(Error::provider_with_status("Mock", 503, "down"))
+                .build(),
+            "hedge",
+            MockProvider::with_text("hedge"),
+            Duration::from_secs(60),
+        );
+        let start = tokio::time::Instant::now();
+        assert_eq!(run(&provider).await.unwrap().0, "hedge");
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn both_failing_returns_primary_error() {
+        let provider = HedgedProvider::new(
+            "primary",
+            MockProvider::with_handler(|_, _| {
+                MockResponse::from_parts(Vec::new(), FinishReason::Stop)
+                    .with_stream_error(Error::provider_with_status("Mock", 502, "primary"))
+            }),
+            "hedge",
+            MockProvider::builder()
+                .fail(Error::provider_with_status("Mock", 503, "hedge"))
+                .build(),
+            Duration::from_millis(10),
+        );
+        let err = run(&provider).await.unwrap_err();
+        assert_eq!(err.status(), Some(502));
+    }
+
+    #[tokio::test]
+    async fn non_retryable_primary_failure_surfaces() {
+        let hedge = MockProvider::with_text("hedge");
+        let hedge_log = hedge.call_log();
+        let provider = HedgedProvider::new(
+            "primary",
+            MockProvider::builder()
+                .fail(Error::invalid_prompt("bad"))
+                .build(),
+            "hedge",
+            hedge,
+            Duration::from_secs(60),
+        );
+        assert!(run(&provider).await.is_err());
+        assert!(hedge_log.is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+//! Provider-wrapping layers — the composable extension point for
+//! cross-cutting concerns (retries, caching, metrics, logging) that
+//! need to see *every* call to a [`Provider`] rather than rewrite a
+//! single request.
+//!
+//! The two extension points split along what they touch:
+//!
+//! - [`crate::middleware::Middleware`] rewrites the `(Prompt,
+//!   RawConfig)` pair on its way to the provider and (optionally) the
+//!   response stream on its way back. It runs inside
+//!   [`crate::generate`] and knows the model's capabilities.
+//! - [`ProviderLayer`] wraps the provider itself, tower-`Layer` style:
+//!   it takes an inner [`SharedProvider`] and returns a new one that
+//!   decides whether, when, and how often to call through. Anything
+//!   that needs to re-issue, skip, time, or record whole calls lives
+//!   here.
+//!
+//! A wrapped provider is still just a `dyn Provider`, so layers stack
+//! freely and the result drops into [`crate::generate`] (or another
+//! layer) unchanged:
+//!
+//! ```ignore
+//! use platformed_llm::{ProviderFactory, ProviderStack};
+//!
+//! let provider = ProviderStack::new()
+//!     .layer(logging)   // outermost — sees the call first
+//!     .layer(retrying)  // innermost — sits directly on the backend
+//!     .wrap(ProviderFactory::from_env().await?.into());
+//! ```
+//!
+//! Layers compose **outside-in** in the order they're added, the same
+//! as tower's `ServiceBuilder`: the first layer added is the first to
+//! see the call and the last to see the response.
+
+use std::sync::Arc;
+
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response};
+
+/// The reference-counted provider handle layers consume and produce.
+/// Cloning is a refcount bump, so a layer that fans out (fallback,
+/// hedging) can hold several without copying the backend.
+///
+/// A `Box<dyn Provider>` from [`crate::ProviderFactory`] converts via
+/// `SharedProvider::from(boxed)`.
+pub type SharedProvider = Arc<dyn Provider>;
+
+/// Wraps a provider in another provider. The tower-`Layer` analogue for
+/// [`Provider`]: implementors return a new [`SharedProvider`] that
+/// delegates to `inner`, adding behaviour around each call.
+///
+/// Any `Fn(SharedProvider) -> SharedProvider` closure is a layer, so
+/// one-off wrappers don't need a named type.
+pub trait ProviderLayer: Send + Sync + 'static {
+    /// Wrap `inner`, returning the decorated provider.
+    fn layer(&self, inner: SharedProvider) -> SharedProvider;
+}
+
+impl<F> ProviderLayer for F
+where
+    F: Fn(SharedProvider) -> SharedProvider + Send + Sync + 'static,
+{
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        self(inner)
+    }
+}
+
+/// Ordered collection of [`ProviderLayer`]s applied to a provider in
+/// one go. Layers added first end up outermost — see the module docs.
+#[derive(Default, Clone)]
+pub struct ProviderStack {
+    layers: Vec<Arc<dyn ProviderLayer>>,
+}
+
+impl ProviderStack {
+    /// An empty stack. [`Self::service`] on an empty stack returns the
+    /// provider unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer *inside* every layer added so far.
+    pub fn layer(mut self, layer: impl ProviderLayer) -> Self {
+        self.layers.push(Arc::new(layer));
+        self
+    }
+
+    /// Number of layers in the stack.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// `true` when no layers have been added.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Wrap a concrete `provider` in every layer of the stack.
+    pub fn service<P: Provider>(&self, provider: P) -> SharedProvider {
+        self.wrap(Arc::new(provider))
+    }
+
+    /// Wrap an already-shared provider (e.g. a converted
+    /// `Box<dyn Provider>` from the factory) in every layer of the
+    /// stack.
+    pub fn wrap(&self, provider: SharedProvider) -> SharedProvider {
+        // Innermost first: the last layer added wraps the backend
+        // directly, the first added ends up on the outside.
+        self.layers
+            .iter()
+            .rev()
+            .fold(provider, |inner, layer| layer.layer(inner))
+    }
+}
+
+impl std::fmt::Debug for ProviderStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderStack")
+            .field("layers", &self.layers.len())
+            .finish()
+    }
+}
+
+// Forwarding impls so a layered `Arc<dyn Provider>` (or the factory's
+// `Box<dyn Provider>`) is itself a `Provider` — lets a wrapped handle go
+// anywhere a concrete provider is expected (`ProviderStack::service`,
+// generic helpers, another provider's constructor).
+
+#[async_trait::async_trait]
+impl<P: Provider + ?Sized> Provider for Arc<P> {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        (**self).generate(prompt, config).await
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        (**self).capabilities(model)
+    }
+}
+
+#[async_trait::async_trait]
+impl<P: Provider + ?Sized> Provider for Box<P> {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        (**self).generate(prompt, config).await
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        (**self).capabilities(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, Config};
+    use std::sync::Mutex;
+
+    /// Records its `tag` into a shared log on every call, then
+    /// delegates.
+    struct Tagging {
+        tag: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+        inner: SharedProvider,
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for Tagging {
+        async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+            self.log.lock().unwrap().push(self.tag);
+            self.inner.generate(prompt, config).await
+        }
+    }
+
+    fn tagging(tag: &'static str, log: &Arc<Mutex<Vec<&'static str>>>) -> impl ProviderLayer {
+        let log = log.clone();
+        move |inner: SharedProvider| -> SharedProvider {
+            Arc::new(Tagging {
+                tag,
+                log: log.clone(),
+                inner,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn first_layer_added_is_outermost() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let provider = ProviderStack::new()
+            .layer(tagging("outer", &log))
+            .layer(tagging("inner", &log))
+            .service(MockProvider::with_text("hi"));
+
+        let cfg = Config::builder("m").build();
+        let text = generate(&*provider, &Prompt::user("x"), &cfg)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(text, "hi");
+        assert_eq!(*log.lock().unwrap(), vec!["outer", "inner"]);
+    }
+
+    #[tokio::test]
+    async fn empty_stack_is_identity() {
+        let stack = ProviderStack::new();
+        assert!(stack.is_empty());
+        let boxed: Box<dyn Provider> = Box::new(MockProvider::with_text("plain"));
+        let provider = stack.wrap(SharedProvider::from(boxed));
+        let cfg = Config::builder("m").build();
+        let text = generate(&*provider, &Prompt::user("x"), &cfg)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(text, "plain");
+    }
+
+    #[test]
+    fn wrappers_report_the_wrapped_provider_name() {
+        let provider = ProviderStack::new()
+            .layer(crate::RetryLayer::new(crate::RetryPolicy::default()))
+            .layer(crate::TimeoutLayer::new(std::time::Duration::from_secs(5)))
+            .service(MockProvider::with_text("hi"));
+        assert_eq!(provider.name(), "Mock");
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let custom = ProviderStack::new()
+            .layer(tagging("t", &log))
+            .service(MockProvider::with_text("hi"));
+        assert!(custom.name().ends_with("Tagging"));
+    }
+}
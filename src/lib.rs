@@ -3,6 +3,22 @@
 //! This library provides a consistent API for interacting with OpenAI,
 //! Google Gemini (via Vertex AI), and Anthropic Claude (via Vertex AI),
 //! with support for streaming responses and function calling.
+//!
+//! # WebAssembly
+//!
+//! The crate builds for `wasm32-unknown-unknown` with any provider
+//! feature. On that target `reqwest` streams through the browser's
+//! `fetch`, so a front-end (or an edge worker) can call a provider and
+//! consume [`Response`] events directly. Vertex providers need an
+//! explicit access token there — Application Default Credentials are
+//! native-only — and [`transport::TransportImpl`] remains the hook for
+//! any other HTTP binding.
+//!
+//! The target has no clock or timer of its own: `std::time::Instant`
+//! panics and `tokio::time::sleep` has no driver. Call providers
+//! directly, or through wrappers that never wait or measure time; the
+//! retry, hedge, router, metrics and token-bucket rate-limit layers
+//! are native-only in practice.
 
 #![deny(missing_docs)]
 
@@ -11,6 +27,26 @@
 /// expose it for advanced users that drive the event stream themselves
 /// (e.g. running the accumulator alongside a live UI handler).
 pub mod accumulator;
+// JSONL audit trail, behind the `audit` feature. Documented via its own
+// module-level docs so intra-doc links resolve in its scope.
+#[cfg(feature = "audit")]
+pub mod audit;
+// Caller-supplied bearer tokens. Documented via its own `//!` docs so
+// intra-doc links there resolve in the module's scope.
+pub mod auth;
+/// Continuing length-truncated responses with follow-up requests —
+/// see [`auto_continue::AutoContinueProvider`].
+pub mod auto_continue;
+/// Cross-request micro-batching for embeddings — see
+/// [`batching::BatchingEmbedder`].
+pub mod batching;
+// Synchronous wrapper owning its own runtime. Documented via its own
+// `//!` docs so intra-doc links there resolve in the module's scope.
+#[cfg(feature = "blocking")]
+pub mod blocking;
+/// Bounded, backpressure-aware streaming — see
+/// [`Response::bounded`].
+pub mod bounded;
 /// Per-model capability table consulted by middleware to decide which
 /// features can be requested natively vs. need a polyfill or drop.
 pub mod capabilities;
@@ -18,9 +54,45 @@ pub mod capabilities;
 /// long-running sessions that would otherwise blow past the model's
 /// context window. See [`compaction::Compactor`].
 pub mod compaction;
+/// Per-provider concurrency limiting — see
+/// [`concurrency::ConcurrencyLimitProvider`].
+pub mod concurrency;
+/// Per-provider default model and generation parameters — see
+/// [`defaults::DefaultsProvider`].
+pub mod defaults;
+/// Ordered failover across providers / models — see
+/// [`fallback::FallbackProvider`].
+pub mod fallback;
+/// Pre- and post-generation guardrail hooks — see
+/// [`guardrails::GuardrailsProvider`].
+pub mod guardrails;
+/// Hedged requests — race a slow primary against a delayed duplicate
+/// on a second backend. See [`hedge::HedgedProvider`].
+pub mod hedge;
+/// Provider-wrapping layers — tower-style decorators for cross-cutting
+/// concerns (retries, caching, metrics) that wrap a whole
+/// [`Provider`]. See [`layer::ProviderLayer`] / [`layer::ProviderStack`].
+pub mod layer;
+// HTTP request / response logging with secret redaction. Documented
+// via its own module-level docs so intra-doc links resolve in its scope.
+pub mod logging;
+/// Per-call latency / TTFT / token-rate reporting — see
+/// [`metrics::MetricsObserver`].
+pub mod metrics;
 /// Request/response middleware applied above the provider layer —
 /// polyfills, validation, and the top-level [`generate`] entry point.
 pub mod middleware;
+/// `backend:model` routing across differently-configured providers —
+/// see [`multi::MultiProvider`].
+pub mod multi;
+// OpenTelemetry GenAI spans, behind the `otel` feature. Documented via
+// its own module-level docs so intra-doc links resolve in its scope.
+#[cfg(feature = "otel")]
+pub mod otel;
+/// Named provider profiles loaded from TOML / YAML files — see
+/// [`profiles::ProviderProfiles`].
+#[cfg(feature = "config-file")]
+pub mod profiles;
 /// Concrete provider implementations. Browse this module to see what
 /// backends the lib supports and how to construct each one.
 pub mod providers;
@@ -31,18 +103,49 @@ pub mod providers;
 pub mod rate_limit;
 /// Retry helpers for transient provider failures — [`RetryPolicy`]
 /// centralises backoff / `Retry-After` arithmetic, [`retry()`] wraps an
-/// async operation in the loop. See the module docs for the buffered
-/// vs streaming patterns.
+/// async operation in the loop, and [`RetryingProvider`] /
+/// [`RetryLayer`] apply it to every call on a provider. See the module
+/// docs for the buffered vs streaming patterns.
 pub mod retry;
+/// Load spreading across interchangeable backends with per-backend
+/// health tracking — see [`router::RouterProvider`].
+pub mod router;
+/// Embeddings-backed cache answering near-duplicate prompts — see
+/// [`semantic_cache::SemanticCacheProvider`].
+pub mod semantic_cache;
+// OpenAI-compatible `/v1/chat/completions` facade. Documented via its
+// own `//!` docs so intra-doc links there resolve in the module's scope.
+#[cfg(feature = "server")]
+pub mod server;
 /// Server-Sent Events parser used by the default streaming response
 /// path. Exposed for callers plugging a custom [`transport`] into a
-/// non-default backend.
+/// non-default backend, and for [`sse_stream::encode_events`], which
+/// frames a unified event stream back into SSE.
 pub mod sse_stream;
+/// Combinators for consuming a response's events — text deltas,
+/// completed tool calls — see [`stream_ext::EventStreamExt`].
+pub mod stream_ext;
+/// Whole-call deadlines and stream idle timeouts — per-request via
+/// [`ConfigBuilder::timeout`] / [`ConfigBuilder::idle_timeout`],
+/// per-provider via [`timeout::TimeoutProvider`] /
+/// [`timeout::IdleTimeoutProvider`].
+pub mod timeout;
+/// Running a turn's tool calls concurrently and answering them. See
+/// [`tools::ToolRegistry`].
+pub mod tools;
 /// HTTP transport abstraction. The default implementation is
 /// `reqwest`-backed; callers can supply their own (recording,
 /// retrying, replaying) [`transport::TransportImpl`] for testing or
 /// fault injection.
 pub mod transport;
+/// Token and cost totals across calls — per model, per provider and
+/// per time window. See [`usage::UsageTracker`].
+pub mod usage;
+// Image preparation for vision input, behind the `vision` feature.
+// Documented via its own module-level docs so intra-doc links resolve
+// in its scope.
+#[cfg(feature = "vision")]
+pub mod vision;
 
 // Test-only helpers for locating/downloading the integration suite's
 // GGUF models, for reuse by downstream crates. Documented via its own
@@ -68,22 +171,54 @@ mod types;
 // and are reachable via the fully-qualified path. No globs — adding a
 // `pub` item to an internal module must not leak it.
 
+pub use auth::{SharedTokenSource, TokenSource};
+pub use auto_continue::{AutoContinueLayer, AutoContinueProvider};
+pub use batching::BatchingEmbedder;
+pub use bounded::{OverflowPolicy, StreamPump};
 pub use capabilities::Capabilities;
 pub use compaction::Compactor;
-pub use error::Error;
-pub use factory::{ProviderConfig, ProviderFactory, ProviderType};
-pub use middleware::{generate, JsonCoercionMiddleware, Middleware};
+pub use concurrency::{ConcurrencyLimitLayer, ConcurrencyLimitProvider};
+pub use defaults::{DefaultsLayer, DefaultsProvider, RequestDefaults};
+pub use error::{Error, ProviderErrorDetails};
+pub use factory::{ProviderConfig, ProviderConfigBuilder, ProviderFactory, ProviderType};
+pub use fallback::FallbackProvider;
+pub use guardrails::{
+    GuardrailsLayer, GuardrailsProvider, InputGuardrail, OutputGuardrail, StreamGuardrail,
+};
+pub use hedge::{HedgeLayer, HedgedProvider};
+pub use layer::{ProviderLayer, ProviderStack, SharedProvider};
+pub use metrics::{
+    CallMetrics, CallOutcome, MetricsLayer, MetricsObserver, MetricsProvider, SharedMetricsObserver,
+};
+pub use middleware::{
+    generate, generate_many, JsonCoercionMiddleware, Middleware, ToolArgumentValidationMiddleware,
+};
+pub use multi::MultiProvider;
+#[cfg(feature = "config-file")]
+pub use profiles::{ProfileAuth, ProfileDefaults, ProviderProfile, ProviderProfiles};
 pub use provider::Provider;
 pub use rate_limit::{
-    InMemoryRateLimiter, NoOpRateLimiter, Priority, ProviderRateInfo, RateLimiter, RateOutcome,
-    RatePermit, RateScope, SharedRateLimiter,
+    InMemoryRateLimiter, NoOpRateLimiter, Priority, ProviderRateInfo, RateLimitLayer,
+    RateLimitedProvider, RateLimiter, RateOutcome, RatePermit, RateScope, SharedRateLimiter,
+    TokenBucketRateLimiter,
+};
+pub use response::{CompleteResponse, EventStream, Response, ResponseMetadata, ResponseTiming};
+pub use retry::{retry, RetryClassifier, RetryLayer, RetryPolicy, RetryingProvider};
+pub use router::{BackendHealth, RouterProvider, RoutingStrategy};
+pub use semantic_cache::{
+    Embedder, SemanticCacheLayer, SemanticCacheProvider, SemanticCacheStats, SharedEmbedder,
 };
-pub use response::{CompleteResponse, Response};
-pub use retry::{retry, RetryPolicy};
+pub use stream_ext::EventStreamExt;
+pub use timeout::{IdleTimeoutLayer, IdleTimeoutProvider, TimeoutLayer, TimeoutProvider};
+pub use tools::{ToolHandler, ToolOutput, ToolRegistry};
 pub use types::{
-    Annotation, AnnotationKind, AssistantPart, ComputerUseConfig, Config, ConfigBuilder,
-    FileResolver, FileSource, FinishReason, Function, FunctionCall, InputItem, LruFileResolver,
-    PartKind, PartUpdate, Prompt, ProviderBuiltin, ProviderContinuation, ProviderScope, RawConfig,
-    ReasoningConfig, ReasoningEffort, ReasoningSummary, ResolvedFile, ResolvedHandle,
-    ResponseFormat, StreamEvent, Tool, ToolChoice, Usage, UserPart,
+    Annotation, AnnotationKind, AnthropicOptions, AssistantPart, ComputerUseConfig, Config,
+    ConfigBuilder, FileResolver, FileSource, FinishReason, Function, FunctionCall, GoogleOptions,
+    GoogleSafetySetting, HarmBlockThreshold, HarmCategory, InputItem, LruFileResolver,
+    OpenAIOptions, OpenAIServiceTier, PartKind, PartUpdate, Prompt, ProviderBuiltin,
+    ProviderContinuation, ProviderOptions, ProviderScope, RawConfig, ReasoningConfig,
+    ReasoningEffort, ReasoningSummary, RequestMetadata, ResolvedFile, ResolvedHandle,
+    ResponseFormat, SafetyFeedback, SafetyLevel, SafetyRating, SafetyTarget, StreamEvent, Tool,
+    ToolChoice, Usage, UserPart,
 };
+pub use usage::{ModelPricing, UsageSnapshot, UsageTotals, UsageTracker, WindowUsage};
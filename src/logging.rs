@@ -0,0 +1,550 @@
+//! HTTP request / response logging with secret redaction.
+//!
+//! [`LoggingTransport`] wraps any [`Transport`] and reports a
+//! [`HttpLogEvent`] for every request it sends, every response it gets
+//! back and every transport failure, to a caller-supplied
+//! [`HttpLogHook`]. Everything in an event has already been through a
+//! [`Redactor`]:
+//!
+//! - header values whose name is sensitive (`Authorization`,
+//!   `x-api-key`, `x-goog-api-key`, cookies, plus any added with
+//!   [`Redactor::with_header`]) are replaced with `[REDACTED]`;
+//! - credential query parameters (`key=`, `api_key=`,
+//!   `access_token=`) are masked in URLs;
+//! - free text — error bodies, transport error messages — has bearer
+//!   tokens and well-known key shapes (`sk-…`, `AIza…`, `ya29.…`)
+//!   masked wherever they appear.
+//!
+//! Request bodies are summarised by size only — prompts are user data
+//! and don't belong in logs by default. Error response bodies are
+//! included (redacted, truncated to [`LoggingTransport::with_max_body_bytes`])
+//! because they are what explains a failure.
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use platformed_llm::logging::{HttpLogEvent, LoggingTransport, Redactor};
+//! use platformed_llm::transport::Transport;
+//!
+//! let transport = Transport::new(
+//!     LoggingTransport::new(Transport::reqwest()?, Arc::new(|event: &HttpLogEvent| {
+//!         tracing::info!(?event, "llm http");
+//!     }))
+//!     .with_redactor(Redactor::new().with_header("x-internal-tenant-token")),
+//! );
+//! let provider = OpenAIProvider::with_transport(key, base_url, transport);
+//! ```
+//!
+//! The same default scrubbing is applied to the upstream error bodies
+//! the hosted providers fold into [`crate::Error`] messages, so an API
+//! echoing a key back in its error envelope doesn't leak it through
+//! `err.to_string()`.
+
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::StreamExt as _;
+use tokio::time::Instant;
+
+use crate::transport::{
+    Method, Transport, TransportImpl, TransportRequest, TransportResponse, UploadRequest,
+};
+use crate::Error;
+
+/// Replacement text for redacted values.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Header names redacted by default (compared case-insensitively).
+const DEFAULT_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "api-key",
+    "x-goog-api-key",
+    "cookie",
+    "set-cookie",
+];
+
+/// Credential-shaped token prefixes masked in free text by default:
+/// OpenAI / Anthropic secret keys, Google API keys, Google OAuth
+/// access tokens.
+const DEFAULT_PREFIXES: &[&str] = &["sk-", "AIza", "ya29."];
+
+/// URL query parameters masked by default.
+const DEFAULT_QUERY_PARAMS: &[&str] = &["key", "api_key", "access_token"];
+
+/// Minimum token length after a prefix for it to count as a secret —
+/// keeps prose like "sk-learn" or "AIza" on its own intact.
+const MIN_SECRET_LEN: usize = 16;
+
+static DEFAULT_REDACTOR: LazyLock<Redactor> = LazyLock::new(Redactor::new);
+
+/// Scrub credentials out of `text` with the default [`Redactor`]. Used
+/// by the hosted providers on upstream error bodies.
+#[cfg_attr(not(any(feature = "openai", feature = "vertex")), allow(dead_code))]
+pub(crate) fn scrub(text: &str) -> String {
+    DEFAULT_REDACTOR.redact_text(text)
+}
+
+/// Decides what gets masked in [`HttpLogEvent`]s. See the module docs
+/// for the defaults.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    headers: Vec<String>,
+    prefixes: Vec<String>,
+    query_params: Vec<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redactor {
+    /// A redactor with the default header names, key prefixes and
+    /// query parameters.
+    pub fn new() -> Self {
+        Self {
+            headers: DEFAULT_HEADERS.iter().map(|h| h.to_string()).collect(),
+            prefixes: DEFAULT_PREFIXES.iter().map(|p| p.to_string()).collect(),
+            query_params: DEFAULT_QUERY_PARAMS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// Also redact the value of header `name` (case-insensitive).
+    pub fn with_header(mut self, name: impl Into<String>) -> Self {
+        self.headers.push(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Also mask tokens starting with `prefix` in free text (e.g. an
+    /// internal gateway's `gw_live_` keys).
+    pub fn with_secret_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Also mask the URL query parameter `name`.
+    pub fn with_query_param(mut self, name: impl Into<String>) -> Self {
+        self.query_params.push(name.into());
+        self
+    }
+
+    /// Whether header `name`'s value is redacted.
+    pub fn is_sensitive_header(&self, name: &str) -> bool {
+        self.headers.iter().any(|h| h.eq_ignore_ascii_case(name))
+    }
+
+    /// `headers` with sensitive values replaced by [`REDACTED`].
+    pub fn redact_headers(&self, headers: &[(String, String)]) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_sensitive_header(name) {
+                    REDACTED.to_string()
+                } else {
+                    self.redact_text(value)
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// `url` with credential query parameters masked.
+    pub fn redact_url(&self, url: &str) -> String {
+        let Some((base, query)) = url.split_once('?') else {
+            return url.to_string();
+        };
+        let query: Vec<String> = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if self.query_params.iter().any(|p| p == name) => {
+                    format!("{name}={REDACTED}")
+                }
+                _ => pair.to_string(),
+            })
+            .collect();
+        format!("{base}?{}", query.join("&"))
+    }
+
+    /// `text` with bearer tokens and credential-shaped tokens masked.
+    pub fn redact_text(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        'scan: while !rest.is_empty() {
+            if out.chars().next_back().is_none_or(|c| !is_token_char(c)) {
+                if let Some(after) = strip_prefix_ignore_case(rest, "bearer ") {
+                    let token_len = token_len(after);
+                    if token_len > 0 {
+                        out.push_str(&rest[..rest.len() - after.len()]);
+                        out.push_str(REDACTED);
+                        rest = &after[token_len..];
+                        continue 'scan;
+                    }
+                }
+                for prefix in &self.prefixes {
+                    if let Some(after) = rest.strip_prefix(prefix.as_str()) {
+                        let token_len = token_len(after);
+                        if token_len >= MIN_SECRET_LEN {
+                            out.push_str(REDACTED);
+                            rest = &after[token_len..];
+                            continue 'scan;
+                        }
+                    }
+                }
+            }
+            let c = rest.chars().next().expect("rest is non-empty");
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        out
+    }
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~' | '+' | '/' | '=')
+}
+
+fn token_len(s: &str) -> usize {
+    s.find(|c: char| !is_token_char(c)).unwrap_or(s.len())
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
+}
+
+/// One logged HTTP interaction, already redacted. See the module docs.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub enum HttpLogEvent {
+    /// A request is about to be sent.
+    Request {
+        /// HTTP method.
+        method: Method,
+        /// Request URL, credential query parameters masked.
+        url: String,
+        /// Request headers, sensitive values masked.
+        headers: Vec<(String, String)>,
+        /// Request body size in bytes, when known (streaming uploads
+        /// may not know it).
+        body_bytes: Option<u64>,
+    },
+    /// Response status and headers arrived.
+    Response {
+        /// Request URL, credential query parameters masked.
+        url: String,
+        /// HTTP status code.
+        status: u16,
+        /// Response headers, sensitive values masked.
+        headers: Vec<(String, String)>,
+        /// Time from sending the request to the response headers.
+        elapsed: Duration,
+        /// For non-2xx responses, the body (redacted, truncated). The
+        /// body of a successful response is the model's output stream
+        /// and is never captured.
+        error_body: Option<String>,
+    },
+    /// The transport failed before a response arrived.
+    Failed {
+        /// Request URL, credential query parameters masked.
+        url: String,
+        /// The error message, redacted.
+        error: String,
+        /// Time from sending the request to the failure.
+        elapsed: Duration,
+    },
+}
+
+/// Receives [`HttpLogEvent`]s. Implemented for any `Fn(&HttpLogEvent)`
+/// closure.
+pub trait HttpLogHook: Send + Sync {
+    /// Record one event.
+    fn log(&self, event: &HttpLogEvent);
+}
+
+impl<F> HttpLogHook for F
+where
+    F: Fn(&HttpLogEvent) + Send + Sync,
+{
+    fn log(&self, event: &HttpLogEvent) {
+        self(event)
+    }
+}
+
+/// Shared handle to an [`HttpLogHook`].
+pub type SharedHttpLogHook = Arc<dyn HttpLogHook>;
+
+/// A [`TransportImpl`] that reports redacted [`HttpLogEvent`]s around
+/// an inner [`Transport`]. See the module docs.
+pub struct LoggingTransport {
+    inner: Transport,
+    hook: SharedHttpLogHook,
+    redactor: Redactor,
+    max_body_bytes: usize,
+}
+
+impl LoggingTransport {
+    /// Log `inner`'s traffic to `hook` with the default [`Redactor`]
+    /// and error bodies truncated at 4 KiB.
+    pub fn new(inner: Transport, hook: SharedHttpLogHook) -> Self {
+        Self {
+            inner,
+            hook,
+            redactor: Redactor::new(),
+            max_body_bytes: 4096,
+        }
+    }
+
+    /// Replace the [`Redactor`].
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Truncate logged error bodies to `max` bytes.
+    pub fn with_max_body_bytes(mut self, max: usize) -> Self {
+        self.max_body_bytes = max;
+        self
+    }
+
+    async fn observe(
+        &self,
+        url: &str,
+        started: Instant,
+        result: Result<TransportResponse, Error>,
+    ) -> Result<TransportResponse, Error> {
+        let url = self.redactor.redact_url(url);
+        let elapsed = started.elapsed();
+        let mut response = match result {
+            Ok(response) => response,
+            Err(err) => {
+                self.hook.log(&HttpLogEvent::Failed {
+                    url,
+                    error: self.redactor.redact_text(&err.to_string()),
+                    elapsed,
+                });
+                return Err(err);
+            }
+        };
+        let headers = self.redactor.redact_headers(&response.headers);
+        if (200..300).contains(&response.status) {
+            self.hook.log(&HttpLogEvent::Response {
+                url,
+                status: response.status,
+                headers,
+                elapsed,
+                error_body: None,
+            });
+            return Ok(response);
+        }
+        // Error bodies are small envelopes the provider buffers anyway;
+        // read it here, log it, and hand the provider an identical body.
+        let status = response.status;
+        let mut bytes = Ok(Vec::new());
+        while let Some(chunk) = response.body.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    if let Ok(buf) = bytes.as_mut() {
+                        buf.extend_from_slice(&chunk);
+                    }
+                }
+                Err(err) => {
+                    bytes = Err(err);
+                    break;
+                }
+            }
+        }
+        let (error_body, body_result) = match bytes {
+            Ok(bytes) => {
+                let shown = &bytes[..bytes.len().min(self.max_body_bytes)];
+                let mut text = self.redactor.redact_text(&String::from_utf8_lossy(shown));
+                if bytes.len() > self.max_body_bytes {
+                    text.push('…');
+                }
+                (Some(text), Ok(Bytes::from(bytes)))
+            }
+            Err(err) => (None, Err(err)),
+        };
+        self.hook.log(&HttpLogEvent::Response {
+            url,
+            status,
+            headers,
+            elapsed,
+            error_body,
+        });
+        response.body = Box::pin(futures_util::stream::once(async move { body_result }));
+        Ok(response)
+    }
+}
+
+impl std::fmt::Debug for LoggingTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoggingTransport")
+            .field("redactor", &self.redactor)
+            .field("max_body_bytes", &self.max_body_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl TransportImpl for LoggingTransport {
+    async fn send(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        self.hook.log(&HttpLogEvent::Request {
+            method: Method::Post,
+            url: self.redactor.redact_url(&req.url),
+            headers: self.redactor.redact_headers(&req.headers),
+            body_bytes: Some(req.body.len() as u64),
+        });
+        let url = req.url.clone();
+        let started = Instant::now();
+        let result = self.inner.send(req).await;
+        self.observe(&url, started, result).await
+    }
+
+    async fn send_upload(&self, req: UploadRequest) -> Result<TransportResponse, Error> {
+        self.hook.log(&HttpLogEvent::Request {
+            method: req.method,
+            url: self.redactor.redact_url(&req.url),
+            headers: self.redactor.redact_headers(&req.headers),
+            body_bytes: req.content_length,
+        });
+        let url = req.url.clone();
+        let started = Instant::now();
+        let result = self.inner.send_upload(req).await;
+        self.observe(&url, started, result).await
+    }
+
+    async fn send_get(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        self.hook.log(&HttpLogEvent::Request {
+            method: Method::Get,
+            url: self.redactor.redact_url(&req.url),
+            headers: self.redactor.redact_headers(&req.headers),
+            body_bytes: None,
+        });
+        let url = req.url.clone();
+        let started = Instant::now();
+        let result = self.inner.send_get(req).await;
+        self.observe(&url, started, result).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn redacts_bearer_tokens_and_key_shapes() {
+        let r = Redactor::new();
+        assert_eq!(
+            r.redact_text("header was Bearer abc.def-123 ok"),
+            "header was Bearer [REDACTED] ok"
+        );
+        assert_eq!(
+            r.redact_text(
+                r#"{"error":"Incorrect API key provided: sk-proj-ABCDEFGHIJKLMNOPQRST."}"#
+            ),
+            r#"{"error":"Incorrect API key provided: [REDACTED]"}"#
+        );
+        assert_eq!(
+            r.redact_text("key AIzaSyA1234567890abcdefghij in use"),
+            "key [REDACTED] in use"
+        );
+        // Too short / not at a token boundary: left alone.
+        assert_eq!(r.redact_text("uses sk-learn"), "uses sk-learn");
+        assert_eq!(
+            r.redact_text("task-ABCDEFGHIJKLMNOPQRSTU"),
+            "task-ABCDEFGHIJKLMNOPQRSTU"
+        );
+    }
+
+    #[test]
+    fn redacts_headers_and_urls() {
+        let r = Redactor::new().with_header("X-Tenant-Secret");
+        let headers = r.redact_headers(&[
+            ("Authorization".into(), "Bearer abc".into()),
+            ("x-tenant-secret".into(), "s3cret".into()),
+            ("content-type".into(), "application/json".into()),
+        ]);
+        assert_eq!(headers[0].1, REDACTED);
+        assert_eq!(headers[1].1, REDACTED);
+        assert_eq!(headers[2].1, "application/json");
+        assert_eq!(
+            r.redact_url("https://x.googleapis.com/v1/m:gen?alt=sse&key=AIzaXYZ"),
+            "https://x.googleapis.com/v1/m:gen?alt=sse&key=[REDACTED]"
+        );
+    }
+
+    struct Canned(u16, &'static str);
+
+    #[async_trait]
+    impl TransportImpl for Canned {
+        async fn send(&self, _req: TransportRequest) -> Result<TransportResponse, Error> {
+            let body = self.1;
+            Ok(TransportResponse {
+                status: self.0,
+                headers: vec![("set-cookie".into(), "session=1".into())],
+                body: Box::pin(futures_util::stream::once(async move {
+                    Ok(Bytes::from_static(body.as_bytes()))
+                })),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn logs_redacted_summaries_and_preserves_error_body() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let transport = LoggingTransport::new(
+            Transport::new(Canned(
+                401,
+                r#"{"error":"bad key sk-ABCDEFGHIJKLMNOPQRSTUV"}"#,
+            )),
+            Arc::new(move |event: &HttpLogEvent| sink.lock().unwrap().push(event.clone())),
+        );
+        let response = transport
+            .send(TransportRequest {
+                url: "https://api.example.com/v1/responses".into(),
+                headers: vec![("Authorization".into(), "Bearer sk-live".into())],
+                body: b"{}".to_vec(),
+            })
+            .await
+            .unwrap();
+        // The provider still sees the original body.
+        let body = response.collect_body().await.unwrap();
+        assert!(String::from_utf8(body)
+            .unwrap()
+            .contains("sk-ABCDEFGHIJKLMNOPQRSTUV"));
+
+        let events = events.lock().unwrap();
+        let HttpLogEvent::Request {
+            headers,
+            body_bytes,
+            ..
+        } = &events[0]
+        else {
+            panic!("expected request event first");
+        };
+        assert_eq!(headers[0].1, REDACTED);
+        assert_eq!(*body_bytes, Some(2));
+        let HttpLogEvent::Response {
+            status,
+            headers,
+            error_body,
+            ..
+        } = &events[1]
+        else {
+            panic!("expected response event");
+        };
+        assert_eq!(*status, 401);
+        assert_eq!(headers[0].1, REDACTED);
+        assert_eq!(
+            error_body.as_deref(),
+            Some(r#"{"error":"bad key [REDACTED]"}"#)
+        );
+    }
+}
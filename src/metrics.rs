@@ -0,0 +1,378 @@
+//! Per-call latency and throughput metrics.
+//!
+//! [`MetricsProvider`] wraps any [`Provider`] and, once per call,
+//! hands a [`CallMetrics`] summary — total duration, time to first
+//! token, output token rate, usage, outcome — to a
+//! [`MetricsObserver`]. Feed those into Prometheus, StatsD, or
+//! whatever backs your dashboards; the crate takes no position on the
+//! metrics library.
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use platformed_llm::metrics::{CallMetrics, MetricsLayer};
+//!
+//! let observer = Arc::new(|m: &CallMetrics| {
+//!     histogram!("llm_ttft_seconds", "model" => m.model.clone())
+//!         .record(m.time_to_first_token.map_or(f64::NAN, |d| d.as_secs_f64()));
+//! });
+//! let provider = ProviderStack::new()
+//!     .layer(MetricsLayer::new(observer))
+//!     .service(openai);
+//! ```
+//!
+//! Apply it once through a [`crate::ProviderStack`] instead of
+//! instrumenting every call site. Place it *outside* a
+//! [`crate::RetryLayer`] to measure what callers experience, *inside*
+//! to measure each upstream attempt.
+//!
+//! # When the observer runs
+//!
+//! Exactly once per call, when the outcome is known: immediately if
+//! `generate` fails, otherwise when the response stream yields `Done`,
+//! yields an error, ends without `Done`, or is dropped unfinished. The
+//! observer runs inline on the task polling the stream, so keep it
+//! cheap — record and return.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::time::Instant;
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{
+    Capabilities, Error, FinishReason, Prompt, Provider, RawConfig, Response, StreamEvent, Usage,
+};
+
+/// How a call ended, from [`CallMetrics::outcome`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallOutcome {
+    /// The stream reached `Done`.
+    Success,
+    /// `generate` or the stream failed. The variant name is in
+    /// [`CallMetrics::error_kind`].
+    Error,
+    /// The stream ended without `Done`, or the caller dropped it.
+    Cancelled,
+}
+
+/// Measurements for one call, passed to [`MetricsObserver::observe`].
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct CallMetrics {
+    /// Model the call was made with.
+    pub model: String,
+    /// Tenant the call was made for
+    /// ([`crate::ConfigBuilder::tenant`]).
+    pub tenant: Option<uuid::Uuid>,
+    /// The call's [`crate::RawConfig::request_id`]. An identifier for
+    /// logs and exemplars, not a metric label.
+    pub request_id: Option<String>,
+    /// Backend that served the call, when a router / fallback wrapper
+    /// underneath recorded one ([`crate::ResponseMetadata::served_by`]).
+    pub served_by: Option<String>,
+    /// How the call ended.
+    pub outcome: CallOutcome,
+    /// [`Error::kind`] of the failure when `outcome` is
+    /// [`CallOutcome::Error`].
+    pub error_kind: Option<&'static str>,
+    /// From the `generate` call to the outcome.
+    pub duration: Duration,
+    /// From the `generate` call to the first stream event. `None` when
+    /// no event arrived.
+    pub time_to_first_token: Option<Duration>,
+    /// Output tokens per second of streaming, measured from the first
+    /// event to `Done`. `None` without usage, or when the whole
+    /// response arrived in one instant.
+    pub output_tokens_per_second: Option<f64>,
+    /// Final token accounting, when the stream reached `Done`.
+    pub usage: Option<Usage>,
+    /// Why generation stopped, when the stream reached `Done`.
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// Receives a [`CallMetrics`] per call. Implemented for any
+/// `Fn(&CallMetrics)` closure.
+pub trait MetricsObserver: Send + Sync {
+    /// Record one call's metrics.
+    fn observe(&self, metrics: &CallMetrics);
+}
+
+impl<F> MetricsObserver for F
+where
+    F: Fn(&CallMetrics) + Send + Sync,
+{
+    fn observe(&self, metrics: &CallMetrics) {
+        self(metrics)
+    }
+}
+
+/// Shared handle to a [`MetricsObserver`].
+pub type SharedMetricsObserver = Arc<dyn MetricsObserver>;
+
+/// A [`Provider`] wrapper reporting [`CallMetrics`] for every call. See
+/// the module docs.
+#[derive(Clone)]
+pub struct MetricsProvider {
+    inner: SharedProvider,
+    observer: SharedMetricsObserver,
+}
+
+impl MetricsProvider {
+    /// Report `inner`'s calls to `observer`.
+    pub fn new(inner: impl Provider, observer: SharedMetricsObserver) -> Self {
+        Self::from_shared(Arc::new(inner), observer)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, observer: SharedMetricsObserver) -> Self {
+        Self { inner, observer }
+    }
+}
+
+impl std::fmt::Debug for MetricsProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsProvider").finish_non_exhaustive()
+    }
+}
+
+/// In-flight measurement for one call. Reports on drop if nothing else
+/// has, so a dropped stream still yields a `Cancelled` record.
+struct Recorder {
+    observer: SharedMetricsObserver,
+    started: Instant,
+    first_event: Option<Instant>,
+    metrics: Option<CallMetrics>,
+}
+
+impl Recorder {
+    fn on_item(&mut self, item: &Result<StreamEvent, Error>) {
+        let now = Instant::now();
+        let first = *self.first_event.get_or_insert(now);
+        match item {
+            Ok(StreamEvent::Done {
+                finish_reason,
+                usage,
+            }) => {
+                let streaming = now.saturating_duration_since(first).as_secs_f64();
+                self.finish(CallOutcome::Success, None, |m| {
+                    m.output_tokens_per_second = (streaming > 0.0 && usage.output_tokens > 0)
+                        .then(|| f64::from(usage.output_tokens) / streaming);
+                    m.usage = Some(usage.clone());
+                    m.finish_reason = Some(finish_reason.clone());
+                });
+            }
+            Ok(_) => {}
+            Err(err) => self.finish(CallOutcome::Error, Some(err.kind()), |_| {}),
+        }
+    }
+
+    fn finish(
+        &mut self,
+        outcome: CallOutcome,
+        error_kind: Option<&'static str>,
+        fill: impl FnOnce(&mut CallMetrics),
+    ) {
+        let Some(mut metrics) = self.metrics.take() else {
+            return;
+        };
+        metrics.outcome = outcome;
+        metrics.error_kind = error_kind;
+        metrics.duration = self.started.elapsed();
+        metrics.time_to_first_token = self
+            .first_event
+            .map(|first| first.saturating_duration_since(self.started));
+        fill(&mut metrics);
+        self.observer.observe(&metrics);
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.finish(CallOutcome::Cancelled, None, |_| {});
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for MetricsProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let mut recorder = Recorder {
+            observer: self.observer.clone(),
+            started: Instant::now(),
+            first_event: None,
+            metrics: Some(CallMetrics {
+                model: config.model.clone(),
+                tenant: config.tenant,
+                request_id: config.request_id.clone(),
+                served_by: None,
+                outcome: CallOutcome::Cancelled,
+                error_kind: None,
+                duration: Duration::ZERO,
+                time_to_first_token: None,
+                output_tokens_per_second: None,
+                usage: None,
+                finish_reason: None,
+            }),
+        };
+        match self.inner.generate(prompt, config).await {
+            Ok(response) => {
+                if let Some(metrics) = recorder.metrics.as_mut() {
+                    metrics.served_by = response.metadata().served_by.clone();
+                }
+                Ok(response
+                    .map_stream(move |stream| stream.inspect(move |item| recorder.on_item(item))))
+            }
+            Err(err) => {
+                recorder.finish(CallOutcome::Error, Some(err.kind()), |_| {});
+                Err(err)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in a [`MetricsProvider`]
+/// reporting to one shared observer.
+#[derive(Clone)]
+pub struct MetricsLayer {
+    observer: SharedMetricsObserver,
+}
+
+impl MetricsLayer {
+    /// A layer reporting every wrapped provider's calls to `observer`.
+    pub fn new(observer: SharedMetricsObserver) -> Self {
+        Self { observer }
+    }
+}
+
+impl std::fmt::Debug for MetricsLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsLayer").finish_non_exhaustive()
+    }
+}
+
+impl ProviderLayer for MetricsLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(MetricsProvider::from_shared(inner, self.observer.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::{generate, Config};
+    use std::sync::Mutex;
+
+    fn collector() -> (SharedMetricsObserver, Arc<Mutex<Vec<CallMetrics>>>) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let observer: SharedMetricsObserver =
+            Arc::new(move |m: &CallMetrics| sink.lock().unwrap().push(m.clone()));
+        (observer, seen)
+    }
+
+    /// Waits `delay` before delegating, to give TTFT something to measure.
+    struct Slow(Duration, MockProvider);
+
+    #[async_trait::async_trait]
+    impl Provider for Slow {
+        async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+            tokio::time::sleep(self.0).await;
+            self.1.generate(prompt, config).await
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_success_with_ttft_and_usage() {
+        let (observer, seen) = collector();
+        let provider = MetricsProvider::new(
+            Slow(
+                Duration::from_millis(250),
+                MockProvider::with_text("one two three"),
+            ),
+            observer,
+        );
+        generate(&provider, &Prompt::user("x"), &Config::builder("m").build())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        let m = &seen[0];
+        assert_eq!(m.outcome, CallOutcome::Success);
+        assert_eq!(m.model, "m");
+        assert_eq!(m.time_to_first_token, Some(Duration::from_millis(250)));
+        assert!(m.duration >= Duration::from_millis(250));
+        assert_eq!(m.finish_reason, Some(FinishReason::Stop));
+        assert!(m.usage.is_some());
+    }
+
+    #[tokio::test]
+    async fn reports_errors_once() {
+        let (observer, seen) = collector();
+        let provider = MetricsProvider::new(
+            MockProvider::builder()
+                .fail(Error::rate_limit(None, "slow"))
+                .reply(
+                    MockResponse::text("x")
+                        .with_stream_error(Error::provider_with_status("Mock", 500, "boom")),
+                )
+                .build(),
+            observer,
+        );
+        let tenant = uuid::Uuid::from_u128(7);
+        let config = Config::builder("m").tenant(tenant).build();
+        assert!(generate(&provider, &Prompt::user("x"), &config)
+            .await
+            .is_err());
+        let response = generate(&provider, &Prompt::user("x"), &config)
+            .await
+            .unwrap();
+        assert!(response.text().await.is_err());
+
+        let kinds: Vec<_> = seen
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|m| (m.outcome, m.error_kind, m.tenant))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (CallOutcome::Error, Some("rate_limit"), Some(tenant)),
+                (CallOutcome::Error, Some("provider"), Some(tenant)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_reports_cancelled() {
+        let (observer, seen) = collector();
+        let provider = crate::ProviderStack::new()
+            .layer(MetricsLayer::new(observer))
+            .service(MockProvider::with_text("a b c"));
+        let response = generate(
+            &*provider,
+            &Prompt::user("x"),
+            &Config::builder("m").build(),
+        )
+        .await
+        .unwrap();
+        drop(response);
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0].outcome, CallOutcome::Cancelled);
+    }
+}
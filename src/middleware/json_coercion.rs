@@ -19,7 +19,7 @@ use std::pin::Pin;
 use futures_util::stream::{Stream, StreamExt};
 
 use crate::types::{FinishReason, Function, PartKind, RawConfig, ResponseFormat, Tool, ToolChoice};
-use crate::{Capabilities, Error, Prompt, Response, StreamEvent};
+use crate::{Capabilities, Error, Prompt, StreamEvent};
 
 use super::{Middleware, ResponseTransform};
 
@@ -162,10 +162,7 @@ impl Middleware for JsonCoercionMiddleware {
         };
 
         let transform: ResponseTransform = Box::new(move |response| {
-            Response::from_stream(rewrite_synth_tool_stream(
-                response.stream(),
-                synth_tool_name,
-            ))
+            response.map_stream(|stream| rewrite_synth_tool_stream(stream, synth_tool_name))
         });
         Ok(Some(transform))
     }
@@ -299,6 +296,7 @@ fn rewrite_synth_tool_stream(
                         Some(Some(mapped)) => Some(Ok(StreamEvent::PartEnd { index: *mapped })),
                         _ => None,
                     },
+                    safety @ StreamEvent::Safety { .. } => Some(Ok(safety)),
                     StreamEvent::Done {
                         finish_reason,
                         usage,
@@ -348,7 +346,7 @@ mod tests {
     use super::*;
     use crate::middleware::generate;
     use crate::types::{FunctionCall, PartKind, Usage};
-    use crate::{AssistantPart, Capabilities, Config, ConfigBuilder};
+    use crate::{AssistantPart, Capabilities, Config, ConfigBuilder, Response};
     use async_trait::async_trait;
     use std::sync::{Arc, Mutex};
 
@@ -876,7 +874,10 @@ mod tests {
                     content: vec![UserPart::Text(
                         r#"{"temp":22,"condition":"sunny"}"#.to_string(),
                     )],
+                    is_error: false,
                 }],
+                name: None,
+                metadata: Default::default(),
             },
         );
 
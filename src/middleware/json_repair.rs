@@ -0,0 +1,525 @@
+//! Lenient repair of malformed tool-call arguments.
+//!
+//! Models occasionally emit tool arguments that are *almost* JSON:
+//! trailing commas, unquoted or single-quoted keys, Python literals, a
+//! markdown fence around the object — and, most often, an object cut
+//! off mid-way because the turn hit `max_tokens`. Strict parsing then
+//! fails and the whole turn is unusable.
+//! [`JsonRepairMiddleware`] rewrites such arguments into valid JSON
+//! before they reach the caller and keeps what the model actually sent
+//! in [`crate::FunctionCall::original_arguments`].
+//!
+//! Opt-in: add it to the chain with
+//! [`crate::ConfigBuilder::with_middleware`]. Combined with
+//! [`super::ToolArgumentValidationMiddleware`], list the validator
+//! **first** — response transforms unwind in reverse, so the repair
+//! then runs before validation sees the arguments. [`repair_json`] is
+//! the same repair as a plain function, for buffered responses, and
+//! [`parse_partial_json`] applies it to a still-streaming prefix.
+//!
+//! Repair is conservative: it completes and normalizes what is there,
+//! never invents values. A truncated string is closed, open containers
+//! are closed, and a trailing key without a value (or a half-written
+//! number / literal) is dropped.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::pin::Pin;
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::types::{PartKind, PartUpdate, RawConfig};
+use crate::{Capabilities, Error, Prompt, StreamEvent};
+
+use super::{Middleware, ResponseTransform};
+
+/// Buffers each tool call's argument deltas and, at the end of the
+/// call, replaces malformed JSON with its [`repair_json`] rewrite,
+/// attaching the original via [`PartUpdate::OriginalArguments`].
+/// Well-formed arguments — and ones beyond repair — pass through
+/// verbatim. A call left open when the turn ends (the `max_tokens`
+/// case) is repaired before `Done`.
+///
+/// Arguments are delivered as a single delta per call, so callers
+/// rendering partial arguments live lose that incremental view. See
+/// the [module docs](self).
+#[derive(Debug, Default)]
+pub struct JsonRepairMiddleware;
+
+impl Middleware for JsonRepairMiddleware {
+    fn name(&self) -> &str {
+        "json_repair"
+    }
+
+    fn apply<'a>(
+        &self,
+        _prompt: &mut Cow<'a, Prompt>,
+        config: &mut Cow<'a, RawConfig>,
+        _capabilities: &Capabilities,
+    ) -> Result<Option<ResponseTransform>, Error> {
+        if config.tools.as_ref().is_none_or(|tools| tools.is_empty()) {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(|response| {
+            response.map_stream(repair_tool_calls)
+        })))
+    }
+}
+
+/// A valid-JSON rewrite of `input`, or `None` when it is beyond repair.
+/// Already-valid input comes back unchanged; an empty input (a
+/// parameterless call) becomes `{}`.
+pub fn repair_json(input: &str) -> Option<String> {
+    if serde_json::from_str::<serde::de::IgnoredAny>(input).is_ok() {
+        return Some(input.to_string());
+    }
+    let body = strip_fence(input.trim());
+    if body.is_empty() {
+        return Some("{}".to_string());
+    }
+    let mut parser = Lenient {
+        chars: body.chars().collect(),
+        pos: 0,
+    };
+    let out = parser.value().ok()??;
+    parser.skip_trivia();
+    if parser.pos < parser.chars.len() {
+        return None;
+    }
+    debug_assert!(serde_json::from_str::<serde::de::IgnoredAny>(&out).is_ok());
+    Some(out)
+}
+
+/// Parse a possibly-incomplete JSON document — a tool call's arguments
+/// mid-stream — into the value it holds so far: open strings and
+/// containers are closed and a dangling key is left out, so
+/// `{"location": "Os` reads as `{"location": "Os"}`. `None` until the
+/// prefix holds a usable value, or if it is malformed beyond repair.
+///
+/// Each call re-parses the whole prefix; that is cheap at tool-argument
+/// sizes.
+pub fn parse_partial_json(prefix: &str) -> Option<serde_json::Value> {
+    if prefix.trim().is_empty() {
+        return None;
+    }
+    serde_json::from_str(&repair_json(prefix)?).ok()
+}
+
+/// Drop a surrounding markdown code fence (```` ```json … ``` ````),
+/// including an unterminated one.
+fn strip_fence(input: &str) -> &str {
+    let Some(rest) = input.strip_prefix("```") else {
+        return input;
+    };
+    let rest = rest.split_once('\n').map_or("", |(_lang, body)| body);
+    rest.trim_end().strip_suffix("```").unwrap_or(rest).trim()
+}
+
+/// Recursive-descent parser that re-emits what it reads as strict JSON.
+/// `Ok(None)` means the input ended before the value became usable;
+/// `Err` means garbage.
+struct Lenient {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+type Parsed = Result<Option<String>, ()>;
+
+impl Lenient {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    /// Whitespace and `//` / `/* */` comments.
+    fn skip_trivia(&mut self) {
+        loop {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.pos += 1;
+            }
+            match (self.peek(), self.chars.get(self.pos + 1)) {
+                (Some('/'), Some('/')) => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.pos += 2;
+                    while self.peek().is_some() && !self.chars[self.pos..].starts_with(&['*', '/'])
+                    {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.chars.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn value(&mut self) -> Parsed {
+        self.skip_trivia();
+        match self.peek() {
+            None => Ok(None),
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some(quote @ ('"' | '\'')) => {
+                let (s, _complete) = self.string(quote);
+                Ok(Some(json_string(&s)))
+            }
+            Some(c) if c == '-' || c == '+' || c == '.' || c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_alphabetic() => self.literal(),
+            Some(_) => Err(()),
+        }
+    }
+
+    fn object(&mut self) -> Parsed {
+        self.pos += 1;
+        let mut members = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => break,
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let key = match self.peek() {
+                Some(quote @ ('"' | '\'')) => match self.string(quote) {
+                    (key, true) => key,
+                    (_, false) => break,
+                },
+                _ => {
+                    let key = self
+                        .take_while(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '-');
+                    if key.is_empty() {
+                        return Err(());
+                    }
+                    key
+                }
+            };
+            self.skip_trivia();
+            match self.peek() {
+                None => break,
+                Some(':' | '=') => self.pos += 1,
+                Some(_) => return Err(()),
+            }
+            let Some(value) = self.value()? else {
+                break;
+            };
+            members.push(format!("{}:{value}", json_string(&key)));
+        }
+        Ok(Some(format!("{{{}}}", members.join(","))))
+    }
+
+    fn array(&mut self) -> Parsed {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => break,
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let Some(item) = self.value()? else {
+                break;
+            };
+            items.push(item);
+        }
+        Ok(Some(format!("[{}]", items.join(","))))
+    }
+
+    /// The string's content and whether its closing quote was seen.
+    fn string(&mut self, quote: char) -> (String, bool) {
+        self.pos += 1;
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                c if c == quote => return (out, true),
+                '\\' => {
+                    let Some(escaped) = self.peek() else { break };
+                    self.pos += 1;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => {
+                            let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                            match u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .filter(|_| hex.len() == 4)
+                            {
+                                Some(code) => {
+                                    self.pos += 4;
+                                    // Lone surrogates can't be represented; drop them.
+                                    out.extend(char::from_u32(code));
+                                }
+                                None => break,
+                            }
+                        }
+                        other => out.push(other),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        (out, false)
+    }
+
+    fn number(&mut self) -> Parsed {
+        let text =
+            self.take_while(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'));
+        let text = text.strip_prefix('+').unwrap_or(&text);
+        let normalized = match text.strip_prefix('.') {
+            Some(fraction) => format!("0.{fraction}"),
+            None => text.replace("-.", "-0."),
+        };
+        match serde_json::from_str::<serde_json::Number>(&normalized) {
+            Ok(n) => Ok(Some(n.to_string())),
+            // `12.` / `1e` at the very end: cut off mid-number.
+            Err(_) if self.at_end() => Ok(None),
+            Err(_) => Err(()),
+        }
+    }
+
+    fn literal(&mut self) -> Parsed {
+        let word = self.take_while(char::is_alphabetic);
+        let json = match word.as_str() {
+            "true" | "True" => "true",
+            "false" | "False" => "false",
+            "null" | "None" => "null",
+            _ if self.at_end()
+                && ["true", "false", "null", "True", "False", "None"]
+                    .iter()
+                    .any(|literal| literal.starts_with(word.as_str())) =>
+            {
+                return Ok(None)
+            }
+            _ => return Err(()),
+        };
+        Ok(Some(json.to_string()))
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&accept) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    serde_json::Value::from(s).to_string()
+}
+
+/// The events that finish a buffered call: its arguments (repaired
+/// when needed) and the original when they changed.
+fn flush(index: u32, arguments: String) -> Vec<Result<StreamEvent, Error>> {
+    let mut events = Vec::with_capacity(2);
+    match repair_json(&arguments) {
+        Some(repaired) if repaired != arguments => {
+            events.push(Ok(StreamEvent::Delta {
+                index,
+                delta: repaired,
+            }));
+            events.push(Ok(StreamEvent::PartUpdate {
+                index,
+                update: PartUpdate::OriginalArguments(arguments),
+            }));
+        }
+        _ if arguments.is_empty() => {}
+        _ => events.push(Ok(StreamEvent::Delta {
+            index,
+            delta: arguments,
+        })),
+    }
+    events
+}
+
+fn repair_tool_calls(
+    inner: Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>>,
+) -> Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>> {
+    // Open tool calls by part index, with their buffered arguments.
+    let mut pending: HashMap<u32, String> = HashMap::new();
+    Box::pin(
+        inner
+            .map(move |event| {
+                let events = match event {
+                    Ok(StreamEvent::PartStart {
+                        index,
+                        kind: kind @ PartKind::ToolCall { .. },
+                    }) => {
+                        pending.insert(index, String::new());
+                        vec![Ok(StreamEvent::PartStart { index, kind })]
+                    }
+                    Ok(StreamEvent::Delta { index, delta }) if pending.contains_key(&index) => {
+                        pending
+                            .get_mut(&index)
+                            .expect("checked above")
+                            .push_str(&delta);
+                        Vec::new()
+                    }
+                    Ok(StreamEvent::PartEnd { index }) if pending.contains_key(&index) => {
+                        let arguments = pending.remove(&index).expect("checked above");
+                        let mut events = flush(index, arguments);
+                        events.push(Ok(StreamEvent::PartEnd { index }));
+                        events
+                    }
+                    Ok(done @ StreamEvent::Done { .. }) => {
+                        let mut open: Vec<_> = pending.drain().collect();
+                        open.sort_by_key(|(index, _)| *index);
+                        let mut events: Vec<_> = open
+                            .into_iter()
+                            .flat_map(|(index, arguments)| flush(index, arguments))
+                            .collect();
+                        events.push(Ok(done));
+                        events
+                    }
+                    other => vec![other],
+                };
+                stream::iter(events)
+            })
+            .flatten(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AssistantPart, FinishReason, Function, Tool, Usage};
+    use crate::{Config, Response};
+
+    #[test]
+    fn repairs_common_near_json() {
+        let cases = [
+            (r#"{"a": 1, "b": [1, 2,],}"#, r#"{"a":1,"b":[1,2]}"#),
+            (r#"{city: 'Oslo', days: +3}"#, r#"{"city":"Oslo","days":3}"#),
+            (r#"{"ok": True, "x": None}"#, r#"{"ok":true,"x":null}"#),
+            ("```json\n{\"a\": 1}\n```", r#"{"a":1}"#),
+            (r#"{"a": 1 /* note */, // more"#, r#"{"a":1}"#),
+            ("", "{}"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(repair_json(input).as_deref(), Some(expected), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn closes_truncated_input_without_inventing_values() {
+        let cases = [
+            (r#"{"q": "weather in Os"#, r#"{"q":"weather in Os"}"#),
+            (r#"{"a": [1, {"b": 2"#, r#"{"a":[1,{"b":2}]}"#),
+            (r#"{"a": 1, "b": "#, r#"{"a":1}"#),
+            (r#"{"a": 1, "b"#, r#"{"a":1}"#),
+            (r#"{"a": 1, "b": 2."#, r#"{"a":1}"#),
+            (r#"{"a": 1, "b": tr"#, r#"{"a":1}"#),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(repair_json(input).as_deref(), Some(expected), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn valid_json_is_untouched_and_garbage_is_refused() {
+        let valid = r#"{ "a" : [1, 2] }"#;
+        assert_eq!(repair_json(valid).as_deref(), Some(valid));
+        assert_eq!(repair_json("not json at all"), None);
+        assert_eq!(repair_json(r#"{"a": 1} trailing"#), None);
+    }
+
+    fn tool_call(deltas: &[&str], close: bool) -> Response {
+        let mut events = vec![Ok(StreamEvent::PartStart {
+            index: 0,
+            kind: PartKind::ToolCall {
+                call_id: "c1".to_string(),
+                name: "search".to_string(),
+            },
+        })];
+        events.extend(deltas.iter().map(|d| {
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: d.to_string(),
+            })
+        }));
+        if close {
+            events.push(Ok(StreamEvent::PartEnd { index: 0 }));
+        }
+        events.push(Ok(StreamEvent::Done {
+            finish_reason: if close {
+                FinishReason::ToolCalls
+            } else {
+                FinishReason::Length
+            },
+            usage: Usage::default(),
+        }));
+        Response::from_stream(stream::iter(events))
+    }
+
+    fn transform() -> ResponseTransform {
+        let prompt = Prompt::user("find it");
+        let config = Config::builder("m")
+            .tools(vec![Tool::Function(Function {
+                name: "search".to_string(),
+                description: None,
+                parameters: Cow::Owned(
+                    serde_json::value::RawValue::from_string("{}".to_string()).unwrap(),
+                ),
+            })])
+            .build();
+        let mut prompt = Cow::Borrowed(&prompt);
+        let mut raw = Cow::Borrowed(config.raw());
+        JsonRepairMiddleware
+            .apply(&mut prompt, &mut raw, &Capabilities::default())
+            .unwrap()
+            .expect("tools get a transform")
+    }
+
+    async fn only_call(response: Response) -> crate::FunctionCall {
+        let complete = response.buffer().await.unwrap();
+        match complete.content.as_slice() {
+            [AssistantPart::ToolCall(call)] => call.clone(),
+            other => panic!("expected one tool call, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn truncated_call_is_repaired_and_original_kept() {
+        let call = only_call(transform()(tool_call(
+            &[r#"{"q": "rust", "#, r#""limit": 1"#],
+            false,
+        )))
+        .await;
+        assert_eq!(call.arguments, r#"{"q":"rust","limit":1}"#);
+        assert_eq!(
+            call.original_arguments.as_deref(),
+            Some(r#"{"q": "rust", "limit": 1"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn well_formed_call_passes_through() {
+        let call = only_call(transform()(tool_call(&[r#"{"q": "#, r#""rust"}"#], true))).await;
+        assert_eq!(call.arguments, r#"{"q": "rust"}"#);
+        assert_eq!(call.original_arguments, None);
+    }
+}
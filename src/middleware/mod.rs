@@ -6,15 +6,24 @@
 //! expects and emits stream events back. Anything that bridges a
 //! *gap* between the caller's intent and what the model natively
 //! supports — JSON coercion via tool-use, schema-vs-tools conflict
-//! reconciliation, future retry/cache/redact behaviors — belongs here
-//! as a [`Middleware`].
+//! reconciliation, redaction — belongs here as a [`Middleware`].
+//! Concerns that wrap the *whole call* rather than rewrite the request
+//! (retries, caching, metrics) are [`crate::layer::ProviderLayer`]s
+//! instead.
 //!
 //! Each concrete middleware lives in its own submodule (e.g.
 //! [`crate::middleware::json_coercion`] for the JSON-via-tool-coercion
-//! polyfill); this module owns the trait, the [`generate`] entry
-//! point, the [`crate::middleware::validate`] post-middleware gate,
-//! and the [`crate::middleware::default_middleware`] derivation from
-//! a capability set.
+//! polyfill, [`crate::middleware::tool_arguments`] for opt-in tool-call
+//! argument validation, [`crate::middleware::json_repair`] for opt-in
+//! repair of malformed arguments, [`crate::middleware::sanitize`] for
+//! opt-in dropping of parameters the model rejects,
+//! [`crate::middleware::stop_guard`] for client-side stop phrases and
+//! patterns, [`crate::middleware::redaction`] for masking PII in
+//! outgoing prompts); this module owns
+//! the trait, the [`generate`] entry point, the
+//! [`crate::middleware::validate`] post-middleware gate, and the
+//! [`crate::middleware::default_middleware`] derivation from a
+//! capability set.
 //!
 //! Pipeline (see [`generate`]):
 //! 1. Borrow the caller's `prompt` and `config.raw()` as `Cow::Borrowed`.
@@ -27,7 +36,13 @@
 //! 3. Run [`crate::middleware::validate`] against the *post-middleware*
 //!    config. If a gap remains, error fast — the provider never sees
 //!    an unsupported request.
-//! 4. Call the provider with `&Prompt` and `&RawConfig` (Cow Deref).
+//! 4. Generate a [`RawConfig::request_id`] if the caller set none
+//!    (cloning the config for it), then call the provider with
+//!    `&Prompt` and `&RawConfig` (Cow Deref) inside a
+//!    `llm.generate` tracing span carrying the provider, model and
+//!    ID. With the `tracing-spans` feature the span is `info`-level
+//!    and stays open over the response stream, recording token
+//!    counts and the finish reason (or error) when the turn ends.
 //! 5. Apply response transforms in **reverse** order (onion: the last
 //!    middleware to touch the request is the first to unwrap the
 //!    response).
@@ -35,13 +50,26 @@
 use std::borrow::Cow;
 use std::sync::Arc;
 
+use futures_util::StreamExt;
+use tracing::Instrument;
+
 use crate::provider::Provider;
 use crate::types::{RawConfig, ResponseFormat, Tool};
 use crate::{Capabilities, Error, Prompt, Response};
 
 pub mod json_coercion;
+pub mod json_repair;
+pub mod redaction;
+pub mod sanitize;
+pub mod stop_guard;
+pub mod tool_arguments;
 
 pub use json_coercion::JsonCoercionMiddleware;
+pub use json_repair::JsonRepairMiddleware;
+pub use redaction::{PiiKind, PiiRedactionMiddleware};
+pub use sanitize::{ParameterAdjustment, ParameterSanitizerMiddleware};
+pub use stop_guard::StopGuardMiddleware;
+pub use tool_arguments::ToolArgumentValidationMiddleware;
 
 /// A response-stream wrapper produced by a middleware during request
 /// rewriting. Captures any per-request state (e.g. the synthetic tool
@@ -104,8 +132,11 @@ pub fn default_middleware(caps: &Capabilities) -> Vec<Arc<dyn Middleware>> {
 /// offending field passes through silently.
 ///
 /// Returns `Err(Error::Config)` with a precise message when a gap
-/// remains.
+/// remains, or when [`RawConfig::validate`] finds a value the target
+/// API would reject.
 pub fn validate(config: &RawConfig, caps: &Capabilities) -> Result<(), Error> {
+    config.validate()?;
+
     // Vertex rejects *controlled generation of any form* combined with
     // function calling on the restricted Gemini families — the wire
     // error is literally "Function calling with a response mime type:
@@ -216,7 +247,7 @@ pub fn validate_prompt(prompt: &Prompt) -> Result<(), Error> {
     };
 
     for (i, item) in items.iter().enumerate() {
-        let InputItem::Assistant { content } = item else {
+        let InputItem::Assistant { content, .. } = item else {
             continue;
         };
         let mut call_ids: HashMap<&str, usize> = HashMap::new();
@@ -231,9 +262,9 @@ pub fn validate_prompt(prompt: &Prompt) -> Result<(), Error> {
         let paired = items
             .iter()
             .skip(i + 1)
-            .find(|it| !matches!(it, InputItem::System(_)));
+            .find(|it| !matches!(it, InputItem::System(_) | InputItem::Developer(_)));
         let mut result_ids: HashMap<&str, usize> = HashMap::new();
-        if let Some(InputItem::User { content }) = paired {
+        if let Some(InputItem::User { content, .. }) = paired {
             for part in content {
                 if let UserPart::ToolResult { call_id, .. } = part {
                     *result_ids.entry(call_id.as_str()).or_default() += 1;
@@ -260,6 +291,24 @@ pub fn validate_prompt(prompt: &Prompt) -> Result<(), Error> {
 /// capabilities, calls the underlying provider, and unwraps the
 /// response stream in reverse middleware order.
 ///
+/// Every call gets a [`RawConfig::request_id`] — the caller's, or a
+/// generated one — which is reported as
+/// [`crate::ResponseMetadata::request_id`] and attached to the
+/// errors from provider responses the call returns or streams (see
+/// [`Error::provider_details`]), as
+/// [`crate::ProviderErrorDetails::client_request_id`]. Client-side
+/// errors (timeouts, invalid prompts, …) carry no details and come
+/// back unchanged: to correlate those, pass your own ID, which you
+/// then already hold.
+///
+/// The call runs in an `llm.generate` [`tracing`] span with
+/// `provider`, `model` and `request_id` fields. Enable the
+/// `tracing-spans` feature to have it at `info` level and kept open
+/// over the response stream, with `input_tokens`, `output_tokens` and
+/// `finish_reason` (or `error`) recorded once the turn ends — so calls
+/// show up in an existing `tracing-subscriber` setup without a
+/// wrapper provider.
+///
 /// `provider` is any `&dyn Provider`. Calling
 /// [`crate::Provider::generate`] directly bypasses middleware — use
 /// that only if you've already run the pipeline yourself or you know
@@ -295,15 +344,147 @@ pub async fn generate(
     validate(&raw_cow, &capabilities)?;
     validate_prompt(&prompt_cow)?;
 
-    let response = provider.generate(&prompt_cow, &raw_cow).await?;
+    let request_id = match &raw_cow.request_id {
+        Some(id) => id.clone(),
+        None => {
+            let id = uuid::Uuid::new_v4().simple().to_string();
+            raw_cow.to_mut().request_id = Some(id.clone());
+            id
+        }
+    };
+    let span = call_span(provider, &raw_cow, &request_id);
+
+    let started = crate::response::request_start();
+    let call = provider
+        .generate(&prompt_cow, &raw_cow)
+        .instrument(span.clone());
+    let call = async {
+        match raw_cow.idle_timeout {
+            Some(idle) => crate::timeout::with_idle_timeout(idle, call).await,
+            None => call.await,
+        }
+    };
+    let response = match raw_cow.timeout {
+        Some(timeout) => crate::timeout::with_deadline(timeout, call).await,
+        None => call.await,
+    }
+    .map_err(|e| {
+        span.record("error", tracing::field::display(&e));
+        e.with_client_request_id(&request_id)
+    })?
+    .started_at(started);
 
-    let response = response_transforms
+    let mut response = response_transforms
         .into_iter()
         .rev()
         .fold(response, |r, transform| transform(r));
+    response.metadata_mut().request_id = Some(request_id.clone());
+    let response = response.map_stream(move |stream| {
+        stream.map(move |event| event.map_err(|e| e.with_client_request_id(&request_id)))
+    });
+    #[cfg(feature = "tracing-spans")]
+    let response = response.map_stream(|stream| trace_stream(stream, span));
     Ok(response)
 }
 
+/// Run [`generate`] for each `(prompt, config)` request, at most
+/// `concurrency` at a time (at least one), and buffer each response.
+/// Returns one result per request, in input order; a failed request
+/// doesn't affect the others.
+///
+/// For evaluation and enrichment jobs. Pair one config with many
+/// prompts via `prompts.iter().map(|p| (p, &config))`. Combine with a
+/// [`crate::RetryLayer`] on `provider` to retry items individually.
+/// Only the `concurrency` requests in flight are pulled from
+/// `requests` at once, so it may be a lazy iterator over a large
+/// dataset — but every result is held until all finish.
+pub async fn generate_many<I, P, C>(
+    provider: &dyn Provider,
+    requests: I,
+    concurrency: usize,
+) -> Vec<Result<crate::CompleteResponse, Error>>
+where
+    I: IntoIterator<Item = (P, C)>,
+    P: std::borrow::Borrow<Prompt>,
+    C: std::borrow::Borrow<crate::Config>,
+{
+    let mut results: Vec<_> = futures_util::stream::iter(requests.into_iter().enumerate())
+        .map(|(i, (prompt, config))| async move {
+            let result = match generate(provider, prompt.borrow(), config.borrow()).await {
+                Ok(response) => response.buffer().await,
+                Err(e) => Err(e),
+            };
+            (i, result)
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_unstable_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// The span a [`generate`] call runs in. With the `tracing-spans`
+/// feature it's an `info` span that also covers the response stream
+/// and records the outcome (see [`trace_stream`]); without it, a
+/// `debug` span around the provider call only.
+#[cfg(feature = "tracing-spans")]
+fn call_span(provider: &dyn Provider, config: &RawConfig, request_id: &str) -> tracing::Span {
+    use tracing::field::Empty;
+    tracing::info_span!(
+        "llm.generate",
+        provider = provider.name(),
+        model = %config.model,
+        request_id,
+        input_tokens = Empty,
+        output_tokens = Empty,
+        finish_reason = Empty,
+        error = Empty,
+    )
+}
+
+#[cfg(not(feature = "tracing-spans"))]
+fn call_span(provider: &dyn Provider, config: &RawConfig, request_id: &str) -> tracing::Span {
+    tracing::debug_span!(
+        "llm.generate",
+        provider = provider.name(),
+        model = %config.model,
+        request_id,
+    )
+}
+
+/// Poll `stream` inside `span`, so whatever the providers log while
+/// streaming nests under the call, and record the turn's usage and
+/// finish reason — or its error — on the span. The span closes when
+/// the stream is dropped.
+#[cfg(feature = "tracing-spans")]
+fn trace_stream<S>(
+    mut stream: S,
+    span: tracing::Span,
+) -> impl futures_util::Stream<Item = Result<crate::StreamEvent, Error>> + Send
+where
+    S: futures_util::Stream<Item = Result<crate::StreamEvent, Error>> + Send + Unpin,
+{
+    futures_util::stream::poll_fn(move |cx| {
+        let _entered = span.enter();
+        let item = std::task::ready!(stream.poll_next_unpin(cx));
+        match &item {
+            Some(Ok(crate::StreamEvent::Done {
+                finish_reason,
+                usage,
+            })) => {
+                span.record("finish_reason", finish_reason.as_str());
+                span.record("input_tokens", usage.input_tokens);
+                span.record("output_tokens", usage.output_tokens);
+            }
+            Some(Err(e)) => {
+                span.record("error", tracing::field::display(e));
+            }
+            _ => {}
+        }
+        std::task::Poll::Ready(item)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -436,6 +617,159 @@ mod tests {
         assert!(matches!(err, Error::Config(_)));
     }
 
+    /// [`generate`] stamps a request ID on calls without one, keeps a
+    /// caller's, and reports it on the response and its errors.
+    #[tokio::test]
+    async fn generate_assigns_and_propagates_request_id() {
+        let prompt = Prompt::user("hi");
+        let provider = MockProvider::new(vec![Err(Error::provider("Mock", "boom"))]);
+        let config = Config::builder("gpt-4o-mini").build();
+        let response = generate(&provider, &prompt, &config).await.unwrap();
+        let generated = provider
+            .last_raw()
+            .request_id
+            .expect("request ID generated");
+        assert_eq!(response.metadata().request_id, Some(generated.clone()));
+        let err = response.text().await.expect_err("stream fails");
+        let details = err.provider_details().expect("details attached");
+        assert_eq!(details.client_request_id, Some(generated));
+
+        let provider = MockProvider::new(Vec::new());
+        let config = Config::builder("gpt-4o-mini").request_id("job-42").build();
+        let response = generate(&provider, &prompt, &config).await.unwrap();
+        assert_eq!(provider.last_raw().request_id.as_deref(), Some("job-42"));
+        assert_eq!(response.metadata().request_id.as_deref(), Some("job-42"));
+    }
+
+    /// Variants without [`crate::ProviderErrorDetails`] pass through
+    /// untouched; only the caller's own ID correlates them.
+    #[tokio::test]
+    async fn generate_leaves_detail_less_errors_unchanged() {
+        let provider = MockProvider::new(vec![Err(Error::idle_timeout(
+            std::time::Duration::from_secs(5),
+        ))]);
+        let config = Config::builder("gpt-4o-mini").request_id("job-42").build();
+        let response = generate(&provider, &Prompt::user("hi"), &config)
+            .await
+            .unwrap();
+        let err = response.text().await.expect_err("stream fails");
+        assert!(matches!(err, Error::IdleTimeout(_)), "got: {err:?}");
+        assert!(err.provider_details().is_none());
+    }
+
+    /// With `tracing-spans`, the call's span stays open over the
+    /// stream and records the turn's outcome on it.
+    #[cfg(feature = "tracing-spans")]
+    #[tokio::test]
+    async fn generate_span_records_the_turn() {
+        use tracing::field::{Field, Visit};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+
+        struct Fields<'a>(&'a mut Vec<(String, String)>);
+        impl Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push((field.name().into(), format!("{value:?}")));
+            }
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.push((field.name().into(), value.into()));
+            }
+        }
+        #[derive(Clone, Default)]
+        struct Capture(Arc<Mutex<Vec<(String, String)>>>);
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for Capture {
+            fn on_new_span(
+                &self,
+                attrs: &tracing::span::Attributes<'_>,
+                _: &tracing::span::Id,
+                _: Context<'_, S>,
+            ) {
+                attrs.record(&mut Fields(&mut self.0.lock().unwrap()));
+            }
+            fn on_record(
+                &self,
+                _: &tracing::span::Id,
+                values: &tracing::span::Record<'_>,
+                _: Context<'_, S>,
+            ) {
+                values.record(&mut Fields(&mut self.0.lock().unwrap()));
+            }
+        }
+
+        let capture = Capture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+        let provider = MockProvider::new(vec![Ok(StreamEvent::Done {
+            finish_reason: FinishReason::Stop,
+            usage: Usage {
+                input_tokens: 3,
+                output_tokens: 5,
+                ..Usage::default()
+            },
+        })]);
+        let config = Config::builder("gpt-4o-mini").request_id("job-42").build();
+        let response = generate(&provider, &Prompt::user("hi"), &config)
+            .await
+            .unwrap();
+        assert!(
+            !capture
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|(k, _)| k == "finish_reason"),
+            "outcome is recorded when the stream ends"
+        );
+        response.buffer().await.unwrap();
+
+        let fields = capture.0.lock().unwrap().clone();
+        for (name, value) in [
+            ("model", "gpt-4o-mini"),
+            ("request_id", "job-42"),
+            ("input_tokens", "3"),
+            ("output_tokens", "5"),
+            ("finish_reason", "stop"),
+        ] {
+            assert!(
+                fields.iter().any(|(k, v)| k == name && v == value),
+                "missing {name}={value} in {fields:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn generate_many_keeps_input_order_and_per_item_errors() {
+        use crate::providers::mock::{MockProvider, MockResponse};
+        let provider = MockProvider::with_handler(|_, config| match config.model.as_str() {
+            "bad" => MockResponse::text("").with_stream_error(Error::provider("Mock", "boom")),
+            model => MockResponse::text(format!("from {model}")),
+        });
+        let prompt = Prompt::user("hi");
+        let configs: Vec<_> = ["m0", "bad", "m2", "m3", "m4"]
+            .into_iter()
+            .map(|model| Config::builder(model).build())
+            .collect();
+
+        let results = generate_many(&provider, configs.iter().map(|c| (&prompt, c)), 2).await;
+        let texts: Vec<_> = results
+            .iter()
+            .map(|r| {
+                r.as_ref()
+                    .map(|response| response.text())
+                    .map_err(Error::kind)
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                Ok("from m0".to_string()),
+                Err("provider"),
+                Ok("from m2".to_string()),
+                Ok("from m3".to_string()),
+                Ok("from m4".to_string()),
+            ]
+        );
+    }
+
     /// When a middleware returns `Err`, [`generate`] short-circuits
     /// before reaching the provider. Uses the
     /// `JsonCoercionMiddleware` conflict case (caller pinned
@@ -505,6 +839,7 @@ mod tests {
             name: "f".into(),
             arguments: "{}".into(),
             provider_signature: None,
+            original_arguments: None,
         }));
         let err = validate_prompt(&prompt).expect_err("unmatched tool call must be rejected");
         assert!(matches!(err, Error::InvalidPrompt(_)), "got: {err}");
@@ -526,13 +861,17 @@ mod tests {
                 name: "f".into(),
                 arguments: "{}".into(),
                 provider_signature: None,
+                original_arguments: None,
             })
             .with_item(InputItem::System("aside".into()))
             .with_item(InputItem::User {
                 content: vec![UserPart::ToolResult {
                     call_id: "c1".into(),
                     content: vec![UserPart::Text("ok".into())],
+                    is_error: false,
                 }],
+                name: None,
+                metadata: Default::default(),
             });
         validate_prompt(&prompt).expect("System between call and result must not break pairing");
     }
@@ -550,6 +889,7 @@ mod tests {
             name: "f".into(),
             arguments: "{}".into(),
             provider_signature: None,
+            original_arguments: None,
         };
         let prompt = Prompt::user("hi")
             .with_item(InputItem::Assistant {
@@ -557,18 +897,24 @@ mod tests {
                     AssistantPart::ToolCall(call("c1")),
                     AssistantPart::ToolCall(call("c2")),
                 ],
+                name: None,
+                metadata: Default::default(),
             })
             .with_item(InputItem::User {
                 content: vec![
                     UserPart::ToolResult {
                         call_id: "c1".into(),
                         content: vec![UserPart::Text("ok".into())],
+                        is_error: false,
                     },
                     UserPart::ToolResult {
                         call_id: "c1".into(),
                         content: vec![UserPart::Text("ok".into())],
+                        is_error: false,
                     },
                 ],
+                name: None,
+                metadata: Default::default(),
             });
         let err = validate_prompt(&prompt)
             .expect_err("id-mismatched results must be rejected even with equal counts");
@@ -585,6 +931,7 @@ mod tests {
             name: "f".into(),
             arguments: "{}".into(),
             provider_signature: None,
+            original_arguments: None,
         };
         let prompt = Prompt::user("hi")
             .with_item(InputItem::Assistant {
@@ -592,18 +939,24 @@ mod tests {
                     AssistantPart::ToolCall(call("c1")),
                     AssistantPart::ToolCall(call("c2")),
                 ],
+                name: None,
+                metadata: Default::default(),
             })
             .with_item(InputItem::User {
                 content: vec![
                     UserPart::ToolResult {
                         call_id: "c1".into(),
                         content: vec![UserPart::Text("ok".into())],
+                        is_error: false,
                     },
                     UserPart::ToolResult {
                         call_id: "c2".into(),
                         content: vec![UserPart::Text("ok".into())],
+                        is_error: false,
                     },
                 ],
+                name: None,
+                metadata: Default::default(),
             });
         validate_prompt(&prompt).expect("matched parallel tool calls are valid");
     }
@@ -0,0 +1,627 @@
+//! PII redaction for outgoing prompts.
+//!
+//! [`PiiRedactionMiddleware`] masks personal data in the prompt before
+//! it leaves the process: each email address, phone number and payment
+//! card number is replaced by a placeholder such as `[EMAIL_1]`. The
+//! same value maps to the same placeholder throughout one request, so
+//! the model can still tell two addresses apart and refer back to
+//! them. The placeholder → value map never leaves the process; when
+//! the model echoes a placeholder, the response stream is rewritten to
+//! carry the original value again (opt out with
+//! [`PiiRedactionMiddleware::restore_responses`]).
+//!
+//! ```ignore
+//! use platformed_llm::middleware::{default_middleware, PiiRedactionMiddleware};
+//!
+//! let mut chain = default_middleware(&caps);
+//! chain.push(Arc::new(PiiRedactionMiddleware::new()));
+//! let config = Config::builder("gpt-4o").with_middleware(chain).build();
+//! ```
+//!
+//! # What is scanned
+//!
+//! System and developer instructions, user text (including text inside
+//! tool results) and earlier assistant text and refusals. Left alone:
+//! media inputs, reasoning (signed by some providers, so it must be
+//! echoed verbatim) and earlier tool-call arguments (which must stay
+//! valid JSON).
+//!
+//! # Detection
+//!
+//! The built-in detectors are deliberately conservative, favouring
+//! precision over recall:
+//!
+//! - [`PiiKind::Email`] — `local@domain.tld`.
+//! - [`PiiKind::CardNumber`] — 13–19 digits, optionally grouped by
+//!   single spaces or hyphens, passing the Luhn check.
+//! - [`PiiKind::Phone`] — 10–15 digits, optionally led by `+` and
+//!   grouped by spaces, hyphens, dots or parentheses.
+//!
+//! They are heuristics, not a compliance guarantee. With the `regex`
+//! feature, [`PiiRedactionMiddleware::pattern`] adds detectors for
+//! anything else (account ids, national id formats, …).
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures_util::stream::{self, StreamExt};
+
+use crate::types::{AssistantPart, InputItem, RawConfig, UserPart};
+use crate::{Capabilities, Error, Prompt, StreamEvent};
+
+use super::{Middleware, ResponseTransform};
+
+/// A category of personal data the built-in detectors recognise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PiiKind {
+    /// Email addresses. Placeholder `[EMAIL_n]`.
+    Email,
+    /// Phone numbers. Placeholder `[PHONE_n]`.
+    Phone,
+    /// Payment card numbers. Placeholder `[CARD_n]`.
+    CardNumber,
+}
+
+impl PiiKind {
+    /// Every built-in kind, cards ahead of phones so a card number
+    /// isn't taken for a phone number.
+    pub const ALL: [PiiKind; 3] = [PiiKind::Email, PiiKind::CardNumber, PiiKind::Phone];
+
+    fn label(self) -> &'static str {
+        match self {
+            PiiKind::Email => "EMAIL",
+            PiiKind::Phone => "PHONE",
+            PiiKind::CardNumber => "CARD",
+        }
+    }
+
+    fn find(self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            PiiKind::Email => find_emails(text),
+            PiiKind::Phone => find_digit_runs(text, true)
+                .into_iter()
+                .filter(|(_, digits)| (10..=15).contains(&digits.len()))
+                .map(|(range, _)| range)
+                .collect(),
+            PiiKind::CardNumber => find_digit_runs(text, false)
+                .into_iter()
+                .filter(|(_, digits)| (13..=19).contains(&digits.len()) && luhn(digits))
+                .map(|(range, _)| range)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Detector {
+    Builtin(PiiKind),
+    #[cfg(feature = "regex")]
+    Pattern {
+        label: String,
+        regex: regex::Regex,
+    },
+}
+
+impl Detector {
+    fn label(&self) -> &str {
+        match self {
+            Detector::Builtin(kind) => kind.label(),
+            #[cfg(feature = "regex")]
+            Detector::Pattern { label, .. } => label,
+        }
+    }
+
+    fn find(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Detector::Builtin(kind) => kind.find(text),
+            #[cfg(feature = "regex")]
+            Detector::Pattern { regex, .. } => regex
+                .find_iter(text)
+                .map(|m| m.range())
+                .filter(|range| !range.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// Masks personal data in outgoing prompts and restores it in the
+/// response. See the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct PiiRedactionMiddleware {
+    detectors: Vec<Detector>,
+    restore: bool,
+}
+
+impl Default for PiiRedactionMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PiiRedactionMiddleware {
+    /// Redact every [`PiiKind`], restoring values in responses.
+    pub fn new() -> Self {
+        Self::with_kinds(PiiKind::ALL)
+    }
+
+    /// Redact only `kinds`. Where two detectors match overlapping text,
+    /// the one listed first wins.
+    pub fn with_kinds(kinds: impl IntoIterator<Item = PiiKind>) -> Self {
+        Self {
+            detectors: kinds.into_iter().map(Detector::Builtin).collect(),
+            restore: true,
+        }
+    }
+
+    /// Also redact matches of `regex`, as `[<label>_n]`. Use an
+    /// uppercase label without spaces, e.g. `"ACCOUNT"`.
+    #[cfg(feature = "regex")]
+    pub fn pattern(mut self, label: impl Into<String>, regex: regex::Regex) -> Self {
+        self.detectors.push(Detector::Pattern {
+            label: label.into(),
+            regex,
+        });
+        self
+    }
+
+    /// Whether placeholders the model echoes are turned back into the
+    /// original values (the default). Off, the caller sees the
+    /// placeholders.
+    pub fn restore_responses(mut self, restore: bool) -> Self {
+        self.restore = restore;
+        self
+    }
+
+    /// Non-overlapping matches in `text`, in order, with their labels.
+    /// Earlier detectors win overlaps.
+    fn find<'d>(&'d self, text: &str) -> Vec<(Range<usize>, &'d str)> {
+        let mut found: Vec<(Range<usize>, &str)> = Vec::new();
+        for detector in &self.detectors {
+            for range in detector.find(text) {
+                let overlaps = found
+                    .iter()
+                    .any(|(taken, _)| range.start < taken.end && taken.start < range.end);
+                if !overlaps {
+                    found.push((range, detector.label()));
+                }
+            }
+        }
+        found.sort_by_key(|(range, _)| range.start);
+        found
+    }
+
+    /// `text` with each match replaced by its placeholder, or `None`
+    /// when nothing matched.
+    fn redact(&self, text: &str, vault: &mut Vault) -> Option<String> {
+        let found = self.find(text);
+        if found.is_empty() {
+            return None;
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, label) in found {
+            out.push_str(&text[last..range.start]);
+            out.push_str(vault.placeholder(label, &text[range.clone()]));
+            last = range.end;
+        }
+        out.push_str(&text[last..]);
+        Some(out)
+    }
+
+    fn redact_in_place(&self, text: &mut String, vault: &mut Vault) {
+        if let Some(redacted) = self.redact(text, vault) {
+            *text = redacted;
+        }
+    }
+
+    fn redact_user_parts(&self, parts: &mut [UserPart], vault: &mut Vault) {
+        for part in parts {
+            match part {
+                UserPart::Text(text) => self.redact_in_place(text, vault),
+                UserPart::ToolResult { content, .. } => self.redact_user_parts(content, vault),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether any scanned text in `prompt` has something to redact.
+    fn detects(&self, prompt: &Prompt) -> bool {
+        fn user_texts<'p>(parts: &'p [UserPart], out: &mut Vec<&'p str>) {
+            for part in parts {
+                match part {
+                    UserPart::Text(text) => out.push(text),
+                    UserPart::ToolResult { content, .. } => user_texts(content, out),
+                    _ => {}
+                }
+            }
+        }
+        let mut texts = Vec::new();
+        for item in prompt.items() {
+            match item {
+                InputItem::System(text) | InputItem::Developer(text) => texts.push(text.as_str()),
+                InputItem::User { content, .. } => user_texts(content, &mut texts),
+                InputItem::Assistant { content, .. } => {
+                    texts.extend(content.iter().filter_map(|part| match part {
+                        AssistantPart::Text { content, .. } | AssistantPart::Refusal(content) => {
+                            Some(content.as_str())
+                        }
+                        _ => None,
+                    }))
+                }
+            }
+        }
+        texts.into_iter().any(|text| !self.find(text).is_empty())
+    }
+
+    fn redact_prompt(&self, prompt: &Prompt, vault: &mut Vault) -> Prompt {
+        let mut items = prompt.items().to_vec();
+        for item in &mut items {
+            match item {
+                InputItem::System(text) | InputItem::Developer(text) => {
+                    self.redact_in_place(text, vault)
+                }
+                InputItem::User { content, .. } => self.redact_user_parts(content, vault),
+                InputItem::Assistant { content, .. } => {
+                    for part in content {
+                        if let AssistantPart::Text { content: text, .. }
+                        | AssistantPart::Refusal(text) = part
+                        {
+                            self.redact_in_place(text, vault);
+                        }
+                    }
+                }
+            }
+        }
+        items.into()
+    }
+}
+
+impl Middleware for PiiRedactionMiddleware {
+    fn name(&self) -> &str {
+        "pii_redaction"
+    }
+
+    fn apply<'a>(
+        &self,
+        prompt: &mut Cow<'a, Prompt>,
+        _config: &mut Cow<'a, RawConfig>,
+        _capabilities: &Capabilities,
+    ) -> Result<Option<ResponseTransform>, Error> {
+        if !self.detects(prompt) {
+            return Ok(None);
+        }
+        let mut vault = Vault::default();
+        let redacted = self.redact_prompt(prompt, &mut vault);
+        tracing::debug!(redacted = vault.values.len(), "redacted PII from prompt");
+        *prompt = Cow::Owned(redacted);
+        if !self.restore {
+            return Ok(None);
+        }
+        let vault = Arc::new(vault);
+        Ok(Some(Box::new(move |response| {
+            response.map_stream(move |inner| restoring(inner, vault))
+        })))
+    }
+}
+
+/// Placeholders minted for one request, both ways round.
+#[derive(Debug, Default)]
+struct Vault {
+    /// Original value → placeholder.
+    placeholders: HashMap<String, String>,
+    /// Placeholder → original value.
+    values: HashMap<String, String>,
+    counters: HashMap<String, usize>,
+    longest: usize,
+}
+
+impl Vault {
+    fn placeholder(&mut self, label: &str, value: &str) -> &str {
+        if !self.placeholders.contains_key(value) {
+            let n = self.counters.entry(label.to_string()).or_default();
+            *n += 1;
+            let placeholder = format!("[{label}_{n}]");
+            self.longest = self.longest.max(placeholder.len());
+            self.values.insert(placeholder.clone(), value.to_string());
+            self.placeholders.insert(value.to_string(), placeholder);
+        }
+        &self.placeholders[value]
+    }
+
+    /// `text` with every known placeholder swapped back.
+    fn restore(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(open) = rest.find('[') {
+            out.push_str(&rest[..open]);
+            rest = &rest[open..];
+            let known = rest
+                .find(']')
+                .and_then(|close| Some((self.values.get(&rest[..=close])?, close)));
+            match known {
+                Some((value, close)) => {
+                    out.push_str(value);
+                    rest = &rest[close + 1..];
+                }
+                None => {
+                    out.push('[');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Where the tail of `text` that might be the start of a split
+    /// placeholder begins, if it does.
+    fn partial_start(&self, text: &str) -> Option<usize> {
+        let open = text.rfind('[')?;
+        let tail = &text[open..];
+        (tail.len() < self.longest && !tail.contains(']')).then_some(open)
+    }
+}
+
+/// `inner` with placeholders restored in every delta. A delta ending
+/// in what may be half a placeholder holds that tail back until the
+/// next delta, the part's end or the end of the turn.
+fn restoring(inner: crate::EventStream, vault: Arc<Vault>) -> crate::EventStream {
+    let mut pending: HashMap<u32, String> = HashMap::new();
+    let flush = |vault: &Vault, index: u32, held: String| {
+        (!held.is_empty()).then(|| {
+            Ok(StreamEvent::Delta {
+                index,
+                delta: vault.restore(&held),
+            })
+        })
+    };
+    Box::pin(
+        inner
+            .map(move |event| {
+                let events: Vec<Result<StreamEvent, Error>> = match event {
+                    Ok(StreamEvent::Delta { index, delta }) => {
+                        let held = pending.entry(index).or_default();
+                        held.push_str(&delta);
+                        let ready = match vault.partial_start(held) {
+                            Some(at) => {
+                                let tail = held.split_off(at);
+                                std::mem::replace(held, tail)
+                            }
+                            None => std::mem::take(held),
+                        };
+                        flush(&vault, index, ready).into_iter().collect()
+                    }
+                    Ok(StreamEvent::PartEnd { index }) => {
+                        let held = pending.remove(&index).unwrap_or_default();
+                        let mut events: Vec<_> = flush(&vault, index, held).into_iter().collect();
+                        events.push(Ok(StreamEvent::PartEnd { index }));
+                        events
+                    }
+                    Ok(done @ StreamEvent::Done { .. }) => {
+                        let mut held: Vec<_> = pending.drain().collect();
+                        held.sort_by_key(|(index, _)| *index);
+                        let mut events: Vec<_> = held
+                            .into_iter()
+                            .filter_map(|(index, held)| flush(&vault, index, held))
+                            .collect();
+                        events.push(Ok(done));
+                        events
+                    }
+                    other => vec![other],
+                };
+                stream::iter(events)
+            })
+            .flatten(),
+    )
+}
+
+fn is_email_local(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-')
+}
+
+fn is_email_domain(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '-')
+}
+
+fn find_emails(text: &str) -> Vec<Range<usize>> {
+    let mut found = Vec::new();
+    let mut searched = 0;
+    for (at, _) in text.match_indices('@') {
+        if at < searched {
+            continue;
+        }
+        let start = text[..at]
+            .rfind(|c: char| !is_email_local(c))
+            .map_or(0, |i| i + 1)
+            .max(searched);
+        let local = text[start..at].trim_start_matches('.');
+        let start = at - local.len();
+        let after = &text[at + 1..];
+        let domain_len = after
+            .find(|c: char| !is_email_domain(c))
+            .unwrap_or(after.len());
+        let domain = after[..domain_len].trim_end_matches(['.', '-']);
+        let tld_ok = domain.rsplit_once('.').is_some_and(|(host, tld)| {
+            !host.is_empty()
+                && !host.starts_with(['.', '-'])
+                && tld.len() >= 2
+                && tld.chars().all(|c| c.is_ascii_alphabetic())
+        });
+        if !local.is_empty() && tld_ok {
+            let end = at + 1 + domain.len();
+            found.push(start..end);
+            searched = end;
+        }
+    }
+    found
+}
+
+/// Runs of digits joined by single separators, with the digits alone.
+/// `phone` admits the separators phone numbers use (and a leading `+`);
+/// otherwise only single spaces and hyphens, as on cards. A run must
+/// not touch a letter or digit on either side.
+fn find_digit_runs(text: &str, phone: bool) -> Vec<(Range<usize>, String)> {
+    let bytes = text.as_bytes();
+    let is_sep = |b: u8| match b {
+        b' ' | b'-' => true,
+        b'.' | b'(' | b')' => phone,
+        _ => false,
+    };
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let starts_run = bytes[i].is_ascii_digit()
+            || (phone
+                && matches!(bytes[i], b'+' | b'(')
+                && bytes.get(i + 1).is_some_and(|b| b.is_ascii_digit()));
+        if !starts_run || (i > 0 && bytes[i - 1].is_ascii_alphanumeric()) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut digits = String::new();
+        let mut end = i;
+        let mut j = i;
+        if matches!(bytes[j], b'+' | b'(') {
+            j += 1;
+        }
+        while j < bytes.len() {
+            if bytes[j].is_ascii_digit() {
+                digits.push(bytes[j] as char);
+                j += 1;
+                end = j;
+                if phone && bytes.get(j) == Some(&b')') {
+                    j += 1;
+                    end = j;
+                }
+            } else if is_sep(bytes[j])
+                && bytes
+                    .get(j + 1)
+                    .is_some_and(|b| b.is_ascii_digit() || (phone && *b == b'('))
+            {
+                j += 1;
+                if bytes[j] == b'(' {
+                    j += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        if !bytes.get(end).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            runs.push((start..end, digits));
+        }
+        i = end.max(i + 1);
+    }
+    runs
+}
+
+fn luhn(digits: &str) -> bool {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = u32::from(b - b'0');
+            match i % 2 {
+                0 => d,
+                _ if d * 2 > 9 => d * 2 - 9,
+                _ => d * 2,
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FinishReason, PartKind, Usage};
+    use crate::{Config, Response};
+
+    fn redacted(text: &str) -> String {
+        PiiRedactionMiddleware::new()
+            .redact(text, &mut Vault::default())
+            .unwrap_or_else(|| text.to_string())
+    }
+
+    #[test]
+    fn masks_each_kind_and_reuses_placeholders() {
+        assert_eq!(
+            redacted(
+                "Mail jane.doe+x@example.co.uk or jane.doe+x@example.co.uk, \
+                 call +1 (415) 555-0132, card 4111 1111 1111 1111."
+            ),
+            "Mail [EMAIL_1] or [EMAIL_1], call [PHONE_1], card [CARD_1]."
+        );
+    }
+
+    #[test]
+    fn leaves_lookalikes_alone() {
+        for text in [
+            "ship on 2024-01-15 at 10:30",
+            "order #4111111111111112 failed",
+            "user@localhost",
+            "version 1.2.3.4",
+            "id ab1234567890",
+        ] {
+            assert_eq!(redacted(text), text);
+        }
+    }
+
+    #[tokio::test]
+    async fn prompt_is_masked_and_response_restored_across_deltas() {
+        let prompt =
+            Prompt::system("Support for bob@example.com.").with_user("Email bob@example.com");
+        let config = Config::builder("m").build();
+        let mut prompt_cow = Cow::Borrowed(&prompt);
+        let mut raw = Cow::Borrowed(config.raw());
+        let transform = PiiRedactionMiddleware::new()
+            .apply(&mut prompt_cow, &mut raw, &Capabilities::default())
+            .unwrap()
+            .expect("restores by default");
+        let sent = serde_json::to_string(prompt_cow.items()).unwrap();
+        assert!(!sent.contains("bob@"), "{sent}");
+        assert!(sent.contains("[EMAIL_1]"), "{sent}");
+
+        let events = [
+            StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::Text,
+            },
+            StreamEvent::Delta {
+                index: 0,
+                delta: "Sent to [EMA".into(),
+            },
+            StreamEvent::Delta {
+                index: 0,
+                delta: "IL_1] [sic]".into(),
+            },
+            StreamEvent::PartEnd { index: 0 },
+            StreamEvent::Done {
+                finish_reason: FinishReason::Stop,
+                usage: Usage::default(),
+            },
+        ];
+        let response = transform(Response::from_stream(stream::iter(events.map(Ok))));
+        assert_eq!(
+            response.text().await.unwrap(),
+            "Sent to bob@example.com [sic]"
+        );
+    }
+
+    #[test]
+    fn clean_prompts_stay_borrowed() {
+        let prompt = Prompt::user("nothing personal here");
+        let config = Config::builder("m").build();
+        let mut prompt_cow = Cow::Borrowed(&prompt);
+        let mut raw = Cow::Borrowed(config.raw());
+        let transform = PiiRedactionMiddleware::new()
+            .apply(&mut prompt_cow, &mut raw, &Capabilities::default())
+            .unwrap();
+        assert!(transform.is_none());
+        assert!(matches!(prompt_cow, Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,248 @@
+//! Capability-aware request parameter sanitization.
+//!
+//! Switching a request from one model to another often trips over a
+//! parameter the new model rejects outright: `temperature` on an
+//! OpenAI o-series model, penalties on Gemini 2.5, a `max_tokens`
+//! above the model's output cap. Each of those is a 400 from the
+//! upstream. [`ParameterSanitizerMiddleware`] consults the resolved
+//! [`Capabilities`] and drops or clamps such parameters before the
+//! request is sent, reporting every change to a warning callback.
+//!
+//! Opt-in: add it to the chain with
+//! [`crate::ConfigBuilder::with_middleware`] (alongside
+//! [`super::default_middleware`] if you want the polyfills too).
+//! Without a callback, adjustments are logged at `warn`.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::types::RawConfig;
+use crate::{Capabilities, Error, Prompt};
+
+use super::{Middleware, ResponseTransform};
+
+/// One change [`ParameterSanitizerMiddleware`] made to a request.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ParameterAdjustment {
+    /// The parameter is unsupported by the model and was removed.
+    Dropped {
+        /// Request field name, e.g. `"temperature"`.
+        parameter: &'static str,
+    },
+    /// The parameter exceeded the model's limit and was lowered to it.
+    Clamped {
+        /// Request field name, e.g. `"max_tokens"`.
+        parameter: &'static str,
+        /// The value the caller asked for.
+        requested: u32,
+        /// The value actually sent.
+        limit: u32,
+    },
+}
+
+impl fmt::Display for ParameterAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterAdjustment::Dropped { parameter } => {
+                write!(f, "dropped unsupported `{parameter}`")
+            }
+            ParameterAdjustment::Clamped {
+                parameter,
+                requested,
+                limit,
+            } => write!(f, "clamped `{parameter}` from {requested} to {limit}"),
+        }
+    }
+}
+
+/// Callback invoked with the model name and each adjustment made.
+pub type AdjustmentCallback = Arc<dyn Fn(&str, &ParameterAdjustment) + Send + Sync>;
+
+/// Drops parameters the model doesn't accept and clamps ones over its
+/// limits, per [`Capabilities::sampling_parameters`],
+/// [`Capabilities::penalties`] and [`Capabilities::max_output_tokens`].
+/// See the [module docs](self).
+#[derive(Default, Clone)]
+pub struct ParameterSanitizerMiddleware {
+    on_adjust: Option<AdjustmentCallback>,
+}
+
+impl ParameterSanitizerMiddleware {
+    /// A sanitizer that logs each adjustment at `warn`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report adjustments to `callback` instead of logging them.
+    pub fn on_adjust(
+        mut self,
+        callback: impl Fn(&str, &ParameterAdjustment) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_adjust = Some(Arc::new(callback));
+        self
+    }
+
+    fn report(&self, model: &str, adjustment: &ParameterAdjustment) {
+        match &self.on_adjust {
+            Some(callback) => callback(model, adjustment),
+            None => tracing::warn!(model, "{adjustment}"),
+        }
+    }
+}
+
+impl fmt::Debug for ParameterSanitizerMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParameterSanitizerMiddleware")
+            .field("on_adjust", &self.on_adjust.as_ref().map(|_| "<callback>"))
+            .finish()
+    }
+}
+
+impl Middleware for ParameterSanitizerMiddleware {
+    fn name(&self) -> &str {
+        "parameter_sanitizer"
+    }
+
+    fn apply<'a>(
+        &self,
+        _prompt: &mut Cow<'a, Prompt>,
+        config: &mut Cow<'a, RawConfig>,
+        capabilities: &Capabilities,
+    ) -> Result<Option<ResponseTransform>, Error> {
+        let adjustments = adjustments(config, capabilities);
+        if adjustments.is_empty() {
+            return Ok(None);
+        }
+        let config = config.to_mut();
+        for adjustment in &adjustments {
+            match *adjustment {
+                ParameterAdjustment::Dropped { parameter } => match parameter {
+                    "temperature" => config.temperature = None,
+                    "top_p" => config.top_p = None,
+                    "presence_penalty" => config.presence_penalty = None,
+                    "frequency_penalty" => config.frequency_penalty = None,
+                    _ => unreachable!("adjustments only names the fields above"),
+                },
+                ParameterAdjustment::Clamped { limit, .. } => config.max_tokens = Some(limit),
+            }
+            self.report(&config.model, adjustment);
+        }
+        Ok(None)
+    }
+}
+
+/// The adjustments `config` needs to satisfy `caps`, in field order.
+fn adjustments(config: &RawConfig, caps: &Capabilities) -> Vec<ParameterAdjustment> {
+    let mut out = Vec::new();
+    let mut drop_if = |unsupported: bool, set: bool, parameter| {
+        if unsupported && set {
+            out.push(ParameterAdjustment::Dropped { parameter });
+        }
+    };
+    let sampling = caps.sampling_parameters;
+    let penalties = sampling && caps.penalties;
+    drop_if(!sampling, config.temperature.is_some(), "temperature");
+    drop_if(!sampling, config.top_p.is_some(), "top_p");
+    drop_if(
+        !penalties,
+        config.presence_penalty.is_some(),
+        "presence_penalty",
+    );
+    drop_if(
+        !penalties,
+        config.frequency_penalty.is_some(),
+        "frequency_penalty",
+    );
+    if let Some(requested) = config.max_tokens {
+        if requested > caps.max_output_tokens {
+            out.push(ParameterAdjustment::Clamped {
+                parameter: "max_tokens",
+                requested,
+                limit: caps.max_output_tokens,
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use std::sync::Mutex;
+
+    fn sanitize(config: &Config, caps: &Capabilities) -> (RawConfig, Vec<String>) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let sanitizer = ParameterSanitizerMiddleware::new().on_adjust(move |model, adjustment| {
+            sink.lock().unwrap().push(format!("{model}: {adjustment}"));
+        });
+        let prompt = Prompt::user("hi");
+        let mut prompt = Cow::Borrowed(&prompt);
+        let mut raw = Cow::Borrowed(config.raw());
+        let transform = sanitizer.apply(&mut prompt, &mut raw, caps).unwrap();
+        assert!(transform.is_none());
+        let seen = seen.lock().unwrap().clone();
+        (raw.into_owned(), seen)
+    }
+
+    #[test]
+    fn reasoning_model_loses_sampling_parameters() {
+        let config = Config::builder("o3-mini")
+            .temperature(0.2)
+            .top_p(0.9)
+            .presence_penalty(0.5)
+            .max_tokens(1000)
+            .build();
+        let (raw, seen) = sanitize(&config, &Capabilities::for_model("o3-mini"));
+        assert_eq!(raw.temperature, None);
+        assert_eq!(raw.top_p, None);
+        assert_eq!(raw.presence_penalty, None);
+        assert_eq!(raw.max_tokens, Some(1000));
+        assert_eq!(
+            seen,
+            [
+                "o3-mini: dropped unsupported `temperature`",
+                "o3-mini: dropped unsupported `top_p`",
+                "o3-mini: dropped unsupported `presence_penalty`",
+            ]
+        );
+    }
+
+    #[test]
+    fn gemini_keeps_temperature_but_drops_penalties_and_clamps_output() {
+        let config = Config::builder("gemini-2.5-flash")
+            .temperature(0.2)
+            .frequency_penalty(0.5)
+            .max_tokens(100_000)
+            .build();
+        let (raw, seen) = sanitize(&config, &Capabilities::for_model("gemini-2.5-flash"));
+        assert_eq!(raw.temperature, Some(0.2));
+        assert_eq!(raw.frequency_penalty, None);
+        assert_eq!(raw.max_tokens, Some(65_535));
+        assert_eq!(
+            seen,
+            [
+                "gemini-2.5-flash: dropped unsupported `frequency_penalty`",
+                "gemini-2.5-flash: clamped `max_tokens` from 100000 to 65535",
+            ]
+        );
+    }
+
+    #[test]
+    fn supported_parameters_leave_the_config_borrowed() {
+        let config = Config::builder("gpt-4o")
+            .temperature(0.2)
+            .presence_penalty(0.5)
+            .build();
+        let prompt = Prompt::user("hi");
+        let mut prompt = Cow::Borrowed(&prompt);
+        let mut raw = Cow::Borrowed(config.raw());
+        ParameterSanitizerMiddleware::new()
+            .apply(&mut prompt, &mut raw, &Capabilities::for_model("gpt-4o"))
+            .unwrap();
+        assert!(matches!(raw, Cow::Borrowed(_)));
+    }
+}
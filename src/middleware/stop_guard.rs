@@ -0,0 +1,402 @@
+//! Client-side stop phrases and patterns.
+//!
+//! Provider stop sequences are matched per token on the server, take a
+//! handful of literals at most (four on OpenAI, five on Gemini), and
+//! can't express patterns at all. [`StopGuardMiddleware`] enforces
+//! stops in-process instead: it watches the visible text of each
+//! [`PartKind::Text`] part as it streams and, on the first match,
+//! truncates the text just before it, closes the open parts, ends the
+//! turn with [`FinishReason::StopSequence`] and drops the upstream
+//! stream — which cancels the request, so the tokens after the match
+//! are neither generated for long nor billed.
+//!
+//! To guarantee that no part of a match reaches the caller, the guard
+//! holds back the tail of each text part that could still turn into
+//! one: `len - 1` bytes for a phrase, and the caller-declared maximum
+//! match length for a regex (with the `regex` feature). Text is
+//! therefore delivered slightly behind the upstream. A regex match
+//! longer than its declared bound can begin in text already emitted;
+//! only matches starting in the held-back tail are caught. Matching is
+//! per part, so a phrase split across two text parts doesn't match.
+//!
+//! Opt-in: add it to the chain with
+//! [`crate::ConfigBuilder::with_middleware`]. The `Done` it emits
+//! carries empty [`Usage`] — the upstream's final counters never
+//! arrive once the request is cancelled.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::ops::Range;
+
+use futures_util::stream::{self, StreamExt};
+
+use crate::types::{FinishReason, PartKind, RawConfig, Usage};
+use crate::{Capabilities, Error, EventStream, Prompt, StreamEvent};
+
+use super::{Middleware, ResponseTransform};
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Phrase(String),
+    #[cfg(feature = "regex")]
+    Regex {
+        regex: regex::Regex,
+        max_len: usize,
+    },
+}
+
+impl Pattern {
+    /// Bytes at the end of a part that might still become a match.
+    fn holdback(&self) -> usize {
+        match self {
+            Pattern::Phrase(phrase) => phrase.len().saturating_sub(1),
+            #[cfg(feature = "regex")]
+            Pattern::Regex { max_len, .. } => max_len.saturating_sub(1),
+        }
+    }
+
+    /// The first match in `text` starting at or after `from`.
+    fn find(&self, text: &str, from: usize) -> Option<Range<usize>> {
+        match self {
+            Pattern::Phrase(phrase) => text[from..]
+                .find(phrase.as_str())
+                .map(|start| from + start..from + start + phrase.len()),
+            #[cfg(feature = "regex")]
+            Pattern::Regex { regex, .. } => regex.find_at(text, from).map(|m| m.range()),
+        }
+    }
+}
+
+/// Stops the stream at the first configured phrase or pattern in the
+/// response text, truncating before it and cancelling the upstream
+/// call. See the [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct StopGuardMiddleware {
+    patterns: Vec<Pattern>,
+}
+
+impl StopGuardMiddleware {
+    /// A guard with nothing to watch for; add phrases or patterns.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop at the literal `phrase`. Empty phrases are ignored.
+    pub fn phrase(mut self, phrase: impl Into<String>) -> Self {
+        let phrase = phrase.into();
+        if !phrase.is_empty() {
+            self.patterns.push(Pattern::Phrase(phrase));
+        }
+        self
+    }
+
+    /// Stop at the first match of `regex`. `max_len` bounds the length
+    /// of a match in bytes and sets how much text is held back while
+    /// one could still be forming; a longer match may slip through
+    /// once its start has been delivered.
+    #[cfg(feature = "regex")]
+    pub fn regex(mut self, regex: regex::Regex, max_len: usize) -> Self {
+        self.patterns.push(Pattern::Regex { regex, max_len });
+        self
+    }
+}
+
+impl Middleware for StopGuardMiddleware {
+    fn name(&self) -> &str {
+        "stop_guard"
+    }
+
+    fn apply<'a>(
+        &self,
+        _prompt: &mut Cow<'a, Prompt>,
+        _config: &mut Cow<'a, RawConfig>,
+        _capabilities: &Capabilities,
+    ) -> Result<Option<ResponseTransform>, Error> {
+        if self.patterns.is_empty() {
+            return Ok(None);
+        }
+        let patterns = self.patterns.clone();
+        Ok(Some(Box::new(move |response| {
+            response.map_stream(move |inner| guard(inner, patterns))
+        })))
+    }
+}
+
+/// Accumulated text of one open text part.
+#[derive(Default)]
+struct TextPart {
+    text: String,
+    /// Bytes of `text` already delivered downstream.
+    emitted: usize,
+}
+
+struct Guard {
+    patterns: Vec<Pattern>,
+    holdback: usize,
+    text: BTreeMap<u32, TextPart>,
+    open: BTreeSet<u32>,
+}
+
+impl Guard {
+    /// Translate one upstream event into `out`. Returns `true` once a
+    /// match has ended the turn.
+    fn feed(&mut self, event: StreamEvent, out: &mut VecDeque<Result<StreamEvent, Error>>) -> bool {
+        match event {
+            StreamEvent::PartStart { index, kind } => {
+                if kind == PartKind::Text {
+                    self.text.insert(index, TextPart::default());
+                }
+                self.open.insert(index);
+                out.push_back(Ok(StreamEvent::PartStart { index, kind }));
+            }
+            StreamEvent::Delta { index, delta } if self.text.contains_key(&index) => {
+                return self.append(index, &delta, out);
+            }
+            StreamEvent::PartEnd { index } => {
+                self.flush(index, out);
+                self.open.remove(&index);
+                out.push_back(Ok(StreamEvent::PartEnd { index }));
+            }
+            done @ StreamEvent::Done { .. } => {
+                let open: Vec<u32> = self.text.keys().copied().collect();
+                for index in open {
+                    self.flush(index, out);
+                }
+                out.push_back(Ok(done));
+            }
+            other => out.push_back(Ok(other)),
+        }
+        false
+    }
+
+    fn append(
+        &mut self,
+        index: u32,
+        delta: &str,
+        out: &mut VecDeque<Result<StreamEvent, Error>>,
+    ) -> bool {
+        let part = self.text.get_mut(&index).expect("caller checked");
+        part.text.push_str(delta);
+        let from = part.emitted;
+        let hit = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| pattern.find(&part.text, from))
+            .min_by_key(|range| range.start);
+        if let Some(hit) = hit {
+            emit(index, &part.text[part.emitted..hit.start], out);
+            for index in std::mem::take(&mut self.open) {
+                out.push_back(Ok(StreamEvent::PartEnd { index }));
+            }
+            out.push_back(Ok(StreamEvent::Done {
+                finish_reason: FinishReason::StopSequence,
+                usage: Usage::default(),
+            }));
+            return true;
+        }
+        let mut safe = part
+            .text
+            .len()
+            .saturating_sub(self.holdback)
+            .max(part.emitted);
+        while !part.text.is_char_boundary(safe) {
+            safe -= 1;
+        }
+        if safe > part.emitted {
+            emit(index, &part.text[part.emitted..safe], out);
+            part.emitted = safe;
+        }
+        false
+    }
+
+    /// Deliver the held-back tail of a text part that ended unmatched.
+    fn flush(&mut self, index: u32, out: &mut VecDeque<Result<StreamEvent, Error>>) {
+        if let Some(part) = self.text.remove(&index) {
+            emit(index, &part.text[part.emitted..], out);
+        }
+    }
+}
+
+fn emit(index: u32, text: &str, out: &mut VecDeque<Result<StreamEvent, Error>>) {
+    if !text.is_empty() {
+        out.push_back(Ok(StreamEvent::Delta {
+            index,
+            delta: text.to_string(),
+        }));
+    }
+}
+
+/// `inner` with text held back and the stream cut at the first match.
+/// The upstream is dropped as soon as a match ends the turn.
+fn guard(inner: EventStream, patterns: Vec<Pattern>) -> EventStream {
+    let guard = Guard {
+        holdback: patterns.iter().map(Pattern::holdback).max().unwrap_or(0),
+        patterns,
+        text: BTreeMap::new(),
+        open: BTreeSet::new(),
+    };
+    Box::pin(stream::unfold(
+        (Some(inner), guard, VecDeque::new()),
+        |(mut inner, mut guard, mut out)| async move {
+            loop {
+                if let Some(event) = out.pop_front() {
+                    return Some((event, (inner, guard, out)));
+                }
+                let stream = inner.as_mut()?;
+                match stream.next().await {
+                    Some(Ok(event)) => {
+                        if guard.feed(event, &mut out) {
+                            tracing::debug!("stop guard matched; cancelling upstream");
+                            inner = None;
+                        }
+                    }
+                    Some(Err(err)) => out.push_back(Err(err)),
+                    None => {
+                        let open: Vec<u32> = guard.text.keys().copied().collect();
+                        for index in open {
+                            guard.flush(index, &mut out);
+                        }
+                        inner = None;
+                    }
+                }
+            }
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AssistantPart;
+    use crate::{Config, Response};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    fn text_response(deltas: &[&str]) -> Vec<StreamEvent> {
+        let mut events = vec![StreamEvent::PartStart {
+            index: 0,
+            kind: PartKind::Text,
+        }];
+        events.extend(deltas.iter().map(|d| StreamEvent::Delta {
+            index: 0,
+            delta: d.to_string(),
+        }));
+        events.push(StreamEvent::PartEnd { index: 0 });
+        events.push(StreamEvent::Done {
+            finish_reason: FinishReason::Stop,
+            usage: Usage::default(),
+        });
+        events
+    }
+
+    fn guarded(middleware: &StopGuardMiddleware, events: Vec<StreamEvent>) -> Response {
+        let prompt = Prompt::user("hi");
+        let config = Config::builder("m").build();
+        let mut prompt = Cow::Borrowed(&prompt);
+        let mut raw = Cow::Borrowed(config.raw());
+        let transform = middleware
+            .apply(&mut prompt, &mut raw, &Capabilities::default())
+            .unwrap()
+            .expect("patterns get a transform");
+        transform(Response::from_stream(stream::iter(
+            events.into_iter().map(Ok),
+        )))
+    }
+
+    fn deltas(events: &[Result<StreamEvent, Error>]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                Ok(StreamEvent::Delta { delta, .. }) => Some(delta.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn phrase_split_across_deltas_is_cut_and_never_emitted() {
+        let guard = StopGuardMiddleware::new().phrase("END");
+        let response = guarded(&guard, text_response(&["Hello wor", "ld E", "ND and more"]));
+        let events: Vec<_> = response.stream().collect().await;
+        assert_eq!(deltas(&events), ["Hello w", "orld", " "]);
+        assert!(matches!(
+            events.last(),
+            Some(Ok(StreamEvent::Done {
+                finish_reason: FinishReason::StopSequence,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unmatched_text_is_delivered_in_full() {
+        let guard = StopGuardMiddleware::new().phrase("STOP");
+        let complete = guarded(&guard, text_response(&["no st", "op h", "ére"]))
+            .buffer()
+            .await
+            .unwrap();
+        assert_eq!(complete.finish_reason, FinishReason::Stop);
+        match complete.content.as_slice() {
+            [AssistantPart::Text { content, .. }] => assert_eq!(content, "no stop hére"),
+            other => panic!("expected one text part, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn match_drops_the_upstream_stream() {
+        struct SetOnDrop(Arc<AtomicBool>);
+        impl Drop for SetOnDrop {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let marker = SetOnDrop(dropped.clone());
+        let upstream = stream::iter(text_response(&["a STOP b"]).into_iter().map(Ok))
+            .chain(stream::pending())
+            .map(move |e| {
+                let _ = &marker;
+                e
+            });
+        let prompt = Prompt::user("hi");
+        let config = Config::builder("m").build();
+        let transform = StopGuardMiddleware::new()
+            .phrase("STOP")
+            .apply(
+                &mut Cow::Borrowed(&prompt),
+                &mut Cow::Borrowed(config.raw()),
+                &Capabilities::default(),
+            )
+            .unwrap()
+            .unwrap();
+        let mut response = transform(Response::from_stream(upstream)).stream();
+        let mut last = None;
+        while let Some(event) = response.next().await {
+            last = Some(event.unwrap());
+        }
+        assert!(matches!(
+            last,
+            Some(StreamEvent::Done {
+                finish_reason: FinishReason::StopSequence,
+                ..
+            })
+        ));
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "regex")]
+    #[tokio::test]
+    async fn regex_matches_across_deltas() {
+        let guard = StopGuardMiddleware::new().regex(regex::Regex::new(r"\d{3}-\d{4}").unwrap(), 8);
+        let complete = guarded(&guard, text_response(&["call 55", "5-12", "34 now"]))
+            .buffer()
+            .await
+            .unwrap();
+        assert_eq!(complete.finish_reason, FinishReason::StopSequence);
+        match complete.content.as_slice() {
+            [AssistantPart::Text { content, .. }] => assert_eq!(content, "call "),
+            other => panic!("expected one text part, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,430 @@
+//! Tool-call argument validation.
+//!
+//! Models regularly emit tool arguments that don't match the declared
+//! schema — a required field left out, a number sent as a string. The
+//! provider passes them through verbatim, so the failure otherwise
+//! surfaces deep inside the caller's tool dispatch.
+//! [`ToolArgumentValidationMiddleware`] checks every completed tool
+//! call against its [`Function::parameters`] and fails the stream with
+//! a structured [`Error::InvalidToolArguments`] instead, which carries
+//! everything needed to re-ask the model.
+//!
+//! Opt-in: add it to the chain with
+//! [`crate::ConfigBuilder::with_middleware`] (alongside
+//! [`super::default_middleware`] if you want the polyfills too). For
+//! buffered responses, [`argument_problems`] runs the same check on a
+//! finished call.
+//!
+//! The checker understands the JSON Schema subset tool definitions use
+//! in practice: `type` (one or a list), `enum`, `const`, `properties`,
+//! `required`, `additionalProperties`, `items`, `anyOf` / `oneOf`, and
+//! the `minimum` / `maximum` / `minLength` / `maxLength` / `minItems` /
+//! `maxItems` bounds. Other keywords (`$ref`, `pattern`, `format`, …)
+//! are ignored rather than guessed at.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::pin::Pin;
+
+use futures_util::stream::{Stream, StreamExt};
+use serde_json::Value;
+
+use crate::types::{Function, PartKind, RawConfig, Tool};
+use crate::{Capabilities, Error, Prompt, StreamEvent};
+
+use super::{Middleware, ResponseTransform};
+
+/// Validates each completed tool call's arguments against the declared
+/// schema. On a mismatch the call's `PartEnd` is replaced by
+/// [`Error::InvalidToolArguments`] and the stream ends. Calls to tools
+/// the request didn't declare pass through unchecked. See the [module
+/// docs](self).
+#[derive(Debug, Default)]
+pub struct ToolArgumentValidationMiddleware;
+
+impl Middleware for ToolArgumentValidationMiddleware {
+    fn name(&self) -> &str {
+        "tool_argument_validation"
+    }
+
+    fn apply<'a>(
+        &self,
+        _prompt: &mut Cow<'a, Prompt>,
+        config: &mut Cow<'a, RawConfig>,
+        _capabilities: &Capabilities,
+    ) -> Result<Option<ResponseTransform>, Error> {
+        let functions: HashMap<String, Function> = config
+            .tools
+            .iter()
+            .flatten()
+            .filter_map(Tool::as_function)
+            .map(|f| (f.name.clone(), f.clone()))
+            .collect();
+        if functions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(move |response| {
+            response.map_stream(|stream| validate_tool_calls(stream, functions))
+        })))
+    }
+}
+
+/// Violations of `function`'s parameter schema in `arguments`, one per
+/// entry, each prefixed with its JSON path. Empty when the arguments
+/// conform. Unparseable arguments are a single problem.
+pub fn argument_problems(function: &Function, arguments: &str) -> Vec<String> {
+    let schema: Value = match serde_json::from_str(function.parameters.get()) {
+        Ok(schema) => schema,
+        Err(_) => return Vec::new(),
+    };
+    // Some providers send no arguments at all for a parameterless call.
+    let arguments = if arguments.trim().is_empty() {
+        "{}"
+    } else {
+        arguments
+    };
+    let value: Value = match serde_json::from_str(arguments) {
+        Ok(value) => value,
+        Err(e) => return vec![format!("$: arguments are not valid JSON: {e}")],
+    };
+    let mut problems = Vec::new();
+    check(&schema, &value, "$", &mut problems);
+    problems
+}
+
+/// One in-flight tool call.
+struct Pending {
+    call_id: String,
+    name: String,
+    arguments: String,
+}
+
+fn validate_tool_calls(
+    inner: Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>>,
+    functions: HashMap<String, Function>,
+) -> Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>> {
+    let mut pending: HashMap<u32, Pending> = HashMap::new();
+    Box::pin(
+        inner
+            .map(move |event| {
+                match &event {
+                    Ok(StreamEvent::PartStart {
+                        index,
+                        kind: PartKind::ToolCall { call_id, name },
+                    }) if functions.contains_key(name) => {
+                        pending.insert(
+                            *index,
+                            Pending {
+                                call_id: call_id.clone(),
+                                name: name.clone(),
+                                arguments: String::new(),
+                            },
+                        );
+                    }
+                    Ok(StreamEvent::Delta { index, delta }) => {
+                        if let Some(call) = pending.get_mut(index) {
+                            call.arguments.push_str(delta);
+                        }
+                    }
+                    Ok(StreamEvent::PartEnd { index }) => {
+                        if let Some(call) = pending.remove(index) {
+                            let problems =
+                                argument_problems(&functions[&call.name], &call.arguments);
+                            if !problems.is_empty() {
+                                return Err(Error::InvalidToolArguments {
+                                    call_id: call.call_id,
+                                    name: call.name,
+                                    arguments: call.arguments,
+                                    problems,
+                                });
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                event
+            })
+            // Nothing after a rejected call is meaningful.
+            .scan(false, |failed, event| {
+                if *failed {
+                    return futures_util::future::ready(None);
+                }
+                *failed = matches!(event, Err(Error::InvalidToolArguments { .. }));
+                futures_util::future::ready(Some(event))
+            }),
+    )
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    let actual = type_name(value);
+    actual == ty
+        || (ty == "number" && actual == "integer")
+        // `3.0` is an integer to JSON Schema.
+        || (ty == "integer" && value.as_f64().is_some_and(|n| n.fract() == 0.0))
+}
+
+fn check(schema: &Value, value: &Value, path: &str, problems: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true` / `{}`-like schemas accept anything; `false` nothing.
+        if schema == &Value::Bool(false) {
+            problems.push(format!("{path}: no value is allowed here"));
+        }
+        return;
+    };
+
+    if let Some(ty) = schema.get("type") {
+        let allowed: Vec<&str> = match ty {
+            Value::String(ty) => vec![ty.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|ty| has_type(value, ty)) {
+            problems.push(format!(
+                "{path}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            problems.push(format!(
+                "{path}: {value} is not one of {}",
+                Value::from(options.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            problems.push(format!("{path}: expected {expected}, got {value}"));
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(branches) = schema.get(keyword).and_then(Value::as_array) {
+            let matches = branches.iter().any(|branch| {
+                let mut scratch = Vec::new();
+                check(branch, value, path, &mut scratch);
+                scratch.is_empty()
+            });
+            if !matches {
+                problems.push(format!(
+                    "{path}: matches none of the {keyword} alternatives"
+                ));
+            }
+        }
+    }
+
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    match value {
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if bound("minimum").is_some_and(|min| n < min) {
+                problems.push(format!(
+                    "{path}: {n} is below the minimum of {}",
+                    schema["minimum"]
+                ));
+            }
+            if bound("maximum").is_some_and(|max| n > max) {
+                problems.push(format!(
+                    "{path}: {n} is above the maximum of {}",
+                    schema["maximum"]
+                ));
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as f64;
+            if bound("minLength").is_some_and(|min| len < min) {
+                problems.push(format!(
+                    "{path}: shorter than {} characters",
+                    schema["minLength"]
+                ));
+            }
+            if bound("maxLength").is_some_and(|max| len > max) {
+                problems.push(format!(
+                    "{path}: longer than {} characters",
+                    schema["maxLength"]
+                ));
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as f64;
+            if bound("minItems").is_some_and(|min| len < min) {
+                problems.push(format!("{path}: fewer than {} items", schema["minItems"]));
+            }
+            if bound("maxItems").is_some_and(|max| len > max) {
+                problems.push(format!("{path}: more than {} items", schema["maxItems"]));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}[{i}]"), problems);
+                }
+            }
+        }
+        Value::Object(fields) => {
+            for required in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !fields.contains_key(required) {
+                    problems.push(format!("{path}.{required}: missing required property"));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, field) in fields {
+                let field_path = format!("{path}.{key}");
+                match properties.and_then(|p| p.get(key)) {
+                    Some(field_schema) => check(field_schema, field, &field_path, problems),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            problems.push(format!("{field_path}: unexpected property"))
+                        }
+                        Some(extra @ Value::Object(_)) => {
+                            check(extra, field, &field_path, problems)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FinishReason, Usage};
+    use crate::{Config, Response};
+
+    const WEATHER: &str = r#"{
+        "type": "object",
+        "properties": {
+            "location": {"type": "string", "minLength": 1},
+            "unit": {"enum": ["celsius", "fahrenheit"]},
+            "days": {"type": "integer", "minimum": 1, "maximum": 14},
+            "tags": {"type": "array", "items": {"type": "string"}}
+        },
+        "required": ["location"],
+        "additionalProperties": false
+    }"#;
+
+    fn weather() -> Function {
+        Function {
+            name: "get_weather".to_string(),
+            description: None,
+            parameters: Cow::Owned(
+                serde_json::value::RawValue::from_string(WEATHER.to_string()).unwrap(),
+            ),
+        }
+    }
+
+    #[test]
+    fn conforming_arguments_have_no_problems() {
+        let problems = argument_problems(
+            &weather(),
+            r#"{"location":"Oslo","unit":"celsius","days":3.0,"tags":["a"]}"#,
+        );
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+
+    #[test]
+    fn every_violation_is_reported_with_its_path() {
+        let problems = argument_problems(
+            &weather(),
+            r#"{"unit":"kelvin","days":"3","tags":[1],"extra":true}"#,
+        );
+        assert_eq!(
+            problems,
+            [
+                "$.location: missing required property",
+                r#"$.days: expected integer, got string"#,
+                "$.extra: unexpected property",
+                r#"$.tags[0]: expected string, got integer"#,
+                r#"$.unit: "kelvin" is not one of ["celsius","fahrenheit"]"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn unparseable_arguments_are_one_problem() {
+        let problems = argument_problems(&weather(), r#"{"location":"Os"#);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("not valid JSON"), "{problems:?}");
+    }
+
+    fn tool_call_stream(arguments: &'static str) -> Response {
+        Response::from_stream(futures_util::stream::iter(vec![
+            Ok(StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::ToolCall {
+                    call_id: "c1".to_string(),
+                    name: "get_weather".to_string(),
+                },
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: arguments.to_string(),
+            }),
+            Ok(StreamEvent::PartEnd { index: 0 }),
+            Ok(StreamEvent::Done {
+                finish_reason: FinishReason::ToolCalls,
+                usage: Usage::default(),
+            }),
+        ]))
+    }
+
+    fn transform() -> ResponseTransform {
+        let prompt = Prompt::user("weather?");
+        let config = Config::builder("m")
+            .tools(vec![Tool::Function(weather())])
+            .build();
+        let mut prompt = Cow::Borrowed(&prompt);
+        let mut raw = Cow::Borrowed(config.raw());
+        ToolArgumentValidationMiddleware
+            .apply(&mut prompt, &mut raw, &Capabilities::default())
+            .unwrap()
+            .expect("declared tools get a transform")
+    }
+
+    #[tokio::test]
+    async fn invalid_call_fails_the_stream() {
+        let response = transform()(tool_call_stream(r#"{"days":2}"#));
+        match response.buffer().await {
+            Err(Error::InvalidToolArguments {
+                call_id,
+                name,
+                arguments,
+                problems,
+            }) => {
+                assert_eq!(call_id, "c1");
+                assert_eq!(name, "get_weather");
+                assert_eq!(arguments, r#"{"days":2}"#);
+                assert_eq!(problems, ["$.location: missing required property"]);
+            }
+            other => panic!("expected InvalidToolArguments, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_call_passes_through() {
+        let response = transform()(tool_call_stream(r#"{"location":"Oslo"}"#));
+        let complete = response.buffer().await.unwrap();
+        assert_eq!(complete.function_calls().len(), 1);
+    }
+}
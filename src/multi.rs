@@ -0,0 +1,242 @@
+//! Model-name routing across differently-configured providers.
+//!
+//! [`MultiProvider`] owns several named backends and picks one per
+//! call from the request's model string, so a single [`Provider`]
+//! handle can serve a mixed-model workload:
+//!
+//! ```ignore
+//! use platformed_llm::MultiProvider;
+//!
+//! let provider = MultiProvider::new()
+//!     .with_backend("openai", openai)
+//!     .with_backend("vertex", google)
+//!     .with_alias("fast", "vertex:gemini-2.5-flash");
+//!
+//! generate(&provider, &prompt, &Config::builder("openai:gpt-4o").build()).await?;
+//! generate(&provider, &prompt, &Config::builder("fast").build()).await?;
+//! ```
+//!
+//! # Resolution
+//!
+//! A model string is resolved in this order:
+//!
+//! 1. An exact alias match ([`MultiProvider::with_alias`]) is replaced
+//!    by its `backend:model` target.
+//! 2. `backend:model`, where `backend` is a registered backend name,
+//!    routes `model` to that backend. Only the first `:` separates, and
+//!    only when the part before it names a backend, so model names
+//!    that contain colons themselves (`ft:gpt-4o-mini:acme::abc123`,
+//!    `llama3:8b`) pass through intact.
+//! 3. Anything else goes to the default backend
+//!    ([`MultiProvider::with_default_backend`]) unchanged, or fails
+//!    with [`Error::Config`] when there is none.
+//!
+//! The backend receives the bare model name; the backend's name and
+//! that model are recorded on [`crate::ResponseMetadata`].
+//! [`MultiProvider::capabilities`] resolves the same way, so the
+//! middleware pipeline sees the capabilities of the backend that will
+//! actually serve the call.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::layer::SharedProvider;
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response};
+
+/// A [`Provider`] that routes each call to one of several named
+/// backends by its model string. See the module docs.
+#[derive(Clone, Default)]
+pub struct MultiProvider {
+    backends: HashMap<String, SharedProvider>,
+    /// Alias → `(backend, model)`.
+    aliases: HashMap<String, (String, String)>,
+    default_backend: Option<String>,
+}
+
+impl MultiProvider {
+    /// A provider with no backends. Every call fails with
+    /// [`Error::Config`] until at least one is added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `provider` under `name`, the prefix callers use in
+    /// `name:model`. Re-registering a name replaces its provider.
+    pub fn with_backend(self, name: impl Into<String>, provider: impl Provider) -> Self {
+        self.with_shared_backend(name, Arc::new(provider))
+    }
+
+    /// Register an already-shared backend (e.g. a converted
+    /// `Box<dyn Provider>` from [`crate::ProviderFactory`], or a
+    /// layered [`crate::ProviderStack`] output).
+    pub fn with_shared_backend(
+        mut self,
+        name: impl Into<String>,
+        provider: SharedProvider,
+    ) -> Self {
+        self.backends.insert(name.into(), provider);
+        self
+    }
+
+    /// Map `alias` to a `backend:model` target, e.g.
+    /// `with_alias("fast", "vertex:gemini-2.5-flash")`. The target is
+    /// resolved when a call uses the alias, so the backend may be
+    /// registered before or after.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target` has no `:` separating backend and model.
+    pub fn with_alias(mut self, alias: impl Into<String>, target: &str) -> Self {
+        let (backend, model) = target
+            .split_once(':')
+            .unwrap_or_else(|| panic!("alias target `{target}` must be `backend:model`"));
+        self.aliases
+            .insert(alias.into(), (backend.to_string(), model.to_string()));
+        self
+    }
+
+    /// Route model strings that match neither an alias nor a
+    /// registered prefix to the backend named `name`, unchanged.
+    pub fn with_default_backend(mut self, name: impl Into<String>) -> Self {
+        self.default_backend = Some(name.into());
+        self
+    }
+
+    /// Resolve `model` to the backend name and bare model it routes to.
+    pub fn resolve<'a>(&'a self, model: &'a str) -> Result<(&'a str, &'a str), Error> {
+        let (name, bare) = if let Some((backend, target)) = self.aliases.get(model) {
+            (backend.as_str(), target.as_str())
+        } else if let Some((prefix, rest)) = model
+            .split_once(':')
+            .filter(|(prefix, _)| self.backends.contains_key(*prefix))
+        {
+            (prefix, rest)
+        } else if let Some(default) = &self.default_backend {
+            (default.as_str(), model)
+        } else {
+            return Err(Error::config(format!(
+                "MultiProvider: model `{model}` names no known backend or alias"
+            )));
+        };
+        if !self.backends.contains_key(name) {
+            return Err(Error::config(format!(
+                "MultiProvider: model `{model}` routes to unknown backend `{name}`"
+            )));
+        }
+        Ok((name, bare))
+    }
+}
+
+impl std::fmt::Debug for MultiProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut backends: Vec<_> = self.backends.keys().collect();
+        backends.sort();
+        f.debug_struct("MultiProvider")
+            .field("backends", &backends)
+            .field("aliases", &self.aliases)
+            .field("default_backend", &self.default_backend)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for MultiProvider {
+    fn name(&self) -> &str {
+        "Multi"
+    }
+
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let (name, model) = self.resolve(&config.model)?;
+        let mut routed = config.clone();
+        routed.model = model.to_string();
+        let mut response = self.backends[name].generate(prompt, &routed).await?;
+        let metadata = response.metadata_mut();
+        metadata.served_by = Some(name.to_string());
+        metadata.model = Some(routed.model);
+        Ok(response)
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        match self.resolve(model) {
+            Ok((name, bare)) => self.backends[name].capabilities(bare),
+            Err(_) => Capabilities::for_model(model),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, Config};
+
+    fn provider() -> (MultiProvider, crate::providers::mock::CallLog) {
+        let openai = MockProvider::with_text("from openai");
+        let log = openai.call_log();
+        let provider = MultiProvider::new()
+            .with_backend("openai", openai)
+            .with_backend("vertex", MockProvider::with_text("from vertex"))
+            .with_alias("fast", "vertex:gemini-2.5-flash");
+        (provider, log)
+    }
+
+    async fn route(provider: &MultiProvider, model: &str) -> Result<(String, String), Error> {
+        let response = generate(
+            provider,
+            &Prompt::user("x"),
+            &Config::builder(model).build(),
+        )
+        .await?;
+        let served_by = response.metadata().served_by.clone().unwrap();
+        let model = response.metadata().model.clone().unwrap();
+        response.buffer().await?;
+        Ok((served_by, model))
+    }
+
+    #[tokio::test]
+    async fn prefix_routes_and_strips_backend_name() {
+        let (provider, log) = provider();
+        assert_eq!(
+            route(&provider, "openai:gpt-4o").await.unwrap(),
+            ("openai".into(), "gpt-4o".into())
+        );
+        assert_eq!(log.calls()[0].config.model, "gpt-4o");
+        assert_eq!(
+            route(&provider, "fast").await.unwrap(),
+            ("vertex".into(), "gemini-2.5-flash".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn colons_inside_model_names_survive() {
+        let (provider, log) = provider();
+        let provider = provider.with_default_backend("openai");
+        route(&provider, "openai:ft:gpt-4o-mini:acme::abc")
+            .await
+            .unwrap();
+        route(&provider, "ft:gpt-4o-mini:acme::abc").await.unwrap();
+        let calls = log.calls();
+        assert_eq!(calls[0].config.model, "ft:gpt-4o-mini:acme::abc");
+        assert_eq!(calls[1].config.model, "ft:gpt-4o-mini:acme::abc");
+    }
+
+    #[tokio::test]
+    async fn unknown_model_without_default_is_a_config_error() {
+        let (provider, _) = provider();
+        let err = route(&provider, "anthropic:claude").await.unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "got {err}");
+    }
+
+    #[test]
+    fn capabilities_follow_the_resolved_model() {
+        let (provider, _) = provider();
+        assert_eq!(
+            provider.capabilities("fast"),
+            Capabilities::for_model("gemini-2.5-flash")
+        );
+        assert_eq!(
+            provider.capabilities("openai:o3-mini"),
+            Capabilities::for_model("o3-mini")
+        );
+    }
+}
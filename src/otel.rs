@@ -0,0 +1,482 @@
+//! OpenTelemetry spans for every call, following the GenAI semantic
+//! conventions.
+//!
+//! [`OtelProvider`] wraps any [`Provider`] and records each call as a
+//! `chat {model}` client span, so LLM requests appear next to the rest
+//! of a request trace in whatever backend the application's
+//! OpenTelemetry SDK exports to. The crate depends on the
+//! `opentelemetry` API only: by default spans go to the global tracer
+//! provider (`opentelemetry::global::set_tracer_provider`), and
+//! [`OtelProvider::with_tracer`] takes an explicit tracer instead.
+//!
+//! ```ignore
+//! use platformed_llm::otel::OtelLayer;
+//!
+//! let provider = ProviderStack::new()
+//!     .layer(OtelLayer::new("openai"))
+//!     .service(openai);
+//! ```
+//!
+//! # Spans
+//!
+//! - **`chat {model}`** (kind `Client`) covers the whole call: the
+//!   upstream `generate` plus the response stream, ending when the
+//!   stream reaches `Done`, fails, or is dropped. It carries the
+//!   request attributes (`gen_ai.operation.name`,
+//!   `gen_ai.provider.name`, `gen_ai.request.model`,
+//!   `gen_ai.request.temperature` / `top_p` / `max_tokens`, plus
+//!   `tenant.id` for a call tagged with
+//!   [`ConfigBuilder::tenant`](crate::ConfigBuilder::tenant)), and once
+//!   the stream finishes `gen_ai.response.model`,
+//!   `gen_ai.response.finish_reasons`, `gen_ai.usage.input_tokens` /
+//!   `output_tokens` and `gen_ai.response.time_to_first_chunk`
+//!   (seconds). Failures set the span status to `Error` and
+//!   `error.type` to [`Error::kind`].
+//! - **`gen_ai.stream`** is a child covering only the response stream,
+//!   from the moment `generate` returns. It records a
+//!   `gen_ai.first_chunk` event at the first stream event and
+//!   `gen_ai.stream.events` (events delivered) when it ends.
+//!
+//! Prompt and completion *content* is never recorded — the
+//! conventions treat it as opt-in, and it routinely contains data that
+//! must not reach a tracing backend.
+//!
+//! The parent span is made current (as an OpenTelemetry [`Context`])
+//! while the inner `generate` runs, so spans created underneath —
+//! an instrumented HTTP client, a nested wrapper — nest under it.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Instant;
+
+use futures_util::Stream;
+use opentelemetry::context::FutureExt as _;
+use opentelemetry::global::BoxedTracer;
+use opentelemetry::trace::{SpanKind, Status, TraceContextExt, Tracer, TracerProvider};
+use opentelemetry::{Context, KeyValue};
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::response::EventStream;
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response, StreamEvent};
+
+/// Instrumentation scope name the spans are recorded under.
+pub const INSTRUMENTATION_SCOPE: &str = "platformed-llm";
+
+/// A [`Provider`] wrapper that records OpenTelemetry spans for every
+/// call. See the module docs.
+#[derive(Clone)]
+pub struct OtelProvider {
+    inner: SharedProvider,
+    provider_name: Arc<str>,
+    tracer: Arc<BoxedTracer>,
+}
+
+impl OtelProvider {
+    /// Instrument `inner`. `provider_name` is the
+    /// `gen_ai.provider.name` attribute — use the well-known values
+    /// (`"openai"`, `"gcp.vertex_ai"`, `"anthropic"`, …) where one
+    /// applies. Spans go to the global tracer provider.
+    pub fn new(inner: impl Provider, provider_name: impl Into<String>) -> Self {
+        Self::from_shared(Arc::new(inner), provider_name)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, provider_name: impl Into<String>) -> Self {
+        Self {
+            inner,
+            provider_name: Arc::from(provider_name.into()),
+            tracer: Arc::new(opentelemetry::global::tracer(INSTRUMENTATION_SCOPE)),
+        }
+    }
+
+    /// Record spans through `provider` instead of the global tracer
+    /// provider.
+    pub fn with_tracer_provider<P>(mut self, provider: &P) -> Self
+    where
+        P: TracerProvider,
+        P::Tracer: Send + Sync + 'static,
+        <P::Tracer as Tracer>::Span: Send + Sync + 'static,
+    {
+        self.tracer = Arc::new(BoxedTracer::new(Box::new(
+            provider.tracer(INSTRUMENTATION_SCOPE),
+        )));
+        self
+    }
+
+    /// Record spans through an explicit tracer.
+    pub fn with_tracer(mut self, tracer: BoxedTracer) -> Self {
+        self.tracer = Arc::new(tracer);
+        self
+    }
+}
+
+impl std::fmt::Debug for OtelProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OtelProvider")
+            .field("provider_name", &self.provider_name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for OtelProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let mut attributes = vec![
+            KeyValue::new("gen_ai.operation.name", "chat"),
+            KeyValue::new("gen_ai.provider.name", self.provider_name.to_string()),
+            KeyValue::new("gen_ai.request.model", config.model.clone()),
+        ];
+        if let Some(temperature) = config.temperature {
+            attributes.push(KeyValue::new(
+                "gen_ai.request.temperature",
+                f64::from(temperature),
+            ));
+        }
+        if let Some(top_p) = config.top_p {
+            attributes.push(KeyValue::new("gen_ai.request.top_p", f64::from(top_p)));
+        }
+        if let Some(max_tokens) = config.max_tokens {
+            attributes.push(KeyValue::new(
+                "gen_ai.request.max_tokens",
+                i64::from(max_tokens),
+            ));
+        }
+        if let Some(tenant) = config.tenant {
+            attributes.push(KeyValue::new("tenant.id", tenant.to_string()));
+        }
+        let span = self
+            .tracer
+            .span_builder(format!("chat {}", config.model))
+            .with_kind(SpanKind::Client)
+            .with_attributes(attributes)
+            .start_with_context(&*self.tracer, &Context::current());
+        let cx = Context::current_with_span(span);
+        let started = Instant::now();
+
+        match self
+            .inner
+            .generate(prompt, config)
+            .with_context(cx.clone())
+            .await
+        {
+            Ok(response) => {
+                let stream_span = self
+                    .tracer
+                    .span_builder("gen_ai.stream")
+                    .with_kind(SpanKind::Internal)
+                    .start_with_context(&*self.tracer, &cx);
+                let stream_cx = cx.with_span(stream_span);
+                let response_model = response.metadata().model.clone();
+                Ok(response.map_stream(move |stream| SpanStream {
+                    inner: stream,
+                    cx,
+                    stream_cx,
+                    started,
+                    response_model,
+                    events: 0,
+                    finished: false,
+                }))
+            }
+            Err(err) => {
+                record_error(&cx, &err);
+                cx.span().end();
+                Err(err)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+fn record_error(cx: &Context, err: &Error) {
+    let span = cx.span();
+    span.set_attribute(KeyValue::new("error.type", err.kind()));
+    span.set_status(Status::error(err.to_string()));
+}
+
+/// Event stream that closes the call's spans when it terminates.
+struct SpanStream {
+    inner: EventStream,
+    /// Context holding the `chat {model}` span.
+    cx: Context,
+    /// Context holding the child `gen_ai.stream` span.
+    stream_cx: Context,
+    started: Instant,
+    response_model: Option<String>,
+    events: u64,
+    finished: bool,
+}
+
+impl SpanStream {
+    fn finish(&mut self, outcome: Option<&Result<StreamEvent, Error>>) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        let span = self.cx.span();
+        if let Some(model) = self.response_model.take() {
+            span.set_attribute(KeyValue::new("gen_ai.response.model", model));
+        }
+        match outcome {
+            Some(Ok(StreamEvent::Done {
+                finish_reason,
+                usage,
+            })) => {
+                let reason = finish_reason.as_str().to_owned();
+                span.set_attribute(KeyValue::new(
+                    "gen_ai.response.finish_reasons",
+                    opentelemetry::Value::Array(
+                        vec![opentelemetry::StringValue::from(reason)].into(),
+                    ),
+                ));
+                span.set_attribute(KeyValue::new(
+                    "gen_ai.usage.input_tokens",
+                    i64::from(usage.input_tokens),
+                ));
+                span.set_attribute(KeyValue::new(
+                    "gen_ai.usage.output_tokens",
+                    i64::from(usage.output_tokens),
+                ));
+                span.set_status(Status::Ok);
+            }
+            Some(Err(err)) => {
+                record_error(&self.cx, err);
+                record_error(&self.stream_cx, err);
+            }
+            // Ended without `Done`: exhausted early or dropped by the
+            // caller. Not an error on our side, but worth seeing.
+            _ => span.set_attribute(KeyValue::new("gen_ai.stream.incomplete", true)),
+        }
+        let stream_span = self.stream_cx.span();
+        stream_span.set_attribute(KeyValue::new("gen_ai.stream.events", self.events as i64));
+        stream_span.end();
+        span.end();
+    }
+}
+
+impl Stream for SpanStream {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let item = match this.inner.as_mut().poll_next(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(item) => item,
+        };
+        match &item {
+            Some(Ok(event)) => {
+                this.events += 1;
+                if this.events == 1 {
+                    let ttft = this.started.elapsed().as_secs_f64();
+                    this.cx
+                        .span()
+                        .set_attribute(KeyValue::new("gen_ai.response.time_to_first_chunk", ttft));
+                    this.stream_cx
+                        .span()
+                        .add_event("gen_ai.first_chunk", Vec::new());
+                }
+                if matches!(event, StreamEvent::Done { .. }) {
+                    this.finish(item.as_ref());
+                }
+            }
+            Some(Err(_)) => this.finish(item.as_ref()),
+            None => this.finish(None),
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl Drop for SpanStream {
+    fn drop(&mut self) {
+        self.finish(None);
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in an [`OtelProvider`].
+#[derive(Clone)]
+pub struct OtelLayer {
+    provider_name: String,
+    tracer: Option<Arc<BoxedTracer>>,
+}
+
+impl OtelLayer {
+    /// A layer recording spans with `gen_ai.provider.name` set to
+    /// `provider_name`, through the global tracer provider.
+    pub fn new(provider_name: impl Into<String>) -> Self {
+        Self {
+            provider_name: provider_name.into(),
+            tracer: None,
+        }
+    }
+
+    /// See [`OtelProvider::with_tracer_provider`].
+    pub fn with_tracer_provider<P>(mut self, provider: &P) -> Self
+    where
+        P: TracerProvider,
+        P::Tracer: Send + Sync + 'static,
+        <P::Tracer as Tracer>::Span: Send + Sync + 'static,
+    {
+        self.tracer = Some(Arc::new(BoxedTracer::new(Box::new(
+            provider.tracer(INSTRUMENTATION_SCOPE),
+        ))));
+        self
+    }
+}
+
+impl std::fmt::Debug for OtelLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OtelLayer")
+            .field("provider_name", &self.provider_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProviderLayer for OtelLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        let mut provider = OtelProvider::from_shared(inner, self.provider_name.clone());
+        if let Some(tracer) = &self.tracer {
+            provider.tracer = tracer.clone();
+        }
+        Arc::new(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::{generate, Config};
+    use opentelemetry::Value;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+
+    fn setup() -> (InMemorySpanExporter, SdkTracerProvider) {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        (exporter, provider)
+    }
+
+    fn attr<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| &kv.value)
+    }
+
+    #[tokio::test]
+    async fn records_chat_and_stream_spans() {
+        let (exporter, tracer_provider) = setup();
+        let provider = OtelProvider::new(MockProvider::with_text("hello there"), "openai")
+            .with_tracer_provider(&tracer_provider);
+        let config = Config::builder("gpt-test")
+            .temperature(0.5)
+            .tenant(uuid::Uuid::from_u128(7))
+            .build();
+        let text = generate(&provider, &Prompt::user("hi"), &config)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(text, "hello there");
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let chat = spans.iter().find(|s| s.name == "chat gpt-test").unwrap();
+        let stream = spans.iter().find(|s| s.name == "gen_ai.stream").unwrap();
+        assert_eq!(stream.parent_span_id, chat.span_context.span_id());
+        assert_eq!(chat.span_kind, SpanKind::Client);
+        assert_eq!(chat.status, Status::Ok);
+        assert_eq!(
+            attr(chat, "gen_ai.provider.name"),
+            Some(&Value::from("openai"))
+        );
+        assert_eq!(
+            attr(chat, "gen_ai.request.model"),
+            Some(&Value::from("gpt-test"))
+        );
+        assert_eq!(
+            attr(chat, "gen_ai.request.temperature"),
+            Some(&Value::F64(0.5))
+        );
+        assert_eq!(
+            attr(chat, "tenant.id"),
+            Some(&Value::from(uuid::Uuid::from_u128(7).to_string()))
+        );
+        assert!(attr(chat, "gen_ai.usage.output_tokens").is_some());
+        assert!(attr(chat, "gen_ai.response.time_to_first_chunk").is_some());
+        assert_eq!(
+            attr(chat, "gen_ai.response.finish_reasons"),
+            Some(&Value::Array(
+                vec![opentelemetry::StringValue::from("stop")].into()
+            ))
+        );
+        assert_eq!(stream.events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn records_errors() {
+        let (exporter, tracer_provider) = setup();
+        let provider = OtelProvider::new(
+            MockProvider::builder()
+                .fail(Error::rate_limit(Some(1), "slow down"))
+                .reply(
+                    MockResponse::text("x")
+                        .with_stream_error(Error::provider_with_status("Mock", 502, "drop")),
+                )
+                .build(),
+            "openai",
+        )
+        .with_tracer_provider(&tracer_provider);
+        let config = Config::builder("m").build();
+        assert!(generate(&provider, &Prompt::user("hi"), &config)
+            .await
+            .is_err());
+        let response = generate(&provider, &Prompt::user("hi"), &config)
+            .await
+            .unwrap();
+        assert!(response.text().await.is_err());
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let errors: Vec<_> = spans
+            .iter()
+            .filter(|s| s.name == "chat m")
+            .map(|s| attr(s, "error.type").cloned())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                Some(Value::from("rate_limit")),
+                Some(Value::from("provider"))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn dropped_stream_still_ends_spans() {
+        let (exporter, tracer_provider) = setup();
+        let provider = OtelProvider::new(MockProvider::with_text("a b c"), "openai")
+            .with_tracer_provider(&tracer_provider);
+        let response = generate(
+            &provider,
+            &Prompt::user("hi"),
+            &Config::builder("m").build(),
+        )
+        .await
+        .unwrap();
+        drop(response);
+        let spans = exporter.get_finished_spans().unwrap();
+        let chat = spans.iter().find(|s| s.name == "chat m").unwrap();
+        assert_eq!(
+            attr(chat, "gen_ai.stream.incomplete"),
+            Some(&Value::Bool(true))
+        );
+    }
+}
@@ -0,0 +1,426 @@
+//! Named provider profiles loaded from a configuration file.
+//!
+//! One file describes every backend a deployment talks to, each under a
+//! profile name. Environment variables only describe one provider at a
+//! time; profiles let a multi-tenant service keep `prod-gemini`,
+//! `eu-claude` and `batch-openai` side by side and pick one by name.
+//!
+//! ```toml
+//! [profiles.batch-openai]
+//! provider = "openai"
+//! model = "gpt-5-mini"
+//! base_url = "https://llm-gateway.internal/v1"
+//! auth = { method = "api_key", env = "OPENAI_API_KEY" }
+//! defaults = { max_tokens = 2048 }
+//!
+//! [profiles.eu-claude]
+//! provider = "anthropic"
+//! model = "claude-sonnet-4-5"
+//! project_id = "acme-prod"
+//! location = "europe-west1"
+//! auth = { method = "service_account", key_file = "/etc/acme/vertex.json" }
+//! timeout_secs = 60
+//! ```
+//!
+//! The same shape works as YAML (`.yaml` / `.yml`). Secrets are read
+//! from environment variables named in the file rather than stored in
+//! it. When `auth` is omitted, OpenAI reads `OPENAI_API_KEY` and the
+//! Vertex providers use Application Default Credentials.
+//!
+//! [`ProviderProfiles::config`] turns a profile into a
+//! [`ProviderConfig`] for [`ProviderFactory::create`], with the
+//! profile's model, temperature and max_tokens as its
+//! [`ProviderConfig::defaults`];
+//! [`ProviderProfile::config_builder`] seeds a request
+//! [`ConfigBuilder`] with the profile's model and defaults.
+//! [`ProviderFactory::create_named`] does the whole lookup from the
+//! file named by `LLM_CONFIG_FILE`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{
+    Config, ConfigBuilder, Error, ProviderConfig, ProviderFactory, ProviderType, RequestDefaults,
+};
+
+/// Environment variable [`ProviderFactory::create_named`] reads the
+/// profile file path from.
+pub const CONFIG_FILE_ENV: &str = "LLM_CONFIG_FILE";
+
+/// A parsed profile file: profile name → [`ProviderProfile`]. See the
+/// [module docs](self) for the format.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderProfiles {
+    profiles: BTreeMap<String, ProviderProfile>,
+}
+
+/// One named backend in a [`ProviderProfiles`] file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ProviderProfile {
+    /// Which backend to instantiate: `openai`, `google` or `anthropic`.
+    pub provider: ProviderType,
+    /// Model requests through this profile use by default.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// GCP project ID (Vertex providers).
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// GCP region (Vertex providers). Defaults to `europe-west1`, as
+    /// with [`ProviderConfig::from_env`].
+    #[serde(default)]
+    pub location: Option<String>,
+    /// Endpoint override; see [`ProviderConfig::base_url`].
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// How to authenticate. Provider-dependent default when absent.
+    #[serde(default)]
+    pub auth: Option<ProfileAuth>,
+    /// Whole-call deadline; see [`ProviderConfig::timeout`].
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Extra HTTP headers; see [`ProviderConfig::extra_headers`].
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Request parameters applied by [`Self::config_builder`].
+    #[serde(default)]
+    pub defaults: ProfileDefaults,
+}
+
+/// Authentication method of a [`ProviderProfile`], tagged by `method`.
+/// Variants that take a secret name the environment variable holding
+/// it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case", deny_unknown_fields)]
+#[non_exhaustive]
+pub enum ProfileAuth {
+    /// API key from `env`: OpenAI's key, or Vertex express mode for
+    /// `google`.
+    ApiKey {
+        /// Environment variable holding the key.
+        env: String,
+    },
+    /// Pre-fetched Vertex OAuth access token from `env`.
+    AccessToken {
+        /// Environment variable holding the token.
+        env: String,
+    },
+    /// Vertex Application Default Credentials.
+    Adc {
+        /// OAuth scopes; `cloud-platform` when empty.
+        #[serde(default)]
+        scopes: Vec<String>,
+    },
+    /// Vertex tokens from a service-account JSON key file.
+    ServiceAccount {
+        /// Path to the key file.
+        key_file: PathBuf,
+    },
+    /// Vertex tokens impersonating `target`, from `key_file` when set
+    /// and ambient ADC otherwise.
+    Impersonate {
+        /// Service-account email to impersonate.
+        target: String,
+        /// Source identity key file.
+        #[serde(default)]
+        key_file: Option<PathBuf>,
+    },
+}
+
+/// Request defaults of a [`ProviderProfile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ProfileDefaults {
+    /// Default `temperature`.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Default `max_tokens`.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Default `top_p`.
+    #[serde(default)]
+    pub top_p: Option<f32>,
+}
+
+impl ProviderProfiles {
+    /// Read and parse a profile file, choosing the format by extension:
+    /// `.toml`, or `.yaml` / `.yml`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            Error::config(format!("cannot read profile file {}: {e}", path.display()))
+        })?;
+        let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&text),
+            Some("yaml" | "yml") => Self::from_yaml_str(&text),
+            _ => Err(Error::config(
+                "profile file must end in .toml, .yaml or .yml",
+            )),
+        };
+        parsed.map_err(|e| Error::config(format!("{}: {e}", path.display())))
+    }
+
+    /// Parse profiles from TOML text.
+    pub fn from_toml_str(text: &str) -> Result<Self, Error> {
+        toml::from_str(text).map_err(|e| Error::config(format!("invalid profile TOML: {e}")))
+    }
+
+    /// Parse profiles from YAML text.
+    pub fn from_yaml_str(text: &str) -> Result<Self, Error> {
+        serde_yaml::from_str(text).map_err(|e| Error::config(format!("invalid profile YAML: {e}")))
+    }
+
+    /// Profile names, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// The profile called `name`.
+    pub fn get(&self, name: &str) -> Option<&ProviderProfile> {
+        self.profiles.get(name)
+    }
+
+    /// The [`ProviderConfig`] for the profile called `name`. Fails with
+    /// [`Error::Config`] for an unknown name or a credential the
+    /// environment doesn't supply.
+    pub fn config(&self, name: &str) -> Result<ProviderConfig, Error> {
+        self.get(name)
+            .ok_or_else(|| Error::config(format!("no provider profile named `{name}`")))?
+            .provider_config()
+    }
+}
+
+impl ProviderProfile {
+    /// Build the [`ProviderConfig`] this profile describes, resolving
+    /// any environment-held secrets now.
+    pub fn provider_config(&self) -> Result<ProviderConfig, Error> {
+        let mut config = match (&self.provider, &self.auth) {
+            (ProviderType::OpenAI, None) => ProviderConfig::openai(secret("OPENAI_API_KEY")?),
+            (ProviderType::OpenAI, Some(ProfileAuth::ApiKey { env })) => {
+                ProviderConfig::openai(secret(env)?)
+            }
+            (ProviderType::OpenAI, Some(auth)) => {
+                return Err(Error::config(format!(
+                    "OpenAI profiles authenticate with an API key, not {auth:?}"
+                )))
+            }
+            (ProviderType::Google, Some(ProfileAuth::ApiKey { env })) => {
+                ProviderConfig::vertex_express(secret(env)?)
+            }
+            (provider, auth) => {
+                let project_id = self.project_id.clone().ok_or_else(|| {
+                    Error::config(format!("project_id is required for {provider:?} profiles"))
+                })?;
+                let location = self
+                    .location
+                    .clone()
+                    .unwrap_or_else(|| "europe-west1".to_string());
+                match auth {
+                    Some(ProfileAuth::AccessToken { env }) => ProviderConfig::vertex(
+                        provider.clone(),
+                        project_id,
+                        location,
+                        secret(env)?,
+                    )?,
+                    Some(ProfileAuth::ApiKey { .. }) => {
+                        return Err(Error::config(
+                            "Vertex express-mode API keys only reach Gemini; \
+                             Claude on Vertex needs OAuth credentials",
+                        ))
+                    }
+                    None | Some(ProfileAuth::Adc { .. }) => {
+                        ProviderConfig::vertex_with_adc(provider.clone(), project_id, location)?
+                    }
+                    Some(ProfileAuth::ServiceAccount { key_file }) => {
+                        ProviderConfig::vertex_with_adc(provider.clone(), project_id, location)?
+                            .with_vertex_service_account_key(key_file.clone())
+                    }
+                    Some(ProfileAuth::Impersonate { target, key_file }) => {
+                        let config = ProviderConfig::vertex_with_adc(
+                            provider.clone(),
+                            project_id,
+                            location,
+                        )?
+                        .with_vertex_impersonation(target.clone());
+                        match key_file {
+                            Some(key_file) => {
+                                config.with_vertex_service_account_key(key_file.clone())
+                            }
+                            None => config,
+                        }
+                    }
+                }
+            }
+        };
+        if let Some(ProfileAuth::Adc { scopes }) = &self.auth {
+            config = config.with_vertex_scopes(scopes.iter().cloned());
+        }
+        if let Some(base_url) = &self.base_url {
+            config = config.with_base_url(base_url.clone());
+        }
+        if let Some(secs) = self.timeout_secs {
+            config = config.with_timeout(Duration::from_secs(secs));
+        }
+        for (name, value) in &self.headers {
+            config = config.with_header(name.clone(), value.clone());
+        }
+        config.defaults = RequestDefaults {
+            model: self.model.clone(),
+            temperature: self.defaults.temperature,
+            max_tokens: self.defaults.max_tokens,
+        };
+        Ok(config)
+    }
+
+    /// A request [`ConfigBuilder`] for this profile's model with its
+    /// [`ProfileDefaults`] applied. Fails with [`Error::Config`] when
+    /// the profile names no model.
+    pub fn config_builder(&self) -> Result<ConfigBuilder, Error> {
+        let model = self
+            .model
+            .as_ref()
+            .ok_or_else(|| Error::config("provider profile has no model"))?;
+        let mut builder = Config::builder(model.clone());
+        if let Some(temperature) = self.defaults.temperature {
+            builder = builder.temperature(temperature);
+        }
+        if let Some(max_tokens) = self.defaults.max_tokens {
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Some(top_p) = self.defaults.top_p {
+            builder = builder.top_p(top_p);
+        }
+        Ok(builder)
+    }
+}
+
+impl ProviderConfig {
+    /// The [`ProviderConfig`] for profile `profile` in the file at
+    /// `path`. Shorthand for [`ProviderProfiles::from_file`] +
+    /// [`ProviderProfiles::config`].
+    pub fn from_file(path: impl AsRef<Path>, profile: &str) -> Result<Self, Error> {
+        ProviderProfiles::from_file(path)?.config(profile)
+    }
+}
+
+impl ProviderFactory {
+    /// Create the provider for profile `name` from the profile file at
+    /// the path in the `LLM_CONFIG_FILE` environment variable. See
+    /// [`ProviderProfiles`].
+    pub async fn create_named(name: &str) -> Result<Box<dyn crate::Provider>, Error> {
+        let path = std::env::var(CONFIG_FILE_ENV)
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .ok_or_else(|| {
+                Error::config(format!(
+                    "{CONFIG_FILE_ENV} environment variable is required for named profiles"
+                ))
+            })?;
+        Self::create(&ProviderConfig::from_file(path, name)?).await
+    }
+}
+
+/// A non-empty secret from environment variable `name`.
+fn secret(name: &str) -> Result<String, Error> {
+    match std::env::var(name) {
+        Ok(value) if !value.trim().is_empty() => Ok(value),
+        _ => Err(Error::config(format!(
+            "{name} environment variable is required and must be non-empty"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+        [profiles.gateway]
+        provider = "openai"
+        model = "gpt-5-mini"
+        base_url = "https://gateway.internal/v1"
+        auth = { method = "api_key", env = "PROFILES_TEST_KEY" }
+        headers = { x-tenant = "acme" }
+        defaults = { max_tokens = 2048, temperature = 0.3 }
+
+        [profiles.eu-claude]
+        provider = "anthropic"
+        project_id = "acme-prod"
+        auth = { method = "impersonate", target = "llm@acme.iam.gserviceaccount.com" }
+        timeout_secs = 60
+    "#;
+
+    #[test]
+    fn toml_profiles_build_provider_configs() {
+        // SAFETY: no other test reads or writes this variable.
+        unsafe { std::env::set_var("PROFILES_TEST_KEY", "sk-test") };
+        let profiles = ProviderProfiles::from_toml_str(TOML).unwrap();
+        assert_eq!(
+            profiles.names().collect::<Vec<_>>(),
+            ["eu-claude", "gateway"]
+        );
+
+        let gateway = profiles.config("gateway").unwrap();
+        assert_eq!(gateway.provider_type, ProviderType::OpenAI);
+        assert_eq!(gateway.api_key.as_deref(), Some("sk-test"));
+        assert_eq!(
+            gateway.base_url.as_deref(),
+            Some("https://gateway.internal/v1")
+        );
+        assert_eq!(gateway.extra_headers, [("x-tenant".into(), "acme".into())]);
+        assert_eq!(gateway.defaults.model.as_deref(), Some("gpt-5-mini"));
+        assert_eq!(gateway.defaults.max_tokens, Some(2048));
+
+        let claude = profiles.config("eu-claude").unwrap();
+        assert_eq!(claude.provider_type, ProviderType::Anthropic);
+        assert_eq!(claude.location.as_deref(), Some("europe-west1"));
+        assert_eq!(
+            claude.vertex_impersonate.as_deref(),
+            Some("llm@acme.iam.gserviceaccount.com")
+        );
+        assert_eq!(claude.timeout, Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn config_builder_applies_model_and_defaults() {
+        let profiles = ProviderProfiles::from_toml_str(TOML).unwrap();
+        let config = profiles
+            .get("gateway")
+            .unwrap()
+            .config_builder()
+            .unwrap()
+            .build();
+        assert_eq!(config.raw().model, "gpt-5-mini");
+        assert_eq!(config.raw().max_tokens, Some(2048));
+        assert_eq!(config.raw().temperature, Some(0.3));
+        assert!(profiles.get("eu-claude").unwrap().config_builder().is_err());
+    }
+
+    #[test]
+    fn yaml_profiles_parse_the_same_shape() {
+        let profiles = ProviderProfiles::from_yaml_str(
+            "profiles:\n  gemini:\n    provider: google\n    project_id: p\n    location: us-central1\n    auth:\n      method: adc\n      scopes: [a, b]\n",
+        )
+        .unwrap();
+        let config = profiles.config("gemini").unwrap();
+        assert_eq!(config.provider_type, ProviderType::Google);
+        assert_eq!(config.location.as_deref(), Some("us-central1"));
+        assert_eq!(config.vertex_scopes, ["a", "b"]);
+    }
+
+    #[test]
+    fn typos_and_unknown_profiles_are_config_errors() {
+        let typo = ProviderProfiles::from_toml_str(
+            "[profiles.x]\nprovider = \"openai\"\nmodle = \"gpt-4o\"\n",
+        );
+        assert!(matches!(typo, Err(Error::Config(_))));
+        let profiles = ProviderProfiles::from_toml_str(TOML).unwrap();
+        assert!(matches!(profiles.config("missing"), Err(Error::Config(_))));
+    }
+}
@@ -23,6 +23,19 @@ pub trait Provider: Send + Sync + 'static {
     /// consume token-by-token or buffer.)
     async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error>;
 
+    /// Name of the backend serving calls — `"OpenAI"`, `"Google"`,
+    /// `"Anthropic"`, … — for logs and metrics in code that only holds
+    /// a `dyn Provider`. The same name attributes the backend's
+    /// [`Error::Provider`] errors.
+    ///
+    /// Wrappers (retries, timeouts, middleware layers) report the
+    /// provider they wrap. The default is the implementing type's
+    /// name; custom providers should override it with something
+    /// shorter.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
     /// Report the [`Capabilities`] of `model` as understood by this
     /// provider. Called by [`crate::generate`] at request time to
     /// decide which middleware should run.
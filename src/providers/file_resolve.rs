@@ -95,8 +95,8 @@ pub(crate) fn media_type_extension(media_type: &str) -> &'static str {
 
 /// Percent-encode `s` for a URL path/query segment, encoding everything outside
 /// the RFC 3986 unreserved set (so `/` becomes `%2F`). Used when building the
-/// GCS upload URL's `name=` parameter.
-#[cfg(feature = "google")]
+/// GCS upload URL's `name=` parameter and Entra token request forms.
+#[cfg(any(feature = "google", feature = "openai"))]
 pub(crate) fn percent_encode(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for b in s.bytes() {
@@ -132,7 +132,7 @@ fn collect_ref_ids(items: &[InputItem]) -> Vec<String> {
     let mut ids = Vec::new();
     let mut seen = HashSet::new();
     for item in items {
-        if let InputItem::User { content } = item {
+        if let InputItem::User { content, .. } = item {
             walk(content, &mut ids, &mut seen);
         }
     }
@@ -335,6 +335,8 @@ mod tests {
     fn img_ref(id: &str) -> InputItem {
         InputItem::User {
             content: vec![UserPart::Image(FileSource::Ref(id.to_string()))],
+            name: None,
+            metadata: Default::default(),
         }
     }
 
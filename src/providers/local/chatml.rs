@@ -83,9 +83,17 @@ impl ChatTemplate for ChatMlTemplate {
         let tool_hint = render_tool_hint(tools, tool_choice);
         let mut tool_hint_emitted = tool_hint.is_none();
 
-        for item in prompt.items() {
+        // A trailing prefill is rendered as the open assistant turn
+        // below rather than as a closed one.
+        let prefill = prompt.assistant_prefill();
+        let items = match prefill {
+            Some(_) => &prompt.items()[..prompt.items().len() - 1],
+            None => prompt.items(),
+        };
+        for item in items {
             match item {
-                InputItem::System(content) => {
+                // ChatML templates have no developer role.
+                InputItem::System(content) | InputItem::Developer(content) => {
                     out.push_str("<|im_start|>system\n");
                     out.push_str(content);
                     if let Some(hint) = &tool_hint {
@@ -97,7 +105,7 @@ impl ChatTemplate for ChatMlTemplate {
                     }
                     out.push_str("<|im_end|>\n");
                 }
-                InputItem::User { content } => {
+                InputItem::User { content, .. } => {
                     // No system turn yet but tools are declared — emit
                     // a synthetic system turn before the first user
                     // turn so the model sees the tools manifest.
@@ -111,7 +119,7 @@ impl ChatTemplate for ChatMlTemplate {
                     }
                     render_user_turn(&mut out, content);
                 }
-                InputItem::Assistant { content } => {
+                InputItem::Assistant { content, .. } => {
                     render_assistant_turn(&mut out, content);
                 }
             }
@@ -119,6 +127,7 @@ impl ChatTemplate for ChatMlTemplate {
 
         // Open an assistant turn for the model to complete.
         out.push_str("<|im_start|>assistant\n");
+        out.push_str(prefill.unwrap_or_default());
         out
     }
 
@@ -443,6 +452,7 @@ mod tests {
             name: "get_weather".into(),
             arguments: r#"{"city":"Paris"}"#.into(),
             provider_signature: None,
+            original_arguments: None,
         };
         let p = Prompt::user("hi")
             .with_item(InputItem::assistant_tool_call(call))
@@ -825,4 +835,18 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn render_leaves_prefilled_assistant_turn_open() {
+        let p = Prompt::user("hi")
+            .with_assistant("hello")
+            .with_user("json please")
+            .with_assistant_prefill("{\"");
+        let out = ChatMlTemplate::new().render(&p, &[], None);
+        let expected = "<|im_start|>user\nhi<|im_end|>\n\
+                        <|im_start|>assistant\nhello<|im_end|>\n\
+                        <|im_start|>user\njson please<|im_end|>\n\
+                        <|im_start|>assistant\n{\"";
+        assert_eq!(out, expected);
+    }
 }
@@ -120,6 +120,10 @@ impl LlamaGgufProvider {
 
 #[async_trait]
 impl Provider for LlamaGgufProvider {
+    fn name(&self) -> &str {
+        "llama-gguf"
+    }
+
     /// Local llama-gguf has no native JSON mode, no schema-constrained
     /// output, no schema+tools. Return [`crate::Capabilities::default`]
     /// (everything false) — the default middleware chain will
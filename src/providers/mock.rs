@@ -29,6 +29,12 @@
 //!   reply based on the incoming [`Prompt`] / [`crate::RawConfig`],
 //!   enabling full tool-call-loop tests.
 //!
+//! On top of any mode, [`MockProviderBuilder::when`] adds per-request
+//! matchers: a call whose prompt / config satisfies a matcher gets that
+//! matcher's reply and leaves the queue untouched. Handy when only a few
+//! requests (say, the one carrying a tool result) need special handling
+//! and the rest are plain scripted turns.
+//!
 //! Every mode records the `(Prompt, RawConfig)` of each call; grab a
 //! [`CallLog`] via [`MockProvider::call_log`] *before* moving the provider
 //! into the code under test, then assert on what your code actually sent.
@@ -121,7 +127,7 @@ impl Chunking {
 /// Split into "word + trailing whitespace" chunks. Concatenation
 /// reproduces the input exactly; a leading run of whitespace becomes its
 /// own chunk.
-fn split_words(s: &str) -> Vec<String> {
+pub(super) fn split_words(s: &str) -> Vec<String> {
     let mut chunks = Vec::new();
     let mut cur = String::new();
     let mut in_word = false;
@@ -173,6 +179,8 @@ enum Repr {
     /// is responsible for a well-formed sequence (monotonic part indices,
     /// terminal `Done`).
     Raw(Vec<StreamEvent>),
+    /// Play this script, delays included. Chunking does not apply.
+    Script(super::script::StreamScript),
 }
 
 impl MockResponse {
@@ -227,6 +235,14 @@ impl MockResponse {
         Self(Repr::Raw(events))
     }
 
+    /// Play a [`super::script::StreamScript`] — exact events with
+    /// delays and mid-stream errors. Like [`MockResponse::raw_events`],
+    /// [`Chunking`], [`MockResponse::usage`] and
+    /// [`MockResponse::with_stream_error`] don't apply.
+    pub fn script(script: super::script::StreamScript) -> Self {
+        Self(Repr::Script(script))
+    }
+
     /// Set the token-usage counters reported by the terminal `Done`.
     /// No-op on a [`MockResponse::raw_events`] response.
     pub fn usage(mut self, usage: Usage) -> Self {
@@ -265,10 +281,11 @@ impl From<String> for MockResponse {
     }
 }
 
-/// Lower a [`MockResponse`] into the event sequence a real provider would
-/// stream.
-fn lower_response(resp: MockResponse, chunking: &Chunking) -> Vec<Result<StreamEvent, Error>> {
-    match resp.0 {
+/// Lower a [`MockResponse`] into the event stream a real provider would
+/// produce.
+fn lower_response(resp: MockResponse, chunking: &Chunking) -> crate::EventStream {
+    let events = match resp.0 {
+        Repr::Script(script) => return script.into_stream(),
         Repr::Raw(events) => events.into_iter().map(Ok).collect(),
         Repr::Parts {
             content,
@@ -292,7 +309,8 @@ fn lower_response(resp: MockResponse, chunking: &Chunking) -> Vec<Result<StreamE
             }
             out
         }
-    }
+    };
+    Box::pin(futures_util::stream::iter(events))
 }
 
 /// Extract an owned [`Error`] from an [`std::sync::Arc<Error>`],
@@ -312,17 +330,18 @@ fn lower_response(resp: MockResponse, chunking: &Chunking) -> Vec<Result<StreamE
 ///
 /// The fallback rebuilds the error by hand. Variants that don't
 /// carry non-`Clone` payloads (`RateLimit`, `Auth`,
-/// `ContextWindowExceeded`, `ModelNotAvailable`, `InvalidPrompt`,
-/// `Config`, `Compaction`, `UnsupportedInput`) are reconstructed
-/// faithfully so callers can match on them. The remaining variants
-/// (`Transport` — wraps a non-`Clone` `reqwest::Error`,
-/// `Serialization` — same, and `Provider` — easiest to rebuild
-/// from-scratch) collapse to a synthetic `Provider("Mock", …)`
+/// `ContextWindowExceeded`, `ContentPolicy`, `ModelNotAvailable`, `InvalidPrompt`,
+/// `Config`, `Compaction`, `UnsupportedInput`, `Timeout`, `IdleTimeout`, `StreamOverflow`,
+/// `SseLimitExceeded`, `Provider`) are
+/// reconstructed faithfully so callers can match on them. The
+/// remaining variants (`Transport` — wraps a non-`Clone`
+/// `reqwest::Error`, and `Serialization` — same) collapse to a
+/// synthetic `Provider("Mock", …)`
 /// carrying the inner's `is_retryable()` verdict. Without that
 /// preservation, a shared mid-stream rate limit would silently
 /// downgrade to a non-retryable provider error and the caller's
 /// retry loop would give up.
-fn unwrap_shared_error(error: std::sync::Arc<Error>) -> Error {
+pub(super) fn unwrap_shared_error(error: std::sync::Arc<Error>) -> Error {
     std::sync::Arc::try_unwrap(error).unwrap_or_else(|arc| {
         // Reconstruct cloneable variants by hand so callers can
         // still match on the original tag (`compaction` needs to
@@ -331,19 +350,43 @@ fn unwrap_shared_error(error: std::sync::Arc<Error>) -> Error {
             Error::RateLimit {
                 retry_after,
                 message,
+                details,
             } => Error::RateLimit {
                 retry_after: *retry_after,
                 message: message.clone(),
+                details: details.clone(),
             },
-            Error::Auth { status, message } => Error::Auth {
+            Error::Auth {
+                status,
+                message,
+                details,
+            } => Error::Auth {
                 status: *status,
                 message: message.clone(),
+                details: details.clone(),
             },
-            Error::ContextWindowExceeded { provider, message } => Error::ContextWindowExceeded {
+            Error::ContextWindowExceeded {
+                provider,
+                message,
+                details,
+            } => Error::ContextWindowExceeded {
                 provider,
                 message: message.clone(),
+                details: details.clone(),
+            },
+            Error::ContentPolicy {
+                provider,
+                message,
+                details,
+            } => Error::ContentPolicy {
+                provider,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            Error::ModelNotAvailable { message, details } => Error::ModelNotAvailable {
+                message: message.clone(),
+                details: details.clone(),
             },
-            Error::ModelNotAvailable(s) => Error::ModelNotAvailable(s.clone()),
             Error::InvalidPrompt(s) => Error::InvalidPrompt(s.clone()),
             Error::Config(s) => Error::Config(s.clone()),
             Error::Compaction { reason } => Error::Compaction {
@@ -352,17 +395,45 @@ fn unwrap_shared_error(error: std::sync::Arc<Error>) -> Error {
             Error::UnsupportedInput { provider, modality } => {
                 Error::UnsupportedInput { provider, modality }
             }
-            // Provider rebuilt from-scratch (cheaper than figuring
-            // out which fields to preserve when the most-common case
-            // is a test-supplied error anyway). Falls into the
-            // catch-all for the non-cloneable variants too
-            // (`Transport`, `Serialization`).
+            Error::Timeout(after) => Error::Timeout(*after),
+            Error::IdleTimeout(after) => Error::IdleTimeout(*after),
+            Error::StreamOverflow { capacity } => Error::StreamOverflow {
+                capacity: *capacity,
+            },
+            Error::SseLimitExceeded {
+                provider,
+                what,
+                limit,
+            } => Error::SseLimitExceeded {
+                provider,
+                what,
+                limit: *limit,
+            },
+            Error::Provider {
+                provider,
+                status,
+                retryable,
+                retry_after,
+                message,
+                details,
+            } => Error::Provider {
+                provider,
+                status: *status,
+                retryable: *retryable,
+                retry_after: *retry_after,
+                message: message.clone(),
+                details: details.clone(),
+            },
+            // The non-cloneable variants (`Transport`,
+            // `Serialization`) collapse to a synthetic provider error
+            // that keeps the retry verdict.
             other => Error::Provider {
                 provider: "Mock",
                 status: None,
                 retryable: other.is_retryable(),
                 retry_after: other.retry_after(),
                 message: format!("mid-stream error (cloned): {arc}"),
+                details: None,
             },
         }
     })
@@ -528,6 +599,8 @@ impl CallLog {
 
 type Handler = Box<dyn Fn(&Prompt, &RawConfig) -> MockResponse + Send + Sync>;
 
+type Matcher = Box<dyn Fn(&Prompt, &RawConfig) -> bool + Send + Sync>;
+
 enum Mode {
     Queue(Mutex<VecDeque<Reply>>),
     Always(MockResponse),
@@ -538,6 +611,9 @@ enum Mode {
 /// [module docs](self) for the full picture.
 pub struct MockProvider {
     mode: Mode,
+    /// Consulted in order before `mode`; the first match wins and is
+    /// not consumed.
+    matchers: Vec<(Matcher, MockResponse)>,
     chunking: Chunking,
     log: Arc<Mutex<Vec<RecordedCall>>>,
     /// Cooperative rate limiter consulted before each scripted reply
@@ -551,6 +627,7 @@ impl MockProvider {
     fn new(mode: Mode, chunking: Chunking) -> Self {
         Self {
             mode,
+            matchers: Vec::new(),
             chunking,
             log: Arc::new(Mutex::new(Vec::new())),
             rate_limiter: crate::rate_limit::default_shared_limiter(),
@@ -564,6 +641,7 @@ impl MockProvider {
         MockProviderBuilder {
             chunking: Chunking::default(),
             replies: VecDeque::new(),
+            matchers: Vec::new(),
         }
     }
 
@@ -615,6 +693,13 @@ impl MockProvider {
     }
 
     fn next_reply(&self, prompt: &Prompt, config: &RawConfig) -> Result<Reply, Error> {
+        if let Some((_, response)) = self
+            .matchers
+            .iter()
+            .find(|(matches, _)| matches(prompt, config))
+        {
+            return Ok(Reply::Respond(response.clone()));
+        }
         match &self.mode {
             Mode::Always(response) => Ok(Reply::Respond(response.clone())),
             Mode::Handler(handler) => Ok(Reply::Respond(handler(prompt, config))),
@@ -634,6 +719,10 @@ impl MockProvider {
 
 #[async_trait]
 impl Provider for MockProvider {
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
     async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
         self.log
             .lock()
@@ -680,8 +769,7 @@ impl Provider for MockProvider {
                 // Defer observation to stream-end so a scripted
                 // `with_stream_error` mid-stream produces an
                 // `OtherFailure` rather than a misleading `Success`.
-                let events = lower_response(response, &self.chunking);
-                let stream = futures_util::stream::iter(events);
+                let stream = lower_response(response, &self.chunking);
                 let observed = crate::rate_limit::observe_response_stream(
                     stream,
                     permit,
@@ -698,6 +786,7 @@ impl Provider for MockProvider {
 pub struct MockProviderBuilder {
     chunking: Chunking,
     replies: VecDeque<Reply>,
+    matchers: Vec<(Matcher, MockResponse)>,
 }
 
 impl MockProviderBuilder {
@@ -725,9 +814,32 @@ impl MockProviderBuilder {
         self
     }
 
+    /// Answer every call for which `matcher` returns `true` with
+    /// `response`, without popping the queue. Matchers are tried in the
+    /// order they were added and are never consumed, so one rule can
+    /// serve any number of calls. Use [`MockResponse::with_stream_error`]
+    /// to inject a failure for matching requests.
+    ///
+    /// ```no_run
+    /// # use platformed_llm::providers::mock::MockProvider;
+    /// let provider = MockProvider::builder()
+    ///     .when(|_, config| config.model == "small", "cheap answer")
+    ///     .reply("scripted answer")
+    ///     .build();
+    /// ```
+    pub fn when<F>(mut self, matcher: F, response: impl Into<MockResponse>) -> Self
+    where
+        F: Fn(&Prompt, &RawConfig) -> bool + Send + Sync + 'static,
+    {
+        self.matchers.push((Box::new(matcher), response.into()));
+        self
+    }
+
     /// Finish building the provider.
     pub fn build(self) -> MockProvider {
-        MockProvider::new(Mode::Queue(Mutex::new(self.replies)), self.chunking)
+        let mut provider = MockProvider::new(Mode::Queue(Mutex::new(self.replies)), self.chunking);
+        provider.matchers = self.matchers;
+        provider
     }
 }
 
@@ -790,6 +902,22 @@ mod tests {
         assert_eq!(b.text().await.unwrap(), "second");
     }
 
+    #[tokio::test]
+    async fn matchers_take_precedence_without_consuming_the_queue() {
+        let provider = MockProvider::builder()
+            .when(|_, config| config.model == "small", "matched")
+            .reply("queued")
+            .build();
+        let small = crate::Config::builder("small").build().raw().clone();
+
+        for _ in 0..2 {
+            let r = provider.generate(&Prompt::user("x"), &small).await.unwrap();
+            assert_eq!(r.text().await.unwrap(), "matched");
+        }
+        let r = provider.generate(&Prompt::user("x"), &cfg()).await.unwrap();
+        assert_eq!(r.text().await.unwrap(), "queued");
+    }
+
     #[tokio::test]
     async fn exhausted_queue_errors() {
         let provider = MockProvider::builder().reply("only").build();
@@ -883,6 +1011,7 @@ mod tests {
                 name: "get_weather".into(),
                 arguments: r#"{"city":"Paris"}"#.into(),
                 provider_signature: None,
+                original_arguments: None,
             }))
             .build();
         let complete = provider
@@ -961,7 +1090,7 @@ mod tests {
     async fn handler_branches_on_prompt() {
         let provider = MockProvider::with_handler(|prompt, _config| {
             let asked_tool = prompt.items().iter().any(|item| {
-                matches!(item, crate::InputItem::User { content }
+                matches!(item, crate::InputItem::User { content, .. }
                     if content.iter().any(|p| matches!(p, crate::UserPart::ToolResult { .. })))
             });
             if asked_tool {
@@ -972,6 +1101,7 @@ mod tests {
                     name: "lookup".into(),
                     arguments: "{}".into(),
                     provider_signature: None,
+                    original_arguments: None,
                 })
             }
         });
@@ -0,0 +1,281 @@
+//! Provider mocks at the HTTP layer, for integration tests.
+//!
+//! [`super::MockProvider`] replaces a provider outright, so nothing
+//! between the caller and the wire gets exercised. [`MockTransport`]
+//! goes one level down: it stands in for the HTTP client under a *real*
+//! [`OpenAIProvider`](crate::providers::OpenAIProvider),
+//! [`GoogleProvider`](crate::providers::GoogleProvider) or
+//! [`AnthropicViaVertexProvider`](crate::providers::AnthropicViaVertexProvider),
+//! so request serialization, SSE parsing and error mapping all run for
+//! real — in-process, with no server or port to manage. It is what this
+//! crate's own cross-provider tests use.
+//!
+//! Each [`MockTurn`] answers one request, in order: a status, headers
+//! and a body — usually an SSE stream from one of the [`sse`] builders,
+//! or a fixture file captured from the real API. A turn can also pin
+//! the request body it expects; a mismatch panics with a JSON diff, so
+//! a request-shape regression fails the test at the call that caused
+//! it. To capture real traffic as turns rather than writing them by
+//! hand, see [`record`].
+//!
+//! ```ignore
+//! use platformed_llm::providers::mock_http::{sse, MockTransport, MockTurn};
+//! use platformed_llm::providers::OpenAIProvider;
+//! use platformed_llm::transport::Transport;
+//!
+//! let mock = MockTransport::new([
+//!     MockTurn::sse(sse::OpenAiSse::new().text("Hello!").build())
+//!         .expect_body(serde_json::json!({ /* … */ })),
+//!     MockTurn::status(429, r#"{"error":{"message":"slow down"}}"#)
+//!         .header("retry-after", "2"),
+//! ]);
+//! let requests = mock.requests();
+//! let provider = OpenAIProvider::with_transport(key, "http://mock".into(), Transport::new(mock));
+//! ```
+
+pub mod record;
+pub mod sse;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::transport::{TransportImpl, TransportRequest, TransportResponse};
+use crate::Error;
+
+/// One scripted HTTP exchange for a [`MockTransport`].
+#[derive(Debug, Clone)]
+pub struct MockTurn {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    chunk_size: Option<usize>,
+    expected_body: Option<serde_json::Value>,
+}
+
+impl MockTurn {
+    /// A `200` streaming `body` as `text/event-stream`.
+    pub fn sse(body: impl Into<Vec<u8>>) -> Self {
+        Self::status(200, body).header("content-type", "text/event-stream")
+    }
+
+    /// A response with `status` and `body` — for error paths.
+    pub fn status(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+            chunk_size: None,
+            expected_body: None,
+        }
+    }
+
+    /// A `200` SSE response read from the fixture file at `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't be read.
+    pub fn fixture(path: impl AsRef<std::path::Path>) -> Self {
+        let path = path.as_ref();
+        let body = std::fs::read(path)
+            .unwrap_or_else(|e| panic!("failed to load fixture {}: {e}", path.display()));
+        Self::sse(body)
+    }
+
+    /// Add a response header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Deliver the body in chunks of at most `size` bytes rather than
+    /// all at once, so SSE frames (and UTF-8 sequences) straddle chunk
+    /// boundaries the way they do on a real connection.
+    pub fn chunked(mut self, size: usize) -> Self {
+        self.chunk_size = Some(size.max(1));
+        self
+    }
+
+    /// Panic unless the request body is JSON equal to `body`.
+    pub fn expect_body(mut self, body: serde_json::Value) -> Self {
+        self.expected_body = Some(body);
+        self
+    }
+
+    fn respond(self) -> TransportResponse {
+        let chunks: Vec<Result<Bytes, Error>> = match self.chunk_size {
+            Some(size) => self
+                .body
+                .chunks(size)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect(),
+            None => vec![Ok(Bytes::from(self.body))],
+        };
+        TransportResponse {
+            status: self.status,
+            headers: self.headers,
+            body: Box::pin(futures_util::stream::iter(chunks)),
+        }
+    }
+}
+
+/// A cloneable handle to the requests a [`MockTransport`] received.
+/// Obtain it via [`MockTransport::requests`] before handing the
+/// transport to a provider.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLog {
+    inner: Arc<Mutex<Vec<TransportRequest>>>,
+}
+
+impl RequestLog {
+    /// Every request so far, in order.
+    pub fn all(&self) -> Vec<TransportRequest> {
+        self.inner
+            .lock()
+            .expect("RequestLog mutex poisoned")
+            .clone()
+    }
+
+    /// The body of request `index`, parsed as JSON.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such request or its body isn't JSON.
+    pub fn body_json(&self, index: usize) -> serde_json::Value {
+        let requests = self.inner.lock().expect("RequestLog mutex poisoned");
+        let request = requests
+            .get(index)
+            .unwrap_or_else(|| panic!("no request #{index}; {} received", requests.len()));
+        serde_json::from_slice(&request.body).expect("request body was not valid JSON")
+    }
+
+    /// How many requests were received.
+    pub fn len(&self) -> usize {
+        self.inner.lock().expect("RequestLog mutex poisoned").len()
+    }
+
+    /// Whether no request was received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A [`TransportImpl`] answering requests from a queue of
+/// [`MockTurn`]s. See the [module docs](self).
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    turns: Mutex<VecDeque<MockTurn>>,
+    log: RequestLog,
+}
+
+impl MockTransport {
+    /// Answer requests with `turns`, in order. A request beyond the
+    /// last turn fails with an [`Error::Config`] naming the overrun.
+    pub fn new(turns: impl IntoIterator<Item = MockTurn>) -> Self {
+        Self {
+            turns: Mutex::new(turns.into_iter().collect()),
+            log: RequestLog::default(),
+        }
+    }
+
+    /// Queue another turn.
+    pub fn push(&self, turn: MockTurn) {
+        self.turns
+            .lock()
+            .expect("MockTransport mutex poisoned")
+            .push_back(turn);
+    }
+
+    /// A handle to the requests received, live as they arrive.
+    pub fn requests(&self) -> RequestLog {
+        self.log.clone()
+    }
+}
+
+impl MockTransport {
+    fn answer(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        self.log
+            .inner
+            .lock()
+            .expect("RequestLog mutex poisoned")
+            .push(req.clone());
+        let turn = self
+            .turns
+            .lock()
+            .expect("MockTransport mutex poisoned")
+            .pop_front()
+            .ok_or_else(|| {
+                Error::config(format!(
+                    "MockTransport: no turn scripted for request #{} to {}",
+                    self.log.len() - 1,
+                    req.url
+                ))
+            })?;
+        if let Some(expected) = &turn.expected_body {
+            let actual: serde_json::Value = serde_json::from_slice(&req.body)
+                .expect("request body sent by the provider was not valid JSON");
+            assert_eq!(
+                &actual, expected,
+                "request body did not match the expected payload"
+            );
+        }
+        Ok(turn.respond())
+    }
+}
+
+#[async_trait]
+impl TransportImpl for MockTransport {
+    async fn send(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        self.answer(req)
+    }
+
+    /// `GET`s take the next turn like any other request and are logged
+    /// with an empty body; tell them apart by URL.
+    async fn send_get(&self, mut req: TransportRequest) -> Result<TransportResponse, Error> {
+        req.body.clear();
+        self.answer(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    fn request(body: &str) -> TransportRequest {
+        TransportRequest {
+            url: "http://mock/v1".into(),
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_in_order_and_records_requests() {
+        let mock = MockTransport::new([
+            MockTurn::sse("data: 1\n\n").chunked(3),
+            MockTurn::status(500, "boom"),
+        ]);
+        let log = mock.requests();
+
+        let first = mock.send(request(r#"{"n":1}"#)).await.unwrap();
+        assert_eq!(first.status, 200);
+        let chunks: Vec<_> = first.body.map(Result::unwrap).collect().await;
+        assert_eq!(chunks, ["dat", "a: ", "1\n\n"]);
+        assert_eq!(mock.send(request("{}")).await.unwrap().status, 500);
+
+        let overrun = mock.send(request("{}")).await;
+        assert!(matches!(overrun, Err(Error::Config(_))));
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.body_json(0), serde_json::json!({"n": 1}));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "did not match")]
+    async fn body_mismatch_panics() {
+        let mock = MockTransport::new([MockTurn::sse("").expect_body(serde_json::json!({"n": 1}))]);
+        let _ = mock.send(request(r#"{"n":2}"#)).await;
+    }
+}
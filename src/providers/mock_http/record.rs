@@ -0,0 +1,294 @@
+//! Recording live provider traffic as [`MockTransport`] fixtures.
+//!
+//! Hand-writing an `.sse` file for a new provider behavior means
+//! guessing at the wire format. [`FixtureRecorder`] captures it
+//! instead: wrapped around a real transport, it writes every request
+//! body and raw response stream to disk, and
+//! [`MockTransport::from_fixtures`] replays them — each request body
+//! becoming the [`MockTurn::expect_body`] of its turn.
+//!
+//! [`record_or_replay`] switches between the two on the
+//! [`RECORD_FIXTURES_ENV`] environment variable, so one test covers
+//! both: run it once with the variable set and live credentials to
+//! (re)capture, then check the fixtures in.
+//!
+//! ```ignore
+//! use platformed_llm::providers::mock_http::record::record_or_replay;
+//!
+//! let transport = record_or_replay(
+//!     "tests/fixtures/openai",
+//!     "parallel_tools",
+//!     Transport::reqwest,
+//! )?;
+//! let provider = OpenAIProvider::with_transport(key, base_url, transport);
+//! ```
+//!
+//! # Fixture layout
+//!
+//! Call `n` (from 1) of a recording named `name` writes:
+//!
+//! - `<name>_<n>.request.json` — the request body, pretty-printed
+//!   (empty for a `GET`).
+//! - `<name>_<n>.sse` — the response body byte for byte, for a 2xx.
+//! - `<name>_<n>.status-<code>` — the response body otherwise.
+//!
+//! Only bodies are recorded: request headers (where API keys and
+//! tokens live) and URLs never touch disk. Review the captured bodies
+//! before committing them all the same — prompts and responses are
+//! recorded verbatim.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+
+use super::{MockTransport, MockTurn};
+use crate::transport::{
+    Transport, TransportImpl, TransportRequest, TransportResponse, UploadRequest,
+};
+use crate::Error;
+
+/// Environment variable that switches [`record_or_replay`] to
+/// recording. Any value other than empty or `0` turns it on.
+pub const RECORD_FIXTURES_ENV: &str = "PLATFORMED_LLM_RECORD_FIXTURES";
+
+/// Whether [`RECORD_FIXTURES_ENV`] asks for recording.
+pub fn recording_enabled() -> bool {
+    std::env::var(RECORD_FIXTURES_ENV).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Record to `dir` under `name` through the transport `live` builds
+/// when [`RECORD_FIXTURES_ENV`] is set; otherwise replay the recording
+/// with [`MockTransport::from_fixtures`]. `live` is only called when
+/// recording.
+pub fn record_or_replay(
+    dir: impl AsRef<Path>,
+    name: &str,
+    live: impl FnOnce() -> Result<Transport, Error>,
+) -> Result<Transport, Error> {
+    if recording_enabled() {
+        Ok(Transport::new(FixtureRecorder::new(live()?, dir, name)?))
+    } else {
+        Ok(Transport::new(MockTransport::from_fixtures(dir, name)?))
+    }
+}
+
+/// A [`TransportImpl`] that forwards to a real transport and writes
+/// each exchange to fixture files. See the [module docs](self).
+pub struct FixtureRecorder {
+    inner: Transport,
+    dir: PathBuf,
+    name: String,
+    calls: AtomicUsize,
+}
+
+impl FixtureRecorder {
+    /// Record `inner`'s traffic into `dir` (created if missing) as
+    /// `name_1.*`, `name_2.*`, …, overwriting earlier captures. Delete
+    /// an old capture first if the new one may make fewer calls — the
+    /// leftover turns would otherwise still replay.
+    pub fn new(inner: Transport, dir: impl AsRef<Path>, name: &str) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|e| {
+            Error::config(format!("cannot create fixture dir {}: {e}", dir.display()))
+        })?;
+        Ok(Self {
+            inner,
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    /// The directory fixtures are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl std::fmt::Debug for FixtureRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixtureRecorder")
+            .field("dir", &self.dir)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+fn write_fixture(path: &Path, bytes: &[u8]) {
+    if let Err(err) = std::fs::write(path, bytes) {
+        tracing::warn!(error = %err, path = %path.display(), "failed to write fixture");
+    }
+}
+
+/// Buffers one response body and writes it when the stream is dropped
+/// — after the last chunk, or part-way for an abandoned stream.
+struct Tape {
+    path: PathBuf,
+    body: Vec<u8>,
+}
+
+impl Drop for Tape {
+    fn drop(&mut self) {
+        write_fixture(&self.path, &self.body);
+    }
+}
+
+impl FixtureRecorder {
+    async fn record(&self, req: TransportRequest, get: bool) -> Result<TransportResponse, Error> {
+        let n = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+        let stem = format!("{}_{n}", self.name);
+        let request = match serde_json::from_slice::<serde_json::Value>(&req.body) {
+            Ok(json) => serde_json::to_vec_pretty(&json)?,
+            Err(_) => req.body.clone(),
+        };
+        write_fixture(&self.dir.join(format!("{stem}.request.json")), &request);
+
+        let response = if get {
+            self.inner.send_get(req).await?
+        } else {
+            self.inner.send(req).await?
+        };
+        let file = if (200..300).contains(&response.status) {
+            format!("{stem}.sse")
+        } else {
+            format!("{stem}.status-{}", response.status)
+        };
+        let mut tape = Tape {
+            path: self.dir.join(file),
+            body: Vec::new(),
+        };
+        let body = response.body.inspect(move |chunk| {
+            if let Ok(bytes) = chunk {
+                tape.body.extend_from_slice(bytes);
+            }
+        });
+        Ok(TransportResponse {
+            status: response.status,
+            headers: response.headers,
+            body: Box::pin(body),
+        })
+    }
+}
+
+#[async_trait]
+impl TransportImpl for FixtureRecorder {
+    async fn send(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        self.record(req, false).await
+    }
+
+    /// Uploads pass through unrecorded; their bodies are the caller's
+    /// files, not provider behavior.
+    async fn send_upload(&self, req: UploadRequest) -> Result<TransportResponse, Error> {
+        self.inner.send_upload(req).await
+    }
+
+    /// `GET`s are recorded with an empty `request.json`, which replays
+    /// as a turn with no body expectation.
+    async fn send_get(&self, mut req: TransportRequest) -> Result<TransportResponse, Error> {
+        req.body.clear();
+        self.record(req, true).await
+    }
+}
+
+impl MockTransport {
+    /// Replay the fixtures a [`FixtureRecorder`] wrote to `dir` under
+    /// `name`, one turn per recorded call, each asserting its recorded
+    /// request body. Fails with [`Error::Config`] when there is no
+    /// `name_1.request.json` or a call's response file is missing.
+    pub fn from_fixtures(dir: impl AsRef<Path>, name: &str) -> Result<Self, Error> {
+        let dir = dir.as_ref();
+        let mut turns = Vec::new();
+        for n in 1.. {
+            let stem = format!("{name}_{n}");
+            let request = match std::fs::read(dir.join(format!("{stem}.request.json"))) {
+                Ok(request) => request,
+                Err(_) if n > 1 => break,
+                Err(e) => {
+                    return Err(Error::config(format!(
+                        "no recorded fixtures for {name} in {}: {e}",
+                        dir.display()
+                    )))
+                }
+            };
+            let mut turn = read_response(dir, &stem)?;
+            if let Ok(body) = serde_json::from_slice(&request) {
+                turn = turn.expect_body(body);
+            }
+            turns.push(turn);
+        }
+        Ok(Self::new(turns))
+    }
+}
+
+fn read_response(dir: &Path, stem: &str) -> Result<MockTurn, Error> {
+    let sse = dir.join(format!("{stem}.sse"));
+    if let Ok(body) = std::fs::read(&sse) {
+        return Ok(MockTurn::sse(body));
+    }
+    let prefix = format!("{stem}.status-");
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| Error::config(format!("cannot read fixture dir {}: {e}", dir.display())))?;
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(status) = file_name
+            .to_str()
+            .and_then(|file| file.strip_prefix(&prefix))
+            .and_then(|code| code.parse().ok())
+        else {
+            continue;
+        };
+        let body = std::fs::read(entry.path()).map_err(|e| {
+            Error::config(format!(
+                "cannot read fixture {}: {e}",
+                entry.path().display()
+            ))
+        })?;
+        return Ok(MockTurn::status(status, body));
+    }
+    Err(Error::config(format!(
+        "no recorded response for {stem} in {}",
+        dir.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: &str) -> TransportRequest {
+        TransportRequest {
+            url: "http://live/v1".into(),
+            headers: vec![("authorization".into(), "Bearer secret".into())],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn recorded_fixtures_replay_as_the_live_traffic() {
+        let dir = std::env::temp_dir().join(format!("fixture-recorder-{}", std::process::id()));
+        let live = Transport::new(MockTransport::new([
+            MockTurn::sse("data: {\"n\":1}\n\n").chunked(4),
+            MockTurn::status(429, r#"{"error":"slow down"}"#),
+        ]));
+        let recorder = FixtureRecorder::new(live, &dir, "turns").unwrap();
+        for body in [r#"{"turn":1}"#, r#"{"turn":2}"#] {
+            let response = recorder.send(request(body)).await.unwrap();
+            let _: Vec<_> = response.body.collect().await;
+        }
+        let written = std::fs::read_to_string(dir.join("turns_1.request.json")).unwrap();
+        assert!(!written.contains("secret"));
+
+        let replay = MockTransport::from_fixtures(&dir, "turns").unwrap();
+        let first = replay.send(request(r#"{"turn":1}"#)).await.unwrap();
+        let body: Vec<_> = first.body.map(Result::unwrap).collect().await;
+        assert_eq!(body.concat(), b"data: {\"n\":1}\n\n");
+        let second = replay.send(request(r#"{"turn":2}"#)).await.unwrap();
+        assert_eq!(second.status, 429);
+        assert!(replay.send(request("{}")).await.is_err());
+
+        assert!(MockTransport::from_fixtures(&dir, "missing").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,516 @@
+//! SSE bodies in each provider's wire format, for [`super::MockTurn::sse`].
+//!
+//! Each builder lists the assistant's output parts in order and renders
+//! the event stream the real API would send for them: OpenAI Responses
+//! events, Gemini `streamGenerateContent` chunks, or Anthropic Messages
+//! events. Text streams one delta per word so the provider's
+//! accumulation path is exercised; tool-call arguments arrive as one
+//! delta. The output mirrors the fixtures captured from the real APIs
+//! under `tests/cross_provider/fixtures/`.
+//!
+//! ```ignore
+//! use platformed_llm::providers::mock_http::sse::AnthropicSse;
+//! use serde_json::json;
+//!
+//! let body = AnthropicSse::new()
+//!     .text("Let me check.")
+//!     .tool_use("toolu_1", "get_weather", json!({"location": "Paris"}))
+//!     .usage(84, 41)
+//!     .build();
+//! ```
+
+use serde_json::{json, Value};
+
+use crate::providers::mock::split_words;
+
+fn frame(out: &mut Vec<u8>, data: &Value) {
+    out.extend_from_slice(b"data: ");
+    out.extend_from_slice(data.to_string().as_bytes());
+    out.extend_from_slice(b"\n\n");
+}
+
+#[derive(Debug, Clone)]
+enum OpenAiItem {
+    Text(String),
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+}
+
+/// An OpenAI Responses API event stream. `response.completed` carries
+/// the usage, and — like the real API — lists the function calls that
+/// determine the finish reason.
+#[derive(Debug, Clone)]
+pub struct OpenAiSse {
+    response_id: String,
+    model: String,
+    items: Vec<OpenAiItem>,
+    usage: (u32, u32),
+}
+
+impl Default for OpenAiSse {
+    fn default() -> Self {
+        Self {
+            response_id: "resp_mock".into(),
+            model: "gpt-4o-mini".into(),
+            items: Vec::new(),
+            usage: (0, 0),
+        }
+    }
+}
+
+impl OpenAiSse {
+    /// An empty response with id `resp_mock`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The response id, echoed back as `previous_response_id` on the
+    /// next turn.
+    pub fn response_id(mut self, id: impl Into<String>) -> Self {
+        self.response_id = id.into();
+        self
+    }
+
+    /// The model reported in `response.completed`.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// A message item with `text`.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.items.push(OpenAiItem::Text(text.into()));
+        self
+    }
+
+    /// A `function_call` item; `arguments` is the raw JSON string.
+    pub fn function_call(
+        mut self,
+        call_id: impl Into<String>,
+        name: impl Into<String>,
+        arguments: impl Into<String>,
+    ) -> Self {
+        self.items.push(OpenAiItem::FunctionCall {
+            call_id: call_id.into(),
+            name: name.into(),
+            arguments: arguments.into(),
+        });
+        self
+    }
+
+    /// Input and output token counts.
+    pub fn usage(mut self, input_tokens: u32, output_tokens: u32) -> Self {
+        self.usage = (input_tokens, output_tokens);
+        self
+    }
+
+    /// Render the event stream.
+    pub fn build(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut completed = Vec::new();
+        for (output_index, item) in self.items.iter().enumerate() {
+            match item {
+                OpenAiItem::Text(text) => {
+                    let id = format!("msg_{output_index}");
+                    frame(
+                        &mut out,
+                        &json!({"type": "response.output_item.added", "output_index": output_index,
+                            "item": {"id": id, "type": "message", "role": "assistant", "content": []}}),
+                    );
+                    frame(
+                        &mut out,
+                        &json!({"type": "response.content_part.added", "output_index": output_index,
+                            "content_index": 0, "part": {"type": "output_text"}}),
+                    );
+                    for word in split_words(text) {
+                        frame(
+                            &mut out,
+                            &json!({"type": "response.output_text.delta", "output_index": output_index,
+                                "content_index": 0, "delta": word}),
+                        );
+                    }
+                    frame(
+                        &mut out,
+                        &json!({"type": "response.content_part.done", "output_index": output_index,
+                            "content_index": 0}),
+                    );
+                    frame(
+                        &mut out,
+                        &json!({"type": "response.output_item.done", "output_index": output_index,
+                            "item": {"id": id, "type": "message"}}),
+                    );
+                }
+                OpenAiItem::FunctionCall {
+                    call_id,
+                    name,
+                    arguments,
+                } => {
+                    let id = format!("fc_{output_index}");
+                    frame(
+                        &mut out,
+                        &json!({"type": "response.output_item.added", "output_index": output_index,
+                            "item": {"id": id, "type": "function_call", "name": name,
+                                "call_id": call_id, "arguments": ""}}),
+                    );
+                    frame(
+                        &mut out,
+                        &json!({"type": "response.function_call_arguments.delta",
+                            "output_index": output_index, "delta": arguments}),
+                    );
+                    let done = json!({"id": id, "type": "function_call", "status": "completed",
+                        "name": name, "arguments": arguments, "call_id": call_id});
+                    frame(
+                        &mut out,
+                        &json!({"type": "response.output_item.done", "output_index": output_index,
+                            "item": done}),
+                    );
+                    completed.push(done);
+                }
+            }
+        }
+        let (input_tokens, output_tokens) = self.usage;
+        frame(
+            &mut out,
+            &json!({"type": "response.completed", "response": {
+                "id": self.response_id, "object": "response", "created_at": 1,
+                "status": "completed", "model": self.model, "output": completed,
+                "usage": {"input_tokens": input_tokens, "output_tokens": output_tokens,
+                    "total_tokens": input_tokens + output_tokens}}}),
+        );
+        out
+    }
+}
+
+/// A Gemini `streamGenerateContent` chunk stream, as served by Vertex
+/// AI. The last chunk carries the finish reason and usage metadata.
+#[derive(Debug, Clone)]
+pub struct GeminiSse {
+    parts: Vec<Value>,
+    finish_reason: String,
+    usage: (u32, u32),
+}
+
+impl Default for GeminiSse {
+    fn default() -> Self {
+        Self {
+            parts: Vec::new(),
+            finish_reason: "STOP".into(),
+            usage: (0, 0),
+        }
+    }
+}
+
+impl GeminiSse {
+    /// An empty response finishing with `STOP`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A text part, one chunk per word.
+    pub fn text(mut self, text: &str) -> Self {
+        self.parts.extend(
+            split_words(text)
+                .into_iter()
+                .map(|word| json!({"text": word})),
+        );
+        self
+    }
+
+    /// A `functionCall` part. Gemini sends no call id; the provider
+    /// mints one.
+    pub fn function_call(mut self, name: impl Into<String>, args: Value) -> Self {
+        self.parts
+            .push(json!({"functionCall": {"name": name.into(), "args": args}}));
+        self
+    }
+
+    /// The raw `finishReason` — `STOP`, `MAX_TOKENS`, `SAFETY`, ….
+    pub fn finish_reason(mut self, reason: impl Into<String>) -> Self {
+        self.finish_reason = reason.into();
+        self
+    }
+
+    /// Prompt and candidate token counts.
+    pub fn usage(mut self, prompt_tokens: u32, candidate_tokens: u32) -> Self {
+        self.usage = (prompt_tokens, candidate_tokens);
+        self
+    }
+
+    /// Render the chunk stream.
+    pub fn build(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let (last, rest) = match self.parts.split_last() {
+            Some((last, rest)) => (vec![last.clone()], rest),
+            None => (Vec::new(), &[][..]),
+        };
+        for part in rest {
+            frame(
+                &mut out,
+                &json!({"candidates": [{"content": {"role": "model", "parts": [part]}}]}),
+            );
+        }
+        let (prompt_tokens, candidate_tokens) = self.usage;
+        frame(
+            &mut out,
+            &json!({"candidates": [{"content": {"role": "model", "parts": last},
+                    "finishReason": self.finish_reason}],
+                "usageMetadata": {"promptTokenCount": prompt_tokens,
+                    "candidatesTokenCount": candidate_tokens,
+                    "totalTokenCount": prompt_tokens + candidate_tokens}}),
+        );
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+enum AnthropicBlock {
+    Text(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+    },
+}
+
+/// An Anthropic Messages API event stream, as served by Vertex AI. The
+/// stop reason defaults to `tool_use` when the turn has a tool call and
+/// `end_turn` otherwise.
+#[derive(Debug, Clone)]
+pub struct AnthropicSse {
+    message_id: String,
+    model: String,
+    blocks: Vec<AnthropicBlock>,
+    stop_reason: Option<String>,
+    usage: (u32, u32),
+}
+
+impl Default for AnthropicSse {
+    fn default() -> Self {
+        Self {
+            message_id: "msg_mock".into(),
+            model: "claude-3-5-sonnet@20241022".into(),
+            blocks: Vec::new(),
+            stop_reason: None,
+            usage: (0, 0),
+        }
+    }
+}
+
+impl AnthropicSse {
+    /// An empty message with id `msg_mock`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The message id in `message_start`.
+    pub fn message_id(mut self, id: impl Into<String>) -> Self {
+        self.message_id = id.into();
+        self
+    }
+
+    /// The model in `message_start`.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// A `text` content block.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.blocks.push(AnthropicBlock::Text(text.into()));
+        self
+    }
+
+    /// A `tool_use` content block with `input` streamed as JSON.
+    pub fn tool_use(
+        mut self,
+        id: impl Into<String>,
+        name: impl Into<String>,
+        input: Value,
+    ) -> Self {
+        self.blocks.push(AnthropicBlock::ToolUse {
+            id: id.into(),
+            name: name.into(),
+            input,
+        });
+        self
+    }
+
+    /// The raw `stop_reason` — `end_turn`, `max_tokens`, `tool_use`, ….
+    pub fn stop_reason(mut self, reason: impl Into<String>) -> Self {
+        self.stop_reason = Some(reason.into());
+        self
+    }
+
+    /// Input and output token counts.
+    pub fn usage(mut self, input_tokens: u32, output_tokens: u32) -> Self {
+        self.usage = (input_tokens, output_tokens);
+        self
+    }
+
+    /// Render the event stream.
+    pub fn build(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let (input_tokens, output_tokens) = self.usage;
+        frame(
+            &mut out,
+            &json!({"type": "message_start", "message": {"id": self.message_id,
+                "model": self.model, "role": "assistant", "content": [], "stop_reason": null,
+                "usage": {"input_tokens": input_tokens, "output_tokens": 1}}}),
+        );
+        for (index, block) in self.blocks.iter().enumerate() {
+            match block {
+                AnthropicBlock::Text(text) => {
+                    frame(
+                        &mut out,
+                        &json!({"type": "content_block_start", "index": index,
+                            "content_block": {"type": "text", "text": ""}}),
+                    );
+                    for word in split_words(text) {
+                        frame(
+                            &mut out,
+                            &json!({"type": "content_block_delta", "index": index,
+                                "delta": {"type": "text_delta", "text": word}}),
+                        );
+                    }
+                }
+                AnthropicBlock::ToolUse { id, name, input } => {
+                    frame(
+                        &mut out,
+                        &json!({"type": "content_block_start", "index": index,
+                            "content_block": {"type": "tool_use", "id": id, "name": name,
+                                "input": {}}}),
+                    );
+                    frame(
+                        &mut out,
+                        &json!({"type": "content_block_delta", "index": index,
+                            "delta": {"type": "input_json_delta",
+                                "partial_json": input.to_string()}}),
+                    );
+                }
+            }
+            frame(
+                &mut out,
+                &json!({"type": "content_block_stop", "index": index}),
+            );
+        }
+        let stop_reason = self.stop_reason.clone().unwrap_or_else(|| {
+            let tool_use = self
+                .blocks
+                .iter()
+                .any(|block| matches!(block, AnthropicBlock::ToolUse { .. }));
+            if tool_use { "tool_use" } else { "end_turn" }.to_string()
+        });
+        frame(
+            &mut out,
+            &json!({"type": "message_delta", "delta": {"stop_reason": stop_reason},
+                "usage": {"output_tokens": output_tokens}}),
+        );
+        frame(&mut out, &json!({"type": "message_stop"}));
+        out
+    }
+}
+
+#[cfg(all(test, any(feature = "openai", feature = "vertex")))]
+mod tests {
+    use super::*;
+    use crate::providers::mock_http::{MockTransport, MockTurn};
+    use crate::transport::Transport;
+    use crate::{generate, Config, Prompt};
+
+    #[cfg(feature = "vertex")]
+    fn endpoint() -> crate::providers::VertexEndpoint {
+        crate::providers::VertexEndpoint::with_access_token(
+            "test-project".to_string(),
+            "europe-west1".to_string(),
+            "test-access-token".to_string(),
+        )
+    }
+
+    #[cfg(feature = "openai")]
+    #[tokio::test]
+    async fn openai_stream_parses() {
+        let body = OpenAiSse::new()
+            .text("Checking the weather.")
+            .function_call("call_1", "get_weather", r#"{"location":"Paris"}"#)
+            .usage(10, 5)
+            .build();
+        let provider = crate::providers::OpenAIProvider::with_transport(
+            "test-key".to_string(),
+            "http://mock".to_string(),
+            Transport::new(MockTransport::new([MockTurn::sse(body).chunked(7)])),
+        );
+        let complete = generate(
+            &provider,
+            &Prompt::user("hi"),
+            &Config::builder("gpt-4o-mini").build(),
+        )
+        .await
+        .unwrap()
+        .buffer()
+        .await
+        .unwrap();
+        assert_eq!(complete.text(), "Checking the weather.");
+        assert_eq!(complete.function_calls()[0].name, "get_weather");
+        assert_eq!(complete.finish_reason, crate::FinishReason::ToolCalls);
+        assert_eq!(complete.usage.output_tokens, 5);
+    }
+
+    #[cfg(feature = "google")]
+    #[tokio::test]
+    async fn gemini_stream_parses() {
+        let body = GeminiSse::new()
+            .text("Sunny in Paris.")
+            .finish_reason("MAX_TOKENS")
+            .usage(50, 20)
+            .build();
+        let provider = crate::providers::GoogleProvider::with_transport(
+            endpoint(),
+            Transport::new(MockTransport::new([MockTurn::sse(body)])),
+        );
+        let complete = generate(
+            &provider,
+            &Prompt::user("hi"),
+            &Config::builder("gemini-2.0-flash").build(),
+        )
+        .await
+        .unwrap()
+        .buffer()
+        .await
+        .unwrap();
+        assert_eq!(complete.text(), "Sunny in Paris.");
+        assert!(complete.was_truncated());
+        assert_eq!(complete.usage.input_tokens, 50);
+    }
+
+    #[cfg(feature = "anthropic-vertex")]
+    #[tokio::test]
+    async fn anthropic_stream_parses() {
+        let body = AnthropicSse::new()
+            .text("Let me check.")
+            .tool_use("toolu_1", "get_weather", json!({"location": "Paris"}))
+            .usage(84, 41)
+            .build();
+        let provider = crate::providers::AnthropicViaVertexProvider::with_transport(
+            endpoint(),
+            Transport::new(MockTransport::new([MockTurn::sse(body)])),
+        );
+        let complete = generate(
+            &provider,
+            &Prompt::user("hi"),
+            &Config::builder("claude-3-5-sonnet@20241022").build(),
+        )
+        .await
+        .unwrap()
+        .buffer()
+        .await
+        .unwrap();
+        assert_eq!(complete.text(), "Let me check.");
+        let calls = complete.function_calls();
+        assert_eq!(calls[0].call_id, "toolu_1");
+        assert_eq!(complete.finish_reason, crate::FinishReason::ToolCalls);
+        assert_eq!(complete.usage.output_tokens, 41);
+    }
+}
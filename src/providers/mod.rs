@@ -3,12 +3,18 @@
 //! Each provider is gated behind a Cargo feature so a leaner build can
 //! drop unused HTTP / auth dependencies:
 //!
-//! - `openai` — OpenAI Responses API (`OpenAIProvider`).
-//! - `google` — Google Gemini via Vertex AI (`GoogleProvider`).
+//! - `openai` — OpenAI Responses API (`OpenAIProvider`), plus
+//!   `EntraCredential` for Azure OpenAI's Entra ID auth.
+//! - `google` — Google Gemini via Vertex AI (`GoogleProvider`), plus
+//!   bidirectional Live API sessions (`gemini_live`).
 //! - `anthropic-vertex` — Anthropic Claude via Vertex AI
 //!   (`AnthropicViaVertexProvider`).
 //! - `llama-gguf` — Local GGUF inference (`LlamaGgufProvider`).
-//! - `mock` — In-process canned responses for testing (`MockProvider`).
+//! - `mock` — In-process canned responses for testing (`MockProvider`),
+//!   exact timed event sequences (`script::StreamScript`), record /
+//!   replay of real calls (`vcr::RecordingProvider` /
+//!   `vcr::ReplayProvider`), and HTTP-level mocks that drive the real
+//!   providers from scripted SSE (`mock_http::MockTransport`).
 //!
 //! No features are enabled by default — opt in per provider.
 
@@ -16,24 +22,32 @@
 pub(crate) mod file_resolve;
 #[cfg(feature = "mock")]
 pub mod mock;
+#[cfg(feature = "mock")]
+pub mod mock_http;
 #[cfg(feature = "openai")]
 mod openai;
 #[cfg(any(feature = "openai", feature = "google", feature = "anthropic-vertex"))]
 pub(crate) mod part_tracker;
+#[cfg(feature = "mock")]
+pub mod script;
+#[cfg(feature = "mock")]
+pub mod vcr;
 #[cfg(feature = "vertex")]
 mod vertex;
 
 #[cfg(feature = "llama-gguf")]
 pub mod local;
 
+#[cfg(all(feature = "openai", not(target_arch = "wasm32")))]
+pub use openai::EntraCredential;
 #[cfg(feature = "openai")]
-pub use openai::OpenAIProvider;
+pub use openai::{BackgroundResponse, BackgroundStatus, OpenAIProvider};
 #[cfg(feature = "anthropic-vertex")]
 pub use vertex::AnthropicViaVertexProvider;
 #[cfg(feature = "google")]
-pub use vertex::GoogleProvider;
+pub use vertex::{gemini_live, GoogleProvider};
 #[cfg(feature = "vertex")]
-pub use vertex::VertexEndpoint;
+pub use vertex::{AdcOptions, VertexEndpoint, VERTEX_SCOPE};
 
 #[cfg(feature = "llama-gguf")]
 pub use local::LlamaGgufProvider;
@@ -41,12 +55,48 @@ pub use local::LlamaGgufProvider;
 #[cfg(feature = "mock")]
 pub use mock::{CallLog, Chunking, MockProvider, MockProviderBuilder, MockResponse, RecordedCall};
 
-/// Best-effort flatten of a tool-result content array into a single
-/// string. Tool-result wire shapes accept only plain text on OpenAI's
-/// `function_call_output`, Gemini's `functionResponse`, and the
-/// Anthropic `tool_result` block — non-text parts (images, audio,
-/// documents) have nowhere to land, so they're dropped with a
-/// `tracing::debug!` so the loss is visible in logs.
+/// Serialize a provider request body, deep-merging the caller's
+/// [`RawConfig::extra_body`](crate::RawConfig::extra_body) over it.
+/// The merge runs on the final wire JSON, so it can reach any field the
+/// provider emits — including ones this crate sets itself.
+#[cfg(any(feature = "openai", feature = "google", feature = "anthropic-vertex"))]
+pub(crate) fn encode_request_body<T: serde::Serialize>(
+    request: &T,
+    extra_body: Option<&serde_json::Value>,
+) -> Result<Vec<u8>, crate::Error> {
+    let Some(extra) = extra_body else {
+        return Ok(serde_json::to_vec(request)?);
+    };
+    if !extra.is_object() {
+        return Err(crate::Error::config("extra_body must be a JSON object"));
+    }
+    let mut body = serde_json::to_value(request)?;
+    merge_json(&mut body, extra);
+    Ok(serde_json::to_vec(&body)?)
+}
+
+#[cfg(any(feature = "openai", feature = "google", feature = "anthropic-vertex"))]
+fn merge_json(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    use serde_json::Value;
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_json(target.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+/// Flatten a tool-result content array into the text half of the
+/// result. Images and documents are carried separately by each
+/// provider's tool-result encoding; audio and video have nowhere to
+/// land and are dropped. Every skipped part is logged at
+/// `tracing::debug!` so a loss is visible.
 #[cfg(any(feature = "openai", feature = "google", feature = "anthropic-vertex"))]
 pub(crate) fn flatten_user_parts_to_text(parts: &[crate::types::UserPart]) -> String {
     use crate::types::UserPart;
@@ -60,13 +110,29 @@ pub(crate) fn flatten_user_parts_to_text(parts: &[crate::types::UserPart]) -> St
                 out.push_str(s);
             }
             _ => {
-                tracing::debug!("dropping non-text tool result part during request flatten");
+                tracing::debug!("skipping non-text tool result part in flattened text");
             }
         }
     }
     out
 }
 
+/// The `error.message` of a JSON error envelope — the shape Vertex and
+/// Anthropic both return — or the whole body when it isn't one (an
+/// HTML page from a proxy, say).
+#[cfg(feature = "vertex")]
+pub(crate) fn error_envelope_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|value| {
+            value
+                .pointer("/error/message")
+                .and_then(|m| m.as_str())
+                .map(str::to_owned)
+        })
+        .unwrap_or_else(|| body.to_string())
+}
+
 /// Reject a prompt that carries an input modality the target provider can't
 /// accept. Run at the top of `generate()` so the caller gets a clear
 /// [`Error::UnsupportedInput`](crate::Error::UnsupportedInput) instead of the
@@ -109,7 +175,7 @@ pub(crate) fn reject_unsupported_modalities(
     }
 
     for item in items {
-        if let InputItem::User { content } = item {
+        if let InputItem::User { content, .. } = item {
             check(content, provider, supports_audio, supports_video)?;
         }
     }
@@ -123,7 +189,11 @@ mod modality_tests {
     use crate::Error;
 
     fn user(parts: Vec<UserPart>) -> InputItem {
-        InputItem::User { content: parts }
+        InputItem::User {
+            content: parts,
+            name: None,
+            metadata: Default::default(),
+        }
     }
 
     #[test]
@@ -174,7 +244,46 @@ mod modality_tests {
         let nested = vec![user(vec![UserPart::ToolResult {
             call_id: "c1".into(),
             content: vec![UserPart::Audio(FileSource::Url("a".into()))],
+            is_error: false,
         }])];
         assert!(reject_unsupported_modalities(&nested, "OpenAI", false, false).is_err());
     }
 }
+
+#[cfg(all(
+    test,
+    any(feature = "openai", feature = "google", feature = "anthropic-vertex")
+))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extra_body_deep_merges_replaces_and_removes() {
+        let request = json!({
+            "model": "m",
+            "generation": { "temperature": 0.2, "seed": 7 },
+            "tools": [1, 2],
+        });
+        let extra = json!({
+            "generation": { "temperature": 0.9, "top_k": 40 },
+            "tools": [3],
+            "model": null,
+            "new_param": true,
+        });
+        let body: serde_json::Value =
+            serde_json::from_slice(&encode_request_body(&request, Some(&extra)).unwrap()).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "generation": { "temperature": 0.9, "seed": 7, "top_k": 40 },
+                "tools": [3],
+                "new_param": true,
+            })
+        );
+        assert!(matches!(
+            encode_request_body(&request, Some(&json!([1]))),
+            Err(crate::Error::Config(_))
+        ));
+    }
+}
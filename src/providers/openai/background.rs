@@ -0,0 +1,277 @@
+//! Background mode for the Responses API; see [`BackgroundResponse`].
+
+use std::time::Duration;
+
+use futures_util::StreamExt as _;
+use serde::Deserialize;
+
+use super::client::{http_error, parse_openai_rate_info, response_events};
+use super::OpenAIProvider;
+use crate::transport::TransportRequest;
+use crate::{Error, Prompt, RawConfig, Response};
+
+/// Server-side state of a background response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum BackgroundStatus {
+    /// Accepted, not yet running.
+    Queued,
+    /// Generating.
+    InProgress,
+    /// Finished normally.
+    Completed,
+    /// Finished early, e.g. on the output-token cap.
+    Incomplete,
+    /// Stopped by an error.
+    Failed,
+    /// Stopped by [`OpenAIProvider::cancel_background`].
+    Cancelled,
+}
+
+impl BackgroundStatus {
+    /// Whether the response has stopped running — polling it again
+    /// won't change the status.
+    pub fn is_terminal(self) -> bool {
+        !matches!(self, Self::Queued | Self::InProgress)
+    }
+}
+
+/// Handle to a background response: its id and its status when last
+/// seen.
+///
+/// A normal call lives exactly as long as its HTTP connection. With
+/// `background: true` OpenAI runs the response server-side instead,
+/// so a long reasoning run survives a dropped connection, a redeploy
+/// or a request timeout in between. [`OpenAIProvider::start_background`]
+/// submits the prompt and returns this handle as
+/// soon as the response is queued; the id is all a later process needs
+/// to pick it back up:
+///
+/// - [`OpenAIProvider::background_status`] polls it once,
+///   [`OpenAIProvider::wait_background`] until it finishes;
+/// - [`OpenAIProvider::stream_background`] streams it — replayed from
+///   the first event, so the [`Response`] is complete whether the run
+///   is still going or long done;
+/// - [`OpenAIProvider::cancel_background`] stops it.
+///
+/// ```ignore
+/// let handle = provider.start_background(&prompt, &config).await?;
+/// // … later, possibly from another process:
+/// let text = provider.stream_background(&handle.id).await?.text().await?;
+/// ```
+///
+/// Background responses are always stored (`store: true`), and OpenAI
+/// keeps them for a limited time only.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[non_exhaustive]
+pub struct BackgroundResponse {
+    /// The response id (`resp_…`), also usable as a
+    /// `previous_response_id` once the response completes.
+    pub id: String,
+    /// Status as of the call that returned this handle.
+    pub status: BackgroundStatus,
+}
+
+/// The envelope of a lifecycle SSE frame; only `response.created`
+/// carries the handle.
+#[derive(Deserialize)]
+struct LifecycleFrame {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    response: Option<BackgroundResponse>,
+}
+
+impl OpenAIProvider {
+    /// Submit `prompt` as a background response and return its handle
+    /// once OpenAI has queued it. See [`BackgroundResponse`].
+    ///
+    /// The request is sent streaming — a background response can only
+    /// be streamed later if it was created that way — and the
+    /// connection is dropped after the `response.created` frame; the
+    /// run carries on server-side.
+    pub async fn start_background(
+        &self,
+        prompt: &Prompt,
+        config: &RawConfig,
+    ) -> Result<BackgroundResponse, Error> {
+        use crate::sse_stream::SseStreamExt;
+
+        let mut request = self.prepare_request(prompt, config).await?;
+        request.stream = Some(true);
+        request.store = Some(true);
+        request.background = Some(true);
+        let (response, permit) = self.send_responses(&request, config).await?;
+        let info = parse_openai_rate_info(&response);
+        permit.observe(crate::rate_limit::RateOutcome::Success { info });
+
+        let mut events = response
+            .body
+            .sse_events("OpenAI")
+            .with_limits(self.sse_limits);
+        while let Some(event) = events.next().await {
+            let frame: LifecycleFrame = event?.json()?;
+            if let ("response.created", Some(handle)) = (frame.kind.as_str(), frame.response) {
+                return Ok(handle);
+            }
+        }
+        Err(Error::provider(
+            "OpenAI",
+            "background response stream ended before response.created",
+        ))
+    }
+
+    /// Fetch the current status of background response `id`.
+    pub async fn background_status(&self, id: &str) -> Result<BackgroundResponse, Error> {
+        let response = self
+            .transport
+            .send_get(TransportRequest {
+                url: format!("{}/responses/{id}", self.base_url),
+                headers: self.request_headers(false, &[]).await?,
+                body: Vec::new(),
+            })
+            .await?;
+        if !(200..300).contains(&response.status) {
+            return Err(http_error(response, None).await);
+        }
+        Ok(serde_json::from_slice(&response.collect_body().await?)?)
+    }
+
+    /// Poll background response `id` every `interval` until it reaches
+    /// a terminal status, and return that last handle.
+    pub async fn wait_background(
+        &self,
+        id: &str,
+        interval: Duration,
+    ) -> Result<BackgroundResponse, Error> {
+        loop {
+            let handle = self.background_status(id).await?;
+            if handle.status.is_terminal() {
+                return Ok(handle);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Stream background response `id` from its first event, following
+    /// it live while it is still running. Works any number of times,
+    /// from any process, within OpenAI's retention window.
+    pub async fn stream_background(&self, id: &str) -> Result<Response, Error> {
+        let response = self
+            .transport
+            .send_get(TransportRequest {
+                url: format!("{}/responses/{id}?stream=true", self.base_url),
+                headers: self.request_headers(false, &[]).await?,
+                body: Vec::new(),
+            })
+            .await?;
+        if !(200..300).contains(&response.status) {
+            return Err(http_error(response, None).await);
+        }
+        Ok(Response::from_stream(response_events(
+            response,
+            self.sse_limits,
+        )))
+    }
+
+    /// Cancel background response `id`. Cancelling one that already
+    /// finished is a no-op that returns its terminal status.
+    pub async fn cancel_background(&self, id: &str) -> Result<BackgroundResponse, Error> {
+        let response = self
+            .transport
+            .send(TransportRequest {
+                url: format!("{}/responses/{id}/cancel", self.base_url),
+                headers: self.request_headers(false, &[]).await?,
+                body: Vec::new(),
+            })
+            .await?;
+        if !(200..300).contains(&response.status) {
+            return Err(http_error(response, None).await);
+        }
+        Ok(serde_json::from_slice(&response.collect_body().await?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock_http::{sse::OpenAiSse, MockTransport, MockTurn, RequestLog};
+    use crate::transport::Transport;
+    use crate::Config;
+
+    fn provider(turns: impl IntoIterator<Item = MockTurn>) -> (OpenAIProvider, RequestLog) {
+        let mock = MockTransport::new(turns);
+        let log = mock.requests();
+        let provider = OpenAIProvider::with_transport(
+            "sk-test".into(),
+            "http://mock/v1".into(),
+            Transport::new(mock),
+        );
+        (provider, log)
+    }
+
+    fn status_body(status: &str) -> String {
+        format!(r#"{{"id":"resp_bg","object":"response","status":"{status}","output":[]}}"#)
+    }
+
+    #[tokio::test]
+    async fn start_background_returns_the_queued_handle() {
+        let created = format!(
+            "data: {{\"type\":\"response.created\",\"response\":{}}}\n\n\
+             data: {{\"type\":\"response.queued\",\"response\":{}}}\n\n",
+            status_body("queued"),
+            status_body("queued"),
+        );
+        let (provider, log) = provider([MockTurn::sse(created)]);
+        let config = Config::builder("gpt-5").build();
+        let handle = provider
+            .start_background(&Prompt::user("Write a novel"), config.raw())
+            .await
+            .unwrap();
+
+        assert_eq!(handle.id, "resp_bg");
+        assert_eq!(handle.status, BackgroundStatus::Queued);
+        let body = log.body_json(0);
+        assert_eq!(body["background"], true);
+        assert_eq!(body["store"], true);
+        assert_eq!(body["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn waits_for_completion_then_replays_the_stream() {
+        let (provider, log) = provider([
+            MockTurn::status(200, status_body("in_progress")),
+            MockTurn::status(200, status_body("completed")),
+            MockTurn::sse(
+                OpenAiSse::new()
+                    .response_id("resp_bg")
+                    .text("Chapter one.")
+                    .build(),
+            ),
+            MockTurn::status(404, r#"{"error":{"message":"No response found"}}"#),
+        ]);
+
+        let done = provider
+            .wait_background("resp_bg", Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(done.status, BackgroundStatus::Completed);
+        assert!(done.status.is_terminal());
+
+        let response = provider.stream_background("resp_bg").await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "Chapter one.");
+        assert!(provider.background_status("resp_gone").await.is_err());
+
+        let urls: Vec<_> = log.all().into_iter().map(|req| req.url).collect();
+        assert_eq!(
+            urls,
+            [
+                "http://mock/v1/responses/resp_bg",
+                "http://mock/v1/responses/resp_bg",
+                "http://mock/v1/responses/resp_bg?stream=true",
+                "http://mock/v1/responses/resp_gone",
+            ]
+        );
+    }
+}
@@ -1,29 +1,33 @@
 use super::types::{
     OpenAIAnnotation, OpenAIReasoning, OpenAIStreamEvent, OpenAIToolChoice, ResponsesRequest,
+    ResponsesResponse, StreamErrorFrame,
 };
+use crate::auth::SharedTokenSource;
 use crate::factory::ProviderType;
 use crate::provider::Provider;
 use crate::providers::file_resolve::{
     media_type_extension, resolve_refs, ProviderUploader, ResolvedRef,
 };
-use crate::transport::{Method, Transport, TransportRequest, UploadRequest};
+use crate::sse_stream::SseLimits;
+use crate::transport::{Method, Transport, TransportRequest, TransportResponse, UploadRequest};
 use crate::types::{
     Annotation, AnnotationKind, FileResolver, PartKind, PartUpdate, ProviderBuiltin, ProviderScope,
-    ReasoningConfig, ReasoningEffort, ReasoningSummary, ResolvedHandle, ToolChoice,
+    ReasoningConfig, ReasoningEffort, ReasoningSummary, ResolvedHandle, SafetyFeedback,
+    SafetyLevel, SafetyRating, SafetyTarget, ToolChoice,
 };
 use crate::{Error, RawConfig, Response, StreamEvent};
 use bytes::Bytes;
 use futures_util::{Stream, StreamExt as _};
 use std::collections::HashMap;
 use std::pin::Pin;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tracing::{debug, trace};
 
 /// OpenAI provider implementation.
 pub struct OpenAIProvider {
-    transport: Transport,
+    pub(super) transport: Transport,
     api_key: String,
-    base_url: String,
+    pub(super) base_url: String,
     /// Optional `OpenAI-Organization` header value for multi-org keys.
     organization: Option<String>,
     /// Optional `OpenAI-Project` header value for project-scoped keys.
@@ -36,19 +40,37 @@ pub struct OpenAIProvider {
     /// [`crate::InMemoryRateLimiter`] (or custom impl) for
     /// multi-tenant fairness.
     rate_limiter: crate::rate_limit::SharedRateLimiter,
+    /// Extra headers sent with every request, before any per-request
+    /// [`RawConfig::extra_headers`](crate::RawConfig::extra_headers).
+    extra_headers: Vec<(String, String)>,
+    /// Size caps on streamed response bodies.
+    pub(super) sse_limits: SseLimits,
+    /// Bearer-token source used instead of `api_key` when set.
+    token_source: Option<SharedTokenSource>,
 }
 
+/// Appended after an assistant prefill, which OpenAI can't continue
+/// natively. See [`crate::Prompt::with_assistant_prefill`].
+const PREFILL_INSTRUCTION: &str = "Continue your previous message exactly where it stops, \
+     without repeating any of it or adding any preamble.";
+
 impl OpenAIProvider {
+    /// The public OpenAI API root used by [`Self::new`].
+    pub const DEFAULT_BASE_URL: &'static str = "https://api.openai.com/v1";
+
     /// Create a new OpenAI provider with the default reqwest-backed transport.
     pub fn new(api_key: String) -> Result<Self, Error> {
         Ok(Self {
             transport: Transport::reqwest()?,
             api_key,
-            base_url: "https://api.openai.com/v1".to_string(),
+            base_url: Self::DEFAULT_BASE_URL.to_string(),
             organization: None,
             project: None,
             file_resolver: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
+            token_source: None,
         })
     }
 
@@ -62,6 +84,9 @@ impl OpenAIProvider {
             project: None,
             file_resolver: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
+            token_source: None,
         })
     }
 
@@ -77,6 +102,9 @@ impl OpenAIProvider {
             project: None,
             file_resolver: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
+            token_source: None,
         }
     }
 
@@ -87,6 +115,61 @@ impl OpenAIProvider {
         self
     }
 
+    /// Send `name: value` with every request. Precedence is as for
+    /// [`ProviderConfig::extra_headers`](crate::ProviderConfig::extra_headers).
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Cap line and event sizes on streamed responses. See
+    /// [`SseLimits`].
+    pub fn with_sse_limits(mut self, limits: SseLimits) -> Self {
+        self.sse_limits = limits;
+        self
+    }
+
+    /// Authenticate with tokens from `source`, asked for before every
+    /// request, instead of the static API key — for short-lived
+    /// credentials minted by a broker or gateway. See [`crate::auth`].
+    pub fn with_token_source(mut self, source: SharedTokenSource) -> Self {
+        self.token_source = Some(source);
+        self
+    }
+
+    /// The `Authorization` header: the token source's current token
+    /// when one is attached, the API key otherwise.
+    async fn auth_header(&self) -> Result<(String, String), Error> {
+        let token = match &self.token_source {
+            Some(source) => source.token().await?,
+            None => self.api_key.clone(),
+        };
+        Ok(("Authorization".to_string(), format!("Bearer {token}")))
+    }
+
+    /// Headers for a Responses API call: auth, `Content-Type` when
+    /// there's a JSON body, organization / project, then the
+    /// provider's extra headers and the per-call `extra`.
+    pub(super) async fn request_headers(
+        &self,
+        json_body: bool,
+        extra: &[(String, String)],
+    ) -> Result<Vec<(String, String)>, Error> {
+        let mut headers = vec![self.auth_header().await?];
+        if json_body {
+            headers.push(("Content-Type".to_string(), "application/json".to_string()));
+        }
+        if let Some(org) = &self.organization {
+            headers.push(("OpenAI-Organization".to_string(), org.clone()));
+        }
+        if let Some(project) = &self.project {
+            headers.push(("OpenAI-Project".to_string(), project.clone()));
+        }
+        crate::transport::merge_headers(&mut headers, &self.extra_headers);
+        crate::transport::merge_headers(&mut headers, extra);
+        Ok(headers)
+    }
+
     /// Attach an `OpenAI-Project` header. Required for project-scoped keys.
     pub fn with_project(mut self, project: impl Into<String>) -> Self {
         self.project = Some(project.into());
@@ -139,6 +222,101 @@ impl OpenAIProvider {
         account
     }
 
+    /// Reject unsupported inputs, resolve file refs and build the wire
+    /// request.
+    pub(super) async fn prepare_request(
+        &self,
+        prompt: &crate::Prompt,
+        config: &RawConfig,
+    ) -> Result<ResponsesRequest, Error> {
+        // The Responses API accepts only image / document inputs — reject
+        // audio / video up front rather than dropping them.
+        crate::providers::reject_unsupported_modalities(prompt.items(), "OpenAI", false, false)?;
+
+        // Resolve any file `Ref`s to provider handles (uploading on a miss)
+        // before the sync request build.
+        let resolved = resolve_refs(
+            prompt.items(),
+            &self.scope(),
+            self.file_resolver.as_deref(),
+            self,
+        )
+        .await?;
+        Ok(self.convert_request(prompt, config, &resolved))
+    }
+
+    /// POST `request` to `/responses` under a rate-limit permit. A 2xx
+    /// comes back with the permit still open for the caller to observe;
+    /// anything else is mapped to an error.
+    pub(super) async fn send_responses(
+        &self,
+        request: &ResponsesRequest,
+        config: &RawConfig,
+    ) -> Result<(TransportResponse, crate::rate_limit::RatePermit), Error> {
+        debug!(
+            model = %request.model,
+            messages = request.input.len(),
+            "sending OpenAI Responses API request"
+        );
+        trace!(
+            request = ?request,
+            "full OpenAI request body"
+        );
+
+        let body = crate::providers::encode_request_body(request, config.extra_body.as_ref())?;
+        // The idempotency key and request ID go first so a caller's own
+        // headers of the same names replace them.
+        let extra_headers: Vec<_> = config
+            .idempotency_key
+            .iter()
+            .map(|key| ("Idempotency-Key".to_string(), key.clone()))
+            .chain(
+                config
+                    .request_id
+                    .iter()
+                    .map(|id| ("X-Client-Request-Id".to_string(), id.clone())),
+            )
+            .chain(config.extra_headers.iter().cloned())
+            .collect();
+        let req = TransportRequest {
+            url: format!("{}/responses", self.base_url),
+            headers: self.request_headers(true, &extra_headers).await?,
+            body,
+        };
+
+        // Acquire a rate-limit permit. The default `NoOpRateLimiter`
+        // returns immediately; a shared `InMemoryRateLimiter` paces
+        // and prioritises us. The permit's `observe()` feeds the
+        // response's normalised headers back to the limiter.
+        //
+        // The bucket key includes `account_key()` (base_url + org +
+        // project) so two providers pointing at different
+        // deployments (e.g. `api.openai.com` vs Azure) with the same
+        // model name don't collide on a shared limiter — each
+        // upstream has its own quota.
+        let scope = crate::rate_limit::RateScope {
+            bucket_key: format!("OpenAI|{}|{}", self.account_key(), config.model),
+            tenant: config.tenant.unwrap_or(uuid::Uuid::nil()),
+            priority: config.priority.unwrap_or_default(),
+        };
+        let permit = self.rate_limiter.acquire(&scope).await?;
+        let response = match self.transport.send(req).await {
+            Ok(r) => r,
+            Err(e) => {
+                // Transport-level failure: no headers to feed back,
+                // so report as OtherFailure (no AIMD update) and
+                // surface the error.
+                permit.observe(crate::rate_limit::RateOutcome::OtherFailure);
+                return Err(e);
+            }
+        };
+
+        if !(200..300).contains(&response.status) {
+            return Err(http_error(response, Some(permit)).await);
+        }
+        Ok((response, permit))
+    }
+
     /// Convert internal request to OpenAI Responses API format.
     ///
     /// `resolved` maps each file-`Ref` id to its wire-ready reference, built
@@ -161,14 +339,41 @@ impl OpenAIProvider {
         for item in &messages[start_index..] {
             Self::flatten_input_item(item, &mut input, resolved);
         }
+        // The Responses API has no prefill — a trailing assistant
+        // message is just history and the model answers afresh — so
+        // ask it to pick up where the prefill stops.
+        if prompt.assistant_prefill().is_some() {
+            Self::flatten_input_item(
+                &crate::types::InputItem::developer(PREFILL_INSTRUCTION),
+                &mut input,
+                resolved,
+            );
+        }
+
+        // Reasoning models (o-series, GPT-5) 400 on sampling
+        // parameters. Omit them rather than make every caller branch
+        // on the model name.
+        let sampling = crate::Capabilities::openai(&config.model).sampling_parameters;
+        if !sampling
+            && (config.temperature.is_some()
+                || config.top_p.is_some()
+                || config.presence_penalty.is_some()
+                || config.frequency_penalty.is_some())
+        {
+            tracing::debug!(
+                model = %config.model,
+                "omitting sampling parameters unsupported by reasoning model"
+            );
+        }
+        let sampled = |value: Option<f32>| value.filter(|_| sampling);
 
         ResponsesRequest {
             model: config.model.clone(),
             input,
             instructions: None,
-            temperature: config.temperature,
+            temperature: sampled(config.temperature),
             max_output_tokens: config.max_tokens,
-            top_p: config.top_p,
+            top_p: sampled(config.top_p),
             tools: config
                 .tools
                 .as_ref()
@@ -178,18 +383,79 @@ impl OpenAIProvider {
             previous_response_id,
             stream: None,
             store: Some(config.store.unwrap_or(false)),
+            background: None,
             reasoning: config.reasoning.as_ref().map(convert_reasoning),
             stop: config.stop.clone(),
-            presence_penalty: config.presence_penalty,
-            frequency_penalty: config.frequency_penalty,
-            prompt_cache_key: derive_prompt_cache_key(messages),
+            presence_penalty: sampled(config.presence_penalty),
+            frequency_penalty: sampled(config.frequency_penalty),
+            prompt_cache_key: config
+                .metadata
+                .prompt_cache_key
+                .clone()
+                .or_else(|| derive_prompt_cache_key(messages)),
             text: config
                 .response_format
                 .as_ref()
                 .and_then(convert_response_format),
+            service_tier: config
+                .provider_options
+                .openai
+                .as_ref()
+                .and_then(|options| options.service_tier)
+                .map(convert_service_tier),
+            safety_identifier: config.metadata.user_id.clone(),
         }
     }
 
+    /// Attach the images / files in a tool result's `content` to its
+    /// text `output`, as the content-array form of
+    /// `function_call_output`. Text-only results stay a bare string.
+    /// The media go through the ordinary user-turn conversion, so
+    /// `Ref` resolution and data-URL encoding behave identically.
+    fn tool_output_with_media(
+        output: String,
+        content: &[crate::types::UserPart],
+        resolved: &HashMap<String, ResolvedRef>,
+    ) -> crate::providers::openai::types::OpenAIToolOutput {
+        use crate::providers::openai::types::{
+            OpenAIContentPart, OpenAIInputMessage, OpenAIMessageContent, OpenAIToolOutput,
+        };
+        use crate::types::{InputItem, UserPart};
+
+        let media: Vec<UserPart> = content
+            .iter()
+            .filter(|part| matches!(part, UserPart::Image(_) | UserPart::Document(_)))
+            .cloned()
+            .collect();
+        if media.is_empty() {
+            return OpenAIToolOutput::Text(output);
+        }
+        let mut converted = Vec::new();
+        Self::flatten_input_item(
+            &InputItem::User {
+                content: media,
+                name: None,
+                metadata: Default::default(),
+            },
+            &mut converted,
+            resolved,
+        );
+        let mut parts = Vec::new();
+        if !output.is_empty() {
+            parts.push(OpenAIContentPart::InputText { text: output });
+        }
+        for message in converted {
+            if let OpenAIInputMessage::Regular {
+                content: OpenAIMessageContent::Parts(media),
+                ..
+            } = message
+            {
+                parts.extend(media);
+            }
+        }
+        OpenAIToolOutput::Parts(parts)
+    }
+
     /// Flatten one canonical `InputItem` into one or more OpenAI input
     /// items. OpenAI's wire model puts function_call / function_call_output
     /// as siblings of message items, so an Assistant turn with mixed
@@ -210,9 +476,19 @@ impl OpenAIProvider {
                     content: crate::providers::openai::types::OpenAIMessageContent::Text(
                         content.clone(),
                     ),
+                    name: None,
+                });
+            }
+            InputItem::Developer(content) => {
+                out.push(OpenAIInputMessage::Regular {
+                    role: "developer".to_string(),
+                    content: crate::providers::openai::types::OpenAIMessageContent::Text(
+                        content.clone(),
+                    ),
+                    name: None,
                 });
             }
-            InputItem::User { content } => {
+            InputItem::User { content, name, .. } => {
                 use crate::providers::openai::types::OpenAIContentPart;
                 // Build a content-parts list. Tool results become their own
                 // top-level items; text and images become InputText /
@@ -255,7 +531,11 @@ impl OpenAIProvider {
                                 }
                             },
                         },
-                        UserPart::ToolResult { call_id, content } => {
+                        UserPart::ToolResult {
+                            call_id,
+                            content,
+                            is_error,
+                        } => {
                             // A user turn mixing free text with a tool
                             // result (legitimate on Anthropic/Gemini,
                             // and how round-tripped history can look)
@@ -271,10 +551,12 @@ impl OpenAIProvider {
                             // trailing user text. Left as-is until
                             // verified rather than risk regressing a
                             // working path on an unverified assumption.
-                            push_user_parts(out, &mut parts);
+                            push_user_parts(out, &mut parts, name.as_deref());
+                            let output =
+                                encode_tool_output(flatten_user_parts_to_text(content), *is_error);
                             out.push(OpenAIInputMessage::FunctionCallOutput {
                                 call_id: call_id.clone(),
-                                output: flatten_user_parts_to_text(content),
+                                output: Self::tool_output_with_media(output, content, resolved),
                             });
                         }
                         UserPart::Audio(_) => {
@@ -339,9 +621,9 @@ impl OpenAIProvider {
                         }
                     }
                 }
-                push_user_parts(out, &mut parts);
+                push_user_parts(out, &mut parts, name.as_deref());
             }
-            InputItem::Assistant { content } => {
+            InputItem::Assistant { content, name, .. } => {
                 let mut buffered_text = String::new();
                 for part in content {
                     match part {
@@ -365,6 +647,7 @@ impl OpenAIProvider {
                                         crate::providers::openai::types::OpenAIMessageContent::Text(
                                             std::mem::take(&mut buffered_text),
                                         ),
+                                    name: name.clone(),
                                 });
                             }
                             out.push(OpenAIInputMessage::FunctionCall {
@@ -390,6 +673,7 @@ impl OpenAIProvider {
                         content: crate::providers::openai::types::OpenAIMessageContent::Text(
                             buffered_text,
                         ),
+                        name: name.clone(),
                     });
                 }
             }
@@ -501,7 +785,7 @@ fn parse_openai_reset(s: &str) -> Option<std::time::Duration> {
 /// response (success or 429) carries these — we always populate the
 /// info struct, even on success, so the limiter's AIMD step gets a real
 /// observed-capacity signal instead of just "no 429 fired".
-fn parse_openai_rate_info(
+pub(super) fn parse_openai_rate_info(
     response: &crate::transport::TransportResponse,
 ) -> crate::rate_limit::ProviderRateInfo {
     crate::rate_limit::ProviderRateInfo {
@@ -514,6 +798,90 @@ fn parse_openai_rate_info(
     }
 }
 
+/// Longest of OpenAI's `x-ratelimit-reset-requests` /
+/// `x-ratelimit-reset-tokens` headers, as a whole-second wait hint.
+/// A 429 doesn't say which budget tripped it, so waiting for the later
+/// of the two is the only choice guaranteed not to re-trip either.
+fn openai_reset_hint(response: &crate::transport::TransportResponse) -> Option<u64> {
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"]
+        .into_iter()
+        .filter_map(|name| response.header(name).and_then(parse_openai_reset))
+        .max()
+        .map(crate::transport::retry_after_from_reset)
+}
+
+/// Map a non-2xx response onto an [`Error`], feeding the rate-limit
+/// outcome to `permit` when the call held one.
+pub(super) async fn http_error(
+    response: TransportResponse,
+    permit: Option<crate::rate_limit::RatePermit>,
+) -> Error {
+    let status = response.status;
+    let info = parse_openai_rate_info(&response);
+    // OpenAI's 429s frequently omit `Retry-After` but always
+    // carry the `x-ratelimit-reset-*` headers; fall back to
+    // those so the retry helper and the limiter both wait for
+    // the window instead of guessing.
+    let retry_after =
+        crate::transport::parse_retry_after(response.header("retry-after")).or_else(|| {
+            (status == 429)
+                .then(|| openai_reset_hint(&response))
+                .flatten()
+        });
+    // Feed the limiter before draining the body — the body
+    // collect is async and we don't want the limiter's
+    // AIMD step to wait on it.
+    //
+    // A 5xx that carries a `Retry-After` is semantically a
+    // rate-limit-ish signal: the upstream is asking us to
+    // back off for `Retry-After` before retrying, same as a
+    // 429. Report it as `RateLimited` so the AIMD model
+    // halves rps and parks for the hint; otherwise an
+    // `OtherFailure` would still trigger the AIMD halving,
+    // but the limiter wouldn't park for the suggested
+    // duration.
+    if let Some(permit) = permit {
+        let rate_limited = status == 429 || (status >= 500 && retry_after.is_some());
+        if rate_limited {
+            permit.observe(crate::rate_limit::RateOutcome::RateLimited {
+                retry_after: retry_after.map(std::time::Duration::from_secs),
+                info,
+            });
+        } else {
+            permit.observe(crate::rate_limit::RateOutcome::OtherFailure);
+        }
+    }
+    let request_id = response.header("x-request-id").map(str::to_owned);
+    let body_bytes = response.collect_body().await.unwrap_or_default();
+    let body_str = crate::logging::scrub(&String::from_utf8_lossy(&body_bytes));
+    parse_openai_error(status, retry_after, &body_str).with_request_id(request_id.as_deref())
+}
+
+/// Decode a 2xx Responses API SSE body into unified stream events.
+pub(super) fn response_events(
+    response: TransportResponse,
+    limits: SseLimits,
+) -> impl Stream<Item = Result<StreamEvent, Error>> + Send {
+    use crate::sse_stream::SseStreamExt;
+    let mut state = OpenAIStreamState::new();
+    response
+        .body
+        .sse_events("OpenAI")
+        .with_limits(limits)
+        .map(move |sse_result| -> Result<Vec<StreamEvent>, Error> {
+            let sse_event = sse_result?;
+            trace!(event = ?sse_event, "received OpenAI SSE event");
+            let stream_event = parse_stream_event(&sse_event.data)?;
+            state.process(stream_event)
+        })
+        .flat_map(|result| match result {
+            Ok(events) => {
+                futures_util::stream::iter(events.into_iter().map(Ok).collect::<Vec<_>>())
+            }
+            Err(e) => futures_util::stream::iter(vec![Err(e)]),
+        })
+}
+
 /// Map an OpenAI HTTP error response onto our [`Error`] variants.
 ///
 /// OpenAI returns `{"error":{"message":..., "type":..., "code":...}}` on
@@ -521,11 +889,17 @@ fn parse_openai_rate_info(
 /// HTTP status:
 ///
 /// - 401 → [`Error::Auth`]
-/// - 429 → [`Error::RateLimit`] (carries `Retry-After` if present)
+/// - 429 → [`Error::RateLimit`] (carries `Retry-After`, or the
+///   `x-ratelimit-reset-*` fallback, if present) — except
+///   `code: "insufficient_quota"`, which OpenAI also sends as a 429
+///   but means the account is out of credit: that becomes a
+///   non-retryable [`Error::Provider`], since no amount of backoff
+///   will clear it
 /// - any other → [`Error::Provider`] with status, type, and message
 ///
-/// The full body is preserved in the message so callers can still extract
-/// the unparsed structured fields if they need them.
+/// The message is the envelope's `error.message` (the whole body when
+/// it isn't an envelope); the full body is kept in
+/// [`crate::ProviderErrorDetails::raw_body`].
 pub(crate) fn parse_openai_error(
     status: u16,
     retry_after_seconds: Option<u64>,
@@ -560,30 +934,120 @@ pub(crate) fn parse_openai_error(
     // `code: "context_length_exceeded"` for this case; surface as a
     // typed variant so callers driving long conversations can trigger
     // compaction without parsing strings.
+    let error = if code == "context_length_exceeded" {
+        Error::context_window_exceeded("OpenAI", format!("HTTP {status}: {message}"))
+    } else {
+        match status {
+            401 => Error::auth_with_status(401, format!("OpenAI 401 ({kind} {code}): {message}")),
+            429 if code == "insufficient_quota" || kind == "insufficient_quota" => {
+                Error::Provider {
+                    provider: "OpenAI",
+                    status: Some(429),
+                    retryable: false,
+                    retry_after: None,
+                    message: format!("HTTP 429 ({kind} {code}): {message}"),
+                    details: None,
+                }
+            }
+            429 => Error::rate_limit(
+                retry_after_seconds,
+                format!("OpenAI 429 ({kind} {code}): {message}"),
+            ),
+            // RFC 7231 explicitly defines `Retry-After` on 503 (and it
+            // shows up on other 5xx in practice); surface it via
+            // `Error::Provider.retry_after` so the retry helper honours
+            // the server's instruction rather than blind exponential
+            // backoff.
+            _ => Error::provider_with_retry_after(
+                "OpenAI",
+                status,
+                retry_after_seconds,
+                format!("HTTP {status} ({kind} {code}): {message}"),
+            ),
+        }
+    };
+    error.with_provider_details(
+        crate::error::ProviderErrorDetails::new()
+            .with_error_type(kind)
+            .with_code(code)
+            .with_raw_body(body),
+    )
+}
+
+/// Classify an in-stream `error` / `response.failed` payload. The
+/// Responses API reports some failures only this way, after a 200 has
+/// gone out, so they get the same typed variants as their HTTP
+/// counterparts:
+///
+/// - `context_length_exceeded` → [`Error::ContextWindowExceeded`].
+/// - rate limits → [`Error::RateLimit`], which the rate limiter's
+///   stream observer also learns from.
+/// - content-policy refusals → [`Error::ContentPolicy`].
+/// - `server_error` / `server_overloaded` / `internal_error` → a
+///   retryable [`Error::Provider`], mirroring the pre-stream 5xx
+///   classification so a transient blip retries the same either side
+///   of the first byte.
+/// - anything else → a non-retryable [`Error::Provider`].
+fn stream_error(error: &super::types::ErrorDetails) -> Error {
+    let code = error.code.as_deref().unwrap_or_default();
+    let is_any = |names: &[&str]| names.contains(&error.r#type.as_str()) || names.contains(&code);
+    let message = if error.r#type.is_empty() {
+        format!("{code}: {}", error.message)
+    } else {
+        format!("{}: {}", error.r#type, error.message)
+    };
     if code == "context_length_exceeded" {
-        return Error::context_window_exceeded("OpenAI", format!("HTTP {status}: {message}"));
-    }
-
-    match status {
-        401 => Error::auth_with_status(401, format!("OpenAI 401 ({kind} {code}): {message}")),
-        429 => Error::rate_limit(
-            retry_after_seconds,
-            format!("OpenAI 429 ({kind} {code}): {message}"),
-        ),
-        // RFC 7231 explicitly defines `Retry-After` on 503 (and it
-        // shows up on other 5xx in practice); surface it via
-        // `Error::Provider.retry_after` so the retry helper honours
-        // the server's instruction rather than blind exponential
-        // backoff.
-        _ => Error::provider_with_retry_after(
-            "OpenAI",
-            status,
-            retry_after_seconds,
-            format!("HTTP {status} ({kind} {code}): {message}"),
-        ),
+        return Error::context_window_exceeded("OpenAI", message);
+    }
+    if is_any(&["rate_limit_exceeded", "rate_limit_error"]) {
+        return Error::rate_limit(None, format!("OpenAI mid-stream {message}"));
+    }
+    if is_any(&["content_policy_violation", "content_filter"]) {
+        return Error::content_policy("OpenAI", message);
+    }
+    Error::Provider {
+        provider: "OpenAI",
+        status: None,
+        retryable: is_any(&["server_error", "server_overloaded", "internal_error"]),
+        retry_after: None,
+        message,
+        details: stream_error_details(error),
+    }
+}
+
+/// Decode one SSE data payload. Error frames in the shapes
+/// [`StreamErrorFrame`] covers become [`OpenAIStreamEvent::Error`]
+/// rather than a parse failure.
+fn parse_stream_event(data: &str) -> Result<OpenAIStreamEvent, Error> {
+    let parse_error = match serde_json::from_str::<OpenAIStreamEvent>(data) {
+        Ok(event) => return Ok(event),
+        Err(e) => e,
+    };
+    let value: serde_json::Value = serde_json::from_str(data)?;
+    let is_error_frame = match value.get("type") {
+        Some(kind) => kind == "error",
+        None => value.get("error").is_some(),
+    };
+    match serde_json::from_value::<StreamErrorFrame>(value) {
+        Ok(frame) if is_error_frame => Ok(OpenAIStreamEvent::Error {
+            error: frame.into(),
+        }),
+        _ => Err(parse_error.into()),
     }
 }
 
+/// Structured details for an in-stream `error` / `response.failed`
+/// frame. No headers are available mid-stream, so no request ID.
+fn stream_error_details(
+    error: &super::types::ErrorDetails,
+) -> Option<Box<crate::error::ProviderErrorDetails>> {
+    let mut details = crate::error::ProviderErrorDetails::new().with_error_type(&error.r#type);
+    if let Some(code) = &error.code {
+        details = details.with_code(code);
+    }
+    (!details.is_empty()).then(|| Box::new(details))
+}
+
 /// Derive a stable cache key from the message prefix that precedes
 /// the first [`crate::UserPart::CacheBreakpoint`]. Returns `None` when
 /// no breakpoint is present (callers who don't opt into caching get
@@ -600,13 +1064,13 @@ fn derive_prompt_cache_key(messages: &[crate::types::InputItem]) -> Option<Strin
     // Common case: no breakpoint anywhere → no key, and skip hashing
     // the entire history (this runs on every request).
     let has_breakpoint = messages.iter().any(|item| match item {
-        InputItem::User { content } => content
+        InputItem::User { content, .. } => content
             .iter()
             .any(|p| matches!(p, UserPart::CacheBreakpoint)),
-        InputItem::Assistant { content } => content
+        InputItem::Assistant { content, .. } => content
             .iter()
             .any(|p| matches!(p, AssistantPart::CacheBreakpoint)),
-        InputItem::System(_) => false,
+        InputItem::System(_) | InputItem::Developer(_) => false,
     });
     if !has_breakpoint {
         return None;
@@ -621,7 +1085,11 @@ fn derive_prompt_cache_key(messages: &[crate::types::InputItem]) -> Option<Strin
                 "system".hash(&mut hasher);
                 s.hash(&mut hasher);
             }
-            InputItem::User { content } => {
+            InputItem::Developer(s) => {
+                "developer".hash(&mut hasher);
+                s.hash(&mut hasher);
+            }
+            InputItem::User { content, .. } => {
                 "user".hash(&mut hasher);
                 for part in content {
                     match part {
@@ -635,8 +1103,13 @@ fn derive_prompt_cache_key(messages: &[crate::types::InputItem]) -> Option<Strin
                             // and small re-encodings would defeat the key.
                             "<media>".hash(&mut hasher);
                         }
-                        UserPart::ToolResult { call_id, content } => {
+                        UserPart::ToolResult {
+                            call_id,
+                            content,
+                            is_error,
+                        } => {
                             call_id.hash(&mut hasher);
+                            is_error.hash(&mut hasher);
                             for inner in content {
                                 if let UserPart::Text(s) = inner {
                                     s.hash(&mut hasher);
@@ -650,7 +1123,7 @@ fn derive_prompt_cache_key(messages: &[crate::types::InputItem]) -> Option<Strin
                     }
                 }
             }
-            InputItem::Assistant { content } => {
+            InputItem::Assistant { content, .. } => {
                 "assistant".hash(&mut hasher);
                 for part in content {
                     match part {
@@ -704,6 +1177,7 @@ fn derive_prompt_cache_key(messages: &[crate::types::InputItem]) -> Option<Strin
 fn push_user_parts(
     out: &mut Vec<crate::providers::openai::types::OpenAIInputMessage>,
     parts: &mut Vec<crate::providers::openai::types::OpenAIContentPart>,
+    name: Option<&str>,
 ) {
     use crate::providers::openai::types::{
         OpenAIContentPart, OpenAIInputMessage, OpenAIMessageContent,
@@ -718,6 +1192,7 @@ fn push_user_parts(
             out.push(OpenAIInputMessage::Regular {
                 role: "user".to_string(),
                 content: OpenAIMessageContent::Text(text.clone()),
+                name: name.map(str::to_string),
             });
             return;
         }
@@ -725,6 +1200,7 @@ fn push_user_parts(
     out.push(OpenAIInputMessage::Regular {
         role: "user".to_string(),
         content: OpenAIMessageContent::Parts(drained),
+        name: name.map(str::to_string),
     });
 }
 
@@ -751,9 +1227,30 @@ fn convert_response_format(
     Some(OpenAITextConfig { format })
 }
 
+/// `function_call_output` has no error flag, so a failed tool's output
+/// is wrapped as `{"error": …}` — JSON details keep their structure —
+/// which the model reliably reads as a failure rather than as data.
+fn encode_tool_output(output: String, is_error: bool) -> String {
+    if !is_error {
+        return output;
+    }
+    let error = serde_json::from_str::<serde_json::Value>(&output)
+        .unwrap_or(serde_json::Value::String(output));
+    serde_json::json!({ "error": error }).to_string()
+}
+
+fn convert_service_tier(tier: crate::OpenAIServiceTier) -> &'static str {
+    match tier {
+        crate::OpenAIServiceTier::Auto => "auto",
+        crate::OpenAIServiceTier::Default => "default",
+        crate::OpenAIServiceTier::Flex => "flex",
+        crate::OpenAIServiceTier::Priority => "priority",
+    }
+}
+
 fn convert_reasoning(cfg: &ReasoningConfig) -> OpenAIReasoning {
     OpenAIReasoning {
-        effort: cfg.effort.map(|e| match e {
+        effort: cfg.effective_effort().map(|e| match e {
             ReasoningEffort::Low => "low",
             ReasoningEffort::Medium => "medium",
             ReasoningEffort::High => "high",
@@ -779,7 +1276,7 @@ fn find_latest_openai_continuation(
 ) -> (Option<String>, usize) {
     use crate::types::{AssistantPart, InputItem, ProviderContinuation};
     for (i, item) in messages.iter().enumerate().rev() {
-        if let InputItem::Assistant { content } = item {
+        if let InputItem::Assistant { content, .. } = item {
             for part in content.iter().rev() {
                 if let AssistantPart::Continuation(ProviderContinuation::OpenAI { response_id }) =
                     part
@@ -796,6 +1293,43 @@ fn find_latest_openai_continuation(
 ///
 /// `Other` variants (forward-compat tag values we don't recognize) are
 /// dropped rather than fabricated as broken citations.
+/// Safety outcome of a finished response: a `content_filter`
+/// incomplete and/or Azure's per-category filter results. `None` when
+/// the response carries neither.
+fn safety_feedback(response: &ResponsesResponse) -> Option<SafetyFeedback> {
+    let filtered = response
+        .incomplete_details
+        .as_ref()
+        .is_some_and(|d| d.reason == "content_filter");
+    if !filtered && response.content_filters.is_empty() {
+        return None;
+    }
+    let mut feedback = SafetyFeedback {
+        block_reason: filtered.then(|| "content_filter".to_string()),
+        ..SafetyFeedback::default()
+    };
+    for filter in &response.content_filters {
+        let target = if filter.source_type == "prompt" {
+            feedback.prompt_blocked |= filter.blocked;
+            SafetyTarget::Prompt
+        } else {
+            SafetyTarget::Response
+        };
+        feedback.ratings.extend(
+            filter
+                .content_filter_results
+                .iter()
+                .map(|(category, result)| SafetyRating {
+                    category: category.clone(),
+                    target,
+                    level: result.severity.as_deref().and_then(SafetyLevel::parse),
+                    blocked: result.filtered,
+                }),
+        );
+    }
+    Some(feedback)
+}
+
 fn map_openai_annotation(a: OpenAIAnnotation) -> Option<Annotation> {
     match a {
         OpenAIAnnotation::UrlCitation {
@@ -809,6 +1343,7 @@ fn map_openai_annotation(a: OpenAIAnnotation) -> Option<Annotation> {
             end: end_index,
             source: url,
             title,
+            snippet: None,
         }),
         OpenAIAnnotation::FileCitation {
             file_id,
@@ -822,6 +1357,28 @@ fn map_openai_annotation(a: OpenAIAnnotation) -> Option<Annotation> {
             end: index,
             source: file_id,
             title: filename,
+            snippet: None,
+        }),
+        OpenAIAnnotation::ContainerFileCitation {
+            file_id,
+            filename,
+            start_index,
+            end_index,
+        } => Some(Annotation {
+            kind: AnnotationKind::FileCitation,
+            start: start_index,
+            end: end_index,
+            source: file_id,
+            title: filename,
+            snippet: None,
+        }),
+        OpenAIAnnotation::FilePath { file_id, index } => Some(Annotation {
+            kind: AnnotationKind::FileCitation,
+            start: index,
+            end: index,
+            source: file_id,
+            title: None,
+            snippet: None,
         }),
         OpenAIAnnotation::Other => None,
     }
@@ -860,6 +1417,9 @@ pub(crate) struct OpenAIStreamState {
     /// — we reconcile from the item's complete `arguments` so they
     /// aren't silently lost.
     fn_args_streamed: std::collections::HashSet<(u32, Option<u32>)>,
+    /// Keys of `refusal` parts that received at least one
+    /// `refusal.delta`; `refusal.done` fills in the others.
+    refusal_streamed: std::collections::HashSet<(u32, Option<u32>)>,
 }
 
 impl OpenAIStreamState {
@@ -868,6 +1428,7 @@ impl OpenAIStreamState {
             tracker: crate::providers::part_tracker::PartTracker::new(),
             emitted_continuation: false,
             fn_args_streamed: std::collections::HashSet::new(),
+            refusal_streamed: std::collections::HashSet::new(),
         }
     }
 
@@ -889,51 +1450,15 @@ impl OpenAIStreamState {
     /// Process one OpenAI wire event into 0 or more `StreamEvent`s.
     pub(crate) fn process(&mut self, event: OpenAIStreamEvent) -> Result<Vec<StreamEvent>, Error> {
         match event {
-            OpenAIStreamEvent::Error { error } => {
-                // OpenAI's Responses API returns 200 OK and emits the
-                // context-length error inside the SSE stream as an
-                // `event: error` frame with `code:
-                // context_length_exceeded`. Detect it here so callers
-                // driving long conversations get the typed
-                // `ContextWindowExceeded` variant instead of a generic
-                // streaming/provider error.
-                if error.code.as_deref() == Some("context_length_exceeded") {
-                    return Err(Error::context_window_exceeded(
-                        "OpenAI",
-                        format!("{}: {}", error.r#type, error.message),
-                    ));
-                }
-                // Mid-stream transient codes mirror the *pre*-stream
-                // 5xx classification: a `server_error` /
-                // `server_overloaded` / `internal_error` frame is the
-                // same upstream condition the HTTP layer would
-                // surface as a 5xx, so the retryable verdict should
-                // match. Without this, a transient blip mid-stream
-                // becomes terminal where the same blip pre-stream
-                // would retry cleanly.
-                let retryable = matches!(
-                    error.r#type.as_str(),
-                    "server_error" | "server_overloaded" | "internal_error"
-                ) || matches!(
-                    error.code.as_deref(),
-                    Some("server_error" | "server_overloaded" | "internal_error")
-                );
-                Err(Error::Provider {
-                    provider: "OpenAI",
-                    status: None,
-                    retryable,
-                    retry_after: None,
-                    message: format!("{}: {}", error.r#type, error.message),
-                })
-            }
+            OpenAIStreamEvent::Error { error } => Err(stream_error(&error)),
 
             // `response.id` is stable across created/in_progress/
             // completed frames — emit the Continuation part at
             // end-of-stream (response.completed) so it lands after the
             // assistant content in the final part order.
-            OpenAIStreamEvent::ResponseCreated | OpenAIStreamEvent::ResponseInProgress => {
-                Ok(vec![])
-            }
+            OpenAIStreamEvent::ResponseCreated
+            | OpenAIStreamEvent::ResponseInProgress
+            | OpenAIStreamEvent::ResponseQueued => Ok(vec![]),
 
             OpenAIStreamEvent::OutputItemAdded { output_index, item } => {
                 match item.r#type.as_str() {
@@ -1002,9 +1527,10 @@ impl OpenAIStreamState {
                 if let Some(idx) = self.tracker.index_of(&key) {
                     if item.r#type == "web_search_call" {
                         if let Some(action) = &item.action {
-                            if let Ok(delta) = serde_json::to_string(action) {
-                                out.push(StreamEvent::Delta { index: idx, delta });
-                            }
+                            out.push(StreamEvent::Delta {
+                                index: idx,
+                                delta: action.get().to_string(),
+                            });
                         }
                     }
                     // Reconcile function-call arguments: if no
@@ -1084,7 +1610,28 @@ impl OpenAIStreamState {
                 output_index,
                 content_index,
                 delta,
-            } => self.text_or_refusal_delta(output_index, content_index, delta, "refusal"),
+            } => {
+                let events =
+                    self.text_or_refusal_delta(output_index, content_index, delta, "refusal")?;
+                if !events.is_empty() {
+                    self.refusal_streamed
+                        .insert((output_index, Some(content_index)));
+                }
+                Ok(events)
+            }
+            OpenAIStreamEvent::RefusalDone {
+                output_index,
+                content_index,
+                refusal,
+            } => {
+                if self
+                    .refusal_streamed
+                    .remove(&(output_index, Some(content_index)))
+                {
+                    return Ok(vec![]);
+                }
+                self.text_or_refusal_delta(output_index, content_index, refusal, "refusal")
+            }
 
             OpenAIStreamEvent::ReasoningSummaryTextDelta {
                 output_index,
@@ -1142,6 +1689,9 @@ impl OpenAIStreamState {
                 } else {
                     crate::types::FinishReason::Stop
                 };
+                out.extend(
+                    safety_feedback(&response).map(|feedback| StreamEvent::Safety { feedback }),
+                );
                 out.push(StreamEvent::Done {
                     finish_reason,
                     usage: response.usage.map(Into::into).unwrap_or_default(),
@@ -1157,8 +1707,12 @@ impl OpenAIStreamState {
                 {
                     Some("max_output_tokens") => crate::types::FinishReason::Length,
                     Some("content_filter") => crate::types::FinishReason::ContentFilter,
-                    _ => crate::types::FinishReason::Stop,
+                    Some(other) => crate::types::FinishReason::Other(other.to_string()),
+                    None => crate::types::FinishReason::Incomplete,
                 };
+                out.extend(
+                    safety_feedback(&response).map(|feedback| StreamEvent::Safety { feedback }),
+                );
                 out.push(StreamEvent::Done {
                     finish_reason,
                     usage: response.usage.map(Into::into).unwrap_or_default(),
@@ -1166,33 +1720,13 @@ impl OpenAIStreamState {
                 Ok(out)
             }
             OpenAIStreamEvent::ResponseFailed { response, error } => {
-                let inner_error = response
-                    .as_ref()
-                    .and_then(|r| r.error.as_ref())
-                    .or(error.as_ref());
-                let message = inner_error
-                    .map(|e| format!("{}: {}", e.r#type, e.message))
-                    .unwrap_or_else(|| "response failed without error details".to_string());
-                // Same retryable-by-transient-type rule as the
-                // mid-stream `error` frame: a `server_error` /
-                // `server_overloaded` / `internal_error` is the
-                // streaming counterpart of a pre-stream 5xx.
-                let retryable = inner_error.is_some_and(|e| {
-                    matches!(
-                        e.r#type.as_str(),
-                        "server_error" | "server_overloaded" | "internal_error"
-                    ) || matches!(
-                        e.code.as_deref(),
-                        Some("server_error" | "server_overloaded" | "internal_error")
-                    )
-                });
-                Err(Error::Provider {
-                    provider: "OpenAI",
-                    status: None,
-                    retryable,
-                    retry_after: None,
-                    message: format!("response.failed — {message}"),
-                })
+                match response.and_then(|r| r.error).or(error) {
+                    Some(error) => Err(stream_error(&error)),
+                    None => Err(Error::provider(
+                        "OpenAI",
+                        "response.failed without error details",
+                    )),
+                }
             }
 
             // Final-canonical-value / lifecycle frames whose payload
@@ -1201,7 +1735,6 @@ impl OpenAIStreamState {
             OpenAIStreamEvent::OutputTextDone
             | OpenAIStreamEvent::ReasoningSummaryTextDone
             | OpenAIStreamEvent::ReasoningTextDone
-            | OpenAIStreamEvent::RefusalDone
             | OpenAIStreamEvent::FunctionCallArgumentsDone
             | OpenAIStreamEvent::WebSearchCallInProgress
             | OpenAIStreamEvent::WebSearchCallSearching
@@ -1325,10 +1858,7 @@ impl ProviderUploader for OpenAIProvider {
             Box::pin(stream_body);
 
         let mut headers = vec![
-            (
-                "Authorization".to_string(),
-                format!("Bearer {}", self.api_key),
-            ),
+            self.auth_header().await?,
             (
                 "Content-Type".to_string(),
                 format!("multipart/form-data; boundary={boundary}"),
@@ -1351,10 +1881,12 @@ impl ProviderUploader for OpenAIProvider {
         let response = self.transport.send_upload(req).await?;
         let status = response.status;
         let retry_after = crate::transport::parse_retry_after(response.header("retry-after"));
+        let request_id = response.header("x-request-id").map(str::to_owned);
         let bytes = response.collect_body().await.unwrap_or_default();
         if !(200..300).contains(&status) {
-            let body_str = String::from_utf8_lossy(&bytes).into_owned();
-            return Err(parse_openai_error(status, retry_after, &body_str));
+            let body_str = crate::logging::scrub(&String::from_utf8_lossy(&bytes));
+            return Err(parse_openai_error(status, retry_after, &body_str)
+                .with_request_id(request_id.as_deref()));
         }
 
         #[derive(serde::Deserialize)]
@@ -1372,154 +1904,26 @@ impl ProviderUploader for OpenAIProvider {
 
 #[async_trait::async_trait]
 impl Provider for OpenAIProvider {
+    fn name(&self) -> &str {
+        "OpenAI"
+    }
+
     /// Generate a chat completion (internally always streams).
     async fn generate(
         &self,
         prompt: &crate::Prompt,
         config: &RawConfig,
     ) -> Result<Response, Error> {
-        // The Responses API accepts only image / document inputs — reject
-        // audio / video up front rather than dropping them.
-        crate::providers::reject_unsupported_modalities(prompt.items(), "OpenAI", false, false)?;
-
-        // Resolve any file `Ref`s to provider handles (uploading on a miss)
-        // before the sync request build.
-        let resolved = resolve_refs(
-            prompt.items(),
-            &self.scope(),
-            self.file_resolver.as_deref(),
-            self,
-        )
-        .await?;
-        let mut openai_request = self.convert_request(prompt, config, &resolved);
+        let mut openai_request = self.prepare_request(prompt, config).await?;
         openai_request.stream = Some(true);
-
-        debug!(
-            model = %openai_request.model,
-            messages = openai_request.input.len(),
-            "sending OpenAI Responses API request"
-        );
-        trace!(
-            request = ?openai_request,
-            "full OpenAI request body"
-        );
-
-        let body = serde_json::to_vec(&openai_request)?;
-        let mut headers = vec![
-            (
-                "Authorization".to_string(),
-                format!("Bearer {}", self.api_key),
-            ),
-            ("Content-Type".to_string(), "application/json".to_string()),
-        ];
-        if let Some(org) = &self.organization {
-            headers.push(("OpenAI-Organization".to_string(), org.clone()));
-        }
-        if let Some(project) = &self.project {
-            headers.push(("OpenAI-Project".to_string(), project.clone()));
-        }
-        let req = TransportRequest {
-            url: format!("{}/responses", self.base_url),
-            headers,
-            body,
-        };
-
-        // Acquire a rate-limit permit. The default `NoOpRateLimiter`
-        // returns immediately; a shared `InMemoryRateLimiter` paces
-        // and prioritises us. The permit's `observe()` feeds the
-        // response's normalised headers back to the limiter.
-        //
-        // The bucket key includes `account_key()` (base_url + org +
-        // project) so two providers pointing at different
-        // deployments (e.g. `api.openai.com` vs Azure) with the same
-        // model name don't collide on a shared limiter — each
-        // upstream has its own quota.
-        let scope = crate::rate_limit::RateScope {
-            bucket_key: format!("OpenAI|{}|{}", self.account_key(), config.model),
-            tenant: config.tenant.unwrap_or(uuid::Uuid::nil()),
-            priority: config.priority.unwrap_or_default(),
-        };
-        let permit = self.rate_limiter.acquire(&scope).await?;
-        let response = match self.transport.send(req).await {
-            Ok(r) => r,
-            Err(e) => {
-                // Transport-level failure: no headers to feed back,
-                // so report as OtherFailure (no AIMD update) and
-                // surface the error.
-                permit.observe(crate::rate_limit::RateOutcome::OtherFailure);
-                return Err(e);
-            }
-        };
-
-        if !(200..300).contains(&response.status) {
-            let status = response.status;
-            let retry_after = crate::transport::parse_retry_after(response.header("retry-after"));
-            let info = parse_openai_rate_info(&response);
-            // Feed the limiter before draining the body — the body
-            // collect is async and we don't want the limiter's
-            // AIMD step to wait on it.
-            //
-            // A 5xx that carries a `Retry-After` is semantically a
-            // rate-limit-ish signal: the upstream is asking us to
-            // back off for `Retry-After` before retrying, same as a
-            // 429. Report it as `RateLimited` so the AIMD model
-            // halves rps and parks for the hint; otherwise an
-            // `OtherFailure` would still trigger the AIMD halving,
-            // but the limiter wouldn't park for the suggested
-            // duration.
-            let rate_limited = status == 429 || (status >= 500 && retry_after.is_some());
-            if rate_limited {
-                permit.observe(crate::rate_limit::RateOutcome::RateLimited {
-                    retry_after: retry_after.map(std::time::Duration::from_secs),
-                    info,
-                });
-            } else {
-                permit.observe(crate::rate_limit::RateOutcome::OtherFailure);
-            }
-            let body_bytes = response.collect_body().await.unwrap_or_default();
-            let body_str = String::from_utf8_lossy(&body_bytes).into_owned();
-            return Err(parse_openai_error(status, retry_after, &body_str));
-        }
+        let (response, permit) = self.send_responses(&openai_request, config).await?;
 
         // Success path: defer the limiter observation until the
         // stream terminates so an in-stream rate-limit / connection
         // drop is observed correctly. See `rate_limit::observe_stream`.
         let info = parse_openai_rate_info(&response);
 
-        use crate::sse_stream::SseStreamExt;
-        let state = Arc::new(Mutex::new(OpenAIStreamState::new()));
-        let state_for_stream = state.clone();
-        let event_stream = response
-            .body
-            .sse_events("OpenAI")
-            .map(move |sse_result| -> Result<Vec<StreamEvent>, Error> {
-                let sse_event = sse_result?;
-                trace!(event = ?sse_event, "received OpenAI SSE event");
-                let stream_event = serde_json::from_str::<OpenAIStreamEvent>(&sse_event.data)?;
-                // A poisoned lock means `process` panicked on a prior
-                // event; surface it as a stream error instead of
-                // panicking this task too.
-                let mut guard = state_for_stream
-                    .lock()
-                    .map_err(|_| Error::provider("OpenAI", "stream state lock poisoned"))?;
-                guard.process(stream_event)
-            })
-            .flat_map(|result| match result {
-                Ok(events) => {
-                    futures_util::stream::iter(events.into_iter().map(Ok).collect::<Vec<_>>())
-                }
-                Err(e) => futures_util::stream::iter(vec![Err(e)]),
-            });
-
-        // We can't read the continuation off the state until the stream
-        // is fully consumed (response.completed sets it). That's fine for
-        // most callers (they want the stream itself); buffer() picks up
-        // the continuation when finalizing — see Response::with_continuation
-        // for the wiring. We attach an empty continuation here; the
-        // accumulator's finalize() can be extended in a follow-up to
-        // poll the state at end-of-stream if we want this populated on
-        // the streaming-only path too.
-        let _ = state; // keep state alive (the closure also clones it)
+        let event_stream = response_events(response, self.sse_limits);
         let observed = crate::rate_limit::observe_response_stream(event_stream, permit, info);
         Ok(Response::from_stream(observed))
     }
@@ -1551,6 +1955,8 @@ mod tests {
             content: vec![UserPart::Audio(FileSource::Url(
                 "http://x/a.mp3".to_string(),
             ))],
+            name: None,
+            metadata: Default::default(),
         });
         let cfg = Config::builder("gpt-4o-mini").build();
         let err = match provider().generate(&prompt, cfg.raw()).await {
@@ -1580,6 +1986,7 @@ mod tests {
             Error::RateLimit {
                 retry_after,
                 message,
+                ..
             } => {
                 assert_eq!(retry_after, Some(std::time::Duration::from_secs(30)));
                 assert!(message.contains("Rate limited"));
@@ -1598,7 +2005,9 @@ mod tests {
         let body = r#"{"error":{"message":"This model's maximum context length is 128000 tokens.","type":"invalid_request_error","code":"context_length_exceeded"}}"#;
         let err = parse_openai_error(400, None, body);
         match err {
-            Error::ContextWindowExceeded { provider, message } => {
+            Error::ContextWindowExceeded {
+                provider, message, ..
+            } => {
                 assert_eq!(provider, "OpenAI");
                 assert!(message.contains("maximum context length"));
             }
@@ -1627,7 +2036,9 @@ mod tests {
             })
             .expect_err("Error event must produce an Err");
         match err {
-            Error::ContextWindowExceeded { provider, message } => {
+            Error::ContextWindowExceeded {
+                provider, message, ..
+            } => {
                 assert_eq!(provider, "OpenAI");
                 assert!(message.contains("context window"));
             }
@@ -1673,6 +2084,46 @@ mod tests {
         }
     }
 
+    /// The Responses API's own `error` frame is flat
+    /// (`{"type":"error","code":…,"message":…}`), and gateways emit the
+    /// chat-completions `{"error":{…}}` envelope; both must classify
+    /// like the nested shape rather than fail as parse errors.
+    #[test]
+    fn flat_and_enveloped_error_frames_are_classified() {
+        let mut state = OpenAIStreamState::new();
+        let mut classify = |data: &str| {
+            let event = parse_stream_event(data).expect("error frame must parse");
+            state
+                .process(event)
+                .expect_err("error frame must produce Err")
+        };
+        let err = classify(
+            r#"{"type":"error","code":"rate_limit_exceeded","message":"slow down","param":null,"sequence_number":3}"#,
+        );
+        assert!(matches!(err, Error::RateLimit { .. }), "{err:?}");
+        let err = classify(
+            r#"{"error":{"type":"invalid_request_error","code":"content_policy_violation","message":"flagged"}}"#,
+        );
+        assert!(
+            matches!(
+                err,
+                Error::ContentPolicy {
+                    provider: "OpenAI",
+                    ..
+                }
+            ),
+            "{err:?}"
+        );
+        assert!(!err.is_retryable());
+        let err = classify(
+            r#"{"type":"response.failed","response":{"id":"r","status":"failed","output":[],"error":{"code":"server_error","message":"boom"}}}"#,
+        );
+        assert!(err.is_retryable(), "{err:?}");
+
+        assert!(parse_stream_event(r#"{"type":"response.created","bogus":1}"#).is_ok());
+        assert!(parse_stream_event(r#"{"type":"response.output_text.delta"}"#).is_err());
+    }
+
     /// In-stream `Error` events for *non-transient* codes (e.g.
     /// `invalid_request_error` other than context-length) must stay
     /// non-retryable — a malformed request won't fix itself on retry.
@@ -1779,6 +2230,7 @@ mod tests {
             .reasoning(ReasoningConfig {
                 effort: Some(ReasoningEffort::High),
                 summary: Some(ReasoningSummary::Auto),
+                ..Default::default()
             })
             .build();
         let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
@@ -1789,6 +2241,17 @@ mod tests {
         );
     }
 
+    /// OpenAI has no token budget; a budget-only config is bucketed
+    /// into the nearest effort.
+    #[test]
+    fn thinking_budget_without_effort_maps_to_effort() {
+        let prompt = Prompt::user("hi");
+        let cfg = Config::builder("gpt-5").thinking_budget(1024).build();
+        let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["reasoning"], serde_json::json!({"effort": "low"}));
+    }
+
     /// OpenAI's reasoning streaming should open one Reasoning part per
     /// `reasoning_summary_part.added` (not per outer `reasoning` item),
     /// since a single reasoning item often emits multiple summaries.
@@ -1948,6 +2411,8 @@ mod tests {
             ],
             finish_reason: FinishReason::Stop,
             usage: Usage::default(),
+            safety: None,
+            timing: None,
         };
         let prompt = Prompt::user("first turn")
             .with_response(&prior)
@@ -2079,6 +2544,8 @@ mod tests {
                     UserPart::CacheBreakpoint,
                     UserPart::Text("variable suffix".into()),
                 ],
+                name: None,
+                metadata: Default::default(),
             });
             p
         };
@@ -2101,6 +2568,117 @@ mod tests {
         assert!(req.prompt_cache_key.is_none());
     }
 
+    #[test]
+    fn developer_items_use_the_developer_role() {
+        let prompt = Prompt::system("platform rules")
+            .with_developer("app rules")
+            .with_user("hi");
+        let cfg = Config::builder("gpt-5").build();
+        let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["input"][0]["role"], "system");
+        assert_eq!(json["input"][1]["role"], "developer");
+        assert_eq!(json["input"][1]["content"], "app rules");
+    }
+
+    #[test]
+    fn participant_names_ride_on_their_messages() {
+        use crate::types::InputItem;
+        let prompt = Prompt::system("sys")
+            .with_item(InputItem::user("hi, I'm Ada").with_name("ada"))
+            .with_item(InputItem::assistant("hello Ada").with_name("tutor"))
+            .with_user("anonymous");
+        let cfg = Config::builder("gpt-5").build();
+        let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+        let json = serde_json::to_value(&req).unwrap();
+        assert!(json["input"][0].get("name").is_none());
+        assert_eq!(json["input"][1]["name"], "ada");
+        assert_eq!(json["input"][2]["name"], "tutor");
+        assert!(json["input"][3].get("name").is_none());
+    }
+
+    #[test]
+    fn item_metadata_never_reaches_the_wire() {
+        use crate::types::InputItem;
+        let prompt = Prompt::new()
+            .with_item(InputItem::user("hi").with_metadata("ui_message_id", "m-17"))
+            .with_item(InputItem::assistant("hello").with_metadata("source_doc", "d-4"));
+        let cfg = Config::builder("gpt-5").build();
+        let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+        let body = serde_json::to_string(&req).unwrap();
+        assert!(!body.contains("m-17"));
+        assert!(!body.contains("d-4"));
+    }
+
+    #[test]
+    fn tool_result_images_use_the_output_array() {
+        use crate::types::InputItem;
+        let prompt = Prompt::user("show me")
+            .with_item(InputItem::assistant_tool_call(crate::types::FunctionCall {
+                call_id: "c1".into(),
+                name: "screenshot".into(),
+                arguments: "{}".into(),
+                provider_signature: None,
+                original_arguments: None,
+            }))
+            .with_item(InputItem::tool_result_parts(
+                "c1",
+                vec![
+                    crate::types::UserPart::Text("the login page".into()),
+                    crate::types::UserPart::Image(crate::types::FileSource::Base64 {
+                        data: "iVBORw0K".into(),
+                        media_type: "image/png".into(),
+                    }),
+                ],
+            ));
+        let cfg = Config::builder("gpt-5").build();
+        let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+        let json = serde_json::to_value(&req).unwrap();
+        let output = &json["input"][2]["output"];
+        assert_eq!(
+            output[0],
+            serde_json::json!({ "type": "input_text", "text": "the login page" })
+        );
+        assert_eq!(output[1]["type"], "input_image");
+        assert_eq!(output[1]["image_url"], "data:image/png;base64,iVBORw0K");
+    }
+
+    #[test]
+    fn tool_error_output_is_wrapped() {
+        assert_eq!(encode_tool_output("42".into(), false), "42");
+        assert_eq!(
+            encode_tool_output("not found".into(), true),
+            r#"{"error":"not found"}"#
+        );
+        assert_eq!(
+            encode_tool_output(r#"{"code":404}"#.into(), true),
+            r#"{"error":{"code":404}}"#
+        );
+    }
+
+    /// Request metadata maps to `safety_identifier`, and an explicit
+    /// cache key wins over the breakpoint-derived one.
+    #[test]
+    fn metadata_sets_safety_identifier_and_cache_key() {
+        let prompt = Prompt::new().with_item(crate::types::InputItem::User {
+            content: vec![
+                crate::types::UserPart::Text("prefix".into()),
+                crate::types::UserPart::CacheBreakpoint,
+            ],
+            name: None,
+            metadata: Default::default(),
+        });
+        let cfg = Config::builder("gpt-5")
+            .user_id("u-7f3a")
+            .prompt_cache_key("agent-v2")
+            .build();
+        let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["safety_identifier"], "u-7f3a");
+        assert_eq!(json["prompt_cache_key"], "agent-v2");
+        assert!(json.get("user").is_none());
+    }
+
     /// Different prefixes BEFORE the breakpoint must produce different
     /// keys; otherwise OpenAI would group unrelated requests.
     #[test]
@@ -2109,6 +2687,8 @@ mod tests {
         let make_prompt = |prefix: &str| {
             Prompt::system(prefix).with_item(InputItem::User {
                 content: vec![UserPart::Text("ctx".into()), UserPart::CacheBreakpoint],
+                name: None,
+                metadata: Default::default(),
             })
         };
         let cfg = Config::builder("gpt-5").build();
@@ -2139,6 +2719,27 @@ mod tests {
         assert_eq!(openai_request.max_output_tokens, Some(100));
     }
 
+    #[test]
+    fn reasoning_models_omit_sampling_parameters() {
+        let prompt = Prompt::user("Hello");
+        for (model, sampled) in [
+            ("o3-mini", false),
+            ("gpt-5", false),
+            ("gpt-5-chat-latest", true),
+        ] {
+            let cfg = Config::builder(model)
+                .temperature(0.7)
+                .top_p(0.9)
+                .max_tokens(100)
+                .build();
+            let request =
+                provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+            assert_eq!(request.temperature.is_some(), sampled, "{model}");
+            assert_eq!(request.top_p.is_some(), sampled, "{model}");
+            assert_eq!(request.max_output_tokens, Some(100), "{model}");
+        }
+    }
+
     #[test]
     fn cache_key_is_none_without_breakpoint() {
         // The common (no-breakpoint) path short-circuits to None.
@@ -2162,6 +2763,8 @@ mod tests {
                 UserPart::Document(FileSource::Ref("doc1".into())),
                 UserPart::Image(FileSource::Ref("img1".into())),
             ],
+            name: None,
+            metadata: Default::default(),
         });
         let mut resolved = std::collections::HashMap::new();
         resolved.insert(
@@ -2197,6 +2800,8 @@ mod tests {
 
         let prompt = Prompt::new().with_item(InputItem::User {
             content: vec![UserPart::Document(FileSource::Ref("doc1".into()))],
+            name: None,
+            metadata: Default::default(),
         });
         let mut resolved = std::collections::HashMap::new();
         resolved.insert(
@@ -2373,4 +2978,196 @@ mod tests {
         assert_eq!(parse_openai_reset("-1m30s"), None);
         assert_eq!(parse_openai_reset("1m-1e400s"), None);
     }
+
+    #[test]
+    fn container_file_and_file_path_annotations_map_to_file_citations() {
+        let container: OpenAIAnnotation = serde_json::from_str(
+            r#"{"type":"container_file_citation","container_id":"cntr_1","file_id":"cfile_1","filename":"plot.png","start_index":4,"end_index":12}"#,
+        )
+        .unwrap();
+        let a = map_openai_annotation(container).unwrap();
+        assert_eq!(a.kind, AnnotationKind::FileCitation);
+        assert_eq!((a.start, a.end), (4, 12));
+        assert_eq!(a.source, "cfile_1");
+        assert_eq!(a.title.as_deref(), Some("plot.png"));
+
+        let path: OpenAIAnnotation =
+            serde_json::from_str(r#"{"type":"file_path","file_id":"file_2","index":7}"#).unwrap();
+        let a = map_openai_annotation(path).unwrap();
+        assert_eq!(a.kind, AnnotationKind::FileCitation);
+        assert_eq!((a.start, a.end, a.source.as_str()), (7, 7, "file_2"));
+    }
+
+    #[test]
+    fn content_filter_results_surface_as_safety_feedback() {
+        let event: OpenAIStreamEvent = serde_json::from_str(
+            r#"{"type":"response.incomplete","response":{"id":"resp_1","output":[],
+                "incomplete_details":{"reason":"content_filter"},
+                "content_filters":[
+                    {"blocked":false,"source_type":"prompt","content_filter_results":{
+                        "hate":{"filtered":false,"severity":"safe"},
+                        "jailbreak":{"filtered":false,"detected":false}}},
+                    {"blocked":true,"source_type":"completion","content_filter_results":{
+                        "violence":{"filtered":true,"severity":"high"}}}]}}"#,
+        )
+        .unwrap();
+        let events = OpenAIStreamState::new().process(event).unwrap();
+        let [.., StreamEvent::Safety { feedback }, StreamEvent::Done { finish_reason, .. }] =
+            events.as_slice()
+        else {
+            panic!("expected Safety then Done, got {events:?}");
+        };
+        assert_eq!(*finish_reason, crate::types::FinishReason::ContentFilter);
+        assert!(!feedback.prompt_blocked);
+        assert_eq!(feedback.block_reason.as_deref(), Some("content_filter"));
+        let ratings: Vec<_> = feedback
+            .ratings
+            .iter()
+            .map(|r| (r.category.as_str(), r.target, r.level, r.blocked))
+            .collect();
+        assert_eq!(
+            ratings,
+            [
+                (
+                    "hate",
+                    SafetyTarget::Prompt,
+                    Some(SafetyLevel::Negligible),
+                    false
+                ),
+                ("jailbreak", SafetyTarget::Prompt, None, false),
+                (
+                    "violence",
+                    SafetyTarget::Response,
+                    Some(SafetyLevel::High),
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn refusal_without_deltas_is_filled_from_refusal_done() {
+        let mut state = OpenAIStreamState::new();
+        let mut feed = |frame: &str| state.process(serde_json::from_str(frame).unwrap()).unwrap();
+        let opened = feed(
+            r#"{"type":"response.content_part.added","output_index":0,"content_index":0,"part":{"type":"refusal","refusal":""}}"#,
+        );
+        assert!(matches!(
+            opened.as_slice(),
+            [StreamEvent::PartStart {
+                kind: PartKind::Refusal,
+                ..
+            }]
+        ));
+        let done = feed(
+            r#"{"type":"response.refusal.done","output_index":0,"content_index":0,"refusal":"I can't help with that."}"#,
+        );
+        assert!(matches!(
+            done.as_slice(),
+            [StreamEvent::Delta { delta, .. }] if delta == "I can't help with that."
+        ));
+
+        // Streamed refusals aren't repeated.
+        feed(
+            r#"{"type":"response.content_part.added","output_index":1,"content_index":0,"part":{"type":"refusal","refusal":""}}"#,
+        );
+        feed(
+            r#"{"type":"response.refusal.delta","output_index":1,"content_index":0,"delta":"No."}"#,
+        );
+        let done = feed(
+            r#"{"type":"response.refusal.done","output_index":1,"content_index":0,"refusal":"No."}"#,
+        );
+        assert!(done.is_empty());
+    }
+
+    #[test]
+    fn plain_completion_emits_no_safety_event() {
+        let event: OpenAIStreamEvent = serde_json::from_str(
+            r#"{"type":"response.completed","response":{"id":"resp_1","output":[]}}"#,
+        )
+        .unwrap();
+        let events = OpenAIStreamState::new().process(event).unwrap();
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, StreamEvent::Safety { .. })));
+    }
+
+    #[test]
+    fn assistant_prefill_is_followed_by_a_continue_instruction() {
+        let prompt = Prompt::user("Name a colour").with_assistant_prefill("The colour is");
+        let cfg = Config::builder("gpt-5").build();
+        let req = provider().convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new());
+        let json = serde_json::to_value(&req).unwrap();
+        let input = json["input"].as_array().unwrap();
+        assert_eq!(input.len(), 3);
+        assert_eq!(input[1]["role"], "assistant");
+        assert_eq!(input[2]["role"], "developer");
+        assert_eq!(input[2]["content"], PREFILL_INSTRUCTION);
+
+        let history = Prompt::user("hi").with_assistant("hello").with_user("bye");
+        let req =
+            provider().convert_request(&history, cfg.raw(), &std::collections::HashMap::new());
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["input"].as_array().unwrap().len(), 3);
+    }
+
+    /// A [`crate::RetryingProvider`] reuses one generated
+    /// `Idempotency-Key` across attempts; a caller's key is sent as-is,
+    /// alongside the request ID.
+    #[tokio::test]
+    async fn retries_resend_the_same_idempotency_key() {
+        use crate::providers::mock_http::{MockTransport, MockTurn};
+        use crate::retry::{RetryPolicy, RetryingProvider};
+        use crate::transport::Transport;
+
+        fn header<'a>(request: &'a TransportRequest, header: &str) -> Option<&'a str> {
+            request
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(header))
+                .map(|(_, value)| value.as_str())
+        }
+        let key = |request| header(request, "idempotency-key");
+
+        let mock = MockTransport::new([
+            MockTurn::status(500, r#"{"error":{"message":"blip"}}"#),
+            MockTurn::status(400, r#"{"error":{"message":"bad"}}"#),
+            MockTurn::status(400, r#"{"error":{"message":"bad"}}"#),
+        ]);
+        let log = mock.requests();
+        let openai = OpenAIProvider::with_transport(
+            "k".into(),
+            "http://mock/v1".into(),
+            Transport::new(mock),
+        );
+        let provider = RetryingProvider::new(
+            openai,
+            RetryPolicy {
+                initial_backoff: std::time::Duration::ZERO,
+                ..RetryPolicy::standard()
+            },
+        );
+
+        let cfg = Config::builder("gpt-4o-mini").build();
+        assert!(provider
+            .generate(&Prompt::user("hi"), cfg.raw())
+            .await
+            .is_err());
+        let cfg = Config::builder("gpt-4o-mini")
+            .idempotency_key("caller-key")
+            .request_id("job-42")
+            .build();
+        assert!(provider
+            .generate(&Prompt::user("hi"), cfg.raw())
+            .await
+            .is_err());
+
+        let requests = log.all();
+        assert_eq!(requests.len(), 3);
+        let generated = key(&requests[0]).expect("retried call carries a key");
+        assert_eq!(key(&requests[1]), Some(generated));
+        assert_eq!(key(&requests[2]), Some("caller-key"));
+        assert_eq!(header(&requests[1], "x-client-request-id"), None);
+        assert_eq!(header(&requests[2], "x-client-request-id"), Some("job-42"));
+    }
 }
@@ -0,0 +1,505 @@
+//! Microsoft Entra ID (Azure AD) tokens for Azure OpenAI.
+//!
+//! Azure OpenAI's v1 API speaks the same Responses protocol as
+//! `api.openai.com`, so [`crate::providers::OpenAIProvider`] pointed at
+//! `https://{resource}.openai.azure.com/openai/v1` talks to it. What
+//! differs is auth: instead of a static key, production deployments use
+//! Entra tokens. [`EntraCredential`] is a [`TokenSource`] for that —
+//! attach it with [`crate::providers::OpenAIProvider::with_token_source`]
+//! or [`crate::ProviderConfig::with_token_source`].
+//!
+//! Three flows are supported:
+//!
+//! - **Client credentials** — an app registration's client secret
+//!   ([`EntraCredential::client_secret`]).
+//! - **Workload identity federation** — a federated token file
+//!   projected by AKS, re-read on every refresh
+//!   ([`EntraCredential::workload_identity`]).
+//! - **Managed identity** — the VM's or container's identity, from the
+//!   instance metadata service (IMDS)
+//!   ([`EntraCredential::managed_identity`],
+//!   [`EntraCredential::user_assigned_identity`]). IMDS is `GET`-only,
+//!   so the transport must implement
+//!   [`crate::transport::TransportImpl::send_get`].
+//!
+//! [`EntraCredential::from_env`] picks between the first two from the
+//! standard `AZURE_*` variables.
+//!
+//! Tokens are cached and refreshed once, five minutes before expiry, no
+//! matter how many requests are waiting on them.
+
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::auth::TokenSource;
+use crate::providers::file_resolve::percent_encode;
+use crate::transport::{Transport, TransportRequest};
+use crate::Error;
+
+/// Public-cloud Entra authority.
+const DEFAULT_AUTHORITY_HOST: &str = "https://login.microsoftonline.com";
+
+/// Azure instance metadata service token endpoint.
+const IMDS_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+
+/// Refresh this long before a token expires.
+const REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// How the app proves its identity to Entra.
+#[derive(Clone)]
+enum ClientProof {
+    Secret(String),
+    FederatedTokenFile(PathBuf),
+    /// The host's managed identity, vouched for by IMDS.
+    ManagedIdentity,
+}
+
+/// Microsoft Entra ID token source for Azure OpenAI. Fetches tokens
+/// via client credentials ([`Self::client_secret`]), workload
+/// identity federation ([`Self::workload_identity`]) or managed
+/// identity ([`Self::managed_identity`]) and caches them until five
+/// minutes before expiry. Attach with
+/// [`crate::providers::OpenAIProvider::with_token_source`] or
+/// [`crate::ProviderConfig::with_token_source`].
+pub struct EntraCredential {
+    tenant_id: String,
+    client_id: String,
+    proof: ClientProof,
+    scope: String,
+    authority_host: String,
+    transport: Transport,
+    cached: tokio::sync::Mutex<Option<(String, Instant)>>,
+}
+
+impl std::fmt::Debug for EntraCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let proof = match &self.proof {
+            ClientProof::Secret(_) => "client_secret",
+            ClientProof::FederatedTokenFile(_) => "workload_identity",
+            ClientProof::ManagedIdentity => "managed_identity",
+        };
+        f.debug_struct("EntraCredential")
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .field("proof", &proof)
+            .field("scope", &self.scope)
+            .field("authority_host", &self.authority_host)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EntraCredential {
+    /// Scope for Azure OpenAI (and other Azure AI services) tokens.
+    pub const COGNITIVE_SERVICES_SCOPE: &'static str =
+        "https://cognitiveservices.azure.com/.default";
+
+    /// Client-credentials flow with an app registration's secret.
+    pub fn client_secret(
+        tenant_id: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        transport: Transport,
+    ) -> Self {
+        Self::new(
+            tenant_id.into(),
+            client_id.into(),
+            ClientProof::Secret(client_secret.into()),
+            transport,
+        )
+    }
+
+    /// Workload identity federation: the assertion is the token in
+    /// `token_file`, which the platform rotates underneath us.
+    pub fn workload_identity(
+        tenant_id: impl Into<String>,
+        client_id: impl Into<String>,
+        token_file: impl Into<PathBuf>,
+        transport: Transport,
+    ) -> Self {
+        Self::new(
+            tenant_id.into(),
+            client_id.into(),
+            ClientProof::FederatedTokenFile(token_file.into()),
+            transport,
+        )
+    }
+
+    /// The host's system-assigned managed identity, via IMDS. The
+    /// transport must support `GET`
+    /// ([`crate::transport::TransportImpl::send_get`]).
+    pub fn managed_identity(transport: Transport) -> Self {
+        Self::new(
+            String::new(),
+            String::new(),
+            ClientProof::ManagedIdentity,
+            transport,
+        )
+    }
+
+    /// A user-assigned managed identity, picked by its client ID.
+    pub fn user_assigned_identity(client_id: impl Into<String>, transport: Transport) -> Self {
+        Self::new(
+            String::new(),
+            client_id.into(),
+            ClientProof::ManagedIdentity,
+            transport,
+        )
+    }
+
+    /// Build from `AZURE_TENANT_ID` and `AZURE_CLIENT_ID` plus either
+    /// `AZURE_CLIENT_SECRET` or `AZURE_FEDERATED_TOKEN_FILE` (the secret
+    /// wins when both are set). `AZURE_AUTHORITY_HOST` overrides the
+    /// public-cloud authority.
+    pub fn from_env(transport: Transport) -> Result<Self, Error> {
+        fn var(name: &str) -> Option<String> {
+            env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        let required = |name: &str| {
+            var(name).ok_or_else(|| {
+                Error::config(format!(
+                    "{name} environment variable is required for Entra auth"
+                ))
+            })
+        };
+        let tenant_id = required("AZURE_TENANT_ID")?;
+        let client_id = required("AZURE_CLIENT_ID")?;
+        let proof = match (
+            var("AZURE_CLIENT_SECRET"),
+            var("AZURE_FEDERATED_TOKEN_FILE"),
+        ) {
+            (Some(secret), _) => ClientProof::Secret(secret),
+            (None, Some(file)) => ClientProof::FederatedTokenFile(file.into()),
+            (None, None) => {
+                return Err(Error::config(
+                    "Entra auth needs AZURE_CLIENT_SECRET or AZURE_FEDERATED_TOKEN_FILE",
+                ))
+            }
+        };
+        let mut credential = Self::new(tenant_id, client_id, proof, transport);
+        if let Some(host) = var("AZURE_AUTHORITY_HOST") {
+            credential = credential.with_authority_host(host);
+        }
+        Ok(credential)
+    }
+
+    fn new(tenant_id: String, client_id: String, proof: ClientProof, transport: Transport) -> Self {
+        Self {
+            tenant_id,
+            client_id,
+            proof,
+            scope: Self::COGNITIVE_SERVICES_SCOPE.to_string(),
+            authority_host: DEFAULT_AUTHORITY_HOST.to_string(),
+            transport,
+            cached: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Request tokens for `scope` instead of
+    /// [`Self::COGNITIVE_SERVICES_SCOPE`].
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = scope.into();
+        self
+    }
+
+    /// Use another authority, e.g. a sovereign cloud's
+    /// (`https://login.microsoftonline.us`). Managed identity ignores
+    /// it: IMDS is local to the host.
+    pub fn with_authority_host(mut self, host: impl Into<String>) -> Self {
+        self.authority_host = host.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// The `application/x-www-form-urlencoded` token request body.
+    fn form(&self) -> Result<String, Error> {
+        let proof = match &self.proof {
+            ClientProof::Secret(secret) => format!("client_secret={}", percent_encode(secret)),
+            ClientProof::FederatedTokenFile(path) => {
+                // A small projected file; not worth a blocking-pool hop.
+                let assertion = std::fs::read_to_string(path).map_err(|e| {
+                    Error::auth(format!(
+                        "failed to read federated token file {}: {e}",
+                        path.display()
+                    ))
+                })?;
+                format!(
+                    "client_assertion_type={}&client_assertion={}",
+                    percent_encode("urn:ietf:params:oauth:client-assertion-type:jwt-bearer"),
+                    percent_encode(assertion.trim()),
+                )
+            }
+            ClientProof::ManagedIdentity => unreachable!("IMDS takes no form"),
+        };
+        Ok(format!(
+            "grant_type=client_credentials&client_id={}&scope={}&{proof}",
+            percent_encode(&self.client_id),
+            percent_encode(&self.scope),
+        ))
+    }
+
+    /// The IMDS token request. IMDS takes a v1 `resource`, not a v2
+    /// scope, so `/.default` is dropped.
+    fn imds_request(&self) -> TransportRequest {
+        let resource = self.scope.trim_end_matches("/.default");
+        let mut url = format!(
+            "{IMDS_ENDPOINT}?api-version=2018-02-01&resource={}",
+            percent_encode(resource)
+        );
+        if !self.client_id.is_empty() {
+            url.push_str("&client_id=");
+            url.push_str(&percent_encode(&self.client_id));
+        }
+        TransportRequest {
+            url,
+            headers: vec![("Metadata".to_string(), "true".to_string())],
+            body: Vec::new(),
+        }
+    }
+
+    async fn fetch(&self) -> Result<(String, Instant), Error> {
+        #[derive(serde::Deserialize)]
+        struct Issued {
+            access_token: String,
+            #[serde(deserialize_with = "seconds")]
+            expires_in: u64,
+        }
+        #[derive(serde::Deserialize)]
+        struct Refused {
+            #[serde(default)]
+            error: String,
+            #[serde(default)]
+            error_description: String,
+        }
+
+        let requested = Instant::now();
+        let response = match self.proof {
+            ClientProof::ManagedIdentity => self.transport.send_get(self.imds_request()).await?,
+            _ => {
+                self.transport
+                    .send(TransportRequest {
+                        url: format!(
+                            "{}/{}/oauth2/v2.0/token",
+                            self.authority_host, self.tenant_id
+                        ),
+                        headers: vec![(
+                            "Content-Type".to_string(),
+                            "application/x-www-form-urlencoded".to_string(),
+                        )],
+                        body: self.form()?.into_bytes(),
+                    })
+                    .await?
+            }
+        };
+        let status = response.status;
+        let body = response.collect_body().await?;
+        if !(200..300).contains(&status) {
+            let message = match serde_json::from_slice::<Refused>(&body) {
+                // The description carries Entra's AADSTS code and advice.
+                Ok(refused) if !refused.error_description.is_empty() => refused.error_description,
+                Ok(refused) if !refused.error.is_empty() => refused.error,
+                _ => String::from_utf8_lossy(&body).into_owned(),
+            };
+            return Err(Error::auth_with_status(
+                status,
+                format!("Entra token request failed: {message}"),
+            ));
+        }
+        let issued: Issued = serde_json::from_slice(&body)
+            .map_err(|e| Error::auth(format!("invalid Entra token response: {e}")))?;
+        Ok((
+            issued.access_token,
+            requested + Duration::from_secs(issued.expires_in),
+        ))
+    }
+}
+
+/// `expires_in` in seconds: a number from the `/token` endpoint, a
+/// string from IMDS.
+fn seconds<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(u64),
+        Text(String),
+    }
+    match serde::Deserialize::deserialize(deserializer)? {
+        Seconds::Number(seconds) => Ok(seconds),
+        Seconds::Text(text) => text.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[async_trait]
+impl TokenSource for EntraCredential {
+    async fn token(&self) -> Result<String, Error> {
+        // Holding the lock across the fetch makes concurrent callers
+        // wait for one refresh instead of each issuing their own.
+        let mut cached = self.cached.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if Instant::now() + REFRESH_MARGIN < *expires_at {
+                return Ok(token.clone());
+            }
+        }
+        let (token, expires_at) = self.fetch().await?;
+        *cached = Some((token.clone(), expires_at));
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{TransportImpl, TransportResponse};
+    use bytes::Bytes;
+    use std::sync::Arc;
+
+    /// Answers every request with `status` / `body`, recording requests.
+    struct Authority {
+        status: u16,
+        body: &'static str,
+        requests: parking_lot::Mutex<Vec<TransportRequest>>,
+    }
+
+    #[async_trait]
+    impl TransportImpl for Arc<Authority> {
+        async fn send(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+            self.requests.lock().push(req);
+            let body = self.body;
+            Ok(TransportResponse {
+                status: self.status,
+                headers: Vec::new(),
+                body: Box::pin(futures_util::stream::once(async move {
+                    Ok(Bytes::from_static(body.as_bytes()))
+                })),
+            })
+        }
+
+        async fn send_get(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+            self.send(req).await
+        }
+    }
+
+    fn authority(status: u16, body: &'static str) -> Arc<Authority> {
+        Arc::new(Authority {
+            status,
+            body,
+            requests: parking_lot::Mutex::new(Vec::new()),
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn client_secret_token_is_cached_until_near_expiry() {
+        let authority = authority(
+            200,
+            r#"{"token_type":"Bearer","expires_in":3599,"access_token":"eyJ.entra"}"#,
+        );
+        let credential = EntraCredential::client_secret(
+            "tenant-1",
+            "client-1",
+            "s3cr&t",
+            Transport::new(authority.clone()),
+        );
+        assert_eq!(credential.token().await.unwrap(), "eyJ.entra");
+        assert_eq!(credential.token().await.unwrap(), "eyJ.entra");
+        assert_eq!(authority.requests.lock().len(), 1);
+
+        tokio::time::advance(Duration::from_secs(3599) - REFRESH_MARGIN).await;
+        credential.token().await.unwrap();
+        assert_eq!(authority.requests.lock().len(), 2);
+
+        let requests = authority.requests.lock();
+        assert_eq!(
+            requests[0].url,
+            "https://login.microsoftonline.com/tenant-1/oauth2/v2.0/token"
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&requests[0].body),
+            "grant_type=client_credentials&client_id=client-1\
+             &scope=https%3A%2F%2Fcognitiveservices.azure.com%2F.default\
+             &client_secret=s3cr%26t"
+        );
+    }
+
+    #[tokio::test]
+    async fn workload_identity_sends_the_federated_token() {
+        let dir = std::env::temp_dir().join(format!("entra-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("token");
+        std::fs::write(&file, "federated.jwt\n").unwrap();
+
+        let authority = authority(200, r#"{"expires_in":3599,"access_token":"eyJ.wi"}"#);
+        let credential = EntraCredential::workload_identity(
+            "tenant-1",
+            "client-1",
+            &file,
+            Transport::new(authority.clone()),
+        )
+        .with_authority_host("https://login.microsoftonline.us/");
+        assert_eq!(credential.token().await.unwrap(), "eyJ.wi");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let requests = authority.requests.lock();
+        assert_eq!(
+            requests[0].url,
+            "https://login.microsoftonline.us/tenant-1/oauth2/v2.0/token"
+        );
+        let body = String::from_utf8_lossy(&requests[0].body).into_owned();
+        assert!(
+            body.ends_with(
+                "client_assertion_type=urn%3Aietf%3Aparams%3Aoauth%3Aclient-assertion-type%3Ajwt-bearer\
+                 &client_assertion=federated.jwt"
+            ),
+            "got: {body}"
+        );
+    }
+
+    #[tokio::test]
+    async fn managed_identity_asks_imds() {
+        let imds = authority(
+            200,
+            r#"{"access_token":"eyJ.mi","expires_in":"86399","resource":"https://cognitiveservices.azure.com","token_type":"Bearer"}"#,
+        );
+        let credential =
+            EntraCredential::user_assigned_identity("client-1", Transport::new(imds.clone()));
+        assert_eq!(credential.token().await.unwrap(), "eyJ.mi");
+
+        let requests = imds.requests.lock();
+        assert_eq!(
+            requests[0].url,
+            "http://169.254.169.254/metadata/identity/oauth2/token?api-version=2018-02-01\
+             &resource=https%3A%2F%2Fcognitiveservices.azure.com&client_id=client-1"
+        );
+        assert_eq!(
+            requests[0].headers,
+            vec![("Metadata".to_string(), "true".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn refusal_surfaces_the_entra_description() {
+        let authority = authority(
+            401,
+            r#"{"error":"invalid_client","error_description":"AADSTS7000215: Invalid client secret provided."}"#,
+        );
+        let credential = EntraCredential::client_secret(
+            "tenant-1",
+            "client-1",
+            "wrong",
+            Transport::new(authority),
+        );
+        let err = credential.token().await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::Auth {
+                    status: Some(401),
+                    ..
+                }
+            ),
+            "got: {err:?}"
+        );
+        assert!(err.to_string().contains("AADSTS7000215"), "got: {err}");
+    }
+}
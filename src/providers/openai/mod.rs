@@ -1,6 +1,12 @@
 //! OpenAI provider implementation.
 
+mod background;
 mod client;
+#[cfg(not(target_arch = "wasm32"))]
+mod entra;
 mod types;
 
+pub use background::{BackgroundResponse, BackgroundStatus};
 pub use client::OpenAIProvider;
+#[cfg(not(target_arch = "wasm32"))]
+pub use entra::EntraCredential;
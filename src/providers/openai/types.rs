@@ -1,7 +1,6 @@
 use std::borrow::Cow;
 
 use crate::types::Usage;
-use ijson::IValue;
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
 
@@ -19,10 +18,16 @@ pub enum OpenAIInputMessage {
     Regular {
         role: String,
         content: OpenAIMessageContent,
+        /// Participant name (`InputItem::with_name`).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
     },
     /// Function call output message.
     #[serde(rename = "function_call_output")]
-    FunctionCallOutput { call_id: String, output: String },
+    FunctionCallOutput {
+        call_id: String,
+        output: OpenAIToolOutput,
+    },
     /// Function call message (when sending previous function calls back).
     #[serde(rename = "function_call")]
     FunctionCall {
@@ -42,6 +47,15 @@ pub enum OpenAIMessageContent {
     Parts(Vec<OpenAIContentPart>),
 }
 
+/// `function_call_output.output`: a bare string, or a content array when
+/// the tool returned images or files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OpenAIToolOutput {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
 /// Tagged content part within an OpenAI message. Variant names mirror
 /// the wire `type` discriminator (`input_text`, `input_image`, …) so
 /// the Rust-side naming stays in lock-step with the API; the shared
@@ -104,6 +118,9 @@ pub struct ResponsesRequest {
     pub instructions: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
+    /// Output-token cap. On reasoning models this bounds reasoning
+    /// plus visible output together — the Responses API's equivalent
+    /// of Chat Completions' `max_completion_tokens`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_output_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -120,6 +137,10 @@ pub struct ResponsesRequest {
     pub stream: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub store: Option<bool>,
+    /// Run the response asynchronously server-side; see
+    /// [`OpenAIProvider::start_background`](super::OpenAIProvider::start_background).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reasoning: Option<OpenAIReasoning>,
     /// Stop sequences. OpenAI Responses API does not support `stop` on
@@ -142,6 +163,15 @@ pub struct ResponsesRequest {
     /// `text.format` block — JSON mode / JSON schema constraint.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<OpenAITextConfig>,
+    /// `auto` / `default` / `flex` / `priority`, from
+    /// [`crate::OpenAIOptions::service_tier`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<&'static str>,
+    /// Opaque end-user id for abuse attribution, from
+    /// [`crate::RequestMetadata::user_id`]. Replaces the deprecated
+    /// `user` field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safety_identifier: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -209,6 +239,34 @@ pub struct ResponsesResponse {
     /// streaming-level error.
     #[serde(default)]
     pub error: Option<ErrorDetails>,
+    /// Azure OpenAI's content-filter results, one entry per filtered
+    /// source (prompt / completion). Absent on api.openai.com.
+    #[serde(default)]
+    pub content_filters: Vec<ContentFilter>,
+}
+
+/// One entry of Azure's `content_filters` array.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentFilter {
+    #[serde(default)]
+    pub blocked: bool,
+    /// `"prompt"` or `"completion"`.
+    #[serde(default)]
+    pub source_type: String,
+    /// Keyed by category (`hate`, `sexual`, `violence`, `self_harm`,
+    /// `jailbreak`, `protected_material_text`, …).
+    #[serde(default)]
+    pub content_filter_results: std::collections::BTreeMap<String, ContentFilterResult>,
+}
+
+/// Per-category result. Severity categories carry `severity`;
+/// detection categories carry `detected` instead.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContentFilterResult {
+    #[serde(default)]
+    pub filtered: bool,
+    #[serde(default)]
+    pub severity: Option<String>,
 }
 
 /// OpenAI usage wire shape. The `*_tokens_details` sub-objects
@@ -224,6 +282,8 @@ pub struct OpenAIUsage {
     pub input_tokens_details: Option<OpenAIInputTokensDetails>,
     #[serde(default)]
     pub output_tokens_details: Option<OpenAIOutputTokensDetails>,
+    #[serde(default)]
+    pub total_tokens: Option<u32>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -246,6 +306,7 @@ impl From<OpenAIUsage> for Usage {
             cache_read_input_tokens: u.input_tokens_details.and_then(|d| d.cached_tokens),
             cache_creation_input_tokens: None,
             reasoning_tokens: u.output_tokens_details.and_then(|d| d.reasoning_tokens),
+            total_tokens: u.total_tokens,
         }
     }
 }
@@ -267,10 +328,11 @@ pub struct ResponseItem {
     pub call_id: Option<String>,
     /// Builtin-tool call payload. Populated on items like
     /// `web_search_call` (search action with queries) or
-    /// `code_interpreter_call`. Preserved as an opaque JSON value so
-    /// new builtins don't fail the parse.
-    #[serde(default)]
-    pub action: Option<IValue>,
+    /// `code_interpreter_call`. Kept as JSON text, keys in wire order,
+    /// so new builtins don't fail the parse and the payload reaches
+    /// the caller as sent.
+    #[serde(default, deserialize_with = "raw_in_wire_order")]
+    pub action: Option<Box<RawValue>>,
     /// Complete function-call arguments JSON on the terminal
     /// `output_item.done` frame. Used to reconcile when the
     /// incremental `function_call_arguments.delta` stream was empty
@@ -280,6 +342,129 @@ pub struct ResponseItem {
     pub arguments: Option<String>,
 }
 
+/// Read a value as compact JSON text with its object keys in wire
+/// order. [`RawValue`] itself can't be read from inside an
+/// internally-tagged enum like [`OpenAIStreamEvent`] — serde buffers
+/// the frame first — but the buffer keeps map entries in order, so
+/// re-serializing an order-keeping tree gives back the sent text.
+fn raw_in_wire_order<'de, D>(deserializer: D) -> Result<Option<Box<RawValue>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<OrderedJson>::deserialize(deserializer)?
+        .map(|value| serde_json::value::to_raw_value(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// A JSON tree whose objects keep their keys in document order.
+enum OrderedJson {
+    Null,
+    Bool(bool),
+    Number(serde_json::Number),
+    String(String),
+    Array(Vec<OrderedJson>),
+    Object(Vec<(String, OrderedJson)>),
+}
+
+impl<'de> Deserialize<'de> for OrderedJson {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = OrderedJson;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("any JSON value")
+            }
+
+            fn visit_unit<E>(self) -> Result<OrderedJson, E> {
+                Ok(OrderedJson::Null)
+            }
+
+            fn visit_none<E>(self) -> Result<OrderedJson, E> {
+                Ok(OrderedJson::Null)
+            }
+
+            fn visit_some<D: serde::Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<OrderedJson, D::Error> {
+                OrderedJson::deserialize(deserializer)
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<OrderedJson, E> {
+                Ok(OrderedJson::Bool(v))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<OrderedJson, E> {
+                Ok(OrderedJson::Number(v.into()))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<OrderedJson, E> {
+                Ok(OrderedJson::Number(v.into()))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<OrderedJson, E> {
+                serde_json::Number::from_f64(v)
+                    .map(OrderedJson::Number)
+                    .ok_or_else(|| E::custom("non-finite number"))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<OrderedJson, E> {
+                Ok(OrderedJson::String(v.to_owned()))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<OrderedJson, E> {
+                Ok(OrderedJson::String(v))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<OrderedJson, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(OrderedJson::Array(items))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<OrderedJson, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(OrderedJson::Object(entries))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl Serialize for OrderedJson {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        match self {
+            OrderedJson::Null => serializer.serialize_unit(),
+            OrderedJson::Bool(v) => serializer.serialize_bool(*v),
+            OrderedJson::Number(v) => v.serialize(serializer),
+            OrderedJson::String(v) => serializer.serialize_str(v),
+            OrderedJson::Array(items) => serializer.collect_seq(items),
+            OrderedJson::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
 /// Content item in a Responses API output. Currently only the `type`
 /// discriminator is consumed (to pick `PartKind::Text` vs
 /// `PartKind::Refusal` on `response.content_part.added`).
@@ -288,10 +473,13 @@ pub struct ResponseContent {
     pub r#type: String, // "output_text", "refusal", etc.
 }
 
-/// Error details from OpenAI API.
+/// Error details from OpenAI API. `type` is absent from the
+/// `response.error` of a `response.failed` frame, which carries only
+/// `code` and `message`.
 #[derive(Debug, Clone, Deserialize)]
 pub struct ErrorDetails {
     pub message: String,
+    #[serde(default)]
     pub r#type: String,
     #[allow(unused)]
     pub param: Option<String>,
@@ -299,6 +487,43 @@ pub struct ErrorDetails {
     pub code: Option<String>,
 }
 
+/// Error frame shapes that don't decode as [`OpenAIStreamEvent::Error`]:
+/// the Responses API's own flat `{"type":"error","code":…,"message":…}`
+/// and the chat-completions-style `{"error":{…}}` that OpenAI-compatible
+/// gateways emit. Both normalise to [`ErrorDetails`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum StreamErrorFrame {
+    Envelope {
+        error: ErrorDetails,
+    },
+    Flat {
+        message: String,
+        #[serde(default)]
+        code: Option<String>,
+        #[serde(default)]
+        param: Option<String>,
+    },
+}
+
+impl From<StreamErrorFrame> for ErrorDetails {
+    fn from(frame: StreamErrorFrame) -> Self {
+        match frame {
+            StreamErrorFrame::Envelope { error } => error,
+            StreamErrorFrame::Flat {
+                message,
+                code,
+                param,
+            } => ErrorDetails {
+                message,
+                r#type: String::new(),
+                param,
+                code,
+            },
+        }
+    }
+}
+
 /// `response.incomplete_details` payload — the model didn't run to
 /// completion. `reason` is `"max_output_tokens"`, `"content_filter"`, or
 /// (rarely) something else; treat unknown values as `Stop` so the
@@ -330,6 +555,10 @@ pub enum OpenAIStreamEvent {
     /// Heartbeat-style status frame; payload unused (see `ResponseCreated`).
     #[serde(rename = "response.in_progress")]
     ResponseInProgress,
+    /// Background-mode frame ahead of `response.in_progress` while the
+    /// response waits to run; payload unused.
+    #[serde(rename = "response.queued")]
+    ResponseQueued,
 
     /// New output item opening (message / function_call / reasoning /
     /// web_search_call / …).
@@ -373,6 +602,16 @@ pub enum OpenAIStreamEvent {
         content_index: u32,
         delta: String,
     },
+    /// The complete refusal text of a `refusal` content part. Usually
+    /// redundant with the deltas, but some refusals (e.g. to a
+    /// structured-output request) arrive here without any.
+    #[serde(rename = "response.refusal.done")]
+    RefusalDone {
+        output_index: u32,
+        content_index: u32,
+        #[serde(default)]
+        refusal: String,
+    },
 
     /// New reasoning summary part inside a `reasoning` item opening.
     #[serde(rename = "response.reasoning_summary_part.added")]
@@ -438,8 +677,6 @@ pub enum OpenAIStreamEvent {
     ReasoningSummaryTextDone,
     #[serde(rename = "response.reasoning_text.done")]
     ReasoningTextDone,
-    #[serde(rename = "response.refusal.done")]
-    RefusalDone,
     #[serde(rename = "response.function_call_arguments.done")]
     FunctionCallArgumentsDone,
     /// Lifecycle frames for `web_search_call` items — status is folded
@@ -487,6 +724,23 @@ pub enum OpenAIAnnotation {
         #[serde(default)]
         index: usize,
     },
+    /// A file the code interpreter wrote to its container, cited over
+    /// a span.
+    ContainerFileCitation {
+        file_id: String,
+        #[serde(default)]
+        filename: Option<String>,
+        #[serde(default)]
+        start_index: usize,
+        #[serde(default)]
+        end_index: usize,
+    },
+    /// A point reference to a generated file.
+    FilePath {
+        file_id: String,
+        #[serde(default)]
+        index: usize,
+    },
     /// Unknown / future annotation variants — captured generically so
     /// new shapes don't fail the parse.
     #[serde(other)]
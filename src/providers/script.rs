@@ -0,0 +1,277 @@
+//! Scripted, timed [`StreamEvent`] sequences for tests.
+//!
+//! [`super::MockProvider`] describes *what* the assistant says; the
+//! stream it synthesizes arrives instantly and in canonical order.
+//! UI- and accumulator-level code often needs the *how* instead: a
+//! pause before the first token, a tool call opening while text is
+//! still streaming, an error halfway through. [`StreamScript`] spells
+//! out the exact event sequence, with delays, and plays it back as a
+//! [`Response`]:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use platformed_llm::providers::script::StreamScript;
+//! use platformed_llm::FinishReason;
+//!
+//! let response = StreamScript::new()
+//!     .delay(Duration::from_millis(300)) // time to first token
+//!     .pace(Duration::from_millis(20)) // then 20 ms between events
+//!     .start_text(0)
+//!     .delta(0, "Let me ")
+//!     .start_tool_call(1, "call_1", "search")
+//!     .delta(1, r#"{"q": "#)
+//!     .delta(0, "check.")
+//!     .delta(1, r#""rust"}"#)
+//!     .end(0)
+//!     .end(1)
+//!     .done(FinishReason::ToolCalls)
+//!     .into_response();
+//! ```
+//!
+//! Nothing checks that a script is well formed — that is the point:
+//! a script can leave parts open, skip `Done`, or emit events after an
+//! [`StreamScript::error`], to test how the code under test copes.
+//! Delays use `tokio::time`, so under `#[tokio::test(start_paused =
+//! true)]` a script runs instantly yet deterministically, with every
+//! timeout and idle check observing the scripted gaps. To serve a
+//! script from a [`super::MockProvider`], wrap it in
+//! [`super::MockResponse::script`].
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::types::{FinishReason, PartKind, PartUpdate, StreamEvent, Usage};
+use crate::{Error, EventStream, Response};
+
+use super::mock::{split_words, unwrap_shared_error};
+
+#[derive(Debug, Clone)]
+enum Step {
+    Event(StreamEvent),
+    Error(Arc<Error>),
+    Delay(Duration),
+}
+
+/// A builder for an exact, timed event sequence. See the
+/// [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct StreamScript {
+    steps: Vec<Step>,
+    pace: Option<Duration>,
+}
+
+impl StreamScript {
+    /// An empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(mut self, step: Step) -> Self {
+        if let Some(pace) = self.pace {
+            self.steps.push(Step::Delay(pace));
+        }
+        self.steps.push(step);
+        self
+    }
+
+    /// Wait `delay` before the next event.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.steps.push(Step::Delay(delay));
+        self
+    }
+
+    /// Wait `pace` before every event added from here on (on top of
+    /// any [`Self::delay`]). `Duration::ZERO` turns pacing off.
+    pub fn pace(mut self, pace: Duration) -> Self {
+        self.pace = (!pace.is_zero()).then_some(pace);
+        self
+    }
+
+    /// Emit `event` as is.
+    pub fn event(self, event: StreamEvent) -> Self {
+        self.push(Step::Event(event))
+    }
+
+    /// Open part `index` as `kind`.
+    pub fn start(self, index: u32, kind: PartKind) -> Self {
+        self.event(StreamEvent::PartStart { index, kind })
+    }
+
+    /// Open a text part.
+    pub fn start_text(self, index: u32) -> Self {
+        self.start(index, PartKind::Text)
+    }
+
+    /// Open a reasoning part.
+    pub fn start_reasoning(self, index: u32) -> Self {
+        self.start(index, PartKind::Reasoning)
+    }
+
+    /// Open a tool-call part; its arguments follow as deltas.
+    pub fn start_tool_call(
+        self,
+        index: u32,
+        call_id: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.start(
+            index,
+            PartKind::ToolCall {
+                call_id: call_id.into(),
+                name: name.into(),
+            },
+        )
+    }
+
+    /// Append `delta` to part `index`.
+    pub fn delta(self, index: u32, delta: impl Into<String>) -> Self {
+        self.event(StreamEvent::Delta {
+            index,
+            delta: delta.into(),
+        })
+    }
+
+    /// Attach `update` to part `index`.
+    pub fn update(self, index: u32, update: PartUpdate) -> Self {
+        self.event(StreamEvent::PartUpdate { index, update })
+    }
+
+    /// Close part `index`.
+    pub fn end(self, index: u32) -> Self {
+        self.event(StreamEvent::PartEnd { index })
+    }
+
+    /// A whole text part at `index`: opened, streamed one delta per
+    /// word, closed.
+    pub fn text(self, index: u32, content: &str) -> Self {
+        self.start_text(index).words(index, content).end(index)
+    }
+
+    /// A whole reasoning part at `index`, streamed one delta per word.
+    pub fn reasoning(self, index: u32, content: &str) -> Self {
+        self.start_reasoning(index).words(index, content).end(index)
+    }
+
+    /// A whole tool call at `index`, its `arguments` streamed one delta
+    /// per whitespace-separated chunk.
+    pub fn tool_call(
+        self,
+        index: u32,
+        call_id: impl Into<String>,
+        name: impl Into<String>,
+        arguments: &str,
+    ) -> Self {
+        self.start_tool_call(index, call_id, name)
+            .words(index, arguments)
+            .end(index)
+    }
+
+    fn words(self, index: u32, content: &str) -> Self {
+        split_words(content)
+            .into_iter()
+            .fold(self, |script, word| script.delta(index, word))
+    }
+
+    /// Yield `error` as a stream item. Later steps still play; end the
+    /// script here to model a stream that dies mid-turn.
+    pub fn error(self, error: Error) -> Self {
+        self.push(Step::Error(Arc::new(error)))
+    }
+
+    /// End the turn with `finish_reason` and zero usage.
+    pub fn done(self, finish_reason: FinishReason) -> Self {
+        self.done_with_usage(finish_reason, Usage::default())
+    }
+
+    /// End the turn with `finish_reason` and `usage`.
+    pub fn done_with_usage(self, finish_reason: FinishReason, usage: Usage) -> Self {
+        self.event(StreamEvent::Done {
+            finish_reason,
+            usage,
+        })
+    }
+
+    /// Play the script as an event stream.
+    pub fn into_stream(self) -> EventStream {
+        Box::pin(futures_util::stream::unfold(
+            VecDeque::from(self.steps),
+            |mut steps| async move {
+                loop {
+                    match steps.pop_front()? {
+                        Step::Delay(delay) => tokio::time::sleep(delay).await,
+                        Step::Event(event) => return Some((Ok(event), steps)),
+                        Step::Error(error) => {
+                            return Some((Err(unwrap_shared_error(error)), steps))
+                        }
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Play the script as a [`Response`].
+    pub fn into_response(self) -> Response {
+        Response::from_stream(self.into_stream())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AssistantPart;
+    use futures_util::StreamExt;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn plays_interleaved_parts_with_their_delays() {
+        let start = Instant::now();
+        let mut stream = StreamScript::new()
+            .delay(Duration::from_millis(500))
+            .start_text(0)
+            .pace(Duration::from_millis(10))
+            .delta(0, "a")
+            .tool_call(1, "c1", "f", "{}")
+            .delta(0, "b")
+            .end(0)
+            .done(FinishReason::ToolCalls)
+            .into_stream();
+
+        let first = stream.next().await.unwrap().unwrap();
+        assert!(matches!(first, StreamEvent::PartStart { index: 0, .. }));
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        let rest: Vec<_> = stream.collect().await;
+        assert_eq!(rest.len(), 7);
+        assert_eq!(start.elapsed(), Duration::from_millis(570));
+    }
+
+    #[tokio::test]
+    async fn buffers_like_a_provider_stream() {
+        let complete = StreamScript::new()
+            .reasoning(0, "thinking it over")
+            .text(1, "Hello there, world")
+            .done(FinishReason::Stop)
+            .into_response()
+            .buffer()
+            .await
+            .unwrap();
+        assert_eq!(complete.text(), "Hello there, world");
+        assert!(matches!(
+            &complete.content[0],
+            AssistantPart::Reasoning { content, .. } if content == "thinking it over"
+        ));
+    }
+
+    #[tokio::test]
+    async fn mid_stream_errors_surface_in_place() {
+        let events: Vec<_> = StreamScript::new()
+            .start_text(0)
+            .delta(0, "partial")
+            .error(Error::rate_limit(Some(1), "overloaded"))
+            .into_stream()
+            .collect()
+            .await;
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[2], Err(Error::RateLimit { .. })));
+    }
+}
@@ -0,0 +1,492 @@
+//! Record / replay ("VCR") providers for integration tests.
+//!
+//! Run a test once against a live provider wrapped in
+//! [`RecordingProvider`]: every call's request and streamed events are
+//! appended to a *cassette* file. Check the cassette in, and from then
+//! on run the same test against a [`ReplayProvider`] loaded from it —
+//! same events, same order, no network, no keys, no HTTP fixtures.
+//!
+//! ```ignore
+//! use platformed_llm::providers::vcr::{RecordingProvider, ReplayProvider};
+//! use platformed_llm::SharedProvider;
+//!
+//! let cassette = "tests/cassettes/tool_loop.jsonl";
+//! let provider: SharedProvider = if std::env::var("RECORD").is_ok() {
+//!     Arc::new(RecordingProvider::new(OpenAIProvider::new(key)?, cassette)?)
+//! } else {
+//!     Arc::new(ReplayProvider::from_file(cassette)?)
+//! };
+//! ```
+//!
+//! # Cassette format
+//!
+//! JSON Lines, one [`Interaction`] per line: the request fingerprint
+//! (model, prompt, tools, response format — see [`Interaction::request`])
+//! and either the events the stream produced or the error it failed
+//! with. Being line-oriented and plain JSON, cassettes diff cleanly in
+//! review and can be hand-edited to inject edge cases.
+//!
+//! # Matching
+//!
+//! [`ReplayProvider`] answers each call with the first *unused*
+//! interaction whose request fingerprint equals the call's, so a test
+//! that sends the same prompt twice gets the two recorded answers in
+//! order. A call with no matching interaction fails with
+//! [`Error::Config`] naming the model — the usual sign the test's
+//! prompt changed and the cassette needs re-recording.
+//!
+//! The fingerprint is taken *after* middleware, so replay must resolve
+//! the same capabilities the live provider did. [`ReplayProvider`]
+//! reports [`Capabilities::for_model`], which is what every hosted
+//! provider uses; pin something else with
+//! [`ReplayProvider::with_capabilities`].
+//!
+//! Errors are recorded by [`Error::kind`], message, status and
+//! `Retry-After`, and replayed as the closest constructor of the same
+//! kind; transport-level errors come back as retryable
+//! [`Error::Provider`]s.
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Capabilities, Error, Prompt, Provider, RawConfig, Response, SharedProvider, StreamEvent,
+};
+
+/// One recorded call: the request fingerprint and what came back.
+#[non_exhaustive]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Request fingerprint — `model`, `prompt`, and, when set,
+    /// `tools` and `response_format`, as JSON. Sampling parameters are
+    /// deliberately left out so tweaking a temperature doesn't
+    /// invalidate every cassette.
+    pub request: serde_json::Value,
+    /// Events the stream yielded, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<StreamEvent>,
+    /// Error the call failed with — from `generate` when `events` is
+    /// empty, otherwise mid-stream after the last event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RecordedError>,
+}
+
+/// Serializable summary of an [`Error`]. See the module docs.
+#[non_exhaustive]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedError {
+    /// [`Error::kind`] of the original error.
+    pub kind: String,
+    /// Display text of the original error.
+    pub message: String,
+    /// HTTP status, when the original error had one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// `Retry-After` in seconds, when the original error had one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+impl RecordedError {
+    fn from_error(err: &Error) -> Self {
+        Self {
+            kind: err.kind().to_string(),
+            message: err.to_string(),
+            status: err.status(),
+            retry_after_secs: err.retry_after().map(|d| d.as_secs()),
+        }
+    }
+
+    fn to_error(&self) -> Error {
+        let message = self.message.clone();
+        match self.kind.as_str() {
+            "auth" => match self.status {
+                Some(status) => Error::auth_with_status(status, message),
+                None => Error::auth(message),
+            },
+            "config" => Error::config(message),
+            "invalid_prompt" => Error::invalid_prompt(message),
+            "rate_limit" => Error::rate_limit(self.retry_after_secs, message),
+            "model_not_available" => Error::model_not_available(message),
+            "context_window_exceeded" => Error::context_window_exceeded("Replay", message),
+            _ => Error::Provider {
+                provider: "Replay",
+                status: self.status,
+                retryable: self
+                    .status
+                    .is_none_or(|s| s == 429 || (500..=599).contains(&s)),
+                retry_after: self.retry_after_secs.map(Duration::from_secs),
+                message,
+                details: None,
+            },
+        }
+    }
+}
+
+fn fingerprint(prompt: &Prompt, config: &RawConfig) -> serde_json::Value {
+    let mut request = serde_json::json!({
+        "model": config.model,
+        "prompt": prompt,
+    });
+    if let Some(tools) = &config.tools {
+        request["tools"] = serde_json::to_value(tools).unwrap_or_default();
+    }
+    if let Some(format) = &config.response_format {
+        request["response_format"] = serde_json::Value::String(format!("{format:?}"));
+    }
+    request
+}
+
+/// A [`Provider`] wrapper that appends every call to a cassette file.
+/// See the module docs.
+#[derive(Clone)]
+pub struct RecordingProvider {
+    inner: SharedProvider,
+    sink: Arc<Mutex<std::fs::File>>,
+    path: PathBuf,
+}
+
+impl RecordingProvider {
+    /// Record `inner`'s calls to `path`, truncating any existing
+    /// cassette there.
+    pub fn new(inner: impl Provider, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::open(Arc::new(inner), path.as_ref(), false)
+    }
+
+    /// Record `inner`'s calls to `path`, appending to an existing
+    /// cassette.
+    pub fn appending(inner: impl Provider, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::open(Arc::new(inner), path.as_ref(), true)
+    }
+
+    fn open(inner: SharedProvider, path: &Path, append: bool) -> Result<Self, Error> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                Error::config(format!("cannot create cassette dir {}: {e}", dir.display()))
+            })?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .map_err(|e| Error::config(format!("cannot open cassette {}: {e}", path.display())))?;
+        Ok(Self {
+            inner,
+            sink: Arc::new(Mutex::new(file)),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// The cassette file being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl std::fmt::Debug for RecordingProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordingProvider")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+fn write_interaction(sink: &Mutex<std::fs::File>, interaction: &Interaction) {
+    let line = match serde_json::to_string(interaction) {
+        Ok(line) => line,
+        Err(err) => {
+            tracing::warn!(error = %err, "failed to serialize recorded interaction");
+            return;
+        }
+    };
+    let mut file = sink.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(err) = writeln!(file, "{line}") {
+        tracing::warn!(error = %err, "failed to write recorded interaction");
+    }
+}
+
+/// Accumulates one call's events and writes the interaction exactly
+/// once — at the terminal event, or on drop for a stream abandoned
+/// mid-way.
+struct Tape {
+    sink: Arc<Mutex<std::fs::File>>,
+    interaction: Option<Interaction>,
+}
+
+impl Tape {
+    fn push(&mut self, item: &Result<StreamEvent, Error>) {
+        let Some(interaction) = self.interaction.as_mut() else {
+            return;
+        };
+        match item {
+            Ok(event) => {
+                interaction.events.push(event.clone());
+                if matches!(event, StreamEvent::Done { .. }) {
+                    self.flush();
+                }
+            }
+            Err(err) => {
+                interaction.error = Some(RecordedError::from_error(err));
+                self.flush();
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(interaction) = self.interaction.take() {
+            write_interaction(&self.sink, &interaction);
+        }
+    }
+}
+
+impl Drop for Tape {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for RecordingProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let mut interaction = Interaction {
+            request: fingerprint(prompt, config),
+            events: Vec::new(),
+            error: None,
+        };
+        match self.inner.generate(prompt, config).await {
+            Ok(response) => {
+                let mut tape = Tape {
+                    sink: self.sink.clone(),
+                    interaction: Some(interaction),
+                };
+                Ok(response.map_stream(move |stream| stream.inspect(move |item| tape.push(item))))
+            }
+            Err(err) => {
+                interaction.error = Some(RecordedError::from_error(&err));
+                write_interaction(&self.sink, &interaction);
+                Err(err)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// A [`Provider`] that serves calls from a recorded cassette. See the
+/// module docs.
+#[derive(Clone)]
+pub struct ReplayProvider {
+    interactions: Arc<Mutex<VecDeque<Interaction>>>,
+    capabilities: Option<Capabilities>,
+}
+
+impl ReplayProvider {
+    /// Load the cassette at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::config(format!("cannot read cassette {}: {e}", path.display())))?;
+        Self::from_jsonl(&text)
+    }
+
+    /// Load a cassette from its JSON Lines text. Blank lines are
+    /// skipped.
+    pub fn from_jsonl(text: &str) -> Result<Self, Error> {
+        let interactions = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<VecDeque<Interaction>, _>>()?;
+        Ok(Self::from_interactions(interactions))
+    }
+
+    /// Serve an in-memory list of interactions.
+    pub fn from_interactions(interactions: impl IntoIterator<Item = Interaction>) -> Self {
+        Self {
+            interactions: Arc::new(Mutex::new(interactions.into_iter().collect())),
+            capabilities: None,
+        }
+    }
+
+    /// Report `capabilities` for every model instead of
+    /// [`Capabilities::for_model`] — match what the recorded provider
+    /// reported so middleware rewrites the prompt the same way.
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Interactions not yet served. A test can assert this is zero to
+    /// check every recorded call was made.
+    pub fn remaining(&self) -> usize {
+        self.interactions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+}
+
+impl std::fmt::Debug for ReplayProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayProvider")
+            .field("remaining", &self.remaining())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for ReplayProvider {
+    fn name(&self) -> &str {
+        "Replay"
+    }
+
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let request = fingerprint(prompt, config);
+        let interaction = {
+            let mut interactions = self.interactions.lock().unwrap_or_else(|e| e.into_inner());
+            let index = interactions
+                .iter()
+                .position(|i| i.request == request)
+                .ok_or_else(|| {
+                    Error::config(format!(
+                        "no recorded interaction matches this `{}` request; re-record the cassette",
+                        config.model
+                    ))
+                })?;
+            interactions.remove(index).expect("index is in bounds")
+        };
+        if interaction.events.is_empty() {
+            if let Some(err) = &interaction.error {
+                return Err(err.to_error());
+            }
+        }
+        let mut items: Vec<Result<StreamEvent, Error>> =
+            interaction.events.into_iter().map(Ok).collect();
+        if let Some(err) = &interaction.error {
+            items.push(Err(err.to_error()));
+        }
+        Ok(Response::from_stream(futures_util::stream::iter(items)))
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.capabilities
+            .unwrap_or_else(|| Capabilities::for_model(model))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::{generate, Config};
+
+    fn cassette(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "platformed-llm-vcr-{}-{name}.jsonl",
+            std::process::id()
+        ))
+    }
+
+    #[tokio::test]
+    async fn records_then_replays_identically() {
+        let path = cassette("roundtrip");
+        let mock = MockProvider::builder()
+            .reply("first answer")
+            .reply("second answer")
+            .fail(Error::rate_limit(Some(7), "slow down"))
+            .build();
+        let recorder = RecordingProvider::new(mock, &path).unwrap();
+        let config = Config::builder("m").build();
+        let mut live = Vec::new();
+        for _ in 0..2 {
+            live.push(
+                generate(&recorder, &Prompt::user("same question"), &config)
+                    .await
+                    .unwrap()
+                    .text()
+                    .await
+                    .unwrap(),
+            );
+        }
+        assert!(generate(&recorder, &Prompt::user("other"), &config)
+            .await
+            .is_err());
+        drop(recorder);
+
+        let replay = ReplayProvider::from_file(&path).unwrap();
+        assert_eq!(replay.remaining(), 3);
+        for expected in &live {
+            let text = generate(&replay, &Prompt::user("same question"), &config)
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+            assert_eq!(&text, expected);
+        }
+        let Err(err) = generate(&replay, &Prompt::user("other"), &config).await else {
+            panic!("recorded error must replay");
+        };
+        assert!(matches!(err, Error::RateLimit { .. }));
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(7)));
+        assert_eq!(replay.remaining(), 0);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn mid_stream_error_replays_after_events() {
+        let path = cassette("midstream");
+        let mock = MockProvider::always(
+            MockResponse::text("partial")
+                .with_stream_error(Error::provider_with_status("Mock", 502, "dropped")),
+        );
+        let recorder = RecordingProvider::new(mock, &path).unwrap();
+        let config = Config::builder("m").build();
+        let response = generate(&recorder, &Prompt::user("q"), &config)
+            .await
+            .unwrap();
+        assert!(response.text().await.is_err());
+
+        let replay = ReplayProvider::from_file(&path).unwrap();
+        let mut stream = generate(&replay, &Prompt::user("q"), &config)
+            .await
+            .unwrap()
+            .stream();
+        let mut saw_event = false;
+        let err = loop {
+            match stream.next().await {
+                Some(Ok(_)) => saw_event = true,
+                Some(Err(err)) => break err,
+                None => panic!("stream must end with the recorded error"),
+            }
+        };
+        assert!(saw_event);
+        assert_eq!(err.status(), Some(502));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn unmatched_request_is_a_config_error() {
+        let replay = ReplayProvider::from_jsonl("").unwrap();
+        let Err(err) = generate(&replay, &Prompt::user("q"), &Config::builder("m").build()).await
+        else {
+            panic!("empty cassette must not answer");
+        };
+        assert!(matches!(err, Error::Config(_)));
+    }
+}
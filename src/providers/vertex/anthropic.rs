@@ -4,15 +4,17 @@ use std::sync::Arc;
 use futures_util::StreamExt;
 
 use super::anthropic_types::*;
+#[cfg(not(target_arch = "wasm32"))]
+use super::endpoint::AdcOptions;
 use super::endpoint::VertexEndpoint;
 use crate::factory::ProviderType;
 use crate::provider::Provider;
 use crate::providers::file_resolve::{resolve_refs, NoLibraryUpload, ResolvedRef};
-use crate::sse_stream::SseStream;
+use crate::sse_stream::{SseLimits, SseStream};
 use crate::transport::{Transport, TransportRequest};
 use crate::types::{
     AssistantPart, FileResolver, FinishReason, InputItem, PartKind, PartUpdate, ProviderScope,
-    ReasoningEffort, Usage, UserPart,
+    Usage, UserPart,
 };
 use crate::{Error, RawConfig, Response, StreamEvent};
 
@@ -27,6 +29,11 @@ pub struct AnthropicViaVertexProvider {
     file_resolver: Option<Arc<dyn FileResolver>>,
     /// Cooperative rate limiter consulted before every send.
     rate_limiter: crate::rate_limit::SharedRateLimiter,
+    /// Extra headers sent with every request, before any per-request
+    /// [`RawConfig::extra_headers`](crate::RawConfig::extra_headers).
+    extra_headers: Vec<(String, String)>,
+    /// Size caps on streamed response bodies.
+    sse_limits: SseLimits,
 }
 
 impl AnthropicViaVertexProvider {
@@ -38,6 +45,8 @@ impl AnthropicViaVertexProvider {
             beta: Vec::new(),
             file_resolver: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         })
     }
 
@@ -55,20 +64,98 @@ impl AnthropicViaVertexProvider {
             beta: Vec::new(),
             file_resolver: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         })
     }
 
     /// Create a new Anthropic provider with Application Default Credentials.
+    /// Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn with_adc(project_id: String, location: String) -> Result<Self, Error> {
+        Self::with_adc_options(project_id, location, AdcOptions::new()).await
+    }
+
+    /// [`Self::with_adc`] with custom ADC scopes and/or audience. See
+    /// [`AdcOptions`]. Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_adc_options(
+        project_id: String,
+        location: String,
+        options: AdcOptions,
+    ) -> Result<Self, Error> {
         Ok(Self {
-            endpoint: VertexEndpoint::with_adc(project_id, location).await?,
+            endpoint: VertexEndpoint::with_adc_options(project_id, location, options).await?,
             transport: Transport::reqwest()?,
             beta: Vec::new(),
             file_resolver: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         })
     }
 
+    /// Create a new Anthropic provider authenticating with a
+    /// service-account JSON key file. See
+    /// [`VertexEndpoint::with_service_account_key`]. Not available on
+    /// wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_service_account_key(
+        project_id: String,
+        location: String,
+        key_path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, Error> {
+        let endpoint = VertexEndpoint::with_service_account_key(
+            project_id,
+            location,
+            key_path,
+            AdcOptions::new(),
+        )?;
+        Ok(Self::with_transport(endpoint, Transport::reqwest()?))
+    }
+
+    /// Create a new Anthropic provider that impersonates
+    /// `target_principal` (a service-account email) using ambient ADC.
+    /// See [`VertexEndpoint::with_impersonation`]. Not available on
+    /// wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_impersonation(
+        project_id: String,
+        location: String,
+        target_principal: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let transport = Transport::reqwest()?;
+        let endpoint = VertexEndpoint::with_impersonation(
+            project_id,
+            location,
+            target_principal,
+            AdcOptions::new(),
+            transport.clone(),
+        )
+        .await?;
+        Ok(Self::with_transport(endpoint, transport))
+    }
+
+    /// Create a new Anthropic provider whose access tokens come from a
+    /// caller-supplied [`crate::auth::TokenSource`]. See
+    /// [`VertexEndpoint::with_token_source`].
+    pub fn with_token_source(
+        project_id: String,
+        location: String,
+        source: crate::auth::SharedTokenSource,
+    ) -> Result<Self, Error> {
+        Ok(Self::with_transport(
+            VertexEndpoint::with_token_source(project_id, location, source),
+            Transport::reqwest()?,
+        ))
+    }
+
+    /// Fetch the first auth token now rather than on the first request.
+    /// See [`VertexEndpoint::warm_up`].
+    pub async fn warm_up(&self) -> Result<(), Error> {
+        self.endpoint.warm_up().await
+    }
+
     /// Create a new Anthropic provider with a caller-supplied [`Transport`]
     /// and pre-built [`VertexEndpoint`].
     pub fn with_transport(endpoint: VertexEndpoint, transport: Transport) -> Self {
@@ -78,6 +165,8 @@ impl AnthropicViaVertexProvider {
             beta: Vec::new(),
             file_resolver: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         }
     }
 
@@ -96,6 +185,20 @@ impl AnthropicViaVertexProvider {
         self.endpoint.set_access_token(token)
     }
 
+    /// Send `name: value` with every request. Precedence is as for
+    /// [`ProviderConfig::extra_headers`](crate::ProviderConfig::extra_headers).
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Cap line and event sizes on streamed responses. See
+    /// [`SseLimits`].
+    pub fn with_sse_limits(mut self, limits: SseLimits) -> Self {
+        self.sse_limits = limits;
+        self
+    }
+
     /// Opt into Anthropic beta features. Each `beta_id` (e.g.
     /// `"computer-use-2025-01-24"`) appears as a comma-separated value
     /// in the `anthropic-beta` header.
@@ -142,14 +245,23 @@ impl AnthropicViaVertexProvider {
         resolved: &HashMap<String, ResolvedRef>,
     ) -> Result<AnthropicRequest, Error> {
         let mut messages = Vec::new();
-        let mut system_message = None;
+        let mut system_message: Option<String> = None;
 
         for item in prompt.items() {
             match item {
-                InputItem::System(content) => {
-                    system_message = Some(content.clone());
+                // Anthropic has a single top-level `system` string and no
+                // developer role: every instruction item is appended to
+                // it, in order.
+                InputItem::System(content) | InputItem::Developer(content) => {
+                    match &mut system_message {
+                        Some(system) => {
+                            system.push_str("\n\n");
+                            system.push_str(content);
+                        }
+                        None => system_message = Some(content.clone()),
+                    }
                 }
-                InputItem::User { content } => {
+                InputItem::User { content, .. } => {
                     let blocks = build_user_blocks(content, resolved)?;
                     if blocks.is_empty() {
                         continue;
@@ -168,7 +280,7 @@ impl AnthropicViaVertexProvider {
                         content: AnthropicContent::Blocks(blocks),
                     });
                 }
-                InputItem::Assistant { content } => {
+                InputItem::Assistant { content, .. } => {
                     let blocks = build_assistant_blocks(content)?;
                     if blocks.is_empty() {
                         continue;
@@ -190,6 +302,19 @@ impl AnthropicViaVertexProvider {
             }
         }
 
+        let mut messages = merge_adjacent_roles(messages);
+        // A trailing assistant message is a prefill, which Anthropic
+        // rejects if it ends in whitespace.
+        if prompt.assistant_prefill().is_some() {
+            if let Some(AnthropicMessage {
+                content: AnthropicContent::Text(text),
+                ..
+            }) = messages.last_mut()
+            {
+                text.truncate(text.trim_end().len());
+            }
+        }
+
         let tools = config.tools.as_ref().and_then(|tools| {
             use crate::types::{ProviderBuiltin, Tool};
             let converted: Vec<AnthropicTool> = tools
@@ -225,17 +350,14 @@ impl AnthropicViaVertexProvider {
             }
         });
 
-        // Map our unified ReasoningConfig onto Anthropic's `thinking` field.
-        // We derive budget_tokens from `effort` with sensible defaults;
-        // callers needing precise control can construct providers directly.
-        let thinking = config.reasoning.as_ref().map(|cfg| {
-            let budget_tokens = match cfg.effort.unwrap_or(ReasoningEffort::Medium) {
-                ReasoningEffort::Low => 2048,
-                ReasoningEffort::Medium => 8192,
-                ReasoningEffort::High => 16384,
-            };
-            AnthropicThinking::Enabled { budget_tokens }
-        });
+        // Map our unified ReasoningConfig onto Anthropic's `thinking` field:
+        // an explicit `budget_tokens` wins, else it's derived from `effort`.
+        let thinking = config
+            .reasoning
+            .as_ref()
+            .map(|cfg| AnthropicThinking::Enabled {
+                budget_tokens: cfg.thinking_budget(),
+            });
 
         // Anthropic requires temperature == 1 when thinking is enabled.
         // Override with a warning rather than erroring; better DX.
@@ -268,11 +390,21 @@ impl AnthropicViaVertexProvider {
             system: system_message,
             temperature,
             top_p: config.top_p,
+            top_k: config
+                .provider_options
+                .anthropic
+                .as_ref()
+                .and_then(|options| options.top_k),
             tools,
             stream: Some(true), // Enable streaming for SSE responses
             thinking,
             stop_sequences: config.stop.clone(),
             tool_choice,
+            metadata: config
+                .metadata
+                .user_id
+                .clone()
+                .map(|user_id| AnthropicMetadata { user_id }),
         };
 
         if config.presence_penalty.is_some() || config.frequency_penalty.is_some() {
@@ -292,6 +424,49 @@ impl AnthropicViaVertexProvider {
     }
 }
 
+/// Collapse runs of same-role messages into one multi-block message.
+/// Claude requires strict user/assistant alternation and 400s on two
+/// user turns in a row, but a [`crate::Prompt`] may legitimately hold
+/// them — a tool result followed by a user follow-up, a history spliced
+/// from several sources, or a system item (hoisted out) that separated
+/// two turns. A merged user message lists its `tool_result` blocks
+/// first, since Claude also requires them to lead the content that
+/// answers a `tool_use`.
+fn merge_adjacent_roles(messages: Vec<AnthropicMessage>) -> Vec<AnthropicMessage> {
+    fn into_blocks(content: AnthropicContent) -> Vec<AnthropicContentBlock> {
+        match content {
+            AnthropicContent::Text(text) => vec![AnthropicContentBlock::Text {
+                text,
+                cache_control: None,
+            }],
+            AnthropicContent::Blocks(blocks) => blocks,
+        }
+    }
+
+    let mut merged: Vec<AnthropicMessage> = Vec::with_capacity(messages.len());
+    for message in messages {
+        match merged.last_mut() {
+            Some(previous) if previous.role == message.role => {
+                let mut blocks = into_blocks(std::mem::replace(
+                    &mut previous.content,
+                    AnthropicContent::Blocks(Vec::new()),
+                ));
+                blocks.extend(into_blocks(message.content));
+                if previous.role == "user" {
+                    // Stable: tool results keep their relative order, as
+                    // does everything after them.
+                    blocks.sort_by_key(|block| {
+                        !matches!(block, AnthropicContentBlock::ToolResult { .. })
+                    });
+                }
+                previous.content = AnthropicContent::Blocks(blocks);
+            }
+            _ => merged.push(message),
+        }
+    }
+    merged
+}
+
 /// Translate user-side parts into Anthropic content blocks. A
 /// `CacheBreakpoint` attaches `cache_control: {type: "ephemeral"}` to
 /// the most recently emitted block.
@@ -326,12 +501,39 @@ fn build_user_blocks(
                     });
                 }
             }
-            UserPart::ToolResult { call_id, content } => {
-                let text = flatten_user_parts_to_text(content);
+            UserPart::ToolResult {
+                call_id,
+                content,
+                is_error,
+            } => {
+                let has_media = content
+                    .iter()
+                    .any(|part| matches!(part, UserPart::Image(_) | UserPart::Document(_)));
+                let content = if has_media {
+                    AnthropicToolResultContent::Blocks(
+                        build_user_blocks(content, resolved)?
+                            .into_iter()
+                            .filter_map(|block| match block {
+                                AnthropicContentBlock::Text { text, .. } => {
+                                    Some(AnthropicToolResultBlock::Text { text })
+                                }
+                                AnthropicContentBlock::Image { source, .. } => {
+                                    Some(AnthropicToolResultBlock::Image { source })
+                                }
+                                AnthropicContentBlock::Document { source, .. } => {
+                                    Some(AnthropicToolResultBlock::Document { source })
+                                }
+                                _ => None,
+                            })
+                            .collect(),
+                    )
+                } else {
+                    AnthropicToolResultContent::Text(flatten_user_parts_to_text(content))
+                };
                 blocks.push(AnthropicContentBlock::ToolResult {
                     tool_use_id: call_id.clone(),
-                    content: AnthropicToolResultContent::Text(text),
-                    is_error: None,
+                    content,
+                    is_error: is_error.then_some(true),
                 });
             }
             // Audio / video are rejected up front in generate() via
@@ -453,7 +655,7 @@ fn build_assistant_blocks(parts: &[AssistantPart]) -> Result<Vec<AnthropicConten
                 });
             }
             AssistantPart::ToolCall(call) => {
-                let input = serde_json::from_str(&call.arguments).map_err(|e| {
+                let input = ToolArguments::parse(&call.arguments).map_err(|e| {
                     Error::provider("Anthropic", format!("Invalid function arguments: {e}"))
                 })?;
                 blocks.push(AnthropicContentBlock::ToolUse {
@@ -483,6 +685,10 @@ use crate::providers::flatten_user_parts_to_text;
 
 #[async_trait::async_trait]
 impl Provider for AnthropicViaVertexProvider {
+    fn name(&self) -> &str {
+        "Anthropic"
+    }
+
     async fn generate(
         &self,
         prompt: &crate::Prompt,
@@ -491,6 +697,13 @@ impl Provider for AnthropicViaVertexProvider {
         // Claude accepts only image / document inputs — reject audio / video
         // up front rather than dropping them.
         crate::providers::reject_unsupported_modalities(prompt.items(), "Anthropic", false, false)?;
+        // Express mode serves only Google's own models.
+        if self.endpoint.is_express() {
+            return Err(Error::config(
+                "Claude on Vertex needs OAuth credentials; express-mode API keys \
+                 only reach Gemini",
+            ));
+        }
 
         let resolved = resolve_refs(
             prompt.items(),
@@ -510,14 +723,25 @@ impl Provider for AnthropicViaVertexProvider {
             Some("alt=sse"),
         );
 
-        let body = serde_json::to_vec(&anthropic_request)?;
+        let body =
+            crate::providers::encode_request_body(&anthropic_request, config.extra_body.as_ref())?;
         let mut headers = vec![
             self.endpoint.auth_header().await?,
             ("Content-Type".to_string(), "application/json".to_string()),
         ];
-        if !self.beta.is_empty() {
-            headers.push(("anthropic-beta".to_string(), self.beta.join(",")));
+        let mut beta = self.beta.clone();
+        if let Some(options) = &config.provider_options.anthropic {
+            for id in &options.beta {
+                if !beta.contains(id) {
+                    beta.push(id.clone());
+                }
+            }
+        }
+        if !beta.is_empty() {
+            headers.push(("anthropic-beta".to_string(), beta.join(",")));
         }
+        crate::transport::merge_headers(&mut headers, &self.extra_headers);
+        crate::transport::merge_headers(&mut headers, &config.extra_headers);
         let req = TransportRequest { url, headers, body };
 
         let scope = crate::rate_limit::RateScope {
@@ -549,7 +773,17 @@ impl Provider for AnthropicViaVertexProvider {
         if !(200..300).contains(&response.status) {
             let status = response.status;
             // Read Retry-After before `collect_body` consumes the response.
-            let retry_after = crate::transport::parse_retry_after(response.header("retry-after"));
+            // A 429 without one still carries
+            // `anthropic-ratelimit-requests-reset`; use it as the hint so
+            // callers wait for the window rather than guessing.
+            let info = parse_anthropic_rate_info(&response.headers);
+            let retry_after = crate::transport::parse_retry_after(response.header("retry-after"))
+                .or_else(|| {
+                    (status == 429)
+                        .then_some(info.requests_reset)
+                        .flatten()
+                        .map(crate::transport::retry_after_from_reset)
+                });
             // A 5xx with `Retry-After` is semantically a
             // rate-limit-ish signal (Anthropic-via-Vertex returns 529
             // overloaded with a hint), so report it as `RateLimited`
@@ -561,42 +795,45 @@ impl Provider for AnthropicViaVertexProvider {
             if rate_limited {
                 permit.observe(crate::rate_limit::RateOutcome::RateLimited {
                     retry_after: retry_after.map(std::time::Duration::from_secs),
-                    info: crate::rate_limit::ProviderRateInfo::default(),
+                    info,
                 });
             } else {
                 permit.observe(crate::rate_limit::RateOutcome::OtherFailure);
             }
+            let request_id = response.header("request-id").map(str::to_owned);
             let body_bytes = response.collect_body().await.unwrap_or_default();
-            let body_text = String::from_utf8_lossy(&body_bytes);
+            let body_text = crate::logging::scrub(&String::from_utf8_lossy(&body_bytes));
             // Anthropic doesn't expose a typed code for "too many input
             // tokens" — detect via message-string match on 400s. The
             // canonical phrasing as of 2026 is "prompt is too long" but
             // the upstream may rephrase; this is best-effort.
-            if status == 400 && is_anthropic_context_exceeded(&body_text) {
-                return Err(Error::context_window_exceeded(
-                    "Anthropic",
-                    body_text.to_string(),
-                ));
-            }
-            return Err(match status {
-                401 | 403 => {
-                    Error::auth_with_status(status, format!("Anthropic {status}: {body_text}"))
+            let message = crate::providers::error_envelope_message(&body_text);
+            let error = if status == 400 && is_anthropic_context_exceeded(&body_text) {
+                Error::context_window_exceeded("Anthropic", message)
+            } else {
+                match status {
+                    401 | 403 => {
+                        Error::auth_with_status(status, format!("Anthropic {status}: {message}"))
+                    }
+                    404 => Error::model_not_available(format!("Anthropic 404: {message}")),
+                    429 => Error::rate_limit(
+                        retry_after,
+                        format!("Anthropic 429 (rate limited): {message}"),
+                    ),
+                    // 5xx (and any other non-special status) may carry
+                    // a `Retry-After` per RFC 7231; thread it through so
+                    // the retry helper honours the server hint.
+                    _ => Error::provider_with_retry_after(
+                        "Anthropic",
+                        status,
+                        retry_after,
+                        format!("API error: {message}"),
+                    ),
                 }
-                404 => Error::ModelNotAvailable(format!("Anthropic 404: {body_text}")),
-                429 => Error::rate_limit(
-                    retry_after,
-                    format!("Anthropic 429 (rate limited): {body_text}"),
-                ),
-                // 5xx (and any other non-special status) may carry
-                // a `Retry-After` per RFC 7231; thread it through so
-                // the retry helper honours the server hint.
-                _ => Error::provider_with_retry_after(
-                    "Anthropic",
-                    status,
-                    retry_after,
-                    format!("API error: {body_text}"),
-                ),
-            });
+            };
+            return Err(error
+                .with_provider_details(anthropic_error_details(&body_text))
+                .with_request_id(request_id.as_deref()));
         }
 
         // Success path: defer the limiter observation until the
@@ -611,7 +848,7 @@ impl Provider for AnthropicViaVertexProvider {
         let response_headers = response.headers.clone();
 
         // Create SSE stream from response
-        let sse_stream = SseStream::new("Anthropic", response.body);
+        let sse_stream = SseStream::new("Anthropic", response.body).with_limits(self.sse_limits);
 
         // Create a stateful processor for function call tracking
         let mut state = StreamState::default();
@@ -660,6 +897,25 @@ impl Provider for AnthropicViaVertexProvider {
     }
 }
 
+/// Structured details from an Anthropic error envelope
+/// (`{"type":"error","error":{"type":"invalid_request_error",...}}`).
+/// Vertex sometimes wraps Anthropic failures in its own
+/// `{"error":{"status":...}}` shape instead; fall back to that.
+fn anthropic_error_details(body: &str) -> crate::error::ProviderErrorDetails {
+    let details = crate::error::ProviderErrorDetails::new().with_raw_body(body);
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return details;
+    };
+    let error_type = value
+        .pointer("/error/type")
+        .or_else(|| value.pointer("/error/status"))
+        .and_then(|v| v.as_str());
+    match error_type {
+        Some(t) => details.with_error_type(t),
+        None => details,
+    }
+}
+
 /// Anthropic exposes its rate-limit state via the
 /// `anthropic-ratelimit-requests-*` family on every successful
 /// response. We surface remaining and reset so the limiter's AIMD
@@ -778,21 +1034,16 @@ fn is_anthropic_context_exceeded(body: &str) -> bool {
 }
 
 /// Map an Anthropic `stop_reason` string onto our unified [`FinishReason`].
-///
-/// Until [`FinishReason`] is extended (Phase 5), `stop_sequence` and
-/// `pause_turn` collapse to `Stop` — the closest existing variant.
+/// Reasons without a unified variant (`pause_turn`, anything new) pass
+/// through as [`FinishReason::Other`].
 pub(crate) fn map_anthropic_stop_reason(reason: Option<&str>) -> FinishReason {
     match reason {
         Some("end_turn") => FinishReason::Stop,
         Some("tool_use") => FinishReason::ToolCalls,
         Some("max_tokens") => FinishReason::Length,
-        Some("stop_sequence") => FinishReason::Stop,
-        Some("pause_turn") => FinishReason::Stop,
-        Some("refusal") => FinishReason::ContentFilter,
-        Some(other) => {
-            tracing::warn!(stop_reason = other, "unknown Anthropic stop_reason");
-            FinishReason::Stop
-        }
+        Some("stop_sequence") => FinishReason::StopSequence,
+        Some("refusal") => FinishReason::Safety,
+        Some(other) => FinishReason::Other(other.to_string()),
         None => FinishReason::Stop,
     }
 }
@@ -868,10 +1119,7 @@ pub(crate) fn convert_stream_event_stateful(
                 events.push(ev);
                 // Per the streaming protocol the initial `input` is `{}`.
                 // Arguments arrive via input_json_delta.
-                let nonempty = !(input.is_null()
-                    || (input.is_object()
-                        && input.as_object().map(|o| o.is_empty()).unwrap_or(true)));
-                if nonempty {
+                if !input.is_empty() {
                     tracing::warn!(
                         ?input,
                         "Anthropic content_block_start carried non-empty `input`; \
@@ -985,21 +1233,29 @@ pub(crate) fn convert_stream_event_stateful(
             // permit across stream consumption picks up the
             // `Err(Error::RateLimit { … })` here and feeds it back
             // as `RateOutcome::RateLimited`, so the AIMD model does
-            // learn from this mid-stream event.) Other mid-stream
-            // errors stay as `Error::Provider`.
-            if error.error_type == "rate_limit_error" || error.error_type == "overloaded_error" {
-                return Err(Error::rate_limit(
-                    None,
-                    format!(
-                        "Anthropic mid-stream {}: {}",
-                        error.error_type, error.message
-                    ),
-                ));
-            }
-            return Err(Error::provider(
-                "Anthropic",
-                format!("{}: {}", error.error_type, error.message),
-            ));
+            // learn from this mid-stream event.) An `api_error` is the
+            // streaming counterpart of a pre-stream 5xx and retries
+            // the same way; other mid-stream errors stay as
+            // non-retryable `Error::Provider`.
+            let message = format!("{}: {}", error.error_type, error.message);
+            return Err(match error.error_type.as_str() {
+                "rate_limit_error" | "overloaded_error" => {
+                    Error::rate_limit(None, format!("Anthropic mid-stream {message}"))
+                }
+                _ if is_anthropic_context_exceeded(&message) => {
+                    Error::context_window_exceeded("Anthropic", message)
+                }
+                error_type => Error::Provider {
+                    provider: "Anthropic",
+                    status: None,
+                    retryable: error_type == "api_error",
+                    retry_after: None,
+                    message,
+                    details: Some(Box::new(
+                        crate::error::ProviderErrorDetails::new().with_error_type(error_type),
+                    )),
+                },
+            });
         }
     }
 
@@ -1057,6 +1313,27 @@ mod tests {
             matches!(err, Error::Provider { .. }),
             "non-rate-limit error should stay generic Provider, got {err:?}",
         );
+        assert!(err.is_retryable(), "api_error mirrors a pre-stream 5xx");
+    }
+
+    #[test]
+    fn mid_stream_prompt_too_long_is_typed() {
+        use crate::providers::vertex::anthropic_types::AnthropicErrorPayload;
+        let err = convert_stream_event_stateful(
+            AnthropicStreamEvent::Error {
+                error: AnthropicErrorPayload {
+                    error_type: "invalid_request_error".to_string(),
+                    message: "prompt is too long: 250000 tokens > 200000 maximum".to_string(),
+                },
+            },
+            &mut StreamState::default(),
+        )
+        .expect_err("error event must produce Err");
+        assert!(
+            matches!(err, Error::ContextWindowExceeded { .. }),
+            "{err:?}"
+        );
+        assert!(!err.is_retryable());
     }
 
     #[test]
@@ -1181,13 +1458,29 @@ mod tests {
             map_anthropic_stop_reason(Some("max_tokens")),
             FinishReason::Length
         );
+        assert_eq!(
+            map_anthropic_stop_reason(Some("stop_sequence")),
+            FinishReason::StopSequence
+        );
         assert_eq!(
             map_anthropic_stop_reason(Some("refusal")),
-            FinishReason::ContentFilter
+            FinishReason::Safety
         );
         assert_eq!(map_anthropic_stop_reason(None), FinishReason::Stop);
     }
 
+    #[test]
+    fn map_anthropic_stop_reason_keeps_unknown_values() {
+        assert_eq!(
+            map_anthropic_stop_reason(Some("pause_turn")),
+            FinishReason::Other("pause_turn".into())
+        );
+        assert_eq!(
+            map_anthropic_stop_reason(Some("model_context_window_exceeded")).as_str(),
+            "model_context_window_exceeded"
+        );
+    }
+
     #[test]
     fn convert_simple_text_request() {
         let prompt = Prompt::user("hi");
@@ -1199,6 +1492,132 @@ mod tests {
         assert_eq!(body.messages[0].role, "user");
     }
 
+    #[test]
+    fn system_and_developer_items_join_the_system_prompt() {
+        let prompt = Prompt::system("platform rules")
+            .with_developer("app rules")
+            .with_user("hi");
+        let cfg = Config::builder("claude").build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        assert_eq!(body.system.as_deref(), Some("platform rules\n\napp rules"));
+        assert_eq!(body.messages.len(), 1);
+    }
+
+    #[test]
+    fn tool_result_images_become_content_blocks() {
+        use crate::types::InputItem;
+        let prompt = Prompt::user("show me")
+            .with_item(InputItem::assistant_tool_call(crate::types::FunctionCall {
+                call_id: "c1".into(),
+                name: "screenshot".into(),
+                arguments: "{}".into(),
+                provider_signature: None,
+                original_arguments: None,
+            }))
+            .with_item(InputItem::tool_result_parts(
+                "c1",
+                vec![
+                    crate::types::UserPart::Text("the login page".into()),
+                    crate::types::UserPart::Image(crate::types::FileSource::Base64 {
+                        data: "iVBORw0K".into(),
+                        media_type: "image/png".into(),
+                    }),
+                ],
+            ));
+        let cfg = Config::builder("claude").build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&body.messages[2].content).unwrap();
+        assert_eq!(
+            json[0]["content"][0],
+            serde_json::json!({ "type": "text", "text": "the login page" })
+        );
+        assert_eq!(json[0]["content"][1]["type"], "image");
+        assert_eq!(json[0]["content"][1]["source"]["media_type"], "image/png");
+    }
+
+    #[test]
+    fn tool_error_sets_is_error() {
+        use crate::types::InputItem;
+        let prompt = Prompt::user("look it up")
+            .with_item(InputItem::assistant_tool_call(crate::types::FunctionCall {
+                call_id: "c1".into(),
+                name: "lookup".into(),
+                arguments: "{}".into(),
+                provider_signature: None,
+                original_arguments: None,
+            }))
+            .with_item(InputItem::tool_error("c1", "upstream timed out"));
+        let cfg = Config::builder("claude").build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&body.messages[2].content).unwrap();
+        assert_eq!(json[0]["type"], "tool_result");
+        assert_eq!(json[0]["is_error"], true);
+        assert_eq!(json[0]["content"], "upstream timed out");
+    }
+
+    /// Two user turns in a row (a tool result, then a follow-up) become
+    /// one user message with the tool result first, so Claude sees
+    /// strict alternation.
+    #[test]
+    fn adjacent_same_role_messages_are_merged() {
+        use crate::types::{FunctionCall, InputItem};
+        let prompt = Prompt::user("what's the weather?")
+            .with_item(InputItem::assistant_tool_call(FunctionCall {
+                call_id: "c1".into(),
+                name: "weather".into(),
+                arguments: "{}".into(),
+                provider_signature: None,
+                original_arguments: None,
+            }))
+            .with_item(InputItem::user("also, be brief"))
+            .with_item(InputItem::tool_result("c1", "sunny"));
+        let cfg = Config::builder("claude").build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let roles: Vec<&str> = body.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "user"]);
+        let json = serde_json::to_value(&body.messages[2].content).unwrap();
+        assert_eq!(json[0]["type"], "tool_result");
+        assert_eq!(
+            json[1],
+            serde_json::json!({ "type": "text", "text": "also, be brief" })
+        );
+    }
+
+    #[test]
+    fn user_id_is_sent_as_metadata() {
+        let cfg = Config::builder("claude").user_id("u-7f3a").build();
+        let body = provider()
+            .convert_request(
+                &Prompt::user("hi"),
+                cfg.raw(),
+                &std::collections::HashMap::new(),
+            )
+            .unwrap();
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["metadata"], serde_json::json!({ "user_id": "u-7f3a" }));
+
+        let anonymous = Config::builder("claude").build();
+        let body = provider()
+            .convert_request(
+                &Prompt::user("hi"),
+                anonymous.raw(),
+                &std::collections::HashMap::new(),
+            )
+            .unwrap();
+        assert!(serde_json::to_value(&body)
+            .unwrap()
+            .get("metadata")
+            .is_none());
+    }
+
     /// A resolved document `Ref` (handle) lands as a `{type:"file", file_id}`
     /// source; a URL result as `{type:"url", url}`.
     #[test]
@@ -1209,6 +1628,8 @@ mod tests {
         // Handle -> file source.
         let prompt = Prompt::new().with_item(InputItem::User {
             content: vec![UserPart::Document(FileSource::Ref("doc1".into()))],
+            name: None,
+            metadata: Default::default(),
         });
         let mut resolved = std::collections::HashMap::new();
         resolved.insert(
@@ -1284,7 +1705,7 @@ mod tests {
             content_block: AnthropicContentBlock::ToolUse {
                 id: "toolu_xyz".to_string(),
                 name: "get_weather".to_string(),
-                input: ijson::ijson!({}),
+                input: ToolArguments::parse("{}").unwrap(),
                 cache_control: None,
             },
         };
@@ -1300,4 +1721,16 @@ mod tests {
             other => panic!("expected PartStart(ToolCall), got {other:?}"),
         }
     }
+
+    #[test]
+    fn assistant_prefill_is_sent_last_without_trailing_whitespace() {
+        let cfg = Config::builder("claude").build();
+        let prompt = Prompt::user("Answer in JSON").with_assistant_prefill("{\n  ");
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["messages"][1]["role"], "assistant");
+        assert_eq!(json["messages"][1]["content"], "{");
+    }
 }
@@ -5,6 +5,8 @@ use ijson::IValue;
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
 
+pub(crate) use super::tool_args::ToolArguments;
+
 /// Anthropic Claude request format via Vertex AI.
 #[derive(Debug, Clone, Serialize)]
 pub struct AnthropicRequest {
@@ -18,6 +20,8 @@ pub struct AnthropicRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<AnthropicTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
@@ -31,6 +35,16 @@ pub struct AnthropicRequest {
     /// `name`), or `none`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<AnthropicToolChoice>,
+    /// End-user attribution, from [`crate::RequestMetadata`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<AnthropicMetadata>,
+}
+
+/// Anthropic request `metadata` block.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnthropicMetadata {
+    /// Opaque end-user id used for abuse attribution.
+    pub user_id: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -78,7 +92,7 @@ pub enum AnthropicContentBlock {
     ToolUse {
         id: String,
         name: String,
-        input: IValue,
+        input: ToolArguments,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         cache_control: Option<AnthropicCacheControl>,
     },
@@ -138,6 +152,7 @@ pub enum AnthropicToolResultContent {
 pub enum AnthropicToolResultBlock {
     Text { text: String },
     Image { source: IValue },
+    Document { source: IValue },
 }
 
 /// Anthropic tool entry. Function tools serialize without a `type`
@@ -295,6 +310,7 @@ impl From<AnthropicUsage> for Usage {
             cache_read_input_tokens: usage.cache_read_input_tokens,
             cache_creation_input_tokens: usage.cache_creation_input_tokens,
             reasoning_tokens: None,
+            total_tokens: None,
         }
     }
 }
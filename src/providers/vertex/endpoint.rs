@@ -7,26 +7,242 @@
 //! [`crate::transport::Transport`] each provider holds independently.
 //!
 //! The endpoint supports both static access tokens and Application Default
-//! Credentials (via `gcp_auth`). Tests can override the host with
-//! [`VertexEndpoint::with_base_url`].
+//! Credentials (via `gcp_auth`). ADC tokens are cached on the endpoint and
+//! shared by every clone; a refresh happens once, just before expiry, no
+//! matter how many requests are waiting on it. [`VertexEndpoint::warm_up`]
+//! fetches the first token ahead of the first request, and [`AdcOptions`]
+//! narrows the OAuth scopes for restricted credentials. Besides ambient
+//! ADC, tokens can come from an explicit service-account key file
+//! ([`VertexEndpoint::with_service_account_key`]) or from impersonating
+//! a service account ([`VertexEndpoint::with_impersonation`]). Express
+//! mode ([`VertexEndpoint::with_api_key`]) skips OAuth entirely: an API
+//! key rides in a header and URLs drop the project and location. A
+//! caller-supplied [`crate::auth::TokenSource`]
+//! ([`VertexEndpoint::with_token_source`])
+//! replaces all of these. Tests can override the host with
+//! [`VertexEndpoint::with_base_url`]. ADC is unavailable on wasm32 —
+//! there is no filesystem or metadata server to discover credentials
+//! from — so browser builds pass a token minted by their backend.
 //!
 //! Renamed from `VertexTransport` once the actual HTTP transport became a
 //! lib-wide concept; calling this a "transport" was misleading because it
 //! never carried bytes.
 
 use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 
+#[cfg(not(target_arch = "wasm32"))]
 use gcp_auth::TokenProvider;
 
+use crate::auth::SharedTokenSource;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::{Transport, TransportRequest};
 use crate::Error;
 
-/// OAuth scope used for all Vertex AI calls.
-const VERTEX_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+/// Default OAuth scope for Vertex AI calls.
+pub const VERTEX_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// How [`VertexEndpoint::with_adc_options`] requests ADC tokens.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdcOptions {
+    scopes: Vec<String>,
+    audience: Option<String>,
+}
+
+impl AdcOptions {
+    /// Default options: the [`VERTEX_SCOPE`] scope, no audience.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request `scopes` instead of [`VERTEX_SCOPE`] — for credentials
+    /// restricted to a narrower grant. Accumulates across calls.
+    pub fn with_scopes(mut self, scopes: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.scopes.extend(scopes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sign the token assertion for `audience` instead of Google's token
+    /// endpoint. Only meaningful for service-account key credentials
+    /// (`GOOGLE_APPLICATION_CREDENTIALS`); building an endpoint with an
+    /// audience fails for any other ADC source.
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// The scopes tokens are requested for.
+    pub fn scopes(&self) -> Vec<&str> {
+        if self.scopes.is_empty() {
+            vec![VERTEX_SCOPE]
+        } else {
+            self.scopes.iter().map(String::as_str).collect()
+        }
+    }
+
+    /// The configured assertion audience, if any.
+    pub fn audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+}
+
+/// ADC token source with an endpoint-level cache. `gcp_auth` caches
+/// too, but several of its sources re-fetch once per concurrent caller
+/// when the token expires; the `refresh` lock makes that a single
+/// fetch.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct AdcTokens {
+    provider: Arc<dyn TokenProvider>,
+    scopes: Vec<String>,
+    cached: parking_lot::RwLock<Option<Arc<gcp_auth::Token>>>,
+    refresh: tokio::sync::Mutex<()>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl AdcTokens {
+    fn new(provider: Arc<dyn TokenProvider>, options: &AdcOptions) -> Self {
+        Self {
+            provider,
+            scopes: options.scopes().into_iter().map(str::to_owned).collect(),
+            cached: parking_lot::RwLock::new(None),
+            refresh: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// The cached token unless it is within `gcp_auth`'s expiry margin.
+    fn fresh(&self) -> Option<Arc<gcp_auth::Token>> {
+        self.cached
+            .read()
+            .as_ref()
+            .filter(|token| !token.has_expired())
+            .cloned()
+    }
+
+    async fn token(&self) -> Result<Arc<gcp_auth::Token>, Error> {
+        if let Some(token) = self.fresh() {
+            return Ok(token);
+        }
+        let _refresh = self.refresh.lock().await;
+        // Another caller may have refreshed while we waited.
+        if let Some(token) = self.fresh() {
+            return Ok(token);
+        }
+        let scopes: Vec<&str> = self.scopes.iter().map(String::as_str).collect();
+        let token = self
+            .provider
+            .token(&scopes)
+            .await
+            .map_err(|e| Error::auth(format!("ADC token fetch failed: {e}")))?;
+        *self.cached.write() = Some(token.clone());
+        Ok(token)
+    }
+}
+
+/// IAM Credentials API root, which mints impersonated tokens.
+#[cfg(not(target_arch = "wasm32"))]
+const IAM_CREDENTIALS_URL: &str = "https://iamcredentials.googleapis.com/v1";
+
+/// Lifetime requested for impersonated tokens — the API's maximum
+/// without an org-policy extension.
+#[cfg(not(target_arch = "wasm32"))]
+const IMPERSONATED_LIFETIME_SECS: u64 = 3600;
+
+/// Service-account impersonation: a `cloud-platform` token from
+/// `source` is exchanged at the IAM Credentials API for one issued to
+/// `target_principal`. The source identity needs the Service Account
+/// Token Creator role on the target. Caching happens in the
+/// [`AdcTokens`] wrapped around this.
+#[cfg(not(target_arch = "wasm32"))]
+struct ImpersonatedTokens {
+    source: Arc<dyn TokenProvider>,
+    target_principal: String,
+    transport: Transport,
+    base_url: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+impl TokenProvider for ImpersonatedTokens {
+    async fn token(&self, scopes: &[&str]) -> Result<Arc<gcp_auth::Token>, gcp_auth::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Generated {
+            access_token: String,
+        }
+
+        let source = self.source.token(&[VERTEX_SCOPE]).await?;
+        let body = serde_json::json!({
+            "scope": scopes,
+            "lifetime": format!("{IMPERSONATED_LIFETIME_SECS}s"),
+        });
+        let response = self
+            .transport
+            .send(TransportRequest {
+                url: format!(
+                    "{}/projects/-/serviceAccounts/{}:generateAccessToken",
+                    self.base_url, self.target_principal
+                ),
+                headers: vec![
+                    (
+                        "Authorization".to_string(),
+                        format!("Bearer {}", source.as_str()),
+                    ),
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                ],
+                body: body.to_string().into_bytes(),
+            })
+            .await
+            .map_err(|e| gcp_auth::Error::Other("impersonation request failed", Box::new(e)))?;
+        let status = response.status;
+        let body = response
+            .collect_body()
+            .await
+            .map_err(|e| gcp_auth::Error::Other("impersonation request failed", Box::new(e)))?;
+        if !(200..300).contains(&status) {
+            let detail = format!("HTTP {status}: {}", String::from_utf8_lossy(&body));
+            return Err(gcp_auth::Error::Other(
+                "impersonation was refused",
+                detail.into(),
+            ));
+        }
+        let generated: Generated = serde_json::from_slice(&body)
+            .map_err(|e| gcp_auth::Error::Json("invalid impersonation response", e))?;
+        // The response's `expireTime` is the requested lifetime from
+        // now, which is what `Token` wants anyway.
+        let token = serde_json::from_value(serde_json::json!({
+            "access_token": generated.access_token,
+            "expires_in": IMPERSONATED_LIFETIME_SECS,
+        }))
+        .map_err(|e| gcp_auth::Error::Json("invalid impersonation response", e))?;
+        Ok(Arc::new(token))
+    }
+
+    async fn project_id(&self) -> Result<Arc<str>, gcp_auth::Error> {
+        self.source.project_id().await
+    }
+}
+
+/// Token source for a service-account JSON key at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+fn key_file_tokens(path: &Path, options: &AdcOptions) -> Result<Arc<dyn TokenProvider>, Error> {
+    let account = gcp_auth::CustomServiceAccount::from_file(path).map_err(|e| {
+        Error::config(format!(
+            "failed to load service-account key {}: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(match options.audience() {
+        Some(audience) => Arc::new(account.with_audience(audience.to_string())),
+        None => Arc::new(account),
+    })
+}
 
 /// Authentication state for Vertex AI. Internal — callers configure
 /// auth via [`VertexEndpoint::with_access_token`] /
-/// [`VertexEndpoint::with_adc`] rather than constructing this directly.
+/// [`VertexEndpoint::with_adc`] and friends rather than constructing
+/// this directly.
 #[derive(Clone)]
 pub(crate) enum VertexAuth {
     /// A pre-fetched access token, behind a shared lock so a
@@ -34,16 +250,24 @@ pub(crate) enum VertexAuth {
     /// [`VertexEndpoint::set_access_token`] without rebuilding. GCP
     /// access tokens last ~1h; the caller owns refresh.
     Static(Arc<RwLock<String>>),
-    /// Application Default Credentials. Token caching/refresh is
-    /// delegated to `gcp_auth`'s `TokenProvider`.
-    Adc(Arc<dyn TokenProvider>),
+    /// Application Default Credentials, a service-account key, or an
+    /// impersonated service account, cached in [`AdcTokens`].
+    #[cfg(not(target_arch = "wasm32"))]
+    Adc(Arc<AdcTokens>),
+    /// Express-mode API key, sent as `x-goog-api-key`.
+    ApiKey(String),
+    /// Caller-supplied token source, asked for a token per request.
+    Source(SharedTokenSource),
 }
 
 impl fmt::Debug for VertexAuth {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             VertexAuth::Static(_) => f.debug_tuple("Static").field(&"<redacted>").finish(),
+            #[cfg(not(target_arch = "wasm32"))]
             VertexAuth::Adc(_) => f.debug_struct("Adc").finish_non_exhaustive(),
+            VertexAuth::ApiKey(_) => f.debug_tuple("ApiKey").field(&"<redacted>").finish(),
+            VertexAuth::Source(_) => f.debug_tuple("Source").field(&"<attached>").finish(),
         }
     }
 }
@@ -63,29 +287,198 @@ impl VertexEndpoint {
     ///
     /// GCP access tokens expire after ~1h. For a long-lived process,
     /// either swap the token before expiry with
-    /// [`Self::set_access_token`], or prefer [`Self::with_adc`] which
+    /// [`Self::set_access_token`], or (natively) prefer `with_adc`, which
     /// refreshes automatically.
     pub fn with_access_token(project_id: String, location: String, access_token: String) -> Self {
         Self {
             project_id,
-            location,
+            location: normalize_location(location),
             base_url: None,
             auth: VertexAuth::Static(Arc::new(RwLock::new(access_token))),
         }
     }
 
+    /// Build from a caller-supplied [`crate::auth::TokenSource`], asked for an OAuth
+    /// access token before every request. The source owns caching and
+    /// refresh.
+    pub fn with_token_source(
+        project_id: String,
+        location: String,
+        source: SharedTokenSource,
+    ) -> Self {
+        Self {
+            project_id,
+            location: normalize_location(location),
+            base_url: None,
+            auth: VertexAuth::Source(source),
+        }
+    }
+
+    /// Build for Vertex AI express mode: `api_key` authenticates every
+    /// call, with no OAuth token or IAM setup. Express mode serves
+    /// Google's own models from the global endpoint only, so there is
+    /// no project or location — [`Self::project_id`] is empty and
+    /// [`Self::location`] is `global`. GCS uploads still need OAuth.
+    pub fn with_api_key(api_key: String) -> Self {
+        Self {
+            project_id: String::new(),
+            location: "global".to_string(),
+            base_url: None,
+            auth: VertexAuth::ApiKey(api_key),
+        }
+    }
+
+    /// Whether this endpoint authenticates with an express-mode API key.
+    pub fn is_express(&self) -> bool {
+        matches!(self.auth, VertexAuth::ApiKey(_))
+    }
+
     /// Build using Application Default Credentials. Async because
     /// `gcp_auth::provider()` may need to discover the credential source.
+    /// Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn with_adc(project_id: String, location: String) -> Result<Self, Error> {
-        let provider = gcp_auth::provider()
+        Self::with_adc_options(project_id, location, AdcOptions::new()).await
+    }
+
+    /// [`Self::with_adc`] with custom scopes and/or audience. Not
+    /// available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_adc_options(
+        project_id: String,
+        location: String,
+        options: AdcOptions,
+    ) -> Result<Self, Error> {
+        let provider: Arc<dyn TokenProvider> = match options.audience() {
+            // Only a service-account key signs its own assertion, so
+            // it's the only source an audience can apply to.
+            Some(audience) => {
+                let account = gcp_auth::CustomServiceAccount::from_env()
+                    .map_err(|e| Error::auth(format!("failed to load service account: {e}")))?
+                    .ok_or_else(|| {
+                        Error::config(
+                            "an ADC audience needs a service-account key in \
+                             GOOGLE_APPLICATION_CREDENTIALS",
+                        )
+                    })?;
+                Arc::new(account.with_audience(audience.to_string()))
+            }
+            None => gcp_auth::provider()
+                .await
+                .map_err(|e| Error::auth(format!("failed to create ADC provider: {e}")))?,
+        };
+        Ok(Self::with_token_provider(
+            project_id, location, provider, &options,
+        ))
+    }
+
+    /// Build from a service-account JSON key file instead of ambient
+    /// ADC — for processes holding several identities, or where
+    /// `GOOGLE_APPLICATION_CREDENTIALS` is already taken. Scopes and
+    /// audience apply as for [`Self::with_adc_options`]. Not available
+    /// on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_service_account_key(
+        project_id: String,
+        location: String,
+        key_path: impl AsRef<Path>,
+        options: AdcOptions,
+    ) -> Result<Self, Error> {
+        let provider = key_file_tokens(key_path.as_ref(), &options)?;
+        Ok(Self::with_token_provider(
+            project_id, location, provider, &options,
+        ))
+    }
+
+    /// Build from tokens issued to `target_principal` (a service-account
+    /// email) by impersonating it with ambient ADC. The ADC identity
+    /// needs the Service Account Token Creator role on the target.
+    /// `options` scopes the impersonated token; an audience is rejected,
+    /// since only key credentials sign their own assertions. The IAM
+    /// Credentials calls go through `transport`. Not available on
+    /// wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_impersonation(
+        project_id: String,
+        location: String,
+        target_principal: impl Into<String>,
+        options: AdcOptions,
+        transport: Transport,
+    ) -> Result<Self, Error> {
+        let source = gcp_auth::provider()
             .await
             .map_err(|e| Error::auth(format!("failed to create ADC provider: {e}")))?;
-        Ok(Self {
+        Self::impersonating(
+            project_id,
+            location,
+            source,
+            target_principal.into(),
+            options,
+            transport,
+        )
+    }
+
+    /// [`Self::with_impersonation`] with a service-account key, rather
+    /// than ambient ADC, as the source identity.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn with_impersonated_key(
+        project_id: String,
+        location: String,
+        key_path: &Path,
+        target_principal: String,
+        options: AdcOptions,
+        transport: Transport,
+    ) -> Result<Self, Error> {
+        let source = key_file_tokens(key_path, &AdcOptions::new())?;
+        Self::impersonating(
             project_id,
             location,
+            source,
+            target_principal,
+            options,
+            transport,
+        )
+    }
+
+    /// [`Self::with_impersonation`] from an arbitrary source identity.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn impersonating(
+        project_id: String,
+        location: String,
+        source: Arc<dyn TokenProvider>,
+        target_principal: String,
+        options: AdcOptions,
+        transport: Transport,
+    ) -> Result<Self, Error> {
+        if options.audience().is_some() {
+            return Err(Error::config(
+                "an audience can't be combined with service-account impersonation",
+            ));
+        }
+        let provider = Arc::new(ImpersonatedTokens {
+            source,
+            target_principal,
+            transport,
+            base_url: IAM_CREDENTIALS_URL.to_string(),
+        });
+        Ok(Self::with_token_provider(
+            project_id, location, provider, &options,
+        ))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn with_token_provider(
+        project_id: String,
+        location: String,
+        provider: Arc<dyn TokenProvider>,
+        options: &AdcOptions,
+    ) -> Self {
+        Self {
+            project_id,
+            location: normalize_location(location),
             base_url: None,
-            auth: VertexAuth::Adc(provider),
-        })
+            auth: VertexAuth::Adc(Arc::new(AdcTokens::new(provider, options))),
+        }
     }
 
     /// Override the base URL (scheme + host). Intended for tests using a mock
@@ -113,16 +506,14 @@ impl VertexEndpoint {
     /// - `method` is the verb (`generateContent`, `streamGenerateContent`,
     ///   `rawPredict`, `streamRawPredict`).
     /// - `query` is appended verbatim after `?`, or omitted when `None`.
+    ///
+    /// Express-mode endpoints leave out the `projects/…/locations/…`
+    /// segments.
     pub fn url(&self, publisher: &str, model: &str, method: &str, query: Option<&str>) -> String {
-        let host = self
-            .base_url
-            .as_deref()
-            .map(|b| b.trim_end_matches('/').to_owned())
-            .unwrap_or_else(|| default_host(&self.location));
         let mut url = format!(
-            "{host}/v1/projects/{project}/locations/{location}/publishers/{publisher}/models/{model}:{method}",
-            project = self.project_id,
-            location = self.location,
+            "{host}/v1/{resource}:{method}",
+            host = self.host(),
+            resource = self.model_resource(publisher, model),
         );
         if let Some(q) = query {
             url.push('?');
@@ -131,6 +522,42 @@ impl VertexEndpoint {
         url
     }
 
+    /// The model's resource name, `projects/…/locations/…/publishers/…/models/…`
+    /// (without the project and location in express mode).
+    pub(crate) fn model_resource(&self, publisher: &str, model: &str) -> String {
+        if self.is_express() {
+            format!("publishers/{publisher}/models/{model}")
+        } else {
+            format!(
+                "projects/{project}/locations/{location}/publishers/{publisher}/models/{model}",
+                project = self.project_id,
+                location = self.location,
+            )
+        }
+    }
+
+    /// WebSocket URL of the Live API's `BidiGenerateContent` service on
+    /// this endpoint's host.
+    #[cfg(feature = "google")]
+    pub(crate) fn live_url(&self) -> String {
+        let host = self.host();
+        let host = match host.split_once("://") {
+            Some(("http", rest)) => format!("ws://{rest}"),
+            Some((_, rest)) => format!("wss://{rest}"),
+            None => format!("wss://{host}"),
+        };
+        format!("{host}/ws/google.cloud.aiplatform.v1.LlmBidiService/BidiGenerateContent")
+    }
+
+    /// Scheme + host: the [`Self::with_base_url`] override, else the
+    /// location's default.
+    fn host(&self) -> String {
+        self.base_url
+            .as_deref()
+            .map(|b| b.trim_end_matches('/').to_owned())
+            .unwrap_or_else(|| default_host(&self.location))
+    }
+
     /// Replace the static access token (e.g. just before the current
     /// one expires). The new token is seen by every clone of this
     /// endpoint and every provider built from it — no rebuild needed.
@@ -143,40 +570,77 @@ impl VertexEndpoint {
                 *slot.write().unwrap_or_else(|e| e.into_inner()) = token.into();
                 Ok(())
             }
+            #[cfg(not(target_arch = "wasm32"))]
             VertexAuth::Adc(_) => Err(Error::auth(
                 "endpoint uses Application Default Credentials; tokens \
                  refresh automatically — set_access_token applies only \
                  to the static-token variant",
             )),
+            VertexAuth::ApiKey(_) => Err(Error::auth(
+                "endpoint uses an express-mode API key; set_access_token \
+                 applies only to the static-token variant",
+            )),
+            VertexAuth::Source(_) => Err(Error::auth(
+                "endpoint uses a token source; set_access_token applies \
+                 only to the static-token variant",
+            )),
         }
     }
 
-    /// Resolve an access token. For ADC this delegates to the cached
-    /// `gcp_auth::TokenProvider`.
+    /// Resolve an access token. For ADC this is the cached token,
+    /// refreshed first if it is about to expire. Express-mode endpoints
+    /// have no access token and return an error.
     pub async fn access_token(&self) -> Result<String, Error> {
         match &self.auth {
             VertexAuth::Static(token) => {
                 Ok(token.read().unwrap_or_else(|e| e.into_inner()).clone())
             }
-            VertexAuth::Adc(provider) => {
-                let token = provider
-                    .token(&[VERTEX_SCOPE])
-                    .await
-                    .map_err(|e| Error::auth(format!("ADC token fetch failed: {e}")))?;
-                Ok(token.as_str().to_string())
-            }
+            #[cfg(not(target_arch = "wasm32"))]
+            VertexAuth::Adc(tokens) => Ok(tokens.token().await?.as_str().to_string()),
+            VertexAuth::ApiKey(_) => Err(Error::auth(
+                "endpoint uses an express-mode API key, not an OAuth access token",
+            )),
+            VertexAuth::Source(source) => source.token().await,
+        }
+    }
+
+    /// Fetch and cache the first ADC token now, so the first request
+    /// doesn't pay for it and a credential problem surfaces at startup.
+    /// A token source is asked once, for the same early failure. A
+    /// no-op for static tokens and API keys.
+    pub async fn warm_up(&self) -> Result<(), Error> {
+        match &self.auth {
+            VertexAuth::Static(_) | VertexAuth::ApiKey(_) => Ok(()),
+            #[cfg(not(target_arch = "wasm32"))]
+            VertexAuth::Adc(tokens) => tokens.token().await.map(drop),
+            VertexAuth::Source(source) => source.token().await.map(drop),
         }
     }
 
-    /// Build the `Authorization: Bearer …` header tuple. Sugar over
+    /// Build the `Authorization: Bearer …` header tuple (or
+    /// `x-goog-api-key` in express mode). Sugar over
     /// [`VertexEndpoint::access_token`] for the common case where the
     /// provider just needs to attach it to a `TransportRequest.headers`.
     pub async fn auth_header(&self) -> Result<(String, String), Error> {
+        if let VertexAuth::ApiKey(key) = &self.auth {
+            return Ok(("x-goog-api-key".to_string(), key.clone()));
+        }
         let token = self.access_token().await?;
         Ok(("Authorization".to_string(), format!("Bearer {token}")))
     }
 }
 
+/// Vertex locations are lowercase ids; accept `Global` / `US-EAST5 `
+/// from hand-edited config rather than building a host that doesn't
+/// resolve.
+fn normalize_location(location: String) -> String {
+    let trimmed = location.trim();
+    if trimmed.len() == location.len() && !trimmed.bytes().any(|b| b.is_ascii_uppercase()) {
+        return location;
+    }
+    trimmed.to_ascii_lowercase()
+}
+
 /// Resolve the default Vertex AI host for a location.
 ///
 /// Vertex AI exposes three URL patterns depending on what `location`
@@ -242,6 +706,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn location_is_normalized_before_routing() {
+        let t = endpoint(" Global ");
+        assert_eq!(t.location(), "global");
+        assert_eq!(
+            t.url("google", "gemini-3-pro-preview", "generateContent", None),
+            "https://aiplatform.googleapis.com/v1/projects/proj-1/locations/global/publishers/google/models/gemini-3-pro-preview:generateContent"
+        );
+    }
+
     /// The multi-region `us` location routes to the `aiplatform.us.rep`
     /// endpoint pool, NOT to a hypothetical `us-aiplatform.googleapis.com`
     /// that would never have resolved.
@@ -294,6 +768,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn express_url_has_no_project_or_location() {
+        let t = VertexEndpoint::with_api_key("key-1".to_string());
+        assert_eq!(
+            t.url("google", "gemini-2.5-flash", "streamGenerateContent", Some("alt=sse")),
+            "https://aiplatform.googleapis.com/v1/publishers/google/models/gemini-2.5-flash:streamGenerateContent?alt=sse"
+        );
+    }
+
+    #[tokio::test]
+    async fn express_auth_header_carries_the_api_key() {
+        let t = VertexEndpoint::with_api_key("key-1".to_string());
+        assert_eq!(
+            t.auth_header().await.unwrap(),
+            ("x-goog-api-key".to_string(), "key-1".to_string()),
+        );
+        assert!(t.access_token().await.is_err());
+        assert!(t.set_access_token("tok").is_err());
+        assert!(!format!("{t:?}").contains("key-1"));
+    }
+
     #[tokio::test]
     async fn access_token_returns_static_token() {
         let t = endpoint("us-east1");
@@ -319,4 +814,220 @@ mod tests {
         assert_eq!(t.access_token().await.unwrap(), "fresh-token");
         assert_eq!(cloned.access_token().await.unwrap(), "fresh-token");
     }
+
+    struct Numbered(std::sync::atomic::AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl crate::auth::TokenSource for Numbered {
+        async fn token(&self) -> Result<String, Error> {
+            let n = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(format!("src-{n}"))
+        }
+    }
+
+    #[tokio::test]
+    async fn token_source_is_asked_per_request() {
+        let t = VertexEndpoint::with_token_source(
+            "proj-1".to_string(),
+            "us-east1".to_string(),
+            Arc::new(Numbered(Default::default())),
+        );
+        assert_eq!(
+            t.auth_header().await.unwrap(),
+            ("Authorization".to_string(), "Bearer src-0".to_string()),
+        );
+        assert_eq!(t.access_token().await.unwrap(), "src-1");
+        assert!(t.set_access_token("tok").is_err());
+    }
+
+    /// Hands out tokens living `expires_in` seconds, recording each
+    /// fetch's scopes.
+    struct CountingTokens {
+        expires_in: u64,
+        fetches: parking_lot::Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TokenProvider for CountingTokens {
+        async fn token(&self, scopes: &[&str]) -> Result<Arc<gcp_auth::Token>, gcp_auth::Error> {
+            let mut fetches = self.fetches.lock();
+            fetches.push(scopes.iter().map(|s| s.to_string()).collect());
+            let token = serde_json::from_value(serde_json::json!({
+                "access_token": format!("tok-{}", fetches.len()),
+                "expires_in": self.expires_in,
+            }))
+            .unwrap();
+            Ok(Arc::new(token))
+        }
+
+        async fn project_id(&self) -> Result<Arc<str>, gcp_auth::Error> {
+            Ok("proj-1".into())
+        }
+    }
+
+    fn adc_endpoint(expires_in: u64, options: AdcOptions) -> (VertexEndpoint, Arc<CountingTokens>) {
+        let source = Arc::new(CountingTokens {
+            expires_in,
+            fetches: parking_lot::Mutex::new(Vec::new()),
+        });
+        let endpoint = VertexEndpoint::with_token_provider(
+            "proj-1".to_string(),
+            "us-east1".to_string(),
+            source.clone(),
+            &options,
+        );
+        (endpoint, source)
+    }
+
+    #[tokio::test]
+    async fn adc_token_is_fetched_once_across_concurrent_callers() {
+        let (endpoint, source) = adc_endpoint(3600, AdcOptions::new());
+        let clone = endpoint.clone();
+        let (a, b) = tokio::join!(endpoint.access_token(), clone.access_token());
+        assert_eq!(a.unwrap(), "tok-1");
+        assert_eq!(b.unwrap(), "tok-1");
+        assert_eq!(endpoint.access_token().await.unwrap(), "tok-1");
+        assert_eq!(*source.fetches.lock(), vec![vec![VERTEX_SCOPE.to_string()]]);
+    }
+
+    #[tokio::test]
+    async fn adc_token_refreshes_once_expiring() {
+        // Inside gcp_auth's 20 s expiry margin from the start.
+        let (endpoint, source) = adc_endpoint(10, AdcOptions::new());
+        assert_eq!(endpoint.access_token().await.unwrap(), "tok-1");
+        assert_eq!(endpoint.access_token().await.unwrap(), "tok-2");
+        assert_eq!(source.fetches.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn warm_up_fetches_with_configured_scopes() {
+        let scope = "https://www.googleapis.com/auth/cloud-platform.read-only";
+        let (endpoint, source) = adc_endpoint(3600, AdcOptions::new().with_scopes([scope]));
+        endpoint.warm_up().await.unwrap();
+        assert_eq!(*source.fetches.lock(), vec![vec![scope.to_string()]]);
+        assert_eq!(endpoint.access_token().await.unwrap(), "tok-1");
+        assert_eq!(source.fetches.lock().len(), 1);
+    }
+
+    /// Answers every request with `status` / `body`, recording requests.
+    struct IamCredentials {
+        status: u16,
+        body: &'static str,
+        requests: parking_lot::Mutex<Vec<TransportRequest>>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::transport::TransportImpl for Arc<IamCredentials> {
+        async fn send(
+            &self,
+            req: TransportRequest,
+        ) -> Result<crate::transport::TransportResponse, Error> {
+            self.requests.lock().push(req);
+            let body = self.body;
+            Ok(crate::transport::TransportResponse {
+                status: self.status,
+                headers: Vec::new(),
+                body: Box::pin(futures_util::stream::once(async move {
+                    Ok(bytes::Bytes::from_static(body.as_bytes()))
+                })),
+            })
+        }
+    }
+
+    fn impersonated_endpoint(
+        status: u16,
+        body: &'static str,
+    ) -> (VertexEndpoint, Arc<IamCredentials>) {
+        let iam = Arc::new(IamCredentials {
+            status,
+            body,
+            requests: parking_lot::Mutex::new(Vec::new()),
+        });
+        let source = Arc::new(CountingTokens {
+            expires_in: 3600,
+            fetches: parking_lot::Mutex::new(Vec::new()),
+        });
+        let endpoint = VertexEndpoint::impersonating(
+            "proj-1".to_string(),
+            "us-east1".to_string(),
+            source,
+            "bot@proj-1.iam.gserviceaccount.com".to_string(),
+            AdcOptions::new(),
+            Transport::new(iam.clone()),
+        )
+        .unwrap();
+        (endpoint, iam)
+    }
+
+    #[tokio::test]
+    async fn impersonation_exchanges_the_source_token() {
+        let (endpoint, iam) = impersonated_endpoint(
+            200,
+            r#"{"accessToken":"imp-1","expireTime":"2030-01-01T00:00:00Z"}"#,
+        );
+        assert_eq!(endpoint.access_token().await.unwrap(), "imp-1");
+        // Cached like any other token source.
+        assert_eq!(endpoint.access_token().await.unwrap(), "imp-1");
+
+        let requests = iam.requests.lock();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].url,
+            "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/bot@proj-1.iam.gserviceaccount.com:generateAccessToken"
+        );
+        assert!(requests[0]
+            .headers
+            .contains(&("Authorization".to_string(), "Bearer tok-1".to_string())));
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"scope": [VERTEX_SCOPE], "lifetime": "3600s"})
+        );
+    }
+
+    #[tokio::test]
+    async fn impersonation_refusal_is_an_auth_error() {
+        let (endpoint, _) =
+            impersonated_endpoint(403, r#"{"error":{"status":"PERMISSION_DENIED"}}"#);
+        let err = endpoint.access_token().await.unwrap_err();
+        assert!(matches!(err, Error::Auth { .. }), "got: {err:?}");
+        assert!(err.to_string().contains("HTTP 403"), "got: {err}");
+    }
+
+    #[test]
+    fn impersonation_rejects_an_audience() {
+        let err = VertexEndpoint::impersonating(
+            "proj-1".to_string(),
+            "us-east1".to_string(),
+            Arc::new(CountingTokens {
+                expires_in: 3600,
+                fetches: parking_lot::Mutex::new(Vec::new()),
+            }),
+            "bot@proj-1.iam.gserviceaccount.com".to_string(),
+            AdcOptions::new().with_audience("https://example.com"),
+            Transport::new(Arc::new(IamCredentials {
+                status: 200,
+                body: "{}",
+                requests: parking_lot::Mutex::new(Vec::new()),
+            })),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "got: {err:?}");
+    }
+
+    #[test]
+    fn missing_service_account_key_is_a_config_error() {
+        let err = VertexEndpoint::with_service_account_key(
+            "proj-1".to_string(),
+            "us-east1".to_string(),
+            "/nonexistent/key.json",
+            AdcOptions::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "got: {err:?}");
+        assert!(
+            err.to_string().contains("/nonexistent/key.json"),
+            "got: {err}"
+        );
+    }
 }
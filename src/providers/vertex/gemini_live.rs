@@ -0,0 +1,646 @@
+//! Gemini Live API: bidirectional text / audio sessions over a
+//! WebSocket.
+//!
+//! Unlike `generate`, a Live session stays open across turns: the
+//! caller streams text or raw audio in, the model streams text or
+//! audio back, and new input can interrupt a reply mid-way. The crate
+//! carries no WebSocket client, so the socket is pluggable the same way
+//! HTTP is: implement [`LiveConnector`] over the client of your choice
+//! (`tokio-tungstenite`, a browser `WebSocket`, …) and hand it to
+//! [`GoogleProvider::connect_live`]. Auth, the endpoint URL and the
+//! protocol itself come from the provider's [`VertexEndpoint`].
+//!
+//! ```ignore
+//! let mut session = provider
+//!     .connect_live(&connector, LiveConfig::new("gemini-live-2.5-flash")
+//!         .with_response_modality(LiveModality::Text))
+//!     .await?;
+//! session.send_text("Hello!").await?;
+//! while let Some(event) = session.next_event().await {
+//!     match event? {
+//!         LiveEvent::Text(text) => print!("{text}"),
+//!         LiveEvent::TurnComplete { .. } => break,
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//!
+//! Audio travels base64-encoded, as [`FileSource::Base64`](crate::FileSource)
+//! payloads do: 16-bit little-endian PCM, 16 kHz in and 24 kHz out.
+
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::google::{convert_tools, encode_function_output};
+use super::{GoogleProvider, VertexEndpoint};
+use crate::types::Tool;
+use crate::{Error, Usage};
+
+/// One open WebSocket, as seen by a [`LiveSession`]. Messages are JSON
+/// documents; Vertex sends them in binary frames, so `recv` returns
+/// the payload of either frame type as bytes.
+#[async_trait]
+pub trait LiveSocket: Send {
+    /// Send `message` as a text frame.
+    async fn send(&mut self, message: String) -> Result<(), Error>;
+
+    /// The next text or binary frame's payload; `None` once the
+    /// connection closes. Implementations answer pings themselves.
+    async fn recv(&mut self) -> Option<Result<Vec<u8>, Error>>;
+}
+
+/// Opens [`LiveSocket`]s. Implement this over a WebSocket client; see
+/// the [module docs](self).
+#[async_trait]
+pub trait LiveConnector: Send + Sync {
+    /// Open a WebSocket to `url`, sending `headers` on the upgrade
+    /// request.
+    async fn connect(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+    ) -> Result<Box<dyn LiveSocket>, Error>;
+}
+
+/// What the model replies with in a Live session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum LiveModality {
+    /// Text replies.
+    Text,
+    /// Spoken replies.
+    #[default]
+    Audio,
+}
+
+/// Session setup for [`GoogleProvider::connect_live`].
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    model: String,
+    modality: LiveModality,
+    system_instruction: Option<String>,
+    voice: Option<String>,
+    tools: Vec<Tool>,
+    transcribe_input: bool,
+    transcribe_output: bool,
+}
+
+impl LiveConfig {
+    /// A session with `model` replying in audio.
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            modality: LiveModality::default(),
+            system_instruction: None,
+            voice: None,
+            tools: Vec::new(),
+            transcribe_input: false,
+            transcribe_output: false,
+        }
+    }
+
+    /// Reply in `modality` instead of audio.
+    pub fn with_response_modality(mut self, modality: LiveModality) -> Self {
+        self.modality = modality;
+        self
+    }
+
+    /// System instruction for the whole session.
+    pub fn with_system_instruction(mut self, instruction: impl Into<String>) -> Self {
+        self.system_instruction = Some(instruction.into());
+        self
+    }
+
+    /// Prebuilt voice for audio replies (e.g. `"Aoede"`).
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = Some(voice.into());
+        self
+    }
+
+    /// Tools the model may call; answer with
+    /// [`LiveSession::send_tool_response`].
+    pub fn with_tools(mut self, tools: impl IntoIterator<Item = Tool>) -> Self {
+        self.tools = tools.into_iter().collect();
+        self
+    }
+
+    /// Emit [`LiveEvent::InputTranscription`] for incoming audio.
+    pub fn with_input_transcription(mut self) -> Self {
+        self.transcribe_input = true;
+        self
+    }
+
+    /// Emit [`LiveEvent::OutputTranscription`] for spoken replies.
+    pub fn with_output_transcription(mut self) -> Self {
+        self.transcribe_output = true;
+        self
+    }
+
+    fn setup_message(&self, endpoint: &VertexEndpoint) -> serde_json::Value {
+        let modality = match self.modality {
+            LiveModality::Text => "TEXT",
+            LiveModality::Audio => "AUDIO",
+        };
+        let mut generation_config = json!({ "responseModalities": [modality] });
+        if let Some(voice) = &self.voice {
+            generation_config["speechConfig"] =
+                json!({ "voiceConfig": { "prebuiltVoiceConfig": { "voiceName": voice } } });
+        }
+        let mut setup = json!({
+            "model": endpoint.model_resource("google", &self.model),
+            "generationConfig": generation_config,
+        });
+        if let Some(instruction) = &self.system_instruction {
+            setup["systemInstruction"] = json!({ "parts": [{ "text": instruction }] });
+        }
+        if let Some(tools) = convert_tools(&self.tools) {
+            setup["tools"] = json!(tools);
+        }
+        if self.transcribe_input {
+            setup["inputAudioTranscription"] = json!({});
+        }
+        if self.transcribe_output {
+            setup["outputAudioTranscription"] = json!({});
+        }
+        json!({ "setup": setup })
+    }
+}
+
+/// One event from a [`LiveSession`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum LiveEvent {
+    /// A chunk of a text reply.
+    Text(String),
+    /// A chunk of a spoken reply.
+    Audio {
+        /// Base64-encoded audio.
+        data: String,
+        /// e.g. `audio/pcm;rate=24000`.
+        mime_type: String,
+    },
+    /// The model calls a tool; answer with
+    /// [`LiveSession::send_tool_response`].
+    ToolCall {
+        /// Call id to echo back in the response.
+        id: String,
+        /// Tool name.
+        name: String,
+        /// Arguments object.
+        arguments: serde_json::Value,
+    },
+    /// Earlier [`Self::ToolCall`]s the model no longer wants answered,
+    /// usually after an interruption.
+    ToolCallCancelled(Vec<String>),
+    /// Transcript of the caller's audio.
+    InputTranscription(String),
+    /// Transcript of the model's audio.
+    OutputTranscription(String),
+    /// New input cut the current reply short; stop playing buffered
+    /// audio.
+    Interrupted,
+    /// The model's turn is over.
+    TurnComplete {
+        /// Token usage reported with the turn, when present.
+        usage: Option<Usage>,
+    },
+    /// The server will close the connection soon.
+    GoAway {
+        /// Time left, as sent (e.g. `"10s"`).
+        time_left: Option<String>,
+    },
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ServerMessage {
+    setup_complete: Option<serde_json::Value>,
+    server_content: Option<ServerContent>,
+    tool_call: Option<ToolCall>,
+    tool_call_cancellation: Option<ToolCallCancellation>,
+    usage_metadata: Option<LiveUsage>,
+    go_away: Option<GoAway>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ServerContent {
+    model_turn: Option<ModelTurn>,
+    input_transcription: Option<Transcription>,
+    output_transcription: Option<Transcription>,
+    interrupted: bool,
+    turn_complete: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ModelTurn {
+    parts: Vec<LivePart>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct LivePart {
+    text: Option<String>,
+    inline_data: Option<InlineData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InlineData {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Transcription {
+    text: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ToolCall {
+    function_calls: Vec<FunctionCall>,
+}
+
+#[derive(Deserialize)]
+struct FunctionCall {
+    #[serde(default)]
+    id: String,
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ToolCallCancellation {
+    ids: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct LiveUsage {
+    prompt_token_count: u32,
+    response_token_count: u32,
+    cached_content_token_count: Option<u32>,
+    thoughts_token_count: Option<u32>,
+    total_token_count: Option<u32>,
+}
+
+impl From<LiveUsage> for Usage {
+    fn from(u: LiveUsage) -> Self {
+        Usage {
+            input_tokens: u.prompt_token_count,
+            // As for `generate`, thinking tokens are billed as output.
+            output_tokens: u
+                .response_token_count
+                .saturating_add(u.thoughts_token_count.unwrap_or(0)),
+            cache_read_input_tokens: u.cached_content_token_count,
+            cache_creation_input_tokens: None,
+            reasoning_tokens: u.thoughts_token_count,
+            total_tokens: u.total_token_count,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct GoAway {
+    time_left: Option<String>,
+}
+
+impl ServerMessage {
+    /// Flatten into events, in the order a listener should see them.
+    fn into_events(self, out: &mut VecDeque<LiveEvent>) {
+        let usage = self.usage_metadata.map(Usage::from);
+        if let Some(content) = self.server_content {
+            if let Some(text) = content.input_transcription.map(|t| t.text) {
+                out.push_back(LiveEvent::InputTranscription(text));
+            }
+            if content.interrupted {
+                out.push_back(LiveEvent::Interrupted);
+            }
+            for part in content.model_turn.map(|t| t.parts).unwrap_or_default() {
+                if let Some(text) = part.text {
+                    out.push_back(LiveEvent::Text(text));
+                }
+                if let Some(inline) = part.inline_data {
+                    out.push_back(LiveEvent::Audio {
+                        data: inline.data,
+                        mime_type: inline.mime_type,
+                    });
+                }
+            }
+            if let Some(text) = content.output_transcription.map(|t| t.text) {
+                out.push_back(LiveEvent::OutputTranscription(text));
+            }
+            if content.turn_complete {
+                out.push_back(LiveEvent::TurnComplete { usage });
+            }
+        }
+        for call in self.tool_call.map(|t| t.function_calls).unwrap_or_default() {
+            out.push_back(LiveEvent::ToolCall {
+                id: call.id,
+                name: call.name,
+                arguments: call.args,
+            });
+        }
+        if let Some(cancelled) = self.tool_call_cancellation {
+            out.push_back(LiveEvent::ToolCallCancelled(cancelled.ids));
+        }
+        if let Some(go_away) = self.go_away {
+            out.push_back(LiveEvent::GoAway {
+                time_left: go_away.time_left,
+            });
+        }
+    }
+}
+
+/// An open Live session. Dropping it closes the connection.
+pub struct LiveSession {
+    socket: Box<dyn LiveSocket>,
+    pending: VecDeque<LiveEvent>,
+}
+
+impl std::fmt::Debug for LiveSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LiveSession")
+            .field("pending", &self.pending.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl LiveSession {
+    /// Send a complete user turn of text.
+    pub async fn send_text(&mut self, text: &str) -> Result<(), Error> {
+        self.send(json!({
+            "clientContent": {
+                "turns": [{ "role": "user", "parts": [{ "text": text }] }],
+                "turnComplete": true,
+            }
+        }))
+        .await
+    }
+
+    /// Stream a chunk of microphone audio — base64-encoded, with a
+    /// `mime_type` such as `audio/pcm;rate=16000`. The server detects
+    /// the end of speech itself.
+    pub async fn send_audio(&mut self, data: &str, mime_type: &str) -> Result<(), Error> {
+        self.send(json!({
+            "realtimeInput": { "mediaChunks": [{ "mimeType": mime_type, "data": data }] }
+        }))
+        .await
+    }
+
+    /// Answer the [`LiveEvent::ToolCall`] `id` to tool `name`. `output`
+    /// is encoded as for `generate`: a JSON object as-is, anything else
+    /// under `result`.
+    pub async fn send_tool_response(
+        &mut self,
+        id: &str,
+        name: &str,
+        output: &str,
+    ) -> Result<(), Error> {
+        self.send(json!({
+            "toolResponse": {
+                "functionResponses": [{
+                    "id": id,
+                    "name": name,
+                    "response": encode_function_output(output),
+                }]
+            }
+        }))
+        .await
+    }
+
+    /// The next event; `None` once the server closes the session.
+    pub async fn next_event(&mut self) -> Option<Result<LiveEvent, Error>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            match self.recv().await? {
+                Ok(message) => message.into_events(&mut self.pending),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    async fn send(&mut self, message: serde_json::Value) -> Result<(), Error> {
+        self.socket.send(message.to_string()).await
+    }
+
+    async fn recv(&mut self) -> Option<Result<ServerMessage, Error>> {
+        let frame = match self.socket.recv().await? {
+            Ok(frame) => frame,
+            Err(e) => return Some(Err(e)),
+        };
+        tracing::trace!(
+            message = %String::from_utf8_lossy(&frame),
+            "received Gemini Live message"
+        );
+        Some(serde_json::from_slice(&frame).map_err(Error::from))
+    }
+}
+
+impl GoogleProvider {
+    /// Open a Live API session through `connector` and complete its
+    /// setup handshake. See the [module docs](self).
+    pub async fn connect_live(
+        &self,
+        connector: &dyn LiveConnector,
+        config: LiveConfig,
+    ) -> Result<LiveSession, Error> {
+        let mut headers = vec![self.endpoint.auth_header().await?];
+        crate::transport::merge_headers(&mut headers, &self.extra_headers);
+        let socket = connector
+            .connect(&self.endpoint.live_url(), &headers)
+            .await?;
+        let mut session = LiveSession {
+            socket,
+            pending: VecDeque::new(),
+        };
+        session.send(config.setup_message(&self.endpoint)).await?;
+        loop {
+            match session.recv().await {
+                Some(Ok(message)) if message.setup_complete.is_some() => return Ok(session),
+                Some(Ok(message)) => message.into_events(&mut session.pending),
+                Some(Err(e)) => return Err(e),
+                None => {
+                    return Err(Error::provider(
+                        "Google",
+                        "Live session closed before setup completed",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Script {
+        url: Mutex<String>,
+        headers: Mutex<Vec<(String, String)>>,
+        sent: Arc<Mutex<Vec<serde_json::Value>>>,
+        replies: Mutex<Vec<serde_json::Value>>,
+    }
+
+    struct ScriptedSocket {
+        sent: Arc<Mutex<Vec<serde_json::Value>>>,
+        replies: VecDeque<serde_json::Value>,
+    }
+
+    #[async_trait]
+    impl LiveSocket for ScriptedSocket {
+        async fn send(&mut self, message: String) -> Result<(), Error> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str(&message).unwrap());
+            Ok(())
+        }
+
+        async fn recv(&mut self) -> Option<Result<Vec<u8>, Error>> {
+            self.replies
+                .pop_front()
+                .map(|reply| Ok(reply.to_string().into_bytes()))
+        }
+    }
+
+    #[async_trait]
+    impl LiveConnector for Script {
+        async fn connect(
+            &self,
+            url: &str,
+            headers: &[(String, String)],
+        ) -> Result<Box<dyn LiveSocket>, Error> {
+            *self.url.lock().unwrap() = url.to_string();
+            *self.headers.lock().unwrap() = headers.to_vec();
+            Ok(Box::new(ScriptedSocket {
+                sent: self.sent.clone(),
+                replies: self.replies.lock().unwrap().drain(..).collect(),
+            }))
+        }
+    }
+
+    fn script(replies: impl IntoIterator<Item = serde_json::Value>) -> Script {
+        let script = Script::default();
+        script
+            .replies
+            .lock()
+            .unwrap()
+            .extend(std::iter::once(json!({"setupComplete": {}})).chain(replies));
+        script
+    }
+
+    fn provider() -> GoogleProvider {
+        GoogleProvider::new("proj".into(), "us-central1".into(), "tok".into()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn connect_sends_setup_and_waits_for_completion() {
+        let connector = script([]);
+        let tool = Tool::function(
+            "lookup",
+            Some("Look something up".to_string()),
+            serde_json::from_str(r#"{"type":"object"}"#).unwrap(),
+        );
+        let config = LiveConfig::new("gemini-live-2.5-flash")
+            .with_response_modality(LiveModality::Text)
+            .with_system_instruction("Be brief.")
+            .with_tools([tool])
+            .with_output_transcription();
+        let mut session = provider().connect_live(&connector, config).await.unwrap();
+
+        assert_eq!(
+            *connector.url.lock().unwrap(),
+            "wss://us-central1-aiplatform.googleapis.com/ws/\
+             google.cloud.aiplatform.v1.LlmBidiService/BidiGenerateContent"
+        );
+        assert!(connector
+            .headers
+            .lock()
+            .unwrap()
+            .contains(&("Authorization".into(), "Bearer tok".into())));
+        let setup = connector.sent.lock().unwrap()[0]["setup"].clone();
+        assert_eq!(
+            setup["model"],
+            "projects/proj/locations/us-central1/publishers/google/models/gemini-live-2.5-flash"
+        );
+        assert_eq!(
+            setup["generationConfig"]["responseModalities"],
+            json!(["TEXT"])
+        );
+        assert_eq!(setup["systemInstruction"]["parts"][0]["text"], "Be brief.");
+        assert_eq!(
+            setup["tools"][0]["functionDeclarations"][0]["name"],
+            "lookup"
+        );
+        assert_eq!(setup["outputAudioTranscription"], json!({}));
+        assert!(session.next_event().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn server_messages_become_events() {
+        let connector = script([
+            json!({"serverContent": {"modelTurn": {"parts": [
+                {"text": "Hi"},
+                {"inlineData": {"mimeType": "audio/pcm;rate=24000", "data": "AAE="}},
+            ]}}}),
+            json!({"serverContent": {"interrupted": true}}),
+            json!({"toolCall": {"functionCalls": [
+                {"id": "call-1", "name": "lookup", "args": {"q": "rust"}},
+            ]}}),
+            json!({
+                "serverContent": {"turnComplete": true},
+                "usageMetadata": {"promptTokenCount": 7, "responseTokenCount": 3,
+                    "totalTokenCount": 10},
+            }),
+        ]);
+        let mut session = provider()
+            .connect_live(&connector, LiveConfig::new("gemini-live-2.5-flash"))
+            .await
+            .unwrap();
+        session.send_text("Hello").await.unwrap();
+        session
+            .send_tool_response("call-1", "lookup", "42")
+            .await
+            .unwrap();
+
+        let mut events = Vec::new();
+        while let Some(event) = session.next_event().await {
+            events.push(event.unwrap());
+        }
+        assert_eq!(events[0], LiveEvent::Text("Hi".into()));
+        assert!(matches!(&events[1], LiveEvent::Audio { data, .. } if data == "AAE="));
+        assert_eq!(events[2], LiveEvent::Interrupted);
+        assert!(
+            matches!(&events[3], LiveEvent::ToolCall { id, arguments, .. }
+            if id == "call-1" && arguments["q"] == "rust")
+        );
+        let LiveEvent::TurnComplete { usage: Some(usage) } = &events[4] else {
+            panic!("expected a turn completion, got {:?}", events[4]);
+        };
+        assert_eq!((usage.input_tokens, usage.output_tokens), (7, 3));
+
+        let sent = connector.sent.lock().unwrap();
+        assert_eq!(
+            sent[1]["clientContent"]["turns"][0]["parts"][0]["text"],
+            "Hello"
+        );
+        assert_eq!(
+            sent[2]["toolResponse"]["functionResponses"][0]["response"],
+            json!({"result": 42})
+        );
+    }
+}
@@ -10,6 +10,8 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bytes::Bytes;
 
+#[cfg(not(target_arch = "wasm32"))]
+use super::endpoint::AdcOptions;
 use super::endpoint::VertexEndpoint;
 use super::google_types::*;
 use crate::factory::ProviderType;
@@ -18,17 +20,18 @@ use crate::providers::file_resolve::{
     media_type_extension, percent_encode, resolve_refs, NoLibraryUpload, ProviderUploader,
     ResolvedRef,
 };
-use crate::sse_stream::SseStream;
+use crate::sse_stream::{SseLimits, SseStream};
 use crate::transport::{Method, Transport, TransportRequest, UploadRequest};
 use crate::types::{
     Annotation, AnnotationKind, AssistantPart, FileResolver, FileSource, FinishReason, InputItem,
-    PartKind, PartUpdate, ProviderScope, ResolvedHandle, UserPart,
+    PartKind, PartUpdate, ProviderScope, ResolvedHandle, SafetyFeedback, SafetyLevel, SafetyRating,
+    SafetyTarget, UserPart,
 };
 use crate::{Error, RawConfig, Response, StreamEvent};
 
 /// Google provider implementation via Vertex AI (for Gemini models).
 pub struct GoogleProvider {
-    endpoint: VertexEndpoint,
+    pub(super) endpoint: VertexEndpoint,
     transport: Transport,
     /// Optional caller-held file registry for resolving `Ref` file inputs.
     file_resolver: Option<Arc<dyn FileResolver>>,
@@ -42,6 +45,11 @@ pub struct GoogleProvider {
     gcs_prefix: Option<String>,
     /// Cooperative rate limiter consulted before every send.
     rate_limiter: crate::rate_limit::SharedRateLimiter,
+    /// Extra headers sent with every request, before any per-request
+    /// [`RawConfig::extra_headers`](crate::RawConfig::extra_headers).
+    pub(super) extra_headers: Vec<(String, String)>,
+    /// Size caps on streamed response bodies.
+    sse_limits: SseLimits,
 }
 
 impl GoogleProvider {
@@ -54,6 +62,8 @@ impl GoogleProvider {
             gcs_bucket: None,
             gcs_prefix: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         })
     }
 
@@ -72,21 +82,109 @@ impl GoogleProvider {
             gcs_bucket: None,
             gcs_prefix: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         })
     }
 
+    /// Create a new Google provider for Vertex AI express mode, which
+    /// authenticates with an API key instead of OAuth. See
+    /// [`VertexEndpoint::with_api_key`].
+    pub fn with_api_key(api_key: String) -> Result<Self, Error> {
+        Ok(Self::with_transport(
+            VertexEndpoint::with_api_key(api_key),
+            Transport::reqwest()?,
+        ))
+    }
+
     /// Create a new Google provider with Application Default Credentials.
+    /// Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn with_adc(project_id: String, location: String) -> Result<Self, Error> {
+        Self::with_adc_options(project_id, location, AdcOptions::new()).await
+    }
+
+    /// [`Self::with_adc`] with custom ADC scopes and/or audience. See
+    /// [`AdcOptions`]. Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_adc_options(
+        project_id: String,
+        location: String,
+        options: AdcOptions,
+    ) -> Result<Self, Error> {
         Ok(Self {
-            endpoint: VertexEndpoint::with_adc(project_id, location).await?,
+            endpoint: VertexEndpoint::with_adc_options(project_id, location, options).await?,
             transport: Transport::reqwest()?,
             file_resolver: None,
             gcs_bucket: None,
             gcs_prefix: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         })
     }
 
+    /// Create a new Google provider authenticating with a
+    /// service-account JSON key file. See
+    /// [`VertexEndpoint::with_service_account_key`]. Not available on
+    /// wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_service_account_key(
+        project_id: String,
+        location: String,
+        key_path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, Error> {
+        let endpoint = VertexEndpoint::with_service_account_key(
+            project_id,
+            location,
+            key_path,
+            AdcOptions::new(),
+        )?;
+        Ok(Self::with_transport(endpoint, Transport::reqwest()?))
+    }
+
+    /// Create a new Google provider that impersonates
+    /// `target_principal` (a service-account email) using ambient ADC.
+    /// See [`VertexEndpoint::with_impersonation`]. Not available on
+    /// wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_impersonation(
+        project_id: String,
+        location: String,
+        target_principal: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let transport = Transport::reqwest()?;
+        let endpoint = VertexEndpoint::with_impersonation(
+            project_id,
+            location,
+            target_principal,
+            AdcOptions::new(),
+            transport.clone(),
+        )
+        .await?;
+        Ok(Self::with_transport(endpoint, transport))
+    }
+
+    /// Create a new Google provider whose access tokens come from a
+    /// caller-supplied [`crate::auth::TokenSource`]. See
+    /// [`VertexEndpoint::with_token_source`].
+    pub fn with_token_source(
+        project_id: String,
+        location: String,
+        source: crate::auth::SharedTokenSource,
+    ) -> Result<Self, Error> {
+        Ok(Self::with_transport(
+            VertexEndpoint::with_token_source(project_id, location, source),
+            Transport::reqwest()?,
+        ))
+    }
+
+    /// Fetch the first auth token now rather than on the first request.
+    /// See [`VertexEndpoint::warm_up`].
+    pub async fn warm_up(&self) -> Result<(), Error> {
+        self.endpoint.warm_up().await
+    }
+
     /// Create a new Google provider with a caller-supplied [`Transport`]
     /// and pre-built [`VertexEndpoint`]. Lets downstream consumers / tests
     /// plug in custom recording / replaying / retrying transports.
@@ -98,6 +196,8 @@ impl GoogleProvider {
             gcs_bucket: None,
             gcs_prefix: None,
             rate_limiter: crate::rate_limit::default_shared_limiter(),
+            extra_headers: Vec::new(),
+            sse_limits: SseLimits::default(),
         }
     }
 
@@ -134,6 +234,20 @@ impl GoogleProvider {
         self
     }
 
+    /// Send `name: value` with every request. Precedence is as for
+    /// [`ProviderConfig::extra_headers`](crate::ProviderConfig::extra_headers).
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Cap line and event sizes on streamed responses. See
+    /// [`SseLimits`].
+    pub fn with_sse_limits(mut self, limits: SseLimits) -> Self {
+        self.sse_limits = limits;
+        self
+    }
+
     /// Attach a shared [`crate::rate_limit::RateLimiter`]. See the
     /// equivalent method on the OpenAI provider for the model — same
     /// trait, same semantics.
@@ -222,7 +336,7 @@ impl GoogleProvider {
         // non-elided user item is its ToolResult — the name still
         // has to resolve.
         for item in messages {
-            if let InputItem::Assistant { content } = item {
+            if let InputItem::Assistant { content, .. } = item {
                 for part in content {
                     if let AssistantPart::ToolCall(call) = part {
                         call_id_to_name.insert(call.call_id.as_str(), call.name.as_str());
@@ -233,7 +347,7 @@ impl GoogleProvider {
 
         for item in active_messages {
             match item {
-                InputItem::System(content) => {
+                InputItem::System(content) | InputItem::Developer(content) => {
                     // `role: "system"` here is confirmed accepted by
                     // the live Vertex API — see the captured real
                     // exchange in
@@ -241,14 +355,23 @@ impl GoogleProvider {
                     // (request sends this shape; response is a valid
                     // 200). Don't "fix" to drop the role without a
                     // fresh capture proving it's required.
-                    system_instruction = Some(GoogleContent {
-                        role: "system".to_string(),
-                        parts: vec![GooglePart::Text {
+                    //
+                    // Gemini takes one `systemInstruction`, wherever the
+                    // system items sit in the history, so every one of
+                    // them contributes a part, in order — a later system
+                    // message (e.g. a mid-conversation steering note)
+                    // adds to the earlier ones instead of replacing them.
+                    system_instruction
+                        .get_or_insert_with(|| GoogleContent {
+                            role: "system".to_string(),
+                            parts: Vec::new(),
+                        })
+                        .parts
+                        .push(GooglePart::Text {
                             text: content.clone(),
-                        }],
-                    });
+                        });
                 }
-                InputItem::User { content } => {
+                InputItem::User { content, .. } => {
                     for part in content {
                         match part {
                             UserPart::Text(s) => {
@@ -258,7 +381,11 @@ impl GoogleProvider {
                                     GooglePart::Text { text: s.clone() },
                                 );
                             }
-                            UserPart::ToolResult { call_id, content } => {
+                            UserPart::ToolResult {
+                                call_id,
+                                content,
+                                is_error,
+                            } => {
                                 // No matching tool_call anywhere in
                                 // history (e.g. the originating call
                                 // was a provider-builtin dropped on a
@@ -284,10 +411,31 @@ impl GoogleProvider {
                                     GooglePart::FunctionResponse {
                                         function_response: GoogleFunctionResponse {
                                             name: function_name,
-                                            response: encode_function_output(&output_text),
+                                            response: if *is_error {
+                                                encode_function_error(&output_text)
+                                            } else {
+                                                encode_function_output(&output_text)
+                                            },
                                         },
                                     },
                                 );
+                                // `functionResponse.response` is JSON only;
+                                // images / documents the tool returned ride
+                                // along as ordinary parts of the same turn.
+                                for part in content {
+                                    let media = match part {
+                                        UserPart::Image(src) => {
+                                            file_source_to_part(src, "image/*", resolved)
+                                        }
+                                        UserPart::Document(src) => {
+                                            file_source_to_part(src, "application/pdf", resolved)
+                                        }
+                                        _ => None,
+                                    };
+                                    if let Some(media) = media {
+                                        push_part(&mut contents, "user", media);
+                                    }
+                                }
                             }
                             // Image / audio / document / video all map the same
                             // way (inlineData for base64, fileData for URL/Ref);
@@ -321,7 +469,7 @@ impl GoogleProvider {
                         }
                     }
                 }
-                InputItem::Assistant { content } => {
+                InputItem::Assistant { content, .. } => {
                     for part in content {
                         match part {
                             AssistantPart::Text { content, .. } => {
@@ -341,7 +489,7 @@ impl GoogleProvider {
                                 );
                             }
                             AssistantPart::ToolCall(call) => {
-                                let args = serde_json::from_str(&call.arguments).map_err(|e| {
+                                let args = ToolArguments::parse(&call.arguments).map_err(|e| {
                                     Error::provider(
                                         "Google",
                                         format!("Invalid function arguments: {e}"),
@@ -374,14 +522,8 @@ impl GoogleProvider {
             }
         }
 
-        let thinking_config = config.reasoning.as_ref().map(|cfg| {
-            let thinking_budget = match cfg.effort.unwrap_or(crate::types::ReasoningEffort::Medium)
-            {
-                crate::types::ReasoningEffort::Low => 2048,
-                crate::types::ReasoningEffort::Medium => 8192,
-                crate::types::ReasoningEffort::High => 16384,
-            };
-            GoogleThinkingConfig { thinking_budget }
+        let thinking_config = config.reasoning.as_ref().map(|cfg| GoogleThinkingConfig {
+            thinking_budget: cfg.thinking_budget(),
         });
 
         let (response_mime_type, response_schema) = match &config.response_format {
@@ -401,10 +543,12 @@ impl GoogleProvider {
             Some(crate::types::ResponseFormat::Text) | None => (None, None),
         };
 
+        let google_options = config.provider_options.google.as_ref();
         let generation_config = Some(GoogleGenerationConfig {
             temperature: config.temperature,
             max_output_tokens: config.max_tokens,
             top_p: config.top_p,
+            top_k: google_options.and_then(|options| options.top_k),
             stop_sequences: config.stop.clone(),
             presence_penalty: config.presence_penalty,
             frequency_penalty: config.frequency_penalty,
@@ -413,52 +557,7 @@ impl GoogleProvider {
             response_schema,
         });
 
-        let tools = config.tools.as_ref().and_then(|tools| {
-            use crate::types::{ProviderBuiltin, Tool};
-            let mut function_decls: Vec<GoogleFunctionDeclaration> = Vec::new();
-            let mut entries: Vec<GoogleTool> = Vec::new();
-            for tool in tools {
-                match tool {
-                    Tool::Function(f) => function_decls.push(GoogleFunctionDeclaration {
-                        name: f.name.clone(),
-                        description: f.description.clone().unwrap_or_default(),
-                        // Gemini's `functionDeclarations[].parameters` accepts
-                        // only the property keywords of JSON Schema — it
-                        // rejects the meta-fields `$schema`, `$ref`, and
-                        // `$defs` with a 400. Normalise before sending:
-                        // drop the meta-fields and inline any `$ref`s against
-                        // the schema's `$defs` / `definitions`.
-                        parameters: normalize_gemini_tool_schema(&f.parameters),
-                    }),
-                    Tool::Builtin(ProviderBuiltin::GoogleSearch) => {
-                        entries.push(GoogleTool::GoogleSearch {
-                            google_search: GoogleEmptyConfig::default(),
-                        });
-                    }
-                    Tool::Builtin(ProviderBuiltin::CodeExecution) => {
-                        entries.push(GoogleTool::CodeExecution {
-                            code_execution: GoogleEmptyConfig::default(),
-                        });
-                    }
-                    Tool::Builtin(b) => {
-                        tracing::debug!(?b, "Google provider dropping unsupported builtin tool");
-                    }
-                }
-            }
-            if !function_decls.is_empty() {
-                entries.insert(
-                    0,
-                    GoogleTool::Functions {
-                        function_declarations: function_decls,
-                    },
-                );
-            }
-            if entries.is_empty() {
-                None
-            } else {
-                Some(entries)
-            }
-        });
+        let tools = config.tools.as_deref().and_then(convert_tools);
 
         let tool_config = config.tool_choice.as_ref().map(|choice| match choice {
             crate::types::ToolChoice::Auto => GoogleToolConfig {
@@ -503,6 +602,15 @@ impl GoogleProvider {
             system_instruction,
             tool_config,
             cached_content,
+            safety_settings: google_options
+                .map(|options| {
+                    options
+                        .safety_settings
+                        .iter()
+                        .map(convert_safety_setting)
+                        .collect()
+                })
+                .unwrap_or_default(),
         };
 
         Ok(google_request)
@@ -511,6 +619,76 @@ impl GoogleProvider {
 
 use crate::providers::flatten_user_parts_to_text;
 
+/// Gemini `tools` entries for `tools`: every function in one
+/// `functionDeclarations` entry first, then supported builtins. `None`
+/// when nothing survives.
+pub(super) fn convert_tools(tools: &[crate::types::Tool]) -> Option<Vec<GoogleTool>> {
+    use crate::types::{ProviderBuiltin, Tool};
+    let mut function_decls: Vec<GoogleFunctionDeclaration> = Vec::new();
+    let mut entries: Vec<GoogleTool> = Vec::new();
+    for tool in tools {
+        match tool {
+            Tool::Function(f) => function_decls.push(GoogleFunctionDeclaration {
+                name: f.name.clone(),
+                description: f.description.clone().unwrap_or_default(),
+                // Gemini's `functionDeclarations[].parameters` accepts
+                // only the property keywords of JSON Schema — it
+                // rejects the meta-fields `$schema`, `$ref`, and
+                // `$defs` with a 400. Normalise before sending:
+                // drop the meta-fields and inline any `$ref`s against
+                // the schema's `$defs` / `definitions`.
+                parameters: normalize_gemini_tool_schema(&f.parameters),
+            }),
+            Tool::Builtin(ProviderBuiltin::GoogleSearch) => {
+                entries.push(GoogleTool::GoogleSearch {
+                    google_search: GoogleEmptyConfig::default(),
+                });
+            }
+            Tool::Builtin(ProviderBuiltin::CodeExecution) => {
+                entries.push(GoogleTool::CodeExecution {
+                    code_execution: GoogleEmptyConfig::default(),
+                });
+            }
+            Tool::Builtin(b) => {
+                tracing::debug!(?b, "Google provider dropping unsupported builtin tool");
+            }
+        }
+    }
+    if !function_decls.is_empty() {
+        entries.insert(
+            0,
+            GoogleTool::Functions {
+                function_declarations: function_decls,
+            },
+        );
+    }
+    if entries.is_empty() {
+        None
+    } else {
+        Some(entries)
+    }
+}
+
+fn convert_safety_setting(setting: &crate::GoogleSafetySetting) -> GoogleSafetySetting {
+    use crate::{HarmBlockThreshold, HarmCategory};
+    GoogleSafetySetting {
+        category: match setting.category {
+            HarmCategory::HateSpeech => "HARM_CATEGORY_HATE_SPEECH",
+            HarmCategory::DangerousContent => "HARM_CATEGORY_DANGEROUS_CONTENT",
+            HarmCategory::Harassment => "HARM_CATEGORY_HARASSMENT",
+            HarmCategory::SexuallyExplicit => "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+            HarmCategory::CivicIntegrity => "HARM_CATEGORY_CIVIC_INTEGRITY",
+        },
+        threshold: match setting.threshold {
+            HarmBlockThreshold::BlockLowAndAbove => "BLOCK_LOW_AND_ABOVE",
+            HarmBlockThreshold::BlockMediumAndAbove => "BLOCK_MEDIUM_AND_ABOVE",
+            HarmBlockThreshold::BlockOnlyHigh => "BLOCK_ONLY_HIGH",
+            HarmBlockThreshold::BlockNone => "BLOCK_NONE",
+            HarmBlockThreshold::Off => "OFF",
+        },
+    }
+}
+
 /// Shape a tool's output for Gemini's `functionResponse.response` field,
 /// which the API requires to be a JSON object.
 ///
@@ -520,7 +698,7 @@ use crate::providers::flatten_user_parts_to_text;
 ///   under `{"result": <value>}` so we still satisfy the object requirement
 ///   without losing structure.
 /// - Non-JSON strings are wrapped under `{"result": "<string>"}`.
-fn encode_function_output(output: &str) -> IValue {
+pub(super) fn encode_function_output(output: &str) -> IValue {
     match serde_json::from_str::<IValue>(output) {
         Ok(value) if value.is_object() => value,
         Ok(value) => ijson!({ "result": value }),
@@ -528,16 +706,29 @@ fn encode_function_output(output: &str) -> IValue {
     }
 }
 
+/// Shape a failed tool's output as `{"error": …}` — the key Gemini
+/// documents for function errors. JSON details keep their structure;
+/// anything else is sent as a string.
+fn encode_function_error(output: &str) -> IValue {
+    match serde_json::from_str::<IValue>(output) {
+        Ok(value) => ijson!({ "error": value }),
+        Err(_) => ijson!({ "error": output }),
+    }
+}
+
 /// Normalise a function tool's JSON-Schema `parameters` into the subset
 /// Gemini's `functionDeclarations[].parameters` accepts. Gemini takes
 /// only the property keywords of JSON Schema and rejects the meta-fields
 /// `$schema`, `$ref`, and `$defs` with a 400, so we:
 ///
 /// - drop meta-fields (`$schema`, `$id`, `$comment`, `$anchor`, `$defs`,
-///   `definitions`) wherever they appear in *schema* position, and
+///   `definitions`) wherever they appear in *schema* position,
 /// - inline every local `$ref` (`#/$defs/Name` or `#/definitions/Name`)
 ///   against the definitions in scope, merging any sibling keywords over
-///   the resolved definition.
+///   the resolved definition, and
+/// - lower union idioms the OpenAPI subset lacks — nullable `type`
+///   arrays, nullable `anyOf`, string `const` — to `nullable` / `enum`
+///   (see [`lower_openapi_unions`]).
 ///
 /// The walk is **position-aware**: it descends only into keywords that
 /// actually hold subschemas (`properties`, `items`, `additionalProperties`,
@@ -724,9 +915,65 @@ fn normalize_schema_keys(
             }
         }
     }
+    lower_openapi_unions(&mut out);
     out
 }
 
+/// Rewrite the two remaining JSON-Schema idioms Gemini's OpenAPI-subset
+/// `Schema` can't express, after the keyword walk:
+///
+/// - A nullable `anyOf` — `[X, {"type": "null"}]`, which is what
+///   `schemars` emits for `Option<T>` where `T` is a struct or enum —
+///   drops the null branch and sets `nullable: true`. With a single
+///   branch left it is hoisted into the node (the node's own keywords,
+///   e.g. `description`, win over the branch's).
+/// - A string `const` becomes a one-value `enum`, Gemini's only way to
+///   pin a value. Other `const`s pass through.
+fn lower_openapi_unions(out: &mut serde_json::Map<String, serde_json::Value>) {
+    use serde_json::Value;
+
+    let is_null_branch = |branch: &Value| {
+        branch.as_object().is_some_and(|branch| {
+            branch.len() == 1 && branch.get("type").and_then(Value::as_str) == Some("null")
+        })
+    };
+    if let Some(Value::Array(branches)) = out.get("anyOf") {
+        if branches.iter().any(is_null_branch) {
+            let Some(Value::Array(branches)) = out.remove("anyOf") else {
+                unreachable!("anyOf was just matched as an array");
+            };
+            let mut concrete: Vec<Value> = branches
+                .into_iter()
+                .filter(|branch| !is_null_branch(branch))
+                .collect();
+            out.insert("nullable".to_string(), Value::Bool(true));
+            match concrete.len() {
+                0 => {}
+                1 => match concrete.swap_remove(0) {
+                    Value::Object(branch) => {
+                        for (k, v) in branch {
+                            out.entry(k).or_insert(v);
+                        }
+                    }
+                    other => {
+                        out.insert("anyOf".to_string(), Value::Array(vec![other]));
+                    }
+                },
+                _ => {
+                    out.insert("anyOf".to_string(), Value::Array(concrete));
+                }
+            }
+        }
+    }
+
+    if matches!(out.get("const"), Some(Value::String(_))) && !out.contains_key("enum") {
+        let value = out.remove("const").expect("const was just matched");
+        out.insert("enum".to_string(), Value::Array(vec![value]));
+        out.entry("type")
+            .or_insert_with(|| Value::String("string".to_string()));
+    }
+}
+
 /// Map [`normalize_schema`] over an array of subschemas.
 fn map_subschema_array(
     value: serde_json::Value,
@@ -795,6 +1042,10 @@ fn resolve_ref(
 
 #[async_trait::async_trait]
 impl Provider for GoogleProvider {
+    fn name(&self) -> &str {
+        "Google"
+    }
+
     async fn generate(
         &self,
         prompt: &crate::Prompt,
@@ -824,15 +1075,15 @@ impl Provider for GoogleProvider {
             Some("alt=sse"),
         );
 
-        let body = serde_json::to_vec(&google_request)?;
-        let req = TransportRequest {
-            url,
-            headers: vec![
-                self.endpoint.auth_header().await?,
-                ("Content-Type".to_string(), "application/json".to_string()),
-            ],
-            body,
-        };
+        let body =
+            crate::providers::encode_request_body(&google_request, config.extra_body.as_ref())?;
+        let mut headers = vec![
+            self.endpoint.auth_header().await?,
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+        crate::transport::merge_headers(&mut headers, &self.extra_headers);
+        crate::transport::merge_headers(&mut headers, &config.extra_headers);
+        let req = TransportRequest { url, headers, body };
 
         let scope = crate::rate_limit::RateScope {
             // Vertex quotas are per-project-per-region, so both
@@ -879,38 +1130,43 @@ impl Provider for GoogleProvider {
                 permit.observe(crate::rate_limit::RateOutcome::OtherFailure);
             }
             let body_bytes = response.collect_body().await.unwrap_or_default();
-            let body_text = String::from_utf8_lossy(&body_bytes);
+            let body_text = crate::logging::scrub(&String::from_utf8_lossy(&body_bytes));
             // Vertex 4xx envelopes carry `"status": "UNAUTHENTICATED"` /
             // `"NOT_FOUND"` / `"RESOURCE_EXHAUSTED"` — map them onto our
             // typed variants where the mapping is clear. Context-window
             // exceeded is a 400 with INVALID_ARGUMENT and a free-form
             // message; detect via wording match (no typed code from
             // the upstream).
-            if status == 400 && is_google_context_exceeded(&body_text) {
-                return Err(Error::context_window_exceeded(
-                    "Google",
-                    body_text.to_string(),
-                ));
-            }
-            return Err(match status {
-                401 | 403 => {
-                    Error::auth_with_status(status, format!("Google {status}: {body_text}"))
+            let message = crate::providers::error_envelope_message(&body_text);
+            let error = if status == 400 && is_google_context_exceeded(&body_text) {
+                Error::context_window_exceeded("Google", message)
+            } else {
+                match status {
+                    401 | 403 => {
+                        Error::auth_with_status(status, format!("Google {status}: {message}"))
+                    }
+                    404 => Error::model_not_available(format!("Google 404: {message}")),
+                    // No `Retry-After` header → fall back to the
+                    // `RetryInfo` detail in the body. The limiter was
+                    // already told above (without it) so its AIMD step
+                    // doesn't wait on the body drain; the caller's retry
+                    // loop still gets the precise hint.
+                    429 => Error::rate_limit(
+                        retry_after.or_else(|| parse_google_retry_delay(&body_text)),
+                        format!("Google 429 (RESOURCE_EXHAUSTED): {message}"),
+                    ),
+                    // 5xx (and any other status) may carry a
+                    // `Retry-After` per RFC 7231; thread it through so
+                    // the retry helper honours the server hint.
+                    _ => Error::provider_with_retry_after(
+                        "Google",
+                        status,
+                        retry_after,
+                        format!("API error: {message}"),
+                    ),
                 }
-                404 => Error::ModelNotAvailable(format!("Google 404: {body_text}")),
-                429 => Error::rate_limit(
-                    retry_after,
-                    format!("Google 429 (RESOURCE_EXHAUSTED): {body_text}"),
-                ),
-                // 5xx (and any other status) may carry a
-                // `Retry-After` per RFC 7231; thread it through so
-                // the retry helper honours the server hint.
-                _ => Error::provider_with_retry_after(
-                    "Google",
-                    status,
-                    retry_after,
-                    format!("API error: {body_text}"),
-                ),
-            });
+            };
+            return Err(error.with_provider_details(google_error_details(&body_text)));
         }
 
         // Success path: defer the limiter observation to stream-end
@@ -920,7 +1176,7 @@ impl Provider for GoogleProvider {
         // `OtherFailure` rather than `Success`.
 
         // Create SSE stream from response (Gemini supports ?alt=sse)
-        let sse_stream = SseStream::new("Google", response.body);
+        let sse_stream = SseStream::new("Google", response.body).with_limits(self.sse_limits);
 
         // Create a stateful processor for tracking output items
         let mut state = GoogleStreamState::default();
@@ -1017,7 +1273,7 @@ impl ProviderUploader for GoogleProvider {
         let status = response.status;
         let bytes = response.collect_body().await.unwrap_or_default();
         if !(200..300).contains(&status) {
-            let body_str = String::from_utf8_lossy(&bytes).into_owned();
+            let body_str = crate::logging::scrub(&String::from_utf8_lossy(&bytes));
             return Err(Error::provider_with_status(
                 "Google",
                 status,
@@ -1100,7 +1356,7 @@ fn find_latest_gemini_continuation(
 ) -> (Option<String>, usize) {
     use crate::types::{AssistantPart, InputItem, ProviderContinuation};
     for (i, item) in messages.iter().enumerate().rev() {
-        if let InputItem::Assistant { content } = item {
+        if let InputItem::Assistant { content, .. } = item {
             for part in content.iter().rev() {
                 if let AssistantPart::Continuation(ProviderContinuation::Gemini {
                     cached_content,
@@ -1117,7 +1373,9 @@ fn find_latest_gemini_continuation(
 /// Convert Gemini's batched `groundingMetadata` payload into one or
 /// more flat [`Annotation`]s. Each `groundingSupport` (span) yields one
 /// annotation per cited chunk, so a span that draws from N sources
-/// surfaces as N URL citations covering the same byte range.
+/// surfaces as N citations covering the same byte range — URL
+/// citations for web chunks, document citations (with the retrieved
+/// passage as the snippet) for retrieval chunks.
 fn flatten_grounding_metadata(meta: &GoogleGroundingMetadata) -> Vec<Annotation> {
     let mut out = Vec::new();
     for support in &meta.grounding_supports {
@@ -1125,15 +1383,26 @@ fn flatten_grounding_metadata(meta: &GoogleGroundingMetadata) -> Vec<Annotation>
             let Some(chunk) = meta.grounding_chunks.get(chunk_idx as usize) else {
                 continue;
             };
-            let Some(web) = &chunk.web else {
-                continue;
+            let (kind, source, title, snippet) = match (&chunk.web, &chunk.retrieved_context) {
+                (Some(web), _) => (AnnotationKind::UrlCitation, &web.uri, &web.title, None),
+                (None, Some(doc)) => match &doc.uri {
+                    Some(uri) => (
+                        AnnotationKind::DocumentCitation,
+                        uri,
+                        &doc.title,
+                        doc.text.clone(),
+                    ),
+                    None => continue,
+                },
+                (None, None) => continue,
             };
             out.push(Annotation {
-                kind: AnnotationKind::UrlCitation,
+                kind,
                 start: support.segment.start_index,
                 end: support.segment.end_index,
-                source: web.uri.clone(),
-                title: web.title.clone(),
+                source: source.clone(),
+                title: title.clone(),
+                snippet,
             });
         }
     }
@@ -1169,6 +1438,9 @@ pub(crate) struct GoogleStreamState {
     /// and closed the text part, the citation target would otherwise
     /// be lost (`index_of(Text)` is `None` at finish).
     last_text_index: Option<u32>,
+    /// Latest candidate `safetyRatings`. Gemini repeats them on most
+    /// chunks but not reliably on the final one.
+    safety_ratings: Vec<GoogleSafetyRating>,
 }
 
 impl Default for GoogleStreamState {
@@ -1176,6 +1448,7 @@ impl Default for GoogleStreamState {
         Self {
             tracker: crate::providers::part_tracker::PartTracker::new(),
             last_text_index: None,
+            safety_ratings: Vec::new(),
         }
     }
 }
@@ -1284,15 +1557,117 @@ fn is_google_context_exceeded(body: &str) -> bool {
             || lower.contains("context length"))
 }
 
+/// Structured details from a Vertex error envelope
+/// (`{"error":{"code":400,"status":"INVALID_ARGUMENT","message":...}}`):
+/// the canonical gRPC status name becomes the error type and the
+/// numeric code the code. Vertex doesn't return a request ID header.
+fn google_error_details(body: &str) -> crate::error::ProviderErrorDetails {
+    let mut details = crate::error::ProviderErrorDetails::new().with_raw_body(body);
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return details;
+    };
+    if let Some(status) = value.pointer("/error/status").and_then(|v| v.as_str()) {
+        details = details.with_error_type(status);
+    }
+    if let Some(code) = value.pointer("/error/code").and_then(|v| v.as_i64()) {
+        details = details.with_code(code.to_string());
+    }
+    details
+}
+
+/// Classify a mid-stream error chunk the way the HTTP path classifies
+/// the same status: `RESOURCE_EXHAUSTED` is a rate limit, over-long
+/// input a context-window error, `UNAVAILABLE` / `INTERNAL` /
+/// `DEADLINE_EXCEEDED` (or any 5xx code) a retryable provider error.
+fn google_stream_error(error: &GoogleStreamError) -> Error {
+    let status = error.status.as_deref().unwrap_or_default();
+    let message = format!("{status}: {}", error.message);
+    if status == "RESOURCE_EXHAUSTED" || error.code == Some(429) {
+        return Error::rate_limit(None, format!("Google mid-stream {message}"));
+    }
+    if is_google_context_exceeded(&message) {
+        return Error::context_window_exceeded("Google", message);
+    }
+    let mut details = crate::error::ProviderErrorDetails::new();
+    if !status.is_empty() {
+        details = details.with_error_type(status);
+    }
+    if let Some(code) = error.code {
+        details = details.with_code(code.to_string());
+    }
+    Error::Provider {
+        provider: "Google",
+        status: None,
+        retryable: matches!(status, "UNAVAILABLE" | "INTERNAL" | "DEADLINE_EXCEEDED")
+            || error.code.is_some_and(|code| code >= 500),
+        retry_after: None,
+        message,
+        details: (!details.is_empty()).then(|| Box::new(details)),
+    }
+}
+
+/// Pull the `google.rpc.RetryInfo.retryDelay` hint out of a Vertex
+/// error envelope, as whole seconds. Gemini's 429s often omit the
+/// `Retry-After` header but carry this in `error.details` instead:
+///
+/// ```json
+/// {"error": {"status": "RESOURCE_EXHAUSTED", "details": [
+///   {"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "31s"}
+/// ]}}
+/// ```
+///
+/// `retryDelay` is a protobuf `Duration` in JSON form — decimal
+/// seconds with an `s` suffix. Anything else yields `None`.
+fn parse_google_retry_delay(body: &str) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    let delay = value
+        .pointer("/error/details")?
+        .as_array()?
+        .iter()
+        .find(|d| {
+            d.get("@type")
+                .and_then(|t| t.as_str())
+                .is_some_and(|t| t.ends_with("google.rpc.RetryInfo"))
+        })?
+        .get("retryDelay")?
+        .as_str()?;
+    let secs: f64 = delay.trim().strip_suffix('s')?.parse().ok()?;
+    std::time::Duration::try_from_secs_f64(secs)
+        .ok()
+        .map(crate::transport::retry_after_from_reset)
+}
+
+fn convert_safety_ratings(
+    ratings: &[GoogleSafetyRating],
+    target: SafetyTarget,
+) -> Vec<SafetyRating> {
+    ratings
+        .iter()
+        .map(|rating| SafetyRating {
+            category: rating.category.clone(),
+            target,
+            level: rating.probability.as_deref().and_then(SafetyLevel::parse),
+            blocked: rating.blocked,
+        })
+        .collect()
+}
+
 /// Stateful per-chunk conversion. `pub(crate)` so unit tests can drive
 /// synthetic `GoogleResponse` values directly.
 pub(crate) fn convert_response_stateful(
     response: GoogleResponse,
     state: &mut GoogleStreamState,
 ) -> Result<Vec<StreamEvent>, Error> {
+    if let Some(error) = &response.error {
+        return Err(google_stream_error(error));
+    }
+
     let mut events = Vec::new();
 
     if let Some(candidate) = response.candidates.first() {
+        if !candidate.safety_ratings.is_empty() {
+            state.safety_ratings = candidate.safety_ratings.clone();
+        }
         for part in &candidate.content.parts {
             match part {
                 GooglePart::Text { text } => {
@@ -1314,9 +1689,7 @@ pub(crate) fn convert_response_stateful(
                     state.close_code_execution(&mut events);
                     let base_id = Uuid::new_v4().simple().to_string();
                     let call_id = format!("call_{base_id}");
-                    let arguments = serde_json::to_string(&function_call.args).map_err(|e| {
-                        Error::provider("Google", format!("Failed to serialize function args: {e}"))
-                    })?;
+                    let arguments = function_call.args.get().to_owned();
                     state.open_close_tool_call(
                         &mut events,
                         call_id,
@@ -1404,17 +1777,30 @@ pub(crate) fn convert_response_stateful(
                 // suppressed", not a clean stop — surfacing them as
                 // Stop would let callers treat a censored or truncated
                 // answer as complete.
-                "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII"
-                | "IMAGE_SAFETY" => FinishReason::ContentFilter,
-                other => {
-                    tracing::warn!(
-                        finish_reason = other,
-                        "Gemini: unknown candidate finishReason; treating as Incomplete",
-                    );
-                    FinishReason::Incomplete
+                "SAFETY" | "IMAGE_SAFETY" => FinishReason::Safety,
+                "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => {
+                    FinishReason::ContentFilter
                 }
+                other => FinishReason::Other(other.to_string()),
             };
 
+            let blocked = matches!(
+                finish_reason,
+                FinishReason::Safety | FinishReason::ContentFilter
+            );
+            if blocked || !state.safety_ratings.is_empty() {
+                events.push(StreamEvent::Safety {
+                    feedback: SafetyFeedback {
+                        block_reason: blocked.then(|| finish_reason_str.clone()),
+                        ratings: convert_safety_ratings(
+                            &std::mem::take(&mut state.safety_ratings),
+                            SafetyTarget::Response,
+                        ),
+                        ..SafetyFeedback::default()
+                    },
+                });
+            }
+
             let usage = response
                 .usage_metadata
                 .map(|meta| meta.into())
@@ -1436,6 +1822,14 @@ pub(crate) fn convert_response_stateful(
                 "Gemini prompt was blocked",
             );
         }
+        events.push(StreamEvent::Safety {
+            feedback: SafetyFeedback {
+                prompt_blocked: true,
+                block_reason: feedback.block_reason.clone(),
+                message: feedback.block_reason_message.clone(),
+                ratings: convert_safety_ratings(&feedback.safety_ratings, SafetyTarget::Prompt),
+            },
+        });
         let usage = response
             .usage_metadata
             .map(|meta| meta.into())
@@ -1510,6 +1904,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_google_retry_delay_reads_retry_info() {
+        let body = r#"{"error":{"details":[
+            {"@type":"type.googleapis.com/google.rpc.QuotaFailure"},
+            {"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"31s"}
+        ]}}"#;
+        assert_eq!(parse_google_retry_delay(body), Some(31));
+        assert_eq!(
+            parse_google_retry_delay(
+                r#"{"error":{"details":[{"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"0.2s"}]}}"#
+            ),
+            Some(1),
+        );
+        assert_eq!(
+            parse_google_retry_delay(r#"{"error":{"details":[]}}"#),
+            None
+        );
+        assert_eq!(
+            parse_google_retry_delay(
+                r#"{"error":{"details":[{"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"-1s"}]}}"#
+            ),
+            None,
+        );
+        assert_eq!(parse_google_retry_delay("not json"), None);
+    }
+
     #[test]
     fn convert_simple_text_request() {
         let provider =
@@ -1542,6 +1962,104 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tool_result_images_follow_the_function_response() {
+        use crate::types::InputItem;
+        let prompt = crate::Prompt::user("show me")
+            .with_item(InputItem::assistant_tool_call(crate::types::FunctionCall {
+                call_id: "c1".into(),
+                name: "screenshot".into(),
+                arguments: "{}".into(),
+                provider_signature: None,
+                original_arguments: None,
+            }))
+            .with_item(InputItem::tool_result_parts(
+                "c1",
+                vec![
+                    crate::types::UserPart::Text("the login page".into()),
+                    crate::types::UserPart::Image(crate::types::FileSource::Base64 {
+                        data: "iVBORw0K".into(),
+                        media_type: "image/png".into(),
+                    }),
+                ],
+            ));
+        let cfg = Config::builder("gemini").build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&body).unwrap();
+        let parts = &json["contents"][2]["parts"];
+        assert_eq!(
+            parts[0]["functionResponse"]["response"],
+            serde_json::json!({ "result": "the login page" }),
+        );
+        assert_eq!(parts[1]["inlineData"]["mimeType"], "image/png");
+    }
+
+    #[test]
+    fn tool_error_uses_the_error_key() {
+        use crate::types::InputItem;
+        let prompt = crate::Prompt::user("look it up")
+            .with_item(InputItem::assistant_tool_call(crate::types::FunctionCall {
+                call_id: "c1".into(),
+                name: "lookup".into(),
+                arguments: "{}".into(),
+                provider_signature: None,
+                original_arguments: None,
+            }))
+            .with_item(InputItem::tool_error("c1", r#"{"code":503}"#));
+        let cfg = Config::builder("gemini").build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(
+            json["contents"][2]["parts"][0]["functionResponse"]["response"],
+            serde_json::json!({ "error": { "code": 503 } }),
+        );
+    }
+
+    #[test]
+    fn multiple_system_messages_merge_in_order() {
+        let prompt = crate::Prompt::system("be terse")
+            .with_user("hi")
+            .with_item(crate::types::InputItem::System("answer in French".into()));
+        let cfg = Config::builder("gemini").build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(
+            json["systemInstruction"]["parts"],
+            serde_json::json!([{ "text": "be terse" }, { "text": "answer in French" }]),
+        );
+        assert_eq!(body.contents.len(), 1);
+    }
+
+    #[test]
+    fn mid_stream_error_chunk_is_classified() {
+        let mut state = GoogleStreamState::default();
+        let mut classify = |chunk: &str| {
+            let response: GoogleResponse = serde_json::from_str(chunk).unwrap();
+            convert_response_stateful(response, &mut state).expect_err("error chunk must be Err")
+        };
+        let err =
+            classify(r#"{"error":{"code":429,"status":"RESOURCE_EXHAUSTED","message":"quota"}}"#);
+        assert!(matches!(err, Error::RateLimit { .. }), "{err:?}");
+        let err =
+            classify(r#"{"error":{"code":503,"status":"UNAVAILABLE","message":"overloaded"}}"#);
+        assert!(
+            matches!(err, Error::Provider { .. }) && err.is_retryable(),
+            "{err:?}"
+        );
+        assert_eq!(
+            err.provider_details().and_then(|d| d.error_type.as_deref()),
+            Some("UNAVAILABLE")
+        );
+        let err = classify(r#"{"error":{"code":400,"status":"INVALID_ARGUMENT","message":"bad"}}"#);
+        assert!(!err.is_retryable(), "{err:?}");
+    }
+
     #[tokio::test]
     async fn streaming_text_yields_partstart_delta_partend() {
         let chunk1 = r#"{"candidates":[{"content":{"role":"model","parts":[{"text":"Hello"}]}}]}"#;
@@ -1566,6 +2084,46 @@ mod tests {
         assert!(matches!(events.last(), Some(StreamEvent::Done { .. })));
     }
 
+    #[test]
+    fn thinking_tokens_count_as_output() {
+        // Numbers from a captured Gemini 2.5 thinking response:
+        // prompt + candidates + thoughts == total.
+        let chunk = r#"{"candidates":[{"content":{"role":"model","parts":[{"text":"4"}]},"finishReason":"STOP"}],"usageMetadata":{"promptTokenCount":36,"candidatesTokenCount":5,"thoughtsTokenCount":72,"totalTokenCount":113,"cachedContentTokenCount":20}}"#;
+        let mut state = GoogleStreamState::default();
+        let response: GoogleResponse = serde_json::from_str(chunk).unwrap();
+        let events = convert_response_stateful(response, &mut state).unwrap();
+        let Some(StreamEvent::Done { usage, .. }) = events.last() else {
+            panic!("expected Done, got {events:?}");
+        };
+        assert_eq!(usage.input_tokens, 36);
+        assert_eq!(usage.output_tokens, 77);
+        assert_eq!(usage.reasoning_tokens, Some(72));
+        assert_eq!(usage.cache_read_input_tokens, Some(20));
+        assert_eq!(usage.total_tokens(), 113);
+    }
+
+    #[test]
+    fn finish_reasons_map_without_loss() {
+        let finish = |reason: &str| {
+            let chunk = format!(
+                r#"{{"candidates":[{{"content":{{"role":"model","parts":[{{"text":"x"}}]}},"finishReason":"{reason}"}}]}}"#
+            );
+            let response: GoogleResponse = serde_json::from_str(&chunk).unwrap();
+            let events =
+                convert_response_stateful(response, &mut GoogleStreamState::default()).unwrap();
+            match events.last() {
+                Some(StreamEvent::Done { finish_reason, .. }) => finish_reason.clone(),
+                other => panic!("expected Done, got {other:?}"),
+            }
+        };
+        assert_eq!(finish("SAFETY"), FinishReason::Safety);
+        assert_eq!(finish("RECITATION"), FinishReason::ContentFilter);
+        assert_eq!(
+            finish("MALFORMED_FUNCTION_CALL"),
+            FinishReason::Other("MALFORMED_FUNCTION_CALL".into())
+        );
+    }
+
     fn provider() -> GoogleProvider {
         GoogleProvider::new("p".to_string(), "us-east1".to_string(), "tok".to_string()).unwrap()
     }
@@ -1586,6 +2144,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn google_options_reach_the_wire() {
+        use crate::{GoogleOptions, GoogleSafetySetting, HarmBlockThreshold, HarmCategory};
+        let cfg = Config::builder("gemini")
+            .provider_options(
+                crate::ProviderOptions::default().with_google(GoogleOptions {
+                    safety_settings: vec![GoogleSafetySetting {
+                        category: HarmCategory::DangerousContent,
+                        threshold: HarmBlockThreshold::BlockOnlyHigh,
+                    }],
+                    top_k: Some(20),
+                }),
+            )
+            .build();
+        let body = provider()
+            .convert_request(
+                &crate::Prompt::user("hi"),
+                cfg.raw(),
+                &std::collections::HashMap::new(),
+            )
+            .unwrap();
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["generationConfig"]["topK"], 20);
+        assert_eq!(
+            json["safetySettings"],
+            serde_json::json!([{
+                "category": "HARM_CATEGORY_DANGEROUS_CONTENT",
+                "threshold": "BLOCK_ONLY_HIGH",
+            }]),
+        );
+    }
+
     /// A resolved document `Ref` lands as a `fileData` part carrying the
     /// resolved URI and real MIME type (handle and URL both map here).
     #[test]
@@ -1595,6 +2185,8 @@ mod tests {
 
         let prompt = crate::Prompt::new().with_item(InputItem::User {
             content: vec![UserPart::Document(FileSource::Ref("doc1".into()))],
+            name: None,
+            metadata: Default::default(),
         });
         let mut resolved = std::collections::HashMap::new();
         resolved.insert(
@@ -1628,6 +2220,8 @@ mod tests {
                     media_type: "video/mp4".into(),
                 }),
             ],
+            name: None,
+            metadata: Default::default(),
         });
         let cfg = Config::builder("gemini").build();
         let body = provider()
@@ -1665,7 +2259,7 @@ mod tests {
         let cfg = Config::builder("gemini-2.5-flash")
             .reasoning(ReasoningConfig {
                 effort: Some(ReasoningEffort::High),
-                summary: None,
+                ..Default::default()
             })
             .build();
         let body = provider()
@@ -1676,6 +2270,20 @@ mod tests {
             json["generationConfig"]["thinkingConfig"]["thinkingBudget"],
             16384,
         );
+
+        // An explicit budget overrides the effort mapping.
+        let cfg = Config::builder("gemini-2.5-flash")
+            .reasoning_effort(ReasoningEffort::High)
+            .thinking_budget(3000)
+            .build();
+        let body = provider()
+            .convert_request(&prompt, cfg.raw(), &std::collections::HashMap::new())
+            .unwrap();
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(
+            json["generationConfig"]["thinkingConfig"]["thinkingBudget"],
+            3000,
+        );
     }
 
     #[test]
@@ -1938,6 +2546,46 @@ mod tests {
         );
     }
 
+    /// `Option<Struct>` from schemars is `anyOf: [{$ref}, {type: null}]`;
+    /// Gemini needs the branch hoisted with `nullable: true`. A string
+    /// `const` (serde's tagged-enum discriminant) becomes a one-value
+    /// `enum`.
+    #[test]
+    fn response_format_nullable_any_of_and_const_lowered() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "address": {
+                    "description": "where to ship",
+                    "anyOf": [{ "$ref": "#/$defs/Address" }, { "type": "null" }]
+                },
+                "kind": { "const": "parcel" }
+            },
+            "$defs": {
+                "Address": {
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } }
+                }
+            }
+        });
+        let raw = serde_json::value::to_raw_value(&schema).unwrap();
+        let lowered: serde_json::Value =
+            serde_json::from_str(normalize_gemini_tool_schema(&raw).get()).unwrap();
+        assert_eq!(
+            lowered["properties"]["address"],
+            serde_json::json!({
+                "description": "where to ship",
+                "nullable": true,
+                "type": "object",
+                "properties": { "city": { "type": "string" } }
+            }),
+        );
+        assert_eq!(
+            lowered["properties"]["kind"],
+            serde_json::json!({ "enum": ["parcel"], "type": "string" }),
+        );
+    }
+
     /// An OpenAI continuation part is ignored by Gemini — the
     /// model-switching contract: hints from the wrong provider degrade
     /// silently to a full-history request.
@@ -2000,6 +2648,8 @@ mod tests {
             ],
             finish_reason: FinishReason::Stop,
             usage: Usage::default(),
+            safety: None,
+            timing: None,
         };
         let prompt = crate::Prompt::user("first turn")
             .with_response(&prior)
@@ -2238,7 +2888,7 @@ mod tests {
             "type": "object",
             "properties": {
                 "tags": { "type": "array", "items": { "$ref": "#/$defs/Tag" } },
-                "either": { "anyOf": [ { "$ref": "#/$defs/Tag" }, { "type": "null" } ] }
+                "either": { "anyOf": [ { "$ref": "#/$defs/Tag" }, { "type": "integer" } ] }
             },
             "$defs": { "Tag": { "type": "string", "minLength": 1 } }
         }"##;
@@ -2246,7 +2896,10 @@ mod tests {
         assert_eq!(params["properties"]["tags"]["items"]["type"], "string");
         assert_eq!(params["properties"]["tags"]["items"]["minLength"], 1);
         assert_eq!(params["properties"]["either"]["anyOf"][0]["type"], "string");
-        assert_eq!(params["properties"]["either"]["anyOf"][1]["type"], "null");
+        assert_eq!(
+            params["properties"]["either"]["anyOf"][1]["type"],
+            "integer"
+        );
         assert!(params.get("$defs").is_none());
     }
 
@@ -2292,6 +2945,7 @@ mod tests {
                 name: "f".into(),
                 arguments: "{}".into(),
                 provider_signature: None,
+                original_arguments: None,
             })
             .with_tool_result("c1", "ok");
         let cfg = Config::builder("gemini").build();
@@ -2311,6 +2965,7 @@ mod tests {
                 name: "f".into(),
                 arguments: "{}".into(),
                 provider_signature: Some("sig_xyz".into()),
+                original_arguments: None,
             })
             .with_tool_result("c1", "ok");
         let cfg = Config::builder("gemini").build();
@@ -2350,4 +3005,69 @@ mod tests {
             .expect("expected a tool call");
         assert_eq!(call.provider_signature.as_deref(), Some("sig_abc"));
     }
+
+    #[test]
+    fn retrieval_chunks_become_document_citations() {
+        let meta: GoogleGroundingMetadata = serde_json::from_str(
+            r#"{
+                "groundingChunks": [
+                    {"retrievedContext": {"uri": "gs://docs/handbook.pdf", "title": "Handbook", "text": "Leave accrues monthly."}},
+                    {"web": {"uri": "https://example.com", "title": "Example"}},
+                    {"retrievedContext": {"text": "no uri"}}
+                ],
+                "groundingSupports": [
+                    {"segment": {"startIndex": 0, "endIndex": 20}, "groundingChunkIndices": [0, 1, 2]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let annotations = flatten_grounding_metadata(&meta);
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].kind, AnnotationKind::DocumentCitation);
+        assert_eq!(annotations[0].source, "gs://docs/handbook.pdf");
+        assert_eq!(annotations[0].title.as_deref(), Some("Handbook"));
+        assert_eq!(
+            annotations[0].snippet.as_deref(),
+            Some("Leave accrues monthly.")
+        );
+        assert_eq!(annotations[1].kind, AnnotationKind::UrlCitation);
+        assert_eq!(annotations[1].snippet, None);
+    }
+
+    #[test]
+    fn safety_ratings_and_prompt_blocks_surface_as_feedback() {
+        let mut state = GoogleStreamState::default();
+        let rated = r#"{"candidates":[{"content":{"role":"model","parts":[{"text":"Sure"}]},"safetyRatings":[{"category":"HARM_CATEGORY_HARASSMENT","probability":"LOW"}]}]}"#;
+        convert_response_stateful(serde_json::from_str(rated).unwrap(), &mut state).unwrap();
+        let blocked = r#"{"candidates":[{"content":{"role":"model"},"finishReason":"SAFETY","safetyRatings":[{"category":"HARM_CATEGORY_HARASSMENT","probability":"HIGH","blocked":true}]}]}"#;
+        let events =
+            convert_response_stateful(serde_json::from_str(blocked).unwrap(), &mut state).unwrap();
+        let [.., StreamEvent::Safety { feedback }, StreamEvent::Done { finish_reason, .. }] =
+            events.as_slice()
+        else {
+            panic!("expected Safety then Done, got {events:?}");
+        };
+        assert_eq!(*finish_reason, FinishReason::Safety);
+        assert!(!feedback.prompt_blocked);
+        assert_eq!(feedback.block_reason.as_deref(), Some("SAFETY"));
+        assert_eq!(feedback.ratings.len(), 1);
+        assert_eq!(feedback.ratings[0].level, Some(SafetyLevel::High));
+        assert_eq!(feedback.ratings[0].target, SafetyTarget::Response);
+        assert!(feedback.is_blocked());
+
+        let prompt = r#"{"promptFeedback":{"blockReason":"PROHIBITED_CONTENT","blockReasonMessage":"nope","safetyRatings":[{"category":"HARM_CATEGORY_DANGEROUS_CONTENT","probability":"MEDIUM"}]}}"#;
+        let events = convert_response_stateful(
+            serde_json::from_str(prompt).unwrap(),
+            &mut GoogleStreamState::default(),
+        )
+        .unwrap();
+        let [StreamEvent::Safety { feedback }, StreamEvent::Done { .. }] = events.as_slice() else {
+            panic!("expected Safety then Done, got {events:?}");
+        };
+        assert!(feedback.prompt_blocked);
+        assert_eq!(feedback.block_reason.as_deref(), Some("PROHIBITED_CONTENT"));
+        assert_eq!(feedback.message.as_deref(), Some("nope"));
+        assert_eq!(feedback.ratings[0].target, SafetyTarget::Prompt);
+        assert_eq!(feedback.ratings[0].level, Some(SafetyLevel::Medium));
+    }
 }
@@ -5,6 +5,8 @@ use ijson::IValue;
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
 
+pub(crate) use super::tool_args::ToolArguments;
+
 /// Google Vertex AI request format (for Gemini models).
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -23,6 +25,18 @@ pub struct GoogleRequest {
     /// message history that produced it.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cached_content: Option<String>,
+    /// Per-category thresholds from [`crate::GoogleOptions`].
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<GoogleSafetySetting>,
+}
+
+/// One `safetySettings` entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct GoogleSafetySetting {
+    /// `HARM_CATEGORY_*`.
+    pub category: &'static str,
+    /// `BLOCK_*` / `OFF`.
+    pub threshold: &'static str,
 }
 
 /// Gemini `toolConfig`. Forces or disables tool calling per request.
@@ -125,7 +139,7 @@ pub struct GoogleFileData {
 #[serde(rename_all = "camelCase")]
 pub struct GoogleFunctionCall {
     pub name: String,
-    pub args: IValue,
+    pub args: ToolArguments,
     /// Gemini 2.5+ thinking models attach an opaque `thoughtSignature`
     /// to each `functionCall`. Captured on parse and echoed back on the
     /// request side to preserve thinking continuity. Absent for
@@ -152,6 +166,8 @@ pub struct GoogleGenerationConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_sequences: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub presence_penalty: Option<f32>,
@@ -228,6 +244,22 @@ pub struct GoogleResponse {
     pub usage_metadata: Option<GoogleUsageMetadata>,
     #[serde(default, rename = "promptFeedback")]
     pub prompt_feedback: Option<GooglePromptFeedback>,
+    /// Set on an error chunk Vertex sends in place of a response after
+    /// the stream has started.
+    #[serde(default)]
+    pub error: Option<GoogleStreamError>,
+}
+
+/// The `error` object of a mid-stream error chunk — the same
+/// `{code, status, message}` envelope Vertex uses for HTTP errors.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GoogleStreamError {
+    #[serde(default)]
+    pub code: Option<i64>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub message: String,
 }
 
 /// Returned in place of (or alongside) candidates when the prompt itself was
@@ -238,6 +270,20 @@ pub struct GooglePromptFeedback {
     pub block_reason: Option<String>,
     #[serde(default, rename = "blockReasonMessage")]
     pub block_reason_message: Option<String>,
+    #[serde(default, rename = "safetyRatings")]
+    pub safety_ratings: Vec<GoogleSafetyRating>,
+}
+
+/// One entry of a `safetyRatings` array, on a candidate or on
+/// `promptFeedback`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GoogleSafetyRating {
+    pub category: String,
+    /// `NEGLIGIBLE` / `LOW` / `MEDIUM` / `HIGH`.
+    #[serde(default)]
+    pub probability: Option<String>,
+    #[serde(default)]
+    pub blocked: bool,
 }
 
 /// Google response candidate.
@@ -252,6 +298,8 @@ pub struct GoogleCandidate {
     /// on the unified surface.
     #[serde(default, rename = "groundingMetadata")]
     pub grounding_metadata: Option<GoogleGroundingMetadata>,
+    #[serde(default, rename = "safetyRatings")]
+    pub safety_ratings: Vec<GoogleSafetyRating>,
 }
 
 /// `groundingMetadata` payload attached to a candidate.
@@ -268,14 +316,28 @@ pub struct GoogleGroundingMetadata {
     pub grounding_supports: Vec<GoogleGroundingSupport>,
 }
 
-/// A single grounding source. Only the `web` variant is documented
-/// today; future tags (e.g. retrieval over uploaded files) deserialize
-/// into the catch-all so unknown shapes don't fail the parse.
+/// A single grounding source: a web page (`googleSearch`) or a chunk
+/// of a retrieved document (Vertex AI Search / RAG Engine retrieval).
+/// Unknown shapes leave both `None` rather than failing the parse.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GoogleGroundingChunk {
     #[serde(default)]
     pub web: Option<GoogleGroundingWebChunk>,
+    #[serde(default)]
+    pub retrieved_context: Option<GoogleRetrievedContext>,
+}
+
+/// `retrievedContext` grounding chunk. `text` is the retrieved passage.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoogleRetrievedContext {
+    #[serde(default)]
+    pub uri: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -325,21 +387,22 @@ pub struct GoogleUsageMetadata {
 
 impl From<GoogleUsageMetadata> for Usage {
     fn from(metadata: GoogleUsageMetadata) -> Self {
-        // OPEN QUESTION (needs live-API verification, not resolvable
-        // offline): does `candidatesTokenCount` already include
-        // `thoughtsTokenCount` on 2.5 thinking models, or are thoughts
-        // billed as output but reported only separately? If the
-        // latter, `output_tokens` here undercounts by the thinking
-        // budget and should be `candidates + thoughts`. Left as the
-        // verbatim wire value until confirmed against a real thinking
-        // response — `reasoning_tokens` carries the breakout either
-        // way, so no information is lost.
+        // `candidatesTokenCount` excludes `thoughtsTokenCount` on
+        // thinking models (captured traces: prompt + candidates +
+        // thoughts == total), yet thoughts are billed as output. Fold
+        // them in so `reasoning_tokens` is a subset of `output_tokens`,
+        // as it is on OpenAI.
+        let thoughts = metadata.thoughts_token_count.unwrap_or(0);
         Usage {
             input_tokens: metadata.prompt_token_count.unwrap_or(0),
-            output_tokens: metadata.candidates_token_count.unwrap_or(0),
+            output_tokens: metadata
+                .candidates_token_count
+                .unwrap_or(0)
+                .saturating_add(thoughts),
             cache_read_input_tokens: metadata.cached_content_token_count,
             cache_creation_input_tokens: None,
             reasoning_tokens: metadata.thoughts_token_count,
+            total_tokens: metadata.total_token_count,
         }
     }
 }
@@ -8,12 +8,16 @@ mod anthropic;
 pub(crate) mod anthropic_types;
 mod endpoint;
 #[cfg(feature = "google")]
+pub mod gemini_live;
+#[cfg(feature = "google")]
 mod google;
 #[cfg(feature = "google")]
 pub(crate) mod google_types;
+#[cfg(any(feature = "google", feature = "anthropic-vertex"))]
+mod tool_args;
 
 #[cfg(feature = "anthropic-vertex")]
 pub use anthropic::AnthropicViaVertexProvider;
-pub use endpoint::VertexEndpoint;
+pub use endpoint::{AdcOptions, VertexEndpoint, VERTEX_SCOPE};
 #[cfg(feature = "google")]
 pub use google::GoogleProvider;
@@ -0,0 +1,78 @@
+//! Tool-call arguments as raw JSON text, shared by the Gemini
+//! `functionCall.args` and Anthropic `tool_use.input` wire fields.
+//!
+//! [`crate::FunctionCall::arguments`] already holds the JSON text, so
+//! the request side only validates it and writes it through verbatim —
+//! no value tree is built and no key order or number formatting is
+//! lost. The response side can't borrow a `RawValue`: both fields sit
+//! inside untagged / internally tagged enums, which serde buffers
+//! before dispatching, so they decode through an [`IValue`] once.
+
+use ijson::IValue;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::value::RawValue;
+
+#[derive(Debug, Clone)]
+pub(crate) struct ToolArguments(Box<RawValue>);
+
+impl ToolArguments {
+    /// Wrap `json`, failing if it isn't a single valid JSON value.
+    pub(crate) fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        RawValue::from_string(json.to_owned()).map(Self)
+    }
+
+    /// The JSON text.
+    pub(crate) fn get(&self) -> &str {
+        self.0.get()
+    }
+
+    /// `null`, or an object without members — what Anthropic sends in
+    /// `content_block_start` before the `input_json_delta`s.
+    #[cfg(feature = "anthropic-vertex")]
+    pub(crate) fn is_empty(&self) -> bool {
+        let json = self.get();
+        json == "null" || json.split_whitespace().collect::<String>() == "{}"
+    }
+}
+
+impl Serialize for ToolArguments {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ToolArguments {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = IValue::deserialize(deserializer)?;
+        let json = serde_json::to_string(&value).map_err(serde::de::Error::custom)?;
+        RawValue::from_string(json)
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_the_text_verbatim() {
+        let args = ToolArguments::parse(r#"{"b": 1.50, "a": [1,2]}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&args).unwrap(),
+            r#"{"b": 1.50, "a": [1,2]}"#
+        );
+        assert!(ToolArguments::parse("{\"a\":").is_err());
+    }
+
+    #[test]
+    fn decodes_inside_an_untagged_enum() {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Part {
+            Call { args: ToolArguments },
+        }
+        let Part::Call { args } = serde_json::from_str(r#"{"args":{"city":"Paris"}}"#).unwrap();
+        assert_eq!(args.get(), r#"{"city":"Paris"}"#);
+    }
+}
@@ -0,0 +1,234 @@
+//! Apply a [`RateLimiter`] around any [`Provider`] from the outside.
+//!
+//! The hosted providers consult their own limiter (installed with
+//! `.with_rate_limiter(...)`) right before the HTTP send, which is
+//! where they can read rate-limit headers. [`RateLimitedProvider`]
+//! offers the same gate for providers that don't take a limiter —
+//! local models, the mock, a router or fallback built from several
+//! backends — and for callers who want one budget per *wrapped*
+//! provider instance regardless of what's inside.
+//!
+//! No headers are visible at this level, so the limiter only learns
+//! from the typed outcome: [`Error::RateLimit`] (pre-stream or
+//! mid-stream) is reported as [`RateOutcome::RateLimited`], any other
+//! error as [`RateOutcome::OtherFailure`], and a stream that reaches
+//! `Done` as [`RateOutcome::Success`].
+
+use std::sync::Arc;
+
+use super::{observe_response_stream, ProviderRateInfo, RateOutcome, RateScope, SharedRateLimiter};
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response};
+
+/// A [`Provider`] that acquires a [`super::RatePermit`] from its limiter
+/// before every call and holds it until the response stream finishes.
+///
+/// The [`RateScope`] is built from the request: `bucket_key` is
+/// `"{prefix}/{model}"` (prefix defaults to `"Layer"`, see
+/// [`Self::with_bucket_prefix`]), tenant and priority come from
+/// [`RawConfig::tenant`] / [`RawConfig::priority`] exactly as the
+/// hosted providers do.
+#[derive(Clone)]
+pub struct RateLimitedProvider {
+    inner: SharedProvider,
+    limiter: SharedRateLimiter,
+    bucket_prefix: Arc<str>,
+}
+
+impl RateLimitedProvider {
+    /// Gate `inner` behind `limiter`.
+    pub fn new(inner: impl Provider, limiter: SharedRateLimiter) -> Self {
+        Self::from_shared(Arc::new(inner), limiter)
+    }
+
+    /// Gate an already-shared provider behind `limiter`.
+    pub fn from_shared(inner: SharedProvider, limiter: SharedRateLimiter) -> Self {
+        Self {
+            inner,
+            limiter,
+            bucket_prefix: Arc::from("Layer"),
+        }
+    }
+
+    /// Override the `bucket_key` prefix. Matters for limiters that key
+    /// state by bucket (e.g. [`super::InMemoryRateLimiter`]): two
+    /// wrapped providers sharing one limiter but not one upstream
+    /// quota should use different prefixes.
+    pub fn with_bucket_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.bucket_prefix = Arc::from(prefix.into());
+        self
+    }
+}
+
+impl std::fmt::Debug for RateLimitedProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitedProvider")
+            .field("bucket_prefix", &self.bucket_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for RateLimitedProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let scope = RateScope {
+            bucket_key: format!("{}/{}", self.bucket_prefix, config.model),
+            tenant: config.tenant.unwrap_or(uuid::Uuid::nil()),
+            priority: config.priority.unwrap_or_default(),
+        };
+        let permit = self.limiter.acquire(&scope).await?;
+        match self.inner.generate(prompt, config).await {
+            Ok(response) => Ok(response.map_stream(|stream| {
+                observe_response_stream(stream, permit, ProviderRateInfo::default())
+            })),
+            Err(err) => {
+                permit.observe(match &err {
+                    Error::RateLimit { retry_after, .. } => RateOutcome::RateLimited {
+                        retry_after: *retry_after,
+                        info: ProviderRateInfo::default(),
+                    },
+                    _ => RateOutcome::OtherFailure,
+                });
+                Err(err)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in a [`RateLimitedProvider`]
+/// sharing one limiter. Place it *inside* a
+/// [`crate::retry::RetryLayer`] so every retry attempt is paced too.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: SharedRateLimiter,
+    bucket_prefix: Option<String>,
+}
+
+impl RateLimitLayer {
+    /// A layer gating every wrapped provider behind `limiter`.
+    pub fn new(limiter: SharedRateLimiter) -> Self {
+        Self {
+            limiter,
+            bucket_prefix: None,
+        }
+    }
+
+    /// See [`RateLimitedProvider::with_bucket_prefix`].
+    pub fn with_bucket_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.bucket_prefix = Some(prefix.into());
+        self
+    }
+}
+
+impl std::fmt::Debug for RateLimitLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitLayer")
+            .field("bucket_prefix", &self.bucket_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProviderLayer for RateLimitLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        let provider = RateLimitedProvider::from_shared(inner, self.limiter.clone());
+        Arc::new(match &self.bucket_prefix {
+            Some(prefix) => provider.with_bucket_prefix(prefix.clone()),
+            None => provider,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::rate_limit::{RateLimiter, RatePermit, TokenBucketRateLimiter};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Records every scope it sees and every outcome reported back.
+    #[derive(Default)]
+    struct Recording {
+        scopes: Mutex<Vec<String>>,
+        outcomes: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RateLimiter for Recording {
+        async fn acquire(&self, scope: &RateScope) -> Result<RatePermit, Error> {
+            self.scopes.lock().unwrap().push(scope.bucket_key.clone());
+            let outcomes = self.outcomes.clone();
+            Ok(RatePermit::new(move |outcome| {
+                outcomes.lock().unwrap().push(match outcome {
+                    RateOutcome::Success { .. } => "success",
+                    RateOutcome::RateLimited { .. } => "rate_limited",
+                    RateOutcome::OtherFailure => "other",
+                    RateOutcome::Cancelled => "cancelled",
+                });
+            }))
+        }
+    }
+
+    fn cfg() -> RawConfig {
+        crate::Config::builder("m").build().raw().clone()
+    }
+
+    #[tokio::test]
+    async fn observes_stream_completion_and_pre_stream_errors() {
+        let limiter = Arc::new(Recording::default());
+        let mock = MockProvider::builder()
+            .reply("ok")
+            .fail(Error::rate_limit(Some(1), "slow"))
+            .fail(Error::auth("nope"))
+            .build();
+        let provider = RateLimitedProvider::new(mock, limiter.clone()).with_bucket_prefix("Mock");
+
+        let text = provider
+            .generate(&Prompt::user("x"), &cfg())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(text, "ok");
+        assert!(provider.generate(&Prompt::user("x"), &cfg()).await.is_err());
+        assert!(provider.generate(&Prompt::user("x"), &cfg()).await.is_err());
+
+        assert_eq!(*limiter.scopes.lock().unwrap(), vec!["Mock/m"; 3]);
+        assert_eq!(
+            *limiter.outcomes.lock().unwrap(),
+            vec!["success", "rate_limited", "other"],
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn layer_paces_calls_through_token_bucket() {
+        let limiter: SharedRateLimiter = Arc::new(TokenBucketRateLimiter::new(2.0).with_burst(1));
+        let provider = crate::ProviderStack::new()
+            .layer(RateLimitLayer::new(limiter))
+            .service(MockProvider::with_text("hi"));
+        let start = tokio::time::Instant::now();
+        for _ in 0..3 {
+            crate::generate(
+                &*provider,
+                &Prompt::user("x"),
+                &crate::Config::builder("m").build(),
+            )
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        }
+        // One immediate, then two at 2 rps.
+        assert!(start.elapsed() >= Duration::from_millis(1000));
+    }
+}
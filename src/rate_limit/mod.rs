@@ -20,7 +20,21 @@
 //! beats a backlog of batch jobs.
 //!
 //! Single-tenant deployments don't need this — the [`NoOpRateLimiter`]
-//! is the default and adds zero overhead.
+//! is the default and adds zero overhead. If a single-tenant caller
+//! simply wants to stay under a *known* quota (a job queue that
+//! bursts past the provider's requests-per-second limit), use
+//! [`TokenBucketRateLimiter`] instead: a fixed rate, a burst
+//! allowance, and an optional cap on in-flight requests, with no
+//! learning.
+//!
+//! # Applying a limiter
+//!
+//! Hosted providers take a limiter via `.with_rate_limiter(...)` and
+//! consult it right before the HTTP send, where they can also feed
+//! back rate-limit headers. For any other [`crate::Provider`] — or to
+//! put one budget around a composite provider — wrap it in a
+//! [`RateLimitedProvider`] (or add a [`RateLimitLayer`] to a
+//! [`crate::ProviderStack`]).
 //!
 //! # Sharing model
 //!
@@ -362,14 +376,16 @@ pub(crate) fn default_shared_limiter() -> SharedRateLimiter {
 }
 
 mod in_memory;
+mod layer;
 mod observe_stream;
+mod token_bucket;
 
 pub use in_memory::{InMemoryRateLimiter, InMemoryRateLimiterConfig};
-// Used by every hosted provider's `generate()` to wrap the success-path
-// stream. `dead_code` allowed for the no-feature build where no provider
-// imports it.
-#[allow(unused_imports)]
+pub use layer::{RateLimitLayer, RateLimitedProvider};
+// Used by every hosted provider's `generate()` and by
+// `RateLimitedProvider` to wrap the success-path stream.
 pub(crate) use observe_stream::observe_response_stream;
+pub use token_bucket::TokenBucketRateLimiter;
 
 #[cfg(test)]
 mod tests {
@@ -24,6 +24,10 @@
 //! - Stream dropped without ever yielding a terminal event → the
 //!   wrapper's `Drop` fires `Cancelled` via the permit's own Drop.
 //!
+//! An `Err` item is terminal for the caller too: the wrapper ends the
+//! stream after it, so whatever an upstream sends after an in-stream
+//! error event never reaches the accumulator.
+//!
 //! Pre-stream errors (transport failure, non-2xx HTTP) are still
 //! observed at the call site before the wrapper is even constructed;
 //! that path doesn't need the wrapper.
@@ -57,12 +61,6 @@ use crate::Error;
 /// Wrap a success-path SSE stream so the rate-limit permit observes
 /// the terminal event rather than the HTTP-200 status. See the
 /// module docs for the rationale.
-///
-/// `dead_code` allowed for the no-provider-feature build: every
-/// hosted provider (`openai`, `google`, `anthropic-vertex`,
-/// `mock`) calls this, but a `--no-default-features` build has
-/// none of them enabled and clippy complains.
-#[allow(dead_code)]
 pub(crate) fn observe_response_stream<S>(
     inner: S,
     permit: RatePermit,
@@ -75,6 +73,7 @@ where
         inner,
         permit: Some(permit),
         info,
+        failed: false,
     }
 }
 
@@ -90,6 +89,8 @@ pin_project_lite::pin_project! {
         // `Cancelled` if the stream was dropped early.
         permit: Option<RatePermit>,
         info: ProviderRateInfo,
+        // Set once an `Err` has been yielded; the stream is over.
+        failed: bool,
     }
 
     impl<S> PinnedDrop for ObservingStream<S> {
@@ -110,6 +111,9 @@ where
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.project();
+        if *this.failed {
+            return Poll::Ready(None);
+        }
         let polled = this.inner.poll_next(cx);
         match &polled {
             Poll::Ready(Some(Ok(event))) => {
@@ -126,6 +130,7 @@ where
                 }
             }
             Poll::Ready(Some(Err(e))) => {
+                *this.failed = true;
                 if let Some(permit) = this.permit.take() {
                     let outcome = match e {
                         Error::RateLimit { retry_after, .. } => RateOutcome::RateLimited {
@@ -216,6 +221,26 @@ mod tests {
         assert_eq!(kinds.lock().unwrap().as_slice(), &["other-failure"]);
     }
 
+    #[tokio::test]
+    async fn stream_ends_after_the_first_error() {
+        let (permit, _, _) = permit_counter();
+        let events: Vec<Result<StreamEvent, Error>> = vec![
+            Err(Error::provider("Stream", "overloaded")),
+            Ok(StreamEvent::Done {
+                finish_reason: FinishReason::Stop,
+                usage: Usage::default(),
+            }),
+        ];
+        let wrapped = observe_response_stream(
+            futures::stream::iter(events),
+            permit,
+            ProviderRateInfo::default(),
+        );
+        let items: Vec<_> = wrapped.collect().await;
+        assert_eq!(items.len(), 1);
+        assert!(items[0].is_err());
+    }
+
     /// Dropping the wrapped stream before a terminal event fires the
     /// permit's `Cancelled` outcome (via the permit's own `Drop`).
     /// This is the cancellation-safety guarantee — the caller can
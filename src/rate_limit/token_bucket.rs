@@ -0,0 +1,306 @@
+//! Fixed-rate token-bucket limiter with an optional concurrency cap.
+//!
+//! [`super::InMemoryRateLimiter`] *learns* the upstream's capacity
+//! (AIMD) and schedules across tenants and priorities. That's the right
+//! tool when the quota is shared and unknown. When the quota is known
+//! up front — "this key gets 50 requests per second and we never want
+//! more than 8 in flight" — a static bucket is simpler, deterministic,
+//! and keeps a bursty job queue from tripping the provider's 429s in
+//! the first place.
+//!
+//! The bucket is **per limiter instance**: [`RateScope::bucket_key`],
+//! tenant and priority are ignored, and every `acquire` draws from the
+//! same tokens and the same concurrency slots. Share one instance
+//! across the providers that share a quota; give each independent
+//! quota its own instance.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+use super::{RateLimiter, RatePermit, RateScope};
+use crate::Error;
+
+/// A [`RateLimiter`] enforcing a static requests-per-second budget
+/// (token bucket with a configurable burst) and, optionally, a cap on
+/// concurrently in-flight requests.
+///
+/// The concurrency slot is held for the whole request — until the
+/// provider observes the permit, which for a successful call means
+/// until the response stream terminates — so `max_concurrent` bounds
+/// open streams, not just open HTTP handshakes.
+///
+/// Waiters reserve tokens in arrival order: a request that arrives
+/// while the bucket is empty takes a token "on credit" and sleeps
+/// exactly until it would have refilled, so queued requests dispatch
+/// at an even `1 / requests_per_second` cadence rather than racing
+/// each other on every refill.
+///
+/// ```ignore
+/// use std::sync::Arc;
+/// use platformed_llm::rate_limit::TokenBucketRateLimiter;
+///
+/// let limiter = Arc::new(
+///     TokenBucketRateLimiter::new(20.0)
+///         .with_burst(5)
+///         .with_max_concurrent(8),
+/// );
+/// let provider = OpenAIProvider::new(key)?.with_rate_limiter(limiter);
+/// ```
+pub struct TokenBucketRateLimiter {
+    requests_per_second: f64,
+    burst: u32,
+    bucket: Mutex<Bucket>,
+    concurrency: Option<(usize, Arc<Semaphore>)>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Available tokens. Goes negative while requests are queued on
+    /// credit; each queued request has already paid for its token.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucketRateLimiter {
+    /// A limiter admitting `requests_per_second` on average, with a
+    /// burst allowance of one second's worth of tokens (rounded up,
+    /// minimum 1) and no concurrency cap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests_per_second` is not finite and positive —
+    /// a zero or negative rate would park every request forever. Use
+    /// [`Self::try_new`] for rates read from configuration.
+    pub fn new(requests_per_second: f64) -> Self {
+        Self::try_new(requests_per_second).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::new`], but errors with [`Error::Config`] on a rate
+    /// that isn't finite and positive instead of panicking.
+    pub fn try_new(requests_per_second: f64) -> Result<Self, Error> {
+        if !requests_per_second.is_finite() || requests_per_second <= 0.0 {
+            return Err(Error::config(format!(
+                "TokenBucketRateLimiter requests_per_second must be finite and > 0, \
+                 got {requests_per_second}",
+            )));
+        }
+        let burst = (requests_per_second.ceil() as u32).max(1);
+        Ok(Self {
+            requests_per_second,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                last_refill: Instant::now(),
+            }),
+            concurrency: None,
+        })
+    }
+
+    /// Set the bucket capacity — how many requests may dispatch
+    /// back-to-back after an idle period before pacing kicks in. The
+    /// bucket starts full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `burst` is zero. Use [`Self::try_with_burst`] for
+    /// values read from configuration.
+    pub fn with_burst(self, burst: u32) -> Self {
+        self.try_with_burst(burst).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::with_burst`], but errors with [`Error::Config`] on
+    /// a zero burst instead of panicking.
+    pub fn try_with_burst(mut self, burst: u32) -> Result<Self, Error> {
+        if burst == 0 {
+            return Err(Error::config("TokenBucketRateLimiter burst must be > 0"));
+        }
+        self.burst = burst;
+        self.bucket = Mutex::new(Bucket {
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+        });
+        Ok(self)
+    }
+
+    /// Cap the number of requests in flight at once. Further
+    /// `acquire` calls wait (FIFO) for a slot before drawing a token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_concurrent` is zero. Use
+    /// [`Self::try_with_max_concurrent`] for values read from
+    /// configuration.
+    pub fn with_max_concurrent(self, max_concurrent: usize) -> Self {
+        self.try_with_max_concurrent(max_concurrent)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::with_max_concurrent`], but errors with
+    /// [`Error::Config`] on a zero cap instead of panicking.
+    pub fn try_with_max_concurrent(mut self, max_concurrent: usize) -> Result<Self, Error> {
+        if max_concurrent == 0 {
+            return Err(Error::config(
+                "TokenBucketRateLimiter max_concurrent must be > 0",
+            ));
+        }
+        self.concurrency = Some((max_concurrent, Arc::new(Semaphore::new(max_concurrent))));
+        Ok(self)
+    }
+
+    /// Configured average rate.
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    /// Configured bucket capacity.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Configured concurrency cap, if any.
+    pub fn max_concurrent(&self) -> Option<usize> {
+        self.concurrency.as_ref().map(|(max, _)| *max)
+    }
+
+    /// Take one token, returning how long the caller must wait before
+    /// dispatching (zero when a token was already available).
+    fn reserve(&self) -> Duration {
+        // A poisoned lock means a previous holder panicked between
+        // two float assignments; the bucket is still a valid pair of
+        // numbers, so keep going rather than wedging every caller.
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.requests_per_second)
+            .min(self.burst as f64);
+        bucket.last_refill = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::try_from_secs_f64(-bucket.tokens / self.requests_per_second)
+                .unwrap_or(Duration::MAX)
+        }
+    }
+}
+
+impl std::fmt::Debug for TokenBucketRateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenBucketRateLimiter")
+            .field("requests_per_second", &self.requests_per_second)
+            .field("burst", &self.burst)
+            .field("max_concurrent", &self.max_concurrent())
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimiter for TokenBucketRateLimiter {
+    async fn acquire(&self, _scope: &RateScope) -> Result<RatePermit, Error> {
+        // Concurrency slot first: holding a slot while sleeping for a
+        // token is harmless, whereas taking a token and then queueing
+        // for a slot would burn the token's time window idle.
+        let slot = match &self.concurrency {
+            Some((_, semaphore)) => Some(
+                semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| Error::config("token bucket semaphore closed"))?,
+            ),
+            None => None,
+        };
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(match slot {
+            // The slot is released when the permit is observed or
+            // dropped; the outcome itself doesn't change a static
+            // budget.
+            Some(slot) => RatePermit::new(move |_outcome| drop(slot)),
+            None => RatePermit::noop(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::{Priority, ProviderRateInfo, RateOutcome};
+    use uuid::Uuid;
+
+    fn scope() -> RateScope {
+        RateScope {
+            bucket_key: "Test/m".into(),
+            tenant: Uuid::nil(),
+            priority: Priority::Interactive,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paces_requests_after_burst() {
+        let limiter = TokenBucketRateLimiter::new(10.0).with_burst(2);
+        let start = Instant::now();
+        for _ in 0..2 {
+            let _ = limiter.acquire(&scope()).await.unwrap();
+        }
+        // Burst drained without waiting.
+        assert!(start.elapsed() < Duration::from_millis(1));
+        for _ in 0..3 {
+            let _ = limiter.acquire(&scope()).await.unwrap();
+        }
+        // Three more at 10 rps → 300ms.
+        let elapsed = start.elapsed();
+        assert!(
+            elapsed >= Duration::from_millis(300) && elapsed < Duration::from_millis(310),
+            "expected ~300ms of pacing, got {elapsed:?}",
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrency_cap_holds_until_permit_released() {
+        let limiter = TokenBucketRateLimiter::new(1000.0).with_max_concurrent(1);
+        let first = limiter.acquire(&scope()).await.unwrap();
+        let blocked = tokio::time::timeout(Duration::from_secs(5), limiter.acquire(&scope())).await;
+        assert!(blocked.is_err(), "second acquire must wait for the slot");
+
+        first.observe(RateOutcome::Success {
+            info: ProviderRateInfo::default(),
+        });
+        let second = tokio::time::timeout(Duration::from_secs(5), limiter.acquire(&scope())).await;
+        assert!(second.is_ok(), "slot must free once the permit is observed");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn dropped_permit_frees_concurrency_slot() {
+        let limiter = TokenBucketRateLimiter::new(1000.0).with_max_concurrent(1);
+        drop(limiter.acquire(&scope()).await.unwrap());
+        let next = tokio::time::timeout(Duration::from_secs(5), limiter.acquire(&scope())).await;
+        assert!(next.is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "requests_per_second")]
+    fn zero_rate_panics() {
+        let _ = TokenBucketRateLimiter::new(0.0);
+    }
+
+    #[test]
+    fn invalid_settings_are_config_errors() {
+        assert!(matches!(
+            TokenBucketRateLimiter::try_new(f64::NAN),
+            Err(Error::Config(_))
+        ));
+        let limiter = TokenBucketRateLimiter::try_new(5.0).unwrap();
+        assert!(matches!(limiter.try_with_burst(0), Err(Error::Config(_))));
+        let limiter = TokenBucketRateLimiter::try_new(5.0).unwrap();
+        assert!(matches!(
+            limiter.try_with_max_concurrent(0),
+            Err(Error::Config(_))
+        ));
+    }
+}
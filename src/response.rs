@@ -1,11 +1,18 @@
 //! Response handling for LLM generations.
 
 use crate::types::{
-    AssistantPart, FinishReason, FunctionCall, InputItem, ProviderContinuation, Usage,
+    Annotation, AssistantPart, FinishReason, FunctionCall, InputItem, PartKind, PartUpdate,
+    ProviderContinuation, SafetyFeedback, Usage,
 };
 use crate::{Error, StreamEvent};
 use futures_util::stream::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use tokio::time::Instant;
 
 /// A complete (buffered) response from an LLM provider — a single
 /// assistant turn's worth of [`AssistantPart`]s plus terminal
@@ -17,7 +24,12 @@ use std::pin::Pin;
 /// are convenience views over `content` — readers can pick whichever
 /// is more ergonomic. Callers that need to mutate the response should
 /// edit `content` directly.
-#[derive(Debug, Clone)]
+///
+/// Serializable, so a finished turn can be stored and later fed back
+/// through [`crate::Prompt::with_response`]. `timing` is process-local
+/// (it holds an `Instant`) and isn't persisted; it deserializes as
+/// `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompleteResponse {
     /// The assistant's emitted parts in order: text, reasoning, tool
     /// calls, continuation marker, etc.
@@ -26,6 +38,68 @@ pub struct CompleteResponse {
     pub finish_reason: FinishReason,
     /// Token accounting for the turn.
     pub usage: Usage,
+    /// Safety ratings and block outcome, from providers that report
+    /// them. Explains a [`FinishReason::ContentFilter`] /
+    /// [`FinishReason::Safety`] finish.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety: Option<SafetyFeedback>,
+    /// Latency of the call, when it was buffered through
+    /// [`Response::buffer`] / [`Response::collect`]. `None` for responses
+    /// assembled by hand or by driving a
+    /// [`crate::accumulator::ResponseAccumulator`] directly, and on
+    /// wasm32, which has no monotonic clock.
+    #[serde(skip)]
+    pub timing: Option<ResponseTiming>,
+}
+
+/// Latency of one buffered call. Measured from the request start —
+/// the [`crate::generate`] call, or the [`Response`]'s construction when
+/// a provider is called directly.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseTiming {
+    /// When the request started.
+    pub started_at: std::time::Instant,
+    /// From the request start to the first stream event. `None` when no
+    /// event arrived.
+    pub time_to_first_token: Option<Duration>,
+    /// From the request start to the end of the stream.
+    pub duration: Duration,
+}
+
+/// Clock readings taken while a [`Response`] drains.
+struct Stopwatch {
+    started: Instant,
+    first_event: Option<Instant>,
+}
+
+impl Stopwatch {
+    fn on_event(&mut self) {
+        self.first_event.get_or_insert_with(Instant::now);
+    }
+
+    fn finish(self) -> ResponseTiming {
+        ResponseTiming {
+            started_at: self.started.into_std(),
+            time_to_first_token: self
+                .first_event
+                .map(|first| first.saturating_duration_since(self.started)),
+            duration: self.started.elapsed(),
+        }
+    }
+}
+
+/// `Instant::now()` where the target has a clock. wasm32 has none
+/// (`Instant::now` panics there), so timing is simply not recorded.
+pub(crate) fn request_start() -> Option<Instant> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Some(Instant::now())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
 }
 
 impl CompleteResponse {
@@ -53,6 +127,18 @@ impl CompleteResponse {
         matches!(self.finish_reason, FinishReason::Length)
     }
 
+    /// The model's refusal message, when it declined to answer —
+    /// the concatenated [`AssistantPart::Refusal`] parts. `None` when
+    /// the turn has no refusal.
+    pub fn refusal(&self) -> Option<String> {
+        let mut refusals = self.content.iter().filter_map(|part| match part {
+            AssistantPart::Refusal(reason) => Some(reason.as_str()),
+            _ => None,
+        });
+        let first = refusals.next()?;
+        Some(refusals.fold(first.to_string(), |acc, next| acc + next))
+    }
+
     /// All tool calls emitted by the assistant, in emit order.
     pub fn function_calls(&self) -> Vec<&FunctionCall> {
         self.content
@@ -64,6 +150,29 @@ impl CompleteResponse {
             .collect()
     }
 
+    /// Citations attached to the assistant's text, in emit order. Spans
+    /// are relative to the text part that carries them; use
+    /// [`sources`](Self::sources) for a deduplicated reference list.
+    pub fn annotations(&self) -> Vec<&Annotation> {
+        self.content
+            .iter()
+            .flat_map(|part| match part {
+                AssistantPart::Text { annotations, .. } => annotations.as_slice(),
+                _ => &[],
+            })
+            .collect()
+    }
+
+    /// One annotation per distinct cited source, in first-cited order —
+    /// the reference list for a RAG or search-grounded answer.
+    pub fn sources(&self) -> Vec<&Annotation> {
+        let mut seen = std::collections::HashSet::new();
+        self.annotations()
+            .into_iter()
+            .filter(|a| seen.insert(a.source.as_str()))
+            .collect()
+    }
+
     /// The provider's resumption hint for the next conversation turn,
     /// if any. Surfaces the latest [`AssistantPart::Continuation`] in
     /// the assistant content. Always optional, always safe to ignore.
@@ -76,7 +185,7 @@ impl CompleteResponse {
 
     /// Convert the response into a list of input items suitable for
     /// appending to the next [`crate::Prompt`]. Returns a single
-    /// `InputItem::Assistant { content }`; any
+    /// `InputItem::Assistant { content, .. }`; any
     /// [`AssistantPart::Continuation`] inside is automatically picked
     /// up by the next same-provider request and elides prior history.
     pub fn to_items(&self) -> Vec<InputItem> {
@@ -85,14 +194,59 @@ impl CompleteResponse {
         } else {
             vec![InputItem::Assistant {
                 content: self.content.clone(),
+                name: None,
+                metadata: Default::default(),
             }]
         }
     }
 }
 
+/// The boxed event stream a [`Response`] wraps.
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>>;
+
+/// Out-of-band facts about how a [`Response`] was produced, available
+/// before the stream is consumed. Providers leave it empty; wrappers
+/// that choose between backends (e.g. [`crate::FallbackProvider`])
+/// record their choice here, and [`crate::generate`] the call's
+/// request ID.
+///
+/// Lives on the streaming [`Response`] rather than
+/// [`CompleteResponse`]: read it before calling [`Response::buffer`]
+/// / [`Response::stream`], which consume the handle.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseMetadata {
+    /// Label of the backend that served the request, when a routing
+    /// wrapper picked one.
+    pub served_by: Option<String>,
+    /// The model the serving backend was actually asked for, when a
+    /// wrapper substituted one for the caller's [`crate::Config`]
+    /// model.
+    pub model: Option<String>,
+    /// The call's [`crate::RawConfig::request_id`], caller-supplied or
+    /// generated. Set by [`crate::generate`].
+    pub request_id: Option<String>,
+}
+
 /// A streaming response.
+///
+/// Consume it whole with [`Self::buffer`] / [`Self::text`], or poll it
+/// directly: `Response` is itself a `Stream` of [`StreamEvent`]s, so
+/// `StreamExt` combinators apply without unwrapping through
+/// [`Self::stream`]. Polling it directly records no
+/// [`ResponseTiming`].
 pub struct Response {
-    stream: Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>>,
+    stream: EventStream,
+    metadata: ResponseMetadata,
+    started: Option<Instant>,
+}
+
+impl std::fmt::Debug for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Response")
+            .field("metadata", &self.metadata)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Response {
@@ -105,9 +259,85 @@ impl Response {
     {
         Self {
             stream: Box::pin(stream),
+            metadata: ResponseMetadata::default(),
+            started: request_start(),
         }
     }
 
+    /// Replay a buffered turn as a stream: one delta per part, the
+    /// safety feedback if any, then `Done`. For wrappers that rewrite a [`CompleteResponse`] and hand
+    /// it back as a [`Response`]; buffering the result reproduces
+    /// `complete` (minus [`AssistantPart::CacheBreakpoint`]s, which have
+    /// no streamed form).
+    pub fn from_complete(complete: CompleteResponse) -> Self {
+        let mut events = Vec::new();
+        let mut index = 0;
+        for part in complete.content {
+            if push_part_events(&mut events, index, part) {
+                index += 1;
+            }
+        }
+        if let Some(feedback) = complete.safety {
+            events.push(Ok(StreamEvent::Safety { feedback }));
+        }
+        events.push(Ok(StreamEvent::Done {
+            finish_reason: complete.finish_reason,
+            usage: complete.usage,
+        }));
+        Self::from_stream(futures_util::stream::iter(events))
+    }
+
+    /// Metadata recorded by the provider stack. See
+    /// [`ResponseMetadata`].
+    pub fn metadata(&self) -> &ResponseMetadata {
+        &self.metadata
+    }
+
+    /// Mutable access to the metadata, for wrappers annotating a
+    /// response on its way out.
+    pub fn metadata_mut(&mut self) -> &mut ResponseMetadata {
+        &mut self.metadata
+    }
+
+    /// Replace the metadata wholesale.
+    pub fn with_metadata(mut self, metadata: ResponseMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Rewrite the event stream while keeping the metadata. Use this
+    /// rather than `Response::from_stream(f(response.stream()))` in
+    /// middleware and provider wrappers, which would silently drop
+    /// whatever an inner layer recorded.
+    pub fn map_stream<F, S>(self, f: F) -> Self
+    where
+        F: FnOnce(EventStream) -> S,
+        S: Stream<Item = Result<StreamEvent, Error>> + Send + 'static,
+    {
+        Self {
+            stream: Box::pin(f(self.stream)),
+            metadata: self.metadata,
+            started: self.started,
+        }
+    }
+
+    /// Backdate the request start to `started` (taken before the
+    /// provider call), so [`ResponseTiming`] covers connection setup
+    /// and time-to-headers too.
+    pub(crate) fn started_at(mut self, started: Option<Instant>) -> Self {
+        if started.is_some() {
+            self.started = started;
+        }
+        self
+    }
+
+    fn stopwatch(&self) -> Option<Stopwatch> {
+        self.started.map(|started| Stopwatch {
+            started,
+            first_event: None,
+        })
+    }
+
     /// Drain the stream and return the buffered [`CompleteResponse`].
     ///
     /// Unlike [`Self::collect`] this does not build (and clone every
@@ -117,8 +347,12 @@ impl Response {
     pub async fn buffer(self) -> Result<CompleteResponse, Error> {
         use futures_util::StreamExt;
         let mut accumulator = crate::accumulator::ResponseAccumulator::new();
+        let mut stopwatch = self.stopwatch();
         let mut stream = self.stream;
         while let Some(event_result) = stream.next().await {
+            if let Some(stopwatch) = stopwatch.as_mut() {
+                stopwatch.on_event();
+            }
             let event = event_result?;
             let done = matches!(event, StreamEvent::Done { .. });
             accumulator.process_event(event)?;
@@ -126,7 +360,9 @@ impl Response {
                 break;
             }
         }
-        accumulator.finalize()
+        let mut response = accumulator.finalize()?;
+        response.timing = stopwatch.map(Stopwatch::finish);
+        Ok(response)
     }
 
     /// Drain the stream and return the concatenated text of all text parts.
@@ -145,16 +381,19 @@ impl Response {
     /// a post-hoc record, not a live feed. Use it for inspection,
     /// snapshot testing, or audit logging. For the buffered result
     /// alone prefer [`Self::buffer`] (no event-log allocation). If you
-    /// need live event handling while the model streams, consume
-    /// [`Self::stream`] directly and feed events into a
-    /// [`crate::accumulator::ResponseAccumulator`] yourself.
+    /// need live event handling while the model streams, use
+    /// [`Self::accumulate`].
     pub async fn collect(self) -> Result<(Vec<StreamEvent>, CompleteResponse), Error> {
         let mut accumulator = crate::accumulator::ResponseAccumulator::new();
         let mut events = Vec::new();
+        let mut stopwatch = self.stopwatch();
 
         use futures_util::StreamExt;
         let mut stream = self.stream;
         while let Some(event_result) = stream.next().await {
+            if let Some(stopwatch) = stopwatch.as_mut() {
+                stopwatch.on_event();
+            }
             let event = event_result?;
             let done = matches!(event, StreamEvent::Done { .. });
             events.push(event.clone());
@@ -164,16 +403,209 @@ impl Response {
             }
         }
 
-        let response = accumulator.finalize()?;
+        let mut response = accumulator.finalize()?;
+        response.timing = stopwatch.map(Stopwatch::finish);
         Ok((events, response))
     }
 
-    /// Unwrap to the raw event stream for direct consumption.
-    pub fn stream(self) -> Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send>> {
+    /// Stream the events while accumulating them, so the partial
+    /// response is readable between events. See
+    /// [`crate::accumulator::AccumulatingStream`].
+    pub fn accumulate(self) -> crate::accumulator::AccumulatingStream {
+        crate::accumulator::AccumulatingStream::new(self.stream)
+    }
+
+    /// Split into two responses that each see every event, from one
+    /// upstream call — e.g. one streamed to a UI while the other is
+    /// buffered for persistence. Metadata is copied to both.
+    ///
+    /// Whichever half is polled drives the upstream stream; the other
+    /// half's events queue until it catches up, so a half that is
+    /// never consumed should be dropped rather than left idle. An
+    /// upstream error reaches both halves; a `reqwest` / `serde_json`
+    /// failure, which can't be cloned, reaches the second as an
+    /// [`Error::Provider`] with the same message.
+    pub fn tee(self) -> (Response, Response) {
+        let shared = Arc::new(Mutex::new(Tee {
+            inner: self.stream,
+            queues: [VecDeque::new(), VecDeque::new()],
+            wakers: [None, None],
+            open: [true, true],
+            finished: false,
+        }));
+        let half = |side| Response {
+            stream: Box::pin(TeeHalf {
+                shared: Arc::clone(&shared),
+                side,
+            }),
+            metadata: self.metadata.clone(),
+            started: self.started,
+        };
+        (half(0), half(1))
+    }
+
+    /// Unwrap to the raw event stream for direct consumption. The
+    /// `Response` is a `Stream` itself; this is for APIs that want the
+    /// boxed [`EventStream`] by value.
+    pub fn stream(self) -> EventStream {
         self.stream
     }
 }
 
+/// State shared by the two halves of a [`Response::tee`].
+struct Tee {
+    inner: EventStream,
+    /// Events polled from `inner` that each half hasn't consumed yet.
+    queues: [VecDeque<Result<StreamEvent, Error>>; 2],
+    /// A half waiting on an empty queue.
+    wakers: [Option<Waker>; 2],
+    /// Whether each half is still alive; dropped halves aren't queued for.
+    open: [bool; 2],
+    finished: bool,
+}
+
+struct TeeHalf {
+    shared: Arc<Mutex<Tee>>,
+    side: usize,
+}
+
+impl Stream for TeeHalf {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let side = self.side;
+        let other = 1 - side;
+        let mut tee = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(item) = tee.queues[side].pop_front() {
+            return Poll::Ready(Some(item));
+        }
+        if tee.finished {
+            return Poll::Ready(None);
+        }
+        match tee.inner.as_mut().poll_next(cx) {
+            Poll::Ready(item) => {
+                if tee.open[other] {
+                    match &item {
+                        Some(Ok(event)) => tee.queues[other].push_back(Ok(event.clone())),
+                        Some(Err(e)) => tee.queues[other].push_back(Err(e.duplicate())),
+                        None => tee.finished = true,
+                    }
+                } else if item.is_none() {
+                    tee.finished = true;
+                }
+                if let Some(waker) = tee.wakers[other].take() {
+                    waker.wake();
+                }
+                Poll::Ready(item)
+            }
+            Poll::Pending => {
+                // `inner` only wakes its most recent poller; that half
+                // forwards to this one when it queues an event.
+                tee.wakers[side] = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for TeeHalf {
+    fn drop(&mut self) {
+        let mut tee = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        tee.open[self.side] = false;
+        tee.queues[self.side].clear();
+        // This half may hold `inner`'s only waker registration; have the
+        // survivor poll `inner` itself.
+        if let Some(waker) = tee.wakers[1 - self.side].take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Stream for Response {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.stream.size_hint()
+    }
+}
+
+/// Push the events streaming `part` at `index`. Returns `false`, having
+/// pushed nothing, for parts without a streamed form.
+fn push_part_events(
+    out: &mut Vec<Result<StreamEvent, Error>>,
+    index: u32,
+    part: AssistantPart,
+) -> bool {
+    let mut push = |event| out.push(Ok(event));
+    let (kind, delta, updates) = match part {
+        AssistantPart::Text {
+            content,
+            annotations,
+        } => (
+            PartKind::Text,
+            content,
+            annotations
+                .into_iter()
+                .map(PartUpdate::Annotation)
+                .collect(),
+        ),
+        AssistantPart::Reasoning { content, signature } => (
+            PartKind::Reasoning,
+            content,
+            signature.into_iter().map(PartUpdate::Signature).collect(),
+        ),
+        AssistantPart::RedactedReasoning { data } => (
+            PartKind::RedactedReasoning { data },
+            String::new(),
+            Vec::new(),
+        ),
+        AssistantPart::Refusal(content) => (PartKind::Refusal, content, Vec::new()),
+        AssistantPart::ToolCall(call) => (
+            PartKind::ToolCall {
+                call_id: call.call_id,
+                name: call.name,
+            },
+            call.arguments,
+            call.provider_signature
+                .into_iter()
+                .map(PartUpdate::Signature)
+                .chain(call.original_arguments.map(PartUpdate::OriginalArguments))
+                .collect(),
+        ),
+        AssistantPart::BuiltinToolCall {
+            kind,
+            arguments,
+            result,
+        } => (
+            PartKind::BuiltinToolCall { kind },
+            arguments,
+            result
+                .into_iter()
+                .map(PartUpdate::BuiltinToolResult)
+                .collect(),
+        ),
+        AssistantPart::Continuation(continuation) => (
+            PartKind::Continuation(continuation),
+            String::new(),
+            Vec::new(),
+        ),
+        AssistantPart::CacheBreakpoint => return false,
+    };
+    push(StreamEvent::PartStart { index, kind });
+    if !delta.is_empty() {
+        push(StreamEvent::Delta { index, delta });
+    }
+    for update in updates {
+        push(StreamEvent::PartUpdate { index, update });
+    }
+    push(StreamEvent::PartEnd { index });
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,6 +633,188 @@ mod tests {
         assert_eq!(text, "Test response");
     }
 
+    #[test]
+    fn complete_response_round_trips_through_json() {
+        let response = CompleteResponse {
+            content: vec![
+                AssistantPart::Text {
+                    content: "Checking.".to_string(),
+                    annotations: Vec::new(),
+                },
+                AssistantPart::ToolCall(FunctionCall {
+                    call_id: "c1".to_string(),
+                    name: "lookup".to_string(),
+                    arguments: r#"{"q":"x"}"#.to_string(),
+                    provider_signature: None,
+                    original_arguments: None,
+                }),
+            ],
+            finish_reason: FinishReason::Other("pause_turn".to_string()),
+            usage: Usage {
+                input_tokens: 12,
+                output_tokens: 5,
+                ..Usage::default()
+            },
+            safety: None,
+            timing: None,
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        let restored: CompleteResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.text(), "Checking.");
+        assert_eq!(restored.function_calls(), response.function_calls());
+        assert_eq!(restored.finish_reason, response.finish_reason);
+        assert_eq!(restored.usage, response.usage);
+
+        let prompt = crate::Prompt::user("look it up").with_response(&restored);
+        assert_eq!(prompt.items().len(), 2);
+    }
+
+    #[test]
+    fn refusal_joins_refusal_parts() {
+        let mut response = CompleteResponse {
+            content: vec![AssistantPart::Text {
+                content: "Sure".to_string(),
+                annotations: Vec::new(),
+            }],
+            finish_reason: FinishReason::Stop,
+            usage: Usage::default(),
+            safety: None,
+            timing: None,
+        };
+        assert_eq!(response.refusal(), None);
+        response.content.extend([
+            AssistantPart::Refusal("I can't ".to_string()),
+            AssistantPart::Refusal("do that.".to_string()),
+        ]);
+        assert_eq!(response.refusal().as_deref(), Some("I can't do that."));
+    }
+
+    #[test]
+    fn sources_dedupes_annotations_across_text_parts() {
+        let cite = |source: &str, start| crate::types::Annotation {
+            kind: crate::types::AnnotationKind::UrlCitation,
+            start,
+            end: start + 5,
+            source: source.to_string(),
+            title: None,
+            snippet: None,
+        };
+        let response = CompleteResponse {
+            content: vec![
+                AssistantPart::Text {
+                    content: "First. Second.".to_string(),
+                    annotations: vec![cite("https://a", 0), cite("https://b", 7)],
+                },
+                AssistantPart::Text {
+                    content: "Third.".to_string(),
+                    annotations: vec![cite("https://a", 0)],
+                },
+            ],
+            finish_reason: FinishReason::Stop,
+            usage: Usage::default(),
+            safety: None,
+            timing: None,
+        };
+        assert_eq!(response.annotations().len(), 3);
+        let sources: Vec<_> = response
+            .sources()
+            .into_iter()
+            .map(|a| a.source.as_str())
+            .collect();
+        assert_eq!(sources, ["https://a", "https://b"]);
+    }
+
+    #[tokio::test]
+    async fn response_is_a_stream() {
+        use futures_util::StreamExt;
+        let events: Vec<Result<StreamEvent, Error>> = vec![
+            Ok(StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::Text,
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "a".to_string(),
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "b".to_string(),
+            }),
+            Ok(StreamEvent::PartEnd { index: 0 }),
+        ];
+        let deltas: Vec<String> = Response::from_stream(futures_util::stream::iter(events))
+            .filter_map(|event| async move {
+                match event {
+                    Ok(StreamEvent::Delta { delta, .. }) => Some(delta),
+                    _ => None,
+                }
+            })
+            .collect()
+            .await;
+        assert_eq!(deltas, ["a", "b"]);
+    }
+
+    /// A stream that yields `Pending` between events, so tee halves
+    /// really interleave.
+    fn yielding(events: Vec<Result<StreamEvent, Error>>) -> Response {
+        use futures_util::StreamExt;
+        Response::from_stream(futures_util::stream::iter(events).then(|event| async {
+            tokio::task::yield_now().await;
+            event
+        }))
+    }
+
+    fn hello() -> Vec<Result<StreamEvent, Error>> {
+        vec![
+            Ok(StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::Text,
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "hel".to_string(),
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "lo".to_string(),
+            }),
+            Ok(StreamEvent::PartEnd { index: 0 }),
+            Ok(StreamEvent::Done {
+                finish_reason: FinishReason::Stop,
+                usage: Usage::default(),
+            }),
+        ]
+    }
+
+    #[tokio::test]
+    async fn tee_halves_each_see_every_event() {
+        let mut response = yielding(hello());
+        response.metadata_mut().served_by = Some("primary".to_string());
+        let (live, persisted) = response.tee();
+        assert_eq!(persisted.metadata().served_by.as_deref(), Some("primary"));
+        let (events, complete) = tokio::join!(live.collect(), persisted.buffer());
+        assert_eq!(events.unwrap().0.len(), 5);
+        assert_eq!(complete.unwrap().text(), "hello");
+    }
+
+    #[tokio::test]
+    async fn tee_half_survives_its_sibling_being_dropped() {
+        use futures_util::StreamExt;
+        let (mut first, second) = yielding(hello()).tee();
+        first.next().await.unwrap().unwrap();
+        drop(first);
+        assert_eq!(second.text().await.unwrap(), "hello");
+    }
+
+    #[tokio::test]
+    async fn tee_copies_errors_to_both_halves() {
+        let (first, second) = yielding(vec![Err(Error::rate_limit(Some(3), "slow down"))]).tee();
+        for half in [first, second] {
+            let err = half.buffer().await.unwrap_err();
+            assert_eq!(err.retry_after(), Some(Duration::from_secs(3)));
+        }
+    }
+
     /// A mid-stream `Err` must propagate out of `buffer` and discard
     /// any events that arrive after it — including a `Done`. Without
     /// the short-circuit, a malformed provider that emitted both an
@@ -239,6 +853,95 @@ mod tests {
         assert!(err.to_string().contains("connection reset"));
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn buffer_records_time_to_first_token_and_duration() {
+        use futures_util::StreamExt;
+        let events = vec![
+            (
+                Duration::from_millis(300),
+                StreamEvent::PartStart {
+                    index: 0,
+                    kind: PartKind::Text,
+                },
+            ),
+            (
+                Duration::from_millis(200),
+                StreamEvent::Delta {
+                    index: 0,
+                    delta: "hi".to_string(),
+                },
+            ),
+            (Duration::ZERO, StreamEvent::PartEnd { index: 0 }),
+            (
+                Duration::from_millis(100),
+                StreamEvent::Done {
+                    finish_reason: FinishReason::Stop,
+                    usage: Usage::default(),
+                },
+            ),
+        ];
+        let stream = futures_util::stream::iter(events).then(|(delay, event)| async move {
+            tokio::time::sleep(delay).await;
+            Ok(event)
+        });
+        let response = Response::from_stream(stream)
+            .map_stream(|s| s)
+            .buffer()
+            .await
+            .unwrap();
+        let timing = response.timing.expect("timing recorded");
+        assert_eq!(timing.time_to_first_token, Some(Duration::from_millis(300)));
+        assert_eq!(timing.duration, Duration::from_millis(600));
+    }
+
+    #[tokio::test]
+    async fn from_complete_replays_the_turn() {
+        let complete = CompleteResponse {
+            content: vec![
+                AssistantPart::Reasoning {
+                    content: "think".into(),
+                    signature: Some("sig".into()),
+                },
+                AssistantPart::CacheBreakpoint,
+                AssistantPart::Text {
+                    content: "hi".into(),
+                    annotations: Vec::new(),
+                },
+                AssistantPart::ToolCall(FunctionCall {
+                    call_id: "c1".into(),
+                    name: "f".into(),
+                    arguments: "{}".into(),
+                    provider_signature: None,
+                    original_arguments: Some("{,}".into()),
+                }),
+            ],
+            finish_reason: FinishReason::ToolCalls,
+            usage: Usage::default(),
+            safety: Some(SafetyFeedback {
+                block_reason: Some("content_filter".into()),
+                ..SafetyFeedback::default()
+            }),
+            timing: None,
+        };
+        let replayed = Response::from_complete(complete.clone())
+            .buffer()
+            .await
+            .unwrap();
+        let mut expected = complete.content;
+        expected.remove(1);
+        assert_eq!(format!("{:?}", replayed.content), format!("{expected:?}"));
+        assert_eq!(replayed.finish_reason, FinishReason::ToolCalls);
+        assert_eq!(replayed.safety, complete.safety);
+    }
+
+    #[tokio::test]
+    async fn map_stream_keeps_metadata() {
+        let mut response = Response::from_stream(futures_util::stream::empty());
+        response.metadata_mut().served_by = Some("primary".into());
+        let mapped = response.map_stream(|s| s);
+        assert_eq!(mapped.metadata().served_by.as_deref(), Some("primary"));
+    }
+
     #[test]
     fn was_truncated_reports_length_finish_reason() {
         let empty_text = AssistantPart::Text {
@@ -249,6 +952,8 @@ mod tests {
             content: vec![empty_text.clone()],
             finish_reason: FinishReason::Length,
             usage: Usage::default(),
+            safety: None,
+            timing: None,
         };
         assert!(truncated.was_truncated());
 
@@ -261,6 +966,8 @@ mod tests {
                 content: vec![empty_text.clone()],
                 finish_reason: reason,
                 usage: Usage::default(),
+                safety: None,
+                timing: None,
             };
             assert!(
                 !r.was_truncated(),
@@ -280,6 +987,29 @@ mod tests {
         assert_eq!(Usage::default().total_tokens(), 0);
     }
 
+    #[test]
+    fn usage_total_tokens_prefers_the_reported_total() {
+        let usage = Usage {
+            input_tokens: 100,
+            output_tokens: 50,
+            total_tokens: Some(170),
+            ..Usage::default()
+        };
+        assert_eq!(usage.total_tokens(), 170);
+    }
+
+    #[test]
+    fn uncached_input_excludes_cache_reads_and_writes() {
+        let usage = Usage {
+            input_tokens: 1_000,
+            cache_read_input_tokens: Some(600),
+            cache_creation_input_tokens: Some(300),
+            ..Usage::default()
+        };
+        assert_eq!(usage.uncached_input_tokens(), 100);
+        assert_eq!(Usage::default().uncached_input_tokens(), 0);
+    }
+
     #[test]
     fn text_concatenates_across_parts() {
         let response = CompleteResponse {
@@ -295,6 +1025,8 @@ mod tests {
             ],
             finish_reason: FinishReason::Stop,
             usage: Usage::default(),
+            safety: None,
+            timing: None,
         };
         assert_eq!(response.text(), "Hello, world!");
     }
@@ -344,11 +1076,13 @@ mod tests {
             ],
             finish_reason: FinishReason::Stop,
             usage: Usage::default(),
+            safety: None,
+            timing: None,
         };
         let items = response.to_items();
         assert_eq!(items.len(), 1);
         match &items[0] {
-            InputItem::Assistant { content } => {
+            InputItem::Assistant { content, .. } => {
                 assert_eq!(content.len(), 2);
                 assert!(matches!(&content[0], AssistantPart::Text { .. }));
                 match &content[1] {
@@ -376,16 +1110,20 @@ mod tests {
                     name: "get_weather".to_string(),
                     arguments: "{}".to_string(),
                     provider_signature: None,
+                    original_arguments: None,
                 }),
                 AssistantPart::ToolCall(FunctionCall {
                     call_id: "call_2".to_string(),
                     name: "get_news".to_string(),
                     arguments: "{}".to_string(),
                     provider_signature: None,
+                    original_arguments: None,
                 }),
             ],
             finish_reason: FinishReason::ToolCalls,
             usage: Usage::default(),
+            safety: None,
+            timing: None,
         };
         let calls = response.function_calls();
         assert_eq!(calls.len(), 2);
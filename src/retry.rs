@@ -22,6 +22,13 @@
 //!   write anyway; the only addition is the wrapping call. See the
 //!   `debug_streaming` and `mock_provider` examples for the buffered
 //!   and streaming shapes side-by-side.
+//! - [`RetryingProvider`] / [`RetryLayer`] — the same loop packaged as
+//!   a [`crate::layer::ProviderLayer`], for callers who want every
+//!   `generate` on a provider retried without touching call sites.
+//!   It re-issues failed `generate` calls *and* streams whose very
+//!   first event is an error (an SSE `error` frame ahead of any
+//!   content); once an event has been handed to the caller the stream
+//!   is passed through untouched, for the stitching reason below.
 //!
 //! # What gets retried
 //!
@@ -47,6 +54,21 @@
 //! Every retry is a fresh request; the helper does not attempt to
 //! "resume" a partially-streamed response.
 //!
+//! # Idempotency keys
+//!
+//! A failed attempt may still have reached the provider — a
+//! connection that drops after the request was sent leaves the model
+//! call (and its billing, and whatever a tool-triggering response goes
+//! on to do) in flight. [`RawConfig::idempotency_key`] lets providers
+//! that support one (OpenAI's `Idempotency-Key` header) recognise the
+//! retry as the same request. [`RetryingProvider`] generates a key for
+//! each call that doesn't carry one and reuses it on every attempt;
+//! callers driving [`retry()`] or their own loop should set one from
+//! [`idempotency_key()`](crate::retry::idempotency_key) before the first attempt.
+//! Retry can sit outside a hedge, fallback or router: those derive a
+//! distinct key per backend from the caller's, so the key only ever
+//! deduplicates repeats of the same upstream request.
+//!
 //! If you're streaming directly to a user and the first attempt
 //! emitted some tokens before failing, retrying will produce
 //! different output that won't stitch with what you already showed.
@@ -69,9 +91,12 @@
 //! [`crate::Compactor`] (see the `auto_compaction` example), not
 //! retried blindly.
 
+use std::borrow::Cow;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::Error;
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response};
 
 /// Knobs governing the retry loop. Construct with
 /// [`RetryPolicy::standard`] for sensible defaults, or build manually
@@ -175,7 +200,19 @@ impl RetryPolicy {
         // and would compute `multiplier ** -1 = 1 / multiplier`,
         // returning a delay even when `RetryPolicy::none()` was
         // explicitly chosen. Treat it as terminal.
-        if attempt == 0 || !err.is_retryable() || attempt >= self.max_attempts {
+        if !err.is_retryable() {
+            return None;
+        }
+        self.schedule(err, attempt)
+    }
+
+    /// [`Self::delay_after`] minus the [`Error::is_retryable`] gate —
+    /// the attempt-budget check plus the hint / exponential schedule.
+    /// Split out so [`RetryingProvider`] can substitute a caller-supplied
+    /// classifier for the built-in one without re-deriving the delay
+    /// arithmetic.
+    fn schedule(&self, err: &Error, attempt: u32) -> Option<Duration> {
+        if attempt == 0 || attempt >= self.max_attempts {
             return None;
         }
         let raw = match err.retry_after() {
@@ -225,6 +262,32 @@ fn apply_jitter(delay: Duration, jitter: f64) -> Duration {
     Duration::try_from_secs_f64(delay.as_secs_f64() * factor).unwrap_or(delay)
 }
 
+/// A fresh idempotency key: a random (v4) UUID as 32 hex digits. Set
+/// it as [`RawConfig::idempotency_key`] once per logical request —
+/// before the first attempt, not inside a [`retry()`] closure — so
+/// every attempt carries the same key. [`RetryingProvider`] does this
+/// for calls that don't set one.
+pub fn idempotency_key() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// `config` as sent to upstream target `target` of a wrapper that
+/// spreads one call over several backends ([`crate::hedge`],
+/// [`crate::fallback`], [`crate::router`]). Target 0 keeps the
+/// caller's idempotency key; every other target gets a key derived
+/// from it, so two backends (or a hedge and its primary racing on the
+/// same one) never share a key, while a retried call still reaches
+/// each target with the key it sent there before.
+pub(crate) fn key_for_target(config: &RawConfig, target: usize) -> Cow<'_, RawConfig> {
+    match &config.idempotency_key {
+        Some(key) if target > 0 => Cow::Owned(RawConfig {
+            idempotency_key: Some(format!("{key}-{target}")),
+            ..config.clone()
+        }),
+        _ => Cow::Borrowed(config),
+    }
+}
+
 /// A tiny splitmix64-style RNG seeded once per-thread from the
 /// system clock. Jitter doesn't need cryptographic strength — only
 /// "different clients pick different waits" — so an inline RNG is
@@ -278,6 +341,10 @@ impl Default for RetryPolicy {
 /// streaming caller might erase what it printed before retrying), or
 /// for tracing.
 ///
+/// The closure should reuse one [`RawConfig::idempotency_key`] across
+/// attempts — set it before calling [`retry()`], not inside the
+/// closure — so providers that deduplicate on it see one request.
+///
 /// Returns the closure's `Ok` value, or the last observed `Err` once
 /// the policy gives up. See the `debug_streaming` and `mock_provider`
 /// examples for end-to-end uses.
@@ -314,6 +381,203 @@ where
     }
 }
 
+/// Predicate deciding whether an error is worth another attempt.
+/// Replaces [`Error::is_retryable`] on a [`RetryingProvider`].
+pub type RetryClassifier = Arc<dyn Fn(&Error) -> bool + Send + Sync>;
+
+/// A [`Provider`] that re-issues failed calls on its inner provider
+/// according to a [`RetryPolicy`].
+///
+/// A call is retried when either
+///
+/// - the inner `generate` returns `Err` (HTTP status errors, connect
+///   failures, rate-limiter rejections), or
+/// - the returned stream's *first* item is an `Err` — nothing has
+///   reached the caller yet, so a fresh request is indistinguishable
+///   from the first one having succeeded.
+///
+/// Failures after the first event are surfaced as-is: the caller may
+/// already have rendered partial output, and a retry would produce a
+/// different completion that doesn't stitch onto it. Wrap the
+/// consuming code in [`retry()`] if whole-response retries are what
+/// you want.
+///
+/// By default the retry decision is [`Error::is_retryable`]; swap in a
+/// narrower or broader predicate with [`Self::with_classifier`] (e.g.
+/// to stop retrying 5xx on a provider that reports deterministic
+/// failures as 500s). The classifier only gates *whether* to retry —
+/// the delay still follows the policy, including any
+/// [`Error::retry_after`] hint.
+#[derive(Clone)]
+pub struct RetryingProvider {
+    inner: SharedProvider,
+    policy: RetryPolicy,
+    classifier: Option<RetryClassifier>,
+}
+
+impl RetryingProvider {
+    /// Wrap `inner` with `policy`, classifying errors via
+    /// [`Error::is_retryable`].
+    pub fn new(inner: impl Provider, policy: RetryPolicy) -> Self {
+        Self::from_shared(Arc::new(inner), policy)
+    }
+
+    /// Wrap an already-shared provider (e.g. a converted
+    /// `Box<dyn Provider>` from [`crate::ProviderFactory`]).
+    pub fn from_shared(inner: SharedProvider, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            classifier: None,
+        }
+    }
+
+    /// Replace the default [`Error::is_retryable`] classification.
+    pub fn with_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// The policy this wrapper retries under.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    fn delay_after(&self, err: &Error, attempt: u32) -> Option<Duration> {
+        match &self.classifier {
+            Some(classify) if classify(err) => self.policy.schedule(err, attempt),
+            Some(_) => None,
+            None => self.policy.delay_after(err, attempt),
+        }
+    }
+}
+
+impl std::fmt::Debug for RetryingProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryingProvider")
+            .field("policy", &self.policy)
+            .field("custom_classifier", &self.classifier.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for RetryingProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        // Every attempt carries the same key, so a provider that saw
+        // an attempt the caller never heard back from won't act twice.
+        let keyed;
+        let config = match config.idempotency_key {
+            Some(_) => config,
+            None => {
+                keyed = RawConfig {
+                    idempotency_key: Some(idempotency_key()),
+                    ..config.clone()
+                };
+                &keyed
+            }
+        };
+        let mut attempt: u32 = 0;
+        loop {
+            attempt = attempt.saturating_add(1);
+            let err = match self.inner.generate(prompt, config).await {
+                Ok(response) => match peek_first(response).await {
+                    Ok(response) => return Ok(response),
+                    Err(err) => err,
+                },
+                Err(err) => err,
+            };
+            match self.delay_after(&err, attempt) {
+                Some(delay) => {
+                    tracing::warn!(
+                        attempt,
+                        max_attempts = self.policy.max_attempts,
+                        delay_ms = delay.as_millis() as u64,
+                        model = %config.model,
+                        error = %err,
+                        "retrying provider call after transient failure",
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                None => return Err(err),
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// Pull the first item off `response`. An `Err` first item becomes
+/// the call's error; anything else is pushed back in front of the
+/// remaining stream so the caller sees the response unchanged —
+/// metadata and request start included.
+pub(crate) async fn peek_first(mut response: Response) -> Result<Response, Error> {
+    use futures_util::StreamExt;
+    match response.next().await {
+        Some(Err(err)) => Err(err),
+        Some(Ok(first)) => Ok(response
+            .map_stream(|rest| futures_util::stream::once(async move { Ok(first) }).chain(rest))),
+        None => Ok(response),
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in a [`RetryingProvider`].
+/// Place it *inside* layers that should see one logical call (metrics,
+/// logging) and *outside* ones that should see every attempt (rate
+/// limiting).
+#[derive(Clone, Default)]
+pub struct RetryLayer {
+    policy: RetryPolicy,
+    classifier: Option<RetryClassifier>,
+}
+
+impl RetryLayer {
+    /// A layer retrying under `policy`.
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy,
+            classifier: None,
+        }
+    }
+
+    /// See [`RetryingProvider::with_classifier`].
+    pub fn with_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+}
+
+impl std::fmt::Debug for RetryLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryLayer")
+            .field("policy", &self.policy)
+            .field("custom_classifier", &self.classifier.is_some())
+            .finish()
+    }
+}
+
+impl ProviderLayer for RetryLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(RetryingProvider {
+            inner,
+            policy: self.policy,
+            classifier: self.classifier.clone(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,7 +592,7 @@ mod tests {
             .delay_after(&Error::invalid_prompt("bad"), 1)
             .is_none());
         assert!(policy
-            .delay_after(&Error::ModelNotAvailable("gpt-x".into()), 1)
+            .delay_after(&Error::model_not_available("gpt-x"), 1)
             .is_none());
         assert!(policy
             .delay_after(&Error::context_window_exceeded("OpenAI", "big"), 1)
@@ -617,6 +881,22 @@ mod tests {
         );
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn peek_keeps_the_request_start() {
+        use crate::providers::mock::MockProvider;
+        let response = MockProvider::with_text("hi")
+            .generate(
+                &crate::Prompt::user("x"),
+                crate::Config::builder("m").build().raw(),
+            )
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let complete = peek_first(response).await.unwrap().buffer().await.unwrap();
+        let timing = complete.timing.expect("timing recorded");
+        assert_eq!(timing.duration, Duration::from_millis(200));
+    }
+
     #[tokio::test]
     async fn retry_returns_after_transient_failures() {
         let policy = fast_policy();
@@ -664,6 +944,164 @@ mod tests {
         assert_eq!(count.get(), 3);
     }
 
+    fn mock_cfg() -> RawConfig {
+        crate::Config::builder("m").build().raw().clone()
+    }
+
+    #[tokio::test]
+    async fn retrying_provider_reissues_failed_generate() {
+        use crate::providers::mock::MockProvider;
+        let mock = MockProvider::builder()
+            .fail(Error::rate_limit(None, "slow"))
+            .fail(Error::provider_with_status("MockProvider", 503, "down"))
+            .reply("ok")
+            .build();
+        let log = mock.call_log();
+        let provider = RetryingProvider::new(mock, fast_policy());
+        let text = provider
+            .generate(&Prompt::user("x"), &mock_cfg())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(text, "ok");
+        assert_eq!(log.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn retrying_provider_keeps_one_idempotency_key_across_attempts() {
+        use crate::providers::mock::MockProvider;
+        let mock = MockProvider::builder()
+            .fail(Error::provider_with_status("MockProvider", 503, "down"))
+            .reply("ok")
+            .reply("ok")
+            .build();
+        let log = mock.call_log();
+        let provider = RetryingProvider::new(mock, fast_policy());
+        provider
+            .generate(&Prompt::user("x"), &mock_cfg())
+            .await
+            .unwrap();
+        provider
+            .generate(&Prompt::user("x"), &mock_cfg())
+            .await
+            .unwrap();
+
+        let keys: Vec<_> = log
+            .calls()
+            .into_iter()
+            .map(|call| {
+                call.config
+                    .idempotency_key
+                    .expect("retried calls carry a key")
+            })
+            .collect();
+        assert_eq!(keys[0], keys[1], "attempts of one call share a key");
+        assert_ne!(keys[1], keys[2], "separate calls get separate keys");
+        assert_eq!(keys[0].len(), 32);
+    }
+
+    /// A stream whose first item is an error never reached the caller,
+    /// so it's retried like a `generate`-level failure.
+    #[tokio::test]
+    async fn retrying_provider_retries_error_before_first_event() {
+        use crate::providers::mock::{MockProvider, MockResponse};
+        use crate::types::FinishReason;
+        let mock = MockProvider::builder()
+            .reply(
+                MockResponse::from_parts(Vec::new(), FinishReason::Stop)
+                    .with_stream_error(Error::rate_limit(Some(0), "overloaded")),
+            )
+            .reply("recovered")
+            .build();
+        let log = mock.call_log();
+        let provider = RetryingProvider::new(mock, fast_policy());
+        let text = provider
+            .generate(&Prompt::user("x"), &mock_cfg())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(text, "recovered");
+        assert_eq!(log.len(), 2);
+    }
+
+    /// Once content has been emitted a retry can't be stitched onto
+    /// it — the mid-stream error must reach the caller.
+    #[tokio::test]
+    async fn retrying_provider_passes_mid_stream_error_through() {
+        use crate::providers::mock::{MockProvider, MockResponse};
+        let mock = MockProvider::builder()
+            .reply(MockResponse::text("partial").with_stream_error(Error::rate_limit(None, "x")))
+            .reply("never")
+            .build();
+        let log = mock.call_log();
+        let provider = RetryingProvider::new(mock, fast_policy());
+        let err = provider
+            .generate(&Prompt::user("x"), &mock_cfg())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .expect_err("mid-stream error must surface");
+        assert!(matches!(err, Error::RateLimit { .. }));
+        assert_eq!(log.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retrying_provider_classifier_overrides_default() {
+        use crate::providers::mock::MockProvider;
+        // 503 is retryable by default; the classifier vetoes it.
+        let mock = MockProvider::builder()
+            .fail(Error::provider_with_status("MockProvider", 503, "down"))
+            .reply("never")
+            .build();
+        let log = mock.call_log();
+        let provider = RetryingProvider::new(mock, fast_policy())
+            .with_classifier(|e| matches!(e, Error::RateLimit { .. }));
+        let Err(err) = provider.generate(&Prompt::user("x"), &mock_cfg()).await else {
+            panic!("classifier must veto the retry");
+        };
+        assert!(matches!(err, Error::Provider { .. }));
+        assert_eq!(log.len(), 1);
+
+        // ...and can widen it: `Config` errors are terminal by default.
+        let mock = MockProvider::builder()
+            .fail(Error::config("flaky"))
+            .reply("ok")
+            .build();
+        let provider = RetryingProvider::new(mock, fast_policy()).with_classifier(|_| true);
+        assert!(provider
+            .generate(&Prompt::user("x"), &mock_cfg())
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn retry_layer_wraps_via_stack() {
+        use crate::providers::mock::MockProvider;
+        let mock = MockProvider::builder()
+            .fail(Error::rate_limit(None, "slow"))
+            .reply("ok")
+            .build();
+        let provider = crate::ProviderStack::new()
+            .layer(RetryLayer::new(fast_policy()))
+            .service(mock);
+        let text = crate::generate(
+            &*provider,
+            &Prompt::user("x"),
+            &crate::Config::builder("m").build(),
+        )
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+        assert_eq!(text, "ok");
+    }
+
     #[tokio::test]
     async fn retry_passes_attempt_number_to_closure() {
         let policy = fast_policy();
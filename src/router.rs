@@ -0,0 +1,511 @@
+//! Load spreading across interchangeable backends.
+//!
+//! [`RouterProvider`] distributes calls over several backends that can
+//! all serve the same request — two OpenAI keys in different orgs, an
+//! OpenAI deployment plus an Azure one, the same Gemini model in two
+//! Vertex projects — so their quotas pool and one key's 429s don't stall
+//! the whole workload.
+//!
+//! ```ignore
+//! use platformed_llm::router::{RouterProvider, RoutingStrategy};
+//!
+//! let provider = RouterProvider::new(RoutingStrategy::Weighted)
+//!     .with_backend("openai-a", openai_a, 2)
+//!     .with_backend("openai-b", openai_b, 1)
+//!     .with_backend_model("vertex", google, "gemini-2.5-flash", 1);
+//! ```
+//!
+//! # Health tracking
+//!
+//! Each backend carries a small circuit breaker. A backend whose calls
+//! fail with a *retryable* error ([`Error::is_retryable`] — 429s, 5xx,
+//! connection drops) `failure_threshold` times in a row is ejected
+//! from rotation for `cooldown`; a 429 carrying a `Retry-After` ejects
+//! it for at least that long, immediately. Non-retryable errors (bad
+//! prompt, context overflow) are the request's fault, not the
+//! backend's, and don't count. A call that runs to `Done` resets the
+//! counter.
+//!
+//! When every backend is ejected the router doesn't fail the call: it
+//! picks the one whose cooldown ends first, so a full outage degrades
+//! to "slow" rather than "down" and recovery is probed naturally.
+//!
+//! # One backend per call
+//!
+//! The router picks once per `generate`; it does not re-dispatch a
+//! failed call itself. Wrap it in a [`crate::RetryingProvider`] (or add
+//! a [`crate::RetryLayer`] outside it) and each retry re-enters the
+//! router and lands on the next healthy backend — the failed one has
+//! just been penalised. For a strict primary-then-secondary order use
+//! [`crate::FallbackProvider`] instead.
+//!
+//! As with [`crate::FallbackProvider`], middleware runs once against
+//! the *first* backend's capabilities, so route only across backends
+//! with comparable capabilities.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::time::Instant;
+
+use crate::layer::SharedProvider;
+use crate::retry::key_for_target;
+use crate::{Capabilities, Error, Prompt, Provider, RawConfig, Response, StreamEvent};
+
+/// How [`RouterProvider`] picks the next backend among the healthy ones.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoutingStrategy {
+    /// Cycle through healthy backends in order, ignoring weights.
+    #[default]
+    RoundRobin,
+    /// Smooth weighted round-robin (the nginx algorithm): a backend
+    /// with weight 3 serves three of every four calls against a
+    /// weight-1 peer, interleaved rather than in bursts. Deterministic
+    /// — no RNG — so tests and replays see the same order.
+    Weighted,
+}
+
+/// Point-in-time health of one [`RouterProvider`] backend, from
+/// [`RouterProvider::health`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendHealth {
+    /// The backend's label.
+    pub label: String,
+    /// `false` while the backend is ejected from rotation.
+    pub healthy: bool,
+    /// Retryable failures since the last successful call.
+    pub consecutive_failures: u32,
+    /// Time left in the current ejection, if ejected.
+    pub ejected_for: Option<Duration>,
+}
+
+struct Backend {
+    label: String,
+    provider: SharedProvider,
+    model: Option<String>,
+    weight: u32,
+}
+
+#[derive(Debug, Default, Clone)]
+struct BackendState {
+    /// Smooth-WRR running weight.
+    current_weight: i64,
+    consecutive_failures: u32,
+    ejected_until: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct RouterState {
+    backends: Vec<BackendState>,
+    next: usize,
+}
+
+/// A [`Provider`] that spreads calls across several backends. See the
+/// module docs.
+pub struct RouterProvider {
+    backends: Vec<Backend>,
+    strategy: RoutingStrategy,
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Arc<Mutex<RouterState>>,
+}
+
+impl RouterProvider {
+    /// An empty router using `strategy`. Defaults: a backend is
+    /// ejected after 3 consecutive retryable failures, for 30 seconds.
+    pub fn new(strategy: RoutingStrategy) -> Self {
+        Self {
+            backends: Vec::new(),
+            strategy,
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+            state: Arc::new(Mutex::new(RouterState::default())),
+        }
+    }
+
+    /// Add a backend that receives the caller's model unchanged.
+    /// `weight` only matters under [`RoutingStrategy::Weighted`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is zero — leave the backend out instead.
+    pub fn with_backend(
+        self,
+        label: impl Into<String>,
+        provider: impl Provider,
+        weight: u32,
+    ) -> Self {
+        self.push(label.into(), Arc::new(provider), None, weight)
+    }
+
+    /// Add a backend that is asked for `model` instead of the
+    /// caller's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is zero.
+    pub fn with_backend_model(
+        self,
+        label: impl Into<String>,
+        provider: impl Provider,
+        model: impl Into<String>,
+        weight: u32,
+    ) -> Self {
+        self.push(label.into(), Arc::new(provider), Some(model.into()), weight)
+    }
+
+    /// Add an already-shared backend. `model` as in
+    /// [`Self::with_backend_model`]; `None` keeps the caller's.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is zero.
+    pub fn with_shared_backend(
+        self,
+        label: impl Into<String>,
+        provider: SharedProvider,
+        model: Option<String>,
+        weight: u32,
+    ) -> Self {
+        self.push(label.into(), provider, model, weight)
+    }
+
+    /// Consecutive retryable failures before a backend is ejected.
+    /// Clamped to at least 1.
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// How long an ejected backend stays out of rotation (unless a
+    /// longer `Retry-After` was observed).
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Snapshot of every backend's health, in the order they were
+    /// added. Handy for a status endpoint or periodic metrics.
+    pub fn health(&self) -> Vec<BackendHealth> {
+        let state = self.lock();
+        let now = Instant::now();
+        self.backends
+            .iter()
+            .zip(&state.backends)
+            .map(|(backend, s)| {
+                let ejected_for = s
+                    .ejected_until
+                    .filter(|until| *until > now)
+                    .map(|until| until - now);
+                BackendHealth {
+                    label: backend.label.clone(),
+                    healthy: ejected_for.is_none(),
+                    consecutive_failures: s.consecutive_failures,
+                    ejected_for,
+                }
+            })
+            .collect()
+    }
+
+    fn push(
+        mut self,
+        label: String,
+        provider: SharedProvider,
+        model: Option<String>,
+        weight: u32,
+    ) -> Self {
+        assert!(weight > 0, "router backend `{label}` must have weight > 0");
+        self.backends.push(Backend {
+            label,
+            provider,
+            model,
+            weight,
+        });
+        self.lock().backends.push(BackendState::default());
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RouterState> {
+        // Every critical section leaves the state consistent, so a
+        // poisoned lock just means a panic elsewhere — keep routing.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Pick the backend for the next call.
+    fn select(&self) -> Option<usize> {
+        let mut state = self.lock();
+        let now = Instant::now();
+        let healthy: Vec<usize> = (0..self.backends.len())
+            .filter(|&i| state.backends[i].ejected_until.is_none_or(|t| t <= now))
+            .collect();
+        if healthy.is_empty() {
+            // Everyone is ejected: probe whoever comes back first.
+            return (0..self.backends.len()).min_by_key(|&i| state.backends[i].ejected_until);
+        }
+        Some(match self.strategy {
+            RoutingStrategy::RoundRobin => {
+                let start = state.next;
+                let pick = healthy
+                    .iter()
+                    .copied()
+                    .find(|&i| i >= start)
+                    .unwrap_or(healthy[0]);
+                state.next = pick + 1;
+                pick
+            }
+            RoutingStrategy::Weighted => {
+                let total: i64 = healthy
+                    .iter()
+                    .map(|&i| self.backends[i].weight as i64)
+                    .sum();
+                for &i in &healthy {
+                    state.backends[i].current_weight += self.backends[i].weight as i64;
+                }
+                let pick = healthy
+                    .iter()
+                    .copied()
+                    .max_by_key(|&i| (state.backends[i].current_weight, std::cmp::Reverse(i)))
+                    .expect("healthy is non-empty");
+                state.backends[pick].current_weight -= total;
+                pick
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for RouterProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let backends: Vec<_> = self
+            .backends
+            .iter()
+            .map(|b| (b.label.as_str(), b.model.as_deref(), b.weight))
+            .collect();
+        f.debug_struct("RouterProvider")
+            .field("strategy", &self.strategy)
+            .field("backends", &backends)
+            .field("failure_threshold", &self.failure_threshold)
+            .field("cooldown", &self.cooldown)
+            .finish()
+    }
+}
+
+/// Outcome reporting for one backend, shared with the response stream
+/// so success / failure is recorded when the stream actually ends.
+#[derive(Clone)]
+struct HealthReporter {
+    state: Arc<Mutex<RouterState>>,
+    index: usize,
+    label: String,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl HealthReporter {
+    fn success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let backend = &mut state.backends[self.index];
+        backend.consecutive_failures = 0;
+        backend.ejected_until = None;
+    }
+
+    fn failure(&self, err: &Error) {
+        if !err.is_retryable() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let backend = &mut state.backends[self.index];
+        backend.consecutive_failures = backend.consecutive_failures.saturating_add(1);
+        let now = Instant::now();
+        let mut until = None;
+        if backend.consecutive_failures >= self.failure_threshold {
+            until = Some(now + self.cooldown);
+        }
+        // A server-supplied backoff ejects immediately — routing more
+        // traffic at a backend that just said "wait 30s" only earns
+        // more 429s.
+        if let Some(hint) = err.retry_after().filter(|d| !d.is_zero()) {
+            until = Some(until.map_or(now + hint, |u: Instant| u.max(now + hint)));
+        }
+        if let Some(until) = until {
+            if backend.ejected_until.is_none_or(|prev| prev < until) {
+                tracing::warn!(
+                    backend = %self.label,
+                    consecutive_failures = backend.consecutive_failures,
+                    ejected_for_ms = (until - now).as_millis() as u64,
+                    error = %err,
+                    "router ejecting unhealthy backend",
+                );
+                backend.ejected_until = Some(until);
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for RouterProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let index = self
+            .select()
+            .ok_or_else(|| Error::config("RouterProvider has no backends"))?;
+        let backend = &self.backends[index];
+        let mut config = key_for_target(config, index);
+        if let Some(model) = &backend.model {
+            config.to_mut().model = model.clone();
+        }
+        let reporter = HealthReporter {
+            state: self.state.clone(),
+            index,
+            label: backend.label.clone(),
+            failure_threshold: self.failure_threshold,
+            cooldown: self.cooldown,
+        };
+        match backend.provider.generate(prompt, &config).await {
+            Ok(response) => {
+                let mut response = response.map_stream(move |stream| {
+                    stream.inspect(move |item| match item {
+                        Ok(StreamEvent::Done { .. }) => reporter.success(),
+                        Err(err) => reporter.failure(err),
+                        Ok(_) => {}
+                    })
+                });
+                let metadata = response.metadata_mut();
+                metadata.served_by = Some(backend.label.clone());
+                metadata.model = Some(config.model.clone());
+                Ok(response)
+            }
+            Err(err) => {
+                reporter.failure(&err);
+                Err(err)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self.backends.first() {
+            Some(first) => first.provider.name(),
+            None => "Router",
+        }
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        match self.backends.first() {
+            Some(first) => first
+                .provider
+                .capabilities(first.model.as_deref().unwrap_or(model)),
+            None => Capabilities::for_model(model),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::{generate, Config, FinishReason};
+
+    async fn served_by(router: &RouterProvider) -> Result<String, Error> {
+        let response = generate(router, &Prompt::user("x"), &Config::builder("m").build()).await?;
+        let label = response.metadata().served_by.clone().unwrap();
+        response.buffer().await?;
+        Ok(label)
+    }
+
+    #[tokio::test]
+    async fn round_robin_cycles_backends() {
+        let router = RouterProvider::new(RoutingStrategy::RoundRobin)
+            .with_backend("a", MockProvider::with_text("a"), 1)
+            .with_backend("b", MockProvider::with_text("b"), 5)
+            .with_backend("c", MockProvider::with_text("c"), 1);
+        let mut seen = Vec::new();
+        for _ in 0..6 {
+            seen.push(served_by(&router).await.unwrap());
+        }
+        assert_eq!(seen, ["a", "b", "c", "a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn weighted_interleaves_by_weight() {
+        let router = RouterProvider::new(RoutingStrategy::Weighted)
+            .with_backend("heavy", MockProvider::with_text("h"), 3)
+            .with_backend("light", MockProvider::with_text("l"), 1);
+        let mut seen = Vec::new();
+        for _ in 0..8 {
+            seen.push(served_by(&router).await.unwrap());
+        }
+        assert_eq!(seen.iter().filter(|s| *s == "heavy").count(), 6);
+        // Smooth WRR never sends the light backend two in a row.
+        assert!(!seen.windows(2).any(|w| w[0] == "light" && w[1] == "light"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failing_backend_is_ejected_then_recovers() {
+        let flaky = MockProvider::with_handler(|_, _| {
+            MockResponse::from_parts(Vec::new(), FinishReason::Stop)
+                .with_stream_error(Error::provider_with_status("Mock", 503, "down"))
+        });
+        let router = RouterProvider::new(RoutingStrategy::RoundRobin)
+            .with_backend("flaky", flaky, 1)
+            .with_backend("steady", MockProvider::with_text("ok"), 1)
+            .with_failure_threshold(2)
+            .with_cooldown(Duration::from_secs(10));
+
+        // flaky, steady, flaky → ejected after its second failure.
+        assert!(served_by(&router).await.is_err());
+        assert_eq!(served_by(&router).await.unwrap(), "steady");
+        assert!(served_by(&router).await.is_err());
+        assert!(!router.health()[0].healthy);
+        for _ in 0..4 {
+            assert_eq!(served_by(&router).await.unwrap(), "steady");
+        }
+
+        tokio::time::advance(Duration::from_secs(11)).await;
+        assert!(router.health()[0].healthy);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_after_ejects_immediately() {
+        let limited = MockProvider::builder()
+            .fail(Error::rate_limit(Some(20), "slow"))
+            .build();
+        let router = RouterProvider::new(RoutingStrategy::RoundRobin)
+            .with_backend("limited", limited, 1)
+            .with_backend("steady", MockProvider::with_text("ok"), 1);
+        assert!(served_by(&router).await.is_err());
+        let health = router.health();
+        assert!(!health[0].healthy, "a Retry-After must eject at once");
+        assert_eq!(health[0].ejected_for, Some(Duration::from_secs(20)));
+    }
+
+    #[tokio::test]
+    async fn non_retryable_errors_do_not_count() {
+        let router = RouterProvider::new(RoutingStrategy::RoundRobin)
+            .with_backend(
+                "bad-prompt",
+                MockProvider::builder()
+                    .fail(Error::invalid_prompt("nope"))
+                    .build(),
+                1,
+            )
+            .with_failure_threshold(1);
+        assert!(served_by(&router).await.is_err());
+        assert!(router.health()[0].healthy);
+        assert_eq!(router.health()[0].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn all_ejected_still_routes() {
+        let router = RouterProvider::new(RoutingStrategy::RoundRobin).with_backend(
+            "only",
+            MockProvider::builder()
+                .fail(Error::rate_limit(Some(60), "slow"))
+                .reply("back")
+                .build(),
+            1,
+        );
+        assert!(served_by(&router).await.is_err());
+        assert_eq!(served_by(&router).await.unwrap(), "only");
+    }
+}
@@ -0,0 +1,598 @@
+//! Embeddings-backed response cache for near-duplicate prompts.
+//!
+//! High-volume FAQ-style traffic asks the same handful of questions in
+//! endlessly varied wording — "how do I reset my password" / "password
+//! reset?" / "I forgot my password, what now". An exact-match cache
+//! misses all of those. [`SemanticCacheProvider`] embeds each prompt
+//! with a caller-supplied [`Embedder`] and, when a previous prompt's
+//! embedding is at least `threshold` cosine-similar, replays that
+//! prompt's response instead of calling the model.
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use platformed_llm::semantic_cache::SemanticCacheProvider;
+//!
+//! let provider = SemanticCacheProvider::new(openai, Arc::new(my_embedder))
+//!     .with_threshold(0.92)
+//!     .with_max_entries(10_000)
+//!     .with_ttl(Duration::from_secs(3600));
+//! ```
+//!
+//! # What is compared
+//!
+//! The embedded text is the whole conversation — every system, user
+//! and assistant turn, role-prefixed — so a follow-up question only
+//! matches a cached follow-up in a similar conversation. Similarity is
+//! only consulted between requests with the same *scope*: identical
+//! [`RawConfig`] (model, sampling, tools, response format,
+//! **tenant**) apart from [`RawConfig::priority`] and the per-call
+//! [`RawConfig::request_id`] / [`RawConfig::idempotency_key`].
+//! Tenants never see each other's cached answers; if every tenant may
+//! share, leave [`RawConfig::tenant`] unset.
+//!
+//! Prompts containing images, audio, documents or video bypass the
+//! cache entirely: the embedding would only see the text around them.
+//!
+//! # What is stored
+//!
+//! The response's events, recorded as they stream through to the first
+//! caller, are stored only once the stream reaches `Done` with
+//! [`FinishReason::Stop`] or [`FinishReason::ToolCalls`]. Errors,
+//! truncated (`Length`) and filtered answers are never cached. A hit
+//! replays the stored events verbatim and reports
+//! [`CACHE_LABEL`](crate::semantic_cache::CACHE_LABEL) in
+//! [`crate::ResponseMetadata::served_by`].
+//!
+//! # Scale
+//!
+//! Lookups are a linear scan over the in-memory entries, which is
+//! fine up to a few tens of thousands of entries per process. Beyond
+//! that, put a vector index behind your own [`crate::Provider`]
+//! wrapper. Embedding failures are logged and the request goes to the
+//! model uncached — the cache never turns a servable request into an
+//! error.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::time::Instant;
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{
+    AssistantPart, Capabilities, Error, FinishReason, InputItem, Prompt, Provider, RawConfig,
+    Response, StreamEvent, UserPart,
+};
+
+/// [`crate::ResponseMetadata::served_by`] value on a cache hit.
+pub const CACHE_LABEL: &str = "semantic-cache";
+
+/// Turns text into an embedding vector. Implement it over whatever
+/// embeddings endpoint you already use; vectors need not be
+/// normalised, but every call must return the same dimension.
+#[async_trait::async_trait]
+pub trait Embedder: Send + Sync {
+    /// Embed `text`.
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, Error>;
+
+    /// Embed several texts, one vector per text, in order. The default
+    /// calls [`Self::embed`] for each in turn; override it when the
+    /// endpoint accepts a list of inputs, so a
+    /// [`crate::batching::BatchingEmbedder`] in front of it can send
+    /// one request for many.
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Error> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for text in texts {
+            vectors.push(self.embed(text).await?);
+        }
+        Ok(vectors)
+    }
+}
+
+/// Shared handle to an [`Embedder`].
+pub type SharedEmbedder = Arc<dyn Embedder>;
+
+/// Hit / miss counters from [`SemanticCacheProvider::stats`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SemanticCacheStats {
+    /// Requests answered from the cache.
+    pub hits: u64,
+    /// Requests that were looked up and forwarded to the model.
+    pub misses: u64,
+    /// Requests that skipped the cache (non-text input, embedding
+    /// failure).
+    pub bypassed: u64,
+}
+
+struct Entry {
+    scope: String,
+    /// Unit-length embedding, so similarity is a dot product.
+    embedding: Arc<[f32]>,
+    events: Arc<[StreamEvent]>,
+    inserted: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+    /// Least recently used at the front.
+    entries: VecDeque<Entry>,
+    stats: SemanticCacheStats,
+}
+
+/// A [`Provider`] wrapper that answers near-duplicate prompts from a
+/// similarity cache. See the module docs.
+#[derive(Clone)]
+pub struct SemanticCacheProvider {
+    inner: SharedProvider,
+    embedder: SharedEmbedder,
+    threshold: f32,
+    max_entries: usize,
+    ttl: Option<Duration>,
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl SemanticCacheProvider {
+    /// Cache `inner`'s responses, comparing prompts with `embedder`.
+    /// Defaults: similarity threshold 0.95, at most 1024 entries, no
+    /// expiry.
+    pub fn new(inner: impl Provider, embedder: SharedEmbedder) -> Self {
+        Self::from_shared(Arc::new(inner), embedder)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, embedder: SharedEmbedder) -> Self {
+        Self {
+            inner,
+            embedder,
+            threshold: 0.95,
+            max_entries: 1024,
+            ttl: None,
+            state: Arc::new(Mutex::new(CacheState::default())),
+        }
+    }
+
+    /// Minimum cosine similarity (`-1.0..=1.0`) for a hit. Higher is
+    /// stricter; start around 0.95 and lower it while checking the
+    /// hits are still acceptable answers.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Cap the number of cached responses. The least recently used
+    /// entry is evicted first. Clamped to at least 1.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// Expire entries `ttl` after they were stored.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Number of cached responses (including any expired ones not yet
+    /// swept by a lookup).
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// `true` when nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cached response. Counters are kept.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Hit / miss counters since construction.
+    pub fn stats(&self) -> SemanticCacheStats {
+        self.lock().stats
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Best stored match for `embedding` in `scope`, if above the
+    /// threshold. Refreshes the entry's LRU position.
+    fn lookup(&self, scope: &str, embedding: &[f32]) -> Option<Arc<[StreamEvent]>> {
+        let mut state = self.lock();
+        if let Some(ttl) = self.ttl {
+            let now = Instant::now();
+            state
+                .entries
+                .retain(|entry| now.saturating_duration_since(entry.inserted) < ttl);
+        }
+        let (index, _) = state
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.scope == scope && entry.embedding.len() == embedding.len())
+            .map(|(i, entry)| (i, dot(&entry.embedding, embedding)))
+            .filter(|(_, similarity)| *similarity >= self.threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        let entry = state.entries.remove(index)?;
+        let events = entry.events.clone();
+        state.entries.push_back(entry);
+        Some(events)
+    }
+}
+
+impl std::fmt::Debug for SemanticCacheProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SemanticCacheProvider")
+            .field("threshold", &self.threshold)
+            .field("max_entries", &self.max_entries)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for SemanticCacheProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let Some(text) = embedding_text(prompt) else {
+            self.lock().stats.bypassed += 1;
+            return self.inner.generate(prompt, config).await;
+        };
+        let embedding = match self.embedder.embed(&text).await {
+            Ok(vector) => match normalize(vector) {
+                Some(unit) => unit,
+                None => {
+                    self.lock().stats.bypassed += 1;
+                    return self.inner.generate(prompt, config).await;
+                }
+            },
+            Err(err) => {
+                tracing::warn!(error = %err, "semantic cache embedding failed; bypassing cache");
+                self.lock().stats.bypassed += 1;
+                return self.inner.generate(prompt, config).await;
+            }
+        };
+        let scope = scope_key(config);
+
+        if let Some(events) = self.lookup(&scope, &embedding) {
+            self.lock().stats.hits += 1;
+            let mut response = Response::from_stream(futures_util::stream::iter(
+                events.iter().cloned().map(Ok).collect::<Vec<_>>(),
+            ));
+            let metadata = response.metadata_mut();
+            metadata.served_by = Some(CACHE_LABEL.to_string());
+            metadata.model = Some(config.model.clone());
+            return Ok(response);
+        }
+        self.lock().stats.misses += 1;
+
+        let response = self.inner.generate(prompt, config).await?;
+        let state = self.state.clone();
+        let max_entries = self.max_entries;
+        let embedding: Arc<[f32]> = embedding.into();
+        let mut recorded = Vec::new();
+        Ok(response.map_stream(move |stream| {
+            stream.inspect(move |item| match item {
+                Ok(event) => {
+                    recorded.push(event.clone());
+                    if let StreamEvent::Done { finish_reason, .. } = event {
+                        if matches!(finish_reason, FinishReason::Stop | FinishReason::ToolCalls) {
+                            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                            state.entries.push_back(Entry {
+                                scope: scope.clone(),
+                                embedding: embedding.clone(),
+                                events: std::mem::take(&mut recorded).into(),
+                                inserted: Instant::now(),
+                            });
+                            while state.entries.len() > max_entries {
+                                state.entries.pop_front();
+                            }
+                        }
+                    }
+                }
+                // Never cache a stream that failed part-way.
+                Err(_) => recorded.clear(),
+            })
+        }))
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in a [`SemanticCacheProvider`].
+/// Each wrapped provider gets its own cache.
+#[derive(Clone)]
+pub struct SemanticCacheLayer {
+    embedder: SharedEmbedder,
+    threshold: Option<f32>,
+    max_entries: Option<usize>,
+    ttl: Option<Duration>,
+}
+
+impl SemanticCacheLayer {
+    /// A layer comparing prompts with `embedder`.
+    pub fn new(embedder: SharedEmbedder) -> Self {
+        Self {
+            embedder,
+            threshold: None,
+            max_entries: None,
+            ttl: None,
+        }
+    }
+
+    /// See [`SemanticCacheProvider::with_threshold`].
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// See [`SemanticCacheProvider::with_max_entries`].
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// See [`SemanticCacheProvider::with_ttl`].
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+impl std::fmt::Debug for SemanticCacheLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SemanticCacheLayer")
+            .field("threshold", &self.threshold)
+            .field("max_entries", &self.max_entries)
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProviderLayer for SemanticCacheLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        let mut provider = SemanticCacheProvider::from_shared(inner, self.embedder.clone());
+        if let Some(threshold) = self.threshold {
+            provider = provider.with_threshold(threshold);
+        }
+        if let Some(max_entries) = self.max_entries {
+            provider = provider.with_max_entries(max_entries);
+        }
+        if let Some(ttl) = self.ttl {
+            provider = provider.with_ttl(ttl);
+        }
+        Arc::new(provider)
+    }
+}
+
+/// Role-prefixed transcript of `prompt`, or `None` when it carries
+/// non-text input the embedding can't see.
+fn embedding_text(prompt: &Prompt) -> Option<String> {
+    fn user_text(parts: &[UserPart], out: &mut String) -> Option<()> {
+        for part in parts {
+            match part {
+                UserPart::Text(text) => out.push_str(text),
+                UserPart::ToolResult { content, .. } => user_text(content, out)?,
+                UserPart::CacheBreakpoint => {}
+                UserPart::Image(_) | UserPart::Audio(_) | UserPart::Document(_) => return None,
+                UserPart::Video(_) => return None,
+            }
+        }
+        Some(())
+    }
+
+    let mut out = String::new();
+    for item in prompt.items() {
+        match item {
+            InputItem::System(text) => {
+                out.push_str("system: ");
+                out.push_str(text);
+            }
+            InputItem::Developer(text) => {
+                out.push_str("developer: ");
+                out.push_str(text);
+            }
+            InputItem::User { content, .. } => {
+                out.push_str("user: ");
+                user_text(content, &mut out)?;
+            }
+            InputItem::Assistant { content, .. } => {
+                out.push_str("assistant: ");
+                for part in content {
+                    match part {
+                        AssistantPart::Text { content, .. } => out.push_str(content),
+                        AssistantPart::Refusal(text) => out.push_str(text),
+                        AssistantPart::ToolCall(call) => {
+                            out.push_str(&call.name);
+                            out.push_str(&call.arguments);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// Everything about the request except the prompt text, its
+/// scheduling priority, its deadlines and the per-call idempotency key
+/// and request ID, as a comparable string.
+fn scope_key(config: &RawConfig) -> String {
+    let mut config = config.clone();
+    config.priority = None;
+    config.timeout = None;
+    config.idle_timeout = None;
+    config.idempotency_key = None;
+    config.request_id = None;
+    format!("{config:?}")
+}
+
+fn normalize(mut vector: Vec<f32>) -> Option<Vec<f32>> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if !norm.is_finite() || norm == 0.0 {
+        return None;
+    }
+    vector.iter_mut().for_each(|x| *x /= norm);
+    Some(vector)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, Config};
+
+    /// Embeds by keyword: one dimension per known topic word, so
+    /// rewordings of the same question land on the same vector.
+    struct Keywords;
+
+    #[async_trait::async_trait]
+    impl Embedder for Keywords {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>, Error> {
+            let text = text.to_lowercase();
+            Ok(["password", "billing", "shipping"]
+                .iter()
+                .map(|word| if text.contains(word) { 1.0 } else { 0.0 })
+                .chain(std::iter::once(0.1))
+                .collect())
+        }
+    }
+
+    async fn ask(provider: &SemanticCacheProvider, question: &str, config: &Config) -> String {
+        generate(provider, &Prompt::user(question), config)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn similar_prompt_hits_cache() {
+        let mock = MockProvider::builder()
+            .reply("reset it in settings")
+            .reply("invoices are monthly")
+            .build();
+        let log = mock.call_log();
+        let provider = SemanticCacheProvider::new(mock, Arc::new(Keywords));
+        let config = Config::builder("m").build();
+
+        assert_eq!(
+            ask(&provider, "How do I reset my password?", &config).await,
+            "reset it in settings"
+        );
+        assert_eq!(
+            ask(&provider, "forgot password, help", &config).await,
+            "reset it in settings"
+        );
+        assert_eq!(
+            ask(&provider, "When is billing?", &config).await,
+            "invoices are monthly"
+        );
+        assert_eq!(log.len(), 2);
+        assert_eq!(
+            provider.stats(),
+            SemanticCacheStats {
+                hits: 1,
+                misses: 2,
+                bypassed: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn hit_reports_cache_label() {
+        let provider = SemanticCacheProvider::new(MockProvider::with_text("a"), Arc::new(Keywords));
+        let config = Config::builder("m").build();
+        ask(&provider, "password", &config).await;
+        let response = generate(&provider, &Prompt::user("password?"), &config)
+            .await
+            .unwrap();
+        assert_eq!(response.metadata().served_by.as_deref(), Some(CACHE_LABEL));
+    }
+
+    #[tokio::test]
+    async fn different_scope_misses() {
+        let mock = MockProvider::with_text("answer");
+        let log = mock.call_log();
+        let provider = SemanticCacheProvider::new(mock, Arc::new(Keywords));
+        ask(&provider, "password", &Config::builder("a").build()).await;
+        ask(&provider, "password", &Config::builder("b").build()).await;
+        assert_eq!(log.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn per_call_settings_do_not_split_the_scope() {
+        let mock = MockProvider::with_text("answer");
+        let log = mock.call_log();
+        let provider = SemanticCacheProvider::new(mock, Arc::new(Keywords));
+        let first = Config::builder("m")
+            .idempotency_key("k1")
+            .idle_timeout(std::time::Duration::from_secs(5))
+            .build();
+        let second = Config::builder("m").idempotency_key("k2").build();
+        ask(&provider, "password", &first).await;
+        ask(&provider, "password", &second).await;
+        assert_eq!(log.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_and_truncated_streams_are_not_cached() {
+        let mock = MockProvider::builder()
+            .reply(
+                crate::providers::mock::MockResponse::text("partial")
+                    .with_stream_error(Error::provider_with_status("Mock", 502, "drop")),
+            )
+            .reply(crate::providers::mock::MockResponse::from_parts(
+                vec![AssistantPart::Text {
+                    content: "cut".into(),
+                    annotations: Vec::new(),
+                }],
+                FinishReason::Length,
+            ))
+            .build();
+        let provider = SemanticCacheProvider::new(mock, Arc::new(Keywords));
+        let config = Config::builder("m").build();
+        let response = generate(&provider, &Prompt::user("password"), &config)
+            .await
+            .unwrap();
+        assert!(response.text().await.is_err());
+        ask(&provider, "password", &config).await;
+        assert!(provider.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ttl_and_capacity_evict() {
+        let mock = MockProvider::with_text("answer");
+        let log = mock.call_log();
+        let provider = SemanticCacheProvider::new(mock, Arc::new(Keywords))
+            .with_max_entries(1)
+            .with_ttl(Duration::from_secs(60));
+        let config = Config::builder("m").build();
+
+        ask(&provider, "password", &config).await;
+        ask(&provider, "billing", &config).await;
+        assert_eq!(provider.len(), 1);
+        ask(&provider, "password", &config).await; // evicted by capacity
+        assert_eq!(log.len(), 3);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        ask(&provider, "password", &config).await; // expired
+        assert_eq!(log.len(), 4);
+    }
+}
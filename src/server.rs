@@ -0,0 +1,769 @@
+//! OpenAI-compatible HTTP facade over any [`Provider`].
+//!
+//! [`router`] builds an [axum] router serving `POST /v1/chat/completions`
+//! — both the buffered and the `stream: true` SSE flavour — on top of a
+//! [`SharedProvider`]. Point an existing OpenAI client (SDKs, `curl`
+//! scripts, IDE plugins) at it and the request flows through
+//! [`crate::generate`] to whatever backend the provider wraps: a
+//! [`crate::RouterProvider`], a [`crate::FallbackProvider`], Gemini,
+//! Claude on Vertex, a local GGUF model.
+//!
+//! ```ignore
+//! let provider: SharedProvider = ProviderStack::new()
+//!     .layer(RetryLayer::new(RetryPolicy::default()))
+//!     .service(gemini);
+//! let app = platformed_llm::server::router(provider);
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
+//! axum::serve(listener, app).await?;
+//! ```
+//!
+//! The router carries no authentication, CORS or body-size policy —
+//! nest it inside your own application and add those as tower layers.
+//!
+//! # What is translated
+//!
+//! - `messages`: read by [`Prompt::from_chat_messages`], so `user`
+//!   content may carry `text`, `image_url`, `input_audio` and `file`
+//!   parts, and any other part is a `400`. A `user` / `assistant`
+//!   `name` is kept as the item's participant name.
+//! - Sampling: `temperature`, `top_p`, `max_tokens` /
+//!   `max_completion_tokens`, `stop`, `presence_penalty`,
+//!   `frequency_penalty`.
+//! - Tools: function `tools`, `tool_choice`, `parallel_tool_calls`.
+//! - `response_format`: `text`, `json_object`, `json_schema`.
+//! - `stream_options.include_usage` adds the trailing usage chunk.
+//! - `safety_identifier` (or the older `user`) and `prompt_cache_key`
+//!   become [`crate::RequestMetadata`].
+//!
+//! Anything else in the body is ignored. Reasoning parts are not
+//! surfaced, since Chat Completions has no field for them.
+//!
+//! # Errors
+//!
+//! Failures before the first byte come back as an OpenAI-shaped
+//! `{"error": {"message", "type", "code"}}` body with a matching
+//! status: 400 for invalid requests (including bodies that aren't a
+//! valid chat request), 401 for auth, 404 for unknown models, 429 for
+//! rate limits, 500 for a misconfigured provider, 504 for timeouts,
+//! the upstream status for other provider errors, 502 otherwise. A
+//! failure mid-stream is sent as one final `data: {"error": ...}`
+//! event, followed by `data: [DONE]`. Messages are scrubbed with
+//! [`crate::logging`]'s default redactor before they are returned.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::body::{Body, Bytes};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response as HttpResponse};
+use axum::routing::post;
+use axum::{Json, Router};
+use futures_util::{stream, StreamExt};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{json, Value};
+
+use crate::layer::SharedProvider;
+use crate::types::chat_messages::{raw_json, response_format, tool_choice};
+use crate::types::{AssistantPart, FinishReason, PartKind, Tool};
+use crate::{Config, Error, Prompt, Provider, StreamEvent, Usage};
+
+/// An axum [`Router`] serving `POST /v1/chat/completions` over
+/// `provider`. See the module docs.
+pub fn router(provider: SharedProvider) -> Router {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .with_state(provider)
+}
+
+async fn chat_completions(
+    State(provider): State<SharedProvider>,
+    request: Result<Json<ChatRequest>, JsonRejection>,
+) -> HttpResponse {
+    let request = match request {
+        Ok(Json(request)) => request,
+        Err(rejection) => {
+            let err = Error::invalid_prompt(rejection.body_text());
+            return (
+                rejection.status(),
+                Json(json!({ "error": error_body(&err) })),
+            )
+                .into_response();
+        }
+    };
+    match serve(&*provider, request).await {
+        Ok(response) => response,
+        Err(err) => error_response(&err),
+    }
+}
+
+async fn serve(provider: &dyn Provider, request: ChatRequest) -> Result<HttpResponse, Error> {
+    let prompt = request.prompt()?;
+    let config = request.config()?;
+    let response = crate::generate(provider, &prompt, &config).await?;
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    if !request.stream {
+        let complete = response.buffer().await?;
+        let body = json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": created,
+            "model": request.model,
+            "choices": [{
+                "index": 0,
+                "message": assistant_message(&complete.content),
+                "finish_reason": finish_reason(&complete.finish_reason),
+            }],
+            "usage": usage_json(&complete.usage),
+        });
+        return Ok(Json(body).into_response());
+    }
+
+    let mut encoder = ChunkEncoder {
+        id,
+        created,
+        model: request.model,
+        include_usage: request
+            .stream_options
+            .is_some_and(|options| options.include_usage),
+        role_sent: false,
+        parts: Vec::new(),
+        tool_calls: 0,
+        finished: false,
+    };
+    let body = response
+        .stream()
+        .map(move |item| stream::iter(encoder.encode(item)))
+        .flatten();
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(body.map(Ok::<_, std::convert::Infallible>)),
+    )
+        .into_response())
+}
+
+/// The sentinel frame OpenAI clients wait for before closing a stream.
+const DONE: &[u8] = b"data: [DONE]\n\n";
+
+/// Turns unified events into `chat.completion.chunk` SSE frames.
+struct ChunkEncoder {
+    id: String,
+    created: u64,
+    model: String,
+    include_usage: bool,
+    role_sent: bool,
+    /// Per part index, the Chat Completions field it streams into;
+    /// absent for parts Chat Completions can't represent.
+    parts: Vec<(u32, ChunkPart)>,
+    tool_calls: usize,
+    finished: bool,
+}
+
+/// Where a part's deltas go in a `chat.completion.chunk`.
+#[derive(Clone, Copy)]
+enum ChunkPart {
+    /// `delta.content`.
+    Text,
+    /// `delta.refusal`.
+    Refusal,
+    /// `delta.tool_calls[n]`, for the `n`th tool call of the turn.
+    ToolCall(usize),
+}
+
+impl ChunkEncoder {
+    fn encode(&mut self, item: Result<StreamEvent, Error>) -> Vec<Bytes> {
+        if self.finished {
+            return Vec::new();
+        }
+        let event = match item {
+            Ok(event) => event,
+            Err(err) => {
+                self.finished = true;
+                return vec![
+                    frame(&json!({ "error": error_body(&err) })),
+                    Bytes::from_static(DONE),
+                ];
+            }
+        };
+        match event {
+            StreamEvent::PartStart {
+                index,
+                kind: PartKind::Text,
+            } => {
+                self.parts.push((index, ChunkPart::Text));
+                Vec::new()
+            }
+            StreamEvent::PartStart {
+                index,
+                kind: PartKind::Refusal,
+            } => {
+                self.parts.push((index, ChunkPart::Refusal));
+                Vec::new()
+            }
+            StreamEvent::PartStart {
+                index,
+                kind: PartKind::ToolCall { call_id, name },
+            } => {
+                let ordinal = self.tool_calls;
+                self.tool_calls += 1;
+                self.parts.push((index, ChunkPart::ToolCall(ordinal)));
+                vec![self.chunk(
+                    json!({ "tool_calls": [{
+                        "index": ordinal,
+                        "id": call_id,
+                        "type": "function",
+                        "function": { "name": name, "arguments": "" },
+                    }]}),
+                    None,
+                )]
+            }
+            StreamEvent::Delta { index, delta } => {
+                match self
+                    .parts
+                    .iter()
+                    .find(|(i, _)| *i == index)
+                    .map(|(_, p)| *p)
+                {
+                    Some(ChunkPart::Text) => vec![self.chunk(json!({ "content": delta }), None)],
+                    Some(ChunkPart::Refusal) => {
+                        vec![self.chunk(json!({ "refusal": delta }), None)]
+                    }
+                    Some(ChunkPart::ToolCall(ordinal)) => {
+                        vec![self.chunk(
+                            json!({ "tool_calls": [{
+                                "index": ordinal,
+                                "function": { "arguments": delta },
+                            }]}),
+                            None,
+                        )]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            StreamEvent::Done {
+                finish_reason: reason,
+                usage,
+            } => {
+                self.finished = true;
+                let mut frames = vec![self.chunk(json!({}), Some(finish_reason(&reason)))];
+                if self.include_usage {
+                    frames.push(frame(&json!({
+                        "id": self.id,
+                        "object": "chat.completion.chunk",
+                        "created": self.created,
+                        "model": self.model,
+                        "choices": [],
+                        "usage": usage_json(&usage),
+                    })));
+                }
+                frames.push(Bytes::from_static(DONE));
+                frames
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn chunk(&mut self, mut delta: Value, finish_reason: Option<&str>) -> Bytes {
+        if !self.role_sent {
+            self.role_sent = true;
+            delta["role"] = json!("assistant");
+        }
+        frame(&json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason,
+            }],
+        }))
+    }
+}
+
+fn frame(value: &Value) -> Bytes {
+    Bytes::from(format!("data: {value}\n\n"))
+}
+
+fn assistant_message(content: &[AssistantPart]) -> Value {
+    let text: String = content
+        .iter()
+        .filter_map(|part| match part {
+            AssistantPart::Text { content, .. } => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    let tool_calls: Vec<Value> = content
+        .iter()
+        .filter_map(|part| match part {
+            AssistantPart::ToolCall(call) => Some(json!({
+                "id": call.call_id,
+                "type": "function",
+                "function": { "name": call.name, "arguments": call.arguments },
+            })),
+            _ => None,
+        })
+        .collect();
+    let refusal: String = content
+        .iter()
+        .filter_map(|part| match part {
+            AssistantPart::Refusal(content) => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut message = json!({
+        "role": "assistant",
+        "content": if text.is_empty() && !(tool_calls.is_empty() && refusal.is_empty()) {
+            Value::Null
+        } else {
+            json!(text)
+        },
+    });
+    if !tool_calls.is_empty() {
+        message["tool_calls"] = json!(tool_calls);
+    }
+    if !refusal.is_empty() {
+        message["refusal"] = json!(refusal);
+    }
+    message
+}
+
+fn finish_reason(reason: &FinishReason) -> &'static str {
+    match reason {
+        FinishReason::Length => "length",
+        FinishReason::ToolCalls => "tool_calls",
+        FinishReason::ContentFilter | FinishReason::Safety => "content_filter",
+        _ => "stop",
+    }
+}
+
+fn usage_json(usage: &Usage) -> Value {
+    let mut value = json!({
+        "prompt_tokens": usage.input_tokens,
+        "completion_tokens": usage.output_tokens,
+        "total_tokens": usage.total_tokens(),
+    });
+    if let Some(cached) = usage.cache_read_input_tokens {
+        value["prompt_tokens_details"] = json!({ "cached_tokens": cached });
+    }
+    if let Some(reasoning) = usage.reasoning_tokens {
+        value["completion_tokens_details"] = json!({ "reasoning_tokens": reasoning });
+    }
+    value
+}
+
+fn error_status(err: &Error) -> StatusCode {
+    match err {
+        Error::InvalidPrompt(_)
+        | Error::UnsupportedInput { .. }
+        | Error::ContextWindowExceeded { .. }
+        | Error::ContentPolicy { .. }
+        | Error::Serialization(_) => StatusCode::BAD_REQUEST,
+        Error::Auth { .. } => StatusCode::UNAUTHORIZED,
+        Error::ModelNotAvailable { .. } => StatusCode::NOT_FOUND,
+        Error::RateLimit { .. } => StatusCode::TOO_MANY_REQUESTS,
+        Error::Timeout(_) | Error::IdleTimeout(_) => StatusCode::GATEWAY_TIMEOUT,
+        Error::StreamOverflow { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        // The server's own provider setup is wrong, not the request.
+        Error::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::Provider { .. } => err
+            .status()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .filter(|status| status.is_client_error() || status.is_server_error())
+            .unwrap_or(StatusCode::BAD_GATEWAY),
+        #[cfg(feature = "reqwest")]
+        Error::Transport(_) => StatusCode::BAD_GATEWAY,
+        Error::Compaction { .. }
+        | Error::SseLimitExceeded { .. }
+        | Error::InvalidToolArguments { .. } => StatusCode::BAD_GATEWAY,
+    }
+}
+
+fn error_body(err: &Error) -> Value {
+    let kind = match err {
+        Error::InvalidPrompt(_)
+        | Error::UnsupportedInput { .. }
+        | Error::ContextWindowExceeded { .. }
+        | Error::ContentPolicy { .. }
+        | Error::Serialization(_) => "invalid_request_error",
+        Error::Auth { .. } => "authentication_error",
+        Error::RateLimit { .. } => "rate_limit_error",
+        #[cfg(feature = "reqwest")]
+        Error::Transport(_) => "api_error",
+        Error::Config(_)
+        | Error::Provider { .. }
+        | Error::ModelNotAvailable { .. }
+        | Error::Compaction { .. }
+        | Error::Timeout(_)
+        | Error::IdleTimeout(_)
+        | Error::StreamOverflow { .. }
+        | Error::SseLimitExceeded { .. }
+        | Error::InvalidToolArguments { .. } => "api_error",
+    };
+    json!({
+        "message": crate::logging::scrub(&err.to_string()),
+        "type": kind,
+        "code": err.kind(),
+    })
+}
+
+fn error_response(err: &Error) -> HttpResponse {
+    let mut response =
+        (error_status(err), Json(json!({ "error": error_body(err) }))).into_response();
+    if let Some(retry_after) = err.retry_after() {
+        response.headers_mut().insert(
+            header::RETRY_AFTER,
+            retry_after.as_secs().max(1).to_string().parse().unwrap(),
+        );
+    }
+    response
+}
+
+/// The subset of the Chat Completions request body the facade reads.
+#[derive(Debug, Deserialize)]
+struct ChatRequest {
+    model: String,
+    messages: Value,
+    #[serde(default)]
+    stream: bool,
+    stream_options: Option<StreamOptions>,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_tokens: Option<u32>,
+    max_completion_tokens: Option<u32>,
+    stop: Option<OneOrMany>,
+    presence_penalty: Option<f32>,
+    frequency_penalty: Option<f32>,
+    tools: Option<Vec<ChatTool>>,
+    tool_choice: Option<Value>,
+    parallel_tool_calls: Option<bool>,
+    response_format: Option<Value>,
+    user: Option<String>,
+    safety_identifier: Option<String>,
+    prompt_cache_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamOptions {
+    #[serde(default)]
+    include_usage: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatTool {
+    function: ChatFunction,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatFunction {
+    name: String,
+    description: Option<String>,
+    parameters: Option<Box<RawValue>>,
+}
+
+impl ChatRequest {
+    fn prompt(&self) -> Result<Prompt, Error> {
+        Prompt::from_chat_messages(&self.messages)
+    }
+
+    fn config(&self) -> Result<Config, Error> {
+        let mut builder = Config::builder(self.model.clone());
+        if let Some(temperature) = self.temperature {
+            builder = builder.temperature(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            builder = builder.top_p(top_p);
+        }
+        if let Some(max_tokens) = self.max_completion_tokens.or(self.max_tokens) {
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Some(stop) = &self.stop {
+            builder = builder.stop(match stop {
+                OneOrMany::One(stop) => vec![stop.clone()],
+                OneOrMany::Many(stop) => stop.clone(),
+            });
+        }
+        if let Some(penalty) = self.presence_penalty {
+            builder = builder.presence_penalty(penalty);
+        }
+        if let Some(penalty) = self.frequency_penalty {
+            builder = builder.frequency_penalty(penalty);
+        }
+        if let Some(tools) = &self.tools {
+            let tools = tools
+                .iter()
+                .map(|tool| {
+                    let parameters = match &tool.function.parameters {
+                        Some(parameters) => raw_json(parameters.get().to_string())?,
+                        None => raw_json(r#"{"type":"object","properties":{}}"#.to_string())?,
+                    };
+                    Ok(Tool::function(
+                        tool.function.name.clone(),
+                        tool.function.description.clone(),
+                        parameters,
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            builder = builder.tools(tools);
+        }
+        if let Some(choice) = &self.tool_choice {
+            builder = builder.tool_choice(tool_choice(choice)?);
+        }
+        if let Some(parallel) = self.parallel_tool_calls {
+            builder = builder.parallel_tool_calls(parallel);
+        }
+        if let Some(user) = self.safety_identifier.as_ref().or(self.user.as_ref()) {
+            builder = builder.user_id(user.clone());
+        }
+        if let Some(key) = &self.prompt_cache_key {
+            builder = builder.prompt_cache_key(key.clone());
+        }
+        if let Some(format) = &self.response_format {
+            builder = builder.response_format(response_format(format)?);
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::types::{FunctionCall, InputItem};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    async fn post(provider: MockProvider, body: Value) -> (StatusCode, String) {
+        let response = router(Arc::new(provider))
+            .oneshot(
+                axum::http::Request::post("/v1/chat/completions")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn buffered_completion_translates_prompt_and_reply() {
+        let provider = MockProvider::with_text("hello there");
+        let log = provider.call_log();
+        let (status, body) = post(
+            provider,
+            json!({
+                "model": "m",
+                "max_completion_tokens": 64,
+                "messages": [
+                    { "role": "system", "content": "be brief" },
+                    { "role": "user", "content": [{ "type": "text", "text": "hi" }] },
+                ],
+            }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["object"], "chat.completion");
+        assert_eq!(body["choices"][0]["message"]["content"], "hello there");
+        assert_eq!(body["choices"][0]["finish_reason"], "stop");
+
+        let call = &log.calls()[0];
+        assert_eq!(call.config.max_tokens, Some(64));
+        assert!(matches!(call.prompt.items()[0], InputItem::System(ref s) if s == "be brief"));
+    }
+
+    #[tokio::test]
+    async fn streams_chunks_with_tool_calls_and_done_marker() {
+        let provider = MockProvider::always(MockResponse::tool_call(FunctionCall {
+            call_id: "call_1".into(),
+            name: "lookup".into(),
+            arguments: r#"{"q":"x"}"#.into(),
+            provider_signature: None,
+            original_arguments: None,
+        }));
+        let (status, body) = post(
+            provider,
+            json!({
+                "model": "m",
+                "stream": true,
+                "stream_options": { "include_usage": true },
+                "messages": [{ "role": "user", "content": "hi" }],
+            }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        let frames: Vec<&str> = body
+            .split("\n\n")
+            .filter_map(|frame| frame.strip_prefix("data: "))
+            .collect();
+        assert_eq!(frames.last(), Some(&"[DONE]"));
+        let chunks: Vec<Value> = frames[..frames.len() - 1]
+            .iter()
+            .map(|frame| serde_json::from_str(frame).unwrap())
+            .collect();
+        assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(
+            chunks[0]["choices"][0]["delta"]["tool_calls"][0]["function"]["name"],
+            "lookup"
+        );
+        let arguments: String = chunks
+            .iter()
+            .filter_map(|c| {
+                c["choices"][0]["delta"]["tool_calls"][0]["function"]["arguments"].as_str()
+            })
+            .collect();
+        assert_eq!(arguments, r#"{"q":"x"}"#);
+        let finish = &chunks[chunks.len() - 2];
+        assert_eq!(finish["choices"][0]["finish_reason"], "tool_calls");
+        assert!(chunks[chunks.len() - 1]["usage"].is_object());
+    }
+
+    #[tokio::test]
+    async fn refusals_use_the_refusal_field() {
+        let refusal = || {
+            MockProvider::always(MockResponse::from_parts(
+                vec![AssistantPart::Refusal("I can't help with that.".into())],
+                FinishReason::Stop,
+            ))
+        };
+        let (_, body) = post(
+            refusal(),
+            json!({ "model": "m", "messages": [{ "role": "user", "content": "hi" }] }),
+        )
+        .await;
+        let body: Value = serde_json::from_str(&body).unwrap();
+        let message = &body["choices"][0]["message"];
+        assert_eq!(message["refusal"], "I can't help with that.");
+        assert!(message["content"].is_null());
+
+        let (_, body) = post(
+            refusal(),
+            json!({ "model": "m", "stream": true, "messages": [{ "role": "user", "content": "hi" }] }),
+        )
+        .await;
+        let streamed: String = body
+            .split("\n\n")
+            .filter_map(|frame| frame.strip_prefix("data: "))
+            .filter_map(|frame| serde_json::from_str::<Value>(frame).ok())
+            .filter_map(|chunk| {
+                chunk["choices"][0]["delta"]["refusal"]
+                    .as_str()
+                    .map(String::from)
+            })
+            .collect();
+        assert_eq!(streamed, "I can't help with that.");
+    }
+
+    #[tokio::test]
+    async fn errors_map_to_openai_error_bodies() {
+        let provider = MockProvider::builder()
+            .fail(Error::rate_limit(Some(3), "slow down"))
+            .build();
+        let (status, body) = post(
+            provider,
+            json!({ "model": "m", "messages": [{ "role": "user", "content": "hi" }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"]["type"], "rate_limit_error");
+
+        let (status, _) = post(
+            MockProvider::with_text("x"),
+            json!({ "model": "m", "messages": [{ "role": "wizard", "content": "hi" }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let provider = MockProvider::builder()
+            .fail(Error::config("no API key"))
+            .build();
+        let (status, _) = post(
+            provider,
+            json!({ "model": "m", "messages": [{ "role": "user", "content": "hi" }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn malformed_bodies_get_openai_error_bodies() {
+        let (status, body) = post(MockProvider::with_text("x"), json!({ "model": "m" })).await;
+        assert!(status.is_client_error());
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("messages"));
+    }
+
+    #[tokio::test]
+    async fn stream_errors_end_with_done_marker() {
+        let provider = MockProvider::always(
+            MockResponse::text("partial").with_stream_error(Error::provider("Mock", "dropped")),
+        );
+        let (_, body) = post(
+            provider,
+            json!({ "model": "m", "stream": true, "messages": [{ "role": "user", "content": "hi" }] }),
+        )
+        .await;
+        let frames: Vec<&str> = body
+            .split("\n\n")
+            .filter_map(|frame| frame.strip_prefix("data: "))
+            .collect();
+        assert_eq!(frames.last(), Some(&"[DONE]"));
+        let error: Value = serde_json::from_str(frames[frames.len() - 2]).unwrap();
+        assert_eq!(error["error"]["code"], "provider");
+    }
+
+    #[tokio::test]
+    async fn messages_share_the_chat_importer() {
+        let provider = MockProvider::with_text("ok");
+        let log = provider.call_log();
+        let audio =
+            json!({ "type": "input_audio", "input_audio": { "data": "AAAA", "format": "wav" } });
+        let (status, _) = post(
+            provider,
+            json!({ "model": "m", "messages": [{ "role": "user", "content": [audio] }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let calls = log.calls();
+        let InputItem::User { content, .. } = &calls[0].prompt.items()[0] else {
+            panic!("expected a user item");
+        };
+        assert!(matches!(content[0], crate::types::UserPart::Audio(_)));
+
+        let unknown = json!({ "type": "hologram" });
+        let (status, _) = post(
+            MockProvider::with_text("x"),
+            json!({ "model": "m", "messages": [{ "role": "user", "content": [unknown] }] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,6 +1,17 @@
-//! Stream adapter for parsing SSE (Server-Sent Events) from byte chunks.
-
-use crate::Error;
+//! Stream adapter for parsing SSE (Server-Sent Events) from byte chunks,
+//! and the inverse: [`sse_stream::encode_events`](crate::sse_stream::encode_events) frames a unified
+//! [`StreamEvent`](crate::StreamEvent) stream back into SSE bytes for
+//! proxying to browsers.
+//!
+//! This is the crate's one SSE implementation; every provider reads
+//! its stream through [`SseStream`](crate::sse_stream::SseStream).
+//! Consumers dispatch on
+//! [`SseEvent::event_name`](crate::sse_stream::SseEvent::event_name),
+//! skip [`SseEvent::is_keep_alive`](crate::sse_stream::SseEvent::is_keep_alive)
+//! frames and decode payloads with
+//! [`SseEvent::json`](crate::sse_stream::SseEvent::json).
+
+use crate::{Error, StreamEvent};
 use futures_util::{Stream, StreamExt};
 use memchr::memchr2;
 use std::collections::VecDeque;
@@ -22,6 +33,55 @@ pub struct SseEvent {
 }
 
 impl SseEvent {
+    /// Render this event in SSE wire format, terminated by the blank
+    /// line that dispatches it. Multi-line `data` is split across
+    /// several `data:` lines, so parsing the output with [`SseStream`]
+    /// reproduces the event.
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+        if !self.event_type.is_empty() {
+            out.push_str("event: ");
+            out.push_str(&self.event_type);
+            out.push('\n');
+        }
+        if !self.id.is_empty() {
+            out.push_str("id: ");
+            out.push_str(&self.id);
+            out.push('\n');
+        }
+        if let Some(retry) = self.retry {
+            out.push_str(&format!("retry: {retry}\n"));
+        }
+        for line in self.data.split('\n') {
+            out.push_str("data: ");
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+        out
+    }
+
+    /// The event's type, or `"message"` — the type the SSE spec
+    /// assigns to an event without an `event:` field.
+    pub fn event_name(&self) -> &str {
+        if self.event_type.is_empty() {
+            "message"
+        } else {
+            &self.event_type
+        }
+    }
+
+    /// True when the event carries no data — a heartbeat some servers
+    /// send to hold the connection open.
+    pub fn is_keep_alive(&self) -> bool {
+        self.data.trim().is_empty()
+    }
+
+    /// Decode `data` as JSON.
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, Error> {
+        Ok(serde_json::from_str(&self.data)?)
+    }
+
     /// True when every field is empty / unset — used by the parser to
     /// avoid dispatching a zero-content event.
     pub fn is_empty(&self) -> bool {
@@ -32,6 +92,54 @@ impl SseEvent {
     }
 }
 
+/// Size caps for [`SseStream`], so a misbehaving upstream that never
+/// ends a line or an event can't grow the parser's buffers until the
+/// process runs out of memory. Exceeding one fails the stream with
+/// [`Error::SseLimitExceeded`].
+///
+/// The defaults — 32 MiB per line, 64 MiB of `data` per event — sit
+/// well above the largest legitimate frames (inline base64 images on
+/// Gemini) while still bounding memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SseLimits {
+    max_line: usize,
+    max_event: usize,
+}
+
+impl Default for SseLimits {
+    fn default() -> Self {
+        Self {
+            max_line: 32 << 20,
+            max_event: 64 << 20,
+        }
+    }
+}
+
+impl SseLimits {
+    /// Longest line, in bytes, before its terminating newline.
+    pub fn with_max_line(mut self, bytes: usize) -> Self {
+        self.max_line = bytes;
+        self
+    }
+
+    /// Most `data` bytes one event may accumulate across its `data:`
+    /// lines.
+    pub fn with_max_event(mut self, bytes: usize) -> Self {
+        self.max_event = bytes;
+        self
+    }
+
+    /// The line limit.
+    pub fn max_line(&self) -> usize {
+        self.max_line
+    }
+
+    /// The event limit.
+    pub fn max_event(&self) -> usize {
+        self.max_event
+    }
+}
+
 /// A stream adapter that parses SSE events from a byte stream.
 /// Uses line-based processing with state machine for robust line ending detection.
 pub struct SseStream<S> {
@@ -53,6 +161,7 @@ struct EventBuffer {
     /// Provider name forwarded from the owning `SseStream` so the
     /// UTF-8 error site can attribute the failure correctly.
     provider: &'static str,
+    limits: SseLimits,
 }
 
 impl EventBuffer {
@@ -62,6 +171,16 @@ impl EventBuffer {
             current_event: SseEvent::default(),
             events: VecDeque::new(),
             provider,
+            limits: SseLimits::default(),
+        }
+    }
+
+    fn limit_exceeded(&mut self, what: &'static str, limit: usize) -> Error {
+        self.current_event = SseEvent::default();
+        Error::SseLimitExceeded {
+            provider: self.provider,
+            what,
+            limit,
         }
     }
 
@@ -87,6 +206,9 @@ impl EventBuffer {
     }
 
     fn process_line(&mut self, line: &[u8]) -> Result<(), Error> {
+        if line.len() > self.limits.max_line {
+            return Err(self.limit_exceeded("line", self.limits.max_line));
+        }
         let line = std::str::from_utf8(line).map_err(|e| {
             // SSE-layer error attributed to the upstream provider so
             // logs / metrics / per-provider retry policies see the
@@ -120,6 +242,9 @@ impl EventBuffer {
                 self.current_event.event_type = value.to_string();
             }
             "data" => {
+                if self.current_event.data.len() + value.len() > self.limits.max_event {
+                    return Err(self.limit_exceeded("event", self.limits.max_event));
+                }
                 self.current_event.data.push_str(value);
                 self.current_event.data.push('\n');
             }
@@ -150,6 +275,12 @@ impl<S> SseStream<S> {
         }
     }
 
+    /// Replace the default [`SseLimits`].
+    pub fn with_limits(mut self, limits: SseLimits) -> Self {
+        self.events.limits = limits;
+        self
+    }
+
     /// Process the buffer using a state machine to detect line endings robustly.
     /// State is preserved across calls to handle line endings split across buffer boundaries.
     fn parse_buffer(&mut self, mut buffer: &[u8]) -> Result<(), Error> {
@@ -163,8 +294,9 @@ impl<S> SseStream<S> {
             } else {
                 // We have a previous line buffer, combine it with the current line
                 self.line_buffer.extend_from_slice(&buffer[..idx]);
-                self.events.process_line(&self.line_buffer)?;
+                let processed = self.events.process_line(&self.line_buffer);
                 self.line_buffer.clear();
+                processed?;
             }
 
             self.last_seen_cr = !is_nl;
@@ -172,6 +304,11 @@ impl<S> SseStream<S> {
         }
 
         // Add any remaining bytes to the line buffer
+        if self.line_buffer.len() + buffer.len() > self.events.limits.max_line {
+            self.line_buffer.clear();
+            let limit = self.events.limits.max_line;
+            return Err(self.events.limit_exceeded("line", limit));
+        }
         self.line_buffer.extend_from_slice(buffer);
 
         Ok(())
@@ -243,11 +380,100 @@ pub trait SseStreamExt: Stream {
 
 impl<S: Stream> SseStreamExt for S {}
 
+/// Frame a unified event stream as SSE bytes — the inverse of
+/// [`SseStream`], for services that proxy a [`crate::Response`] to a
+/// browser `EventSource`.
+///
+/// Each [`StreamEvent`] becomes one SSE event whose `event:` field is
+/// the `snake_case` variant name (`part_start`, `delta`, `part_update`,
+/// `part_end`, `done`) and whose `data:` is the event's JSON, so a
+/// client can deserialize it straight back into a `StreamEvent`. An
+/// `Err` becomes a final `event: error` carrying
+/// `{"kind": ..., "message": ...}` (see [`Error::kind`]); the encoded
+/// stream ends there. Error messages pass through
+/// [`crate::logging`]'s default redactor before leaving the process.
+///
+/// ```ignore
+/// let body = sse_stream::encode_events(response.stream());
+/// axum::response::Response::builder()
+///     .header("content-type", "text/event-stream")
+///     .body(axum::body::Body::from_stream(body.map(Ok::<_, Infallible>)))
+/// ```
+pub fn encode_events<S>(events: S) -> impl Stream<Item = bytes::Bytes> + Send
+where
+    S: Stream<Item = Result<StreamEvent, Error>> + Send,
+{
+    events
+        .scan(false, |failed, item| {
+            if *failed {
+                return std::future::ready(None);
+            }
+            *failed = item.is_err();
+            std::future::ready(Some(encode_item(item)))
+        })
+        .map(|event| bytes::Bytes::from(event.encode()))
+}
+
+fn encode_item(item: Result<StreamEvent, Error>) -> SseEvent {
+    match item {
+        Ok(event) => {
+            let value = serde_json::to_value(&event).unwrap_or_default();
+            let event_type = value
+                .as_object()
+                .and_then(|object| object.keys().next().cloned())
+                .unwrap_or_default();
+            SseEvent {
+                event_type,
+                data: value.to_string(),
+                ..SseEvent::default()
+            }
+        }
+        Err(err) => SseEvent {
+            event_type: "error".to_string(),
+            data: serde_json::json!({
+                "kind": err.kind(),
+                "message": crate::logging::scrub(&err.to_string()),
+            })
+            .to_string(),
+            ..SseEvent::default()
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use futures_util::stream;
 
+    #[tokio::test]
+    async fn encoded_events_round_trip_through_the_parser() {
+        let events: Vec<Result<StreamEvent, Error>> = vec![
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "line one\nline two".into(),
+            }),
+            Err(Error::rate_limit(None, "slow down")),
+            Ok(StreamEvent::PartEnd { index: 0 }),
+        ];
+        let bytes: Vec<Result<bytes::Bytes, Error>> =
+            encode_events(stream::iter(events)).map(Ok).collect().await;
+        let parsed: Vec<SseEvent> = stream::iter(bytes)
+            .sse_events("Test")
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(parsed.len(), 2, "nothing follows the error event");
+        assert_eq!(parsed[0].event_type, "delta");
+        match serde_json::from_str::<StreamEvent>(&parsed[0].data).unwrap() {
+            StreamEvent::Delta { delta, .. } => assert_eq!(delta, "line one\nline two"),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(parsed[1].event_type, "error");
+        let error: serde_json::Value = serde_json::from_str(&parsed[1].data).unwrap();
+        assert_eq!(error["kind"], "rate_limit");
+    }
+
     #[tokio::test]
     async fn test_sse_stream_complete_events() {
         let chunks: Vec<Result<bytes::Bytes, Error>> =
@@ -308,6 +534,69 @@ mod tests {
         assert_eq!(event.id, "123".to_string());
     }
 
+    #[tokio::test]
+    async fn dispatch_helpers_name_skip_and_decode_events() {
+        let chunks: Vec<Result<bytes::Bytes, Error>> = vec![Ok(bytes::Bytes::from(
+            "event: ping\ndata: \n\ndata: {\"n\":1}\n\n",
+        ))];
+        let events: Vec<_> = stream::iter(chunks)
+            .sse_events("Test")
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(events[0].event_name(), "ping");
+        assert!(events[0].is_keep_alive());
+        assert_eq!(events[1].event_name(), "message");
+        assert!(!events[1].is_keep_alive());
+        let payload: serde_json::Value = events[1].json().unwrap();
+        assert_eq!(payload["n"], 1);
+        assert!(events[0].json::<serde_json::Value>().is_err());
+    }
+
+    #[tokio::test]
+    async fn oversized_lines_and_events_fail_with_a_typed_error() {
+        let limits = SseLimits::default().with_max_line(16).with_max_event(24);
+
+        // A line that never ends, fed in chunks each under the limit.
+        let endless: Vec<Result<bytes::Bytes, Error>> = vec![
+            Ok(bytes::Bytes::from("data: 0123456789")),
+            Ok(bytes::Bytes::from("0123456789")),
+        ];
+        let mut sse = stream::iter(endless).sse_events("Test").with_limits(limits);
+        let err = sse.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::SseLimitExceeded {
+                provider: "Test",
+                what: "line",
+                limit: 16,
+            }
+        ));
+        assert!(!err.is_retryable());
+
+        // Short lines that add up to an oversized event.
+        let chunks: Vec<Result<bytes::Bytes, Error>> = vec![Ok(bytes::Bytes::from(
+            "data: 0123456789\ndata: 0123456789\ndata: 0123456789\n\n",
+        ))];
+        let mut sse = stream::iter(chunks).sse_events("Test").with_limits(limits);
+        let err = sse.next().await.unwrap().unwrap_err();
+        assert_eq!(err.kind(), "sse_limit_exceeded");
+        assert!(matches!(err, Error::SseLimitExceeded { what: "event", .. }));
+        let within = SseLimits::default().with_max_line(16).with_max_event(64);
+        let chunks: Vec<Result<bytes::Bytes, Error>> = vec![Ok(bytes::Bytes::from(
+            "data: 0123456789\ndata: 0123456789\n\n",
+        ))];
+        let event = stream::iter(chunks)
+            .sse_events("Test")
+            .with_limits(within)
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.data, "0123456789\n0123456789");
+    }
+
     #[tokio::test]
     async fn test_sse_stream_utf8_boundary() {
         // Test UTF-8 character split across chunk boundaries
@@ -0,0 +1,214 @@
+//! Combinators over [`StreamEvent`] streams.
+//!
+//! Most consumers of a live [`crate::Response`] want one of a few
+//! things out of it — the visible text as it arrives, the tool calls
+//! as each one completes, or the events up to the end of the turn.
+//! [`EventStreamExt`] provides those directly, for a `Response` or any
+//! other `Stream` of `Result<StreamEvent, Error>`:
+//!
+//! ```ignore
+//! use futures_util::StreamExt;
+//! use platformed_llm::EventStreamExt;
+//!
+//! let mut text = generate(&provider, &prompt, &config).await?.text_deltas();
+//! while let Some(delta) = text.next().await {
+//!     print!("{}", delta?);
+//! }
+//! ```
+//!
+//! Every combinator ends after the turn's `Done` — events a transport
+//! sends after it are never polled — and after the first error, which
+//! is yielded.
+
+use std::collections::HashSet;
+use std::future::ready;
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::accumulator::ResponseAccumulator;
+use crate::types::{FunctionCall, PartKind, StreamEvent};
+use crate::Error;
+
+/// Common ways to consume an event stream. Implemented for every
+/// `Stream<Item = Result<StreamEvent, Error>>`, including
+/// [`crate::Response`]. See the module docs.
+pub trait EventStreamExt: Stream<Item = Result<StreamEvent, Error>> + Sized {
+    /// The events up to and including `Done`, or up to the first
+    /// error.
+    fn until_done(self) -> impl Stream<Item = Result<StreamEvent, Error>> + Send
+    where
+        Self: Send,
+    {
+        end_after(self, |event| {
+            matches!(event, Ok(StreamEvent::Done { .. }) | Err(_))
+        })
+    }
+
+    /// The deltas of the turn's text parts, in order. Reasoning,
+    /// refusals and tool-call arguments are left out.
+    fn text_deltas(self) -> impl Stream<Item = Result<String, Error>> + Send
+    where
+        Self: Send,
+    {
+        let mut text_parts = HashSet::new();
+        self.until_done().filter_map(move |event| {
+            ready(match event {
+                Ok(StreamEvent::PartStart {
+                    index,
+                    kind: PartKind::Text,
+                }) => {
+                    text_parts.insert(index);
+                    None
+                }
+                Ok(StreamEvent::Delta { index, delta }) if text_parts.contains(&index) => {
+                    Some(Ok(delta))
+                }
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            })
+        })
+    }
+
+    /// Each tool call once its part closes, with its full arguments —
+    /// so a caller can start running one while the model is still
+    /// writing the next.
+    fn function_calls(self) -> impl Stream<Item = Result<FunctionCall, Error>> + Send
+    where
+        Self: Send,
+    {
+        let mut accumulator = ResponseAccumulator::new();
+        let calls = self.until_done().filter_map(move |event| {
+            ready(match event {
+                Ok(event) => {
+                    let closed = match event {
+                        StreamEvent::PartEnd { index } => Some(index),
+                        _ => None,
+                    };
+                    match accumulator.process_event(event) {
+                        Ok(()) => closed
+                            .and_then(|index| accumulator.function_call(index))
+                            .cloned()
+                            .map(Ok),
+                        Err(e) => Some(Err(e)),
+                    }
+                }
+                Err(e) => Some(Err(e)),
+            })
+        });
+        end_after(calls, Result::is_err)
+    }
+}
+
+impl<S> EventStreamExt for S where S: Stream<Item = Result<StreamEvent, Error>> {}
+
+/// Yield the items of `stream` up to and including the first one
+/// `last` accepts, without polling past it.
+fn end_after<S, F>(stream: S, last: F) -> impl Stream<Item = S::Item> + Send
+where
+    S: Stream + Send,
+    F: Fn(&S::Item) -> bool + Send,
+{
+    stream::unfold(Some((Box::pin(stream), last)), |state| async move {
+        let (mut stream, last) = state?;
+        let item = stream.next().await?;
+        let next = (!last(&item)).then_some((stream, last));
+        Some((item, next))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FinishReason, Usage};
+
+    fn events() -> Vec<Result<StreamEvent, Error>> {
+        vec![
+            Ok(StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::Reasoning,
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "thinking".into(),
+            }),
+            Ok(StreamEvent::PartEnd { index: 0 }),
+            Ok(StreamEvent::PartStart {
+                index: 1,
+                kind: PartKind::Text,
+            }),
+            Ok(StreamEvent::Delta {
+                index: 1,
+                delta: "Hel".into(),
+            }),
+            Ok(StreamEvent::Delta {
+                index: 1,
+                delta: "lo".into(),
+            }),
+            Ok(StreamEvent::PartEnd { index: 1 }),
+            Ok(StreamEvent::PartStart {
+                index: 2,
+                kind: PartKind::ToolCall {
+                    call_id: "c1".into(),
+                    name: "lookup".into(),
+                },
+            }),
+            Ok(StreamEvent::Delta {
+                index: 2,
+                delta: r#"{"q":"#.into(),
+            }),
+            Ok(StreamEvent::Delta {
+                index: 2,
+                delta: r#""rust"}"#.into(),
+            }),
+            Ok(StreamEvent::PartEnd { index: 2 }),
+            Ok(StreamEvent::Done {
+                finish_reason: FinishReason::ToolCalls,
+                usage: Usage::default(),
+            }),
+            // Never polled: everything stops at `Done`.
+            Err(Error::provider("Library", "after done")),
+        ]
+    }
+
+    #[tokio::test]
+    async fn splits_out_text_and_completed_calls() {
+        let deltas: Vec<_> = stream::iter(events()).text_deltas().collect().await;
+        let deltas: Vec<_> = deltas.into_iter().map(Result::unwrap).collect();
+        assert_eq!(deltas, ["Hel", "lo"]);
+
+        let calls: Vec<_> = stream::iter(events()).function_calls().collect().await;
+        let [Ok(call)] = calls.as_slice() else {
+            panic!("expected one call: {calls:?}");
+        };
+        assert_eq!(call.call_id, "c1");
+        assert_eq!(call.name, "lookup");
+        assert_eq!(call.arguments, r#"{"q":"rust"}"#);
+
+        let events: Vec<_> = stream::iter(events()).until_done().collect().await;
+        assert_eq!(events.len(), 12);
+        assert!(matches!(events.last(), Some(Ok(StreamEvent::Done { .. }))));
+    }
+
+    #[tokio::test]
+    async fn ends_after_the_first_error() {
+        let failing = vec![
+            Ok(StreamEvent::PartStart {
+                index: 0,
+                kind: PartKind::Text,
+            }),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "partial".into(),
+            }),
+            Err(Error::provider("Library", "connection reset")),
+            Ok(StreamEvent::Delta {
+                index: 0,
+                delta: "unreachable".into(),
+            }),
+        ];
+        let deltas: Vec<_> = stream::iter(failing).text_deltas().collect().await;
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].as_deref().unwrap(), "partial");
+        assert!(deltas[1].is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! Whole-call deadlines.
+//!
+//! The default transport sets only a connect timeout: a streaming
+//! response has no natural length, and a reasoning model can think for
+//! minutes. That is the wrong default for interactive callers, who would
+//! rather fail fast than hang. Two knobs cover both ends:
+//!
+//! - **Per request** — [`crate::ConfigBuilder::timeout`]. Enforced by
+//!   [`crate::generate`] around the provider call *and* the response
+//!   stream.
+//! - **Per provider** — [`TimeoutProvider`] / [`TimeoutLayer`] apply a
+//!   default to every call that doesn't set its own. The factory wraps
+//!   its provider in one when
+//!   [`crate::ProviderConfig::with_timeout`] is set.
+//!
+//! A per-request timeout always wins over the provider default, in
+//! either direction — a long batch job can extend it, a UI call can
+//! shorten it. When the deadline passes, the call (or the stream, if it
+//! already started) yields [`Error::Timeout`] and stops; dropping the
+//! inner stream cancels the upstream request.
+//!
+//! Connect timeouts are a transport concern: see
+//! [`crate::ProviderConfig::with_connect_timeout`].
+//!
+//! # Idle timeouts
+//!
+//! A whole-call deadline long enough for a slow reasoning model is far
+//! too long to notice a connection that silently died mid-stream. The
+//! idle timeout bounds the *gap* between response events instead —
+//! per request via [`crate::ConfigBuilder::idle_timeout`], per provider
+//! via [`IdleTimeoutProvider`] (what
+//! [`crate::ProviderConfig::with_idle_timeout`] installs). The clock
+//! starts when the call does, restarts on every event, and on expiry
+//! the call or stream yields [`Error::IdleTimeout`].
+//!
+//! Only unified [`crate::StreamEvent`]s restart the clock. Transport
+//! keep-alives — SSE comments, Anthropic `ping` events, empty Vertex
+//! chunks — never become events, so a connection that stays open on
+//! pings alone while producing nothing still counts as stalled.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::{select, Either};
+use futures_util::StreamExt;
+use tokio::time::Instant;
+
+use crate::layer::{ProviderLayer, SharedProvider};
+use crate::{Capabilities, Error, EventStream, Prompt, Provider, RawConfig, Response};
+
+/// Run `call` and its response stream under one `timeout` measured from
+/// now. The deadline spans both, so a response that starts promptly but
+/// streams forever still times out.
+pub(crate) async fn with_deadline(
+    timeout: Duration,
+    call: impl Future<Output = Result<Response, Error>>,
+) -> Result<Response, Error> {
+    let deadline = Instant::now() + timeout;
+    let response = tokio::time::timeout_at(deadline, call)
+        .await
+        .map_err(|_| Error::timeout(timeout))??;
+    Ok(response.map_stream(move |stream| bounded(stream, deadline, timeout)))
+}
+
+fn bounded(stream: EventStream, deadline: Instant, timeout: Duration) -> EventStream {
+    let sleep = Box::pin(tokio::time::sleep_until(deadline));
+    Box::pin(futures_util::stream::unfold(
+        Some((stream, sleep)),
+        move |state| async move {
+            let (mut stream, sleep) = state?;
+            match select(stream.next(), sleep).await {
+                Either::Left((Some(item), sleep)) => Some((item, Some((stream, sleep)))),
+                Either::Left((None, _)) => None,
+                Either::Right(_) => Some((Err(Error::timeout(timeout)), None)),
+            }
+        },
+    ))
+}
+
+/// Run `call` and its response stream with at most `idle` between
+/// events (or before the response arrives).
+pub(crate) async fn with_idle_timeout(
+    idle: Duration,
+    call: impl Future<Output = Result<Response, Error>>,
+) -> Result<Response, Error> {
+    let response = tokio::time::timeout(idle, call)
+        .await
+        .map_err(|_| Error::idle_timeout(idle))??;
+    Ok(response.map_stream(move |stream| watchdog(stream, idle)))
+}
+
+fn watchdog(stream: EventStream, idle: Duration) -> EventStream {
+    Box::pin(futures_util::stream::unfold(
+        Some(stream),
+        move |state| async move {
+            let mut stream = state?;
+            match tokio::time::timeout(idle, stream.next()).await {
+                Ok(Some(item)) => Some((item, Some(stream))),
+                Ok(None) => None,
+                Err(_) => Some((Err(Error::idle_timeout(idle)), None)),
+            }
+        },
+    ))
+}
+
+/// A [`Provider`] wrapper applying a default deadline to every call
+/// whose config doesn't set [`RawConfig::timeout`]. See the module
+/// docs.
+#[derive(Clone)]
+pub struct TimeoutProvider {
+    inner: SharedProvider,
+    timeout: Duration,
+}
+
+impl TimeoutProvider {
+    /// Give `inner`'s calls `timeout` unless they carry their own.
+    pub fn new(inner: impl Provider, timeout: Duration) -> Self {
+        Self::from_shared(Arc::new(inner), timeout)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// The default deadline.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl std::fmt::Debug for TimeoutProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimeoutProvider")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for TimeoutProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let timeout = config.timeout.unwrap_or(self.timeout);
+        with_deadline(timeout, self.inner.generate(prompt, config)).await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// A [`Provider`] wrapper applying a default idle timeout to every
+/// call whose config doesn't set [`RawConfig::idle_timeout`]. See the
+/// [module docs](self#idle-timeouts).
+#[derive(Clone)]
+pub struct IdleTimeoutProvider {
+    inner: SharedProvider,
+    idle: Duration,
+}
+
+impl IdleTimeoutProvider {
+    /// Abort `inner`'s calls after `idle` without an event, unless
+    /// they carry their own idle timeout.
+    pub fn new(inner: impl Provider, idle: Duration) -> Self {
+        Self::from_shared(Arc::new(inner), idle)
+    }
+
+    /// [`Self::new`] over an already-shared provider.
+    pub fn from_shared(inner: SharedProvider, idle: Duration) -> Self {
+        Self { inner, idle }
+    }
+
+    /// The default idle timeout.
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle
+    }
+}
+
+impl std::fmt::Debug for IdleTimeoutProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdleTimeoutProvider")
+            .field("idle", &self.idle)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for IdleTimeoutProvider {
+    async fn generate(&self, prompt: &Prompt, config: &RawConfig) -> Result<Response, Error> {
+        let idle = config.idle_timeout.unwrap_or(self.idle);
+        with_idle_timeout(idle, self.inner.generate(prompt, config)).await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self, model: &str) -> Capabilities {
+        self.inner.capabilities(model)
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in a [`TimeoutProvider`].
+#[derive(Debug, Clone)]
+pub struct TimeoutLayer {
+    timeout: Duration,
+}
+
+impl TimeoutLayer {
+    /// A layer giving every wrapped provider a `timeout` default.
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl ProviderLayer for TimeoutLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(TimeoutProvider::from_shared(inner, self.timeout))
+    }
+}
+
+/// [`ProviderLayer`] that wraps providers in an [`IdleTimeoutProvider`].
+#[derive(Debug, Clone)]
+pub struct IdleTimeoutLayer {
+    idle: Duration,
+}
+
+impl IdleTimeoutLayer {
+    /// A layer giving every wrapped provider an `idle` default.
+    pub fn new(idle: Duration) -> Self {
+        Self { idle }
+    }
+}
+
+impl ProviderLayer for IdleTimeoutLayer {
+    fn layer(&self, inner: SharedProvider) -> SharedProvider {
+        Arc::new(IdleTimeoutProvider::from_shared(inner, self.idle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use crate::{generate, Config};
+
+    /// Yields its first event, then stalls forever.
+    struct Stalling;
+
+    #[async_trait::async_trait]
+    impl Provider for Stalling {
+        async fn generate(&self, _: &Prompt, _: &RawConfig) -> Result<Response, Error> {
+            let first = futures_util::stream::iter([Ok(crate::StreamEvent::PartStart {
+                index: 0,
+                kind: crate::PartKind::Text,
+            })]);
+            Ok(Response::from_stream(
+                first.chain(futures_util::stream::pending()),
+            ))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_stream_times_out_mid_stream() {
+        let provider = TimeoutProvider::new(Stalling, Duration::from_secs(5));
+        let response = generate(&provider, &Prompt::user("x"), &Config::builder("m").build())
+            .await
+            .unwrap();
+        let err = response.text().await.unwrap_err();
+        assert!(matches!(err, Error::Timeout(d) if d == Duration::from_secs(5)));
+        assert!(err.is_retryable());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn per_request_timeout_overrides_the_default() {
+        let provider = TimeoutProvider::new(Stalling, Duration::from_secs(60));
+        let config = Config::builder("m")
+            .timeout(Duration::from_millis(300))
+            .build();
+        let started = Instant::now();
+        let err = generate(&provider, &Prompt::user("x"), &config)
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout(_)));
+        assert_eq!(started.elapsed(), Duration::from_millis(300));
+    }
+
+    /// Emits an event every `gap`, forever.
+    struct Trickling(Duration);
+
+    #[async_trait::async_trait]
+    impl Provider for Trickling {
+        async fn generate(&self, _: &Prompt, _: &RawConfig) -> Result<Response, Error> {
+            let gap = self.0;
+            Ok(Response::from_stream(futures_util::stream::unfold(
+                0,
+                move |index| async move {
+                    tokio::time::sleep(gap).await;
+                    let event = crate::StreamEvent::PartStart {
+                        index,
+                        kind: crate::PartKind::Text,
+                    };
+                    Some((Ok(event), index + 1))
+                },
+            )))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_timeout_fires_on_a_stall_not_on_a_long_stream() {
+        let provider = IdleTimeoutProvider::new(Stalling, Duration::from_secs(5));
+        let mut stream = generate(&provider, &Prompt::user("x"), &Config::builder("m").build())
+            .await
+            .unwrap()
+            .stream();
+        assert!(stream.next().await.unwrap().is_ok());
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, Error::IdleTimeout(d) if d == Duration::from_secs(5)));
+        assert!(err.is_retryable());
+        assert!(stream.next().await.is_none());
+
+        // Events every second keep a 5 s watchdog quiet for well past
+        // 5 s in total.
+        let config = Config::builder("m")
+            .idle_timeout(Duration::from_secs(5))
+            .build();
+        let events: Vec<_> = generate(
+            &Trickling(Duration::from_secs(1)),
+            &Prompt::user("x"),
+            &config,
+        )
+        .await
+        .unwrap()
+        .stream()
+        .take(20)
+        .collect()
+        .await;
+        assert!(events.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fast_calls_are_untouched() {
+        let provider = TimeoutProvider::new(MockProvider::with_text("hi"), Duration::from_secs(1));
+        let text = generate(&provider, &Prompt::user("x"), &Config::builder("m").build())
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(text, "hi");
+    }
+}
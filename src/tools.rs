@@ -0,0 +1,338 @@
+//! Running the model's tool calls.
+//!
+//! A turn can end with several [`FunctionCall`]s at once. A
+//! [`ToolRegistry`] maps tool names to [`ToolHandler`]s, runs a turn's
+//! calls concurrently (up to a limit), and hands back the results as
+//! the single user turn every provider expects next: one
+//! [`UserPart::ToolResult`] per call, in call order, each carrying its
+//! call's `call_id`. Anthropic requires all results of a turn in one
+//! message and Gemini matches them to calls by position, so the order
+//! holds however the handlers finish.
+//!
+//! ```ignore
+//! use platformed_llm::{ToolOutput, ToolRegistry};
+//!
+//! let tools = ToolRegistry::new()
+//!     .with_fn("get_weather", |args| async move {
+//!         ToolOutput::json(&weather(&args).await)
+//!     })
+//!     .with_concurrency(4);
+//!
+//! let mut prompt = Prompt::user("Weather in Paris and Rome?");
+//! loop {
+//!     let response = generate(&provider, &prompt, &config).await?.buffer().await?;
+//!     if response.function_calls().is_empty() {
+//!         break;
+//!     }
+//!     prompt = tools.continue_prompt(prompt, &response).await;
+//! }
+//! ```
+//!
+//! A call naming an unregistered tool gets an error result rather than
+//! failing the batch, so the model can recover on its next turn.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+
+use crate::response::CompleteResponse;
+use crate::types::{FunctionCall, InputItem, UserPart};
+use crate::Prompt;
+
+/// What a tool returns: the result content and whether it is an error
+/// report. See [`UserPart::ToolResult`].
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    content: Vec<UserPart>,
+    is_error: bool,
+}
+
+impl ToolOutput {
+    /// A plain-text result.
+    pub fn text(output: impl Into<String>) -> Self {
+        Self::parts(vec![UserPart::Text(output.into())])
+    }
+
+    /// A structured result, sent as JSON text (as a native object on
+    /// Gemini).
+    pub fn json(output: &serde_json::Value) -> Self {
+        Self::text(output.to_string())
+    }
+
+    /// A result built from arbitrary parts, e.g. an image and a caption.
+    pub fn parts(content: Vec<UserPart>) -> Self {
+        Self {
+            content,
+            is_error: false,
+        }
+    }
+
+    /// A failure the model should read as one.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            is_error: true,
+            ..Self::text(message)
+        }
+    }
+
+    /// Whether this is an error report.
+    pub fn is_error(&self) -> bool {
+        self.is_error
+    }
+
+    fn into_part(self, call_id: &str) -> UserPart {
+        UserPart::ToolResult {
+            call_id: call_id.to_string(),
+            content: self.content,
+            is_error: self.is_error,
+        }
+    }
+}
+
+impl From<String> for ToolOutput {
+    fn from(output: String) -> Self {
+        Self::text(output)
+    }
+}
+
+impl From<&str> for ToolOutput {
+    fn from(output: &str) -> Self {
+        Self::text(output)
+    }
+}
+
+/// Executes one tool.
+#[async_trait::async_trait]
+pub trait ToolHandler: Send + Sync {
+    /// Run the tool for `call`. The arguments are
+    /// [`FunctionCall::arguments`], the model's JSON text. Failures
+    /// are reported as [`ToolOutput::error`].
+    async fn call(&self, call: &FunctionCall) -> ToolOutput;
+}
+
+/// [`ToolHandler`] over an async closure taking the arguments.
+struct FnHandler<F>(F);
+
+#[async_trait::async_trait]
+impl<F, Fut> ToolHandler for FnHandler<F>
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = ToolOutput> + Send,
+{
+    async fn call(&self, call: &FunctionCall) -> ToolOutput {
+        (self.0)(call.arguments.clone()).await
+    }
+}
+
+/// Tool handlers by name, plus how many may run at once. See the
+/// module docs.
+#[derive(Clone)]
+pub struct ToolRegistry {
+    handlers: HashMap<String, Arc<dyn ToolHandler>>,
+    concurrency: usize,
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+/// Calls run at once unless [`ToolRegistry::with_concurrency`] says
+/// otherwise.
+const DEFAULT_CONCURRENCY: usize = 8;
+
+impl ToolRegistry {
+    /// An empty registry running up to 8 calls at once.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` for tool `name`, replacing any previous one.
+    pub fn with_handler(
+        mut self,
+        name: impl Into<String>,
+        handler: impl ToolHandler + 'static,
+    ) -> Self {
+        self.handlers.insert(name.into(), Arc::new(handler));
+        self
+    }
+
+    /// Register an async closure taking the call's JSON arguments.
+    pub fn with_fn<F, Fut>(self, name: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ToolOutput> + Send + 'static,
+    {
+        self.with_handler(name, FnHandler(handler))
+    }
+
+    /// Run at most `limit` calls at once (at least one).
+    pub fn with_concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Whether a handler is registered for `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Run `calls` and return their results in the same order.
+    pub async fn execute(&self, calls: &[&FunctionCall]) -> Vec<ToolOutput> {
+        futures_util::stream::iter(calls)
+            .map(|call| async move {
+                match self.handlers.get(&call.name) {
+                    Some(handler) => handler.call(call).await,
+                    None => ToolOutput::error(format!("unknown tool `{}`", call.name)),
+                }
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await
+    }
+
+    /// Run every tool call in `response` and return the user turn
+    /// answering them, or `None` when there are none.
+    pub async fn results(&self, response: &CompleteResponse) -> Option<InputItem> {
+        let calls = response.function_calls();
+        if calls.is_empty() {
+            return None;
+        }
+        let outputs = self.execute(&calls).await;
+        Some(InputItem::User {
+            content: calls
+                .iter()
+                .zip(outputs)
+                .map(|(call, output)| output.into_part(&call.call_id))
+                .collect(),
+            name: None,
+            metadata: Default::default(),
+        })
+    }
+
+    /// Append `response` and the results of its tool calls to
+    /// `prompt` — the next request of a tool loop.
+    pub async fn continue_prompt(&self, prompt: Prompt, response: &CompleteResponse) -> Prompt {
+        let results = self.results(response).await;
+        let prompt = prompt.with_response(response);
+        match results {
+            Some(item) => prompt.with_item(item),
+            None => prompt,
+        }
+    }
+}
+
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tools: Vec<_> = self.handlers.keys().collect();
+        tools.sort();
+        f.debug_struct("ToolRegistry")
+            .field("tools", &tools)
+            .field("concurrency", &self.concurrency)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AssistantPart, FinishReason, Usage};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn call(id: &str, name: &str, arguments: &str) -> AssistantPart {
+        AssistantPart::ToolCall(FunctionCall {
+            call_id: id.into(),
+            name: name.into(),
+            arguments: arguments.into(),
+            provider_signature: None,
+            original_arguments: None,
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runs_calls_concurrently_and_keeps_call_order() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (r, p) = (running.clone(), peak.clone());
+        let tools = ToolRegistry::new()
+            .with_fn("sleep", move |args| {
+                let (running, peak) = (r.clone(), p.clone());
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    let ms: u64 = args.parse().unwrap();
+                    tokio::time::sleep(Duration::from_millis(ms)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    ToolOutput::text(format!("slept {ms}"))
+                }
+            })
+            .with_concurrency(2);
+        let response = CompleteResponse {
+            content: vec![
+                call("c1", "sleep", "30"),
+                call("c2", "sleep", "10"),
+                call("c3", "missing", "{}"),
+                call("c4", "sleep", "20"),
+            ],
+            finish_reason: FinishReason::ToolCalls,
+            usage: Usage::default(),
+            safety: None,
+            timing: None,
+        };
+
+        let prompt = tools.continue_prompt(Prompt::user("go"), &response).await;
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        let [_, InputItem::Assistant { .. }, InputItem::User { content, .. }] = prompt.items()
+        else {
+            panic!(
+                "expected user, assistant, tool results: {:?}",
+                prompt.items()
+            );
+        };
+        let results: Vec<_> = content
+            .iter()
+            .map(|part| match part {
+                UserPart::ToolResult {
+                    call_id,
+                    content,
+                    is_error,
+                } => match content.as_slice() {
+                    [UserPart::Text(text)] => (call_id.as_str(), text.as_str(), *is_error),
+                    other => panic!("unexpected result content {other:?}"),
+                },
+                other => panic!("unexpected part {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("c1", "slept 30", false),
+                ("c2", "slept 10", false),
+                ("c3", "unknown tool `missing`", true),
+                ("c4", "slept 20", false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn no_calls_means_no_results() {
+        let response = CompleteResponse {
+            content: vec![AssistantPart::Text {
+                content: "done".into(),
+                annotations: Vec::new(),
+            }],
+            finish_reason: FinishReason::Stop,
+            usage: Usage::default(),
+            safety: None,
+            timing: None,
+        };
+        assert!(ToolRegistry::new().results(&response).await.is_none());
+    }
+}
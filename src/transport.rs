@@ -35,7 +35,7 @@
 
 use std::pin::Pin;
 use std::sync::Arc;
-#[cfg(feature = "reqwest")]
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -48,10 +48,28 @@ use crate::Error;
 /// deliberately do **not** set a total request timeout — streaming
 /// responses (especially reasoning / extended thinking) can
 /// legitimately run for many minutes.
-#[cfg(feature = "reqwest")]
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
 const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
-/// A request to be sent by a [`Transport`]. POST-only for now.
+/// Which HTTP version the factory's default client speaks. See
+/// [`crate::ProviderConfig::with_http_version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum HttpVersion {
+    /// Negotiate over TLS ALPN: HTTP/2 where the server offers it and
+    /// the `http2` feature is on, HTTP/1.1 otherwise.
+    #[default]
+    Auto,
+    /// Always HTTP/1.1, e.g. behind a proxy that mishandles HTTP/2.
+    Http1Only,
+    /// HTTP/2 from the first byte, without negotiation — for cleartext
+    /// (h2c) gateways and servers known to speak it. Needs the `http2`
+    /// feature.
+    Http2PriorKnowledge,
+}
+
+/// A request to be sent by a [`Transport`]: a `POST` via
+/// [`Transport::send`], or a bodiless `GET` via [`Transport::send_get`].
 #[derive(Debug, Clone)]
 pub struct TransportRequest {
     /// Full request URL.
@@ -62,14 +80,17 @@ pub struct TransportRequest {
     pub body: Vec<u8>,
 }
 
-/// HTTP method for a streaming [`UploadRequest`]. File-upload endpoints use
-/// `POST` (multipart create) or `PUT` (resumable-session data).
+/// HTTP method of a request. File-upload endpoints use `POST`
+/// (multipart create) or `PUT` (resumable-session data); `GET` only
+/// comes from [`Transport::send_get`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Method {
     /// HTTP `POST`.
     Post,
     /// HTTP `PUT`.
     Put,
+    /// HTTP `GET`.
+    Get,
 }
 
 /// A streaming-body request used for **file uploads** — the one place the
@@ -111,6 +132,18 @@ pub struct TransportResponse {
     pub body: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>,
 }
 
+/// Merge `extra` headers into `headers`. An entry whose name is
+/// already present (ASCII case-insensitively) replaces it rather than
+/// duplicating it, so callers can pin e.g. an API version the provider
+/// would otherwise set; later entries in `extra` win over earlier ones.
+#[cfg_attr(not(any(feature = "openai", feature = "vertex")), allow(dead_code))]
+pub(crate) fn merge_headers(headers: &mut Vec<(String, String)>, extra: &[(String, String)]) {
+    for (name, value) in extra {
+        headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        headers.push((name.clone(), value.clone()));
+    }
+}
+
 /// Parse a `Retry-After` header value into whole seconds.
 ///
 /// Handles **both** forms RFC 7231 defines for the header:
@@ -143,6 +176,24 @@ pub(crate) fn parse_retry_after(value: Option<&str>) -> Option<u64> {
     parse_imf_fixdate_offset_seconds(raw)
 }
 
+/// Stand-in for a missing `Retry-After`: turn a provider's "quota
+/// window resets in …" duration (OpenAI `x-ratelimit-reset-*`,
+/// Anthropic `anthropic-ratelimit-*-reset`, Gemini `RetryInfo`) into
+/// the whole-second hint [`crate::Error::rate_limit`] carries.
+///
+/// Rounds *up*: the reset is the earliest instant the quota can have
+/// room again, so truncating `1.5s` to `1s` would retry straight into
+/// the same 429.
+#[cfg(any(feature = "openai", feature = "google", feature = "anthropic-vertex"))]
+pub(crate) fn retry_after_from_reset(reset: std::time::Duration) -> u64 {
+    let secs = reset.as_secs();
+    if reset.subsec_nanos() > 0 {
+        secs.saturating_add(1)
+    } else {
+        secs
+    }
+}
+
 /// Parse an IMF-fixdate / RFC 5322 date (`"Wed, 21 Oct 2026 07:28:00 GMT"`)
 /// and return the number of seconds between *now* and that instant.
 /// Returns `Some(0)` for past dates (clock skew → retry immediately),
@@ -255,6 +306,18 @@ pub trait TransportImpl: Send + Sync + 'static {
             "this transport does not support file uploads (send_upload)",
         ))
     }
+
+    /// Issue a `GET` to `req.url` with `req.headers`; `req.body` is
+    /// ignored. Used to poll resources a provider stored server-side,
+    /// such as OpenAI background responses.
+    ///
+    /// Default implementation errors, like [`Self::send_upload`].
+    async fn send_get(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        Err(Error::config(format!(
+            "this transport does not support GET requests (send_get to {})",
+            req.url
+        )))
+    }
 }
 
 /// The shared transport handle that providers store. Cheap to clone
@@ -278,9 +341,10 @@ impl Transport {
     /// **Footgun:** there is no overall or idle-read timeout (a
     /// streaming response has no fixed duration). A server that
     /// accepts the connection then stalls will hang `generate()`
-    /// indefinitely. Wrap calls in [`tokio::time::timeout`], or
-    /// supply a custom client via [`Self::reqwest_with_client`] /
-    /// [`Self::new`] with your own idle timeout.
+    /// indefinitely. Bound calls with [`crate::ConfigBuilder::timeout`]
+    /// or a [`crate::TimeoutProvider`], or supply a custom client via
+    /// [`Self::reqwest_with_client`] / [`Self::new`] with your own idle
+    /// timeout.
     ///
     /// Available when any hosted-provider feature
     /// (`openai` / `google` / `anthropic-vertex`) is enabled.
@@ -296,6 +360,12 @@ impl Transport {
         Self::new(ReqwestTransport::new(client))
     }
 
+    /// Whether both handles share one underlying transport.
+    #[cfg(all(test, feature = "openai"))]
+    pub(crate) fn ptr_eq(&self, other: &Transport) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
     /// Send a request via the underlying transport.
     pub async fn send(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
         self.inner.send(req).await
@@ -305,6 +375,11 @@ impl Transport {
     pub async fn send_upload(&self, req: UploadRequest) -> Result<TransportResponse, Error> {
         self.inner.send_upload(req).await
     }
+
+    /// Send a bodiless `GET` via the underlying transport.
+    pub async fn send_get(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        self.inner.send_get(req).await
+    }
 }
 
 impl std::fmt::Debug for Transport {
@@ -333,14 +408,29 @@ impl ReqwestTransport {
 
     /// Build with the default client config used by the lib.
     pub fn with_default_client() -> Result<Self, Error> {
-        let client = reqwest::Client::builder()
-            .connect_timeout(DEFAULT_CONNECT_TIMEOUT)
-            .build()
-            .map_err(Error::from)?;
+        let client = default_client_builder().build().map_err(Error::from)?;
         Ok(Self::new(client))
     }
 }
 
+/// The lib's default client settings, as a builder the factory can
+/// layer [`crate::ProviderConfig`] network options onto.
+#[cfg(feature = "reqwest")]
+pub(crate) fn default_client_builder() -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    // The browser owns connection setup under `fetch`.
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = builder.connect_timeout(DEFAULT_CONNECT_TIMEOUT);
+    // Opt-in per config even when the decoders are compiled in: a
+    // feature enabled elsewhere in the dependency graph shouldn't change
+    // what this client asks for.
+    #[cfg(all(feature = "gzip", not(target_arch = "wasm32")))]
+    let builder = builder.gzip(false);
+    #[cfg(all(feature = "brotli", not(target_arch = "wasm32")))]
+    let builder = builder.brotli(false);
+    builder
+}
+
 #[cfg(feature = "reqwest")]
 #[async_trait]
 impl TransportImpl for ReqwestTransport {
@@ -349,38 +439,15 @@ impl TransportImpl for ReqwestTransport {
         for (k, v) in &req.headers {
             builder = builder.header(k, v);
         }
-        let response = builder.send().await?;
-
-        let status = response.status().as_u16();
-        let headers: Vec<(String, String)> = response
-            .headers()
-            .iter()
-            .filter_map(|(k, v)| {
-                v.to_str()
-                    .ok()
-                    .map(|s| (k.as_str().to_string(), s.to_string()))
-            })
-            .collect();
-
-        // Map reqwest's per-chunk stream error onto ours. Dropping this
-        // boxed stream drops the underlying reqwest body, which closes
-        // the connection — preserving the cancellation contract.
-        let body = response
-            .bytes_stream()
-            .map(|chunk| chunk.map_err(Error::from));
-        let body: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>> = Box::pin(body);
-
-        Ok(TransportResponse {
-            status,
-            headers,
-            body,
-        })
+        into_transport_response(send_request(builder).await?)
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
     async fn send_upload(&self, req: UploadRequest) -> Result<TransportResponse, Error> {
         let mut builder = match req.method {
             Method::Post => self.client.post(&req.url),
             Method::Put => self.client.put(&req.url),
+            Method::Get => self.client.get(&req.url),
         };
         for (k, v) in &req.headers {
             builder = builder.header(k, v);
@@ -392,31 +459,61 @@ impl TransportImpl for ReqwestTransport {
         // dropping the response (and thus this request future) cancels it.
         builder = builder.body(reqwest::Body::wrap_stream(req.body));
 
-        let response = builder.send().await?;
+        into_transport_response(send_request(builder).await?)
+    }
 
-        let status = response.status().as_u16();
-        let headers: Vec<(String, String)> = response
-            .headers()
-            .iter()
-            .filter_map(|(k, v)| {
-                v.to_str()
-                    .ok()
-                    .map(|s| (k.as_str().to_string(), s.to_string()))
-            })
-            .collect();
-        let body = response
-            .bytes_stream()
-            .map(|chunk| chunk.map_err(Error::from));
-        let body: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>> = Box::pin(body);
-
-        Ok(TransportResponse {
-            status,
-            headers,
-            body,
-        })
+    async fn send_get(&self, req: TransportRequest) -> Result<TransportResponse, Error> {
+        let mut builder = self.client.get(&req.url);
+        for (k, v) in &req.headers {
+            builder = builder.header(k, v);
+        }
+        into_transport_response(send_request(builder).await?)
     }
 }
 
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+async fn send_request(builder: reqwest::RequestBuilder) -> Result<reqwest::Response, Error> {
+    Ok(builder.send().await?)
+}
+
+/// Browser `fetch` futures and bodies are `!Send`. wasm32 is
+/// single-threaded, so `SendWrapper` satisfies the `Send` bounds the
+/// transport contract carries without ever crossing a thread.
+#[cfg(all(feature = "reqwest", target_arch = "wasm32"))]
+async fn send_request(builder: reqwest::RequestBuilder) -> Result<reqwest::Response, Error> {
+    Ok(send_wrapper::SendWrapper::new(builder.send()).await?)
+}
+
+#[cfg(feature = "reqwest")]
+fn into_transport_response(response: reqwest::Response) -> Result<TransportResponse, Error> {
+    let status = response.status().as_u16();
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .filter_map(|(k, v)| {
+            v.to_str()
+                .ok()
+                .map(|s| (k.as_str().to_string(), s.to_string()))
+        })
+        .collect();
+
+    // Map reqwest's per-chunk stream error onto ours. Dropping this
+    // boxed stream drops the underlying reqwest body, which closes
+    // the connection — preserving the cancellation contract.
+    let body = response
+        .bytes_stream()
+        .map(|chunk| chunk.map_err(Error::from));
+    #[cfg(target_arch = "wasm32")]
+    let body = send_wrapper::SendWrapper::new(body);
+    let body: Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>> = Box::pin(body);
+
+    Ok(TransportResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,6 +547,16 @@ mod tests {
         assert_eq!(parse_retry_after(Some("not a number")), None);
     }
 
+    #[cfg(any(feature = "openai", feature = "google", feature = "anthropic-vertex"))]
+    #[test]
+    fn retry_after_from_reset_rounds_up() {
+        use std::time::Duration;
+        assert_eq!(retry_after_from_reset(Duration::from_secs(30)), 30);
+        assert_eq!(retry_after_from_reset(Duration::from_millis(1500)), 2);
+        assert_eq!(retry_after_from_reset(Duration::from_millis(1)), 1);
+        assert_eq!(retry_after_from_reset(Duration::ZERO), 0);
+    }
+
     /// HTTP-date form: must convert to delta-seconds against the
     /// current clock. A past date floors to 0 (retry now); a future
     /// date returns a positive delta.
@@ -0,0 +1,232 @@
+//! Conversions between this crate's request types and `async-openai`'s
+//! chat request types, for codebases moving over one call site at a
+//! time.
+//!
+//! Messages travel through the chat `messages` array both crates
+//! serialize to, so they share the mapping (and the lossiness) of
+//! [`Prompt::from_chat_messages`] and [`Prompt::to_chat_messages`].
+//! Tools convert function-for-function; builtin tools and
+//! async-openai's custom tools have no counterpart and are an error. A
+//! [`CreateChatCompletionRequest`] splits into its [`Prompt`] and its
+//! [`Config`]; request fields with no [`Config`] setting (`n`,
+//! `logit_bias`, `audio`, …) are ignored.
+
+use async_openai::types::chat::{
+    ChatCompletionRequestMessage, ChatCompletionTool, ChatCompletionTools,
+    CreateChatCompletionRequest, FunctionObject, StopConfiguration,
+};
+use serde_json::Value;
+
+use super::chat_messages::{raw_json, response_format, tool_choice};
+use super::message::Tool;
+use super::prompt::Prompt;
+use crate::{Config, Error};
+
+impl TryFrom<&[ChatCompletionRequestMessage]> for Prompt {
+    type Error = Error;
+
+    fn try_from(messages: &[ChatCompletionRequestMessage]) -> Result<Self, Error> {
+        let messages = serde_json::to_value(messages)
+            .map_err(|e| Error::invalid_prompt(format!("unserializable chat messages: {e}")))?;
+        Prompt::from_chat_messages(&messages)
+    }
+}
+
+impl TryFrom<Vec<ChatCompletionRequestMessage>> for Prompt {
+    type Error = Error;
+
+    fn try_from(messages: Vec<ChatCompletionRequestMessage>) -> Result<Self, Error> {
+        Prompt::try_from(messages.as_slice())
+    }
+}
+
+impl TryFrom<&Prompt> for Vec<ChatCompletionRequestMessage> {
+    type Error = Error;
+
+    fn try_from(prompt: &Prompt) -> Result<Self, Error> {
+        serde_json::from_value(prompt.to_chat_messages()?).map_err(|e| {
+            Error::invalid_prompt(format!("chat messages async-openai can't read: {e}"))
+        })
+    }
+}
+
+impl TryFrom<&ChatCompletionTools> for Tool {
+    type Error = Error;
+
+    fn try_from(tool: &ChatCompletionTools) -> Result<Self, Error> {
+        let function = match tool {
+            ChatCompletionTools::Function(tool) => &tool.function,
+            ChatCompletionTools::Custom(tool) => {
+                return Err(Error::invalid_prompt(format!(
+                    "custom tool {:?} has no equivalent",
+                    tool.custom.name
+                )))
+            }
+        };
+        let parameters = match &function.parameters {
+            Some(parameters) => raw_json(parameters.to_string())?,
+            None => raw_json(r#"{"type":"object","properties":{}}"#.to_string())?,
+        };
+        Ok(Tool::function(
+            function.name.clone(),
+            function.description.clone(),
+            parameters,
+        ))
+    }
+}
+
+impl TryFrom<&Tool> for ChatCompletionTools {
+    type Error = Error;
+
+    fn try_from(tool: &Tool) -> Result<Self, Error> {
+        let function = tool.as_function().ok_or_else(|| {
+            Error::invalid_prompt("builtin tools have no async-openai equivalent")
+        })?;
+        let parameters = serde_json::from_str::<Value>(function.parameters.get())
+            .map_err(|e| Error::invalid_prompt(format!("invalid JSON schema: {e}")))?;
+        Ok(ChatCompletionTools::Function(ChatCompletionTool {
+            function: FunctionObject {
+                name: function.name.clone(),
+                description: function.description.clone(),
+                parameters: Some(parameters),
+                strict: None,
+            },
+        }))
+    }
+}
+
+impl TryFrom<&CreateChatCompletionRequest> for Prompt {
+    type Error = Error;
+
+    fn try_from(request: &CreateChatCompletionRequest) -> Result<Self, Error> {
+        Prompt::try_from(request.messages.as_slice())
+    }
+}
+
+impl TryFrom<&CreateChatCompletionRequest> for Config {
+    type Error = Error;
+
+    fn try_from(request: &CreateChatCompletionRequest) -> Result<Self, Error> {
+        #[allow(deprecated)]
+        let max_tokens = request.max_completion_tokens.or(request.max_tokens);
+        #[allow(deprecated)]
+        let user = request.safety_identifier.as_ref().or(request.user.as_ref());
+
+        let mut builder = Config::builder(request.model.clone());
+        if let Some(temperature) = request.temperature {
+            builder = builder.temperature(temperature);
+        }
+        if let Some(top_p) = request.top_p {
+            builder = builder.top_p(top_p);
+        }
+        if let Some(max_tokens) = max_tokens {
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Some(stop) = &request.stop {
+            builder = builder.stop(match stop {
+                StopConfiguration::String(stop) => vec![stop.clone()],
+                StopConfiguration::StringArray(stop) => stop.clone(),
+            });
+        }
+        if let Some(penalty) = request.presence_penalty {
+            builder = builder.presence_penalty(penalty);
+        }
+        if let Some(penalty) = request.frequency_penalty {
+            builder = builder.frequency_penalty(penalty);
+        }
+        if let Some(tools) = &request.tools {
+            let tools = tools
+                .iter()
+                .map(Tool::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            builder = builder.tools(tools);
+        }
+        if let Some(choice) = &request.tool_choice {
+            builder = builder.tool_choice(tool_choice(&json(choice)?)?);
+        }
+        if let Some(parallel) = request.parallel_tool_calls {
+            builder = builder.parallel_tool_calls(parallel);
+        }
+        if let Some(user) = user {
+            builder = builder.user_id(user.clone());
+        }
+        if let Some(key) = &request.prompt_cache_key {
+            builder = builder.prompt_cache_key(key.clone());
+        }
+        if let Some(format) = &request.response_format {
+            builder = builder.response_format(response_format(&json(format)?)?);
+        }
+        Ok(builder.build())
+    }
+}
+
+/// A request field in its chat-format JSON shape.
+fn json(value: &impl serde::Serialize) -> Result<Value, Error> {
+    serde_json::to_value(value)
+        .map_err(|e| Error::invalid_prompt(format!("unserializable request field: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{InputItem, ToolChoice};
+    use serde_json::json;
+
+    fn request() -> CreateChatCompletionRequest {
+        serde_json::from_value(json!({
+            "model": "gpt-4o",
+            "messages": [
+                { "role": "system", "content": "be brief" },
+                { "role": "user", "content": "weather in Paris?" },
+            ],
+            "max_completion_tokens": 64,
+            "stop": "END",
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "weather",
+                    "parameters": { "type": "object", "properties": { "city": { "type": "string" } } },
+                },
+            }],
+            "tool_choice": { "type": "function", "function": { "name": "weather" } },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn request_splits_into_prompt_and_config() {
+        let request = request();
+        let prompt = Prompt::try_from(&request).unwrap();
+        assert!(matches!(&prompt.items()[0], InputItem::System(s) if s == "be brief"));
+
+        let config = Config::try_from(&request).unwrap();
+        let raw = config.raw();
+        assert_eq!(raw.model, "gpt-4o");
+        assert_eq!(raw.max_tokens, Some(64));
+        assert_eq!(raw.stop.as_deref(), Some(&["END".to_string()][..]));
+        assert!(matches!(
+            &raw.tool_choice,
+            Some(ToolChoice::Function { name }) if name == "weather"
+        ));
+        let tools = raw.tools.as_deref().unwrap();
+        assert_eq!(tools[0].as_function().unwrap().name, "weather");
+    }
+
+    #[test]
+    fn messages_and_tools_round_trip() {
+        let request = request();
+        let prompt = Prompt::try_from(request.messages.clone()).unwrap();
+        let messages = Vec::<ChatCompletionRequestMessage>::try_from(&prompt).unwrap();
+        assert_eq!(messages, request.messages);
+
+        let tools = request.tools.unwrap();
+        let tool = Tool::try_from(&tools[0]).unwrap();
+        assert_eq!(ChatCompletionTools::try_from(&tool).unwrap(), tools[0]);
+    }
+
+    #[test]
+    fn builtin_tools_have_no_equivalent() {
+        let tool = Tool::builtin(crate::types::ProviderBuiltin::WebSearch);
+        assert!(ChatCompletionTools::try_from(&tool).is_err());
+    }
+}
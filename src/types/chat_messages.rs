@@ -0,0 +1,520 @@
+//! Conversion between [`Prompt`] and the OpenAI Chat Completions
+//! `messages` array — the de-facto interchange format for logged
+//! conversations.
+//!
+//! The mapping is lossy where the formats differ. On import, tool
+//! messages become [`InputItem::tool_result`] turns (the chat format
+//! has no error flag, so `is_error` is always `false`). On export,
+//! parts the chat format can't carry — reasoning, continuation
+//! markers, builtin tool calls, cache breakpoints — are dropped, a
+//! failed tool result is wrapped as `{"error": …}` the way the OpenAI
+//! provider sends it, and inputs with no chat encoding (video, audio
+//! by URL, images by file reference) are an error rather than silently
+//! lost.
+//!
+//! The array is the wire shape other OpenAI client crates serialize
+//! their message types to, so it doubles as the bridge to them; the
+//! `async-openai` feature builds its conversions on it.
+
+use serde_json::{json, Map, Value};
+
+use super::message::{AssistantPart, FileSource, FunctionCall, InputItem, UserPart};
+use super::prompt::Prompt;
+use crate::Error;
+
+impl Prompt {
+    /// Build a prompt from an OpenAI chat `messages` array
+    /// (`[{"role": "user", "content": "…"}, …]`). Roles `system`,
+    /// `developer`, `user`, `assistant` and `tool` are understood, with
+    /// string or content-part-array `content`. Tool messages become
+    /// [`InputItem::tool_result`] turns; the chat format has no error
+    /// flag, so `is_error` is always `false`.
+    pub fn from_chat_messages(messages: &Value) -> Result<Prompt, Error> {
+        let messages = messages
+            .as_array()
+            .ok_or_else(|| Error::invalid_prompt("chat messages must be a JSON array"))?;
+        let items = messages
+            .iter()
+            .enumerate()
+            .map(|(i, message)| {
+                import_message(message)
+                    .map_err(|e| Error::invalid_prompt(format!("messages[{i}]: {e}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Prompt::from(items))
+    }
+
+    /// Render the prompt as an OpenAI chat `messages` array. Parts the
+    /// format can't carry — reasoning, continuation markers, builtin
+    /// tool calls, cache breakpoints — are dropped, and a failed tool
+    /// result is wrapped as `{"error": …}` the way the OpenAI provider
+    /// sends it. Inputs with no chat encoding (video, audio by URL,
+    /// images by file reference) are an [`Error::InvalidPrompt`]
+    /// rather than silently lost.
+    pub fn to_chat_messages(&self) -> Result<Value, Error> {
+        let mut out = Vec::new();
+        for item in self.items() {
+            export_item(item, &mut out).map_err(Error::invalid_prompt)?;
+        }
+        Ok(Value::Array(out))
+    }
+}
+
+fn import_message(message: &Value) -> Result<InputItem, String> {
+    let role = message
+        .get("role")
+        .and_then(Value::as_str)
+        .ok_or("missing \"role\"")?;
+    let content = message.get("content").filter(|c| !c.is_null());
+    let name = message.get("name").and_then(Value::as_str);
+    let item = match role {
+        "system" => InputItem::system(plain_text(content)?),
+        "developer" => InputItem::developer(plain_text(content)?),
+        "user" => InputItem::User {
+            content: user_parts(content)?,
+            name: None,
+            metadata: Default::default(),
+        },
+        "assistant" => InputItem::Assistant {
+            content: assistant_parts(message, content)?,
+            name: None,
+            metadata: Default::default(),
+        },
+        "tool" => {
+            let call_id = message
+                .get("tool_call_id")
+                .and_then(Value::as_str)
+                .ok_or("tool message without \"tool_call_id\"")?;
+            InputItem::tool_result(call_id, plain_text(content)?)
+        }
+        other => return Err(format!("unsupported role {other:?}")),
+    };
+    Ok(match name {
+        Some(name) => item.with_name(name),
+        None => item,
+    })
+}
+
+/// String content, or the text parts of a content array joined.
+fn plain_text(content: Option<&Value>) -> Result<String, String> {
+    match content {
+        None => Ok(String::new()),
+        Some(Value::String(text)) => Ok(text.clone()),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .map(|part| match part_type(part)? {
+                "text" => text_field(part, "text"),
+                other => Err(format!("unsupported content part {other:?} here")),
+            })
+            .collect(),
+        Some(_) => Err("\"content\" must be a string or an array".to_string()),
+    }
+}
+
+fn part_type(part: &Value) -> Result<&str, String> {
+    part.get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| "content part without \"type\"".to_string())
+}
+
+fn text_field(value: &Value, field: &str) -> Result<String, String> {
+    value
+        .get(field)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| format!("missing {field:?}"))
+}
+
+fn user_parts(content: Option<&Value>) -> Result<Vec<UserPart>, String> {
+    let parts = match content {
+        None => return Ok(Vec::new()),
+        Some(Value::String(text)) => return Ok(vec![UserPart::Text(text.clone())]),
+        Some(Value::Array(parts)) => parts,
+        Some(_) => return Err("\"content\" must be a string or an array".to_string()),
+    };
+    parts
+        .iter()
+        .map(|part| {
+            Ok(match part_type(part)? {
+                "text" => UserPart::Text(text_field(part, "text")?),
+                "image_url" => {
+                    let url = part
+                        .get("image_url")
+                        .ok_or("image part without \"image_url\"")
+                        .and_then(|image| {
+                            text_field(image, "url").map_err(|_| "image part without a url")
+                        })?;
+                    UserPart::Image(url_source(url))
+                }
+                "input_audio" => {
+                    let audio = part
+                        .get("input_audio")
+                        .ok_or("audio part without \"input_audio\"")?;
+                    UserPart::Audio(FileSource::Base64 {
+                        data: text_field(audio, "data")?,
+                        media_type: format!("audio/{}", text_field(audio, "format")?),
+                    })
+                }
+                "file" => {
+                    let file = part.get("file").ok_or("file part without \"file\"")?;
+                    if let Ok(id) = text_field(file, "file_id") {
+                        UserPart::Document(FileSource::Ref(id))
+                    } else {
+                        UserPart::Document(url_source(text_field(file, "file_data")?))
+                    }
+                }
+                other => return Err(format!("unsupported content part {other:?}")),
+            })
+        })
+        .collect()
+}
+
+/// A `data:` URL becomes inline base64; anything else stays a URL.
+fn url_source(url: String) -> FileSource {
+    if let Some((media_type, data)) = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+    {
+        return FileSource::Base64 {
+            data: data.to_string(),
+            media_type: media_type.to_string(),
+        };
+    }
+    FileSource::Url(url)
+}
+
+fn assistant_parts(message: &Value, content: Option<&Value>) -> Result<Vec<AssistantPart>, String> {
+    let mut parts = Vec::new();
+    let text_part = |content: String| AssistantPart::Text {
+        content,
+        annotations: Vec::new(),
+    };
+    match content {
+        // Clients often send `""` next to tool calls; it isn't a turn.
+        None => {}
+        Some(Value::String(text)) if text.is_empty() => {}
+        Some(Value::String(text)) => parts.push(text_part(text.clone())),
+        Some(Value::Array(items)) => {
+            for item in items {
+                parts.push(match part_type(item)? {
+                    "text" => text_part(text_field(item, "text")?),
+                    "refusal" => AssistantPart::Refusal(text_field(item, "refusal")?),
+                    other => return Err(format!("unsupported content part {other:?}")),
+                });
+            }
+        }
+        Some(_) => return Err("\"content\" must be a string or an array".to_string()),
+    }
+    if let Some(refusal) = message.get("refusal").and_then(Value::as_str) {
+        parts.push(AssistantPart::Refusal(refusal.to_string()));
+    }
+    for call in message
+        .get("tool_calls")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let function = call
+            .get("function")
+            .ok_or("tool call without \"function\"")?;
+        parts.push(AssistantPart::ToolCall(FunctionCall {
+            call_id: text_field(call, "id")?,
+            name: text_field(function, "name")?,
+            arguments: text_field(function, "arguments")?,
+            provider_signature: None,
+            original_arguments: None,
+        }));
+    }
+    Ok(parts)
+}
+
+fn export_item(item: &InputItem, out: &mut Vec<Value>) -> Result<(), String> {
+    match item {
+        InputItem::System(text) => out.push(json!({"role": "system", "content": text})),
+        InputItem::Developer(text) => out.push(json!({"role": "developer", "content": text})),
+        InputItem::User { content, name, .. } => {
+            let mut parts = Vec::new();
+            for part in content {
+                match part {
+                    // Tool messages answer the preceding assistant turn,
+                    // so they go ahead of whatever else the user said.
+                    UserPart::ToolResult {
+                        call_id,
+                        content,
+                        is_error,
+                    } => out.push(json!({
+                        "role": "tool",
+                        "tool_call_id": call_id,
+                        "content": tool_output(content, *is_error)?,
+                    })),
+                    UserPart::CacheBreakpoint => {}
+                    other => parts.push(export_user_part(other)?),
+                }
+            }
+            if !parts.is_empty() {
+                let content = match parts.as_slice() {
+                    [part] if part["type"] == "text" => part["text"].clone(),
+                    _ => Value::Array(parts),
+                };
+                out.push(message("user", content, name.as_deref()));
+            }
+        }
+        InputItem::Assistant { content, name, .. } => {
+            let mut text = String::new();
+            let mut refusal = String::new();
+            let mut tool_calls = Vec::new();
+            for part in content {
+                match part {
+                    AssistantPart::Text { content, .. } => text.push_str(content),
+                    AssistantPart::Refusal(content) => refusal.push_str(content),
+                    AssistantPart::ToolCall(call) => tool_calls.push(json!({
+                        "id": call.call_id,
+                        "type": "function",
+                        "function": {"name": call.name, "arguments": call.arguments},
+                    })),
+                    AssistantPart::Reasoning { .. }
+                    | AssistantPart::RedactedReasoning { .. }
+                    | AssistantPart::BuiltinToolCall { .. }
+                    | AssistantPart::Continuation(_)
+                    | AssistantPart::CacheBreakpoint => {}
+                }
+            }
+            if text.is_empty() && refusal.is_empty() && tool_calls.is_empty() {
+                return Ok(());
+            }
+            let content = if text.is_empty() {
+                Value::Null
+            } else {
+                Value::String(text)
+            };
+            let mut message = message("assistant", content, name.as_deref());
+            if !refusal.is_empty() {
+                message["refusal"] = Value::String(refusal);
+            }
+            if !tool_calls.is_empty() {
+                message["tool_calls"] = Value::Array(tool_calls);
+            }
+            out.push(message);
+        }
+    }
+    Ok(())
+}
+
+fn message(role: &str, content: Value, name: Option<&str>) -> Value {
+    let mut message = Map::new();
+    message.insert("role".to_string(), Value::from(role));
+    message.insert("content".to_string(), content);
+    if let Some(name) = name {
+        message.insert("name".to_string(), Value::from(name));
+    }
+    Value::Object(message)
+}
+
+fn export_user_part(part: &UserPart) -> Result<Value, String> {
+    let data_url = |media_type: &str, data: &str| format!("data:{media_type};base64,{data}");
+    Ok(match part {
+        UserPart::Text(text) => json!({"type": "text", "text": text}),
+        UserPart::Image(FileSource::Url(url)) => {
+            json!({"type": "image_url", "image_url": {"url": url}})
+        }
+        UserPart::Image(FileSource::Base64 { data, media_type }) => {
+            json!({"type": "image_url", "image_url": {"url": data_url(media_type, data)}})
+        }
+        UserPart::Audio(FileSource::Base64 { data, media_type }) => json!({
+            "type": "input_audio",
+            "input_audio": {
+                "data": data,
+                "format": media_type.strip_prefix("audio/").unwrap_or(media_type),
+            },
+        }),
+        UserPart::Document(FileSource::Ref(id)) => json!({"type": "file", "file": {"file_id": id}}),
+        UserPart::Document(FileSource::Base64 { data, media_type }) => {
+            json!({"type": "file", "file": {"file_data": data_url(media_type, data)}})
+        }
+        UserPart::Image(FileSource::Ref(_)) => {
+            return Err("image file references have no chat encoding".into())
+        }
+        UserPart::Audio(_) => return Err("only inline base64 audio has a chat encoding".into()),
+        UserPart::Document(FileSource::Url(_)) => {
+            return Err("document URLs have no chat encoding".into())
+        }
+        UserPart::Video(_) => return Err("video has no chat encoding".into()),
+        UserPart::ToolResult { .. } | UserPart::CacheBreakpoint => {
+            unreachable!("handled by the caller")
+        }
+    })
+}
+
+/// Tool output text — the chat format's `tool` message only carries
+/// text, so other parts are an error.
+fn tool_output(content: &[UserPart], is_error: bool) -> Result<String, String> {
+    let text = content
+        .iter()
+        .map(|part| match part {
+            UserPart::Text(text) => Ok(text.as_str()),
+            _ => Err("tool results with non-text parts have no chat encoding".to_string()),
+        })
+        .collect::<Result<String, _>>()?;
+    if !is_error {
+        return Ok(text);
+    }
+    let error = serde_json::from_str::<Value>(&text).unwrap_or(Value::String(text));
+    Ok(json!({ "error": error }).to_string())
+}
+
+/// The request-level fields of the chat format (`tool_choice`,
+/// `response_format`, tool schemas), parsed for the OpenAI-compatible
+/// server and the `async-openai` bridge.
+#[cfg(any(feature = "server", feature = "async-openai"))]
+mod request {
+    use std::borrow::Cow;
+
+    use serde_json::value::RawValue;
+    use serde_json::Value;
+
+    use crate::types::{ResponseFormat, ToolChoice};
+    use crate::Error;
+
+    /// A JSON schema as the raw value tools and response formats carry.
+    pub(crate) fn raw_json(value: String) -> Result<Cow<'static, RawValue>, Error> {
+        RawValue::from_string(value)
+            .map(Cow::Owned)
+            .map_err(|err| Error::invalid_prompt(format!("invalid JSON schema: {err}")))
+    }
+
+    pub(crate) fn tool_choice(value: &Value) -> Result<ToolChoice, Error> {
+        match value {
+            Value::String(choice) => match choice.as_str() {
+                "auto" => Ok(ToolChoice::Auto),
+                "none" => Ok(ToolChoice::None),
+                "required" => Ok(ToolChoice::Required),
+                other => Err(Error::invalid_prompt(format!(
+                    "unsupported tool_choice {other:?}"
+                ))),
+            },
+            _ => value["function"]["name"]
+                .as_str()
+                .map(|name| ToolChoice::Function {
+                    name: name.to_string(),
+                })
+                .ok_or_else(|| Error::invalid_prompt("tool_choice object needs function.name")),
+        }
+    }
+
+    pub(crate) fn response_format(value: &Value) -> Result<ResponseFormat, Error> {
+        match value["type"].as_str() {
+            Some("text") => Ok(ResponseFormat::Text),
+            Some("json_object") => Ok(ResponseFormat::JsonObject),
+            Some("json_schema") => {
+                let spec = &value["json_schema"];
+                Ok(ResponseFormat::JsonSchema {
+                    name: spec["name"].as_str().unwrap_or("response").to_string(),
+                    schema: raw_json(spec["schema"].to_string())?,
+                    strict: spec["strict"].as_bool().unwrap_or(false),
+                })
+            }
+            _ => Err(Error::invalid_prompt(
+                "response_format.type must be text, json_object or json_schema",
+            )),
+        }
+    }
+}
+
+#[cfg(any(feature = "server", feature = "async-openai"))]
+pub(crate) use request::{raw_json, response_format, tool_choice};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript() -> Value {
+        json!([
+            {"role": "system", "content": "Be terse."},
+            {"role": "user", "name": "ada", "content": [
+                {"type": "text", "text": "What's this?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0K"}}
+            ]},
+            {"role": "assistant", "content": null, "tool_calls": [
+                {"id": "call_1", "type": "function",
+                 "function": {"name": "lookup", "arguments": "{\"q\":\"png\"}"}}
+            ]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "a logo"},
+            {"role": "assistant", "content": "A logo."}
+        ])
+    }
+
+    #[test]
+    fn imports_a_chat_transcript() {
+        let prompt = Prompt::from_chat_messages(&transcript()).unwrap();
+        let items = prompt.items();
+        assert_eq!(items.len(), 5);
+        assert!(matches!(&items[0], InputItem::System(text) if text == "Be terse."));
+        match &items[1] {
+            InputItem::User { content, name, .. } => {
+                assert_eq!(name.as_deref(), Some("ada"));
+                assert!(matches!(
+                    &content[1],
+                    UserPart::Image(FileSource::Base64 { media_type, data })
+                        if media_type == "image/png" && data == "iVBORw0K"
+                ));
+            }
+            other => panic!("expected a user turn, got {other:?}"),
+        }
+        match &items[2] {
+            InputItem::Assistant { content, .. } => {
+                assert!(
+                    matches!(&content[..], [AssistantPart::ToolCall(call)] if call.call_id == "call_1")
+                );
+            }
+            other => panic!("expected an assistant turn, got {other:?}"),
+        }
+        assert!(matches!(
+            &items[3],
+            InputItem::User { content, .. }
+                if matches!(&content[..], [UserPart::ToolResult { call_id, .. }] if call_id == "call_1")
+        ));
+    }
+
+    #[test]
+    fn export_round_trips_the_chat_shape() {
+        let prompt = Prompt::from_chat_messages(&transcript()).unwrap();
+        assert_eq!(prompt.to_chat_messages().unwrap(), transcript());
+    }
+
+    #[test]
+    fn export_orders_tool_results_first_and_wraps_errors() {
+        let prompt = Prompt::from(InputItem::User {
+            content: vec![
+                UserPart::Text("and now?".into()),
+                UserPart::ToolResult {
+                    call_id: "call_1".into(),
+                    content: vec![UserPart::Text("timed out".into())],
+                    is_error: true,
+                },
+            ],
+            name: None,
+            metadata: Default::default(),
+        });
+        assert_eq!(
+            prompt.to_chat_messages().unwrap(),
+            json!([
+                {"role": "tool", "tool_call_id": "call_1", "content": "{\"error\":\"timed out\"}"},
+                {"role": "user", "content": "and now?"}
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_what_the_formats_cannot_express() {
+        let err =
+            Prompt::from_chat_messages(&json!([{"role": "function", "content": "x"}])).unwrap_err();
+        assert!(err.to_string().contains("messages[0]"), "{err}");
+
+        let video = Prompt::from(InputItem::User {
+            content: vec![UserPart::Video(FileSource::Url("https://x/v.mp4".into()))],
+            name: None,
+            metadata: Default::default(),
+        });
+        assert!(video.to_chat_messages().is_err());
+    }
+}
@@ -35,21 +35,38 @@ pub struct Usage {
     /// request (Anthropic-only; charged at a 1.25× premium). Reported
     /// as `cache_creation_input_tokens`.
     pub cache_creation_input_tokens: Option<u32>,
-    /// Output tokens spent on the model's internal reasoning (gpt-5 /
-    /// o-series and Gemini thinking). OpenAI reports this under
-    /// `output_tokens_details.reasoning_tokens`; Gemini reports it as
-    /// `thoughtsTokenCount`.
+    /// Subset of [`Self::output_tokens`] spent on the model's internal
+    /// reasoning (gpt-5 / o-series and Gemini thinking). OpenAI reports
+    /// this under `output_tokens_details.reasoning_tokens`; Gemini
+    /// reports it as `thoughtsTokenCount`.
     pub reasoning_tokens: Option<u32>,
+    /// Total tokens for the turn as reported by the provider — OpenAI's
+    /// `total_tokens`, Gemini's `totalTokenCount`. Can exceed
+    /// `input_tokens + output_tokens` where the provider bills tokens
+    /// outside both (Gemini's tool-use prompt tokens). `None` when the
+    /// provider doesn't report one (Anthropic); read
+    /// [`Self::total_tokens`] instead of the raw field.
+    pub total_tokens: Option<u32>,
 }
 
 impl Usage {
-    /// `input_tokens + output_tokens` — the total tokens charged for
-    /// this turn. Cache-read / cache-creation / reasoning fields are
-    /// already counted inside input / output respectively, so this
+    /// Total tokens charged for this turn: the provider-reported
+    /// total when there is one, otherwise `input_tokens +
+    /// output_tokens`. Cache-read / cache-creation / reasoning fields
+    /// are breakdowns of input / output, never added on top, so this
     /// gives the right number for "how much of the context window did
     /// this turn touch?".
     pub fn total_tokens(&self) -> u32 {
-        self.input_tokens.saturating_add(self.output_tokens)
+        self.total_tokens
+            .unwrap_or_else(|| self.input_tokens.saturating_add(self.output_tokens))
+    }
+
+    /// Input tokens billed at the full rate — `input_tokens` minus the
+    /// cache-read and cache-write breakdowns.
+    pub fn uncached_input_tokens(&self) -> u32 {
+        self.input_tokens
+            .saturating_sub(self.cache_read_input_tokens.unwrap_or(0))
+            .saturating_sub(self.cache_creation_input_tokens.unwrap_or(0))
     }
 }
 
@@ -63,12 +80,46 @@ pub struct ReasoningConfig {
     /// How much effort to spend reasoning. Maps to OpenAI's `effort` and
     /// to Anthropic / Gemini's `budget_tokens` (rough mapping).
     pub effort: Option<ReasoningEffort>,
+    /// Exact thinking-token budget: Anthropic's `thinking.budget_tokens`
+    /// and Gemini's `thinkingConfig.thinkingBudget`. Takes precedence
+    /// over `effort` on those providers; OpenAI has no token budget, so
+    /// without an explicit `effort` it is bucketed into one.
+    pub budget_tokens: Option<u32>,
     /// Whether (and how) to surface reasoning summaries (OpenAI). Anthropic
     /// returns thinking content unconditionally when enabled; Gemini's
     /// thinking is not exposed to clients.
     pub summary: Option<ReasoningSummary>,
 }
 
+impl ReasoningConfig {
+    /// Thinking-token budget for providers that take one: the explicit
+    /// `budget_tokens`, else `effort` mapped to 2048 / 8192 / 16384
+    /// (medium when neither is set).
+    #[cfg(feature = "vertex")]
+    pub(crate) fn thinking_budget(&self) -> u32 {
+        self.budget_tokens
+            .unwrap_or(match self.effort.unwrap_or(ReasoningEffort::Medium) {
+                ReasoningEffort::Low => 2048,
+                ReasoningEffort::Medium => 8192,
+                ReasoningEffort::High => 16384,
+            })
+    }
+
+    /// Effort for providers that take one: the explicit `effort`, else
+    /// `budget_tokens` bucketed against the same thresholds
+    /// `thinking_budget` uses.
+    #[cfg(feature = "openai")]
+    pub(crate) fn effective_effort(&self) -> Option<ReasoningEffort> {
+        self.effort.or_else(|| {
+            self.budget_tokens.map(|budget| match budget {
+                0..=2048 => ReasoningEffort::Low,
+                2049..=8192 => ReasoningEffort::Medium,
+                _ => ReasoningEffort::High,
+            })
+        })
+    }
+}
+
 /// Coarse "how hard to think" knob. Each provider maps it onto its own
 /// budget / effort parameter.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -170,6 +221,26 @@ pub enum ToolChoice {
     },
 }
 
+/// Caller-side facts about a request that providers forward as
+/// attribution rather than as generation parameters. Set via
+/// [`ConfigBuilder::metadata`] or [`ConfigBuilder::user_id`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestMetadata {
+    /// Stable, opaque identifier of the end user the request is made
+    /// for, so the provider can attribute abuse reports and per-user
+    /// rate limits. Use a hash or internal id — never an email, name or
+    /// phone number. Sent as Anthropic's `metadata.user_id` and
+    /// OpenAI's `safety_identifier`.
+    pub user_id: Option<String>,
+    /// Caller-chosen key grouping requests that share a prompt prefix,
+    /// for providers with prefix-cache routing (OpenAI's
+    /// `prompt_cache_key`). Overrides the key OpenAI otherwise derives
+    /// from a [`crate::UserPart::CacheBreakpoint`] — use it when the
+    /// grouping should follow your own notion (tenant, agent, template
+    /// version) rather than the prefix bytes.
+    pub prompt_cache_key: Option<String>,
+}
+
 /// The request payload that flows through the middleware chain and
 /// into the provider.
 ///
@@ -223,6 +294,11 @@ pub struct RawConfig {
     /// Map your own tenant identifier (workspace id, user id, …) to
     /// a stable [`Uuid`](uuid::Uuid) once per tenant — e.g. UUIDv5 over your
     /// identifier namespace — and reuse it across requests.
+    ///
+    /// The same id attributes the call for accounting: it labels the
+    /// call's [`crate::metrics::CallMetrics`], its
+    /// [`crate::usage::UsageTracker`] totals and its OpenTelemetry
+    /// span. It is never sent to the provider.
     pub tenant: Option<uuid::Uuid>,
     /// Latency priority for the rate limiter. Defaults to
     /// [`crate::Priority::Interactive`] when unset — most callers
@@ -230,6 +306,166 @@ pub struct RawConfig {
     /// latency by default. Background batches should explicitly
     /// pick [`crate::Priority::Background`].
     pub priority: Option<crate::rate_limit::Priority>,
+    /// Deadline for the whole call, response stream included. Enforced
+    /// by [`crate::generate`]; overrides any
+    /// [`crate::timeout::TimeoutProvider`] default in either direction.
+    /// `None` leaves the call unbounded unless a wrapper sets one.
+    pub timeout: Option<std::time::Duration>,
+    /// Longest gap allowed between response events — and before the
+    /// first one — independent of [`Self::timeout`]. Enforced by
+    /// [`crate::generate`]; overrides any
+    /// [`crate::timeout::IdleTimeoutProvider`] default.
+    pub idle_timeout: Option<std::time::Duration>,
+    /// Extra HTTP headers sent with this request — gateway routing,
+    /// API-version pinning, tenant tagging and the like. Merged over the
+    /// provider's own headers (and any provider-level extras), replacing
+    /// same-named ones case-insensitively. Providers without an HTTP
+    /// hop (local models, mocks) ignore them.
+    pub extra_headers: Vec<(String, String)>,
+    /// Key identifying this logical request across retries, so a
+    /// provider that deduplicates on it acts on a retried request once
+    /// rather than once per attempt. OpenAI sends it as the
+    /// `Idempotency-Key` header (an `Idempotency-Key` in
+    /// [`Self::extra_headers`] wins); providers whose APIs take no such
+    /// key ignore it. [`crate::RetryingProvider`] generates one for
+    /// calls that don't set it; set it yourself — e.g. from
+    /// [`crate::retry::idempotency_key`] — to cover retries driven
+    /// outside it, such as [`crate::retry()`] or a job queue.
+    pub idempotency_key: Option<String>,
+    /// Correlation ID for this call, carried into its logs, metrics,
+    /// audit records and [`crate::Error::provider_details`], and
+    /// returned as [`crate::ResponseMetadata::request_id`], so one
+    /// failed generation can be followed across systems. Set your own
+    /// — an inbound trace or job ID — to join the call to your records
+    /// and to correlate errors that carry no details;
+    /// [`crate::generate`] generates one when unset. OpenAI also
+    /// receives it as the `X-Client-Request-Id` header; the Vertex
+    /// APIs take no such header and don't.
+    pub request_id: Option<String>,
+    /// Vendor-specific JSON deep-merged into the provider's request body
+    /// just before it is sent — the escape hatch for parameters this
+    /// crate doesn't model yet. Objects merge key by key, recursively;
+    /// any other value replaces what the provider built, and `null`
+    /// removes the key. Must be a JSON object. Providers without a JSON
+    /// payload (local models, mocks) ignore it.
+    pub extra_body: Option<serde_json::Value>,
+    /// Typed settings unique to one provider. Each provider reads its
+    /// own section and ignores the rest.
+    pub provider_options: crate::types::ProviderOptions,
+    /// Attribution metadata forwarded to providers that accept it.
+    pub metadata: RequestMetadata,
+}
+
+impl RawConfig {
+    /// Check the request for values every supported provider would
+    /// reject, so they fail here with a precise [`Error::Config`]
+    /// instead of as an opaque upstream 400. Run by
+    /// [`crate::middleware::validate`] on every call, after middleware.
+    ///
+    /// Covers:
+    ///
+    /// - sampling parameters outside the ranges the
+    ///   [`ConfigBuilder`] setters enforce (reachable by editing the
+    ///   public fields directly or from a middleware), and a zero
+    ///   `max_tokens`;
+    /// - empty stop sequences;
+    /// - function tools: names outside `[A-Za-z0-9_-]{1,64}` (the set
+    ///   all three hosted APIs accept), duplicate names, and
+    ///   `parameters` that aren't a JSON object schema;
+    /// - a [`ToolChoice`] that forces a tool when none is declared, or
+    ///   names a function that isn't;
+    /// - [`Self::extra_body`] that isn't a JSON object, and whatever
+    ///   [`crate::ProviderOptions::validate`] rejects.
+    ///
+    /// Model-specific limits (Anthropic's lower temperature cap, how
+    /// many stop sequences a model takes) are left to the provider.
+    ///
+    /// [`Error::Config`]: crate::Error::Config
+    pub fn validate(&self) -> Result<(), crate::Error> {
+        use crate::Error;
+
+        let in_range =
+            |name: &str, value: Option<f32>, range: std::ops::RangeInclusive<f32>| match value {
+                Some(v) if !v.is_finite() || !range.contains(&v) => Err(Error::config(format!(
+                    "{name} must be finite and in {}..={}, got {v}",
+                    range.start(),
+                    range.end(),
+                ))),
+                _ => Ok(()),
+            };
+        in_range("temperature", self.temperature, 0.0..=2.0)?;
+        in_range("top_p", self.top_p, 0.0..=1.0)?;
+        in_range("presence_penalty", self.presence_penalty, -2.0..=2.0)?;
+        in_range("frequency_penalty", self.frequency_penalty, -2.0..=2.0)?;
+        if self.max_tokens == Some(0) {
+            return Err(Error::config("max_tokens must be at least 1"));
+        }
+        if self.stop.iter().flatten().any(String::is_empty) {
+            return Err(Error::config("stop sequences must not be empty"));
+        }
+
+        let functions: Vec<&super::message::Function> = self
+            .tools
+            .iter()
+            .flatten()
+            .filter_map(super::message::Tool::as_function)
+            .collect();
+        for (i, function) in functions.iter().enumerate() {
+            let name = function.name.as_str();
+            let valid_name = (1..=64).contains(&name.len())
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+            if !valid_name {
+                return Err(Error::config(format!(
+                    "tool name '{name}' must be 1-64 characters of A-Z, a-z, 0-9, '_' or '-'"
+                )));
+            }
+            if functions[..i].iter().any(|earlier| earlier.name == name) {
+                return Err(Error::config(format!(
+                    "tool '{name}' is declared more than once"
+                )));
+            }
+            let schema: serde_json::Value = serde_json::from_str(function.parameters.get())
+                .map_err(|e| {
+                    Error::config(format!("tool '{name}' parameters are not valid JSON: {e}"))
+                })?;
+            let is_object_schema = schema.as_object().is_some_and(|schema| {
+                schema
+                    .get("type")
+                    .is_none_or(|ty| ty == &serde_json::Value::from("object"))
+            });
+            if !is_object_schema {
+                return Err(Error::config(format!(
+                    "tool '{name}' parameters must be a JSON schema of type \"object\""
+                )));
+            }
+        }
+
+        let has_tools = self.tools.as_ref().is_some_and(|tools| !tools.is_empty());
+        match &self.tool_choice {
+            Some(ToolChoice::Required) if !has_tools => {
+                return Err(Error::config(
+                    "tool_choice=Required needs at least one tool",
+                ));
+            }
+            Some(ToolChoice::Function { name }) if !functions.iter().any(|f| &f.name == name) => {
+                return Err(Error::config(format!(
+                    "tool_choice names function '{name}', which is not among the declared tools"
+                )));
+            }
+            _ => {}
+        }
+
+        if self
+            .extra_body
+            .as_ref()
+            .is_some_and(|body| !body.is_object())
+        {
+            return Err(Error::config("extra_body must be a JSON object"));
+        }
+        self.provider_options.validate()
+    }
 }
 
 /// User-facing request spec. Bundles the [`RawConfig`] payload with
@@ -258,6 +494,13 @@ impl Config {
         ConfigBuilder::new(model)
     }
 
+    /// Start a builder with no model, for a provider that supplies its
+    /// own default — see [`crate::defaults`]. A provider without a
+    /// default model rejects the request.
+    pub fn with_provider_defaults() -> ConfigBuilder {
+        ConfigBuilder::new(String::new())
+    }
+
     /// Borrow the [`RawConfig`] payload. This is what gets threaded
     /// through middleware and reaches the provider.
     pub fn raw(&self) -> &RawConfig {
@@ -315,6 +558,14 @@ pub struct ConfigBuilder {
     response_format: Option<ResponseFormat>,
     tenant: Option<uuid::Uuid>,
     priority: Option<crate::rate_limit::Priority>,
+    timeout: Option<std::time::Duration>,
+    idle_timeout: Option<std::time::Duration>,
+    extra_headers: Vec<(String, String)>,
+    idempotency_key: Option<String>,
+    request_id: Option<String>,
+    extra_body: Option<serde_json::Value>,
+    provider_options: crate::types::ProviderOptions,
+    metadata: RequestMetadata,
     #[allow(clippy::type_complexity)]
     middleware_override: Option<Vec<std::sync::Arc<dyn crate::middleware::Middleware>>>,
 }
@@ -340,6 +591,14 @@ impl ConfigBuilder {
             response_format: None,
             tenant: None,
             priority: None,
+            timeout: None,
+            idle_timeout: None,
+            extra_headers: Vec::new(),
+            idempotency_key: None,
+            request_id: None,
+            extra_body: None,
+            provider_options: crate::types::ProviderOptions::default(),
+            metadata: RequestMetadata::default(),
             middleware_override: None,
         }
     }
@@ -439,6 +698,23 @@ impl ConfigBuilder {
         self
     }
 
+    /// Enable reasoning at the given effort, keeping any other
+    /// [`ReasoningConfig`] fields already set.
+    pub fn reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning.get_or_insert_with(Default::default).effort = Some(effort);
+        self
+    }
+
+    /// Enable reasoning with an exact thinking-token budget, keeping any
+    /// other [`ReasoningConfig`] fields already set. See
+    /// [`ReasoningConfig::budget_tokens`].
+    pub fn thinking_budget(mut self, budget_tokens: u32) -> Self {
+        self.reasoning
+            .get_or_insert_with(Default::default)
+            .budget_tokens = Some(budget_tokens);
+        self
+    }
+
     /// Constrain the response to a structured shape (JSON mode / schema).
     pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
         self.response_format = Some(response_format);
@@ -453,7 +729,9 @@ impl ConfigBuilder {
     /// every other request through the shared limiter.
     ///
     /// Map your own tenant identifier to a stable [`Uuid`](uuid::Uuid) once per
-    /// tenant (e.g. UUIDv5 over your id namespace) and reuse it.
+    /// tenant (e.g. UUIDv5 over your id namespace) and reuse it. The
+    /// id also attributes the call's metrics, usage and spans to the
+    /// tenant — see [`RawConfig::tenant`].
     pub fn tenant(mut self, tenant: uuid::Uuid) -> Self {
         self.tenant = Some(tenant);
         self
@@ -467,6 +745,83 @@ impl ConfigBuilder {
         self
     }
 
+    /// Bound the whole call — connection, time to first token and the
+    /// full response stream — by `timeout`. Past it the call fails, or
+    /// the stream ends, with [`crate::Error::Timeout`]. Overrides the
+    /// provider-level default from [`crate::timeout::TimeoutProvider`],
+    /// so a long batch job can extend it and an interactive call can
+    /// shorten it.
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Abort the call with [`crate::Error::IdleTimeout`] when the
+    /// response goes `idle_timeout` without producing an event. Unlike
+    /// [`Self::timeout`] this doesn't limit how long a healthy stream
+    /// may run, only how long it may stall. See [`crate::timeout`].
+    pub fn idle_timeout(mut self, idle_timeout: std::time::Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Send an extra HTTP header with this request. Accumulates across
+    /// calls; see [`RawConfig::extra_headers`] for merge order.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Identify this request to the provider so retries of it aren't
+    /// acted on twice. See [`RawConfig::idempotency_key`].
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// Tag this call with your own correlation ID instead of a
+    /// generated one. See [`RawConfig::request_id`].
+    pub fn request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// Deep-merge `body` into the provider's request payload. See
+    /// [`RawConfig::extra_body`] for the merge rules; a non-object
+    /// `body` fails the call with [`crate::Error::Config`].
+    pub fn extra_body(mut self, body: serde_json::Value) -> Self {
+        self.extra_body = Some(body);
+        self
+    }
+
+    /// Attach typed provider-specific settings. Checked by
+    /// [`crate::generate`] before the call; see
+    /// [`crate::ProviderOptions::validate`].
+    pub fn provider_options(mut self, options: crate::types::ProviderOptions) -> Self {
+        self.provider_options = options;
+        self
+    }
+
+    /// Replace the request's attribution metadata.
+    pub fn metadata(mut self, metadata: RequestMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Attribute the request to an end user. Shorthand for setting
+    /// [`RequestMetadata::user_id`]; pass an opaque id, not PII.
+    pub fn user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.metadata.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Group this request with others sharing `key` for prefix caching.
+    /// Shorthand for setting [`RequestMetadata::prompt_cache_key`].
+    pub fn prompt_cache_key(mut self, key: impl Into<String>) -> Self {
+        self.metadata.prompt_cache_key = Some(key.into());
+        self
+    }
+
     /// Override the middleware chain. Pass `Vec::new()` to disable all
     /// polyfills (validation will still run and surface unsupported
     /// requests as `Error::Config`). Pass a custom list to add your
@@ -503,6 +858,14 @@ impl ConfigBuilder {
                 response_format: self.response_format,
                 tenant: self.tenant,
                 priority: self.priority,
+                timeout: self.timeout,
+                idle_timeout: self.idle_timeout,
+                extra_headers: self.extra_headers,
+                idempotency_key: self.idempotency_key,
+                request_id: self.request_id,
+                extra_body: self.extra_body,
+                provider_options: self.provider_options,
+                metadata: self.metadata,
             },
             middleware_override: self.middleware_override,
         }
@@ -556,6 +919,117 @@ mod tests {
         assert_eq!(cfg.raw().temperature, Some(2.0));
     }
 
+    fn function(name: &str, parameters: &str) -> crate::types::Tool {
+        crate::types::Tool::function(
+            name,
+            None,
+            std::borrow::Cow::Owned(
+                serde_json::value::RawValue::from_string(parameters.to_string()).unwrap(),
+            ),
+        )
+    }
+
+    #[test]
+    fn validate_accepts_a_well_formed_request() {
+        let cfg = Config::builder("m")
+            .temperature(0.7)
+            .max_tokens(100)
+            .tools(vec![
+                function("get_weather", r#"{"type":"object","properties":{}}"#),
+                function("lookup-2", "{}"),
+            ])
+            .tool_choice(ToolChoice::Function {
+                name: "get_weather".to_string(),
+            })
+            .build();
+        cfg.raw().validate().unwrap();
+    }
+
+    #[test]
+    fn validate_rejects_what_every_provider_would() {
+        let base = Config::builder("m").build().raw().clone();
+        let cases: Vec<(RawConfig, &str)> = vec![
+            (
+                RawConfig {
+                    temperature: Some(3.0),
+                    ..base.clone()
+                },
+                "temperature",
+            ),
+            (
+                RawConfig {
+                    top_p: Some(f32::NAN),
+                    ..base.clone()
+                },
+                "top_p",
+            ),
+            (
+                RawConfig {
+                    max_tokens: Some(0),
+                    ..base.clone()
+                },
+                "max_tokens",
+            ),
+            (
+                RawConfig {
+                    stop: Some(vec![String::new()]),
+                    ..base.clone()
+                },
+                "stop",
+            ),
+            (
+                RawConfig {
+                    tools: Some(vec![function("get weather", "{}")]),
+                    ..base.clone()
+                },
+                "get weather",
+            ),
+            (
+                RawConfig {
+                    tools: Some(vec![function("f", "{}"), function("f", "{}")]),
+                    ..base.clone()
+                },
+                "more than once",
+            ),
+            (
+                RawConfig {
+                    tools: Some(vec![function("f", r#"{"type":"string"}"#)]),
+                    ..base.clone()
+                },
+                "type \"object\"",
+            ),
+            (
+                RawConfig {
+                    tool_choice: Some(ToolChoice::Required),
+                    ..base.clone()
+                },
+                "Required",
+            ),
+            (
+                RawConfig {
+                    tools: Some(vec![function("f", "{}")]),
+                    tool_choice: Some(ToolChoice::Function {
+                        name: "g".to_string(),
+                    }),
+                    ..base.clone()
+                },
+                "'g'",
+            ),
+            (
+                RawConfig {
+                    extra_body: Some(serde_json::json!([1])),
+                    ..base.clone()
+                },
+                "extra_body",
+            ),
+        ];
+        for (raw, expected) in cases {
+            let err = raw.validate().expect_err(expected);
+            assert!(matches!(err, crate::Error::Config(_)), "got: {err:?}");
+            assert!(err.to_string().contains(expected), "{expected}: got {err}");
+        }
+    }
+
     #[test]
     fn build_records_middleware_override() {
         let cfg = Config::builder("claude-sonnet-4-5")
@@ -1,6 +1,10 @@
 //! Canonical message model.
 //!
-//! `InputItem` is variant-by-role: `System`, `User`, `Assistant`. The
+//! `InputItem` is variant-by-role: `System`, `Developer`, `User`,
+//! `Assistant`. Tool output is a [`UserPart::ToolResult`] inside a
+//! `User` item rather than a role of its own — each provider maps it to
+//! its tool encoding (OpenAI `function_call_output`, Anthropic
+//! `tool_result`, Gemini `functionResponse`, ChatML `<tool_response>`). The
 //! content of `User` and `Assistant` items is a `Vec` of typed parts so
 //! the model can represent interleaved text + reasoning + tool calls + …
 //! within a single turn — the way Anthropic emits its content blocks.
@@ -14,6 +18,7 @@
 //! for the full drop / translate matrix.
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
@@ -24,13 +29,25 @@ use serde_json::value::RawValue;
 /// content is a sequence of typed parts (for `User` and `Assistant`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum InputItem {
-    /// System / developer instruction.
+    /// System instruction.
     System(String),
+    /// Developer instruction — the application's own directions, which
+    /// newer OpenAI models rank below platform policy but above the
+    /// user. Sent as a `developer` message on OpenAI and as a system
+    /// instruction (merged with any `System` items) everywhere else.
+    Developer(String),
     /// User turn. Contains text, multimedia, tool results, and optional
     /// cache breakpoints in emit order.
     User {
         /// Ordered user-turn parts.
         content: Vec<UserPart>,
+        /// Optional participant name, for transcripts with several
+        /// humans or persona few-shots. See [`InputItem::with_name`].
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// Caller-side tags. See [`InputItem::with_metadata`].
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
     },
     /// Assistant turn. Contains the model's emissions in the order they
     /// were produced — text, reasoning, refusals, tool calls,
@@ -38,6 +55,12 @@ pub enum InputItem {
     Assistant {
         /// Ordered assistant-turn parts.
         content: Vec<AssistantPart>,
+        /// Optional participant name. See [`InputItem::with_name`].
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// Caller-side tags. See [`InputItem::with_metadata`].
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
     },
 }
 
@@ -47,10 +70,17 @@ impl InputItem {
         InputItem::System(content.into())
     }
 
+    /// Build a developer instruction.
+    pub fn developer(content: impl Into<String>) -> Self {
+        InputItem::Developer(content.into())
+    }
+
     /// Build a user turn from a single text string.
     pub fn user(content: impl Into<String>) -> Self {
         InputItem::User {
             content: vec![UserPart::Text(content.into())],
+            name: None,
+            metadata: Default::default(),
         }
     }
 
@@ -61,6 +91,8 @@ impl InputItem {
                 content: content.into(),
                 annotations: Vec::new(),
             }],
+            name: None,
+            metadata: Default::default(),
         }
     }
 
@@ -71,7 +103,48 @@ impl InputItem {
             content: vec![UserPart::ToolResult {
                 call_id: call_id.into(),
                 content: vec![UserPart::Text(output.into())],
+                is_error: false,
+            }],
+            name: None,
+            metadata: Default::default(),
+        }
+    }
+
+    /// Build a tool-result message carrying structured JSON. The value
+    /// is sent as JSON text; Gemini receives it as a native object in
+    /// `functionResponse.response`.
+    pub fn tool_result_json(call_id: impl Into<String>, output: &serde_json::Value) -> Self {
+        Self::tool_result(call_id, output.to_string())
+    }
+
+    /// Build a tool-result message from arbitrary parts — e.g. a
+    /// screenshot tool returning [`UserPart::Image`] alongside a text
+    /// caption. Each provider sends the images and documents natively
+    /// where its tool-result shape allows (see [`UserPart::ToolResult`]).
+    pub fn tool_result_parts(call_id: impl Into<String>, content: Vec<UserPart>) -> Self {
+        InputItem::User {
+            content: vec![UserPart::ToolResult {
+                call_id: call_id.into(),
+                content,
+                is_error: false,
+            }],
+            name: None,
+            metadata: Default::default(),
+        }
+    }
+
+    /// Build a failed tool-result message: `error` is the failure
+    /// description (plain text, or a JSON object for structured
+    /// details) and the part is flagged `is_error`.
+    pub fn tool_error(call_id: impl Into<String>, error: impl Into<String>) -> Self {
+        InputItem::User {
+            content: vec![UserPart::ToolResult {
+                call_id: call_id.into(),
+                content: vec![UserPart::Text(error.into())],
+                is_error: true,
             }],
+            name: None,
+            metadata: Default::default(),
         }
     }
 
@@ -79,6 +152,8 @@ impl InputItem {
     pub fn assistant_tool_call(call: FunctionCall) -> Self {
         InputItem::Assistant {
             content: vec![AssistantPart::ToolCall(call)],
+            name: None,
+            metadata: Default::default(),
         }
     }
 
@@ -89,6 +164,51 @@ impl InputItem {
     pub fn assistant_continuation(continuation: super::config::ProviderContinuation) -> Self {
         InputItem::Assistant {
             content: vec![AssistantPart::Continuation(continuation)],
+            name: None,
+            metadata: Default::default(),
+        }
+    }
+
+    /// Attach a participant name to a `User` or `Assistant` turn. Sent
+    /// as the message `name` on OpenAI; dropped on Anthropic, Gemini and
+    /// ChatML, which have no per-message participant field. No-op on
+    /// `System` / `Developer` items.
+    pub fn with_name(mut self, participant: impl Into<String>) -> Self {
+        if let InputItem::User { name, .. } | InputItem::Assistant { name, .. } = &mut self {
+            *name = Some(participant.into());
+        }
+        self
+    }
+
+    /// The participant name of a `User` or `Assistant` turn, if any.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            InputItem::User { name, .. } | InputItem::Assistant { name, .. } => name.as_deref(),
+            InputItem::System(_) | InputItem::Developer(_) => None,
+        }
+    }
+
+    /// Tag a `User` or `Assistant` turn with a caller-side key/value
+    /// pair (source document ID, UI message ID, …). Metadata travels
+    /// with the item through [`Prompt`](crate::Prompt), serde and
+    /// compaction, and is never sent to a provider. No-op on
+    /// `System` / `Developer` items.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        if let InputItem::User { metadata, .. } | InputItem::Assistant { metadata, .. } = &mut self
+        {
+            metadata.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    /// The caller-side metadata of a `User` or `Assistant` turn; `None`
+    /// for `System` / `Developer` items.
+    pub fn metadata(&self) -> Option<&BTreeMap<String, String>> {
+        match self {
+            InputItem::User { metadata, .. } | InputItem::Assistant { metadata, .. } => {
+                Some(metadata)
+            }
+            InputItem::System(_) | InputItem::Developer(_) => None,
         }
     }
 }
@@ -109,12 +229,24 @@ pub enum UserPart {
     Video(FileSource),
     /// Result of a tool the assistant previously called. `call_id`
     /// correlates with a prior `AssistantPart::ToolCall`.
+    ///
+    /// Text parts are joined into the tool's output. Images and
+    /// documents travel with it: as `tool_result` content blocks on
+    /// Anthropic, as the `function_call_output` content array on
+    /// OpenAI, and as parts following the `functionResponse` on Gemini.
     ToolResult {
         /// Identifier of the originating tool call.
         call_id: String,
         /// Result payload, modelled as user parts so it can include
         /// text, images, etc.
         content: Vec<UserPart>,
+        /// The tool failed and `content` describes the failure (plain
+        /// text or a JSON error object). Sent as Anthropic's
+        /// `tool_result.is_error` and as an `{"error": …}` payload on
+        /// OpenAI and Gemini, so the model reads it as a failure rather
+        /// than as data.
+        #[serde(default)]
+        is_error: bool,
     },
     /// Anthropic-only: marks the end of a cacheable prefix in the
     /// surrounding message. Best-effort on OpenAI (derives a stable
@@ -205,12 +337,17 @@ pub struct Annotation {
     /// Exclusive end byte offset into the annotated text.
     pub end: usize,
     /// Primary identifier — URL for [`AnnotationKind::UrlCitation`],
-    /// file ID for [`AnnotationKind::FileCitation`].
+    /// file ID for [`AnnotationKind::FileCitation`], document URI for
+    /// [`AnnotationKind::DocumentCitation`].
     pub source: String,
     /// Human-readable label, when the provider supplies one (e.g. page
     /// title for a URL citation, filename for a file citation).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    /// The cited passage of the source, when the provider returns it
+    /// (Gemini's retrieved chunks do).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
 }
 
 /// Kind of citation an [`Annotation`] represents.
@@ -220,8 +357,12 @@ pub struct Annotation {
 pub enum AnnotationKind {
     /// Cites a web URL (e.g. web search result).
     UrlCitation,
-    /// Cites a previously uploaded file.
+    /// Cites a previously uploaded file, or one a tool wrote (OpenAI's
+    /// code-interpreter container files and `file_path` references).
     FileCitation,
+    /// Cites a chunk of a retrieved document — Gemini grounding over a
+    /// Vertex AI Search data store or RAG Engine corpus.
+    DocumentCitation,
 }
 
 /// Source for a binary file input — image, audio, or document. The
@@ -384,25 +525,159 @@ pub struct FunctionCall {
     /// don't emit one.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub provider_signature: Option<String>,
+    /// The arguments exactly as the model emitted them, when
+    /// [`crate::middleware::JsonRepairMiddleware`] had to rewrite
+    /// malformed JSON into `arguments`. `None` when `arguments` is
+    /// verbatim. Local bookkeeping only — never sent to a provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_arguments: Option<String>,
 }
 
 /// Why the model stopped generating.
+///
+/// Provider reasons without a dedicated variant are kept verbatim in
+/// [`Self::Other`] rather than coerced to [`Self::Stop`].
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[non_exhaustive]
 pub enum FinishReason {
-    /// Natural end of the response (hit a stop sequence or the model decided to stop).
+    /// Natural end of the response — the model decided to stop.
     Stop,
+    /// Hit one of the caller's stop sequences (Anthropic
+    /// `stop_sequence`). Gemini and OpenAI don't distinguish this from
+    /// a natural stop and report [`Self::Stop`].
+    StopSequence,
     /// Hit `max_tokens` / provider-side length cap before finishing.
     Length,
     /// The turn ended because the model emitted one or more tool calls.
     ToolCalls,
-    /// The provider's content filter blocked or truncated the response.
+    /// The provider's content filter blocked or truncated the response
+    /// (OpenAI `content_filter`, Gemini `RECITATION` / `BLOCKLIST` /
+    /// `PROHIBITED_CONTENT` / `SPII`).
     ContentFilter,
+    /// Generation was stopped on safety grounds (Gemini `SAFETY` /
+    /// `IMAGE_SAFETY`, Anthropic `refusal`).
+    Safety,
     /// The stream ended without a terminal `Done`/stop signal — the
     /// response is *incomplete* (connection dropped, task cancelled,
     /// or a local engine cut off mid-emit). Distinct from [`Self::Stop`]
     /// so callers driving tool-call loops or billing don't mistake a
     /// truncated turn for a clean finish.
     Incomplete,
+    /// A provider reason with no unified equivalent, verbatim (e.g.
+    /// Anthropic `pause_turn`, Gemini `MALFORMED_FUNCTION_CALL`).
+    Other(String),
+}
+
+/// Structured safety outcome of a turn: the provider's per-category
+/// ratings and, when something was blocked, why. Carried on
+/// [`crate::CompleteResponse::safety`] and streamed as
+/// [`crate::StreamEvent::Safety`], so a [`FinishReason::ContentFilter`]
+/// or [`FinishReason::Safety`] finish can be explained — and a
+/// low-severity rating acted on even when nothing was blocked.
+///
+/// Sources: Gemini `safetyRatings` (per candidate and on
+/// `promptFeedback`) and `blockReason`; OpenAI `content_filter`
+/// incompletes; Azure OpenAI `content_filters` results.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SafetyFeedback {
+    /// The prompt itself was rejected, so no output was generated.
+    #[serde(default)]
+    pub prompt_blocked: bool,
+    /// The provider's block reason, verbatim (Gemini `SAFETY` /
+    /// `BLOCKLIST` / `PROHIBITED_CONTENT` / …, OpenAI
+    /// `content_filter`). `None` when nothing was blocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_reason: Option<String>,
+    /// Human-readable explanation, when the provider sends one
+    /// (Gemini `blockReasonMessage`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Per-category ratings, prompt and response alike.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ratings: Vec<SafetyRating>,
+}
+
+impl SafetyFeedback {
+    /// Whether the provider blocked anything — the prompt, the
+    /// response, or a single rated category.
+    pub fn is_blocked(&self) -> bool {
+        self.prompt_blocked || self.block_reason.is_some() || self.ratings.iter().any(|r| r.blocked)
+    }
+}
+
+/// One category's safety assessment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SafetyRating {
+    /// The provider's category name, verbatim (Gemini
+    /// `HARM_CATEGORY_HATE_SPEECH`, Azure `hate`, `jailbreak`, …).
+    pub category: String,
+    /// Whether the rating is of the prompt or of the response.
+    pub target: SafetyTarget,
+    /// How likely / severe the harm is. `None` for detection-only
+    /// categories (Azure `jailbreak`, `protected_material_*`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<SafetyLevel>,
+    /// Whether this category caused content to be blocked.
+    #[serde(default)]
+    pub blocked: bool,
+}
+
+/// What a [`SafetyRating`] assessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyTarget {
+    /// The caller's input.
+    Prompt,
+    /// The model's output.
+    Response,
+}
+
+/// Harm level, normalized across providers: Gemini's probability
+/// buckets (`NEGLIGIBLE` … `HIGH`) and Azure's severities (`safe` …
+/// `high`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyLevel {
+    /// Gemini `NEGLIGIBLE`, Azure `safe`.
+    Negligible,
+    /// `LOW` / `low`.
+    Low,
+    /// `MEDIUM` / `medium`.
+    Medium,
+    /// `HIGH` / `high`.
+    High,
+}
+
+impl SafetyLevel {
+    /// Parse either provider's label, case-insensitively. Unknown
+    /// labels (e.g. `HARM_PROBABILITY_UNSPECIFIED`) give `None`.
+    pub fn parse(label: &str) -> Option<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "negligible" | "safe" => Some(Self::Negligible),
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+impl FinishReason {
+    /// Stable snake_case label — the serialized name for unit variants,
+    /// the provider's own string for [`Self::Other`].
+    pub fn as_str(&self) -> &str {
+        match self {
+            FinishReason::Stop => "stop",
+            FinishReason::StopSequence => "stop_sequence",
+            FinishReason::Length => "length",
+            FinishReason::ToolCalls => "tool_calls",
+            FinishReason::ContentFilter => "content_filter",
+            FinishReason::Safety => "safety",
+            FinishReason::Incomplete => "incomplete",
+            FinishReason::Other(reason) => reason,
+        }
+    }
 }
@@ -1,5 +1,13 @@
 //! Core types used throughout the library.
 
+// `From` / `TryFrom` impls between this crate's request types and
+// async-openai's; trait impls only, so nothing to re-export.
+#[cfg(feature = "async-openai")]
+mod async_openai;
+// `Prompt` <-> OpenAI chat `messages` conversion; adds inherent methods
+// to `Prompt` only, so nothing to re-export.
+pub(crate) mod chat_messages;
+
 /// Request configuration, reasoning options, response formats, and usage
 /// accounting.
 pub mod config;
@@ -10,6 +18,9 @@ pub mod message;
 /// Convenience builder for assembling a [`prompt::Prompt`] without writing
 /// out the underlying `Vec<InputItem>` by hand.
 pub mod prompt;
+/// Typed settings only one provider understands — see
+/// [`provider_options::ProviderOptions`].
+pub mod provider_options;
 pub mod streaming;
 
 // Explicit re-exports — no globs so that adding a `pub` item inside a
@@ -17,12 +28,18 @@ pub mod streaming;
 
 pub use config::{
     Config, ConfigBuilder, ProviderContinuation, RawConfig, ReasoningConfig, ReasoningEffort,
-    ReasoningSummary, ResponseFormat, ToolChoice, Usage,
+    ReasoningSummary, RequestMetadata, ResponseFormat, ToolChoice, Usage,
 };
 pub use files::{FileResolver, LruFileResolver, ProviderScope, ResolvedFile, ResolvedHandle};
 pub use message::{
     Annotation, AnnotationKind, AssistantPart, ComputerUseConfig, FileSource, FinishReason,
-    Function, FunctionCall, InputItem, ProviderBuiltin, Tool, UserPart,
+    Function, FunctionCall, InputItem, ProviderBuiltin, SafetyFeedback, SafetyLevel, SafetyRating,
+    SafetyTarget, Tool, UserPart,
 };
+pub(crate) use prompt::estimated_text_tokens;
 pub use prompt::Prompt;
+pub use provider_options::{
+    AnthropicOptions, GoogleOptions, GoogleSafetySetting, HarmBlockThreshold, HarmCategory,
+    OpenAIOptions, OpenAIServiceTier, ProviderOptions,
+};
 pub use streaming::{PartKind, PartUpdate, StreamEvent};
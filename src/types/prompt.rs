@@ -1,9 +1,24 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use super::message::{FunctionCall, InputItem};
+use super::message::{AssistantPart, FunctionCall, InputItem, UserPart};
+
+/// Bytes of text per estimated token. Counting bytes rather than chars
+/// keeps the estimate on the high side for non-Latin scripts, which
+/// tokenize denser than English.
+const BYTES_PER_TOKEN: usize = 4;
+/// Role markers and separators each message costs on the wire.
+const TOKENS_PER_MESSAGE: u32 = 4;
+/// Flat allowance per image, audio, video or document input. Real
+/// costs depend on resolution / duration / page count, which a
+/// `FileSource` doesn't carry.
+const TOKENS_PER_MEDIA: u32 = 1_000;
 
 /// A structured prompt containing a sequence of input items.
-#[derive(Debug, Clone, Serialize)]
+///
+/// Serializes losslessly (`{"items": [...]}`) for storage; for the
+/// OpenAI chat `messages` format see [`Self::from_chat_messages`] /
+/// [`Self::to_chat_messages`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prompt {
     items: Vec<InputItem>,
 }
@@ -34,6 +49,12 @@ impl Prompt {
         self
     }
 
+    /// Append a developer instruction. See [`InputItem::Developer`].
+    pub fn with_developer(mut self, content: impl Into<String>) -> Self {
+        self.items.push(InputItem::developer(content));
+        self
+    }
+
     /// Append a user message.
     pub fn with_user(mut self, content: impl Into<String>) -> Self {
         self.items.push(InputItem::user(content));
@@ -46,6 +67,37 @@ impl Prompt {
         self
     }
 
+    /// End the prompt with the opening of the model's answer — e.g.
+    /// `{` to force JSON, or `<analysis>` to force a format — so the
+    /// model continues from it rather than starting fresh. The
+    /// response holds only the continuation; prepend the prefill to
+    /// get the whole answer.
+    ///
+    /// Anthropic and Gemini continue a trailing assistant turn
+    /// natively (Anthropic rejects trailing whitespace, so it is
+    /// trimmed there). Local ChatML models get the turn left open in
+    /// the template. OpenAI has no prefill; the message is sent as
+    /// history with a developer instruction to continue it.
+    ///
+    /// Must be the last item: anything appended after it turns it
+    /// back into an ordinary assistant message.
+    pub fn with_assistant_prefill(self, content: impl Into<String>) -> Self {
+        self.with_assistant(content)
+    }
+
+    /// The assistant prefill the prompt ends with — its last item is
+    /// an assistant turn consisting of a single text part. See
+    /// [`Self::with_assistant_prefill`].
+    pub fn assistant_prefill(&self) -> Option<&str> {
+        match self.items.last()? {
+            InputItem::Assistant { content, .. } => match content.as_slice() {
+                [AssistantPart::Text { content, .. }] => Some(content),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     /// Append a pre-built [`InputItem`] verbatim.
     pub fn with_item(mut self, item: InputItem) -> Self {
         self.items.push(item);
@@ -82,6 +134,18 @@ impl Prompt {
         self
     }
 
+    /// Rough token count of the prompt, for picking a model or trimming
+    /// history *before* a request instead of learning from a
+    /// context-length error. Text is costed at one token per four
+    /// bytes plus a small per-message overhead, and every media input
+    /// at a flat allowance — no tokenizer is involved, so treat it as
+    /// an estimate within tens of percent, not a bill. See
+    /// [`crate::Capabilities::fits_in_context`] for the whole-request
+    /// check.
+    pub fn estimated_tokens(&self) -> u32 {
+        self.items.iter().map(estimated_item_tokens).sum()
+    }
+
     /// Borrow the accumulated items.
     pub fn items(&self) -> &[InputItem] {
         &self.items
@@ -93,6 +157,50 @@ impl Prompt {
     }
 }
 
+pub(crate) fn estimated_text_tokens(text: &str) -> u32 {
+    text.len().div_ceil(BYTES_PER_TOKEN) as u32
+}
+
+fn estimated_item_tokens(item: &InputItem) -> u32 {
+    let content = match item {
+        InputItem::System(text) | InputItem::Developer(text) => estimated_text_tokens(text),
+        InputItem::User { content, .. } => content.iter().map(estimated_user_part_tokens).sum(),
+        InputItem::Assistant { content, .. } => content
+            .iter()
+            .map(|part| match part {
+                AssistantPart::Text { content, .. }
+                | AssistantPart::Reasoning { content, .. }
+                | AssistantPart::Refusal(content) => estimated_text_tokens(content),
+                AssistantPart::ToolCall(call) => {
+                    estimated_text_tokens(&call.name) + estimated_text_tokens(&call.arguments)
+                }
+                AssistantPart::BuiltinToolCall {
+                    arguments, result, ..
+                } => {
+                    estimated_text_tokens(arguments)
+                        + result.as_deref().map_or(0, estimated_text_tokens)
+                }
+                AssistantPart::RedactedReasoning { data } => estimated_text_tokens(data),
+                AssistantPart::Continuation(_) | AssistantPart::CacheBreakpoint => 0,
+            })
+            .sum(),
+    };
+    TOKENS_PER_MESSAGE + content
+}
+
+fn estimated_user_part_tokens(part: &UserPart) -> u32 {
+    match part {
+        UserPart::Text(text) => estimated_text_tokens(text),
+        UserPart::Image(_) | UserPart::Audio(_) | UserPart::Document(_) | UserPart::Video(_) => {
+            TOKENS_PER_MEDIA
+        }
+        UserPart::ToolResult { content, .. } => {
+            content.iter().map(estimated_user_part_tokens).sum()
+        }
+        UserPart::CacheBreakpoint => 0,
+    }
+}
+
 impl Default for Prompt {
     fn default() -> Self {
         Self::new()
@@ -143,6 +251,46 @@ mod tests {
         assert!(matches!(prompt.items()[1], InputItem::User { .. }));
     }
 
+    #[test]
+    fn estimated_tokens_scales_with_content() {
+        use crate::types::FileSource;
+        let short = Prompt::user("hi").estimated_tokens();
+        let long = Prompt::user("hi ".repeat(400)).estimated_tokens();
+        assert_eq!(short, TOKENS_PER_MESSAGE + 1);
+        assert_eq!(long, TOKENS_PER_MESSAGE + 300);
+
+        let with_image = Prompt::from(InputItem::User {
+            content: vec![
+                UserPart::Text("what is this".into()),
+                UserPart::Image(FileSource::Url("https://x/y.png".into())),
+            ],
+            name: None,
+            metadata: Default::default(),
+        });
+        assert_eq!(
+            with_image.estimated_tokens(),
+            TOKENS_PER_MESSAGE + 3 + TOKENS_PER_MEDIA
+        );
+    }
+
+    #[test]
+    fn item_metadata_survives_serde_round_trip() {
+        let prompt = Prompt::system("sys")
+            .with_item(InputItem::user("hi").with_metadata("ui_message_id", "m-17"))
+            .with_item(InputItem::system("ignored").with_metadata("k", "v"));
+        let json = serde_json::to_string(&prompt).unwrap();
+        let back: Prompt = serde_json::from_str(&json).unwrap();
+        let metadata = back.items()[1].metadata().unwrap();
+        assert_eq!(
+            metadata.get("ui_message_id").map(String::as_str),
+            Some("m-17")
+        );
+        assert!(back.items()[2].metadata().is_none());
+        assert!(!serde_json::to_string(&Prompt::user("x"))
+            .unwrap()
+            .contains("metadata"));
+    }
+
     #[test]
     fn from_str_creates_single_user_item() {
         let p: Prompt = "hello".into();
@@ -160,8 +308,25 @@ mod tests {
             }],
             finish_reason: FinishReason::Stop,
             usage: Usage::default(),
+            safety: None,
+            timing: None,
         };
         let extended = prompt.with_response(&response);
         assert_eq!(extended.items().len(), 3);
     }
+
+    #[test]
+    fn assistant_prefill_is_a_trailing_text_only_assistant_turn() {
+        let prompt = Prompt::user("List three colours as JSON").with_assistant_prefill("[");
+        assert_eq!(prompt.assistant_prefill(), Some("["));
+        assert_eq!(prompt.clone().with_user("again").assistant_prefill(), None);
+        let tool_call = Prompt::user("hi").with_assistant_tool_call(FunctionCall {
+            call_id: "c1".into(),
+            name: "f".into(),
+            arguments: "{}".into(),
+            provider_signature: None,
+            original_arguments: None,
+        });
+        assert_eq!(tool_call.assistant_prefill(), None);
+    }
 }
@@ -0,0 +1,222 @@
+//! Typed, provider-unique request settings.
+//!
+//! [`RawConfig`](crate::RawConfig) models what every backend shares;
+//! [`ProviderOptions`] carries the knobs only one backend has — OpenAI's
+//! service tier, Gemini's safety settings, Anthropic's beta flags. Each
+//! provider reads its own section and ignores the others, so one config
+//! can carry settings for every backend in a fallback or router chain.
+//!
+//! Unlike [`RawConfig::extra_body`](crate::RawConfig::extra_body), these
+//! are checked by [`crate::generate`] before the call (see
+//! [`ProviderOptions::validate`]), so a typo fails fast with
+//! [`Error::Config`] instead of as an upstream 400.
+
+use crate::Error;
+
+/// Per-provider settings attached via
+/// [`ConfigBuilder::provider_options`](crate::ConfigBuilder::provider_options).
+/// Unset sections leave the provider's defaults alone.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderOptions {
+    /// Read by the OpenAI provider.
+    pub openai: Option<OpenAIOptions>,
+    /// Read by the Google (Gemini on Vertex) provider.
+    pub google: Option<GoogleOptions>,
+    /// Read by the Anthropic-on-Vertex provider.
+    pub anthropic: Option<AnthropicOptions>,
+}
+
+impl ProviderOptions {
+    /// Set the OpenAI section.
+    pub fn with_openai(mut self, options: OpenAIOptions) -> Self {
+        self.openai = Some(options);
+        self
+    }
+
+    /// Set the Google section.
+    pub fn with_google(mut self, options: GoogleOptions) -> Self {
+        self.google = Some(options);
+        self
+    }
+
+    /// Set the Anthropic section.
+    pub fn with_anthropic(mut self, options: AnthropicOptions) -> Self {
+        self.anthropic = Some(options);
+        self
+    }
+
+    /// Check every section for values the upstream API would reject.
+    /// Run by [`crate::RawConfig::validate`] on every call.
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(google) = &self.google {
+            google.validate()?;
+        }
+        if let Some(anthropic) = &self.anthropic {
+            anthropic.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// OpenAI-only settings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenAIOptions {
+    /// Processing tier (`service_tier`). `None` uses the project default.
+    pub service_tier: Option<OpenAIServiceTier>,
+}
+
+/// OpenAI processing tier — trades latency and availability for price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenAIServiceTier {
+    /// Let OpenAI pick, per the project's settings.
+    Auto,
+    /// Standard pricing and performance.
+    Default,
+    /// Cheaper, slower, and may be rejected under load.
+    Flex,
+    /// Faster, at a premium.
+    Priority,
+}
+
+/// Gemini-only settings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoogleOptions {
+    /// Per-category blocking thresholds (`safetySettings`). Each
+    /// category may appear at most once.
+    pub safety_settings: Vec<GoogleSafetySetting>,
+    /// Sample from the `top_k` most likely tokens (`topK`). Must be at
+    /// least 1.
+    pub top_k: Option<u32>,
+}
+
+impl GoogleOptions {
+    fn validate(&self) -> Result<(), Error> {
+        if self.top_k == Some(0) {
+            return Err(Error::config("google top_k must be at least 1"));
+        }
+        for (i, setting) in self.safety_settings.iter().enumerate() {
+            if self.safety_settings[..i]
+                .iter()
+                .any(|earlier| earlier.category == setting.category)
+            {
+                return Err(Error::config(format!(
+                    "google safety setting for {:?} is given more than once",
+                    setting.category
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One Gemini safety threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoogleSafetySetting {
+    /// Which harm category the threshold applies to.
+    pub category: HarmCategory,
+    /// How likely a harm must be before the response is blocked.
+    pub threshold: HarmBlockThreshold,
+}
+
+/// Gemini harm category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HarmCategory {
+    /// `HARM_CATEGORY_HATE_SPEECH`.
+    HateSpeech,
+    /// `HARM_CATEGORY_DANGEROUS_CONTENT`.
+    DangerousContent,
+    /// `HARM_CATEGORY_HARASSMENT`.
+    Harassment,
+    /// `HARM_CATEGORY_SEXUALLY_EXPLICIT`.
+    SexuallyExplicit,
+    /// `HARM_CATEGORY_CIVIC_INTEGRITY`.
+    CivicIntegrity,
+}
+
+/// Gemini blocking threshold, from strictest to off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HarmBlockThreshold {
+    /// Block at low probability and above.
+    BlockLowAndAbove,
+    /// Block at medium probability and above.
+    BlockMediumAndAbove,
+    /// Block only high-probability harm.
+    BlockOnlyHigh,
+    /// Never block, but still report safety ratings.
+    BlockNone,
+    /// Turn the filter off entirely.
+    Off,
+}
+
+/// Anthropic-only settings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnthropicOptions {
+    /// Beta feature ids (e.g. `"context-1m-2025-08-07"`) for this
+    /// request, sent in `anthropic-beta` alongside any set on the
+    /// provider.
+    pub beta: Vec<String>,
+    /// Sample from the `top_k` most likely tokens. Must be at least 1.
+    pub top_k: Option<u32>,
+}
+
+impl AnthropicOptions {
+    fn validate(&self) -> Result<(), Error> {
+        if self.top_k == Some(0) {
+            return Err(Error::config("anthropic top_k must be at least 1"));
+        }
+        if let Some(bad) = self
+            .beta
+            .iter()
+            .find(|id| id.is_empty() || id.contains(|c: char| c == ',' || c.is_whitespace()))
+        {
+            return Err(Error::config(format!(
+                "anthropic beta id {bad:?} must be a single non-empty token"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_what_the_api_would() {
+        let ok = ProviderOptions::default()
+            .with_google(GoogleOptions {
+                safety_settings: vec![GoogleSafetySetting {
+                    category: HarmCategory::Harassment,
+                    threshold: HarmBlockThreshold::BlockOnlyHigh,
+                }],
+                top_k: Some(40),
+            })
+            .with_anthropic(AnthropicOptions {
+                beta: vec!["context-1m-2025-08-07".into()],
+                top_k: None,
+            });
+        ok.validate().unwrap();
+
+        let duplicate = GoogleSafetySetting {
+            category: HarmCategory::HateSpeech,
+            threshold: HarmBlockThreshold::Off,
+        };
+        let bad = [
+            ProviderOptions::default().with_google(GoogleOptions {
+                safety_settings: vec![duplicate, duplicate],
+                top_k: None,
+            }),
+            ProviderOptions::default().with_anthropic(AnthropicOptions {
+                beta: vec!["a,b".into()],
+                top_k: None,
+            }),
+            ProviderOptions::default().with_anthropic(AnthropicOptions {
+                beta: Vec::new(),
+                top_k: Some(0),
+            }),
+        ];
+        for options in bad {
+            assert!(matches!(options.validate(), Err(Error::Config(_))));
+        }
+    }
+}
@@ -5,10 +5,19 @@
 //! (0, 1, 2, …). The accumulator becomes a straight-line dispatch on
 //! variant — no implicit "currently-active part" state.
 
-use crate::types::{Annotation, FinishReason, ProviderBuiltin, ProviderContinuation, Usage};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    Annotation, FinishReason, ProviderBuiltin, ProviderContinuation, SafetyFeedback, Usage,
+};
 
 /// Events emitted by [`crate::Response`] streams.
-#[derive(Debug, Clone)]
+///
+/// Serializable (externally tagged, `snake_case` variant names) so
+/// event sequences can be recorded and replayed — see
+/// `providers::vcr`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StreamEvent {
     /// A new assistant content part is opening. `index` is monotonically
     /// increasing within the turn. One-shot parts
@@ -48,6 +57,14 @@ pub enum StreamEvent {
         index: u32,
     },
 
+    /// The provider's safety ratings and block outcome for the turn.
+    /// Arrives at most once, just before `Done`, and only from
+    /// providers that report safety information.
+    Safety {
+        /// Ratings and block reason.
+        feedback: SafetyFeedback,
+    },
+
     /// The assistant turn is complete.
     Done {
         /// Why the model stopped.
@@ -59,7 +76,8 @@ pub enum StreamEvent {
 
 /// Kind of part being streamed. Mirrors [`crate::AssistantPart`] but in
 /// "header" form — the content arrives via subsequent [`StreamEvent`]s.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PartKind {
     /// Visible text part.
     Text,
@@ -94,7 +112,8 @@ pub enum PartKind {
 }
 
 /// Metadata update for a streaming part.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PartUpdate {
     /// Opaque provider signature for the part being updated. On a
     /// [`PartKind::Reasoning`] part it carries Anthropic's thinking
@@ -108,6 +127,10 @@ pub enum PartUpdate {
     /// shape depends on the builtin (`{"outcome": "...", "output":
     /// "..."}` for code execution).
     BuiltinToolResult(String),
+    /// The tool call's arguments were rewritten into valid JSON; this is
+    /// what the model originally emitted. Lands on
+    /// [`crate::FunctionCall::original_arguments`].
+    OriginalArguments(String),
 }
 
 #[cfg(test)]
@@ -0,0 +1,439 @@
+//! Token and cost accounting aggregated across calls.
+//!
+//! A [`UsageTracker`] sums the [`Usage`] of every call it is fed —
+//! input, output, cache-read, cache-write and reasoning tokens, plus
+//! cost from a per-model [`ModelPricing`] table — three ways at once:
+//! per model, per provider, per tenant, and per fixed time window.
+//! Clones share
+//! one set of totals, so a single tracker can sit under every provider
+//! in a service; [`UsageTracker::snapshot`] exports the lot as a
+//! serializable [`UsageSnapshot`].
+//!
+//! ```ignore
+//! use platformed_llm::usage::{ModelPricing, UsageTracker};
+//!
+//! let tracker = UsageTracker::new()
+//!     .with_pricing("gpt-4o-mini", ModelPricing::new(0.15, 0.60).with_cache_read(0.075))
+//!     .with_pricing("claude-sonnet-4", ModelPricing::new(3.0, 15.0));
+//! let openai = ProviderStack::new()
+//!     .layer(tracker.layer("openai"))
+//!     .service(openai);
+//! // …
+//! let snapshot = tracker.snapshot();
+//! println!("{}", serde_json::to_string_pretty(&snapshot)?);
+//! ```
+//!
+//! Feeding goes through [`crate::metrics`]: [`UsageTracker::layer`] is
+//! a [`MetricsLayer`] whose observer records each finished call under
+//! the given provider label — or under
+//! [`ResponseMetadata::served_by`](crate::ResponseMetadata::served_by)
+//! when a router or fallback underneath reports the backend that
+//! actually answered. Calls tagged with
+//! [`ConfigBuilder::tenant`](crate::ConfigBuilder::tenant) — the same
+//! id the rate limiter queues by — are also summed per tenant, the
+//! basis for multi-tenant billing. Wrappers
+//! with their own accounting can call [`UsageTracker::record`]
+//! directly. Only calls that report usage count; failed and cancelled
+//! calls have none.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::metrics::{CallMetrics, MetricsLayer, SharedMetricsObserver};
+use crate::Usage;
+
+/// Prices for one model, per million tokens, in whatever currency the
+/// caller works in. Cache rates default to the input rate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Uncached input tokens.
+    pub input_per_million: f64,
+    /// Output tokens, reasoning included.
+    pub output_per_million: f64,
+    /// Input tokens served from the prompt cache.
+    pub cache_read_per_million: Option<f64>,
+    /// Input tokens written to the prompt cache.
+    pub cache_write_per_million: Option<f64>,
+}
+
+impl ModelPricing {
+    /// Input and output rates per million tokens.
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+            cache_read_per_million: None,
+            cache_write_per_million: None,
+        }
+    }
+
+    /// Rate for cache-read input tokens.
+    pub fn with_cache_read(mut self, per_million: f64) -> Self {
+        self.cache_read_per_million = Some(per_million);
+        self
+    }
+
+    /// Rate for cache-write input tokens.
+    pub fn with_cache_write(mut self, per_million: f64) -> Self {
+        self.cache_write_per_million = Some(per_million);
+        self
+    }
+
+    /// What `usage` costs at these rates.
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let rate = |tokens: u32, per_million: f64| f64::from(tokens) * per_million / 1_000_000.0;
+        rate(usage.uncached_input_tokens(), self.input_per_million)
+            + rate(
+                usage.cache_read_input_tokens.unwrap_or(0),
+                self.cache_read_per_million
+                    .unwrap_or(self.input_per_million),
+            )
+            + rate(
+                usage.cache_creation_input_tokens.unwrap_or(0),
+                self.cache_write_per_million
+                    .unwrap_or(self.input_per_million),
+            )
+            + rate(usage.output_tokens, self.output_per_million)
+    }
+}
+
+/// Summed usage for one slice of traffic.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    /// Calls counted.
+    pub requests: u64,
+    /// Input tokens, cached ones included.
+    pub input_tokens: u64,
+    /// Output tokens, reasoning included.
+    pub output_tokens: u64,
+    /// Input tokens served from the prompt cache.
+    pub cache_read_input_tokens: u64,
+    /// Input tokens written to the prompt cache.
+    pub cache_creation_input_tokens: u64,
+    /// Output tokens spent on reasoning.
+    pub reasoning_tokens: u64,
+    /// Cost of the priced calls.
+    pub cost: f64,
+    /// Calls whose model had no [`ModelPricing`], and so add nothing
+    /// to `cost`.
+    pub unpriced_requests: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &Usage, cost: Option<f64>) {
+        self.requests += 1;
+        self.input_tokens += u64::from(usage.input_tokens);
+        self.output_tokens += u64::from(usage.output_tokens);
+        self.cache_read_input_tokens += u64::from(usage.cache_read_input_tokens.unwrap_or(0));
+        self.cache_creation_input_tokens +=
+            u64::from(usage.cache_creation_input_tokens.unwrap_or(0));
+        self.reasoning_tokens += u64::from(usage.reasoning_tokens.unwrap_or(0));
+        match cost {
+            Some(cost) => self.cost += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+}
+
+/// Usage within one time window of a [`UsageSnapshot`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowUsage {
+    /// Window start, in seconds since the UNIX epoch. Windows are
+    /// aligned to multiples of their length.
+    pub start_unix_secs: u64,
+    /// Window length in seconds.
+    pub length_secs: u64,
+    /// Usage recorded within the window.
+    pub totals: UsageTotals,
+}
+
+/// A point-in-time export of a [`UsageTracker`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageSnapshot {
+    /// Everything recorded since creation or the last
+    /// [`UsageTracker::reset`].
+    pub total: UsageTotals,
+    /// Keyed by model.
+    pub by_model: BTreeMap<String, UsageTotals>,
+    /// Keyed by provider label.
+    pub by_provider: BTreeMap<String, UsageTotals>,
+    /// Keyed by tenant id, hyphenated. Calls without a tenant appear
+    /// only in the other breakdowns.
+    pub by_tenant: BTreeMap<String, UsageTotals>,
+    /// The retained time windows, oldest first.
+    pub windows: Vec<WindowUsage>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    total: UsageTotals,
+    by_model: BTreeMap<String, UsageTotals>,
+    by_provider: BTreeMap<String, UsageTotals>,
+    by_tenant: BTreeMap<String, UsageTotals>,
+    windows: VecDeque<(u64, UsageTotals)>,
+}
+
+/// Aggregates usage and cost across calls. See the
+/// [module docs](self).
+#[derive(Debug, Clone)]
+pub struct UsageTracker {
+    pricing: Arc<BTreeMap<String, ModelPricing>>,
+    window: Duration,
+    retained_windows: usize,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self {
+            pricing: Arc::default(),
+            window: Duration::from_secs(3600),
+            retained_windows: 24,
+            state: Arc::default(),
+        }
+    }
+}
+
+impl UsageTracker {
+    /// A tracker with no pricing and hourly windows, keeping the last
+    /// 24.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Price calls to `model` with `pricing`. A model without an exact
+    /// entry takes the longest entry that prefixes it, so a
+    /// `"gpt-4o"` entry covers `"gpt-4o-2024-08-06"` while a
+    /// `"gpt-4o-mini"` entry still wins for that model. Configure
+    /// before cloning — clones share totals, not later pricing.
+    pub fn with_pricing(mut self, model: impl Into<String>, pricing: ModelPricing) -> Self {
+        Arc::make_mut(&mut self.pricing).insert(model.into(), pricing);
+        self
+    }
+
+    /// Aggregate into windows of `length` (whole seconds, at least
+    /// one), keeping the most recent `retain`.
+    pub fn with_windows(mut self, length: Duration, retain: usize) -> Self {
+        self.window = length.max(Duration::from_secs(1));
+        self.retained_windows = retain;
+        self
+    }
+
+    /// The pricing that applies to `model`, if any.
+    pub fn pricing_for(&self, model: &str) -> Option<&ModelPricing> {
+        self.pricing.get(model).or_else(|| {
+            self.pricing
+                .iter()
+                .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, pricing)| pricing)
+        })
+    }
+
+    /// Record one call's `usage` now, attributed to `tenant` if any.
+    pub fn record(&self, provider: &str, model: &str, tenant: Option<Uuid>, usage: &Usage) {
+        self.record_at(provider, model, tenant, usage, SystemTime::now());
+    }
+
+    /// Record one call's `usage` as of `at` — for backfilling from logs.
+    /// Usage older than the retained windows still counts towards the
+    /// model, provider and overall totals.
+    pub fn record_at(
+        &self,
+        provider: &str,
+        model: &str,
+        tenant: Option<Uuid>,
+        usage: &Usage,
+        at: SystemTime,
+    ) {
+        let cost = self.pricing_for(model).map(|pricing| pricing.cost(usage));
+        let length = self.window.as_secs();
+        let secs = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let start = secs - secs % length;
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.total.add(usage, cost);
+        state
+            .by_model
+            .entry(model.to_string())
+            .or_default()
+            .add(usage, cost);
+        state
+            .by_provider
+            .entry(provider.to_string())
+            .or_default()
+            .add(usage, cost);
+        if let Some(tenant) = tenant {
+            state
+                .by_tenant
+                .entry(tenant.hyphenated().to_string())
+                .or_default()
+                .add(usage, cost);
+        }
+
+        let windows = &mut state.windows;
+        let slot = match windows.binary_search_by_key(&start, |(start, _)| *start) {
+            Ok(slot) => Some(slot),
+            Err(slot) if slot > 0 || windows.len() < self.retained_windows => {
+                windows.insert(slot, (start, UsageTotals::default()));
+                Some(slot)
+            }
+            Err(_) => None,
+        };
+        if let Some(slot) = slot {
+            windows[slot].1.add(usage, cost);
+        }
+        while windows.len() > self.retained_windows {
+            windows.pop_front();
+        }
+    }
+
+    /// Export the current totals.
+    pub fn snapshot(&self) -> UsageSnapshot {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let length_secs = self.window.as_secs();
+        UsageSnapshot {
+            total: state.total.clone(),
+            by_model: state.by_model.clone(),
+            by_provider: state.by_provider.clone(),
+            by_tenant: state.by_tenant.clone(),
+            windows: state
+                .windows
+                .iter()
+                .map(|(start, totals)| WindowUsage {
+                    start_unix_secs: *start,
+                    length_secs,
+                    totals: totals.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Export the current totals and start again from zero.
+    pub fn reset(&self) -> UsageSnapshot {
+        let snapshot = self.snapshot();
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = State::default();
+        snapshot
+    }
+
+    /// A [`MetricsObserver`](crate::MetricsObserver) recording each
+    /// call under `provider`, or under the backend a wrapper reported
+    /// in [`CallMetrics::served_by`], and under its
+    /// [`CallMetrics::tenant`].
+    pub fn observer(&self, provider: impl Into<String>) -> SharedMetricsObserver {
+        let tracker = self.clone();
+        let provider = provider.into();
+        Arc::new(move |metrics: &CallMetrics| {
+            if let Some(usage) = &metrics.usage {
+                let provider = metrics.served_by.as_deref().unwrap_or(&provider);
+                tracker.record(provider, &metrics.model, metrics.tenant, usage);
+            }
+        })
+    }
+
+    /// A [`MetricsLayer`] feeding this tracker — see [`Self::observer`].
+    pub fn layer(&self, provider: impl Into<String>) -> MetricsLayer {
+        MetricsLayer::new(self.observer(provider))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use crate::{generate, Config, Prompt};
+
+    fn usage(input: u32, output: u32) -> Usage {
+        Usage {
+            input_tokens: input,
+            output_tokens: output,
+            ..Usage::default()
+        }
+    }
+
+    #[test]
+    fn prices_cached_tokens_and_matches_model_prefixes() {
+        let pricing = ModelPricing::new(2.0, 8.0).with_cache_read(0.5);
+        let cached = Usage {
+            cache_read_input_tokens: Some(400_000),
+            ..usage(1_000_000, 500_000)
+        };
+        assert!((pricing.cost(&cached) - (1.2 + 0.2 + 4.0)).abs() < 1e-9);
+
+        let tracker = UsageTracker::new()
+            .with_pricing("gpt-4o", ModelPricing::new(2.5, 10.0))
+            .with_pricing("gpt-4o-mini", ModelPricing::new(0.15, 0.6));
+        let price = |model| tracker.pricing_for(model).map(|p| p.input_per_million);
+        assert_eq!(price("gpt-4o-2024-08-06"), Some(2.5));
+        assert_eq!(price("gpt-4o-mini-2024-07-18"), Some(0.15));
+        assert_eq!(price("claude"), None);
+    }
+
+    #[test]
+    fn aggregates_per_model_provider_and_window() {
+        let tracker = UsageTracker::new()
+            .with_pricing("a", ModelPricing::new(1.0, 1.0))
+            .with_windows(Duration::from_secs(60), 2);
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        let (t1, t2) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        tracker.record_at("p1", "a", Some(t1), &usage(500_000, 500_000), at(0));
+        tracker.record_at("p2", "a", Some(t2), &usage(10, 5), at(59));
+        tracker.record_at("p2", "b", Some(t2), &usage(1, 1), at(60));
+        tracker.record_at("p2", "b", None, &usage(1, 1), at(130));
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.total.requests, 4);
+        assert_eq!(snapshot.total.unpriced_requests, 2);
+        assert!((snapshot.by_model["a"].cost - 1.000015).abs() < 1e-9);
+        assert_eq!(snapshot.by_provider["p2"].input_tokens, 12);
+        assert!((snapshot.by_tenant[&t1.to_string()].cost - 1.0).abs() < 1e-9);
+        assert_eq!(snapshot.by_tenant[&t2.to_string()].requests, 2);
+        assert_eq!(snapshot.by_tenant.len(), 2);
+        let windows: Vec<_> = snapshot
+            .windows
+            .iter()
+            .map(|w| (w.start_unix_secs, w.totals.requests))
+            .collect();
+        assert_eq!(windows, [(60, 1), (120, 1)]);
+
+        // Too old for the retained windows, but still in the totals.
+        tracker.record_at("p1", "a", None, &usage(1, 1), at(0));
+        assert_eq!(tracker.snapshot().windows.len(), 2);
+        assert_eq!(tracker.reset().total.requests, 5);
+        assert_eq!(tracker.snapshot(), UsageSnapshot::default());
+    }
+
+    #[tokio::test]
+    async fn layer_records_finished_calls() {
+        let tracker = UsageTracker::new();
+        let tenant = Uuid::from_u128(7);
+        let provider = crate::ProviderStack::new()
+            .layer(tracker.layer("mock"))
+            .service(
+                MockProvider::builder()
+                    .reply(MockResponse::text("hi").usage(usage(7, 3)))
+                    .build(),
+            );
+        generate(
+            &*provider,
+            &Prompt::user("x"),
+            &Config::builder("m").tenant(tenant).build(),
+        )
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.by_provider["mock"].input_tokens, 7);
+        assert_eq!(snapshot.by_model["m"].output_tokens, 3);
+        assert_eq!(snapshot.by_tenant[&tenant.to_string()].requests, 1);
+    }
+}
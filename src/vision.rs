@@ -0,0 +1,557 @@
+//! Preparing images for vision input.
+//!
+//! Every provider takes images, but not the same ones: OpenAI accepts
+//! GIFs that Gemini rejects, Anthropic caps an image at 5 MB where
+//! OpenAI allows 20, and pixels past a provider's working resolution
+//! are scaled away server-side after being paid for in upload time.
+//! [`ImageLimits::for_provider`] captures those rules, and
+//! [`prepare_image`] turns raw bytes into a [`UserPart::Image`] that
+//! fits them.
+//!
+//! Format and dimensions come from the file header, parsed here for
+//! PNG, JPEG, GIF and WebP, so an image that already fits is passed
+//! through without being decoded. One that doesn't is scaled and
+//! re-encoded by [`DefaultTranscoder`], over the `image` crate; pass
+//! your own [`ImageTranscoder`] to use a different codec or filter.
+//!
+//! ```ignore
+//! use platformed_llm::vision::image_part;
+//!
+//! let image = image_part(std::fs::read("scan.png")?, ProviderType::Anthropic, None)?;
+//! let prompt = Prompt::new().with_item(InputItem::User {
+//!     content: vec![UserPart::Text("What is this?".into()), image],
+//!     name: None,
+//!     metadata: Default::default(),
+//! });
+//! ```
+
+use base64::Engine as _;
+
+use crate::{Error, FileSource, ProviderType, UserPart};
+
+/// An image encoding the helpers can recognize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ImageFormat {
+    /// PNG.
+    Png,
+    /// JPEG.
+    Jpeg,
+    /// GIF. Only the first frame is read by providers that accept it.
+    Gif,
+    /// WebP, lossy or lossless.
+    Webp,
+}
+
+impl ImageFormat {
+    /// The MIME type, e.g. `image/png`.
+    pub fn media_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Webp => "image/webp",
+        }
+    }
+
+    /// Recognize an encoding from its leading magic bytes.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some(ImageFormat::Webp)
+        } else {
+            None
+        }
+    }
+}
+
+/// Format and pixel dimensions of an encoded image, read from its
+/// header without decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    /// The encoding.
+    pub format: ImageFormat,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+impl ImageInfo {
+    /// Read the header of `bytes`. Fails with [`Error::InvalidPrompt`]
+    /// on an unrecognized format or a truncated header.
+    pub fn read(bytes: &[u8]) -> Result<Self, Error> {
+        let format = ImageFormat::detect(bytes).ok_or_else(|| {
+            Error::invalid_prompt("image is not a recognized PNG, JPEG, GIF or WebP file")
+        })?;
+        let dimensions = match format {
+            ImageFormat::Png => png_dimensions(bytes),
+            ImageFormat::Jpeg => jpeg_dimensions(bytes),
+            ImageFormat::Gif => le16(bytes, 6).zip(le16(bytes, 8)),
+            ImageFormat::Webp => webp_dimensions(bytes),
+        };
+        let (width, height) = dimensions.filter(|&(w, h)| w > 0 && h > 0).ok_or_else(|| {
+            Error::invalid_prompt(format!(
+                "{} image header is truncated or malformed",
+                format.media_type()
+            ))
+        })?;
+        Ok(Self {
+            format,
+            width,
+            height,
+        })
+    }
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?).into())
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?).into())
+}
+
+fn le24(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], 0]))
+}
+
+/// `IHDR` is always the first chunk: width and height follow its type.
+fn png_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let word = |at: usize| Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    Some((word(16)?, word(20)?))
+}
+
+/// Walk the marker segments to the first start-of-frame.
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    loop {
+        if *bytes.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(at + 1)?;
+        match marker {
+            // Fill byte before a marker.
+            0xFF => at += 1,
+            // Standalone markers carry no length.
+            0x01 | 0xD0..=0xD7 => at += 2,
+            // SOF0–SOF15, minus DHT (C4), JPG (C8) and DAC (CC).
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                return Some((be16(bytes, at + 7)?, be16(bytes, at + 5)?));
+            }
+            _ => at += 2 + usize::try_from(be16(bytes, at + 2)?).ok()?,
+        }
+    }
+}
+
+fn webp_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        // Lossy: 14-bit sizes after the key frame's start code.
+        b"VP8 " if bytes.get(23..26)? == [0x9D, 0x01, 0x2A] => {
+            Some((le16(bytes, 26)? & 0x3FFF, le16(bytes, 28)? & 0x3FFF))
+        }
+        // Lossless: 14-bit `size - 1` pairs after the 0x2F signature.
+        b"VP8L" if *bytes.get(20)? == 0x2F => {
+            let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        // Extended: 24-bit `canvas size - 1`.
+        b"VP8X" => Some((le24(bytes, 24)? + 1, le24(bytes, 27)? + 1)),
+        _ => None,
+    }
+}
+
+/// Inline size of `len` raw bytes once base64-encoded — what every
+/// provider's cap is measured against.
+fn encoded_len(len: usize) -> usize {
+    len.div_ceil(3) * 4
+}
+
+/// What a provider accepts as an inline image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageLimits {
+    formats: Vec<ImageFormat>,
+    max_bytes: usize,
+    max_long_edge: u32,
+}
+
+impl ImageLimits {
+    /// Accept `formats` (the first is the fallback when re-encoding is
+    /// needed), up to `max_bytes` base64-encoded, with neither side
+    /// longer than `max_long_edge` pixels.
+    pub fn new(
+        formats: impl IntoIterator<Item = ImageFormat>,
+        max_bytes: usize,
+        max_long_edge: u32,
+    ) -> Self {
+        Self {
+            formats: formats.into_iter().collect(),
+            max_bytes,
+            max_long_edge: max_long_edge.max(1),
+        }
+    }
+
+    /// The limits of `provider`'s vision input:
+    ///
+    /// | Provider  | Formats              | Size (encoded) | Long edge |
+    /// |-----------|----------------------|----------------|-----------|
+    /// | OpenAI    | PNG, JPEG, WebP, GIF | 20 MB          | 2048 px   |
+    /// | Google    | PNG, JPEG, WebP      | 7 MB           | 3072 px   |
+    /// | Anthropic | JPEG, PNG, WebP, GIF | 5 MB           | 1568 px   |
+    ///
+    /// The long edge is where each provider starts scaling images down
+    /// server-side, not its hard maximum: larger images are accepted
+    /// but cost upload time for pixels the model never sees.
+    pub fn for_provider(provider: ProviderType) -> Self {
+        const MB: usize = 1024 * 1024;
+        use ImageFormat::*;
+        match provider {
+            ProviderType::OpenAI => Self::new([Png, Jpeg, Webp, Gif], 20 * MB, 2048),
+            ProviderType::Google => Self::new([Png, Jpeg, Webp], 7 * MB, 3072),
+            ProviderType::Anthropic => Self::new([Jpeg, Png, Webp, Gif], 5 * MB, 1568),
+        }
+    }
+
+    /// Tighten the size cap, e.g. to leave room for several images in
+    /// one request.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Change the longest side allowed, in pixels.
+    pub fn with_max_long_edge(mut self, max_long_edge: u32) -> Self {
+        self.max_long_edge = max_long_edge.max(1);
+        self
+    }
+
+    /// Whether `format` is accepted.
+    pub fn supports(&self, format: ImageFormat) -> bool {
+        self.formats.contains(&format)
+    }
+
+    /// The size cap, base64-encoded.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// The longest side allowed, in pixels.
+    pub fn max_long_edge(&self) -> u32 {
+        self.max_long_edge
+    }
+
+    /// Whether an image of `info` and `len` raw bytes fits as-is.
+    pub fn admits(&self, info: &ImageInfo, len: usize) -> bool {
+        self.supports(info.format)
+            && info.width.max(info.height) <= self.max_long_edge
+            && encoded_len(len) <= self.max_bytes
+    }
+
+    /// `width × height` scaled down, aspect ratio kept, so the long
+    /// edge fits.
+    fn fit(&self, width: u32, height: u32) -> (u32, u32) {
+        let long = width.max(height);
+        if long <= self.max_long_edge {
+            return (width, height);
+        }
+        let scale = |side: u32| {
+            ((u64::from(side) * u64::from(self.max_long_edge) + u64::from(long) / 2)
+                / u64::from(long))
+            .max(1) as u32
+        };
+        (scale(width), scale(height))
+    }
+}
+
+/// What an [`ImageTranscoder`] is asked to produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TranscodeTarget {
+    /// Output encoding.
+    pub format: ImageFormat,
+    /// Output width in pixels — the source's, or smaller with the
+    /// aspect ratio kept.
+    pub width: u32,
+    /// Output height in pixels.
+    pub height: u32,
+}
+
+/// Scales and re-encodes images for [`prepare_image`]. Implemented for
+/// closures `Fn(&[u8], TranscodeTarget) -> Result<Vec<u8>, Error>`.
+pub trait ImageTranscoder: Send + Sync {
+    /// Decode `bytes`, resize to the target dimensions and encode as
+    /// the target format. Lossy formats should pick their own quality.
+    fn transcode(&self, bytes: &[u8], target: TranscodeTarget) -> Result<Vec<u8>, Error>;
+}
+
+impl<F> ImageTranscoder for F
+where
+    F: Fn(&[u8], TranscodeTarget) -> Result<Vec<u8>, Error> + Send + Sync,
+{
+    fn transcode(&self, bytes: &[u8], target: TranscodeTarget) -> Result<Vec<u8>, Error> {
+        self(bytes, target)
+    }
+}
+
+/// JPEG quality [`DefaultTranscoder`] encodes at.
+const JPEG_QUALITY: u8 = 85;
+
+/// The [`ImageTranscoder`] [`prepare_image`] uses when none is given:
+/// decodes with the `image` crate, resizes with a Lanczos filter, and
+/// encodes JPEG at quality 85 (alpha dropped) or the other formats
+/// losslessly.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultTranscoder;
+
+impl ImageTranscoder for DefaultTranscoder {
+    fn transcode(&self, bytes: &[u8], target: TranscodeTarget) -> Result<Vec<u8>, Error> {
+        use image::codecs::jpeg::JpegEncoder;
+        use image::imageops::FilterType;
+        use image::DynamicImage;
+
+        let mut decoded = image::load_from_memory(bytes)
+            .map_err(|e| Error::invalid_prompt(format!("undecodable image: {e}")))?;
+        if (decoded.width(), decoded.height()) != (target.width, target.height) {
+            decoded = decoded.resize_exact(target.width, target.height, FilterType::Lanczos3);
+        }
+        let rgba = |image: DynamicImage| DynamicImage::ImageRgba8(image.to_rgba8());
+        let mut out = std::io::Cursor::new(Vec::new());
+        let written = match target.format {
+            ImageFormat::Jpeg => DynamicImage::ImageRgb8(decoded.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)),
+            ImageFormat::Png => rgba(decoded).write_to(&mut out, image::ImageFormat::Png),
+            ImageFormat::Gif => rgba(decoded).write_to(&mut out, image::ImageFormat::Gif),
+            ImageFormat::Webp => rgba(decoded).write_to(&mut out, image::ImageFormat::WebP),
+        };
+        written.map_err(|e| Error::invalid_prompt(format!("image re-encoding failed: {e}")))?;
+        Ok(out.into_inner())
+    }
+}
+
+/// Re-encoding attempts before [`prepare_image`] gives up on an image
+/// that still exceeds the size cap.
+const MAX_ATTEMPTS: usize = 4;
+
+/// An image that fits a provider's [`ImageLimits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedImage {
+    /// The encoded image.
+    pub bytes: Vec<u8>,
+    /// Its format and dimensions.
+    pub info: ImageInfo,
+}
+
+impl PreparedImage {
+    /// An inline base64 [`FileSource`].
+    pub fn into_source(self) -> FileSource {
+        FileSource::Base64 {
+            data: base64::engine::general_purpose::STANDARD.encode(&self.bytes),
+            media_type: self.info.format.media_type().to_string(),
+        }
+    }
+
+    /// A [`UserPart::Image`] ready for a prompt.
+    pub fn into_part(self) -> UserPart {
+        UserPart::Image(self.into_source())
+    }
+}
+
+/// Fit `bytes` to `limits`: returned untouched when they already fit,
+/// otherwise scaled to the long-edge limit and re-encoded through
+/// `transcoder` ([`DefaultTranscoder`] when `None`) — into an accepted
+/// format if the source's isn't, and falling back to JPEG, then
+/// smaller sizes, while the result is still over the size cap.
+///
+/// Fails with [`Error::InvalidPrompt`] when the image is unreadable or
+/// still doesn't fit after a few attempts; transcoder errors pass
+/// through.
+pub fn prepare_image(
+    bytes: Vec<u8>,
+    limits: &ImageLimits,
+    transcoder: Option<&dyn ImageTranscoder>,
+) -> Result<PreparedImage, Error> {
+    let info = ImageInfo::read(&bytes)?;
+    if limits.admits(&info, bytes.len()) {
+        return Ok(PreparedImage { bytes, info });
+    }
+    let transcoder = transcoder.unwrap_or(&DefaultTranscoder);
+
+    let jpeg = limits
+        .supports(ImageFormat::Jpeg)
+        .then_some(ImageFormat::Jpeg);
+    let fallback = match info.format {
+        // Keep transparency lossless where the source had it.
+        ImageFormat::Gif | ImageFormat::Webp if limits.supports(ImageFormat::Png) => {
+            Some(ImageFormat::Png)
+        }
+        _ => jpeg,
+    };
+    let format = Some(info.format)
+        .filter(|&format| limits.supports(format))
+        .or(fallback)
+        .or_else(|| limits.formats.first().copied())
+        .ok_or_else(|| Error::invalid_prompt("image limits accept no formats"))?;
+    let (width, height) = limits.fit(info.width, info.height);
+    let mut target = TranscodeTarget {
+        format,
+        width,
+        height,
+    };
+    for _ in 0..MAX_ATTEMPTS {
+        let out = transcoder.transcode(&bytes, target)?;
+        let out_info = ImageInfo::read(&out)?;
+        if limits.admits(&out_info, out.len()) {
+            return Ok(PreparedImage {
+                bytes: out,
+                info: out_info,
+            });
+        }
+        match jpeg {
+            Some(jpeg) if target.format != jpeg => target.format = jpeg,
+            _ => {
+                target.width = (target.width * 3 / 4).max(1);
+                target.height = (target.height * 3 / 4).max(1);
+            }
+        }
+    }
+    Err(Error::invalid_prompt(format!(
+        "image still exceeds {} encoded bytes after {MAX_ATTEMPTS} transcoding attempts",
+        limits.max_bytes
+    )))
+}
+
+/// [`prepare_image`] against [`ImageLimits::for_provider`], as a
+/// prompt part.
+pub fn image_part(
+    bytes: Vec<u8>,
+    provider: ProviderType,
+    transcoder: Option<&dyn ImageTranscoder>,
+) -> Result<UserPart, Error> {
+    prepare_image(bytes, &ImageLimits::for_provider(provider), transcoder)
+        .map(PreparedImage::into_part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn png(width: u32, height: u32, padding: usize) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.resize(bytes.len() + padding, 0);
+        bytes
+    }
+
+    fn jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46];
+        bytes.extend_from_slice(&[0xFF, 0xC2, 0x00, 0x11, 0x08]);
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&[0x03; 10]);
+        bytes
+    }
+
+    #[test]
+    fn reads_dimensions_from_headers() {
+        let info = |bytes: &[u8]| {
+            let info = ImageInfo::read(bytes).unwrap();
+            (info.format, info.width, info.height)
+        };
+        assert_eq!(info(&png(640, 480, 0)), (ImageFormat::Png, 640, 480));
+        assert_eq!(info(&jpeg(1024, 768)), (ImageFormat::Jpeg, 1024, 768));
+        assert_eq!(
+            info(b"GIF89a\x20\x03\x58\x02\0\0"),
+            (ImageFormat::Gif, 800, 600)
+        );
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend_from_slice(&[0x7F, 0x07, 0x00, 0x37, 0x04, 0x00]);
+        assert_eq!(info(&webp), (ImageFormat::Webp, 1920, 1080));
+
+        assert!(matches!(
+            ImageInfo::read(b"not an image"),
+            Err(Error::InvalidPrompt(_))
+        ));
+        assert!(ImageInfo::read(&jpeg(10, 10)[..10]).is_err());
+    }
+
+    #[test]
+    fn fitting_images_pass_through_as_inline_parts() {
+        let bytes = png(800, 600, 3);
+        let part = image_part(bytes.clone(), ProviderType::Google, None).unwrap();
+        let UserPart::Image(FileSource::Base64 { data, media_type }) = part else {
+            panic!("expected an inline image part");
+        };
+        assert_eq!(media_type, "image/png");
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .unwrap(),
+            bytes
+        );
+    }
+
+    #[test]
+    fn oversized_images_are_scaled_and_reencoded() {
+        let targets = Mutex::new(Vec::new());
+        let transcoder = |_: &[u8], target: TranscodeTarget| {
+            targets.lock().unwrap().push(target);
+            Ok(match target.format {
+                ImageFormat::Jpeg => jpeg(target.width as u16, target.height as u16),
+                // Too big until re-encoded as JPEG.
+                _ => png(target.width, target.height, 4096),
+            })
+        };
+        let limits = ImageLimits::for_provider(ProviderType::Anthropic).with_max_bytes(1024);
+        let prepared = prepare_image(png(4000, 3000, 0), &limits, Some(&transcoder)).unwrap();
+
+        assert_eq!(prepared.info.format, ImageFormat::Jpeg);
+        assert_eq!((prepared.info.width, prepared.info.height), (1568, 1176));
+        let targets = targets.lock().unwrap();
+        assert_eq!(targets[0].format, ImageFormat::Png);
+        assert_eq!(targets.len(), 2);
+    }
+
+    #[test]
+    fn undecodable_images_that_need_transcoding_are_rejected() {
+        let gif = b"GIF89a\x10\0\x10\0\0\0".to_vec();
+        assert!(image_part(gif.clone(), ProviderType::OpenAI, None).is_ok());
+        let err = image_part(gif, ProviderType::Google, None).unwrap_err();
+        assert!(matches!(err, Error::InvalidPrompt(_)), "{err}");
+    }
+
+    #[test]
+    fn default_transcoder_scales_and_converts() {
+        let encode = |width, height, format| {
+            let image = image::RgbaImage::from_fn(width, height, |x, y| {
+                image::Rgba([x as u8, y as u8, 128, 255])
+            });
+            let mut out = std::io::Cursor::new(Vec::new());
+            image.write_to(&mut out, format).unwrap();
+            out.into_inner()
+        };
+
+        let limits = ImageLimits::for_provider(ProviderType::Anthropic).with_max_long_edge(100);
+        let prepared =
+            prepare_image(encode(400, 300, image::ImageFormat::Png), &limits, None).unwrap();
+        assert_eq!(prepared.info.format, ImageFormat::Png);
+        assert_eq!((prepared.info.width, prepared.info.height), (100, 75));
+
+        let gif = encode(32, 16, image::ImageFormat::Gif);
+        let UserPart::Image(FileSource::Base64 { media_type, .. }) =
+            image_part(gif, ProviderType::Google, None).unwrap()
+        else {
+            panic!("expected an inline image part");
+        };
+        assert_eq!(media_type, "image/png");
+    }
+}
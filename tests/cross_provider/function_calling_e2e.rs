@@ -1,7 +1,7 @@
 //! Two-turn function-calling test driver, one variant per provider.
 //!
 //! Verifies that the lib's `convert_request` emits the exact JSON shape
-//! each provider expects (asserted inside [`ScriptedTransport`]) and that
+//! each provider expects (asserted inside [`MockTransport`]) and that
 //! the round-trip — model emits tool call → caller appends the tool
 //! result → caller sends the follow-up — produces a non-empty final
 //! response.
@@ -31,7 +31,7 @@ async fn run_function_calling_test<T: ProviderTestSetup>() -> Result<(), Box<dyn
         .tools(vec![create_weather_tool()])
         .build();
 
-    // First turn: ScriptedTransport asserts the lib's emitted request
+    // First turn: MockTransport asserts the lib's emitted request
     // body matches the expected initial payload, then returns the
     // canned function-call SSE.
     let response = generate(&*provider, &conversation, &cfg).await?;
@@ -104,7 +104,7 @@ async fn run_function_calling_test<T: ProviderTestSetup>() -> Result<(), Box<dyn
         .max_tokens(150)
         .build();
 
-    // Second turn: ScriptedTransport asserts the follow-up body shape.
+    // Second turn: MockTransport asserts the follow-up body shape.
     let followup_response = generate(&*provider, &conversation, &followup_cfg).await?;
     let followup_text = followup_response.text().await?;
     assert!(
@@ -1,4 +1,3 @@
 pub mod function_calling_e2e;
 pub mod model_switching;
 pub mod providers;
-pub mod scripted;
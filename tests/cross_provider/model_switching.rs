@@ -93,6 +93,8 @@ fn rich_assistant_turn() -> CompleteResponse {
         ],
         finish_reason: FinishReason::Stop,
         usage: Usage::default(),
+        safety: None,
+        timing: None,
     }
 }
 
@@ -340,6 +342,8 @@ async fn redacted_reasoning_drops_on_non_anthropic_providers() {
         ],
         finish_reason: FinishReason::Stop,
         usage: Usage::default(),
+        safety: None,
+        timing: None,
     };
     let prompt = Prompt::user("hi")
         .with_response(&prior)
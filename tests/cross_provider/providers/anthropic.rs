@@ -1,5 +1,5 @@
 use super::{create_weather_tool, ProviderConfig, ProviderTestSetup};
-use crate::cross_provider::scripted::{load_fixture, ScriptedTransport, ScriptedTurn};
+use platformed_llm::providers::mock_http::{MockTransport, MockTurn};
 use platformed_llm::providers::{AnthropicViaVertexProvider, VertexEndpoint};
 use platformed_llm::transport::Transport;
 use platformed_llm::Provider;
@@ -78,19 +78,11 @@ impl ProviderTestSetup for AnthropicTestSetup {
             "stream": true
         });
 
-        let scripted = ScriptedTransport::new(vec![
-            ScriptedTurn {
-                expected_body: initial,
-                response_sse: load_fixture(
-                    "tests/cross_provider/fixtures/anthropic/function_call_response.sse",
-                ),
-            },
-            ScriptedTurn {
-                expected_body: followup,
-                response_sse: load_fixture(
-                    "tests/cross_provider/fixtures/anthropic/followup_response.sse",
-                ),
-            },
+        let scripted = MockTransport::new([
+            MockTurn::fixture("tests/cross_provider/fixtures/anthropic/function_call_response.sse")
+                .expect_body(initial),
+            MockTurn::fixture("tests/cross_provider/fixtures/anthropic/followup_response.sse")
+                .expect_body(followup),
         ]);
         let endpoint = VertexEndpoint::with_access_token(
             "test-project".to_string(),
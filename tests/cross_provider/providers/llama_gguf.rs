@@ -1,7 +1,7 @@
 //! Cross-provider test setup for the local llama-gguf provider.
 //!
 //! The hosted-provider variants in this directory use
-//! [`ScriptedTransport`](crate::cross_provider::scripted::ScriptedTransport)
+//! [`MockTransport`](platformed_llm::providers::mock_http::MockTransport)
 //! to assert the lib's HTTP request shape. The local provider doesn't
 //! flow through `Transport`, so we substitute at the next layer
 //! down: a [`ScriptedLocalEngine`] takes the place of
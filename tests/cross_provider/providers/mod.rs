@@ -36,7 +36,7 @@ pub struct ProviderConfig {
 }
 
 /// Provider-specific test setup. Each impl builds a fully-wired provider
-/// backed by a `ScriptedTransport` that asserts the lib's emitted request
+/// backed by a `MockTransport` that asserts the lib's emitted request
 /// body matches the expected payload for each of the two scripted turns
 /// (initial tool-emitting call + follow-up after the tool result).
 pub trait ProviderTestSetup {
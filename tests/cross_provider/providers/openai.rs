@@ -1,5 +1,5 @@
 use super::{create_weather_tool, ProviderConfig, ProviderTestSetup};
-use crate::cross_provider::scripted::{load_fixture, ScriptedTransport, ScriptedTurn};
+use platformed_llm::providers::mock_http::{MockTransport, MockTurn};
 use platformed_llm::providers::OpenAIProvider;
 use platformed_llm::transport::Transport;
 use platformed_llm::Provider;
@@ -72,19 +72,11 @@ impl ProviderTestSetup for OpenAITestSetup {
             "store": false
         });
 
-        let scripted = ScriptedTransport::new(vec![
-            ScriptedTurn {
-                expected_body: initial,
-                response_sse: load_fixture(
-                    "tests/cross_provider/fixtures/openai/function_call_response.sse",
-                ),
-            },
-            ScriptedTurn {
-                expected_body: followup,
-                response_sse: load_fixture(
-                    "tests/cross_provider/fixtures/openai/followup_response.sse",
-                ),
-            },
+        let scripted = MockTransport::new([
+            MockTurn::fixture("tests/cross_provider/fixtures/openai/function_call_response.sse")
+                .expect_body(initial),
+            MockTurn::fixture("tests/cross_provider/fixtures/openai/followup_response.sse")
+                .expect_body(followup),
         ]);
         let provider = OpenAIProvider::with_transport(
             "test-api-key".to_string(),
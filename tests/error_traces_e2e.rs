@@ -233,8 +233,8 @@ async fn captured_error_bodies_map_to_typed_errors() {
             // it happens).
             (_, 429, Error::RateLimit { .. }, _) => true,
             // 404 → ModelNotAvailable on Vertex.
-            (Provider::Google, 404, Error::ModelNotAvailable(_), _) => true,
-            (Provider::Anthropic, 404, Error::ModelNotAvailable(_), _) => true,
+            (Provider::Google, 404, Error::ModelNotAvailable { .. }, _) => true,
+            (Provider::Anthropic, 404, Error::ModelNotAvailable { .. }, _) => true,
             // The `context_window_exceeded` scenario must surface as the
             // typed `ContextWindowExceeded` on every provider — that's
             // the load-bearing contract for compaction-aware callers.
@@ -308,6 +308,14 @@ async fn captured_error_bodies_map_to_typed_errors() {
         if msg.is_empty() {
             failures.push(format!("{label}: error message is empty"));
         }
+        // Every variant mapped from an HTTP error keeps the body for
+        // debugging, not just the generic `Provider` fallback.
+        let has_raw_body = err
+            .provider_details()
+            .is_some_and(|details| details.raw_body.is_some());
+        if trace.status >= 400 && !has_raw_body {
+            failures.push(format!("{label}: raw body not kept on {err:?}"));
+        }
     }
 
     if !failures.is_empty() {
@@ -149,6 +149,8 @@ async fn file_ref_uploads_and_references_recorded_file_id() {
             UserPart::Text("Briefly describe what you see in this image.".to_string()),
             UserPart::Image(FileSource::Ref("img-1".to_string())),
         ],
+        name: None,
+        metadata: Default::default(),
     });
     let cfg = Config::builder("gpt-4o-mini").max_tokens(256).build();
 
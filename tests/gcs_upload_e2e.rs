@@ -126,6 +126,8 @@ async fn gemini_ref_uploads_to_gcs_and_references_gs_uri() {
             UserPart::Text("Describe this image.".to_string()),
             UserPart::Image(FileSource::Ref("img-1".to_string())),
         ],
+        name: None,
+        metadata: Default::default(),
     });
     let cfg = Config::builder("gemini-2.5-flash").max_tokens(256).build();
 
@@ -75,10 +75,16 @@ async fn http_429_with_retry_after_surfaces_as_rate_limit() {
     .await
     .expect_err("429 must produce an error");
 
+    let details = err
+        .provider_details()
+        .unwrap_or_else(|| panic!("expected provider details on {err:?}"));
+    assert_eq!(details.code.as_deref(), Some("rate_limit_exceeded"));
+    assert_eq!(details.raw_body.as_deref(), Some(body));
     match err {
         Error::RateLimit {
             retry_after,
             message,
+            ..
         } => {
             assert_eq!(retry_after, Some(std::time::Duration::from_secs(42)));
             assert!(
@@ -103,6 +109,82 @@ async fn http_429_without_retry_after_still_maps_to_rate_limit() {
     }
 }
 
+/// OpenAI rarely sends `Retry-After` on 429 but always sends the
+/// `x-ratelimit-reset-*` pair; the longer of the two becomes the hint.
+#[tokio::test]
+async fn http_429_falls_back_to_ratelimit_reset_headers() {
+    let body =
+        r#"{"error":{"message":"Slow down","type":"requests","code":"rate_limit_exceeded"}}"#;
+    let err = openai_against(
+        429,
+        vec![
+            ("x-ratelimit-reset-requests".to_string(), "1.5s".to_string()),
+            ("x-ratelimit-reset-tokens".to_string(), "6m0s".to_string()),
+        ],
+        body,
+    )
+    .await
+    .expect_err("429 must error");
+
+    match err {
+        Error::RateLimit { retry_after, .. } => {
+            assert_eq!(retry_after, Some(std::time::Duration::from_secs(360)));
+        }
+        other => panic!("expected RateLimit, got {other:?}"),
+    }
+}
+
+/// `insufficient_quota` arrives as a 429 but is a billing state, not
+/// congestion — it must not look retryable.
+#[tokio::test]
+async fn http_429_insufficient_quota_is_terminal() {
+    let body = r#"{"error":{"message":"You exceeded your current quota","type":"insufficient_quota","code":"insufficient_quota"}}"#;
+    let err = openai_against(
+        429,
+        vec![("x-ratelimit-reset-requests".to_string(), "1s".to_string())],
+        body,
+    )
+    .await
+    .expect_err("429 must error");
+
+    assert!(
+        matches!(
+            err,
+            Error::Provider {
+                status: Some(429),
+                retryable: false,
+                ..
+            }
+        ),
+        "expected terminal Provider error, got {err:?}",
+    );
+    assert!(!err.is_retryable());
+}
+
+/// Error type, code and the `x-request-id` header must be reachable
+/// without parsing the message.
+#[tokio::test]
+async fn http_400_exposes_structured_details() {
+    let body = r#"{"error":{"message":"Invalid value for max_output_tokens","type":"invalid_request_error","param":"max_output_tokens","code":"integer_below_min_value"}}"#;
+    let err = openai_against(
+        400,
+        vec![("x-request-id".to_string(), "req_abc123".to_string())],
+        body,
+    )
+    .await
+    .expect_err("400 must error");
+
+    let details = err
+        .provider_details()
+        .unwrap_or_else(|| panic!("expected provider details on {err:?}"));
+    assert_eq!(details.error_type.as_deref(), Some("invalid_request_error"));
+    assert_eq!(details.code.as_deref(), Some("integer_below_min_value"));
+    assert_eq!(details.request_id.as_deref(), Some("req_abc123"));
+    assert_eq!(details.raw_body.as_deref(), Some(body));
+    assert_eq!(err.status(), Some(400));
+    assert!(!err.is_retryable());
+}
+
 #[tokio::test]
 async fn http_401_surfaces_as_auth() {
     let body = r#"{"error":{"message":"Bad key","type":"invalid_request_error","code":"invalid_api_key"}}"#;
@@ -110,8 +192,14 @@ async fn http_401_surfaces_as_auth() {
         .await
         .expect_err("401 must error");
 
+    assert_eq!(
+        err.provider_details().and_then(|d| d.raw_body.as_deref()),
+        Some(body)
+    );
     match err {
-        Error::Auth { status, message } => {
+        Error::Auth {
+            status, message, ..
+        } => {
             assert_eq!(status, Some(401));
             assert!(
                 message.contains("Bad key"),
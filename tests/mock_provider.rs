@@ -14,6 +14,7 @@ async fn drives_a_tool_call_loop() {
             name: "lookup".into(),
             arguments: r#"{"q":"answer"}"#.into(),
             provider_signature: None,
+            original_arguments: None,
         }))
         .reply("The answer is 42.")
         .build();
@@ -48,7 +49,7 @@ async fn drives_a_tool_call_loop() {
     let calls = log.calls();
     assert_eq!(calls.len(), 2);
     let second_has_tool_result = calls[1].prompt.items().iter().any(|item| {
-        matches!(item, InputItem::User { content }
+        matches!(item, InputItem::User { content, .. }
             if content.iter().any(|p| matches!(p, UserPart::ToolResult { .. })))
     });
     assert!(
@@ -286,10 +286,19 @@ fn format_events(events: &[StreamEvent]) -> String {
                 PartUpdate::BuiltinToolResult(r) => out.push_str(&format!(
                     "PartUpdate[{index}] builtin_tool_result {r:?}\n"
                 )),
+                PartUpdate::OriginalArguments(a) => out.push_str(&format!(
+                    "PartUpdate[{index}] original_arguments {a:?}\n"
+                )),
             },
             StreamEvent::PartEnd { index } => {
                 out.push_str(&format!("PartEnd[{index}]\n"));
             }
+            StreamEvent::Safety { feedback } => out.push_str(&format!(
+                "Safety prompt_blocked={} block_reason={:?} ratings={}\n",
+                feedback.prompt_blocked,
+                feedback.block_reason,
+                feedback.ratings.len()
+            )),
             StreamEvent::Done { finish_reason, .. } => {
                 // Usage masked to keep snapshots stable across re-captures.
                 out.push_str(&format!(
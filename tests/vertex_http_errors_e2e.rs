@@ -115,6 +115,87 @@ async fn anthropic_429_with_retry_after_is_rate_limit() {
     assert_rate_limited(err, Some(7));
 }
 
+/// Gemini often puts the backoff in a `google.rpc.RetryInfo` detail
+/// instead of a `Retry-After` header — it must still reach the error.
+#[tokio::test]
+async fn google_429_falls_back_to_retry_info_detail() {
+    let err = google_err(
+        429,
+        vec![],
+        r#"{"error":{"code":429,"status":"RESOURCE_EXHAUSTED","details":[{"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"12.5s"}]}}"#,
+    )
+    .await;
+    assert_rate_limited(err, Some(13));
+}
+
+/// The `Retry-After` header wins over the body detail when both exist.
+#[tokio::test]
+async fn google_429_header_beats_retry_info_detail() {
+    let err = google_err(
+        429,
+        vec![("retry-after".to_string(), "3".to_string())],
+        r#"{"error":{"details":[{"@type":"type.googleapis.com/google.rpc.RetryInfo","retryDelay":"60s"}]}}"#,
+    )
+    .await;
+    assert_rate_limited(err, Some(3));
+}
+
+/// Without `Retry-After`, Anthropic's request-window reset header is
+/// the hint. A reset in the past floors to "retry now".
+#[tokio::test]
+async fn anthropic_429_falls_back_to_reset_header() {
+    let err = anthropic_err(
+        429,
+        vec![(
+            "anthropic-ratelimit-requests-reset".to_string(),
+            "2020-01-01T00:00:00Z".to_string(),
+        )],
+        r#"{"type":"error","error":{"type":"rate_limit_error"}}"#,
+    )
+    .await;
+    assert_rate_limited(err, Some(0));
+}
+
+#[tokio::test]
+async fn google_400_exposes_status_and_code() {
+    let err = google_err(
+        400,
+        vec![],
+        r#"{"error":{"code":400,"message":"Unknown name \"foo\"","status":"INVALID_ARGUMENT"}}"#,
+    )
+    .await;
+    let details = err.provider_details().expect("structured details");
+    assert_eq!(details.error_type.as_deref(), Some("INVALID_ARGUMENT"));
+    assert_eq!(details.code.as_deref(), Some("400"));
+    assert_eq!(details.request_id, None);
+    assert!(details
+        .raw_body
+        .as_deref()
+        .is_some_and(|body| body.contains("INVALID_ARGUMENT")));
+    assert_eq!(
+        err.to_string(),
+        r#"provider error (Google, status 400): API error: Unknown name "foo""#
+    );
+}
+
+#[tokio::test]
+async fn anthropic_400_exposes_type_and_request_id() {
+    let err = anthropic_err(
+        400,
+        vec![("request-id".to_string(), "req_011".to_string())],
+        r#"{"type":"error","error":{"type":"invalid_request_error","message":"bad"}}"#,
+    )
+    .await;
+    let details = err.provider_details().expect("structured details");
+    assert_eq!(details.error_type.as_deref(), Some("invalid_request_error"));
+    assert_eq!(details.request_id.as_deref(), Some("req_011"));
+    assert_eq!(
+        details.raw_body.as_deref(),
+        Some(r#"{"type":"error","error":{"type":"invalid_request_error","message":"bad"}}"#)
+    );
+    assert!(err.to_string().ends_with("API error: bad"), "{err}");
+}
+
 #[tokio::test]
 async fn google_500_is_generic_provider_error() {
     let err = google_err(500, vec![], "boom").await;
@@ -122,4 +203,6 @@ async fn google_500_is_generic_provider_error() {
         matches!(err, Error::Provider { .. }),
         "500 should be a generic provider error, got {err:?}"
     );
+    // Not an envelope: the body itself is the message.
+    assert!(err.to_string().ends_with("API error: boom"), "{err}");
 }
@@ -0,0 +1,20 @@
+#![cfg(target_arch = "wasm32")]
+//! wasm32-unknown-unknown smoke test: a whole [`generate`] call —
+//! request-ID minting, middleware, the mock provider's stream — runs
+//! without the clock, process and thread APIs that panic on the target.
+//! Run with `wasm-bindgen-test-runner` as the target runner (see CI).
+
+use platformed_llm::providers::mock::MockProvider;
+use platformed_llm::{generate, Config, Prompt};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+async fn generate_runs_on_wasm() {
+    let provider = MockProvider::with_text("hello from wasm");
+    let config = Config::builder("gpt-4o-mini").build();
+    let response = generate(&provider, &Prompt::user("hi"), &config)
+        .await
+        .unwrap();
+    assert!(response.metadata().request_id.is_some());
+    assert_eq!(response.text().await.unwrap(), "hello from wasm");
+}